@@ -269,16 +269,13 @@ export const useProgram = () => {
       setSuccess(null);
       
       const client = new UniversalNFTClient(connection, wallet);
-      const mint = new PublicKey(mintAddress);
       
       // Convert message to Uint8Array (placeholder implementation)
       const messageBytes = new TextEncoder().encode(message);
       
-      const signature = await client.receiveCrossChainMessage(
-        tokenId,
-        messageBytes,
-        mint
-      );
+      // The program derives the bridged mint itself and the recipient from the message;
+      // the connected wallet must be the configured gateway
+      const signature = await client.receiveCrossChainMessage(tokenId, messageBytes);
       
       setSuccess(`Message received! Signature: ${signature}`);
       
@@ -1,13 +1,31 @@
-import { Program, AnchorProvider, web3, BN } from '@coral-xyz/anchor';
-import { Connection, PublicKey, SYSVAR_RENT_PUBKEY } from '@solana/web3.js';
+import { Program, AnchorProvider, web3, BN, utils } from '@coral-xyz/anchor';
+import {
+  AddressLookupTableAccount,
+  Connection,
+  PublicKey,
+  SYSVAR_RENT_PUBKEY,
+  Transaction,
+  VersionedTransaction,
+} from '@solana/web3.js';
 import { TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID } from '@solana/spl-token';
 import IDL from '../../target/idl/universal_nft_program.json';
+import {
+  buildLookupTableInstructions,
+  buildTransaction,
+  estimateComputeUnits,
+  getStaticLookupAddresses,
+  InstructionKind,
+  TransactionOptions,
+} from './transactions';
 
 // Fallback BN import in case the Anchor BN has issues
 
 
 export const PROGRAM_ID = new PublicKey('HNNDxSioZreQBawW5momWuHmiWJrLmGBKH1LyH6uUZJL');
 export const METADATA_PROGRAM_ID = new PublicKey('metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s');
+export const GATEWAY_PROGRAM_ID = new PublicKey('ZETAjseVjuFsxdRxo6MmTCvqFwb3ZHUx56Co3vCmGis');
+
+export type BuildOptions = Omit<TransactionOptions, 'payer' | 'recentBlockhash' | 'lookupTable'>;
 
 // ZetaChain Testnet Chain ID Constants
 export const CHAIN_IDS = {
@@ -18,7 +36,8 @@ export const CHAIN_IDS = {
   POLYGON_AMOY: 80002,
   ARBITRUM_SEPOLIA: 421614,
   BITCOIN_TESTNET: 18332,
-  SOLANA_MAINNET: 0, // Legacy for backward compatibility
+  SOLANA_MAINNET: 900,
+  SOLANA_LOCALNET: 902,
 } as const;
 
 export const CHAIN_NAMES = {
@@ -29,9 +48,20 @@ export const CHAIN_NAMES = {
   80002: 'Polygon Amoy Testnet',
   421614: 'Arbitrum Sepolia Testnet',
   18332: 'Bitcoin Testnet',
-  0: 'Solana Mainnet',
+  900: 'Solana Mainnet',
+  902: 'Solana Localnet',
+} as const;
+
+// Mirrors the program's PauseReason enum
+export const PAUSE_REASONS = {
+  INCIDENT: 0,
+  UPGRADE: 1,
+  MAINTENANCE: 2,
+  OTHER: 3,
 } as const;
 
+export type PauseReasonCode = typeof PAUSE_REASONS[keyof typeof PAUSE_REASONS];
+
 export function getChainName(chainId: number): string {
   return CHAIN_NAMES[chainId as keyof typeof CHAIN_NAMES] || 'Unknown Chain';
 }
@@ -49,9 +79,34 @@ export interface ProgramState {
   nextTokenId: BN;
   paused: boolean;
   bump: number;
+  pauseReason: number;
+  autoUnpauseAt: BN | null;
+  nativeIdBase: BN;
+  declaredChainId: BN;
+  inboundCount: BN;
+  maxUriMintsPerWindow: BN;
+  uriDedupWindowSeconds: BN;
+  maxMessageBytes: number;
+  attestationKey: PublicKey;
+  minUserSolReserve: BN;
+  bridgeBackend: { zetaChain: {} } | { wormhole: {} };
+  requireNftAcceptance: boolean;
+  metadataPolicy: { atomic: {} } | { bestEffort: {} };
+  inboundMode: { onePhase: {} } | { twoPhase: {} };
+  maxMetadataUriRequestsPerSlot: number;
+  metadataSyncRequestsThisSlot: number;
+  lastSyncSlot: BN;
+  lazyOriginsEnabled: boolean;
+  maxNativeTokenId: BN;
+  allowSemiFungible: boolean;
+  locked: boolean;
+  expectedUpgradeAuthority: PublicKey;
+  forcedRecallEnabled: boolean;
+  recallDisputeWindowSeconds: BN;
 }
 
 export interface NFTOrigin {
+  schema_version: number;
   token_id: BN; // Match the Rust program's snake_case field names
   origin_chain: number;
   origin_token_id: BN;
@@ -59,11 +114,60 @@ export interface NFTOrigin {
   mint: PublicKey;
   created_at: BN;
   bump: number;
+  metadata_encryption_key: number[] | null;
+  arrived_at: BN;
+  logical_owner: PublicKey;
+  last_memo: string;
+  stranded_supply: BN;
+  metadata_pending: boolean;
+  reveal_collection_id: BN | null;
+}
+
+/**
+ * Commitment to a burn made by `transfer_cross_chain`, for destination contracts that check it
+ * before minting: the keccak256 of the 80-byte preimage
+ *
+ * `mint (32) || token_id (8, big-endian) || owner (32) || slot (8, big-endian)`
+ *
+ * where `owner` is the burned token account's owner and `slot` the slot of the burn, i.e.
+ * Solidity's `keccak256(abi.encodePacked(bytes32 mint, uint64 tokenId, bytes32 owner, uint64 slot))`
+ */
+export type BurnCommitment = number[];
+
+export interface PendingTransfer {
+  nonce: BN;
+  tokenId: BN;
+  sender: PublicKey;
+  submittedAt: BN;
+  destination: number[];
+  cancellableUntil: BN;
+  status: number;
+  bump: number;
+  // Also sent in the outbound message trailer
+  burnCommitment: BurnCommitment;
+  insured: boolean;
+  insuredPayoutLamports: BN;
+  amount: BN;
+}
+
+export interface CrossChainTransferInitiated {
+  tokenId: BN;
+  destinationChain: BN;
+  destinationOwner: number[];
+  mint: PublicKey;
+  destinationZrc20: number[];
+  logicalOwner: PublicKey;
+  // owner and burnSlot, with mint and tokenId, are the preimage of burnCommitment
+  owner: PublicKey;
+  burnSlot: BN;
+  burnCommitment: BurnCommitment;
+  amount: BN;
 }
 
 export class UniversalNFTClient {
   private program: Program;
   private wallet: any;
+  private lookupTable: AddressLookupTableAccount | null = null;
 
   constructor(connection: Connection, wallet: any) {
     this.wallet = wallet;
@@ -96,6 +200,9 @@ export class UniversalNFTClient {
       const hex = evmContractHex.trim().toLowerCase();
       if (!/^0x[0-9a-f]{40}$/.test(hex)) throw new Error('Invalid EVM contract address');
       const contract20 = Uint8Array.from(Buffer.from(hex.slice(2), 'hex'));
+      // The program can't read the genesis hash itself, so it is declared here with the chain
+      // id it names; initialize refuses a pair that disagrees
+      const genesisHash = await this.program.provider.connection.getGenesisHash();
       
       console.log('Initialize parameters:', {
         gateway: gateway.toString(),
@@ -110,6 +217,8 @@ export class UniversalNFTClient {
           new BN(nextTokenId), 
           Array.from(contract20),
           new BN(gasLimit),
+          Array.from(utils.bytes.bs58.decode(genesisHash)),
+          new BN(UniversalNFTClient.getSolanaChainId(genesisHash)),
         )
         .accounts({
           programState: programStatePDA,
@@ -126,28 +235,175 @@ export class UniversalNFTClient {
     }
   }
 
+  // ZetaChain's id for the Solana cluster with this genesis hash: mainnet-beta 900, devnet 901,
+  // and 902 for any other cluster, such as a local validator
+  static getSolanaChainId(genesisHash: string): number {
+    const knownClusters: Record<string, number> = {
+      '5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d': 900,
+      'EtWTRABZaYq6iMfeYKouRu166VU2xqa1wcaWoxPkrZBG': 901,
+    };
+    return knownClusters[genesisHash] ?? 902;
+  }
+
   // Get program state PDA
   static getProgramStatePDA(): [PublicKey, number] {
     return PublicKey.findProgramAddressSync(
-      [Buffer.from('test_program_state')],
+      [Buffer.from('test')],
       PROGRAM_ID
     );
   }
 
   // Get NFT origin PDA
   static getNFTOriginPDA(tokenId: number): [PublicKey, number] {
-    // Match the Rust program's nft_origin_seed function
-    // Creates: ["nft_origin", token_id.to_le_bytes()]
-    const seed1 = Buffer.from('nft_origin'); // "nft_origin" (10 bytes)
-    const seed2 = Buffer.alloc(8); // 8 bytes for u64
-    seed2.writeBigUInt64LE(BigInt(tokenId), 0); // Write token_id as little-endian u64
-    
+    // Match the Rust program's nft_origin_seed function: a single seed of
+    // "nft_origin", token_id.to_le_bytes(), then "unique"
+    const tokenIdSeed = Buffer.alloc(8);
+    tokenIdSeed.writeBigUInt64LE(BigInt(tokenId), 0);
+
     return PublicKey.findProgramAddressSync(
-      [seed1, seed2], // Pass as TWO separate seeds, not one combined
+      [Buffer.concat([Buffer.from('nft_origin'), tokenIdSeed, Buffer.from('unique')])],
+      PROGRAM_ID
+    );
+  }
+
+  // Get the deterministic mint for an NFT bridged in from another chain
+  // Matches the program's ["bridged_mint", origin_chain.to_le_bytes(), origin_token_id.to_le_bytes()]
+  static getBridgedMintPDA(originChain: number, originTokenId: number): [PublicKey, number] {
+    const chainSeed = Buffer.alloc(8);
+    chainSeed.writeBigUInt64LE(BigInt(originChain), 0);
+    const tokenIdSeed = Buffer.alloc(8);
+    tokenIdSeed.writeBigUInt64LE(BigInt(originTokenId), 0);
+
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from('bridged_mint'), chainSeed, tokenIdSeed],
       PROGRAM_ID
     );
   }
 
+  // Get the per-URI deduplication window an inbound message is counted against
+  // Matches the program's ["dedup_window", sha256(uri)]
+  static getDedupWindowPDA(uri: string): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from('dedup_window'), Buffer.from(utils.sha256.hash(uri), 'hex')],
+      PROGRAM_ID
+    );
+  }
+
+  // URI of a V1 or V2 inbound message, read the way the program's ABI decoder does: a legacy
+  // body has its 8-byte uri offset in bytes 64-72, which a canonical offset word leaves zero
+  static getMessageUri(message: Uint8Array): string {
+    const body = Buffer.from(message).subarray(message[0] === 2 ? 9 : 1);
+    const legacyOffset = Number(body.readBigUInt64BE(64));
+    if (legacyOffset !== 0) {
+      const uriLength = Number(body.readBigUInt64BE(legacyOffset));
+      return body.subarray(legacyOffset + 8, legacyOffset + 8 + uriLength).toString('utf8');
+    }
+    const uriOffset = Number(body.readBigUInt64BE(88));
+    const uriLength = Number(body.readBigUInt64BE(uriOffset + 24));
+    return body.subarray(uriOffset + 32, uriOffset + 32 + uriLength).toString('utf8');
+  }
+
+  // Solana wallet a V1 or V2 inbound message delivers to: its whole 32-byte receiver word
+  static getMessageRecipient(message: Uint8Array): PublicKey {
+    const body = Buffer.from(message).subarray(message[0] === 2 ? 9 : 1);
+    return new PublicKey(body.subarray(0, 32));
+  }
+
+  // Get the program PDA that signs as the bridged mint authority
+  static getMintAuthorityPDA(): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from('mint_authority')],
+      PROGRAM_ID
+    );
+  }
+
+  static getRegistryPDA(): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from('registry')],
+      PROGRAM_ID
+    );
+  }
+
+  // Chains other than ZetaChain are reached through their registered ZRC-20
+  static registryFor(finalChainId: number): PublicKey | null {
+    return finalChainId === CHAIN_IDS.ZETACHAIN_TESTNET ? null : UniversalNFTClient.getRegistryPDA()[0];
+  }
+
+  // Get the departure receipt mint for a token id
+  static getReceiverBindingPDA(owner: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from('receiver_binding'), owner.toBuffer()],
+      PROGRAM_ID
+    );
+  }
+
+  static getInboundOfferPDA(tokenId: number, recipient: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from('inbound_offer'), new BN(tokenId).toArrayLike(Buffer, 'le', 8), recipient.toBuffer()],
+      PROGRAM_ID
+    );
+  }
+
+  // Matches the program's ["origin_policy", origin_chain LE, sha256(origin_contract)]; async for WebCrypto
+  static async getOriginPolicyPDA(originChain: number, originContract: Uint8Array): Promise<[PublicKey, number]> {
+    const contractHash = Buffer.from(await crypto.subtle.digest('SHA-256', originContract));
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from('origin_policy'), new BN(originChain).toArrayLike(Buffer, 'le', 8), contractHash],
+      PROGRAM_ID
+    );
+  }
+
+  // Matches the program's ["pending_inbound", sha256(message)]; async for WebCrypto
+  static async getPendingInboundPDA(message: Uint8Array): Promise<[PublicKey, number]> {
+    const messageHash = Buffer.from(await crypto.subtle.digest('SHA-256', message));
+    return PublicKey.findProgramAddressSync([Buffer.from('pending_inbound'), messageHash], PROGRAM_ID);
+  }
+
+  // Every delivery records its message in ["inbound_rcpt", sha256(message)] for replay_inbound
+  static async getInboundReceiptPDA(message: Uint8Array): Promise<[PublicKey, number]> {
+    const messageHash = Buffer.from(await crypto.subtle.digest('SHA-256', message));
+    return PublicKey.findProgramAddressSync([Buffer.from('inbound_rcpt'), messageHash], PROGRAM_ID);
+  }
+
+  static getReceiptMintPDA(tokenId: number): [PublicKey, number] {
+    const tokenIdSeed = Buffer.alloc(8);
+    tokenIdSeed.writeBigUInt64LE(BigInt(tokenId), 0);
+
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from('receipt_mint'), tokenIdSeed],
+      PROGRAM_ID
+    );
+  }
+
+  static getReceiptConfigPDA(): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from('receipt_config')],
+      PROGRAM_ID
+    );
+  }
+
+  // Transfers pass their final chain's ChainGasConfig
+  static getChainGasConfigPDA(chainId: number): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from('chain_gas'), new BN(chainId).toArrayLike(Buffer, 'le', 8)],
+      PROGRAM_ID
+    );
+  }
+
+  static getFeeConfigPDA(): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from('fee_config')],
+      PROGRAM_ID
+    );
+  }
+
+  // Transfers always pass the fee config and its treasury, even when the fee is zero
+  async transferFeeAccounts(): Promise<{ feeConfig: PublicKey; feeTreasury: PublicKey }> {
+    const [feeConfig] = UniversalNFTClient.getFeeConfigPDA();
+    const config = await (this.program.account as any).feeConfig.fetch(feeConfig);
+    return { feeConfig, feeTreasury: config.treasury as PublicKey };
+  }
+
   // Get NFT origin PDA for mintNFT (with hardcoded seed matching the program)
   static getNFTOriginPDAMintNFT(): [PublicKey, number] {
     // Generate the seed that matches the Solana program's mintNFT instruction
@@ -162,7 +418,11 @@ export class UniversalNFTClient {
   }
 
   // Create mint account and mint NFT in one transaction
-  async createMintAndNFT(uri: string, decimals: number): Promise<{ signature: string; mintAddress: string; tokenId: number }> {
+  async createMintAndNFT(
+    uri: string,
+    decimals: number,
+    encryptionKey: number[] | null = null, // 32-byte key for metadata behind an encrypted URI
+  ): Promise<{ signature: string; mintAddress: string; tokenId: number }> {
     try {
       console.log('=== CREATE MINT AND NFT START ===');
       console.log('Creating mint and NFT with URI:', uri);
@@ -250,7 +510,7 @@ export class UniversalNFTClient {
       });
       
       const tx = await this.program.methods
-        .createMintAndNft(uri, decimals, new BN(currentTokenIdForPDA))
+        .createMintAndNft(uri, decimals, new BN(currentTokenIdForPDA), encryptionKey, null, null)
         .accounts({
           programState: programStatePDA,
           nftOrigin: nftOriginPDA,
@@ -265,6 +525,13 @@ export class UniversalNFTClient {
           tokenMetadataProgram: METADATA_PROGRAM_ID,
           metadata: metadataPDA,
           masterEdition: masterEditionPDA,
+          feeConfig: UniversalNFTClient.getFeeConfigPDA()[0],
+          feeTreasury: null,
+          sellerFeeConfig: null,
+          feeCreator: null,
+          mintOnly: null,
+          lazyRevealConfig: null,
+          hourlyMetrics: null,
         })
         .signers([mintKeypair]) // Include the keypair as a signer
         .rpc();
@@ -299,8 +566,9 @@ export class UniversalNFTClient {
   // Transfer NFT cross-chain
   async transferCrossChain(
     tokenId: number,
-    destinationChain: number, // kept for UI compatibility/logging; not used in IX args
-    zrc20OrOwner20: Uint8Array // 20-byte EVM address (used as ZRC-20 addr for now)
+    destinationChain: number, // final chain; ZetaChain mints there, anything else is forwarded
+    destinationOwner: Uint8Array, // 20-byte EVM address on the final chain (extra bytes are ignored)
+    memo: string | null = null, // kept in NFTOrigin.last_memo, max 128 bytes
   ): Promise<string> {
     try {
       console.log('=== INITIATE CROSS-CHAIN TRANSFER START ===');
@@ -309,8 +577,8 @@ export class UniversalNFTClient {
       console.log('Token ID type:', typeof tokenId);
       console.log('Token ID as BigInt:', BigInt(tokenId));
       console.log('Token ID as Buffer:', Buffer.from(tokenId.toString()).toString('hex'));
-      console.log('Destination Chain (ignored in ix args):', destinationChain);
-      console.log('ZRC20/Owner bytes20:', zrc20OrOwner20);
+      console.log('Destination Chain:', destinationChain);
+      console.log('Destination Owner bytes:', destinationOwner);
       
       const [programStatePDA] = UniversalNFTClient.getProgramStatePDA();
       console.log('Program State PDA:', programStatePDA.toString());
@@ -370,22 +638,19 @@ export class UniversalNFTClient {
         throw new Error(`You don't have any tokens to transfer for token ID ${tokenId}. Please mint an NFT first or switch to the wallet that holds it.`);
       }
 
-      // Build destination_owner [u8;32] (left-pad 12 zeros + 20-byte EVM address)
-      const destinationOwnerArray = new Uint8Array(32);
-      destinationOwnerArray.set(zrc20OrOwner20.slice(0, 20), 12);
-      console.log('Destination Owner [u8;32]:', Array.from(destinationOwnerArray));
-
-      // ZRC-20 address [u8;20]
-      const zrc20Address = new Uint8Array(20);
-      zrc20Address.set(zrc20OrOwner20.slice(0, 20));
-      console.log('ZRC20 Address [u8;20]:', Array.from(zrc20Address));
+      // final_receiver: the 20-byte EVM address
+      const finalReceiver = Buffer.from(destinationOwner.slice(0, 20));
+      console.log('Final Receiver [u8;20]:', Array.from(finalReceiver));
 
       console.log('Calling program.transferCrossChain...');
       const tx = await this.program.methods
         .transferCrossChain(
           new BN(tokenId),
-          Array.from(zrc20Address),
-          Array.from(destinationOwnerArray)
+          new BN(destinationChain),
+          finalReceiver,
+          memo,
+          null,
+          null
         )
         .accounts({
           programState: programStatePDA,
@@ -395,6 +660,13 @@ export class UniversalNFTClient {
           user: this.wallet.publicKey,
           tokenProgram: TOKEN_PROGRAM_ID,
           gatewayProgram: new PublicKey("ZETAjseVjuFsxdRxo6MmTCvqFwb3ZHUx56Co3vCmGis"),
+          registry: UniversalNFTClient.registryFor(destinationChain),
+          ...(await this.transferFeeAccounts()),
+          chainGasConfig: UniversalNFTClient.getChainGasConfigPDA(destinationChain)[0],
+          hourlyMetrics: null,
+          insuranceOptIn: null,
+          coOwnership: null,
+          coOwnerConsent: null,
         })
         .rpc();
 
@@ -414,43 +686,42 @@ export class UniversalNFTClient {
     }
   }
 
-  // Receive cross-chain message
+  // Receive cross-chain message; only the configured gateway may sign, so the wallet must be it.
+  // The message names the recipient
   async receiveCrossChainMessage(
     tokenId: number,
     message: Uint8Array,
-    mint: PublicKey
+    memo: string | null = null,
   ): Promise<string> {
     try {
+      const recipient = UniversalNFTClient.getMessageRecipient(message);
       const [programStatePDA] = UniversalNFTClient.getProgramStatePDA();
       const [nftOriginPDA] = UniversalNFTClient.getNFTOriginPDA(tokenId);
-      
-      // Validate the NFT origin account exists
-      try {
-        const nftOriginAccount = await (this.program.account as any).nftOrigin.fetch(nftOriginPDA);
-        if (!nftOriginAccount) {
-          throw new Error('NFT origin account not found');
-        }
-      } catch (error) {
-        throw new Error('Failed to fetch NFT origin account');
-      }
+      // Bridged mints are PDAs, so the same token always lands at the same mint
+      const [mint] = UniversalNFTClient.getBridgedMintPDA(CHAIN_IDS.ZETACHAIN_TESTNET, tokenId);
+      const [mintAuthority] = UniversalNFTClient.getMintAuthorityPDA();
       
       const tx = await this.program.methods
-        .receiveCrossChainMessage(
-          new BN(tokenId),
-          Array.from(message)
-        )
+        .receiveCrossChainMessage(Buffer.from(message), memo)
         .accounts({
           programState: programStatePDA,
           nftOrigin: nftOriginPDA,
           mint: mint,
-          mintAuthority: this.wallet.publicKey, // Use wallet as mint authority for now
-          recipientTokenAccount: await this.getAssociatedTokenAddress(mint),
+          dedupWindow: UniversalNFTClient.getDedupWindowPDA(UniversalNFTClient.getMessageUri(message))[0],
+          mintAuthority: mintAuthority,
+          recipient: recipient,
+          recipientTokenAccount: await this.getAssociatedTokenAddress(mint, recipient),
           payer: this.wallet.publicKey,
+          gateway: this.wallet.publicKey,
           systemProgram: web3.SystemProgram.programId,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           rent: SYSVAR_RENT_PUBKEY,
+          hourlyMetrics: null,
+          inboundValueClaim: null,
+          inboundReceipt: (await UniversalNFTClient.getInboundReceiptPDA(message))[0],
         })
+        .remainingAccounts(await this.originPolicyAccounts())
         .rpc();
 
       return tx;
@@ -460,13 +731,295 @@ export class UniversalNFTClient {
     }
   }
 
-  // Pause program
-  async pauseProgram(): Promise<string> {
+  // Create a lookup table holding the static program accounts and use it for later builds
+  async bootstrapLookupTable(): Promise<PublicKey> {
+    const connection = this.program.provider.connection;
+    const recentSlot = await connection.getSlot('finalized');
+    const { lookupTable, instructions } = buildLookupTableInstructions(
+      this.wallet.publicKey,
+      this.wallet.publicKey,
+      recentSlot,
+      getStaticLookupAddresses(PROGRAM_ID, [GATEWAY_PROGRAM_ID]),
+    );
+    await this.program.provider.sendAndConfirm!(new Transaction().add(...instructions));
+
+    await this.loadLookupTable(lookupTable);
+    return lookupTable;
+  }
+
+  // Use an existing lookup table; builds fall back to legacy transactions until one is loaded
+  async loadLookupTable(address: PublicKey): Promise<AddressLookupTableAccount | null> {
+    const { value } = await this.program.provider.connection.getAddressLookupTable(address);
+    this.lookupTable = value;
+    return value;
+  }
+
+  // Without an explicit limit, the measured estimate for `kind` is requested so relayed
+  // transactions carry a compute budget during congestion
+  private async buildWithLookupTable(
+    kind: InstructionKind,
+    instructions: web3.TransactionInstruction[],
+    options: BuildOptions,
+  ): Promise<Transaction | VersionedTransaction> {
+    const { blockhash } = await this.program.provider.connection.getLatestBlockhash();
+    return buildTransaction(instructions, {
+      ...options,
+      computeUnitLimit: options.computeUnitLimit ?? estimateComputeUnits(kind, instructions.length),
+      payer: this.wallet.publicKey,
+      recentBlockhash: blockhash,
+      lookupTable: this.lookupTable,
+    });
+  }
+
+  // Unsigned mint transaction; the returned mint keypair must co-sign it
+  async buildMintTransaction(
+    uri: string,
+    decimals: number,
+    options: BuildOptions = {},
+    encryptionKey: number[] | null = null,
+  ): Promise<{ transaction: Transaction | VersionedTransaction; mintKeypair: web3.Keypair; tokenId: number }> {
+    const [programStatePDA] = UniversalNFTClient.getProgramStatePDA();
+    const programState = await (this.program.account as any).programState.fetch(programStatePDA);
+    const tokenId = programState.nextTokenId.toNumber();
+    const mintKeypair = web3.Keypair.generate();
+
+    const [metadataPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from('metadata'), METADATA_PROGRAM_ID.toBuffer(), mintKeypair.publicKey.toBuffer()],
+      METADATA_PROGRAM_ID
+    );
+    const [masterEditionPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from('metadata'), METADATA_PROGRAM_ID.toBuffer(), mintKeypair.publicKey.toBuffer(), Buffer.from('edition')],
+      METADATA_PROGRAM_ID
+    );
+
+    const instruction = await this.program.methods
+      .createMintAndNft(uri, decimals, new BN(tokenId), encryptionKey, null, null)
+      .accounts({
+        programState: programStatePDA,
+        nftOrigin: UniversalNFTClient.getNFTOriginPDA(tokenId)[0],
+        mint: mintKeypair.publicKey,
+        tokenAccount: await this.getAssociatedTokenAddress(mintKeypair.publicKey),
+        mintAuthority: this.wallet.publicKey,
+        payer: this.wallet.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: web3.SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+        tokenMetadataProgram: METADATA_PROGRAM_ID,
+        metadata: metadataPDA,
+        masterEdition: masterEditionPDA,
+        feeConfig: UniversalNFTClient.getFeeConfigPDA()[0],
+        feeTreasury: null,
+        sellerFeeConfig: null,
+        feeCreator: null,
+        mintOnly: null,
+        lazyRevealConfig: null,
+        hourlyMetrics: null,
+      })
+      .instruction();
+
+    return {
+      transaction: await this.buildWithLookupTable('createMintAndNft', [instruction], options),
+      mintKeypair,
+      tokenId,
+    };
+  }
+
+  // Unsigned transfer transaction
+  async buildTransferTransaction(
+    tokenId: number,
+    finalChainId: number,
+    finalReceiver: Uint8Array, // 20-byte address on the final chain
+    options: BuildOptions = {},
+    memo: string | null = null,
+  ): Promise<Transaction | VersionedTransaction> {
+    const [programStatePDA] = UniversalNFTClient.getProgramStatePDA();
+    const [nftOriginPDA] = UniversalNFTClient.getNFTOriginPDA(tokenId);
+    const nftOrigin = await (this.program.account as any).nftOrigin.fetch(nftOriginPDA);
+    const mint = nftOrigin.mint as PublicKey;
+
+    // With receipts enabled the program mints one to the sender, so it needs the receipt accounts
+    const [receiptConfigPDA] = UniversalNFTClient.getReceiptConfigPDA();
+    const receiptConfig = await (this.program.account as any).receiptConfig.fetchNullable(receiptConfigPDA);
+    const [receiptMint] = UniversalNFTClient.getReceiptMintPDA(tokenId);
+    const receipts = receiptConfig?.enabled
+      ? {
+          receiptConfig: receiptConfigPDA,
+          receiptMint,
+          receiptTokenAccount: await this.getAssociatedTokenAddress(receiptMint),
+        }
+      : { receiptConfig: null, receiptMint: null, receiptTokenAccount: null };
+
+    const instruction = await this.program.methods
+      .transferCrossChain(new BN(tokenId), new BN(finalChainId), Buffer.from(finalReceiver), memo, null, null)
+      .accounts({
+        programState: programStatePDA,
+        nftOrigin: nftOriginPDA,
+        mint,
+        userTokenAccount: await this.getAssociatedTokenAddress(mint),
+        user: this.wallet.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        gatewayProgram: GATEWAY_PROGRAM_ID,
+        ...(await this.transferFeeAccounts()),
+        chainGasConfig: UniversalNFTClient.getChainGasConfigPDA(finalChainId)[0],
+        registry: UniversalNFTClient.registryFor(finalChainId),
+        zrc20GasVault: null,
+        zrc20VaultTokenAccount: null,
+        gasFeeDestination: null,
+        ...receipts,
+        hourlyMetrics: null,
+        insuranceOptIn: null,
+        coOwnership: null,
+        coOwnerConsent: null,
+      })
+      .instruction();
+
+    return this.buildWithLookupTable('transferCrossChain', [instruction], options);
+  }
+
+  // Unsigned receive transaction, for the gateway to sign
+  async buildReceiveTransaction(
+    tokenId: number,
+    message: Uint8Array,
+    gateway: PublicKey,
+    options: BuildOptions = {},
+    memo: string | null = null,
+  ): Promise<Transaction | VersionedTransaction> {
+    const instruction = await this.program.methods
+      .receiveCrossChainMessage(Buffer.from(message), memo)
+      .accounts({
+        ...(await this.receiveAccounts(tokenId, message, UniversalNFTClient.getMessageRecipient(message))),
+        gateway,
+        pendingInbound: null,
+        hourlyMetrics: null,
+        inboundValueClaim: null,
+        inboundReceipt: (await UniversalNFTClient.getInboundReceiptPDA(message))[0],
+      })
+      .remainingAccounts(await this.originPolicyAccounts())
+      .instruction();
+
+    return this.buildWithLookupTable('receiveCrossChainMessage', [instruction], options);
+  }
+
+  // Two-phase deployments: record the message for a later executeInbound. Only the configured
+  // gateway may sign, so the wallet must be it; the message names the recipient
+  async queueInbound(
+    message: Uint8Array,
+    memo: string | null = null,
+  ): Promise<string> {
+    try {
+      const tx = await this.program.methods
+        .queueInbound(Buffer.from(message), memo)
+        .accounts({
+          programState: UniversalNFTClient.getProgramStatePDA()[0],
+          pendingInbound: (await UniversalNFTClient.getPendingInboundPDA(message))[0],
+          gateway: this.wallet.publicKey,
+          payer: this.wallet.publicKey,
+        })
+        .rpc();
+
+      return tx;
+    } catch (error) {
+      console.error('Error queueing inbound message:', error);
+      throw error;
+    }
+  }
+
+  // Deliver a queued message; anyone can, and the queued record's rent comes back to them
+  async executeInbound(message: Uint8Array): Promise<string> {
+    try {
+      const [pendingInbound] = await UniversalNFTClient.getPendingInboundPDA(message);
+      const pending = await (this.program.account as any).pendingInbound.fetch(pendingInbound);
+
+      const tx = await this.program.methods
+        .executeInbound(Buffer.from(message))
+        .accounts({
+          ...(await this.receiveAccounts(pending.tokenId.toNumber(), message, pending.recipient)),
+          pendingInbound,
+          gateway: null,
+          hourlyMetrics: null,
+          inboundValueClaim: null,
+          inboundReceipt: (await UniversalNFTClient.getInboundReceiptPDA(message))[0],
+        })
+        .remainingAccounts(await this.originPolicyAccounts())
+        .rpc();
+
+      return tx;
+    } catch (error) {
+      console.error('Error executing inbound message:', error);
+      throw error;
+    }
+  }
+
+  // Deliveries always pass the OriginPolicy PDA of the configured universal contract, set or not
+  private async originPolicyAccounts(): Promise<web3.AccountMeta[]> {
+    const state = await (this.program.account as any).programState
+      .fetch(UniversalNFTClient.getProgramStatePDA()[0]);
+    const [originPolicy] = await UniversalNFTClient.getOriginPolicyPDA(
+      CHAIN_IDS.ZETACHAIN_TESTNET,
+      Uint8Array.from(state.universalNftContract),
+    );
+    return [{ pubkey: originPolicy, isSigner: false, isWritable: false }];
+  }
+
+  // Accounts shared by receiveCrossChainMessage and executeInbound
+  private async receiveAccounts(tokenId: number, message: Uint8Array, recipient: PublicKey) {
+    const [mint] = UniversalNFTClient.getBridgedMintPDA(CHAIN_IDS.ZETACHAIN_TESTNET, tokenId);
+
+    // A returning token with an outstanding receipt only goes back to the receipt holder
+    const nftOrigin = await (this.program.account as any).nftOrigin
+      .fetchNullable(UniversalNFTClient.getNFTOriginPDA(tokenId)[0]);
+    const [receiptMint] = UniversalNFTClient.getReceiptMintPDA(tokenId);
+    const receipts = nftOrigin?.receiptOutstanding
+      ? { receiptMint, receiptTokenAccount: await this.getAssociatedTokenAddress(receiptMint, recipient) }
+      : { receiptMint: null, receiptTokenAccount: null };
+
+    // A custodial binding redirects the delivery into the custodian's token account
+    const [receiverBinding] = UniversalNFTClient.getReceiverBindingPDA(recipient);
+    const binding = await (this.program.account as any).receiverBinding.fetchNullable(receiverBinding);
+    const custodialTokenAccount = binding?.deliveryMode?.custodial?.tokenAccount ?? null;
+
+    // While acceptance is required, other deliveries wait in escrow under an inbound offer
+    const state = await (this.program.account as any).programState
+      .fetch(UniversalNFTClient.getProgramStatePDA()[0]);
+    const acceptance = state.requireNftAcceptance && !custodialTokenAccount
+      ? {
+          inboundOffer: UniversalNFTClient.getInboundOfferPDA(tokenId, recipient)[0],
+          provisionalEscrow: await this.getAssociatedTokenAddress(mint, UniversalNFTClient.getMintAuthorityPDA()[0]),
+        }
+      : { inboundOffer: null, provisionalEscrow: null };
+
+    return {
+      programState: UniversalNFTClient.getProgramStatePDA()[0],
+      nftOrigin: UniversalNFTClient.getNFTOriginPDA(tokenId)[0],
+      mint,
+      dedupWindow: UniversalNFTClient.getDedupWindowPDA(UniversalNFTClient.getMessageUri(message))[0],
+      mintAuthority: UniversalNFTClient.getMintAuthorityPDA()[0],
+      recipient,
+      recipientTokenAccount: await this.getAssociatedTokenAddress(mint, recipient),
+      sourceChainConfig: UniversalNFTClient.getChainGasConfigPDA(CHAIN_IDS.ZETACHAIN_TESTNET)[0],
+      ...acceptance,
+      ...receipts,
+      receiverBinding,
+      custodialTokenAccount,
+      payer: this.wallet.publicKey,
+      systemProgram: web3.SystemProgram.programId,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      rent: SYSVAR_RENT_PUBKEY,
+    };
+  }
+
+  // Pause program; with autoUnpauseAt (unix seconds) the pause lifts itself at that time
+  async pauseProgram(
+    reasonCode: PauseReasonCode = PAUSE_REASONS.OTHER,
+    autoUnpauseAt: number | null = null,
+  ): Promise<string> {
     try {
       const [programStatePDA] = UniversalNFTClient.getProgramStatePDA();
       
       const tx = await this.program.methods
-        .pause()
+        .pause(reasonCode, autoUnpauseAt === null ? null : new BN(autoUnpauseAt))
         .accounts({
           programState: programStatePDA,
           admin: this.wallet.publicKey,
@@ -500,6 +1053,56 @@ export class UniversalNFTClient {
     }
   }
 
+  // Claim an inbound NFT offered to the connected wallet while acceptance is required
+  async acceptInboundNft(tokenId: number): Promise<string> {
+    try {
+      const [inboundOffer] = UniversalNFTClient.getInboundOfferPDA(tokenId, this.wallet.publicKey);
+      const offer = await (this.program.account as any).inboundNftOffer.fetch(inboundOffer);
+
+      const tx = await this.program.methods
+        .acceptInboundNft(new BN(tokenId))
+        .accounts({
+          inboundOffer,
+          mint: offer.mint,
+          recipient: this.wallet.publicKey,
+          payer: offer.payer,
+        })
+        .rpc();
+
+      return tx;
+    } catch (error) {
+      console.error('Error accepting inbound NFT:', error);
+      throw error;
+    }
+  }
+
+  // Write metadata a best-effort delivery skipped; anyone can pay for it
+  async retryMetadata(tokenId: number): Promise<string> {
+    try {
+      const [nftOrigin] = UniversalNFTClient.getNFTOriginPDA(tokenId);
+      const origin = await (this.program.account as any).nftOrigin.fetch(nftOrigin);
+      const [metadata] = PublicKey.findProgramAddressSync(
+        [Buffer.from('metadata'), METADATA_PROGRAM_ID.toBuffer(), origin.mint.toBuffer()],
+        METADATA_PROGRAM_ID
+      );
+
+      const tx = await this.program.methods
+        .retryMetadata(new BN(tokenId))
+        .accounts({
+          nftOrigin,
+          mint: origin.mint,
+          metadata,
+          payer: this.wallet.publicKey,
+        })
+        .rpc();
+
+      return tx;
+    } catch (error) {
+      console.error('Error retrying metadata:', error);
+      throw error;
+    }
+  }
+
   // Get program state
   async getProgramState(): Promise<ProgramState | null> {
     try {
@@ -876,10 +1479,13 @@ export class UniversalNFTClient {
   }
 
   // Helper function to get associated token address
-  private async getAssociatedTokenAddress(mint: PublicKey): Promise<PublicKey> {
+  private async getAssociatedTokenAddress(
+    mint: PublicKey,
+    owner: PublicKey = this.wallet.publicKey
+  ): Promise<PublicKey> {
     return await web3.PublicKey.findProgramAddress(
       [
-        this.wallet.publicKey.toBuffer(),
+        owner.toBuffer(),
         TOKEN_PROGRAM_ID.toBuffer(),
         mint.toBuffer(),
       ],
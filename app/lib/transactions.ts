@@ -0,0 +1,142 @@
+import {
+  AddressLookupTableAccount,
+  AddressLookupTableProgram,
+  ComputeBudgetProgram,
+  PACKET_DATA_SIZE,
+  PublicKey,
+  SYSVAR_RENT_PUBKEY,
+  SystemProgram,
+  Transaction,
+  TransactionInstruction,
+  TransactionMessage,
+  VersionedTransaction,
+} from '@solana/web3.js';
+import { TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID } from '@solana/spl-token';
+
+const METADATA_PROGRAM_ID = new PublicKey('metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s');
+
+export type InstructionKind =
+  | 'createMintAndNft'
+  | 'transferCrossChain'
+  | 'receiveCrossChainMessage'
+  | 'batchReceiveCrossChain';
+
+// Compute units each instruction consumes on localnet (per message for batches).
+// The Phase 8 test re-measures them and fails when one drifts by more than COMPUTE_UNIT_DRIFT.
+export const COMPUTE_UNIT_ESTIMATES: Record<InstructionKind, number> = {
+  createMintAndNft: 120_000,
+  transferCrossChain: 30_000,
+  receiveCrossChainMessage: 60_000,
+  batchReceiveCrossChain: 55_000,
+};
+export const COMPUTE_UNIT_DRIFT = 0.2;
+
+// Limit to request for `count` instructions of `kind`, leaving room for the drift above
+export function estimateComputeUnits(kind: InstructionKind, count: number = 1): number {
+  return Math.ceil(COMPUTE_UNIT_ESTIMATES[kind] * count * (1 + COMPUTE_UNIT_DRIFT));
+}
+
+export interface TransactionOptions {
+  payer: PublicKey;
+  recentBlockhash: string;
+  computeUnitLimit?: number;
+  priorityFeeMicroLamports?: number;
+  // When missing, a legacy transaction is built instead of a v0 one
+  lookupTable?: AddressLookupTableAccount | null;
+}
+
+// Accounts every mint/transfer/receive touches. Programs invoked at the top level
+// (the compute budget program, our own program) must stay static keys, so they are
+// not listed here.
+export function getStaticLookupAddresses(programId: PublicKey, extra: PublicKey[] = []): PublicKey[] {
+  const [programState] = PublicKey.findProgramAddressSync([Buffer.from('test')], programId);
+  const [mintAuthority] = PublicKey.findProgramAddressSync([Buffer.from('mint_authority')], programId);
+  const [kycConfig] = PublicKey.findProgramAddressSync([Buffer.from('kyc_config')], programId);
+
+  return [
+    programState,
+    mintAuthority,
+    kycConfig,
+    TOKEN_PROGRAM_ID,
+    ASSOCIATED_TOKEN_PROGRAM_ID,
+    METADATA_PROGRAM_ID,
+    SystemProgram.programId,
+    SYSVAR_RENT_PUBKEY,
+    ...extra,
+  ];
+}
+
+// Instructions that create a lookup table and fill it with `addresses`
+export function buildLookupTableInstructions(
+  authority: PublicKey,
+  payer: PublicKey,
+  recentSlot: number,
+  addresses: PublicKey[],
+): { lookupTable: PublicKey; instructions: TransactionInstruction[] } {
+  const [createIx, lookupTable] = AddressLookupTableProgram.createLookupTable({
+    authority,
+    payer,
+    recentSlot,
+  });
+  const extendIx = AddressLookupTableProgram.extendLookupTable({
+    lookupTable,
+    authority,
+    payer,
+    addresses,
+  });
+
+  return { lookupTable, instructions: [createIx, extendIx] };
+}
+
+export function withComputeBudget(
+  instructions: TransactionInstruction[],
+  options: Pick<TransactionOptions, 'computeUnitLimit' | 'priorityFeeMicroLamports'>,
+): TransactionInstruction[] {
+  const budget: TransactionInstruction[] = [];
+  if (options.computeUnitLimit !== undefined) {
+    budget.push(ComputeBudgetProgram.setComputeUnitLimit({ units: options.computeUnitLimit }));
+  }
+  if (options.priorityFeeMicroLamports !== undefined) {
+    budget.push(ComputeBudgetProgram.setComputeUnitPrice({ microLamports: options.priorityFeeMicroLamports }));
+  }
+  return [...budget, ...instructions];
+}
+
+// v0 transaction through the lookup table, or a legacy one when there is no table
+export function buildTransaction(
+  instructions: TransactionInstruction[],
+  options: TransactionOptions,
+): Transaction | VersionedTransaction {
+  const all = withComputeBudget(instructions, options);
+
+  if (!options.lookupTable) {
+    const transaction = new Transaction().add(...all);
+    transaction.feePayer = options.payer;
+    transaction.recentBlockhash = options.recentBlockhash;
+    return transaction;
+  }
+
+  const message = new TransactionMessage({
+    payerKey: options.payer,
+    recentBlockhash: options.recentBlockhash,
+    instructions: all,
+  }).compileToV0Message([options.lookupTable]);
+  return new VersionedTransaction(message);
+}
+
+// Wire size once signed, counting every required signature
+export function transactionSize(transaction: Transaction | VersionedTransaction): number {
+  const message = transaction instanceof VersionedTransaction
+    ? transaction.message.serialize()
+    : transaction.compileMessage().serialize();
+  const signatures = transaction instanceof VersionedTransaction
+    ? transaction.message.header.numRequiredSignatures
+    : transaction.compileMessage().header.numRequiredSignatures;
+
+  // Signature count is a compact-u16; one byte covers anything that fits a packet
+  return 1 + signatures * 64 + message.length;
+}
+
+export function fitsInPacket(transaction: Transaction | VersionedTransaction): boolean {
+  return transactionSize(transaction) <= PACKET_DATA_SIZE;
+}
@@ -0,0 +1,379 @@
+//! Portable form of the connected contract registry, the chain configs and the origin
+//! policies, so a redeployment under a new program id can take them over without
+//! re-entering every entry. `export_config` returns one page, `import_config` takes the same
+//! bytes back:
+//!
+//! `version (1) || kind (1) || page (1) || page_count (1) || count (1)`, then per entry, by kind:
+//!
+//! - registry: `chain_id (8, LE) || zrc20 (20) || flags (1) || contract_len (1) || contract`
+//! - chain configs: `chain_id || gas_limit || block_gas_limit || trust_level || provisional_hold_seconds
+//!   || supports_batch`, Borsh-encoded
+//! - origin policies: `origin_chain || origin_contract_hash || royalty_bps || royalty_recipient
+//!   || update_authority_mode || is_mutable`, Borsh-encoded
+//!
+//! The registry is paged by the program. Chain configs and policies live in one PDA each,
+//! which the program can't list, so they are exported from the accounts the caller passes
+//! and each such page stands alone as page 0 of 1. Liveness and verification times belong
+//! to the deployment that recorded them and are left out, as are bumps
+
+use anchor_lang::prelude::*;
+
+use crate::{ChainGasConfig, OriginPolicy, RegistryEntry, UpdateAuthorityMode, MAX_REGISTRY_CONTRACT_BYTES};
+
+pub const CONFIG_EXPORT_VERSION: u8 = 2;
+pub const CONFIG_KIND_REGISTRY: u8 = 0;
+pub const CONFIG_KIND_CHAIN_CONFIGS: u8 = 1;
+pub const CONFIG_KIND_ORIGIN_POLICIES: u8 = 2;
+/// Entries per page; a full page of 64-byte contracts (5 + 8 * 94 bytes) still fits in the
+/// transaction that imports it
+pub const CONFIG_EXPORT_PAGE_ENTRIES: usize = 8;
+/// Origin policies per page; each takes up to 108 bytes and its PDA comes along on import
+pub const CONFIG_EXPORT_POLICY_PAGE_ENTRIES: usize = 4;
+
+const PAGE_HEADER_BYTES: usize = 5;
+const ENTRY_HEADER_BYTES: usize = 8 + 20 + 1 + 1;
+
+/// One registry entry as exported
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConfigEntry {
+    pub chain_id: u64,
+    pub zrc20: [u8; 20],
+    pub flags: u8,
+    pub contract: Vec<u8>,
+}
+
+impl From<&RegistryEntry> for ConfigEntry {
+    fn from(entry: &RegistryEntry) -> Self {
+        Self {
+            chain_id: entry.chain_id,
+            zrc20: entry.zrc20,
+            flags: entry.flags,
+            contract: entry.contract[..entry.contract_len as usize].to_vec(),
+        }
+    }
+}
+
+/// One ChainGasConfig as exported
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ChainConfigEntry {
+    pub chain_id: u64,
+    pub gas_limit: u64,
+    pub block_gas_limit: u64,
+    pub trust_level: u8,
+    pub provisional_hold_seconds: u32,
+    pub supports_batch: bool,
+}
+
+impl From<&ChainGasConfig> for ChainConfigEntry {
+    fn from(config: &ChainGasConfig) -> Self {
+        Self {
+            chain_id: config.chain_id,
+            gas_limit: config.gas_limit,
+            block_gas_limit: config.block_gas_limit,
+            trust_level: config.trust_level,
+            provisional_hold_seconds: config.provisional_hold_seconds,
+            supports_batch: config.supports_batch,
+        }
+    }
+}
+
+/// One OriginPolicy as exported
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct OriginPolicyEntry {
+    pub origin_chain: u64,
+    pub origin_contract_hash: [u8; 32],
+    pub royalty_bps: u16,
+    pub royalty_recipient: Pubkey,
+    pub update_authority_mode: UpdateAuthorityMode,
+    pub is_mutable: bool,
+}
+
+impl From<&OriginPolicy> for OriginPolicyEntry {
+    fn from(policy: &OriginPolicy) -> Self {
+        Self {
+            origin_chain: policy.origin_chain,
+            origin_contract_hash: policy.origin_contract_hash,
+            royalty_bps: policy.royalty_bps,
+            royalty_recipient: policy.royalty_recipient,
+            update_authority_mode: policy.update_authority_mode,
+            is_mutable: policy.is_mutable,
+        }
+    }
+}
+
+/// The entries of one page, all of one kind
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConfigEntries {
+    Registry(Vec<ConfigEntry>),
+    ChainConfigs(Vec<ChainConfigEntry>),
+    OriginPolicies(Vec<OriginPolicyEntry>),
+}
+
+impl ConfigEntries {
+    pub fn kind(&self) -> u8 {
+        match self {
+            Self::Registry(_) => CONFIG_KIND_REGISTRY,
+            Self::ChainConfigs(_) => CONFIG_KIND_CHAIN_CONFIGS,
+            Self::OriginPolicies(_) => CONFIG_KIND_ORIGIN_POLICIES,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Registry(entries) => entries.len(),
+            Self::ChainConfigs(entries) => entries.len(),
+            Self::OriginPolicies(entries) => entries.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Most entries a page of `kind` holds; None for an unknown kind
+pub fn page_entries(kind: u8) -> Option<usize> {
+    match kind {
+        CONFIG_KIND_REGISTRY | CONFIG_KIND_CHAIN_CONFIGS => Some(CONFIG_EXPORT_PAGE_ENTRIES),
+        CONFIG_KIND_ORIGIN_POLICIES => Some(CONFIG_EXPORT_POLICY_PAGE_ENTRIES),
+        _ => None,
+    }
+}
+
+/// Page `page` of `page_count`, holding up to `page_entries` entries of its kind
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConfigPage {
+    pub page: u8,
+    pub page_count: u8,
+    pub entries: ConfigEntries,
+}
+
+impl ConfigPage {
+    /// Pages needed for `entry_count` registry entries; an empty registry still exports one page
+    pub fn page_count(entry_count: usize) -> u8 {
+        entry_count.div_ceil(CONFIG_EXPORT_PAGE_ENTRIES).max(1) as u8
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut blob = vec![
+            CONFIG_EXPORT_VERSION,
+            self.entries.kind(),
+            self.page,
+            self.page_count,
+            self.entries.len() as u8,
+        ];
+        match &self.entries {
+            ConfigEntries::Registry(entries) => {
+                for entry in entries {
+                    blob.extend_from_slice(&entry.chain_id.to_le_bytes());
+                    blob.extend_from_slice(&entry.zrc20);
+                    blob.push(entry.flags);
+                    blob.push(entry.contract.len() as u8);
+                    blob.extend_from_slice(&entry.contract);
+                }
+            }
+            // Writing into a Vec can't fail
+            ConfigEntries::ChainConfigs(entries) => {
+                entries.iter().for_each(|entry| entry.serialize(&mut blob).unwrap());
+            }
+            ConfigEntries::OriginPolicies(entries) => {
+                entries.iter().for_each(|entry| entry.serialize(&mut blob).unwrap());
+            }
+        }
+        blob
+    }
+
+    /// Read a page back, rejecting other format versions and any malformed or trailing bytes
+    pub fn decode(blob: &[u8]) -> Result<Self> {
+        let (header, mut rest) = blob.split_at_checked(PAGE_HEADER_BYTES)
+            .ok_or(crate::ErrorCode::InvalidConfigBlob)?;
+        require!(header[0] == CONFIG_EXPORT_VERSION, crate::ErrorCode::UnsupportedConfigVersion);
+        let (kind, page, page_count, count) = (header[1], header[2], header[3], header[4] as usize);
+        let max_entries = page_entries(kind).ok_or(crate::ErrorCode::InvalidConfigBlob)?;
+        require!(page < page_count && count <= max_entries, crate::ErrorCode::InvalidConfigBlob);
+
+        let entries = match kind {
+            CONFIG_KIND_REGISTRY => {
+                let mut entries = Vec::with_capacity(count);
+                for _ in 0..count {
+                    let (entry_header, tail) = rest.split_at_checked(ENTRY_HEADER_BYTES)
+                        .ok_or(crate::ErrorCode::InvalidConfigBlob)?;
+                    let contract_len = entry_header[29] as usize;
+                    require!(
+                        contract_len > 0 && contract_len <= MAX_REGISTRY_CONTRACT_BYTES,
+                        crate::ErrorCode::InvalidConfigBlob
+                    );
+                    let (contract, tail) = tail.split_at_checked(contract_len)
+                        .ok_or(crate::ErrorCode::InvalidConfigBlob)?;
+                    entries.push(ConfigEntry {
+                        chain_id: u64::from_le_bytes(entry_header[..8].try_into().unwrap()),
+                        zrc20: entry_header[8..28].try_into().unwrap(),
+                        flags: entry_header[28],
+                        contract: contract.to_vec(),
+                    });
+                    rest = tail;
+                }
+                ConfigEntries::Registry(entries)
+            }
+            CONFIG_KIND_CHAIN_CONFIGS => ConfigEntries::ChainConfigs(Self::decode_borsh(&mut rest, count)?),
+            _ => ConfigEntries::OriginPolicies(Self::decode_borsh(&mut rest, count)?),
+        };
+        require!(rest.is_empty(), crate::ErrorCode::InvalidConfigBlob);
+
+        Ok(Self { page, page_count, entries })
+    }
+
+    fn decode_borsh<T: AnchorDeserialize>(rest: &mut &[u8], count: usize) -> Result<Vec<T>> {
+        (0..count)
+            .map(|_| T::deserialize(rest).map_err(|_| crate::ErrorCode::InvalidConfigBlob.into()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(seed: u8, contract_len: usize) -> ConfigEntry {
+        ConfigEntry {
+            chain_id: 11155111 + seed as u64,
+            zrc20: [seed; 20],
+            flags: seed % 4,
+            contract: vec![seed.wrapping_add(1); contract_len],
+        }
+    }
+
+    fn chain_config(seed: u8) -> ChainConfigEntry {
+        ChainConfigEntry {
+            chain_id: 11155111 + seed as u64,
+            gas_limit: 100_000 * seed as u64,
+            block_gas_limit: 1_000_000,
+            trust_level: seed % 2,
+            provisional_hold_seconds: 60 * seed as u32,
+            supports_batch: seed.is_multiple_of(2),
+        }
+    }
+
+    fn origin_policy(seed: u8) -> OriginPolicyEntry {
+        OriginPolicyEntry {
+            origin_chain: 11155111 + seed as u64,
+            origin_contract_hash: [seed; 32],
+            royalty_bps: 500,
+            royalty_recipient: Pubkey::new_from_array([seed.wrapping_add(1); 32]),
+            update_authority_mode: UpdateAuthorityMode::Retained { authority: Pubkey::new_from_array([seed; 32]) },
+            is_mutable: seed.is_multiple_of(2),
+        }
+    }
+
+    fn error_name(error: anchor_lang::error::Error) -> String {
+        match error {
+            anchor_lang::error::Error::AnchorError(error) => error.error_name,
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+
+    #[test]
+    fn pages_round_trip() {
+        let pages = [
+            ConfigPage { page: 0, page_count: 1, entries: ConfigEntries::Registry(vec![]) },
+            ConfigPage { page: 0, page_count: 2, entries: ConfigEntries::Registry(vec![entry(1, 20), entry(2, 32)]) },
+            ConfigPage {
+                page: 7,
+                page_count: 8,
+                entries: ConfigEntries::Registry(
+                    (0..CONFIG_EXPORT_PAGE_ENTRIES as u8).map(|seed| entry(seed, MAX_REGISTRY_CONTRACT_BYTES)).collect(),
+                ),
+            },
+            ConfigPage {
+                page: 0,
+                page_count: 1,
+                entries: ConfigEntries::ChainConfigs((0..CONFIG_EXPORT_PAGE_ENTRIES as u8).map(chain_config).collect()),
+            },
+            ConfigPage {
+                page: 0,
+                page_count: 1,
+                entries: ConfigEntries::OriginPolicies(vec![
+                    origin_policy(1),
+                    OriginPolicyEntry { update_authority_mode: UpdateAuthorityMode::Program, ..origin_policy(2) },
+                ]),
+            },
+        ];
+        for page in pages {
+            let blob = page.encode();
+            assert_eq!(ConfigPage::decode(&blob).unwrap(), page);
+        }
+    }
+
+    #[test]
+    fn full_page_fits_the_documented_size() {
+        let page = ConfigPage {
+            page: 0,
+            page_count: 1,
+            entries: ConfigEntries::Registry(
+                (0..CONFIG_EXPORT_PAGE_ENTRIES as u8).map(|seed| entry(seed, MAX_REGISTRY_CONTRACT_BYTES)).collect(),
+            ),
+        };
+        assert_eq!(page.encode().len(), 5 + 8 * 94);
+        let page = ConfigPage {
+            page: 0,
+            page_count: 1,
+            entries: ConfigEntries::OriginPolicies((0..CONFIG_EXPORT_POLICY_PAGE_ENTRIES as u8).map(origin_policy).collect()),
+        };
+        assert_eq!(page.encode().len(), 5 + 4 * 108);
+    }
+
+    #[test]
+    fn page_count_rounds_up() {
+        assert_eq!(ConfigPage::page_count(0), 1);
+        assert_eq!(ConfigPage::page_count(CONFIG_EXPORT_PAGE_ENTRIES), 1);
+        assert_eq!(ConfigPage::page_count(CONFIG_EXPORT_PAGE_ENTRIES + 1), 2);
+        assert_eq!(ConfigPage::page_count(crate::REGISTRY_CAPACITY), 8);
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let mut blob = ConfigPage { page: 0, page_count: 1, entries: ConfigEntries::Registry(vec![entry(1, 20)]) }.encode();
+        blob[0] = CONFIG_EXPORT_VERSION + 1;
+        assert_eq!(error_name(ConfigPage::decode(&blob).unwrap_err()), "UnsupportedConfigVersion");
+    }
+
+    #[test]
+    fn rejects_malformed_blobs() {
+        let blob = ConfigPage { page: 0, page_count: 1, entries: ConfigEntries::Registry(vec![entry(1, 20)]) }.encode();
+        let mut trailing = blob.clone();
+        trailing.push(0);
+        let mut page_past_count = blob.clone();
+        page_past_count[2] = 1;
+        let mut unknown_kind = blob.clone();
+        unknown_kind[1] = CONFIG_KIND_ORIGIN_POLICIES + 1;
+        let mut too_many_policies = ConfigPage {
+            page: 0,
+            page_count: 1,
+            entries: ConfigEntries::OriginPolicies(vec![origin_policy(1)]),
+        }.encode();
+        too_many_policies[4] = CONFIG_EXPORT_POLICY_PAGE_ENTRIES as u8 + 1;
+        let truncated_config = ConfigPage {
+            page: 0,
+            page_count: 1,
+            entries: ConfigEntries::ChainConfigs(vec![chain_config(1)]),
+        }.encode();
+        let mut empty_contract = blob.clone();
+        empty_contract[PAGE_HEADER_BYTES + 29] = 0;
+        let mut oversized_contract = blob.clone();
+        oversized_contract[PAGE_HEADER_BYTES + 29] = MAX_REGISTRY_CONTRACT_BYTES as u8 + 1;
+
+        for malformed in [
+            &blob[..3],
+            &blob[..blob.len() - 1],
+            &trailing[..],
+            &page_past_count[..],
+            &unknown_kind[..],
+            &too_many_policies[..],
+            &truncated_config[..truncated_config.len() - 1],
+            &empty_contract[..],
+            &oversized_contract[..],
+        ] {
+            assert_eq!(error_name(ConfigPage::decode(malformed).unwrap_err()), "InvalidConfigBlob");
+        }
+    }
+}
@@ -0,0 +1,29 @@
+//! Events collected while a handler runs and logged together at its end. Handlers write
+//! state first, then make their CPIs, then flush, so an indexer reading the logs sees every
+//! event of an instruction after all of its CPI output and in the order they were pushed
+
+use anchor_lang::Event;
+use anchor_lang::solana_program::log::sol_log_data;
+
+#[derive(Default)]
+pub struct EventBuffer {
+    events: Vec<Vec<u8>>,
+}
+
+impl EventBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue `event`; it is logged exactly as `emit!` would log it
+    pub fn push<E: Event>(&mut self, event: E) {
+        self.events.push(event.data());
+    }
+
+    /// Log the queued events in push order; the handler's last step before returning
+    pub fn flush(self) {
+        for data in &self.events {
+            sol_log_data(&[data]);
+        }
+    }
+}
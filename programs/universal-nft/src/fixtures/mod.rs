@@ -0,0 +1,163 @@
+//! Message encoding vectors shared with the Solidity Universal NFT. A message built on one
+//! side must decode identically on the other, so both repos check their codec against the
+//! same bytes: here with `cargo test --features fixtures`, on the EVM side by asserting
+//! `abi.encode(receiver, tokenId, uri, gasAmount, sender)` equals `inbound` and
+//! `abi.encode(destination, receiver, tokenId, uri, sender)` equals `outbound` for the same
+//! inputs. `abi_vectors` gives the `cast` commands that produce both.
+//!
+//! The decoder also still reads bodies in the legacy layout that came before these vectors;
+//! see `UniversalNFTCoreImpl::body_fields`.
+
+pub mod abi_vectors;
+
+pub use abi_vectors::*;
+
+/// One vector: the inputs to `abi.encode` and the bytes it produces for each tuple
+pub struct AbiFixture {
+    pub name: &'static str,
+    pub destination: [u8; 20],
+    pub receiver: [u8; 20],
+    pub token_id: u64,
+    pub uri: &'static str,
+    pub gas_amount: u64,
+    pub sender: [u8; 20],
+    /// `abi.encode(receiver, tokenId, uri, gasAmount, sender)`
+    pub inbound: &'static [u8],
+    /// `abi.encode(destination, receiver, tokenId, uri, sender)`
+    pub outbound: &'static [u8],
+}
+
+pub const ABI_FIXTURES: &[AbiFixture] =
+    &[SHORT_URI, LONG_URI, MAX_TOKEN_ID, ZERO_SENDER, UNICODE_URI, MINT_ON_ZETACHAIN];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::universal_nft_core::{
+        CrossChainNftMessage, OutboundTrailers, UniversalNFTCoreImpl, ZetaChainCallOptions,
+    };
+
+    fn word(encoded: &[u8], index: usize) -> &[u8] {
+        &encoded[index * 32..(index + 1) * 32]
+    }
+
+    fn word_u64(encoded: &[u8], index: usize) -> u64 {
+        let word = word(encoded, index);
+        assert!(word[..24].iter().all(|&byte| byte == 0), "word {} exceeds u64", index);
+        u64::from_be_bytes(word[24..].try_into().unwrap())
+    }
+
+    fn address(encoded: &[u8], index: usize, name: &str) -> [u8; 20] {
+        assert_eq!(word(encoded, index)[..12], [0u8; 12], "{}: word {} padding", name, index);
+        word(encoded, index)[12..].try_into().unwrap()
+    }
+
+    /// The uri tail after a five-word head: its length word, then its bytes zero-padded
+    fn assert_uri_tail(encoded: &[u8], fixture: &AbiFixture) {
+        let uri = fixture.uri.as_bytes();
+        assert_eq!(encoded.len(), 6 * 32 + uri.len().div_ceil(32) * 32, "{}: length", fixture.name);
+        assert_eq!(word_u64(encoded, 5), uri.len() as u64, "{}: uri length", fixture.name);
+        assert_eq!(&encoded[6 * 32..6 * 32 + uri.len()], uri, "{}: uri", fixture.name);
+        assert!(encoded[6 * 32 + uri.len()..].iter().all(|&byte| byte == 0), "{}: uri padding", fixture.name);
+    }
+
+    fn message(fixture: &AbiFixture) -> CrossChainNftMessage {
+        CrossChainNftMessage::forward(
+            fixture.destination,
+            fixture.receiver,
+            fixture.token_id,
+            fixture.uri.to_string(),
+            fixture.sender,
+        )
+    }
+
+    /// Guards the vectors themselves against the two Solidity tuples
+    #[test]
+    fn fixtures_are_canonical_abi() {
+        for fixture in ABI_FIXTURES {
+            let inbound = fixture.inbound;
+            assert_eq!(address(inbound, 0, fixture.name), fixture.receiver, "{}: receiver", fixture.name);
+            assert_eq!(word_u64(inbound, 1), fixture.token_id, "{}: token id", fixture.name);
+            assert_eq!(word_u64(inbound, 2), 5 * 32, "{}: uri offset", fixture.name);
+            assert_eq!(word_u64(inbound, 3), fixture.gas_amount, "{}: gas amount", fixture.name);
+            assert_eq!(address(inbound, 4, fixture.name), fixture.sender, "{}: sender", fixture.name);
+            assert_uri_tail(inbound, fixture);
+
+            let outbound = fixture.outbound;
+            assert_eq!(address(outbound, 0, fixture.name), fixture.destination, "{}: destination", fixture.name);
+            assert_eq!(address(outbound, 1, fixture.name), fixture.receiver, "{}: receiver", fixture.name);
+            assert_eq!(word_u64(outbound, 2), fixture.token_id, "{}: token id", fixture.name);
+            assert_eq!(word_u64(outbound, 3), 5 * 32, "{}: uri offset", fixture.name);
+            assert_eq!(address(outbound, 4, fixture.name), fixture.sender, "{}: sender", fixture.name);
+            assert_uri_tail(outbound, fixture);
+        }
+    }
+
+    /// Inbound bodies carry no destination; the NFT is minted here
+    #[test]
+    fn decoder_parses_canonical_bytes() {
+        for fixture in ABI_FIXTURES {
+            let decoded = UniversalNFTCoreImpl::decode_cross_chain_message(fixture.inbound)
+                .unwrap_or_else(|error| panic!("{}: {:?}", fixture.name, error));
+            let expected = CrossChainNftMessage::mint_here(
+                fixture.receiver,
+                fixture.token_id,
+                fixture.uri.to_string(),
+                fixture.sender,
+            );
+            assert_eq!(decoded, expected, "{}", fixture.name);
+        }
+    }
+
+    /// Gateways that still send the legacy layout must decode to the same message
+    #[test]
+    fn decoder_still_parses_legacy_bytes() {
+        for fixture in ABI_FIXTURES {
+            let uri = fixture.uri.as_bytes();
+            let mut legacy = vec![0u8; 108];
+            legacy[12..32].copy_from_slice(&fixture.receiver);
+            legacy[32..40].copy_from_slice(&fixture.token_id.to_be_bytes());
+            legacy[64..72].copy_from_slice(&100u64.to_be_bytes());
+            legacy[80..100].copy_from_slice(&fixture.sender);
+            legacy[100..108].copy_from_slice(&(uri.len() as u64).to_be_bytes());
+            legacy.extend_from_slice(uri);
+            legacy.resize(108 + uri.len().div_ceil(32) * 32, 0);
+
+            let decoded = UniversalNFTCoreImpl::decode_cross_chain_message(&legacy)
+                .unwrap_or_else(|error| panic!("{}: {:?}", fixture.name, error));
+            let expected = CrossChainNftMessage::mint_here(
+                fixture.receiver,
+                fixture.token_id,
+                fixture.uri.to_string(),
+                fixture.sender,
+            );
+            assert_eq!(decoded, expected, "{}", fixture.name);
+        }
+    }
+
+    #[test]
+    fn encoder_reproduces_inbound_bytes() {
+        for fixture in ABI_FIXTURES {
+            let encoded = UniversalNFTCoreImpl::encode_inbound_body(&message(fixture), fixture.gas_amount);
+            assert_eq!(encoded, fixture.inbound, "{}", fixture.name);
+        }
+    }
+
+    /// The encoder appends its trailers; Solidity's abi.decode ignores trailing bytes
+    #[test]
+    fn encoder_reproduces_outbound_bytes() {
+        for fixture in ABI_FIXTURES {
+            let call_options = ZetaChainCallOptions::default();
+            let encoded = UniversalNFTCoreImpl::encode_cross_chain_message(&message(fixture), &call_options);
+            assert!(encoded.len() > fixture.outbound.len(), "{}: too short", fixture.name);
+            assert_eq!(&encoded[..fixture.outbound.len()], fixture.outbound, "{}", fixture.name);
+
+            let full = UniversalNFTCoreImpl::encode_outbound_message(
+                &message(fixture),
+                &call_options,
+                &OutboundTrailers::default(),
+            );
+            assert_eq!(&full[..fixture.outbound.len()], fixture.outbound, "{}: full message", fixture.name);
+        }
+    }
+}
@@ -0,0 +1,101 @@
+//! Accounting invariants, checked where the handlers that can break them finish. Compiled in
+//! only with the `runtime-invariants` feature: devnet builds turn it on, mainnet builds leave
+//! it off and pay nothing. A violation logs which invariant broke and fails the instruction
+//! with `InvariantViolated`
+//!
+//! The checks run at the end of every handler that changes a mint's supply, `next_token_id`
+//! or a chain's stats, for what it changed; every chain stats write goes through one. That is
+//! the whole scope: the remaining handlers write none of this state, so there would be nothing
+//! for a check to catch and it would only cost them compute. A handler that starts writing
+//! such state must add the check. Semi-fungible tokens are exempt from the one-unit checks,
+//! and `cancel_pending_transfer` from the monotone one, since taking back a departure lowers
+//! `departed`
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+use crate::{ChainStats, ProgramState};
+
+fn ensure(holds: bool, invariant: &str) -> Result<()> {
+    if !holds {
+        msg!("Invariant violated: {}", invariant);
+        return err!(crate::ErrorCode::InvariantViolated);
+    }
+    Ok(())
+}
+
+// Read from the account data rather than a typed account, which keeps the supply it
+// loaded before the handler's CPIs
+fn mint_supply(mint: &AccountInfo) -> Result<u64> {
+    Ok(Mint::try_deserialize(&mut &mint.try_borrow_data()?[..])?.supply)
+}
+
+/// A token on Solana has one circulating unit, held by its owner or in escrow; units
+/// stranded by `recover_misdelivered` count toward the supply on top of it
+pub fn token_on_solana(mint: &AccountInfo, stranded_supply: u64) -> Result<()> {
+    ensure(
+        mint_supply(mint)? == stranded_supply.saturating_add(1),
+        "a token on Solana has a circulating supply of one",
+    )
+}
+
+/// A departed token's unit was burned, leaving only stranded units
+pub fn token_departed(mint: &AccountInfo, stranded_supply: u64) -> Result<()> {
+    ensure(
+        mint_supply(mint)? == stranded_supply,
+        "a departed token has no circulating supply",
+    )
+}
+
+/// `next_token_id` stays above every native token id issued
+pub fn next_token_id(program_state: &ProgramState) -> Result<()> {
+    ensure(
+        program_state.next_token_id > program_state.max_native_token_id,
+        "next_token_id is above every native token id issued",
+    )
+}
+
+/// Chain counters only grow; `active` is the one that may fall
+pub fn chain_stats_monotone(before: &ChainStats, after: &ChainStats) -> Result<()> {
+    ensure(
+        after.received >= before.received
+            && after.departed >= before.departed
+            && after.cumulative_dwell_seconds >= before.cumulative_dwell_seconds,
+        "chain stats counters never decrease",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::match_error_code;
+
+    fn program_state(next_token_id: u64, max_native_token_id: u64) -> ProgramState {
+        let mut state = ProgramState::deserialize(&mut &[0u8; ProgramState::INIT_SPACE][..]).unwrap();
+        state.next_token_id = next_token_id;
+        state.max_native_token_id = max_native_token_id;
+        state
+    }
+
+    fn chain_stats(received: u64, departed: u64, active: u64, cumulative_dwell_seconds: u64) -> ChainStats {
+        ChainStats { chain_id: 7001, received, departed, active, cumulative_dwell_seconds, bump: 255 }
+    }
+
+    #[test]
+    fn next_token_id_must_pass_every_native_id() {
+        assert!(next_token_id(&program_state(11, 10)).is_ok());
+        assert!(next_token_id(&program_state(1, 0)).is_ok());
+        assert!(match_error_code(&next_token_id(&program_state(10, 10)), crate::ErrorCode::InvariantViolated));
+        assert!(match_error_code(&next_token_id(&program_state(0, 0)), crate::ErrorCode::InvariantViolated));
+    }
+
+    #[test]
+    fn only_active_may_fall() {
+        let before = chain_stats(5, 3, 2, 100);
+        assert!(chain_stats_monotone(&before, &before).is_ok());
+        assert!(chain_stats_monotone(&before, &chain_stats(6, 4, 0, 160)).is_ok());
+        for after in [chain_stats(4, 3, 2, 100), chain_stats(5, 2, 2, 100), chain_stats(5, 3, 2, 99)] {
+            assert!(match_error_code(&chain_stats_monotone(&before, &after), crate::ErrorCode::InvariantViolated));
+        }
+    }
+}
@@ -1,14 +1,30 @@
+// Anchor's generated IDL instructions still call the deprecated `AccountInfo::realloc`
+#![allow(deprecated)]
+
 use anchor_lang::prelude::*;
 use anchor_spl::{
     token::{Mint, Token, TokenAccount},
     associated_token::AssociatedToken,
+    memo::Memo,
 };
  
+use anchor_lang::solana_program::program_option::COption;
 use anchor_lang::solana_program::rent::Rent;
 
 // Import our custom modules
 pub mod universal_nft;
 pub mod universal_nft_core;
+pub mod pyth;
+pub mod pda;
+pub mod event_buffer;
+pub mod planner;
+pub mod config_export;
+#[cfg(feature = "serde")]
+pub mod serde_helpers;
+#[cfg(feature = "fixtures")]
+pub mod fixtures;
+#[cfg(feature = "runtime-invariants")]
+pub mod invariants;
 
 // Re-export main types for easy access
 pub use universal_nft::*;
@@ -27,17 +43,329 @@ pub const CHAIN_ID_BSC_TESTNET: u64 = 97;
 pub const CHAIN_ID_POLYGON_AMOY: u64 = 80002;
 pub const CHAIN_ID_ARBITRUM_SEPOLIA: u64 = 421614;
 pub const CHAIN_ID_BITCOIN_TESTNET: u64 = 18332;
-pub const CHAIN_ID_SOLANA: u64 = 0;
+pub const CHAIN_ID_SOLANA_MAINNET: u64 = 900;
+pub const CHAIN_ID_SOLANA_LOCALNET: u64 = 902;
+pub const CHAIN_ID_SOLANA: u64 = CHAIN_ID_SOLANA_MAINNET;
+
+// Genesis hashes of the public Solana clusters, for naming the one initialize is told it runs on
+pub const GENESIS_HASH_SOLANA_MAINNET: [u8; 32] = pubkey!("5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d").to_bytes();
+pub const GENESIS_HASH_SOLANA_DEVNET: [u8; 32] = pubkey!("EtWTRABZaYq6iMfeYKouRu166VU2xqa1wcaWoxPkrZBG").to_bytes();
+pub const GENESIS_HASH_SOLANA_TESTNET: [u8; 32] = pubkey!("4uhcVJyU9pJkvQyS88uRDiswHXSCkY3zQawwpjk2NsNY").to_bytes();
 
 // Add these constants for ZetaChain integration
 pub const ZETA_CHAIN_ID: u64 = 7001; // ZetaChain testnet
 pub const ZETA_MAINNET_ID: u64 = 7000; // ZetaChain mainnet
 
+// Gas limit bounds for outbound calls, in destination-chain gas units
+#[constant]
+pub const MIN_GAS_LIMIT: u64 = 100_000; // below this even a bare onCall runs out of gas
+#[constant]
+pub const MAX_GAS_LIMIT: u64 = 30_000_000; // Ethereum mainnet block gas limit
+
+// Swap router versions used by `swap_tokens`
+pub const SWAP_ROUTER_V2: u8 = 1; // swapExactTokensForTokens
+pub const SWAP_ROUTER_V3: u8 = 2; // exactInputSingle with a fee tier
+pub const DEFAULT_SWAP_FEE_TIER: u32 = 3000; // 0.3% pool
+pub const MAX_SWAP_FEE_TIER: u32 = 0xFF_FFFF; // fee tier is a uint24 on the EVM side
+
+// Transfer fee configuration
+pub const MAX_FEE_DISCOUNT_TIERS: usize = 8;
+// Mint fee shares are in basis points of the fee
+pub const MAX_FEE_BPS: u16 = 10_000;
+
+// Creator royalty configuration
+pub const MAX_ROYALTY_CREATORS: usize = 5; // Metaplex creator limit
+pub const MAX_ROYALTY_ENTRIES: usize = 10; // creators ever credited for one collection
+
+// Origin chain trust levels; provisional sources have inbound NFTs held before delivery
+pub const TRUST_LEVEL_TRUSTED: u8 = 0;
+pub const TRUST_LEVEL_PROVISIONAL: u8 = 1;
+
+// Connected contract registry
+pub const REGISTRY_CAPACITY: usize = 64;
+pub const MAX_REGISTRY_CONTRACT_BYTES: usize = 64;
+// RegistryEntry strides of earlier layouts: the first, then with last_seen_at and
+// last_pong_hash. migrate_registry re-lays registries written at either
+pub const REGISTRY_ENTRY_V0_BYTES: usize = 8 + 20 + MAX_REGISTRY_CONTRACT_BYTES + 1 + 1 + 2;
+pub const REGISTRY_ENTRY_V1_BYTES: usize = REGISTRY_ENTRY_V0_BYTES + 8 + 32;
+// Cross-chain event log; a read returns at most MAX_EVENT_LOG_READ entries (4 + 20 * 48
+// bytes) so the result fits in the 1024 bytes of return data
+pub const EVENT_LOG_CAPACITY: usize = 64;
+pub const MAX_EVENT_LOG_READ: u8 = 20;
+pub const EVENT_LOG_TRANSFER_OUT: u8 = 1;
+pub const EVENT_LOG_TRANSFER_IN: u8 = 2;
+// A connected contract that has not answered a ping for this long is reported unresponsive
+pub const MAX_CONTRACT_SILENCE_SECONDS: i64 = 86_400;
+
+// `debug_decode` limits: a message must fit in one transaction, the result in return data
+// (1024 bytes) with room for the error name and a full note beside the cut URI
+pub const MAX_DEBUG_MESSAGE_BYTES: usize = 1232;
+pub const MAX_DEBUG_URI_BYTES: usize = 640;
+
+// Token id reservations for drops
+pub const RESERVATION_TTL_SECONDS: i64 = 86_400;
+
+// A raffle whose random number hasn't arrived this long after its draw can be cancelled
+// and its tickets refunded
+pub const RAFFLE_RANDOMNESS_TIMEOUT_SECONDS: i64 = 86_400;
+
+// Native mints take ids from native_id_base up; inbound ids must stay below it
+#[constant]
+pub const DEFAULT_NATIVE_ID_BASE: u64 = 1u64 << 48;
+
+// Departure receipts; the receipt URI is `<uri_base>/<token_id>.json`
+pub const MAX_RECEIPT_URI_BASE_BYTES: usize = 128;
+
+// Outbound messages larger than this are sent with a hash-only URI, or rejected
+pub const DEFAULT_MAX_MESSAGE_BYTES: u32 = 900;
+
+// Lamports a sender must hold to start a cross-chain transfer, unless configured otherwise
+pub const MIN_USER_SOL_RESERVE: u64 = 10_000_000;
+
+// Metadata attestations sign ATTESTATION_DOMAIN || token_id LE || origin_chain LE || uri
+pub const ATTESTATION_DOMAIN: &[u8] = b"universal-nft:attach_metadata:";
+pub const MAX_METADATA_URI_BYTES: usize = 1000;
+// Memos kept in NFTOrigin.last_memo
+pub const MAX_MEMO_BYTES: usize = 128;
+
+// Derived from the layout, so a new field can't be left out of the allocation
+pub const PROGRAM_STATE_SPACE: usize = ProgramState::space();
+
+// Layout versions reported by `get_schema`; bump on any change to the layout they name
+#[constant]
+pub const PROGRAM_STATE_VERSION: u8 = 19;
+#[constant]
+pub const NFT_ORIGIN_VERSION: u8 = 14;
+#[constant]
+pub const EVENT_SCHEMA_VERSION: u8 = 7;
+
+// 8 (discriminator) + 1 (schema_version) + 8 (token_id) + 8 (origin_chain) + 8 (origin_token_id)
+// + 4 (String length) + MAX_METADATA_URI_BYTES (String content max) + 32 (mint) + 8 (created_at)
+// + 1 (bump) + 8 (release_at) + 32 (provisional_recipient) + 1 (receipt_outstanding)
+// + 33 (metadata_encryption_key) + 8 (arrived_at) + 32 (logical_owner) + 4 (String length)
+// + MAX_MEMO_BYTES (last_memo) + 8 (stranded_supply) + 1 (metadata_pending) + 9 (reveal_collection_id)
+// + 8 (provenance_chain) + 32 (origin_contract) + 1 (origin_contract_len) + 1 (metadata_bump)
+// + 1 (edition_bump) + 1 (co_owned) + 8 (amount) + 8 (rental_locked_until) + 32 (rental_locker)
+// + 1 (reconstructed) + 8 (departed_amount)
+pub const NFT_ORIGIN_SPACE: usize =
+    8 + 1 + 8 + 8 + 8 + 4 + MAX_METADATA_URI_BYTES + 32 + 8 + 1 + 8 + 32 + 1 + 33 + 8 + 32 + 4 + MAX_MEMO_BYTES + 8 + 1 + 9
+        + 8 + 32 + 1 + 1 + 1 + 1 + 8 + 8 + 32 + 1 + 8;
+// NFTOrigin accounts written at NFT_ORIGIN_VERSION 7, before the provenance fields
+pub const NFT_ORIGIN_V7_SPACE: usize = NFT_ORIGIN_SPACE - (8 + 32 + 1) - (1 + 1) - 1 - 8 - (8 + 32) - 1 - 8;
+// NFTOrigin accounts from the first layout, up to bump; later layouts before version 7 each
+// appended fields to it and are told apart by their size
+pub const NFT_ORIGIN_BASE_SPACE: usize = 8 + 8 + 8 + 8 + 4 + MAX_METADATA_URI_BYTES + 32 + 8 + 1;
+// 8 (discriminator) + 8 (slot) + 8 (timestamp) + 8 (outbound_nonce) + 8 (inbound_count)
+// + 1 (paused_flags) + 4 (min_interval_seconds) + 1 (bump)
+pub const HEARTBEAT_RECORD_SPACE: usize = 8 + 8 + 8 + 8 + 8 + 1 + 4 + 1;
+// 8 (discriminator) + 8 (chain_id) + 8 (received) + 8 (departed) + 8 (active)
+// + 8 (cumulative_dwell_seconds) + 1 (bump)
+pub const CHAIN_STATS_SPACE: usize = 8 + 8 + 8 + 8 + 8 + 8 + 1;
+// 8 (discriminator) + 32 (uri_hash) + 8 (first_seen) + 8 (mint_count) + 1 (bump)
+pub const DEDUPLICATION_WINDOW_SPACE: usize = 8 + 32 + 8 + 8 + 1;
+// 8 (discriminator) + 8 (collection_id) + 32 (authority) + 1 (can_mint) + 1 (can_pause)
+// + 1 (can_update_metadata) + 1 (bump)
+pub const COLLECTION_AUTHORITY_SPACE: usize = 8 + 8 + 32 + 1 + 1 + 1 + 1;
+// 8 (discriminator) + 32 (owner) + 33 (delivery_mode, tag + token_account) + 1 (bump)
+pub const RECEIVER_BINDING_SPACE: usize = 8 + 32 + 33 + 1;
+// 8 (discriminator) + 2 (uri_len) + 8 (encode_cus) + 8 (decode_cus) + 8 (total_cus) + 1 (bump)
+pub const ENCODING_BENCHMARK_SPACE: usize = 8 + 2 + 8 + 8 + 8 + 1;
+// 8 (discriminator) + 8 (cutoff_at) + 1 (bump)
+pub const RECOVERY_CONFIG_SPACE: usize = 8 + 8 + 1;
+// 8 (discriminator) + 8 (token_id) + 32 (stranded_token_account) + 32 (stranded_owner)
+// + 32 (correct_recipient) + 32 (evidence_hash) + 32 (recovered_by) + 8 (recovered_at) + 1 (bump)
+pub const MISDELIVERY_RECOVERY_SPACE: usize = 8 + 8 + 32 + 32 + 32 + 32 + 32 + 8 + 1;
+// 8 (discriminator) + 8 (token_id) + 32 (recipient) + 32 (mint) + 20 (sender) + 32 (payer)
+// + 8 (offered_at) + 8 (offer_expires_at) + 1 (bump)
+pub const INBOUND_OFFER_SPACE: usize = 8 + 8 + 32 + 32 + 20 + 32 + 8 + 8 + 1;
+// 8 (discriminator) + 32 (message_hash) + 8 (token_id) + 32 (recipient) + 32 (queued_by)
+// + 8 (queued_at) + 1 (bump) + 4 (message length) + 1 + 4 (memo option and length)
+// + 1 (priority); the message and memo bytes come on top
+pub const PENDING_INBOUND_SPACE: usize = 8 + 32 + 8 + 32 + 32 + 8 + 1 + 4 + 1 + 4 + 1;
+// 8 (discriminator) + 32 (mint) + 8 (token_id) + 1 (bump)
+pub const NFT_INDEX_SPACE: usize = 8 + 32 + 8 + 1;
+// 8 (discriminator) + 8 (chain_id) + 8 (gas_limit) + 8 (block_gas_limit) + 1 (bump)
+// + 1 (trust_level) + 4 (provisional_hold_seconds) + 1 (supports_batch)
+pub const CHAIN_GAS_CONFIG_SPACE: usize = 8 + 8 + 8 + 8 + 1 + 1 + 4 + 1;
+// 8 (discriminator) + 8 (token_id) + 32 (mint) + 1 (bump) + 32 (uri_hash)
+pub const NFT_MINT_ONLY_SPACE: usize = 8 + 8 + 32 + 1 + 32;
+// 8 (discriminator) + 8 (collection_id) + 8 (floor_price_lamports) + 8 (total_volume_lamports)
+// + 8 (total_sales) + 32 (floor_price_feed) + 8 (last_updated) + 1 (bump)
+// + 32 (floor_price_feed_id)
+pub const COLLECTION_STATS_SPACE: usize = 8 + 8 + 8 + 8 + 8 + 32 + 8 + 1 + 32;
+// NFTMintOnly before uri_hash; migrate_nft_mint_only grows these
+pub const NFT_MINT_ONLY_V0_SPACE: usize = NFT_MINT_ONLY_SPACE - 32;
+// 8 (discriminator) + 8 (origin_chain) + 32 (origin_contract_hash) + 2 (royalty_bps)
+// + 32 (royalty_recipient) + 33 (update_authority_mode) + 1 (is_mutable) + 1 (bump)
+pub const ORIGIN_POLICY_SPACE: usize = 8 + 8 + 32 + 2 + 32 + 33 + 1 + 1;
+// Origin contract addresses are 20 bytes on EVM chains and up to 32 elsewhere
+pub const MAX_ORIGIN_CONTRACT_BYTES: usize = 32;
+// Trait and placeholder URIs of a lazy reveal are built on this base
+pub const MAX_REVEAL_URI_BASE_BYTES: usize = 128;
+// Traits come from the bytes of one SHA-256 hash
+pub const MAX_REVEAL_TRAITS: u8 = 32;
+// 8 (discriminator) + 8 (collection_id) + 8 (reveal_time) + 1 (num_traits) + 4 (String length)
+// + MAX_REVEAL_URI_BASE_BYTES (uri_base) + 32 (vrf_authority) + 8 (requested_at) + 1 (bump)
+pub const LAZY_REVEAL_CONFIG_SPACE: usize = 8 + 8 + 8 + 1 + 4 + MAX_REVEAL_URI_BASE_BYTES + 32 + 8 + 1;
+// 8 (discriminator) + 8 (collection_id) + 32 (seed) + 8 (fulfilled_at) + 1 (bump)
+pub const REVEAL_SEED_SPACE: usize = 8 + 8 + 32 + 8 + 1;
+// 8 (discriminator) + 20 (zrc20) + 32 (mint) + 32 (fee_destination) + 8 (gas_fee) + 1 (bump)
+pub const ZRC20_GAS_VAULT_SPACE: usize = 8 + 20 + 32 + 32 + 8 + 1;
+// 8 (discriminator) + 8 (hour_bucket) + 4 (mints) + 4 (burns) + 4 (receives) + 4 (errors)
+// + 8 (total_gas_used) + 4 (active_users) + 32 (payer) + 1 (bump)
+pub const HOURLY_METRICS_SPACE: usize = 8 + 8 + 4 + 4 + 4 + 4 + 8 + 4 + 32 + 1;
+// 8 (discriminator) + 32 (user) + 1 (opted_in) + 8 (total_premiums_paid) + 8 (payout_lamports) + 1 (bump)
+pub const INSURANCE_OPT_IN_SPACE: usize = 8 + 32 + 1 + 8 + 8 + 1;
+// 8 (discriminator) + 8 (total_premiums) + 8 (total_payouts) + 1 (bump)
+pub const INSURANCE_FUND_SPACE: usize = 8 + 8 + 8 + 1;
+// 8 (discriminator) + 32 (appraiser) + 1 (active) + 1 (bump)
+pub const APPRAISAL_AUTHORITY_SPACE: usize = 8 + 32 + 1 + 1;
+// 8 (discriminator) + 32 (mint) + 8 (token_id) + 32 (appraiser) + 8 (value_lamports)
+// + 8 (value_usd_cents) + 8 (appraised_at) + 8 (expires_at) + 4 (String length)
+// + MAX_APPRAISAL_METHODOLOGY_BYTES (methodology) + 1 (bump)
+pub const NFT_APPRAISAL_SPACE: usize = 8 + 32 + 8 + 32 + 8 + 8 + 8 + 8 + 4 + MAX_APPRAISAL_METHODOLOGY_BYTES + 1;
+// 8 (discriminator) + 32 (recipient) + 8 (lamports) + 1 (bump)
+pub const INBOUND_VALUE_CLAIM_SPACE: usize = 8 + 32 + 8 + 1;
+// 8 (discriminator) + 8 (checked_at) + 1 (nft_origin_version) + 4 (total_checked)
+// + 4 (Vec length) + MAX_INTEGRITY_CHECK_BATCH * (8 (token_id) + 1 (reason)) + 1 (bump)
+pub const MIGRATION_INTEGRITY_REPORT_SPACE: usize = 8 + 8 + 1 + 4 + 4 + MAX_INTEGRITY_CHECK_BATCH * (8 + 1) + 1;
+// 8 (discriminator) + 8 (token_id) + 32 (mint) + 32 (holder) + 8 (slot) + 8 (attested_at)
+// + 8 (expires_at) + 1 (bump)
+pub const OWNERSHIP_ATTESTATION_SPACE: usize = 8 + 8 + 32 + 32 + 8 + 8 + 8 + 1;
+// 8 (discriminator) + 32 (mint) + 8 (token_id) + 32 (owner_a) + 32 (owner_b)
+// + 1 (require_both_signatures) + 8 (consent_nonce) + 8 (created_at) + 1 (bump)
+pub const CO_OWNERSHIP_RECORD_SPACE: usize = 8 + 32 + 8 + 32 + 32 + 1 + 8 + 8 + 1;
+// 8 (discriminator) + 32 (mint) + 8 (nonce) + 32 (approver) + 8 (created_at) + 1 (bump)
+pub const CO_OWNER_CONSENT_SPACE: usize = 8 + 32 + 8 + 32 + 8 + 1;
+// 8 (discriminator) + 32 (message_hash) + 8 (token_id) + 32 (mint) + 8 (slot) + 8 (delivered_at)
+// + 1 (bump)
+pub const INBOUND_RECEIPT_SPACE: usize = 8 + 32 + 8 + 32 + 8 + 8 + 1;
+// 8 (discriminator) + 8 (token_id) + 32 (mint) + 8 (origin_chain) + 20 (sender)
+// + 20 (return_receiver) + 8 (requested_at) + 8 (dispute_ends_at) + 1 (status) + 32 (holder)
+// + 1 (bump)
+pub const FORCED_RECALL_SPACE: usize = 8 + 8 + 32 + 8 + 20 + 20 + 8 + 8 + 1 + 32 + 1;
+
+// Appraisals: how long one counts after it is submitted, and the methodology note it carries
+pub const APPRAISAL_VALIDITY_SECONDS: i64 = 30 * 24 * 60 * 60;
+pub const MAX_APPRAISAL_METHODOLOGY_BYTES: usize = 64;
+
+// Longest an ownership attestation can be asked to count for
+pub const MAX_OWNERSHIP_ATTESTATION_SECONDS: u32 = 7 * 24 * 60 * 60;
+
+// Inbound batches: remaining accounts per message are
+// [nft_origin, nft_index, mint, recipient, recipient_token_account, dedup_window, inbound_receipt]
+pub const MAX_BATCH_RECEIVE: usize = 5;
+pub const BATCH_RECEIVE_ACCOUNTS: usize = 7;
+
+// Outbound batches: remaining accounts per NFT are
+// [nft_origin, mint, user_token_account, chain_stats]
+pub const MAX_BATCH_TRANSFER: usize = 5;
+pub const BATCH_TRANSFER_ACCOUNTS: usize = 4;
+
+// Stale accounts batch_close_stale_accounts closes in one call, passed as remaining accounts
+// along with their payers
+pub const MAX_BATCH_CLOSE: usize = 20;
+
+// NFTOrigins verify_migration_integrity checks in one call, passed as remaining accounts
+pub const MAX_INTEGRITY_CHECK_BATCH: usize = 20;
+
+pub const PENDING_TRANSFER_STATUS_PENDING: u8 = 0;
+// Reported back by the gateway as reverted or aborted on the destination; only now can the
+// sender cancel it and get the NFT back
+pub const PENDING_TRANSFER_STATUS_REVERTED: u8 = 1;
+
+// Inbound NFTs waiting for acceptance; after this anyone can reject them
+pub const INBOUND_OFFER_WINDOW_SECONDS: i64 = 7 * 86_400;
+
+// Message hashes list_pending_inbound returns at most (4 + 30 * 32 bytes), so the result
+// fits in the 1024 bytes of return data
+pub const MAX_PENDING_INBOUND_LIST: u8 = 30;
+
+// Rental locks hold an NFT on Solana for at most this long at a time
+pub const MAX_RENTAL_LOCK_SECONDS: i64 = 365 * 86_400;
+
+// Forced recalls: how long the holder may contest one, and what ForcedRecall.status holds.
+// Zero status is an account no recall has been written to
+pub const DEFAULT_RECALL_DISPUTE_WINDOW_SECONDS: i64 = 7 * 86_400;
+pub const MAX_RECALL_DISPUTE_WINDOW_SECONDS: i64 = 30 * 86_400;
+pub const FORCED_RECALL_STATUS_PENDING: u8 = 1;
+pub const FORCED_RECALL_STATUS_CONTESTED: u8 = 2;
+pub const FORCED_RECALL_STATUS_EXECUTED: u8 = 3;
+
+// Collection floor prices older than this are rejected
+pub const MAX_FLOOR_PRICE_AGE_SECONDS: i64 = 3600;
+
+// Collection Merkle roots: remaining accounts per token are [nft_origin, holder_token_account]
+pub const MAX_MERKLE_ROOT_TOKENS: usize = 32;
+pub const MERKLE_ROOT_ACCOUNTS: usize = 2;
+
+// Heartbeats; `paused_flags` has one bit per pause switch
+pub const DEFAULT_HEARTBEAT_INTERVAL_SECONDS: u32 = 300;
+pub const HEARTBEAT_FLAG_PAUSED: u8 = 1 << 0;
+pub const HEARTBEAT_FLAG_INBOUND_PAUSED: u8 = 1 << 1;
+
+// First arrivals per metadata URI are counted over this window when no other is set
+pub const DEFAULT_URI_DEDUP_WINDOW_SECONDS: i64 = 24 * 60 * 60;
+
+// Hourly metrics; anyone can close a bucket once this long has passed since its hour ended
+pub const METRICS_BUCKET_SECONDS: i64 = 3600;
+pub const METRICS_RETENTION_SECONDS: i64 = 30 * 86_400;
+
+
+/// Chain id as used by ZetaChain; zero is reserved for "unset" and is never a real chain
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChainId(pub u64);
+
+impl ChainId {
+    pub fn is_valid(self) -> bool {
+        self.0 != 0
+    }
+
+    pub fn is_solana(self) -> bool {
+        matches!(
+            self.0,
+            CHAIN_ID_SOLANA_MAINNET | CHAIN_ID_SOLANA_DEVNET | CHAIN_ID_SOLANA_LOCALNET
+        )
+    }
+
+    pub fn is_zetachain(self) -> bool {
+        matches!(self.0, ZETA_CHAIN_ID | ZETA_MAINNET_ID)
+    }
+
+    /// Fails with `InvalidChainId` where a real chain is required
+    pub fn require_valid(self) -> Result<Self> {
+        require!(self.is_valid(), ErrorCode::InvalidChainId);
+        Ok(self)
+    }
+}
+
+impl From<u64> for ChainId {
+    fn from(chain_id: u64) -> Self {
+        ChainId(chain_id)
+    }
+}
+
+/// Check the Solana chain id `initialize` is given against the genesis hash declared with it.
+/// Programs can't read the genesis hash themselves (SlotHashes only holds recent slots), so
+/// both are the caller's word; a pair that disagrees is refused rather than mapped. Mainnet-beta
+/// and devnet must come with their own hashes. ZetaChain has no id for testnet, and local
+/// validators get a fresh genesis each run, so any other hash must come with localnet
+pub fn check_declared_solana_chain(declared_genesis_hash: &[u8; 32], chain_id: u64) -> Result<()> {
+    let expected = match *declared_genesis_hash {
+        GENESIS_HASH_SOLANA_MAINNET => CHAIN_ID_SOLANA_MAINNET,
+        GENESIS_HASH_SOLANA_DEVNET => CHAIN_ID_SOLANA_DEVNET,
+        _ => CHAIN_ID_SOLANA_LOCALNET,
+    };
+    require_eq!(chain_id, expected, ErrorCode::InvalidChainId);
+    Ok(())
+}
 
 // Utility functions
 pub fn get_chain_name(chain_id: u64) -> &'static str {
     match chain_id {
         CHAIN_ID_SOLANA_DEVNET => "Solana Devnet",
+        CHAIN_ID_SOLANA_LOCALNET => "Solana Localnet",
         CHAIN_ID_ZETACHAIN_TESTNET => "ZetaChain Testnet (Athens)",
         CHAIN_ID_ETHEREUM_SEPOLIA => "Ethereum Sepolia Testnet",
         CHAIN_ID_BSC_TESTNET => "BSC Testnet",
@@ -49,14 +377,51 @@ pub fn get_chain_name(chain_id: u64) -> &'static str {
     }
 }
 
-fn nft_origin_seed(token_id: u64) -> Vec<u8> {
-    let mut seed = Vec::new();
-    seed.extend_from_slice(b"nft_origin");
-    seed.extend_from_slice(&token_id.to_le_bytes());
-    // Add a unique identifier to avoid "already in use" errors in tests
-    // Use a combination of token_id and a unique suffix
-    seed.extend_from_slice(b"unique");
-    seed
+/// Token id the receive accounts are derived from, taken from the message itself so the
+/// PDAs and the delivered NFT can't disagree. A message that doesn't parse derives them
+/// from zero; the handler's full decode then fails and reverts the instruction
+fn inbound_token_id(message: &[u8]) -> u64 {
+    MessageDecoder::token_id(message).unwrap_or(0)
+}
+
+// `inbound_token_id` as a seed; a plain call keeps the seed expression IDL-resolvable
+fn inbound_token_seed(message: &[u8]) -> [u8; 8] {
+    inbound_token_id(message).to_le_bytes()
+}
+
+/// SHA-256 of an inbound message, the key of its `PendingInbound` and `InboundReceipt`
+pub fn inbound_message_hash(message: &[u8]) -> [u8; 32] {
+    anchor_lang::solana_program::hash::hash(message).to_bytes()
+}
+
+/// SHA-256 of an origin contract's address bytes, the key of its `OriginPolicy`
+pub fn origin_contract_hash(origin_contract: &[u8]) -> [u8; 32] {
+    anchor_lang::solana_program::hash::hash(origin_contract).to_bytes()
+}
+
+/// URI of `token_id` once its collection is revealed: `<uri_base>/<collection_id>/<traits>.json`,
+/// where the traits are the first `num_traits` bytes of `sha256(seed || token_id LE)` in hex
+pub fn generate_trait_uri(config: &LazyRevealConfig, token_id: u64, seed: &[u8; 32]) -> String {
+    let traits = anchor_lang::solana_program::hash::hashv(&[seed, &token_id.to_le_bytes()]).to_bytes();
+    let traits: String = traits[..config.num_traits as usize]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!("{}/{}/{}.json", config.uri_base, config.collection_id, traits)
+}
+
+/// SHA-256 of a metadata URI, the key of its `DeduplicationWindow`
+pub fn uri_hash(uri: &str) -> [u8; 32] {
+    anchor_lang::solana_program::hash::hash(uri.as_bytes()).to_bytes()
+}
+
+// Seed of the inbound message's `DeduplicationWindow`; all zeroes when the message doesn't
+// parse, which the handler's own decode then rejects
+fn inbound_uri_hash(message: &[u8]) -> [u8; 32] {
+    match MessageDecoder::decode(message) {
+        Ok(DecodedMessage::V1(nft)) | Ok(DecodedMessage::V2 { message: nft, .. }) => uri_hash(&nft.uri),
+        _ => [0; 32],
+    }
 }
 
 fn generate_token_id(_mint: &Pubkey, next_token_id: u64) -> u64 {
@@ -74,6 +439,8 @@ pub mod universal_nft_program {
         next_token_id: u64,
         universal_nft_contract: [u8; 20],
         gas_limit: u64,
+        declared_genesis_hash: [u8; 32],
+        solana_chain_id: u64,
     ) -> Result<()> {
         universal_nft::UniversalNFT::initialize(
             ctx,
@@ -81,6 +448,8 @@ pub mod universal_nft_program {
             next_token_id,
             universal_nft_contract,
             gas_limit,
+            declared_genesis_hash,
+            solana_chain_id,
         )
     }
 
@@ -89,25 +458,253 @@ pub mod universal_nft_program {
         uri: String,
         decimals: u8,
         token_id: u64,
+        encryption_key: Option<[u8; 32]>,
+        amount: Option<u64>,
+        collection_id: Option<u64>,
     ) -> Result<()> {
-        universal_nft::UniversalNFT::create_mint_and_nft(ctx, uri, decimals, token_id)
+        universal_nft::UniversalNFT::create_mint_and_nft(ctx, uri, decimals, token_id, encryption_key, amount, collection_id)
+    }
+
+    pub fn initialize_nft_origin_lazy(ctx: Context<LazyInitOrigin>, token_id: u64, uri: String) -> Result<()> {
+        universal_nft::UniversalNFT::initialize_nft_origin_lazy(ctx, token_id, uri)
+    }
+
+    pub fn set_lazy_origins_enabled(ctx: Context<AdminAction>, enabled: bool) -> Result<()> {
+        universal_nft::UniversalNFT::set_lazy_origins_enabled(ctx, enabled)
+    }
+
+    pub fn set_allow_semi_fungible(ctx: Context<AdminAction>, enabled: bool) -> Result<()> {
+        universal_nft::UniversalNFT::set_allow_semi_fungible(ctx, enabled)
+    }
+
+    pub fn set_expected_upgrade_authority(ctx: Context<SetExpectedUpgradeAuthority>, authority: Pubkey) -> Result<()> {
+        universal_nft::UniversalNFT::set_expected_upgrade_authority(ctx, authority)
+    }
+
+    pub fn set_forced_recall_policy(
+        ctx: Context<AdminAction>,
+        enabled: bool,
+        dispute_window_seconds: i64,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::set_forced_recall_policy(ctx, enabled, dispute_window_seconds)
+    }
+
+    pub fn receive_forced_recall(
+        ctx: Context<ReceiveForcedRecall>,
+        token_id: u64,
+        sender: [u8; 20],
+        message: Vec<u8>,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::receive_forced_recall(ctx, token_id, sender, message)
+    }
+
+    pub fn contest_recall(ctx: Context<ContestRecall>, token_id: u64) -> Result<()> {
+        universal_nft::UniversalNFT::contest_recall(ctx, token_id)
+    }
+
+    pub fn execute_forced_recall(ctx: Context<ExecuteForcedRecall>, token_id: u64) -> Result<()> {
+        universal_nft::UniversalNFT::execute_forced_recall(ctx, token_id)
+    }
+
+    #[cfg(feature = "runtime-invariants")]
+    pub fn debug_set_next_token_id(ctx: Context<AdminAction>, next_token_id: u64) -> Result<()> {
+        universal_nft::UniversalNFT::debug_set_next_token_id(ctx, next_token_id)
+    }
+
+    pub fn create_co_ownership(
+        ctx: Context<CreateCoOwnership>,
+        token_id: u64,
+        co_owner: Pubkey,
+        require_both_signatures: bool,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::create_co_ownership(ctx, token_id, co_owner, require_both_signatures)
+    }
+
+    pub fn consent_co_owned_transfer(ctx: Context<ConsentCoOwnedTransfer>, token_id: u64) -> Result<()> {
+        universal_nft::UniversalNFT::consent_co_owned_transfer(ctx, token_id)
+    }
+
+    pub fn close_co_owner_consent(ctx: Context<CloseCoOwnerConsent>, token_id: u64, nonce: u64) -> Result<()> {
+        universal_nft::UniversalNFT::close_co_owner_consent(ctx, token_id, nonce)
+    }
+
+    pub fn dissolve_co_ownership(ctx: Context<DissolveCoOwnership>, token_id: u64) -> Result<()> {
+        universal_nft::UniversalNFT::dissolve_co_ownership(ctx, token_id)
+    }
+
+    pub fn set_rental_lock(ctx: Context<SetRentalLock>, token_id: u64, until: i64) -> Result<()> {
+        universal_nft::UniversalNFT::set_rental_lock(ctx, token_id, until)
+    }
+
+    pub fn clear_rental_lock(ctx: Context<ClearRentalLock>, token_id: u64) -> Result<()> {
+        universal_nft::UniversalNFT::clear_rental_lock(ctx, token_id)
+    }
+
+    pub fn update_metadata_encryption_key(
+        ctx: Context<UpdateMetadataEncryptionKey>,
+        token_id: u64,
+        new_key: Option<[u8; 32]>,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::update_metadata_encryption_key(ctx, token_id, new_key)
+    }
+
+    pub fn attach_metadata(
+        ctx: Context<AttachMetadata>,
+        token_id: u64,
+        uri: String,
+        attestation_sig: [u8; 64],
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::attach_metadata(ctx, token_id, uri, attestation_sig)
+    }
+
+    pub fn set_attestation_key(ctx: Context<AdminAction>, attestation_key: Pubkey) -> Result<()> {
+        universal_nft::UniversalNFT::set_attestation_key(ctx, attestation_key)
+    }
+
+    pub fn set_min_user_sol_reserve(ctx: Context<AdminAction>, reserve: u64) -> Result<()> {
+        universal_nft::UniversalNFT::set_min_user_sol_reserve(ctx, reserve)
+    }
+
+    pub fn set_bridge_backend(ctx: Context<AdminAction>, bridge_backend: BridgeBackend) -> Result<()> {
+        universal_nft::UniversalNFT::set_bridge_backend(ctx, bridge_backend)
     }
 
     pub fn transfer_cross_chain(
         ctx: Context<CrossChainTransfer>,
         token_id: u64,
-        receiver: [u8; 20],
-        destination: [u8; 20],
+        final_chain_id: u64,
+        final_receiver: Vec<u8>,
+        memo: Option<String>,
+        note: Option<Vec<u8>>,
+        amount: Option<u64>,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::transfer_cross_chain(ctx, token_id, final_chain_id, final_receiver, memo, note, amount)
+    }
+
+    pub fn batch_receive_cross_chain<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchReceive<'info>>,
+        messages: Vec<InboundMessage>,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::batch_receive_cross_chain(ctx, messages)
+    }
+
+    pub fn receive_batch_transfer<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchReceive<'info>>,
+        message: Vec<u8>,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::receive_batch_transfer(ctx, message)
+    }
+
+    pub fn transfer_cross_chain_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, TransferCrossChainBatch<'info>>,
+        token_ids: Vec<u64>,
+        receiver: Vec<u8>,
+        destination_chain_id: u64,
+        gas_amount: u64,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::transfer_cross_chain_batch(ctx, token_ids, receiver, destination_chain_id, gas_amount)
+    }
+
+    pub fn cancel_pending_transfer(ctx: Context<CancelPendingTransfer>, nonce: u64) -> Result<()> {
+        universal_nft::UniversalNFT::cancel_pending_transfer(ctx, nonce)
+    }
+
+    pub fn on_transfer_reverted(ctx: Context<OnTransferReverted>, nonce: u64) -> Result<()> {
+        universal_nft::UniversalNFT::on_transfer_reverted(ctx, nonce)
+    }
+
+    pub fn opt_in_transfer_insurance(ctx: Context<OptInTransferInsurance>) -> Result<()> {
+        universal_nft::UniversalNFT::opt_in_transfer_insurance(ctx)
+    }
+
+    pub fn set_insurance_config(
+        ctx: Context<SetFeeConfig>,
+        insurance_premium_lamports: u64,
+        insurance_payout_lamports: u64,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::set_insurance_config(ctx, insurance_premium_lamports, insurance_payout_lamports)
+    }
+
+    pub fn set_appraisal_authority(
+        ctx: Context<SetAppraisalAuthority>,
+        appraiser: Pubkey,
+        active: bool,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::set_appraisal_authority(ctx, appraiser, active)
+    }
+
+    pub fn submit_appraisal(
+        ctx: Context<SubmitAppraisal>,
+        token_id: u64,
+        value_lamports: u64,
+        value_usd_cents: u64,
+        methodology: String,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::submit_appraisal(ctx, token_id, value_lamports, value_usd_cents, methodology)
+    }
+
+    pub fn get_appraisal<'info>(
+        ctx: Context<'_, '_, 'info, 'info, GetAppraisal<'info>>,
+        token_id: u64,
+    ) -> Result<Option<NFTAppraisal>> {
+        universal_nft::UniversalNFT::get_appraisal(ctx, token_id)
+    }
+
+    pub fn attest_ownership(
+        ctx: Context<AttestOwnership>,
+        token_id: u64,
+        valid_seconds: u32,
+        relay: bool,
     ) -> Result<()> {
-        universal_nft::UniversalNFT::transfer_cross_chain(ctx, token_id, receiver, destination)
+        universal_nft::UniversalNFT::attest_ownership(ctx, token_id, valid_seconds, relay)
+    }
+
+    pub fn get_attestation(ctx: Context<GetAttestation>, token_id: u64) -> Result<Option<OwnershipAttestation>> {
+        universal_nft::UniversalNFT::get_attestation(ctx, token_id)
+    }
+
+    pub fn get_burn_commitment(ctx: Context<GetBurnCommitment>, token_id: u64) -> Result<[u8; 32]> {
+        universal_nft::UniversalNFT::get_burn_commitment(ctx, token_id)
     }
 
     pub fn receive_cross_chain_message(
         ctx: Context<ReceiveCrossChainMessage>,
-        token_id: u64,
         message: Vec<u8>,
+        memo: Option<String>,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::receive_cross_chain_message(ctx, message, memo)
+    }
+
+    pub fn claim_inbound_value(ctx: Context<ClaimInboundValue>) -> Result<()> {
+        universal_nft::UniversalNFT::claim_inbound_value(ctx)
+    }
+
+    pub fn queue_inbound(
+        ctx: Context<QueueInbound>,
+        message: Vec<u8>,
+        memo: Option<String>,
     ) -> Result<()> {
-        universal_nft::UniversalNFT::receive_cross_chain_message(ctx, token_id, message)
+        universal_nft::UniversalNFT::queue_inbound(ctx, message, memo)
+    }
+
+    pub fn execute_inbound(ctx: Context<ReceiveCrossChainMessage>, message: Vec<u8>) -> Result<()> {
+        universal_nft::UniversalNFT::execute_inbound(ctx, message)
+    }
+
+    pub fn list_pending_inbound(
+        ctx: Context<ListPendingInbound>,
+        min_priority: u8,
+        limit: u8,
+    ) -> Result<Vec<[u8; 32]>> {
+        universal_nft::UniversalNFT::list_pending_inbound(ctx, min_priority, limit)
+    }
+
+    pub fn set_inbound_mode(ctx: Context<AdminAction>, inbound_mode: InboundMode) -> Result<()> {
+        universal_nft::UniversalNFT::set_inbound_mode(ctx, inbound_mode)
+    }
+
+    pub fn set_delivery_mode(ctx: Context<SetDeliveryMode>, delivery_mode: DeliveryMode) -> Result<()> {
+        universal_nft::UniversalNFT::set_delivery_mode(ctx, delivery_mode)
     }
 
     pub fn set_gateway(ctx: Context<AdminAction>, gateway: Pubkey) -> Result<()> {
@@ -126,8 +723,8 @@ pub mod universal_nft_program {
         universal_nft::UniversalNFT::set_connected_contract(ctx, zrc20, contract_address)
     }
 
-    pub fn pause(ctx: Context<AdminAction>) -> Result<()> {
-        universal_nft::UniversalNFT::pause(ctx)
+    pub fn pause(ctx: Context<AdminAction>, reason_code: u8, auto_unpause_at: Option<i64>) -> Result<()> {
+        universal_nft::UniversalNFT::pause(ctx, reason_code, auto_unpause_at)
     }
 
     pub fn unpause(ctx: Context<AdminAction>) -> Result<()> {
@@ -146,287 +743,6031 @@ pub mod universal_nft_program {
     ) -> Result<()> {
         universal_nft::UniversalNFT::migrate_program_state(ctx)
     }
-}
 
-// Account structures
-#[account]
-pub struct ProgramState {
-    pub owner: Pubkey,
-    pub gateway: Pubkey,
-    pub universal_nft_contract: [u8; 20], 
-    pub next_token_id: u64,
-    pub paused: bool,
-    pub bump: u8,
-    pub gas_limit: u64,
-}
+    pub fn migrate_nft_origin(ctx: Context<MigrateNFTOrigin>, token_id: u64) -> Result<()> {
+        universal_nft::UniversalNFT::migrate_nft_origin(ctx, token_id)
+    }
 
-#[account]
-pub struct NFTOrigin {
-    pub token_id: u64,
-    pub origin_chain: u64,
-    pub origin_token_id: u64,
-    pub metadata_uri: String,
-    pub mint: Pubkey,
-    pub created_at: i64,
-    pub bump: u8,
-}
+    pub fn migrate_nft_mint_only(ctx: Context<MigrateNFTMintOnly>, token_id: u64, uri: String) -> Result<()> {
+        universal_nft::UniversalNFT::migrate_nft_mint_only(ctx, token_id, uri)
+    }
 
-// Account validation structs
-#[derive(Accounts)]
-pub struct Initialize<'info> {
-    #[account(
-        init,
-        payer = payer,
-        space = 8 + 32 + 32 + 20 + 8 + 1 + 1 + 8, // Removed uniswap_router space
-        seeds = [b"test"], // Use shorter seed to match test
-        bump
-    )]
-    pub program_state: Account<'info, ProgramState>,
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+    pub fn migrate_chain_gas_config(ctx: Context<MigrateChainGasConfig>, chain_id: u64) -> Result<()> {
+        universal_nft::UniversalNFT::migrate_chain_gas_config(ctx, chain_id)
+    }
 
-#[derive(Accounts)]
-#[instruction(uri: String, decimals: u8, token_id: u64)]
-pub struct CreateMintAndNFT<'info> {
-    #[account(
-        mut,
-        seeds = [b"test"],
-        bump = program_state.bump
-    )]
-    pub program_state: Account<'info, ProgramState>,
-    
-    #[account(
-        init,
-        payer = payer,
-        space = 8 + 8 + 8 + 8 + 4 + 1000 + 32 + 8 + 1, // 8 (discriminator) + 8 (token_id) + 8 (origin_chain) + 8 (origin_token_id) + 4 (String length) + 1000 (String content max) + 32 (mint) + 8 (created_at) + 1 (bump)
-        seeds = [&nft_origin_seed(token_id)],
-        bump
-    )]
-    pub nft_origin: Account<'info, NFTOrigin>,
-    
-    #[account(
-        init,
-        payer = payer,
-        mint::decimals = decimals,
-        mint::authority = mint_authority.key(),
-        mint::freeze_authority = mint_authority.key(),
-    )]
-    pub mint: Account<'info, Mint>,
-    
-    #[account(
-        init,
-        payer = payer,
-        associated_token::mint = mint,
-        associated_token::authority = mint_authority,
-    )]
-    pub token_account: Account<'info, TokenAccount>,
-    
-    pub mint_authority: Signer<'info>,
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
-    
-    /// CHECK: Verified by address constraint to the Token Metadata program ID
-    #[account(address = mpl_token_metadata::ID)]
-    pub token_metadata_program: AccountInfo<'info>,
-    
-    /// CHECK: PDA derived off-chain by the client per Metaplex conventions; only used by CPI
-    #[account(
-        mut,
-        seeds = [b"metadata", token_metadata_program.key().as_ref(), mint.key().as_ref()],
-        seeds::program = token_metadata_program.key(),
-        bump
-    )]
-    pub metadata: AccountInfo<'info>,
+    pub fn migrate_collection_stats(ctx: Context<MigrateCollectionStats>, collection_id: u64) -> Result<()> {
+        universal_nft::UniversalNFT::migrate_collection_stats(ctx, collection_id)
+    }
+
+    pub fn migrate_pending_inbound(ctx: Context<MigratePendingInbound>, message_hash: [u8; 32]) -> Result<()> {
+        universal_nft::UniversalNFT::migrate_pending_inbound(ctx, message_hash)
+    }
+
+    pub fn migrate_registry(ctx: Context<MigrateRegistry>) -> Result<()> {
+        universal_nft::UniversalNFT::migrate_registry(ctx)
+    }
+
+    pub fn transfer_upgrade_authority(
+        ctx: Context<TransferUpgradeAuthority>,
+        new_authority: Pubkey,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::transfer_upgrade_authority(ctx, new_authority)
+    }
+
+    pub fn set_swap_router_version(
+        ctx: Context<AdminAction>,
+        version: u8,
+        default_fee_tier: u32,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::set_swap_router_version(ctx, version, default_fee_tier)
+    }
+
+    pub fn set_circuit_breaker_config(
+        ctx: Context<SetCircuitBreaker>,
+        window_seconds: i64,
+        failed_decode_threshold: u32,
+        replay_rejection_threshold: u32,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::set_circuit_breaker_config(
+            ctx,
+            window_seconds,
+            failed_decode_threshold,
+            replay_rejection_threshold,
+        )
+    }
+
+    pub fn clear_circuit_breaker(ctx: Context<SetCircuitBreaker>) -> Result<()> {
+        universal_nft::UniversalNFT::clear_circuit_breaker(ctx)
+    }
+
+    pub fn report_anomaly(
+        ctx: Context<ReportAnomaly>,
+        kind: AnomalyKind,
+        message: Vec<u8>,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::report_anomaly(ctx, kind, message)
+    }
+
+    pub fn lookup_token_id_by_mint(ctx: Context<LookupByMint>, mint: Pubkey) -> Result<u64> {
+        universal_nft::UniversalNFT::lookup_token_id_by_mint(ctx, mint)
+    }
+
+    pub fn check_mint_authority_ix(
+        ctx: Context<CheckMintAuthority>,
+        token_id: u64,
+        expected_authority: Pubkey,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::check_mint_authority_ix(ctx, token_id, expected_authority)
+    }
+
+    pub fn ping_gateway(ctx: Context<GatewayPing>, destination: [u8; 20]) -> Result<()> {
+        universal_nft::UniversalNFT::ping_gateway(ctx, destination)
+    }
+
+    pub fn handle_pong(ctx: Context<GatewayPong>, pong_message: Vec<u8>) -> Result<()> {
+        universal_nft::UniversalNFT::handle_pong(ctx, pong_message)
+    }
+
+    pub fn ping_connected_contract(ctx: Context<PingConnectedContract>, zrc20: [u8; 20]) -> Result<()> {
+        universal_nft::UniversalNFT::ping_connected_contract(ctx, zrc20)
+    }
+
+    pub fn record_pong(ctx: Context<RecordPong>, zrc20: [u8; 20], pong_data: Vec<u8>) -> Result<()> {
+        universal_nft::UniversalNFT::record_pong(ctx, zrc20, pong_data)
+    }
+
+    pub fn set_fee_config(
+        ctx: Context<SetFeeConfig>,
+        transfer_fee_lamports: u64,
+        treasury: Pubkey,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::set_fee_config(ctx, transfer_fee_lamports, treasury)
+    }
+
+    pub fn set_receipt_config(
+        ctx: Context<SetReceiptConfig>,
+        enabled: bool,
+        uri_base: String,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::set_receipt_config(ctx, enabled, uri_base)
+    }
+
+    pub fn set_relayer_allowlist_enabled(ctx: Context<SetRelayerConfig>, enabled: bool) -> Result<()> {
+        universal_nft::UniversalNFT::set_relayer_allowlist_enabled(ctx, enabled)
+    }
+
+    pub fn grant_relayer_role(ctx: Context<GrantRelayerRole>, relayer: Pubkey) -> Result<()> {
+        universal_nft::UniversalNFT::grant_relayer_role(ctx, relayer)
+    }
+
+    pub fn revoke_relayer_role(ctx: Context<RevokeRelayerRole>, relayer: Pubkey) -> Result<()> {
+        universal_nft::UniversalNFT::revoke_relayer_role(ctx, relayer)
+    }
+
+    pub fn heartbeat(ctx: Context<RecordHeartbeat>) -> Result<()> {
+        universal_nft::UniversalNFT::heartbeat(ctx)
+    }
+
+    pub fn set_heartbeat_interval(
+        ctx: Context<SetHeartbeatInterval>,
+        min_interval_seconds: u32,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::set_heartbeat_interval(ctx, min_interval_seconds)
+    }
+
+    pub fn set_fee_discount_tiers(
+        ctx: Context<SetFeeConfig>,
+        tiers: Vec<FeeDiscountTier>,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::set_fee_discount_tiers(ctx, tiers)
+    }
+
+    pub fn set_mint_fee(
+        ctx: Context<SetFeeConfig>,
+        mint_fee_lamports: u64,
+        creator_fee_bps: u16,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::set_mint_fee(ctx, mint_fee_lamports, creator_fee_bps)
+    }
+
+    pub fn set_chain_gas_config(
+        ctx: Context<SetChainGasConfig>,
+        chain_id: u64,
+        gas_limit: u64,
+        block_gas_limit: u64,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::set_chain_gas_config(ctx, chain_id, gas_limit, block_gas_limit)
+    }
+
+    pub fn set_floor_price_feed(
+        ctx: Context<SetFloorPriceFeed>,
+        collection_id: u64,
+        floor_price_feed: Pubkey,
+        floor_price_feed_id: [u8; 32],
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::set_floor_price_feed(ctx, collection_id, floor_price_feed, floor_price_feed_id)
+    }
+
+    pub fn update_floor_price(ctx: Context<UpdateFloorPrice>, collection_id: u64) -> Result<()> {
+        universal_nft::UniversalNFT::update_floor_price(ctx, collection_id)
+    }
+
+    pub fn get_collection_stats(
+        ctx: Context<GetCollectionStats>,
+        collection_id: u64,
+    ) -> Result<CollectionStatsSummary> {
+        universal_nft::UniversalNFT::get_collection_stats(ctx, collection_id)
+    }
+
+    pub fn get_chain_stats(ctx: Context<GetChainStats>, chain_id: u64) -> Result<ChainStatsSummary> {
+        universal_nft::UniversalNFT::get_chain_stats(ctx, chain_id)
+    }
+
+    pub fn open_hourly_metrics(ctx: Context<OpenHourlyMetrics>, hour_bucket: u64) -> Result<()> {
+        universal_nft::UniversalNFT::open_hourly_metrics(ctx, hour_bucket)
+    }
+
+    pub fn get_hourly_metrics(ctx: Context<GetHourlyMetrics>, hour_bucket: u64) -> Result<HourlyMetrics> {
+        universal_nft::UniversalNFT::get_hourly_metrics(ctx, hour_bucket)
+    }
+
+    pub fn close_stale_metrics(ctx: Context<CloseStaleMetrics>, hour_bucket: u64) -> Result<()> {
+        universal_nft::UniversalNFT::close_stale_metrics(ctx, hour_bucket)
+    }
+
+    pub fn batch_close_stale_accounts<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchClose<'info>>,
+        account_type: StaleAccountType,
+        pda_addresses: Vec<Pubkey>,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::batch_close_stale_accounts(ctx, account_type, pda_addresses)
+    }
+
+    pub fn repair_nft_origin_chain(ctx: Context<RepairNFTOrigin>, token_id: u64) -> Result<()> {
+        universal_nft::UniversalNFT::repair_nft_origin_chain(ctx, token_id)
+    }
+
+    pub fn verify_migration_integrity(ctx: Context<VerifyMigration>, token_ids: Vec<u64>) -> Result<()> {
+        universal_nft::UniversalNFT::verify_migration_integrity(ctx, token_ids)
+    }
+
+    pub fn replay_inbound(ctx: Context<ReplayInbound>, message: Vec<u8>, original_slot: u64) -> Result<()> {
+        universal_nft::UniversalNFT::replay_inbound(ctx, message, original_slot)
+    }
+
+    pub fn set_reservation_fee(ctx: Context<AdminAction>, reservation_fee: u64) -> Result<()> {
+        universal_nft::UniversalNFT::set_reservation_fee(ctx, reservation_fee)
+    }
+
+    pub fn set_native_id_base(ctx: Context<AdminAction>, native_id_base: u64) -> Result<()> {
+        universal_nft::UniversalNFT::set_native_id_base(ctx, native_id_base)
+    }
+
+    pub fn set_uri_dedup_window(
+        ctx: Context<AdminAction>,
+        max_uri_mints_per_window: u64,
+        window_seconds: i64,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::set_uri_dedup_window(ctx, max_uri_mints_per_window, window_seconds)
+    }
+
+    pub fn set_max_message_bytes(ctx: Context<AdminAction>, max_message_bytes: u32) -> Result<()> {
+        universal_nft::UniversalNFT::set_max_message_bytes(ctx, max_message_bytes)
+    }
+
+    pub fn create_raffle(
+        ctx: Context<CreateRaffle>,
+        token_id_to_raffle: u64,
+        ticket_price_lamports: u64,
+        max_tickets: u32,
+        end_time: i64,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::create_raffle(ctx, token_id_to_raffle, ticket_price_lamports, max_tickets, end_time)
+    }
+
+    pub fn buy_raffle_ticket(ctx: Context<BuyRaffleTicket>, raffle_id: u64) -> Result<()> {
+        universal_nft::UniversalNFT::buy_raffle_ticket(ctx, raffle_id)
+    }
+
+    pub fn request_winner_selection(ctx: Context<RequestWinnerSelection>, raffle_id: u64) -> Result<()> {
+        universal_nft::UniversalNFT::request_winner_selection(ctx, raffle_id)
+    }
+
+    pub fn fulfill_winner_selection(
+        ctx: Context<FulfillWinnerSelection>,
+        raffle_id: u64,
+        random_number: u64,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::fulfill_winner_selection(ctx, raffle_id, random_number)
+    }
+
+    pub fn cancel_raffle(ctx: Context<CancelRaffle>, raffle_id: u64) -> Result<()> {
+        universal_nft::UniversalNFT::cancel_raffle(ctx, raffle_id)
+    }
+
+    pub fn claim_raffle_refund(ctx: Context<ClaimRaffleRefund>, raffle_id: u64, ticket_index: u32) -> Result<()> {
+        universal_nft::UniversalNFT::claim_raffle_refund(ctx, raffle_id, ticket_index)
+    }
+
+    pub fn reserve_token_id(
+        ctx: Context<ReserveTokenId>,
+        token_id: u64,
+        payment: u64,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::reserve_token_id(ctx, token_id, payment)
+    }
+
+    pub fn close_expired_reservation(
+        ctx: Context<CloseExpiredReservation>,
+        token_id: u64,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::close_expired_reservation(ctx, token_id)
+    }
+
+    pub fn set_chain_trust(
+        ctx: Context<SetChainGasConfig>,
+        chain_id: u64,
+        trust_level: u8,
+        provisional_hold_seconds: u32,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::set_chain_trust(ctx, chain_id, trust_level, provisional_hold_seconds)
+    }
+
+    pub fn set_chain_batch_support(
+        ctx: Context<SetChainGasConfig>,
+        chain_id: u64,
+        supports_batch: bool,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::set_chain_batch_support(ctx, chain_id, supports_batch)
+    }
+
+    pub fn release_provisional(ctx: Context<ReleaseProvisional>, token_id: u64) -> Result<()> {
+        universal_nft::UniversalNFT::release_provisional(ctx, token_id)
+    }
+
+    pub fn revoke_provisional(ctx: Context<RevokeProvisional>, token_id: u64) -> Result<()> {
+        universal_nft::UniversalNFT::revoke_provisional(ctx, token_id)
+    }
+
+    pub fn reconcile_escrow(ctx: Context<ReconcileEscrow>, token_id: u64) -> Result<()> {
+        universal_nft::UniversalNFT::reconcile_escrow(ctx, token_id)
+    }
+
+    pub fn get_escrow_status(ctx: Context<GetEscrowStatus>, token_id: u64) -> Result<EscrowStatus> {
+        universal_nft::UniversalNFT::get_escrow_status(ctx, token_id)
+    }
+
+    pub fn set_require_nft_acceptance(ctx: Context<AdminAction>, required: bool) -> Result<()> {
+        universal_nft::UniversalNFT::set_require_nft_acceptance(ctx, required)
+    }
+
+    pub fn accept_inbound_nft(ctx: Context<AcceptInboundNFT>, token_id: u64) -> Result<()> {
+        universal_nft::UniversalNFT::accept_inbound_nft(ctx, token_id)
+    }
+
+    pub fn reject_inbound_nft(
+        ctx: Context<RejectInboundNFT>,
+        token_id: u64,
+        return_to_sender: bool,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::reject_inbound_nft(ctx, token_id, return_to_sender)
+    }
+
+    pub fn set_metadata_policy(ctx: Context<AdminAction>, metadata_policy: MetadataPolicy) -> Result<()> {
+        universal_nft::UniversalNFT::set_metadata_policy(ctx, metadata_policy)
+    }
+
+    pub fn retry_metadata(ctx: Context<RetryMetadata>, token_id: u64) -> Result<()> {
+        universal_nft::UniversalNFT::retry_metadata(ctx, token_id)
+    }
+
+    pub fn set_metadata_sync_throttle(ctx: Context<AdminAction>, max: u16) -> Result<()> {
+        universal_nft::UniversalNFT::set_metadata_sync_throttle(ctx, max)
+    }
+
+    pub fn set_origin_policy(
+        ctx: Context<SetOriginPolicy>,
+        origin_chain: u64,
+        origin_contract: Vec<u8>,
+        royalty_bps: u16,
+        royalty_recipient: Pubkey,
+        update_authority_mode: UpdateAuthorityMode,
+        is_mutable: bool,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::set_origin_policy(
+            ctx,
+            origin_chain,
+            origin_contract,
+            royalty_bps,
+            royalty_recipient,
+            update_authority_mode,
+            is_mutable,
+        )
+    }
+
+    pub fn remove_origin_policy(
+        ctx: Context<RemoveOriginPolicy>,
+        origin_chain: u64,
+        origin_contract: Vec<u8>,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::remove_origin_policy(ctx, origin_chain, origin_contract)
+    }
+
+    pub fn set_zrc20_gas_vault(ctx: Context<SetZRC20GasVault>, zrc20: [u8; 20], gas_fee: u64) -> Result<()> {
+        universal_nft::UniversalNFT::set_zrc20_gas_vault(ctx, zrc20, gas_fee)
+    }
+
+    pub fn deposit_zrc20_gas(ctx: Context<DepositZRC20Gas>, zrc20: [u8; 20], amount: u64) -> Result<()> {
+        universal_nft::UniversalNFT::deposit_zrc20_gas(ctx, zrc20, amount)
+    }
+
+    pub fn withdraw_zrc20_gas(
+        ctx: Context<WithdrawZRC20Gas>,
+        zrc20: [u8; 20],
+        amount: u64,
+        dest: Pubkey,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::withdraw_zrc20_gas(ctx, zrc20, amount, dest)
+    }
+
+    pub fn set_recovery_cutoff(ctx: Context<SetRecoveryCutoff>, cutoff_at: i64) -> Result<()> {
+        universal_nft::UniversalNFT::set_recovery_cutoff(ctx, cutoff_at)
+    }
+
+    pub fn recover_misdelivered(
+        ctx: Context<RecoverMisdelivered>,
+        token_id: u64,
+        correct_recipient: Pubkey,
+        evidence_hash: [u8; 32],
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::recover_misdelivered(ctx, token_id, correct_recipient, evidence_hash)
+    }
+
+    pub fn debug_decode(ctx: Context<DebugDecode>, message: Vec<u8>) -> Result<DebugDecodeResult> {
+        universal_nft::UniversalNFT::debug_decode(ctx, message)
+    }
+
+    pub fn benchmark_encoding(ctx: Context<BenchmarkCtx>, uri_len: u16) -> Result<()> {
+        universal_nft::UniversalNFT::benchmark_encoding(ctx, uri_len)
+    }
+
+    pub fn get_schema(ctx: Context<GetSchema>) -> Result<SchemaInfo> {
+        universal_nft::UniversalNFT::get_schema(ctx)
+    }
+
+    pub fn verify_integrity(ctx: Context<VerifyIntegrity>) -> Result<IntegrityReport> {
+        universal_nft::UniversalNFT::verify_integrity(ctx)
+    }
+
+    pub fn get_full_config_snapshot<'info>(
+        ctx: Context<'_, '_, 'info, 'info, FullConfigRead<'info>>,
+    ) -> Result<FullConfigSnapshot> {
+        universal_nft::UniversalNFT::get_full_config_snapshot(ctx)
+    }
+
+    pub fn set_kyc_config(
+        ctx: Context<SetKYCConfig>,
+        identity_program: Pubkey,
+        required_kyc_level: u8,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::set_kyc_config(ctx, identity_program, required_kyc_level)
+    }
+
+    pub fn init_registry(ctx: Context<InitRegistry>) -> Result<()> {
+        universal_nft::UniversalNFT::init_registry(ctx)
+    }
+
+    pub fn init_event_log(ctx: Context<InitEventLog>) -> Result<()> {
+        universal_nft::UniversalNFT::init_event_log(ctx)
+    }
+
+    pub fn read_event_log(
+        ctx: Context<ReadEventLog>,
+        from_index: u16,
+        count: u8,
+    ) -> Result<Vec<CrossChainEventEntry>> {
+        universal_nft::UniversalNFT::read_event_log(ctx, from_index, count)
+    }
+
+    pub fn set_registry_entry(
+        ctx: Context<UpdateRegistry>,
+        zrc20: [u8; 20],
+        contract: Vec<u8>,
+        chain_id: u64,
+        flags: u8,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::set_registry_entry(ctx, zrc20, contract, chain_id, flags)
+    }
+
+    pub fn remove_registry_entry(ctx: Context<UpdateRegistry>, zrc20: [u8; 20]) -> Result<()> {
+        universal_nft::UniversalNFT::remove_registry_entry(ctx, zrc20)
+    }
+
+    pub fn set_chain_registry_config(
+        ctx: Context<SetChainRegistryConfig>,
+        observer_program: Pubkey,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::set_chain_registry_config(ctx, observer_program)
+    }
+
+    pub fn validate_chain_id_against_zeta_registry(
+        ctx: Context<ValidateChain>,
+        chain_id: u64,
+        zrc20: [u8; 20],
+        contract: Vec<u8>,
+        flags: u8,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::validate_chain_id_against_zeta_registry(ctx, chain_id, zrc20, contract, flags)
+    }
+
+    pub fn lookup_connected_contract(
+        ctx: Context<LookupConnectedContract>,
+        zrc20: [u8; 20],
+    ) -> Result<Vec<u8>> {
+        universal_nft::UniversalNFT::lookup_connected_contract(ctx, zrc20)
+    }
+
+    pub fn export_config(ctx: Context<LookupConnectedContract>, kind: u8, page: u8) -> Result<Vec<u8>> {
+        universal_nft::UniversalNFT::export_config(ctx, kind, page)
+    }
+
+    pub fn import_config<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ImportConfig<'info>>,
+        blob: Vec<u8>,
+        force: bool,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::import_config(ctx, blob, force)
+    }
+
+    pub fn set_collection_authority(
+        ctx: Context<SetCollectionAuthority>,
+        collection_id: u64,
+        authority: Pubkey,
+        permissions: CollectionPermissions,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::set_collection_authority(ctx, collection_id, authority, permissions)
+    }
+
+    pub fn set_seller_fee_config(
+        ctx: Context<SetSellerFeeConfig>,
+        collection_id: u64,
+        creators: Vec<RoyaltyCreator>,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::set_seller_fee_config(ctx, collection_id, creators)
+    }
+
+    pub fn update_royalty_recipient(
+        ctx: Context<UpdateRoyaltyRecipient>,
+        collection_id: u64,
+        creator_index: u8,
+        new_recipient: Pubkey,
+        new_share: u8,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::update_royalty_recipient(ctx, collection_id, creator_index, new_recipient, new_share)
+    }
+
+    pub fn deposit_royalties(
+        ctx: Context<DepositRoyalties>,
+        collection_id: u64,
+        amount: u64,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::deposit_royalties(ctx, collection_id, amount)
+    }
+
+    pub fn distribute_royalties<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DistributeRoyalties<'info>>,
+        collection_id: u64,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::distribute_royalties(ctx, collection_id)
+    }
+
+    pub fn claim_creator_royalties(
+        ctx: Context<ClaimCreatorRoyalties>,
+        collection_id: u64,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::claim_creator_royalties(ctx, collection_id)
+    }
+
+    pub fn compute_collection_merkle_root<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ComputeMerkleRoot<'info>>,
+        collection_id: u64,
+        token_ids: Vec<u64>,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::compute_collection_merkle_root(ctx, collection_id, token_ids)
+    }
+
+    pub fn configure_lazy_reveal(
+        ctx: Context<ConfigureLazyReveal>,
+        collection_id: u64,
+        reveal_time: i64,
+        num_traits: u8,
+        uri_base: String,
+        vrf_authority: Pubkey,
+    ) -> Result<()> {
+        universal_nft::UniversalNFT::configure_lazy_reveal(
+            ctx,
+            collection_id,
+            reveal_time,
+            num_traits,
+            uri_base,
+            vrf_authority,
+        )
+    }
+
+    pub fn trigger_reveal(ctx: Context<TriggerReveal>, collection_id: u64) -> Result<()> {
+        universal_nft::UniversalNFT::trigger_reveal(ctx, collection_id)
+    }
+
+    pub fn fulfill_reveal(ctx: Context<FulfillReveal>, collection_id: u64, vrf_result: [u8; 32]) -> Result<()> {
+        universal_nft::UniversalNFT::fulfill_reveal(ctx, collection_id, vrf_result)
+    }
+
+    pub fn update_token_uri(ctx: Context<UpdateTokenUri>, collection_id: u64, token_id: u64) -> Result<()> {
+        universal_nft::UniversalNFT::update_token_uri(ctx, collection_id, token_id)
+    }
+}
+
+// Account structures
+#[account]
+#[derive(InitSpace)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProgramState {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub owner: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub gateway: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
+    pub universal_nft_contract: [u8; 20], 
+    pub next_token_id: u64,
+    pub paused: bool,
+    pub bump: u8,
+    pub gas_limit: u64,
+    pub swap_router_version: u8,
+    pub default_swap_fee_tier: u32,
+    pub auto_paused_inbound: bool,
+    pub ping_count: u64,
+    pub reservation_fee: u64,
+    pub transfer_nonce: u64,
+    pub pause_reason: u8,
+    pub auto_unpause_at: Option<i64>,
+    // PROGRAM_STATE_VERSION this account was written with; zero until migrated
+    pub schema_version: u8,
+    // First token id native mints may use; ids below it are left to inbound transfers
+    pub native_id_base: u64,
+    // ZetaChain id of the Solana cluster declared at initialize, recorded as origin_chain.
+    // Nothing on chain verifies the declaration
+    pub declared_chain_id: u64,
+    // Inbound NFTs delivered, single and batched; zero for deliveries before it was added
+    pub inbound_count: u64,
+    // New inbound token ids one metadata URI may arrive under per window; zero is unlimited
+    pub max_uri_mints_per_window: u64,
+    // Zero means DEFAULT_URI_DEDUP_WINDOW_SECONDS
+    pub uri_dedup_window_seconds: i64,
+    // Largest outbound gateway message; zero means DEFAULT_MAX_MESSAGE_BYTES
+    pub max_message_bytes: u32,
+    // Signs metadata attestations for inbound NFTs that arrived without a URI; default
+    // (unset) turns attach_metadata off
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub attestation_key: Pubkey,
+    // Lamports a transfer's sender must hold; zero means MIN_USER_SOL_RESERVE
+    pub min_user_sol_reserve: u64,
+    // Bridge transfers and deliveries go through; accounts from before it was added read
+    // as ZetaChain
+    pub bridge_backend: BridgeBackend,
+    // Direct deliveries wait in escrow until the recipient accepts them
+    pub require_nft_acceptance: bool,
+    // Whether metadata Metaplex would reject fails an inbound delivery; accounts from
+    // before it was added read as Atomic
+    pub metadata_policy: MetadataPolicy,
+    // Whether inbound messages are delivered in one instruction or queued and executed
+    // later; accounts from before it was added read as OnePhase
+    pub inbound_mode: InboundMode,
+    // Metadata CPIs `retry_metadata` may make per slot; zero is unlimited
+    pub max_metadata_uri_requests_per_slot: u16,
+    // Metadata CPIs made so far in last_sync_slot
+    pub metadata_sync_requests_this_slot: u16,
+    pub last_sync_slot: u64,
+    // Native mints leave a NFTMintOnly record and their NFTOrigin is created on first use
+    pub lazy_origins_enabled: bool,
+    // Largest native token id issued; zero for mints before it was added
+    pub max_native_token_id: u64,
+    // Mints, deliveries and transfers may carry more than one unit of a token
+    pub allow_semi_fungible: bool,
+    // Set while a handler is inside a gateway CPI; every guarded handler refuses to run
+    // until it clears
+    pub locked: bool,
+    // Announced upgrade authority `verify_integrity` checks the deployed one against;
+    // default (unset) passes only an immutable program
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub expected_upgrade_authority: Pubkey,
+    // Origin-chain contracts may recall tokens bridged in from their chain
+    pub forced_recall_enabled: bool,
+    // How long a holder has to contest a recall; zero means DEFAULT_RECALL_DISPUTE_WINDOW_SECONDS
+    pub recall_dispute_window_seconds: i64,
+}
+
+#[account]
+#[derive(InitSpace)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NFTOrigin {
+    // NFT_ORIGIN_VERSION this account was last written at. First, so its offset never moves
+    // and migrate_nft_origin can tell which layout the rest of the account has
+    pub schema_version: u8,
+    pub token_id: u64,
+    pub origin_chain: u64,
+    pub origin_token_id: u64,
+    #[max_len(MAX_METADATA_URI_BYTES)]
+    pub metadata_uri: String,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey,
+    pub created_at: i64,
+    pub bump: u8,
+    // Non-zero while a provisional delivery is held in escrow
+    pub release_at: i64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub provisional_recipient: Pubkey,
+    // Set while the departed token's sender holds a departure receipt for it
+    pub receipt_outstanding: bool,
+    // Key for metadata behind an encrypted URI, wrapped for the holder; never a raw secret,
+    // since account data is public. Travels with the NFT on cross-chain transfers
+    pub metadata_encryption_key: Option<[u8; 32]>,
+    // When the NFT last arrived on Solana; zero for native mints
+    pub arrived_at: i64,
+    // Wallet a custodial delivery was made for, since the custodian holds the token;
+    // default when the holder owns it directly
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub logical_owner: Pubkey,
+    // Memo of the last transfer or delivery that carried one, also logged through SPL Memo
+    #[max_len(MAX_MEMO_BYTES)]
+    pub last_memo: String,
+    // Units frozen in accounts nobody controls by `recover_misdelivered`; they count toward
+    // the mint's supply but never circulate
+    pub stranded_supply: u64,
+    // Set when a best-effort delivery skipped the metadata; cleared by `retry_metadata`
+    pub metadata_pending: bool,
+    // Lazy-reveal collection whose placeholder URI this token holds until `update_token_uri`
+    pub reveal_collection_id: Option<u64>,
+    // Chain and contract the NFT was first minted by, where origin_token_id is its id. The
+    // contract is kept as its bytes when they fit, else as origin_contract_hash of them;
+    // origin_contract_len is zero until a provenance is recorded
+    pub provenance_chain: u64,
+    pub origin_contract: [u8; 32],
+    pub origin_contract_len: u8,
+    // Bumps of the mint's Token Metadata metadata and master edition PDAs, found once when
+    // the origin is written so later instructions check those accounts without searching
+    pub metadata_bump: u8,
+    pub edition_bump: u8,
+    // Set while a CoOwnershipRecord exists for the mint; transfer_cross_chain then needs it
+    pub co_owned: bool,
+    // Most units the token has been minted or delivered with: one for an NFT, more for a
+    // semi-fungible token. Zero on origins written before it was added, which are NFTs
+    pub amount: u64,
+    // While in the future, the NFT is rented out and can't leave Solana. Only
+    // rental_locker, the holder or their delegate that set it, can lift it early
+    pub rental_locked_until: i64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub rental_locker: Pubkey,
+    // Rebuilt by replay_inbound from an archived message rather than written by a delivery
+    pub reconstructed: bool,
+    // Units that have left Solana and not come back. A returning delivery brings back at
+    // most this many, so a replayed message finds none left to deliver
+    pub departed_amount: u64,
+}
+
+impl ProgramState {
+    /// Largest serialized size of the layout, discriminator included
+    pub const fn space() -> usize {
+        8 + Self::INIT_SPACE
+    }
+}
+
+impl NFTOrigin {
+    /// Largest serialized size of the layout, discriminator included
+    pub const fn space() -> usize {
+        8 + Self::INIT_SPACE
+    }
+
+    /// Record where the NFT was first minted, origin_token_id included
+    pub fn set_provenance(&mut self, provenance: &Provenance) {
+        let contract = &provenance.origin_contract;
+        self.provenance_chain = provenance.origin_chain;
+        self.origin_token_id = provenance.origin_token_id;
+        self.origin_contract = match contract.len() {
+            len if len <= 32 => {
+                let mut stored = [0u8; 32];
+                stored[..len].copy_from_slice(contract);
+                stored
+            }
+            _ => origin_contract_hash(contract),
+        };
+        self.origin_contract_len = contract.len() as u8;
+    }
+
+    /// Provenance to send along when the NFT departs; none until one is recorded. A contract
+    /// kept hashed goes out as its hash
+    pub fn provenance(&self) -> Option<Provenance> {
+        if self.origin_contract_len == 0 {
+            return None;
+        }
+        Some(Provenance {
+            origin_chain: self.provenance_chain,
+            origin_token_id: self.origin_token_id,
+            origin_contract: self.origin_contract[..(self.origin_contract_len as usize).min(32)].to_vec(),
+        })
+    }
+
+    /// Find and record the bumps of the mint's metadata and master edition PDAs
+    pub fn record_metadata_bumps(&mut self) {
+        self.metadata_bump = mpl_token_metadata::accounts::Metadata::find_pda(&self.mint).1;
+        self.edition_bump = mpl_token_metadata::accounts::MasterEdition::find_pda(&self.mint).1;
+    }
+
+    /// Whether the token was ever minted or delivered as more than one unit
+    pub fn is_semi_fungible(&self) -> bool {
+        self.amount > 1
+    }
+
+    /// Whether the account was written at the current layout and has room for it. An older
+    /// one decodes its newer fields from bytes its own layout never wrote, so every
+    /// instruction but migrate_nft_origin refuses it
+    pub fn is_current(&self, data_len: usize) -> bool {
+        self.schema_version == NFT_ORIGIN_VERSION && data_len >= NFT_ORIGIN_SPACE
+    }
+
+    /// `is_current`, or the zeroed account `init_if_needed` has just created
+    pub fn is_current_or_new(&self, data_len: usize) -> bool {
+        self.is_current(data_len)
+            || (self.schema_version == 0 && self.mint == Pubkey::default() && data_len >= NFT_ORIGIN_SPACE)
+    }
+
+    /// Decode an account written at any layout up to the current one. Only the fields that
+    /// layout had are read; later ones start at their defaults, since the bytes past its end
+    /// may be zero padding or stale from a longer string it once held. Accounts from before
+    /// schema_version are told apart by their size
+    pub fn decode_any_version(data: &[u8]) -> Result<Self> {
+        require!(
+            data.get(..8) == Some(NFTOrigin::DISCRIMINATOR),
+            anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch
+        );
+        let mut body = &data[8..];
+        if data.len() < NFT_ORIGIN_V7_SPACE {
+            let (legacy, version) = LegacyNFTOrigin::decode_sized(&mut body, data.len())?;
+            return Ok(NFTOrigin { schema_version: version, ..legacy.into() });
+        }
+        let schema_version = u8::deserialize(&mut body)?;
+        let mut origin = NFTOrigin::from(LegacyNFTOrigin::deserialize(&mut body)?);
+        origin.schema_version = schema_version;
+        if schema_version >= 8 {
+            origin.provenance_chain = u64::deserialize(&mut body)?;
+            origin.origin_contract = <[u8; 32]>::deserialize(&mut body)?;
+            origin.origin_contract_len = u8::deserialize(&mut body)?;
+        }
+        if schema_version >= 9 {
+            origin.metadata_bump = u8::deserialize(&mut body)?;
+            origin.edition_bump = u8::deserialize(&mut body)?;
+        }
+        if schema_version >= 10 {
+            origin.co_owned = bool::deserialize(&mut body)?;
+        }
+        if schema_version >= 11 {
+            origin.amount = u64::deserialize(&mut body)?;
+        }
+        if schema_version >= 12 {
+            origin.rental_locked_until = i64::deserialize(&mut body)?;
+            origin.rental_locker = Pubkey::deserialize(&mut body)?;
+        }
+        if schema_version >= 13 {
+            origin.reconstructed = bool::deserialize(&mut body)?;
+        }
+        if schema_version >= 14 {
+            origin.departed_amount = u64::deserialize(&mut body)?;
+        }
+        Ok(origin)
+    }
+}
+
+/// NFTOrigin as written before `schema_version` existed, at NFT_ORIGIN_VERSION 6. Such
+/// accounts are one byte short of NFT_ORIGIN_V7_SPACE; only migrate_nft_origin reads them
+#[derive(AnchorSerialize, AnchorDeserialize, Default)]
+pub struct LegacyNFTOrigin {
+    pub token_id: u64,
+    pub origin_chain: u64,
+    pub origin_token_id: u64,
+    pub metadata_uri: String,
+    pub mint: Pubkey,
+    pub created_at: i64,
+    pub bump: u8,
+    pub release_at: i64,
+    pub provisional_recipient: Pubkey,
+    pub receipt_outstanding: bool,
+    pub metadata_encryption_key: Option<[u8; 32]>,
+    pub arrived_at: i64,
+    pub logical_owner: Pubkey,
+    pub last_memo: String,
+    pub stranded_supply: u64,
+    pub metadata_pending: bool,
+    pub reveal_collection_id: Option<u64>,
+}
+
+impl LegacyNFTOrigin {
+    pub const VERSION: u8 = 6;
+
+    /// Decode an account of `space` bytes written before schema_version, reading the fields
+    /// of whichever earlier layout that size belongs to and defaulting the rest. Also returns
+    /// the NFT_ORIGIN_VERSION it was written at: zero before that constant existed, and 3 for
+    /// the logical_owner and last_memo layouts, which shipped without bumping it
+    pub fn decode_sized(body: &mut &[u8], space: usize) -> Result<(Self, u8)> {
+        let mut origin = Self {
+            token_id: u64::deserialize(body)?,
+            origin_chain: u64::deserialize(body)?,
+            origin_token_id: u64::deserialize(body)?,
+            metadata_uri: String::deserialize(body)?,
+            mint: Pubkey::deserialize(body)?,
+            created_at: i64::deserialize(body)?,
+            bump: u8::deserialize(body)?,
+            ..Self::default()
+        };
+        let mut end = NFT_ORIGIN_BASE_SPACE;
+        let mut version = 0;
+        let mut appended = |bytes: usize, at_version: u8| {
+            end += bytes;
+            let present = space >= end;
+            if present {
+                version = at_version;
+            }
+            present
+        };
+        if appended(8 + 32, 0) {
+            origin.release_at = i64::deserialize(body)?;
+            origin.provisional_recipient = Pubkey::deserialize(body)?;
+        }
+        if appended(1, 1) {
+            origin.receipt_outstanding = bool::deserialize(body)?;
+        }
+        if appended(33, 2) {
+            origin.metadata_encryption_key = Option::<[u8; 32]>::deserialize(body)?;
+        }
+        if appended(8, 3) {
+            origin.arrived_at = i64::deserialize(body)?;
+        }
+        if appended(32, 3) {
+            origin.logical_owner = Pubkey::deserialize(body)?;
+        }
+        if appended(4 + MAX_MEMO_BYTES, 3) {
+            origin.last_memo = String::deserialize(body)?;
+        }
+        if appended(8, 4) {
+            origin.stranded_supply = u64::deserialize(body)?;
+        }
+        if appended(1, 5) {
+            origin.metadata_pending = bool::deserialize(body)?;
+        }
+        if appended(9, Self::VERSION) {
+            origin.reveal_collection_id = Option::<u64>::deserialize(body)?;
+        }
+        Ok((origin, version))
+    }
+}
+
+impl From<LegacyNFTOrigin> for NFTOrigin {
+    fn from(legacy: LegacyNFTOrigin) -> Self {
+        Self {
+            schema_version: LegacyNFTOrigin::VERSION,
+            token_id: legacy.token_id,
+            origin_chain: legacy.origin_chain,
+            origin_token_id: legacy.origin_token_id,
+            metadata_uri: legacy.metadata_uri,
+            mint: legacy.mint,
+            created_at: legacy.created_at,
+            bump: legacy.bump,
+            release_at: legacy.release_at,
+            provisional_recipient: legacy.provisional_recipient,
+            receipt_outstanding: legacy.receipt_outstanding,
+            metadata_encryption_key: legacy.metadata_encryption_key,
+            arrived_at: legacy.arrived_at,
+            logical_owner: legacy.logical_owner,
+            last_memo: legacy.last_memo,
+            stranded_supply: legacy.stranded_supply,
+            metadata_pending: legacy.metadata_pending,
+            reveal_collection_id: legacy.reveal_collection_id,
+            provenance_chain: 0,
+            origin_contract: [0u8; 32],
+            origin_contract_len: 0,
+            metadata_bump: 0,
+            edition_bump: 0,
+            co_owned: false,
+            amount: 1,
+            rental_locked_until: 0,
+            rental_locker: Pubkey::default(),
+            reconstructed: false,
+            departed_amount: 0,
+        }
+    }
+}
+
+// A field added without growing the hand-counted *_SPACE stops the build instead of
+// leaving accounts too small to hold it
+const _: () = assert!(NFTOrigin::space() <= NFT_ORIGIN_SPACE, "NFTOrigin exceeds allocated space");
+// migrate_registry re-lays older entries as prefixes of the current one, after an 8-byte header
+const _: () = assert!(
+    std::mem::size_of::<RegistryEntry>() == REGISTRY_ENTRY_V1_BYTES + 8
+        && std::mem::size_of::<Registry>() == 8 + REGISTRY_CAPACITY * std::mem::size_of::<RegistryEntry>(),
+    "RegistryEntry changed without a migrate_registry stride for its previous layout"
+);
+// The legacy layouts decode_sized steps through, plus the version byte, end at version 7's
+const _: () = assert!(
+    NFT_ORIGIN_BASE_SPACE + (8 + 32) + 1 + 33 + 8 + 32 + (4 + MAX_MEMO_BYTES) + 8 + 1 + 9 + 1 == NFT_ORIGIN_V7_SPACE,
+    "legacy NFTOrigin layouts don't add up to NFT_ORIGIN_V7_SPACE"
+);
+
+/// How a wallet takes delivery of bridged NFTs. Without a binding it gets its own ATA
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReceiverBinding {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub owner: Pubkey,
+    pub delivery_mode: DeliveryMode,
+    pub bump: u8,
+}
+
+/// Metadata overrides for NFTs bridged in from one origin contract, in place of the
+/// program-wide defaults: no royalties, the mint authority PDA as update authority, mutable
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OriginPolicy {
+    pub origin_chain: u64,
+    pub origin_contract_hash: [u8; 32],
+    pub royalty_bps: u16,
+    // Sole creator on the metadata while royalty_bps is non-zero
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub royalty_recipient: Pubkey,
+    pub update_authority_mode: UpdateAuthorityMode,
+    pub is_mutable: bool,
+    pub bump: u8,
+}
+
+/// Who may update the metadata of NFTs bridged in under an OriginPolicy
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UpdateAuthorityMode {
+    // The mint authority PDA, as without a policy
+    Program,
+    // The partner collection's own authority
+    Retained {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+        authority: Pubkey,
+    },
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DeliveryMode {
+    Ata,
+    // An existing token account, typically a custodian's omnibus account; it must be for
+    // the delivered NFT's mint
+    Custodial {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+        token_account: Pubkey,
+    },
+}
+
+/// Compute units the message codec took for a URI of `uri_len` bytes, as last measured
+/// by `benchmark_encoding`
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EncodingBenchmark {
+    pub uri_len: u16,
+    pub encode_cus: u64,
+    pub decode_cus: u64,
+    pub total_cus: u64,
+    pub bump: u8,
+}
+
+/// NFTs delivered before `cutoff_at` may be recovered by `recover_misdelivered`
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RecoveryConfig {
+    pub cutoff_at: i64,
+    pub bump: u8,
+}
+
+/// An inbound message queued by `queue_inbound`, waiting for `execute_inbound` to deliver it
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PendingInbound {
+    pub message_hash: [u8; 32],
+    pub token_id: u64,
+    // Fixed when queued so the permissionless executor can't redirect the NFT
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub recipient: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub queued_by: Pubkey,
+    pub queued_at: i64,
+    pub bump: u8,
+    pub message: Vec<u8>,
+    pub memo: Option<String>,
+    // From the message's priority trailer; orders list_pending_inbound and nothing else.
+    // Appended after the variable-length fields, so records queued before it lack the byte
+    pub priority: u8,
+}
+
+impl PendingInbound {
+    /// Decode a record queued at either layout. One from before `priority` ends at its memo
+    /// and reads as priority zero, which every message had then
+    pub fn decode_any_version(data: &[u8]) -> Result<Self> {
+        require!(
+            data.get(..8) == Some(PendingInbound::DISCRIMINATOR),
+            anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch
+        );
+        let mut body = &data[8..];
+        Ok(PendingInbound {
+            message_hash: <[u8; 32]>::deserialize(&mut body)?,
+            token_id: u64::deserialize(&mut body)?,
+            recipient: Pubkey::deserialize(&mut body)?,
+            queued_by: Pubkey::deserialize(&mut body)?,
+            queued_at: i64::deserialize(&mut body)?,
+            bump: u8::deserialize(&mut body)?,
+            message: Vec::<u8>::deserialize(&mut body)?,
+            memo: Option::<String>::deserialize(&mut body)?,
+            priority: if body.is_empty() { 0 } else { u8::deserialize(&mut body)? },
+        })
+    }
+
+    /// The size of this record at the current layout
+    pub fn space(&self) -> usize {
+        PENDING_INBOUND_SPACE + self.message.len() + self.memo.as_ref().map_or(0, String::len)
+    }
+
+    /// Whether the account has room for every field. An older one can't be loaded as an
+    /// `Account` until migrate_pending_inbound grows it
+    pub fn is_current(&self, data_len: usize) -> bool {
+        data_len >= self.space()
+    }
+}
+
+/// An inbound NFT held in escrow until `recipient` accepts or rejects it
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InboundNFTOffer {
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub recipient: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey,
+    // EVM sender of the inbound message; a rejected NFT can be sent back to it
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
+    pub sender: [u8; 20],
+    // Paid the offer's rent, and gets it back when the offer closes
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub payer: Pubkey,
+    pub offered_at: i64,
+    pub offer_expires_at: i64,
+    pub bump: u8,
+}
+
+/// Audit record of a `recover_misdelivered`; one per token, so a token is recovered once
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MisdeliveryRecovery {
+    pub token_id: u64,
+    // Frozen in place; its owner is a zero-padded EVM address nobody holds a key for
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub stranded_token_account: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub stranded_owner: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub correct_recipient: Pubkey,
+    // Hash of the off-chain claim ticket the recovery was approved on
+    pub evidence_hash: [u8; 32],
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub recovered_by: Pubkey,
+    pub recovered_at: i64,
+    pub bump: u8,
+}
+
+/// Inbound NFTs from one origin chain, for per-chain analytics. Created by the first
+/// delivery from that chain; `active` is how many currently live on Solana
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChainStats {
+    pub chain_id: u64,
+    pub received: u64,
+    pub departed: u64,
+    pub active: u64,
+    // Sum over departures of how long each NFT stayed, from arrival to departure
+    pub cumulative_dwell_seconds: u64,
+    pub bump: u8,
+}
+
+/// Bridge activity within one hour, `hour_bucket = unix_timestamp / 3600`. Counted only
+/// while the current hour's bucket exists and is passed; `active_users` counts user-signed
+/// mints and departures, not distinct wallets
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HourlyMetrics {
+    pub hour_bucket: u64,
+    pub mints: u32,
+    pub burns: u32,
+    pub receives: u32,
+    // Anomalies reported through report_anomaly; failed transactions leave no trace
+    pub errors: u32,
+    // Sum of the gas limits requested for outbound messages
+    pub total_gas_used: u64,
+    pub active_users: u32,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub payer: Pubkey,
+    pub bump: u8,
+}
+
+/// First arrivals of new token ids under one metadata URI, so a source chain minting fresh
+/// ids for the same content can't flood Solana with copies. The window restarts at the
+/// first arrival after it has elapsed
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeduplicationWindow {
+    pub uri_hash: [u8; 32],
+    pub first_seen: i64,
+    pub mint_count: u64,
+    pub bump: u8,
+}
+
+/// Every setting a monitoring dashboard needs, in one flat read. Fields backed by an account
+/// the caller didn't pass, or that doesn't exist yet, are zero
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FullConfigSnapshot {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub owner: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub gateway: Pubkey,
+    pub next_token_id: u64,
+    pub paused: bool,
+    pub gas_limit: u64,
+    // Fee for reserving a native token id, the only fee charged around minting
+    pub fee_mint_lamports: u64,
+    // FeeConfig.transfer_fee_lamports, before any holder discount
+    pub fee_transfer_lamports: u64,
+    // Lamports held by the fee treasury
+    pub gas_reserve_balance: u64,
+    // Inbound circuit breaker: its counting window and failed decode threshold
+    pub rate_limit_window: i64,
+    pub rate_limit_max: u32,
+    pub connected_contract_count: u32,
+    pub migration_version: u8,
+    // Name of the Solana cluster declared at initialization
+    pub network: String,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChainStatsSummary {
+    pub chain_id: u64,
+    pub received: u64,
+    pub departed: u64,
+    pub active: u64,
+    pub cumulative_dwell_seconds: u64,
+    pub average_dwell_seconds: u64,
+}
+
+/// Anomaly counters feeding the inbound circuit breaker
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SecurityState {
+    pub window_start: i64,
+    pub window_seconds: i64,
+    pub failed_decode_count: u32,
+    pub replay_rejection_count: u32,
+    pub failed_decode_threshold: u32,
+    pub replay_rejection_threshold: u32,
+    pub bump: u8,
+}
+
+/// `kind` values carried by `AdminActionExecuted`; append only, never reorder
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AdminActionKind {
+    SetGateway,
+    SetGasLimit,
+    SetChainGasConfig,
+    SetConnectedContract,
+    Pause,
+    Unpause,
+    SetUniversalNftContract,
+    SetCircuitBreakerConfig,
+    ClearCircuitBreaker,
+    SetFeeConfig,
+    SetFeeDiscountTiers,
+    SetSellerFeeConfig,
+    SetSwapRouterVersion,
+    SetFloorPriceFeed,
+    RepairNftOrigin,
+    SetReservationFee,
+    SetChainTrust,
+    RevokeProvisional,
+    SetKycConfig,
+    InitRegistry,
+    SetRegistryEntry,
+    RemoveRegistryEntry,
+    UpdateRoyaltyRecipient,
+    SetReceiptConfig,
+    SetNativeIdBase,
+    SetRelayerAllowlist,
+    GrantRelayerRole,
+    RevokeRelayerRole,
+    ComputeMerkleRoot,
+    SetHeartbeatInterval,
+    SetUriDedupWindow,
+    SetMaxMessageBytes,
+    SetAttestationKey,
+    AttachMetadataOverride,
+    SetMinUserSolReserve,
+    SetCollectionAuthority,
+    SetRecoveryCutoff,
+    RecoverMisdelivered,
+    SetBridgeBackend,
+    InitEventLog,
+    SetRequireNftAcceptance,
+    SetMetadataPolicy,
+    SetMintFee,
+    SetInboundMode,
+    SetMetadataSyncThrottle,
+    SetLazyOrigins,
+    SetOriginPolicy,
+    RemoveOriginPolicy,
+    SetZRC20GasVault,
+    WithdrawZRC20Gas,
+    DebugSetNextTokenId,
+    ConfigureLazyReveal,
+    SetChainBatchSupport,
+    SetChainRegistryConfig,
+    ImportConfig,
+    SetInsuranceConfig,
+    BatchCloseStaleAccounts,
+    SetAppraisalAuthority,
+    VerifyMigrationIntegrity,
+    SetAllowSemiFungible,
+    ReplayInbound,
+    SetExpectedUpgradeAuthority,
+    SetForcedRecallPolicy,
+    MigrateNftMintOnly,
+}
+
+/// Cross-chain messaging backend, stored as `ProgramState::bridge_backend`; see
+/// `CrossChainNFTBridge`. Append only, never reorder
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BridgeBackend {
+    ZetaChain,
+    Wormhole,
+}
+
+/// What an inbound delivery does when its metadata can't be written, stored as
+/// `ProgramState::metadata_policy`. Outbound and native mint paths are always atomic.
+/// Append only, never reorder
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MetadataPolicy {
+    // The delivery fails with the metadata
+    Atomic,
+    // The token is delivered without metadata and flagged `metadata_pending`
+    BestEffort,
+}
+
+/// How inbound messages are delivered, stored as `ProgramState::inbound_mode`. Append
+/// only, never reorder
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InboundMode {
+    // `receive_cross_chain_message` validates and delivers in one instruction
+    OnePhase,
+    // `queue_inbound` only validates and records the message; `execute_inbound` delivers it
+    TwoPhase,
+}
+
+/// Kinds of expiring account `batch_close_stale_accounts` can sweep. Append only, never reorder
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StaleAccountType {
+    // Past METRICS_RETENTION_SECONDS after its hour
+    HourlyMetrics,
+}
+
+/// Why `verify_migration_integrity` flagged an NFTOrigin; the first check it fails. Append
+/// only, never reorder
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IntegrityFailureReason {
+    // No NFTOrigin at the token's PDA, as for a lazily minted token
+    Missing,
+    // Owned by the program but not readable as an NFTOrigin
+    Undecodable,
+    // Written before NFT_ORIGIN_VERSION; migrate_nft_origin brings it up to date
+    OutdatedSchema,
+    MissingMint,
+    MissingCreatedAt,
+    EmptyMetadataUri,
+    // Not a known chain id; repair_nft_origin_chain rewrites it
+    InvalidOriginChain,
+}
+
+/// How a token's escrow, the mint authority's ATA for its mint, compares with the records
+/// that claim it: a provisional hold (`NFTOrigin::release_at`) or an inbound offer. Append
+/// only, never reorder
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EscrowFinding {
+    // Nothing held and nothing claimed
+    Empty,
+    // One unit held for a hold or an offer
+    Held,
+    // A hold over an empty escrow; nothing is left to release, so reconcile_escrow clears it
+    StaleHold,
+    // Units held that no hold or offer accounts for
+    OrphanedEscrow,
+    // An offer over an empty escrow; accepting or rejecting it would fail
+    OfferWithoutEscrow,
+    // More than one unit held
+    ExcessEscrow,
+}
+
+impl EscrowFinding {
+    /// Drift `reconcile_escrow` can't repair on its own and flags for the admin
+    pub fn needs_admin(self) -> bool {
+        matches!(
+            self,
+            EscrowFinding::OrphanedEscrow | EscrowFinding::OfferWithoutEscrow | EscrowFinding::ExcessEscrow
+        )
+    }
+}
+
+/// Why the program was paused; stored as `ProgramState::pause_reason`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PauseReason {
+    Incident,
+    Upgrade,
+    Maintenance,
+    Other,
+}
+
+impl TryFrom<u8> for PauseReason {
+    type Error = Error;
+
+    fn try_from(code: u8) -> Result<Self> {
+        match code {
+            0 => Ok(PauseReason::Incident),
+            1 => Ok(PauseReason::Upgrade),
+            2 => Ok(PauseReason::Maintenance),
+            3 => Ok(PauseReason::Other),
+            _ => err!(ErrorCode::InvalidPauseReason),
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AnomalyKind {
+    DecodeFailure,
+    ReplayRejection,
+}
+
+/// Stand-in for the NFTOrigin of a native mint made while lazy origins were enabled, until
+/// `initialize_nft_origin_lazy` creates the origin
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NFTMintOnly {
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey,
+    pub bump: u8,
+    // uri_hash of the URI the token was minted with, which the lazily created origin must carry
+    pub uri_hash: [u8; 32],
+}
+
+/// Reverse lookup from a mint to its universal token id
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NFTIndex {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey,
+    pub token_id: u64,
+    pub bump: u8,
+}
+
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FeeConfig {
+    pub transfer_fee_lamports: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub treasury: Pubkey,
+    pub discount_tiers: Vec<FeeDiscountTier>,
+    pub bump: u8,
+    // Charged by create_mint_and_nft; zero makes minting free
+    pub mint_fee_lamports: u64,
+    // Share of the mint fee paid to the collection's first creator; the treasury gets the rest
+    pub creator_fee_bps: u16,
+    // Paid into the ProtocolInsuranceFund per opt-in; zero turns insurance off
+    pub insurance_premium_lamports: u64,
+    // Paid back from the fund when an insured transfer reverts
+    pub insurance_payout_lamports: u64,
+}
+
+/// A user's transfer insurance. Each opt-in pays one premium and covers the next
+/// `transfer_cross_chain`, which uses it up
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InsuranceOptIn {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub user: Pubkey,
+    pub opted_in: bool,
+    pub total_premiums_paid: u64,
+    // FeeConfig::insurance_payout_lamports when the cover was bought
+    pub payout_lamports: u64,
+    pub bump: u8,
+}
+
+/// Holds the premiums as lamports above its rent; reverted insured transfers are paid from it
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProtocolInsuranceFund {
+    pub total_premiums: u64,
+    pub total_payouts: u64,
+    pub bump: u8,
+}
+
+/// Value sent with inbound NFTs to a recipient that couldn't hold it, kept as lamports above
+/// this account's rent until the recipient claims it
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InboundValueClaim {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub recipient: Pubkey,
+    pub lamports: u64,
+    pub bump: u8,
+}
+
+/// One NFTOrigin `verify_migration_integrity` flagged
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IntegrityFailure {
+    pub token_id: u64,
+    pub reason: IntegrityFailureReason,
+}
+
+/// Result of the latest `verify_migration_integrity` call, overwritten by the next one
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MigrationIntegrityReport {
+    pub checked_at: i64,
+    // NFT_ORIGIN_VERSION the origins were checked against
+    pub nft_origin_version: u8,
+    pub total_checked: u32,
+    pub failures: Vec<IntegrityFailure>,
+    pub bump: u8,
+}
+
+/// A delivered inbound message, kept so `replay_inbound` can rebuild the NFTOrigin it wrote.
+/// Written by every delivery; a later delivery of the same bytes overwrites it
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InboundReceipt {
+    pub message_hash: [u8; 32],
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey,
+    pub slot: u64,
+    pub delivered_at: i64,
+    pub bump: u8,
+}
+
+/// A recall an origin-chain contract commanded for one token. The holder can contest it
+/// until dispute_ends_at; after that anyone can execute it. Once contested or executed, the
+/// next recall of the token reuses the account
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ForcedRecall {
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey,
+    pub origin_chain: u64,
+    // Connected contract the gateway delivered the recall from
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
+    pub sender: [u8; 20],
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
+    pub return_receiver: [u8; 20],
+    pub requested_at: i64,
+    pub dispute_ends_at: i64,
+    pub status: u8,
+    // Holder that contested the recall, or whose token account its execution froze
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub holder: Pubkey,
+    pub bump: u8,
+}
+
+/// Two wallets owning an NFT together. The token stays in one holder's account; the record
+/// decides who may send it cross-chain: either owner, and with require_both_signatures
+/// only with the other's CoOwnerConsent
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CoOwnershipRecord {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey,
+    pub token_id: u64,
+    // The holder who created the record, and the wallet they named
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub owner_a: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub owner_b: Pubkey,
+    pub require_both_signatures: bool,
+    // Nonce the next consent is made for; each co-owned transfer uses one up
+    pub consent_nonce: u64,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+/// One owner's approval of the next cross-chain transfer of a co-owned NFT by the other
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CoOwnerConsent {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey,
+    pub nonce: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub approver: Pubkey,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+/// The latest `attest_ownership` of a token, overwritten by the next one, whoever makes it
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnershipAttestation {
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub holder: Pubkey,
+    pub slot: u64,
+    pub attested_at: i64,
+    pub expires_at: i64,
+    pub bump: u8,
+}
+
+/// An appraiser the owner has approved to submit valuations
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AppraisalAuthority {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub appraiser: Pubkey,
+    // Cleared to revoke; the appraiser's submitted appraisals stop counting with it
+    pub active: bool,
+    pub bump: u8,
+}
+
+/// An appraiser's latest valuation of one NFT, for lending and insurance. Each appraiser
+/// keeps one per mint and overwrites it on resubmission
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NFTAppraisal {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey,
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub appraiser: Pubkey,
+    pub value_lamports: u64,
+    pub value_usd_cents: u64,
+    pub appraised_at: i64,
+    pub expires_at: i64,
+    pub methodology: String,
+    pub bump: u8,
+}
+
+impl NFTAppraisal {
+    /// The most recent of `appraisals` that hasn't expired at `now` and whose appraiser is
+    /// still active, the one loan-to-value and insurance readers should value the NFT at.
+    /// Each appraisal comes with its appraiser's AppraisalAuthority
+    pub fn freshest<'a>(
+        appraisals: impl IntoIterator<Item = (&'a NFTAppraisal, &'a AppraisalAuthority)>,
+        now: i64,
+    ) -> Option<&'a NFTAppraisal> {
+        appraisals.into_iter()
+            .filter(|(appraisal, authority)| {
+                authority.active && authority.appraiser == appraisal.appraiser && now < appraisal.expires_at
+            })
+            .map(|(appraisal, _)| appraisal)
+            .max_by_key(|appraisal| appraisal.appraised_at)
+    }
+}
+
+/// Holding `min_balance` of `mint` grants `discount_percent` off the transfer fee
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FeeDiscountTier {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey,
+    pub min_balance: u64,
+    pub discount_percent: u8,
+}
+
+/// Per-chain settings. `block_gas_limit` caps what any single outbound call may request;
+/// `trust_level` decides whether inbound NFTs from the chain are held before delivery;
+/// `supports_batch` says the chain's contract decodes batched transfers
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChainGasConfig {
+    pub chain_id: u64,
+    pub gas_limit: u64,
+    pub block_gas_limit: u64,
+    pub bump: u8,
+    pub trust_level: u8,
+    pub provisional_hold_seconds: u32,
+    pub supports_batch: bool,
+}
+
+/// Tokens of one ZRC-20's gas asset, held in the vault's associated token account, that
+/// pay `gas_fee` into `fee_destination` for each transfer to the ZRC-20's chain
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ZRC20GasVault {
+    pub zrc20: [u8; 20],
+    // SPL mint of the gas asset on Solana; fixed once the vault exists
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub fee_destination: Pubkey,
+    pub gas_fee: u64,
+    pub bump: u8,
+}
+
+/// When `enabled`, departing NFTs leave a frozen receipt token in the sender's wallet
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReceiptConfig {
+    pub enabled: bool,
+    pub uri_base: String,
+    pub bump: u8,
+}
+
+/// Upgrade authority as of the last `transfer_upgrade_authority`, for monitoring.
+/// A transfer made outside the program (e.g. `solana program set-upgrade-authority`)
+/// shows up as a mismatch between `authority` and the program data account.
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UpgradeAuthorityRecord {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub authority: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub previous_authority: Pubkey,
+    pub last_transfer_at: i64,
+    pub bump: u8,
+}
+
+/// While `allowlist_enabled`, crank instructions need a signer holding a `RelayerRole`
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RelayerConfig {
+    pub allowlist_enabled: bool,
+    pub bump: u8,
+}
+
+/// Last heartbeat, overwritten in place for uptime monitoring; `slot` is zero until the first
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HeartbeatRecord {
+    pub slot: u64,
+    pub timestamp: i64,
+    pub outbound_nonce: u64,
+    pub inbound_count: u64,
+    pub paused_flags: u8,
+    // Zero means DEFAULT_HEARTBEAT_INTERVAL_SECONDS
+    pub min_interval_seconds: u32,
+    pub bump: u8,
+}
+
+/// Marks `relayer` as allowed to run cranks while the relayer allowlist is on
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RelayerRole {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub relayer: Pubkey,
+    pub granted_at: i64,
+    pub bump: u8,
+}
+
+/// Holds a token id for one user until `expires_at`; the fee is escrowed in the account
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MintReservation {
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub reserved_by: Pubkey,
+    pub reservation_fee: u64,
+    pub expires_at: i64,
+    pub bump: u8,
+}
+
+/// Hands out raffle ids
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RaffleCounter {
+    pub next_raffle_id: u64,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RaffleStatus {
+    Open,
+    AwaitingRandomness,
+    Settled,
+    // The draw never happened; the NFT went back to the creator and tickets are refunded
+    Cancelled,
+}
+
+/// An NFT raffled for lamport tickets. The NFT sits in the raffle's ATA and ticket
+/// payments accumulate as lamports on this account until the raffle settles.
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Raffle {
+    pub raffle_id: u64,
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub creator: Pubkey,
+    pub ticket_price_lamports: u64,
+    pub max_tickets: u32,
+    pub ticket_count: u32,
+    // Ticket sales close here. Requesting the draw later moves it to the request time, which
+    // RAFFLE_RANDOMNESS_TIMEOUT_SECONDS counts from
+    pub end_time: i64,
+    pub status: RaffleStatus,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub winner: Pubkey,
+    pub bump: u8,
+}
+
+/// Ticket number `index` of a raffle
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RaffleTicket {
+    pub raffle_id: u64,
+    pub index: u32,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub buyer: Pubkey,
+    pub bump: u8,
+}
+
+/// One message of a `batch_receive_cross_chain` call
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InboundMessage {
+    pub token_id: u64,
+    pub message: Vec<u8>,
+}
+
+/// An outbound transfer. The message leaves with the burn, so the sender can cancel it only
+/// once the gateway reports it reverted. Relayers treat a closed account as a cancelled transfer.
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PendingTransfer {
+    pub nonce: u64,
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub sender: Pubkey,
+    pub submitted_at: i64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
+    pub destination: [u8; 20],
+    // Always zero now that cancels wait for the gateway's revert; kept for the layout
+    pub cancellable_until: i64,
+    pub status: u8,
+    pub bump: u8,
+    // UniversalNFTCoreImpl::burn_commitment of the burn; also sent in the message trailer
+    pub burn_commitment: [u8; 32],
+    // Covered by the sender's InsuranceOptIn; paid out automatically if the transfer reverts
+    pub insured: bool,
+    pub insured_payout_lamports: u64,
+    // Units burned; cancel_pending_transfer mints them back
+    pub amount: u64,
+}
+
+/// What `get_escrow_status` found for a token
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EscrowStatus {
+    pub finding: EscrowFinding,
+    pub escrow_amount: u64,
+    // NFTOrigin::release_at; zero without a provisional hold
+    pub release_at: i64,
+    pub has_offer: bool,
+}
+
+/// What `debug_decode` saw; `error_code` is zero when the message decodes.
+/// `uri` is cut to MAX_DEBUG_URI_BYTES, `uri_len` is its full length.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DebugDecodeResult {
+    pub error_code: u32,
+    pub error_name: String,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
+    pub destination: [u8; 20],
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
+    pub receiver: [u8; 20],
+    pub token_id: u64,
+    pub uri: String,
+    pub uri_len: u32,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
+    pub sender: [u8; 20],
+    // Trailers, read as far as decoding got
+    pub has_encryption_key: bool,
+    pub note: Option<String>,
+    pub has_provenance: bool,
+    pub amount: u64,
+    pub priority: u8,
+}
+
+/// KYC gate for minting; the level comes from an identity program's per-user record.
+/// A `required_kyc_level` of zero disables the gate.
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KYCConfig {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub identity_program: Pubkey,
+    pub required_kyc_level: u8,
+    pub enabled: bool,
+    pub bump: u8,
+}
+
+/// Layout of the identity program's record at `[b"identity", user]`, after its discriminator
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KYCRecord {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub user: Pubkey,
+    pub kyc_level: u8,
+}
+
+/// Where ZetaChain's chain registry is read from; set with `set_chain_registry_config`
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChainRegistryConfig {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub observer_program: Pubkey,
+    pub bump: u8,
+}
+
+/// Layout of the observer program's ChainRegistry at `[b"chain_registry"]`, after its
+/// discriminator
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ZetaChainRegistry {
+    pub chains: Vec<ZetaSupportedChain>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ZetaSupportedChain {
+    pub chain_id: u64,
+    pub is_active: bool,
+}
+
+/// Connected contracts in one zero-copy account, sorted by `zrc20` for binary search
+#[account(zero_copy)]
+pub struct Registry {
+    pub count: u32,
+    pub bump: u8,
+    pub _padding: [u8; 3],
+    pub entries: [RegistryEntry; REGISTRY_CAPACITY],
+}
+
+#[zero_copy]
+pub struct RegistryEntry {
+    pub chain_id: u64,
+    pub zrc20: [u8; 20],
+    pub contract: [u8; MAX_REGISTRY_CONTRACT_BYTES],
+    pub contract_len: u8,
+    pub flags: u8,
+    pub _padding: [u8; 2],
+    // Liveness of the connected contract, updated by record_pong; zero until it first answers
+    pub last_seen_at: i64,
+    pub last_pong_hash: [u8; 32],
+    // When validate_chain_id_against_zeta_registry last found chain_id active on ZetaChain;
+    // zero for entries never checked
+    pub last_verified_at: i64,
+}
+
+impl Registry {
+    pub fn entries(&self) -> &[RegistryEntry] {
+        &self.entries[..self.count as usize]
+    }
+
+    /// Index of `zrc20`, or where it would be inserted
+    pub fn search(&self, zrc20: &[u8; 20]) -> std::result::Result<usize, usize> {
+        self.entries().binary_search_by(|entry| entry.zrc20.cmp(zrc20))
+    }
+
+    pub fn find(&self, zrc20: &[u8; 20]) -> Option<&RegistryEntry> {
+        self.search(zrc20).ok().map(|index| &self.entries[index])
+    }
+
+    pub fn find_mut(&mut self, zrc20: &[u8; 20]) -> Option<&mut RegistryEntry> {
+        self.search(zrc20).ok().map(|index| &mut self.entries[index])
+    }
+
+    /// Entry for a chain's gas ZRC-20; entries are sorted by zrc20, so this scans
+    pub fn find_by_chain(&self, chain_id: u64) -> Option<&RegistryEntry> {
+        self.entries().iter().find(|entry| entry.chain_id == chain_id)
+    }
+}
+
+/// The last EVENT_LOG_CAPACITY cross-chain transfers in both directions, in a ring that
+/// clients can poll with plain account reads instead of archival log queries
+#[account(zero_copy)]
+pub struct CrossChainEventLog {
+    // Entries ever appended; slot `total % EVENT_LOG_CAPACITY` is overwritten next
+    pub total: u64,
+    pub head: u16,
+    pub bump: u8,
+    pub _padding: [u8; 5],
+    pub events: [CrossChainEventEntry; EVENT_LOG_CAPACITY],
+}
+
+/// One event log slot; a plain Pod struct rather than `#[zero_copy]` so read_event_log can
+/// return it, and without implicit padding its Borsh encoding matches the account layout
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CrossChainEventEntry {
+    pub token_id: u64,
+    pub timestamp: i64,
+    // Transfer nonce for departures, the inbound sequence number for arrivals
+    pub nonce: u64,
+    // EVM receiver for departures, EVM sender for arrivals
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
+    pub destination: [u8; 20],
+    // EVENT_LOG_TRANSFER_OUT or EVENT_LOG_TRANSFER_IN; zero in a slot never written
+    pub event_type: u8,
+    pub _padding: [u8; 3],
+}
+
+impl CrossChainEventLog {
+    pub fn append(&mut self, entry: CrossChainEventEntry) {
+        self.events[self.head as usize] = entry;
+        self.head = ((self.head as usize + 1) % EVENT_LOG_CAPACITY) as u16;
+        self.total = self.total.saturating_add(1);
+    }
+
+    /// `count` slots starting at `from_index`, wrapping past the end of the ring
+    pub fn read(&self, from_index: u16, count: u8) -> Vec<CrossChainEventEntry> {
+        (0..count as usize)
+            .map(|offset| self.events[(from_index as usize + offset) % EVENT_LOG_CAPACITY])
+            .collect()
+    }
+}
+
+/// Marketplace-facing statistics for a collection
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CollectionStats {
+    pub collection_id: u64,
+    pub floor_price_lamports: u64,
+    pub total_volume_lamports: u64,
+    pub total_sales: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub floor_price_feed: Pubkey,
+    pub last_updated: i64,
+    pub bump: u8,
+    // Pyth feed id the floor price account must hold; zero until set_floor_price_feed sets it
+    pub floor_price_feed_id: [u8; 32],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CollectionStatsSummary {
+    pub collection_id: u64,
+    pub floor_price_lamports: u64,
+    pub total_volume_lamports: u64,
+    pub total_sales: u64,
+    pub last_updated: i64,
+}
+
+/// Collection-level admin delegated by the owner, so a creator can run their own
+/// collection without the global key
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CollectionAuthority {
+    pub collection_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub authority: Pubkey,
+    pub can_mint: bool,
+    pub can_pause: bool,
+    pub can_update_metadata: bool,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CollectionPermissions {
+    pub can_mint: bool,
+    pub can_pause: bool,
+    pub can_update_metadata: bool,
+}
+
+/// Creators entitled to a collection's royalties; shares sum to 100
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SellerFeeConfig {
+    pub collection_id: u64,
+    pub creators: Vec<RoyaltyCreator>,
+    pub bump: u8,
+}
+
+/// Merkle root over a collection's NFTs, for light clients that verify ownership without
+/// trusting an RPC. Leaves are `sha256(token_id LE || mint || metadata_uri || owner)` in
+/// ascending token id order; each node is `sha256(left || right)` and an odd node is carried
+/// up unchanged
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CollectionMerkleRoot {
+    pub collection_id: u64,
+    pub root: [u8; 32],
+    pub token_count: u64,
+    pub computed_at: i64,
+    pub bump: u8,
+}
+
+/// Native mints passing this config get `<uri_base>/<collection_id>/placeholder.json` until the
+/// collection's `RevealSeed` exists and `update_token_uri` gives them their trait URI
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LazyRevealConfig {
+    pub collection_id: u64,
+    pub reveal_time: i64,
+    pub num_traits: u8,
+    pub uri_base: String,
+    // Delivers the VRF result through `fulfill_reveal`
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub vrf_authority: Pubkey,
+    // When `trigger_reveal` last asked for randomness; zero until then
+    pub requested_at: i64,
+    pub bump: u8,
+}
+
+impl LazyRevealConfig {
+    pub fn placeholder_uri(&self) -> String {
+        format!("{}/{}/placeholder.json", self.uri_base, self.collection_id)
+    }
+}
+
+/// VRF output a lazy-reveal collection's traits are derived from; written once
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RevealSeed {
+    pub collection_id: u64,
+    pub seed: [u8; 32],
+    pub fulfilled_at: i64,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RoyaltyCreator {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub address: Pubkey,
+    pub share: u8,
+}
+
+/// Lamports held for a collection's creators, credited per creator at deposit time
+#[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CollectionRoyaltyBalance {
+    pub collection_id: u64,
+    pub owed: Vec<CreatorRoyaltyOwed>,
+    pub total_deposited: u64,
+    pub total_paid: u64,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CreatorRoyaltyOwed {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub creator: Pubkey,
+    pub amount: u64,
+}
+
+// Account validation structs
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = PROGRAM_STATE_SPACE,
+        seeds = [pda::PROGRAM_STATE_SEED], // Use shorter seed to match test
+        bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(
+    uri: String,
+    decimals: u8,
+    token_id: u64,
+    encryption_key: Option<[u8; 32]>,
+    amount: Option<u64>,
+    collection_id: Option<u64>
+)]
+pub struct CreateMintAndNFT<'info> {
+    #[account(
+        mut,
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    
+    // Left out while lazy origins are enabled, with mint_only passed instead
+    #[account(
+        init,
+        payer = payer,
+        space = NFT_ORIGIN_SPACE,
+        seeds = [&pda::nft_origin_seed(token_id)],
+        bump
+    )]
+    pub nft_origin: Option<Box<Account<'info, NFTOrigin>>>,
+    #[account(
+        init,
+        payer = payer,
+        space = NFT_MINT_ONLY_SPACE,
+        seeds = [pda::MINT_ONLY_SEED, &token_id.to_le_bytes()],
+        bump
+    )]
+    pub mint_only: Option<Account<'info, NFTMintOnly>>,
+    
+    #[account(
+        init,
+        payer = payer,
+        mint::decimals = decimals,
+        mint::authority = mint_authority.key(),
+        mint::freeze_authority = mint_authority.key(),
+    )]
+    pub mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = NFT_INDEX_SPACE,
+        seeds = [pda::NFT_INDEX_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub nft_index: Account<'info, NFTIndex>,
+    
+    #[account(
+        init,
+        payer = payer,
+        associated_token::mint = mint,
+        associated_token::authority = mint_authority,
+    )]
+    pub token_account: Account<'info, TokenAccount>,
+    
+    // Writable so a closed reservation can be refunded to it
+    #[account(mut)]
+    pub mint_authority: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+    
+    /// CHECK: Verified by address constraint to the Token Metadata program ID
+    #[account(address = mpl_token_metadata::ID)]
+    pub token_metadata_program: AccountInfo<'info>,
+    
+    /// CHECK: Seeds pin it to the mint's metadata PDA under the Token Metadata program, which
+    /// creates and owns it; only passed through to that program's CPI. Its bump is stored in
+    /// the NFTOrigin for later instructions
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), mint.key().as_ref()],
+        seeds::program = token_metadata_program.key(),
+        bump
+    )]
+    pub metadata: UncheckedAccount<'info>,
     
-    /// CHECK: PDA derived off-chain by the client per Metaplex conventions; only used by CPI
+    /// CHECK: Seeds pin it to the mint's master edition PDA under the Token Metadata program,
+    /// which creates and owns it; only passed through to that program's CPI. Its bump is
+    /// stored in the NFTOrigin for later instructions
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), mint.key().as_ref(), b"edition"],
+        seeds::program = token_metadata_program.key(),
+        bump
+    )]
+    pub master_edition: UncheckedAccount<'info>,
+
+    /// CHECK: MintReservation PDA for token_id, usually empty; checked and closed in the handler
+    #[account(
+        mut,
+        seeds = [pda::RESERVATION_SEED, &token_id.to_le_bytes()],
+        bump
+    )]
+    pub reservation: UncheckedAccount<'info>,
+
+    /// CHECK: KYCConfig PDA, may be uninitialized; read in the handler. When enabled, the
+    /// minter's identity record is passed as a remaining account
+    #[account(
+        seeds = [pda::KYC_CONFIG_SEED],
+        bump
+    )]
+    pub kyc_config: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [pda::FEE_CONFIG_SEED],
+        bump = fee_config.bump
+    )]
+    pub fee_config: Box<Account<'info, FeeConfig>>,
+    /// CHECK: Must match fee_config.treasury, verified in the handler
+    #[account(mut)]
+    pub fee_treasury: Option<UncheckedAccount<'info>>,
+    // The token's collection, whose first creator gets the creator share of the mint fee.
+    // Required whenever collection_id is given, checked in the handler
+    #[account(
+        constraint = Some(seller_fee_config.collection_id) == collection_id @ ErrorCode::SellerFeeConfigMismatch
+    )]
+    pub seller_fee_config: Option<Box<Account<'info, SellerFeeConfig>>>,
+    /// CHECK: Must be seller_fee_config's first creator, verified in the handler
+    #[account(mut)]
+    pub fee_creator: Option<UncheckedAccount<'info>>,
+    /// CHECK: The named collection's LazyRevealConfig PDA, required whenever collection_id is
+    /// given and empty when the collection has no reveal; checked and read in the handler.
+    /// Mints into a lazy-reveal collection get its placeholder URI
+    pub lazy_reveal_config: Option<UncheckedAccount<'info>>,
+    /// CHECK: HourlyMetrics PDA for the current hour, checked in the handler; counting is
+    /// skipped when it is left out or doesn't exist
+    #[account(mut)]
+    pub hourly_metrics: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct SetKYCConfig<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + 32 + 1 + 1 + 1,
+        seeds = [pda::KYC_CONFIG_SEED],
+        bump
+    )]
+    pub kyc_config: Account<'info, KYCConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_id_to_raffle: u64)]
+pub struct CreateRaffle<'info> {
+    #[account(
+        mut,
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        seeds = [&pda::nft_origin_seed(token_id_to_raffle)],
+        bump = nft_origin.bump,
+        constraint = nft_origin.is_current(nft_origin.to_account_info().data_len()) @ ErrorCode::NFTOriginNeedsMigration
+    )]
+    pub nft_origin: Account<'info, NFTOrigin>,
+    #[account(address = nft_origin.mint)]
+    pub mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = creator,
+    )]
+    pub creator_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = creator,
+        space = 8 + 8 + 1, // 8 (discriminator) + 8 (next_raffle_id) + 1 (bump)
+        seeds = [pda::RAFFLE_COUNTER_SEED],
+        bump
+    )]
+    pub raffle_counter: Account<'info, RaffleCounter>,
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + 8 + 8 + 32 + 32 + 8 + 4 + 4 + 8 + 1 + 32 + 1, // 8 (discriminator) + 8 (raffle_id) + 8 (token_id) + 32 (mint) + 32 (creator) + 8 (ticket_price_lamports) + 4 (max_tickets) + 4 (ticket_count) + 8 (end_time) + 1 (status) + 32 (winner) + 1 (bump)
+        seeds = [pda::RAFFLE_SEED, &raffle_counter.next_raffle_id.to_le_bytes()],
+        bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+    #[account(
+        init,
+        payer = creator,
+        associated_token::mint = mint,
+        associated_token::authority = raffle,
+    )]
+    pub raffle_escrow: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(raffle_id: u64)]
+pub struct BuyRaffleTicket<'info> {
+    #[account(
+        mut,
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        mut,
+        seeds = [pda::RAFFLE_SEED, &raffle_id.to_le_bytes()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + 8 + 4 + 32 + 1, // 8 (discriminator) + 8 (raffle_id) + 4 (index) + 32 (buyer) + 1 (bump)
+        seeds = [pda::RAFFLE_TICKET_SEED, &raffle_id.to_le_bytes(), &raffle.ticket_count.to_le_bytes()],
+        bump
+    )]
+    pub ticket: Account<'info, RaffleTicket>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(raffle_id: u64)]
+pub struct RequestWinnerSelection<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        mut,
+        seeds = [pda::RAFFLE_SEED, &raffle_id.to_le_bytes()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+    #[account(mut)]
+    pub requester: Signer<'info>,
+    /// CHECK: External program account; only its pubkey is used to invoke CPI
+    pub gateway_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(raffle_id: u64)]
+pub struct FulfillWinnerSelection<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    // Only the configured gateway may supply the random number
+    #[account(
+        mut,
+        constraint = gateway.key() == program_state.gateway @ ErrorCode::Unauthorized,
+    )]
+    pub gateway: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [pda::RAFFLE_SEED, &raffle_id.to_le_bytes()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+    #[account(address = raffle.mint)]
+    pub mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = raffle,
+    )]
+    pub raffle_escrow: Account<'info, TokenAccount>,
+    #[account(constraint = winning_ticket.raffle_id == raffle_id @ ErrorCode::WinnerIndexMismatch)]
+    pub winning_ticket: Account<'info, RaffleTicket>,
+    /// CHECK: Holder of the winning ticket; only used as the token account owner
+    #[account(address = winning_ticket.buyer)]
+    pub winner: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = gateway,
+        associated_token::mint = mint,
+        associated_token::authority = winner,
+    )]
+    pub winner_token_account: Account<'info, TokenAccount>,
+    /// CHECK: Raffle creator receiving the ticket proceeds
+    #[account(mut, address = raffle.creator)]
+    pub creator: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(raffle_id: u64)]
+pub struct CancelRaffle<'info> {
+    #[account(
+        mut,
+        seeds = [pda::RAFFLE_SEED, &raffle_id.to_le_bytes()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+    #[account(address = raffle.mint)]
+    pub mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = raffle,
+    )]
+    pub raffle_escrow: Account<'info, TokenAccount>,
+    /// CHECK: Raffle creator getting the NFT and the escrow rent back
+    #[account(mut, address = raffle.creator)]
+    pub creator: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint,
+        associated_token::authority = creator,
+    )]
+    pub creator_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(raffle_id: u64, ticket_index: u32)]
+pub struct ClaimRaffleRefund<'info> {
+    #[account(
+        mut,
+        seeds = [pda::RAFFLE_SEED, &raffle_id.to_le_bytes()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+    #[account(
+        mut,
+        close = buyer,
+        seeds = [pda::RAFFLE_TICKET_SEED, &raffle_id.to_le_bytes(), &ticket_index.to_le_bytes()],
+        bump = ticket.bump
+    )]
+    pub ticket: Account<'info, RaffleTicket>,
+    /// CHECK: Ticket holder getting the price and the ticket's rent back
+    #[account(mut, address = ticket.buyer)]
+    pub buyer: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_id: u64)]
+pub struct ReserveTokenId<'info> {
+    #[account(
+        mut,
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        init,
+        payer = user,
+        space = 8 + 8 + 32 + 8 + 8 + 1,
+        seeds = [pda::RESERVATION_SEED, &token_id.to_le_bytes()],
+        bump
+    )]
+    pub reservation: Account<'info, MintReservation>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_id: u64)]
+pub struct CloseExpiredReservation<'info> {
+    #[account(
+        mut,
+        close = reserved_by,
+        seeds = [pda::RESERVATION_SEED, &token_id.to_le_bytes()],
+        bump = reservation.bump
+    )]
+    pub reservation: Account<'info, MintReservation>,
+    /// CHECK: Refund destination, must be the reserving user
+    #[account(mut, address = reservation.reserved_by)]
+    pub reserved_by: UncheckedAccount<'info>,
+    /// CHECK: RelayerConfig PDA; uninitialized reads as an open allowlist
+    #[account(seeds = [pda::RELAYER_CONFIG_SEED], bump)]
+    pub relayer_config: UncheckedAccount<'info>,
+    #[account(
+        seeds = [pda::RELAYER_ROLE_SEED, cranker.key().as_ref()],
+        bump = relayer_role.bump
+    )]
+    pub relayer_role: Option<Account<'info, RelayerRole>>,
+    pub cranker: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_id: u64)]
+pub struct CheckMintAuthority<'info> {
+    #[account(
+        seeds = [&pda::nft_origin_seed(token_id)],
+        bump = nft_origin.bump,
+        constraint = nft_origin.is_current(nft_origin.to_account_info().data_len()) @ ErrorCode::NFTOriginNeedsMigration
+    )]
+    pub nft_origin: Account<'info, NFTOrigin>,
+    /// CHECK: Must be nft_origin.mint; owner and layout are checked by verify_mint_authority
+    #[account(address = nft_origin.mint)]
+    pub mint: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_id: u64, final_chain_id: u64)]
+pub struct CrossChainTransfer<'info> {
+    #[account(
+        mut,
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        mut,
+        seeds = [&pda::nft_origin_seed(token_id)],
+        bump = nft_origin.bump,
+        constraint = nft_origin.is_current(nft_origin.to_account_info().data_len()) @ ErrorCode::NFTOriginNeedsMigration
+    )]
+    pub nft_origin: Account<'info, NFTOrigin>,
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
+    // Held by the caller, or approved to them through SPL `approve`
+    #[account(
+        mut,
+        token::mint = mint,
+        constraint = user_token_account.owner == user.key()
+            || user_token_account.delegate == COption::Some(user.key()) @ ErrorCode::Unauthorized,
+        constraint = user_token_account.owner == user.key()
+            || user_token_account.delegated_amount >= 1 @ ErrorCode::InsufficientDelegatedAmount,
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    /// CHECK: External program account; only its pubkey is used to invoke CPI
+    pub gateway_program: AccountInfo<'info>,
+    pub memo_program: Program<'info, Memo>,
+    // Always passed, so a transfer can't leave the fee out; a zero fee charges nothing
+    #[account(
+        seeds = [pda::FEE_CONFIG_SEED],
+        bump = fee_config.bump
+    )]
+    pub fee_config: Box<Account<'info, FeeConfig>>,
+    /// CHECK: Must match fee_config.treasury, verified in the handler
+    #[account(mut)]
+    pub fee_treasury: UncheckedAccount<'info>,
+    // The destination chain's config; a zero gas_limit falls back to program_state.gas_limit
+    #[account(
+        seeds = [pda::CHAIN_GAS_SEED, &final_chain_id.to_le_bytes()],
+        bump = chain_gas_config.bump
+    )]
+    pub chain_gas_config: Box<Account<'info, ChainGasConfig>>,
+    #[account(
+        seeds = [pda::REGISTRY_SEED],
+        bump = registry.load()?.bump
+    )]
+    pub registry: Option<AccountLoader<'info, Registry>>,
+    #[account(
+        init,
+        payer = user,
+        space = 8 + 8 + 8 + 32 + 8 + 20 + 8 + 1 + 1 + 32 + 1 + 8 + 8, // 8 (discriminator) + 8 (nonce) + 8 (token_id) + 32 (sender) + 8 (submitted_at) + 20 (destination) + 8 (cancellable_until) + 1 (status) + 1 (bump) + 32 (burn_commitment) + 1 (insured) + 8 (insured_payout_lamports) + 8 (amount)
+        seeds = [pda::PENDING_TRANSFER_SEED, &program_state.transfer_nonce.to_le_bytes()],
+        bump
+    )]
+    pub pending_transfer: Account<'info, PendingTransfer>,
+    #[account(
+        seeds = [pda::RECEIPT_CONFIG_SEED],
+        bump = receipt_config.bump
+    )]
+    pub receipt_config: Option<Account<'info, ReceiptConfig>>,
+    // One receipt mint per token id, re-used each time the token departs
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [pda::RECEIPT_MINT_SEED, &token_id.to_le_bytes()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = mint_authority,
+        mint::freeze_authority = mint_authority,
+    )]
+    pub receipt_mint: Option<Account<'info, Mint>>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = user,
+    )]
+    pub receipt_token_account: Option<Account<'info, TokenAccount>>,
+    // Pays the destination chain's gas when it holds enough; matched to the destination
+    // ZRC-20 in the handler, which is only known once the registry is read
+    pub zrc20_gas_vault: Option<Box<Account<'info, ZRC20GasVault>>>,
+    #[account(mut)]
+    pub zrc20_vault_token_account: Option<Box<Account<'info, TokenAccount>>>,
+    /// CHECK: Must match zrc20_gas_vault.fee_destination, verified in the handler
+    #[account(mut)]
+    pub gas_fee_destination: Option<UncheckedAccount<'info>>,
+    /// CHECK: Program PDA that signs as the bridged mint authority; holds no data
+    #[account(
+        seeds = [pda::MINT_AUTHORITY_SEED],
+        bump
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+    /// CHECK: ChainStats PDA for the NFT's origin chain; stats are skipped when it doesn't exist
+    #[account(
+        mut,
+        seeds = [pda::CHAIN_STATS_SEED, &nft_origin.origin_chain.to_le_bytes()],
+        bump
+    )]
+    pub chain_stats: UncheckedAccount<'info>,
+    /// CHECK: CrossChainEventLog PDA; nothing is logged until init_event_log creates it
+    #[account(
+        mut,
+        seeds = [pda::EVENT_LOG_SEED],
+        bump
+    )]
+    pub event_log: UncheckedAccount<'info>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: HourlyMetrics PDA for the current hour, checked in the handler; counting is
+    /// skipped when it is left out or doesn't exist
+    #[account(mut)]
+    pub hourly_metrics: Option<UncheckedAccount<'info>>,
+    // Cover bought with opt_in_transfer_insurance; this transfer uses it up
+    #[account(
+        mut,
+        seeds = [pda::INSURANCE_OPT_IN_SEED, user.key().as_ref()],
+        bump = insurance_opt_in.bump
+    )]
+    pub insurance_opt_in: Option<Box<Account<'info, InsuranceOptIn>>>,
+    // Required when nft_origin.co_owned
+    #[account(
+        mut,
+        seeds = [pda::CO_OWNERSHIP_SEED, mint.key().as_ref()],
+        bump = co_ownership.bump
+    )]
+    pub co_ownership: Option<Box<Account<'info, CoOwnershipRecord>>>,
+    // The other owner's consent for co_ownership.consent_nonce, checked in the handler
+    pub co_owner_consent: Option<Box<Account<'info, CoOwnerConsent>>>,
+}
+
+// The NFTs themselves come as remaining accounts, BATCH_TRANSFER_ACCOUNTS per token id
+#[derive(Accounts)]
+#[instruction(token_ids: Vec<u64>, receiver: Vec<u8>, destination_chain_id: u64)]
+pub struct TransferCrossChainBatch<'info> {
+    #[account(
+        mut,
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        seeds = [pda::CHAIN_GAS_SEED, &destination_chain_id.to_le_bytes()],
+        bump = chain_gas_config.bump
+    )]
+    pub chain_gas_config: Account<'info, ChainGasConfig>,
+    #[account(
+        seeds = [pda::REGISTRY_SEED],
+        bump = registry.load()?.bump
+    )]
+    pub registry: Option<AccountLoader<'info, Registry>>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    /// CHECK: External program account; only its pubkey is used to invoke CPI
+    pub gateway_program: AccountInfo<'info>,
+    // Each NFT in the batch pays the transfer fee, as it would on its own
+    #[account(
+        seeds = [pda::FEE_CONFIG_SEED],
+        bump = fee_config.bump
+    )]
+    pub fee_config: Box<Account<'info, FeeConfig>>,
+    /// CHECK: Must match fee_config.treasury, verified in the handler
+    #[account(mut)]
+    pub fee_treasury: UncheckedAccount<'info>,
+    /// CHECK: The user's token account for a fee discount tier, read by resolve_fee_discount;
+    /// anything else earns no discount
+    pub fee_discount_token_account: Option<UncheckedAccount<'info>>,
+    /// CHECK: CrossChainEventLog PDA; nothing is logged until init_event_log creates it
+    #[account(
+        mut,
+        seeds = [pda::EVENT_LOG_SEED],
+        bump
+    )]
+    pub event_log: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: HourlyMetrics PDA for the current hour, checked in the handler; counting is
+    /// skipped when it is left out or doesn't exist
+    #[account(mut)]
+    pub hourly_metrics: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct BatchReceive<'info> {
+    #[account(
+        mut,
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    /// CHECK: Program PDA that signs as the bridged mint authority; holds no data
+    #[account(
+        seeds = [pda::MINT_AUTHORITY_SEED],
+        bump
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+    // Every inbound NFT arrives from ZetaChain; an empty (trusted) config delivers at once
+    #[account(
+        seeds = [pda::CHAIN_GAS_SEED, &CHAIN_ID_ZETACHAIN_TESTNET.to_le_bytes()],
+        bump = source_chain_config.bump
+    )]
+    pub source_chain_config: Box<Account<'info, ChainGasConfig>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = CHAIN_STATS_SPACE,
+        seeds = [pda::CHAIN_STATS_SEED, &CHAIN_ID_ZETACHAIN_TESTNET.to_le_bytes()],
+        bump
+    )]
+    pub chain_stats: Account<'info, ChainStats>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    // Batches are deliveries too; only the gateway may hand them over
+    #[account(address = program_state.gateway @ ErrorCode::Unauthorized)]
+    pub gateway: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_id: u64)]
+pub struct GetBurnCommitment<'info> {
+    #[account(constraint = pending_transfer.token_id == token_id @ ErrorCode::PendingTransferTokenMismatch)]
+    pub pending_transfer: Account<'info, PendingTransfer>,
+}
+
+#[derive(Accounts)]
+#[instruction(nonce: u64)]
+pub struct CancelPendingTransfer<'info> {
+    #[account(
+        mut,
+        close = sender,
+        has_one = sender,
+        seeds = [pda::PENDING_TRANSFER_SEED, &nonce.to_le_bytes()],
+        bump = pending_transfer.bump
+    )]
+    pub pending_transfer: Account<'info, PendingTransfer>,
+    #[account(
+        mut,
+        seeds = [&pda::nft_origin_seed(pending_transfer.token_id)],
+        bump = nft_origin.bump,
+        constraint = nft_origin.is_current(nft_origin.to_account_info().data_len()) @ ErrorCode::NFTOriginNeedsMigration
+    )]
+    pub nft_origin: Account<'info, NFTOrigin>,
+    #[account(mut, address = nft_origin.mint)]
+    pub mint: Account<'info, Mint>,
+    /// CHECK: Program PDA that signs as the bridged mint authority; holds no data
+    #[account(
+        seeds = [pda::MINT_AUTHORITY_SEED],
+        bump
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = sender,
+    )]
+    pub sender_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    // Required while nft_origin.receipt_outstanding is set
+    #[account(
+        mut,
+        seeds = [pda::RECEIPT_MINT_SEED, &pending_transfer.token_id.to_le_bytes()],
+        bump
+    )]
+    pub receipt_mint: Option<Account<'info, Mint>>,
+    #[account(
+        mut,
+        token::mint = receipt_mint,
+        token::authority = sender,
+    )]
+    pub receipt_token_account: Option<Account<'info, TokenAccount>>,
+    /// CHECK: ChainStats PDA for the NFT's origin chain; stats are skipped when it doesn't exist
+    #[account(
+        mut,
+        seeds = [pda::CHAIN_STATS_SEED, &nft_origin.origin_chain.to_le_bytes()],
+        bump
+    )]
+    pub chain_stats: UncheckedAccount<'info>,
+    // Natively created mints answer to their creator, who co-signs to restore the token
+    pub native_mint_authority: Option<Signer<'info>>,
+    pub token_program: Program<'info, Token>,
+}
+
+// Every token-keyed account is derived from the token id inside `message`; there is no
+// separate token id argument to disagree with it
+#[derive(Accounts)]
+#[instruction(message: Vec<u8>)]
+pub struct ReceiveCrossChainMessage<'info> {
+    #[account(
+        mut,
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    // Re-used when a token returns to Solana; its departed_amount check in the handler prevents replays
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = NFT_ORIGIN_SPACE,
+        seeds = [&pda::nft_origin_seed(inbound_token_id(&message))],
+        bump,
+        constraint = nft_origin.is_current_or_new(nft_origin.to_account_info().data_len()) @ ErrorCode::NFTOriginNeedsMigration
+    )]
+    pub nft_origin: Account<'info, NFTOrigin>,
+    // Bridged mints are PDAs so the same foreign NFT always lands at the same address
+    #[account(
+        init_if_needed,
+        payer = payer,
+        seeds = [pda::BRIDGED_MINT_SEED, &CHAIN_ID_ZETACHAIN_TESTNET.to_le_bytes(), &inbound_token_seed(&message)],
+        bump,
+        mint::decimals = 0,
+        mint::authority = mint_authority,
+        mint::freeze_authority = mint_authority,
+    )]
+    pub mint: Account<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = NFT_INDEX_SPACE,
+        seeds = [pda::NFT_INDEX_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub nft_index: Account<'info, NFTIndex>,
+    /// CHECK: Program PDA that signs as the bridged mint authority; holds no data
+    #[account(
+        seeds = [pda::MINT_AUTHORITY_SEED],
+        bump
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+    /// CHECK: Wallet receiving the bridged NFT, as the token account owner, and any value
+    /// sent with it
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+    /// CHECK: Recipient's ATA for the mint, checked and created in the handler; unused when
+    /// a custodial binding redirects the delivery
+    #[account(mut)]
+    pub recipient_token_account: UncheckedAccount<'info>,
+    /// CHECK: Recipient's ReceiverBinding PDA; uninitialized means delivery to the ATA
+    #[account(
+        seeds = [pda::RECEIVER_BINDING_SEED, recipient.key().as_ref()],
+        bump
+    )]
+    pub receiver_binding: UncheckedAccount<'info>,
+    /// CHECK: CrossChainEventLog PDA; nothing is logged until init_event_log creates it
+    #[account(
+        mut,
+        seeds = [pda::EVENT_LOG_SEED],
+        bump
+    )]
+    pub event_log: UncheckedAccount<'info>,
+    // Required while program_state.require_nft_acceptance is set, except for custodial
+    // and held deliveries
+    #[account(
+        init,
+        payer = payer,
+        space = INBOUND_OFFER_SPACE,
+        seeds = [pda::INBOUND_OFFER_SEED, &inbound_token_seed(&message), recipient.key().as_ref()],
+        bump
+    )]
+    pub inbound_offer: Option<Box<Account<'info, InboundNFTOffer>>>,
+    // Required when receiver_binding is custodial
+    #[account(mut)]
+    pub custodial_token_account: Option<Account<'info, TokenAccount>>,
+    pub memo_program: Program<'info, Memo>,
+    // Every inbound NFT arrives from ZetaChain; an empty (trusted) config delivers at once
+    #[account(
+        seeds = [pda::CHAIN_GAS_SEED, &CHAIN_ID_ZETACHAIN_TESTNET.to_le_bytes()],
+        bump = source_chain_config.bump
+    )]
+    pub source_chain_config: Box<Account<'info, ChainGasConfig>>,
+    // Inbound NFTs are recorded with ZetaChain as their origin chain
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = CHAIN_STATS_SPACE,
+        seeds = [pda::CHAIN_STATS_SEED, &CHAIN_ID_ZETACHAIN_TESTNET.to_le_bytes()],
+        bump
+    )]
+    pub chain_stats: Account<'info, ChainStats>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = DEDUPLICATION_WINDOW_SPACE,
+        seeds = [pda::DEDUP_WINDOW_SEED, &inbound_uri_hash(&message)],
+        bump
+    )]
+    pub dedup_window: Account<'info, DeduplicationWindow>,
+    /// CHECK: Mint authority's ATA for the mint, created in the handler when a delivery is held
+    #[account(mut)]
+    pub provisional_escrow: Option<UncheckedAccount<'info>>,
+    // Required by execute_inbound, which closes it into the executor; never passed to
+    // receive_cross_chain_message
+    #[account(
+        mut,
+        close = payer,
+        seeds = [pda::PENDING_INBOUND_SEED, &inbound_message_hash(&message)],
+        bump = pending_inbound.bump
+    )]
+    pub pending_inbound: Option<Box<Account<'info, PendingInbound>>>,
+    // Required while nft_origin.receipt_outstanding is set; only the receipt holder gets the NFT back
+    #[account(
+        mut,
+        seeds = [pda::RECEIPT_MINT_SEED, &inbound_token_seed(&message)],
+        bump
+    )]
+    pub receipt_mint: Option<Account<'info, Mint>>,
+    #[account(
+        mut,
+        token::mint = receipt_mint,
+        token::authority = recipient,
+    )]
+    pub receipt_token_account: Option<Account<'info, TokenAccount>>,
+    // Only execute_inbound checks the allowlist; direct deliveries are gateway-signed
+    /// CHECK: RelayerConfig PDA; uninitialized reads as an open allowlist
+    #[account(seeds = [pda::RELAYER_CONFIG_SEED], bump)]
+    pub relayer_config: UncheckedAccount<'info>,
+    #[account(
+        seeds = [pda::RELAYER_ROLE_SEED, payer.key().as_ref()],
+        bump = relayer_role.bump
+    )]
+    pub relayer_role: Option<Account<'info, RelayerRole>>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    // Signs every direct delivery; execute_inbound runs a delivery the gateway already
+    // queued and leaves it out
+    #[account(address = program_state.gateway @ ErrorCode::Unauthorized)]
+    pub gateway: Option<Signer<'info>>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub rent: Sysvar<'info, Rent>,
+    /// CHECK: HourlyMetrics PDA for the current hour, checked in the handler; counting is
+    /// skipped when it is left out or doesn't exist
+    #[account(mut)]
+    pub hourly_metrics: Option<UncheckedAccount<'info>>,
+    /// CHECK: Recipient's InboundValueClaim PDA, checked and created in the handler; needed
+    /// only when value arrives for a recipient that can't hold it
+    #[account(mut)]
+    pub inbound_value_claim: Option<UncheckedAccount<'info>>,
+    // Recorded for replay_inbound on every delivery
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = INBOUND_RECEIPT_SPACE,
+        seeds = [pda::INBOUND_RECEIPT_SEED, &inbound_message_hash(&message)],
+        bump
+    )]
+    pub inbound_receipt: Box<Account<'info, InboundReceipt>>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimInboundValue<'info> {
+    #[account(
+        mut,
+        close = destination,
+        seeds = [pda::INBOUND_VALUE_CLAIM_SEED, recipient.key().as_ref()],
+        bump = inbound_value_claim.bump
+    )]
+    pub inbound_value_claim: Account<'info, InboundValueClaim>,
+    pub recipient: Signer<'info>,
+    /// CHECK: Any account the recipient names; receives the parked value and the claim's rent
+    #[account(mut)]
+    pub destination: UncheckedAccount<'info>,
+}
+
+// The PendingInbound accounts to order are remaining accounts
+#[derive(Accounts)]
+pub struct ListPendingInbound<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+}
+
+#[derive(Accounts)]
+#[instruction(message: Vec<u8>, memo: Option<String>)]
+pub struct QueueInbound<'info> {
+    // Mutable for require_not_paused's scheduled unpause
+    #[account(
+        mut,
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        init,
+        payer = payer,
+        space = PENDING_INBOUND_SPACE + message.len() + memo.as_ref().map_or(0, String::len),
+        seeds = [pda::PENDING_INBOUND_SEED, &inbound_message_hash(&message)],
+        bump
+    )]
+    pub pending_inbound: Account<'info, PendingInbound>,
+    // Queued messages are deliveries too; only the gateway may hand them over
+    #[account(address = program_state.gateway @ ErrorCode::Unauthorized)]
+    pub gateway: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetDeliveryMode<'info> {
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = RECEIVER_BINDING_SPACE,
+        seeds = [pda::RECEIVER_BINDING_SEED, owner.key().as_ref()],
+        bump
+    )]
+    pub receiver_binding: Account<'info, ReceiverBinding>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_id: u64)]
+pub struct ReleaseProvisional<'info> {
+    #[account(
+        mut,
+        seeds = [&pda::nft_origin_seed(token_id)],
+        bump = nft_origin.bump,
+        constraint = nft_origin.is_current(nft_origin.to_account_info().data_len()) @ ErrorCode::NFTOriginNeedsMigration
+    )]
+    pub nft_origin: Account<'info, NFTOrigin>,
+    #[account(address = nft_origin.mint)]
+    pub mint: Account<'info, Mint>,
+    /// CHECK: Program PDA that owns the escrow; holds no data
+    #[account(
+        seeds = [pda::MINT_AUTHORITY_SEED],
+        bump
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = mint_authority,
+    )]
+    pub provisional_escrow: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        token::mint = mint,
+        constraint = recipient_token_account.owner == nft_origin.provisional_recipient
+            @ ErrorCode::ProvisionalRecipientMismatch,
+    )]
+    pub recipient_token_account: Account<'info, TokenAccount>,
+    /// CHECK: RelayerConfig PDA; uninitialized reads as an open allowlist
+    #[account(seeds = [pda::RELAYER_CONFIG_SEED], bump)]
+    pub relayer_config: UncheckedAccount<'info>,
+    #[account(
+        seeds = [pda::RELAYER_ROLE_SEED, cranker.key().as_ref()],
+        bump = relayer_role.bump
+    )]
+    pub relayer_role: Option<Account<'info, RelayerRole>>,
+    pub cranker: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_id: u64)]
+pub struct RevokeProvisional<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        mut,
+        seeds = [&pda::nft_origin_seed(token_id)],
+        bump = nft_origin.bump,
+        constraint = nft_origin.is_current(nft_origin.to_account_info().data_len()) @ ErrorCode::NFTOriginNeedsMigration
+    )]
+    pub nft_origin: Account<'info, NFTOrigin>,
+    #[account(mut, address = nft_origin.mint)]
+    pub mint: Account<'info, Mint>,
+    /// CHECK: Program PDA that owns the escrow; holds no data
+    #[account(
+        seeds = [pda::MINT_AUTHORITY_SEED],
+        bump
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = mint_authority,
+    )]
+    pub provisional_escrow: Account<'info, TokenAccount>,
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_id: u64)]
+pub struct ReconcileEscrow<'info> {
+    #[account(
+        mut,
+        seeds = [&pda::nft_origin_seed(token_id)],
+        bump = nft_origin.bump,
+        constraint = nft_origin.is_current(nft_origin.to_account_info().data_len()) @ ErrorCode::NFTOriginNeedsMigration
+    )]
+    pub nft_origin: Account<'info, NFTOrigin>,
+    /// CHECK: Program PDA that owns the escrow; holds no data
+    #[account(
+        seeds = [pda::MINT_AUTHORITY_SEED],
+        bump
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+    /// CHECK: The mint authority's ATA for the token's mint; reads as empty when it doesn't exist
+    #[account(address = anchor_spl::associated_token::get_associated_token_address(&mint_authority.key(), &nft_origin.mint))]
+    pub escrow: UncheckedAccount<'info>,
+    // The token's open offer, if it has one; matched to the token in the handler
+    pub inbound_offer: Option<Account<'info, InboundNFTOffer>>,
+    /// CHECK: RelayerConfig PDA; uninitialized reads as an open allowlist
+    #[account(seeds = [pda::RELAYER_CONFIG_SEED], bump)]
+    pub relayer_config: UncheckedAccount<'info>,
+    #[account(
+        seeds = [pda::RELAYER_ROLE_SEED, cranker.key().as_ref()],
+        bump = relayer_role.bump
+    )]
+    pub relayer_role: Option<Account<'info, RelayerRole>>,
+    pub cranker: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_id: u64)]
+pub struct GetEscrowStatus<'info> {
+    #[account(
+        seeds = [&pda::nft_origin_seed(token_id)],
+        bump = nft_origin.bump,
+        constraint = nft_origin.is_current(nft_origin.to_account_info().data_len()) @ ErrorCode::NFTOriginNeedsMigration
+    )]
+    pub nft_origin: Account<'info, NFTOrigin>,
+    /// CHECK: Program PDA that owns the escrow; holds no data
+    #[account(
+        seeds = [pda::MINT_AUTHORITY_SEED],
+        bump
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+    /// CHECK: The mint authority's ATA for the token's mint; reads as empty when it doesn't exist
+    #[account(address = anchor_spl::associated_token::get_associated_token_address(&mint_authority.key(), &nft_origin.mint))]
+    pub escrow: UncheckedAccount<'info>,
+    pub inbound_offer: Option<Account<'info, InboundNFTOffer>>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_id: u64)]
+pub struct AcceptInboundNFT<'info> {
+    #[account(
+        mut,
+        close = payer,
+        seeds = [pda::INBOUND_OFFER_SEED, &token_id.to_le_bytes(), recipient.key().as_ref()],
+        bump = inbound_offer.bump
+    )]
+    pub inbound_offer: Account<'info, InboundNFTOffer>,
+    #[account(address = inbound_offer.mint)]
+    pub mint: Account<'info, Mint>,
+    /// CHECK: Program PDA that owns the escrow; holds no data
+    #[account(
+        seeds = [pda::MINT_AUTHORITY_SEED],
+        bump
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = mint_authority,
+    )]
+    pub escrow: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = recipient,
+        associated_token::mint = mint,
+        associated_token::authority = recipient,
+    )]
+    pub recipient_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+    /// CHECK: Gets the offer's rent back
+    #[account(mut, address = inbound_offer.payer)]
+    pub payer: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_id: u64)]
+pub struct RejectInboundNFT<'info> {
+    // Mutable for require_not_paused's scheduled unpause
+    #[account(
+        mut,
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        mut,
+        seeds = [&pda::nft_origin_seed(token_id)],
+        bump = nft_origin.bump,
+        constraint = nft_origin.is_current(nft_origin.to_account_info().data_len()) @ ErrorCode::NFTOriginNeedsMigration
+    )]
+    pub nft_origin: Account<'info, NFTOrigin>,
+    #[account(
+        mut,
+        close = payer,
+        seeds = [pda::INBOUND_OFFER_SEED, &token_id.to_le_bytes(), inbound_offer.recipient.as_ref()],
+        bump = inbound_offer.bump
+    )]
+    pub inbound_offer: Account<'info, InboundNFTOffer>,
+    #[account(mut, address = inbound_offer.mint)]
+    pub mint: Account<'info, Mint>,
+    /// CHECK: Program PDA that owns the escrow; holds no data
+    #[account(
+        seeds = [pda::MINT_AUTHORITY_SEED],
+        bump
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = mint_authority,
+    )]
+    pub escrow: Account<'info, TokenAccount>,
+    // The recipient, or anyone once the offer has expired
+    pub authority: Signer<'info>,
+    /// CHECK: Gets the offer's rent back
+    #[account(mut, address = inbound_offer.payer)]
+    pub payer: UncheckedAccount<'info>,
+    /// CHECK: External program account; only its pubkey is used to invoke CPI. Required
+    /// to return the NFT to its sender
+    pub gateway_program: Option<UncheckedAccount<'info>>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_id: u64)]
+pub struct RetryMetadata<'info> {
+    #[account(
+        mut,
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        mut,
+        seeds = [&pda::nft_origin_seed(token_id)],
+        bump = nft_origin.bump,
+        constraint = nft_origin.is_current(nft_origin.to_account_info().data_len()) @ ErrorCode::NFTOriginNeedsMigration
+    )]
+    pub nft_origin: Account<'info, NFTOrigin>,
+    #[account(address = nft_origin.mint)]
+    pub mint: Account<'info, Mint>,
+    /// CHECK: Program PDA; signs the metadata CPI as mint and update authority
+    #[account(
+        seeds = [pda::MINT_AUTHORITY_SEED],
+        bump
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+    /// CHECK: Verified by address constraint to the Token Metadata program ID
+    #[account(address = mpl_token_metadata::ID)]
+    pub token_metadata_program: AccountInfo<'info>,
+    /// CHECK: Seeds with the bump stored in the NFTOrigin pin it to the mint's metadata PDA
+    /// under the Token Metadata program; only passed through to that program's CPI. Origins
+    /// written before NFT_ORIGIN_VERSION 9 need migrate_nft_origin first
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), mint.key().as_ref()],
+        seeds::program = token_metadata_program.key(),
+        bump = nft_origin.metadata_bump
+    )]
+    pub metadata: UncheckedAccount<'info>,
+    /// CHECK: Update authority the origin's policy retains, checked against it in the
+    /// handler; left out when it is the mint authority PDA
+    pub update_authority: Option<UncheckedAccount<'info>>,
+    /// CHECK: RelayerConfig PDA; uninitialized reads as an open allowlist
+    #[account(seeds = [pda::RELAYER_CONFIG_SEED], bump)]
+    pub relayer_config: UncheckedAccount<'info>,
+    #[account(
+        seeds = [pda::RELAYER_ROLE_SEED, payer.key().as_ref()],
+        bump = relayer_role.bump
+    )]
+    pub relayer_role: Option<Account<'info, RelayerRole>>,
+    // Anyone; pays for the metadata account
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetRecoveryCutoff<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = RECOVERY_CONFIG_SPACE,
+        seeds = [pda::RECOVERY_CONFIG_SEED],
+        bump
+    )]
+    pub recovery_config: Account<'info, RecoveryConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_id: u64, correct_recipient: Pubkey)]
+pub struct RecoverMisdelivered<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        seeds = [pda::RECOVERY_CONFIG_SEED],
+        bump = recovery_config.bump
+    )]
+    pub recovery_config: Account<'info, RecoveryConfig>,
+    #[account(
+        mut,
+        seeds = [&pda::nft_origin_seed(token_id)],
+        bump = nft_origin.bump,
+        constraint = nft_origin.is_current(nft_origin.to_account_info().data_len()) @ ErrorCode::NFTOriginNeedsMigration
+    )]
+    pub nft_origin: Account<'info, NFTOrigin>,
+    #[account(mut, address = nft_origin.mint)]
+    pub mint: Account<'info, Mint>,
+    #[account(mut, token::mint = mint)]
+    pub stranded_token_account: Account<'info, TokenAccount>,
+    /// CHECK: Wallet the NFT should have gone to; only used as the token account owner
+    #[account(address = correct_recipient)]
+    pub recipient: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        associated_token::mint = mint,
+        associated_token::authority = recipient,
+    )]
+    pub recipient_token_account: Account<'info, TokenAccount>,
+    // `init` makes a second recovery of the same token fail
+    #[account(
+        init,
+        payer = admin,
+        space = MISDELIVERY_RECOVERY_SPACE,
+        seeds = [pda::MISDELIVERY_RECOVERY_SEED, &token_id.to_le_bytes()],
+        bump
+    )]
+    pub misdelivery_recovery: Account<'info, MisdeliveryRecovery>,
+    /// CHECK: Program PDA that signs as the bridged mint and freeze authority; holds no data
+    #[account(
+        seeds = [pda::MINT_AUTHORITY_SEED],
+        bump
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitEventLog<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + std::mem::size_of::<CrossChainEventLog>(),
+        seeds = [pda::EVENT_LOG_SEED],
+        bump
+    )]
+    pub event_log: AccountLoader<'info, CrossChainEventLog>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReadEventLog<'info> {
+    #[account(
+        seeds = [pda::EVENT_LOG_SEED],
+        bump = event_log.load()?.bump
+    )]
+    pub event_log: AccountLoader<'info, CrossChainEventLog>,
+}
+
+#[derive(Accounts)]
+pub struct InitRegistry<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + std::mem::size_of::<Registry>(),
+        seeds = [pda::REGISTRY_SEED],
+        bump
+    )]
+    pub registry: AccountLoader<'info, Registry>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateRegistry<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        mut,
+        seeds = [pda::REGISTRY_SEED],
+        bump = registry.load()?.bump
+    )]
+    pub registry: AccountLoader<'info, Registry>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetChainRegistryConfig<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + 32 + 1,
+        seeds = [pda::CHAIN_REGISTRY_CONFIG_SEED],
+        bump
+    )]
+    pub chain_registry_config: Account<'info, ChainRegistryConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ValidateChain<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        mut,
+        seeds = [pda::REGISTRY_SEED],
+        bump = registry.load()?.bump
+    )]
+    pub registry: AccountLoader<'info, Registry>,
+    #[account(
+        seeds = [pda::CHAIN_REGISTRY_CONFIG_SEED],
+        bump = chain_registry_config.bump
+    )]
+    pub chain_registry_config: Account<'info, ChainRegistryConfig>,
+    /// CHECK: ZetaChain's ChainRegistry; its address and owner are checked against
+    /// chain_registry_config in the handler
+    pub zeta_chain_registry: UncheckedAccount<'info>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct LookupConnectedContract<'info> {
+    #[account(
+        seeds = [pda::REGISTRY_SEED],
+        bump = registry.load()?.bump
+    )]
+    pub registry: AccountLoader<'info, Registry>,
+}
+
+/// Chain config and origin policy pages take their target PDAs as remaining accounts, in
+/// entry order; missing ones are created at the admin's expense
+#[derive(Accounts)]
+pub struct ImportConfig<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        mut,
+        seeds = [pda::REGISTRY_SEED],
+        bump = registry.load()?.bump
+    )]
+    pub registry: AccountLoader<'info, Registry>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PingConnectedContract<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        seeds = [pda::REGISTRY_SEED],
+        bump = registry.load()?.bump
+    )]
+    pub registry: AccountLoader<'info, Registry>,
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    /// CHECK: RelayerConfig PDA; uninitialized reads as an open allowlist
+    #[account(seeds = [pda::RELAYER_CONFIG_SEED], bump)]
+    pub relayer_config: UncheckedAccount<'info>,
+    #[account(
+        seeds = [pda::RELAYER_ROLE_SEED, sender.key().as_ref()],
+        bump = relayer_role.bump
+    )]
+    pub relayer_role: Option<Account<'info, RelayerRole>>,
+    /// CHECK: External program account; only its pubkey is used to invoke CPI
+    pub gateway_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RecordPong<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        mut,
+        seeds = [pda::REGISTRY_SEED],
+        bump = registry.load()?.bump
+    )]
+    pub registry: AccountLoader<'info, Registry>,
+    // Pongs come back through the gateway; nobody else may vouch for a contract
+    #[account(address = program_state.gateway @ ErrorCode::Unauthorized)]
+    pub gateway: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GetSchema<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_id: u64)]
+pub struct ReceiveForcedRecall<'info> {
+    // Mutable for require_not_paused's scheduled unpause
+    #[account(
+        mut,
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        seeds = [pda::REGISTRY_SEED],
+        bump = registry.load()?.bump
+    )]
+    pub registry: AccountLoader<'info, Registry>,
+    #[account(
+        seeds = [&pda::nft_origin_seed(token_id)],
+        bump = nft_origin.bump,
+        constraint = nft_origin.is_current(nft_origin.to_account_info().data_len()) @ ErrorCode::NFTOriginNeedsMigration
+    )]
+    pub nft_origin: Account<'info, NFTOrigin>,
+    #[account(
+        init_if_needed,
+        payer = gateway,
+        space = FORCED_RECALL_SPACE,
+        seeds = [pda::FORCED_RECALL_SEED, &token_id.to_le_bytes()],
+        bump
+    )]
+    pub forced_recall: Account<'info, ForcedRecall>,
+    // Recalls come in through the gateway, which vouches for their sender
+    #[account(mut, address = program_state.gateway @ ErrorCode::Unauthorized)]
+    pub gateway: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_id: u64)]
+pub struct ContestRecall<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        mut,
+        seeds = [pda::FORCED_RECALL_SEED, &token_id.to_le_bytes()],
+        bump = forced_recall.bump
+    )]
+    pub forced_recall: Account<'info, ForcedRecall>,
+    #[account(
+        token::mint = forced_recall.mint,
+        token::authority = holder,
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+    pub holder: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_id: u64)]
+pub struct ExecuteForcedRecall<'info> {
+    #[account(
+        mut,
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        mut,
+        seeds = [pda::FORCED_RECALL_SEED, &token_id.to_le_bytes()],
+        bump = forced_recall.bump
+    )]
+    pub forced_recall: Account<'info, ForcedRecall>,
+    #[account(
+        mut,
+        seeds = [&pda::nft_origin_seed(token_id)],
+        bump = nft_origin.bump,
+        constraint = nft_origin.is_current(nft_origin.to_account_info().data_len()) @ ErrorCode::NFTOriginNeedsMigration
+    )]
+    pub nft_origin: Account<'info, NFTOrigin>,
+    #[account(mut, address = nft_origin.mint)]
+    pub mint: Account<'info, Mint>,
+    // Whoever holds the token now; frozen in place, since SPL Token can't move it without them
+    #[account(mut, token::mint = mint)]
+    pub holder_token_account: Account<'info, TokenAccount>,
+    /// CHECK: Program PDA that signs as the bridged mint and freeze authority; holds no data
+    #[account(
+        seeds = [pda::MINT_AUTHORITY_SEED],
+        bump
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+    #[account(
+        seeds = [pda::REGISTRY_SEED],
+        bump = registry.load()?.bump
+    )]
+    pub registry: AccountLoader<'info, Registry>,
+    /// CHECK: External program account; only its pubkey is used to invoke CPI
+    pub gateway_program: AccountInfo<'info>,
+    /// CHECK: RelayerConfig PDA; uninitialized reads as an open allowlist
+    #[account(seeds = [pda::RELAYER_CONFIG_SEED], bump)]
+    pub relayer_config: UncheckedAccount<'info>,
+    #[account(
+        seeds = [pda::RELAYER_ROLE_SEED, payer.key().as_ref()],
+        bump = relayer_role.bump
+    )]
+    pub relayer_role: Option<Account<'info, RelayerRole>>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyIntegrity<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = bpf_loader_upgradeable.key(),
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    /// CHECK: The BPF upgradeable loader
+    #[account(address = anchor_lang::solana_program::bpf_loader_upgradeable::ID)]
+    pub bpf_loader_upgradeable: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetExpectedUpgradeAuthority<'info> {
+    #[account(
+        mut,
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    pub admin: Signer<'info>,
+    // The announcement vouches for the upgrade authority, so whoever holds it co-signs; an
+    // immutable program has nothing to announce
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = bpf_loader_upgradeable.key(),
+        constraint = program_data.upgrade_authority_address == Some(upgrade_authority.key()) @ ErrorCode::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    pub upgrade_authority: Signer<'info>,
+    /// CHECK: The BPF upgradeable loader
+    #[account(address = anchor_lang::solana_program::bpf_loader_upgradeable::ID)]
+    pub bpf_loader_upgradeable: UncheckedAccount<'info>,
+}
+
+// Remaining accounts: any of the fee config, its treasury, the registry and the security
+// state, in any order
+#[derive(Accounts)]
+pub struct FullConfigRead<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+}
+
+#[derive(Accounts)]
+pub struct DebugDecode<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+}
+
+#[derive(Accounts)]
+#[instruction(uri_len: u16)]
+pub struct BenchmarkCtx<'info> {
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = ENCODING_BENCHMARK_SPACE,
+        seeds = [pda::ENCODING_BENCHMARK_SEED, &uri_len.to_le_bytes()],
+        bump
+    )]
+    pub encoding_benchmark: Account<'info, EncodingBenchmark>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AdminAction<'info> {
+    #[account(
+        mut,
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetCircuitBreaker<'info> {
+    #[account(
+        mut,
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + 8 + 8 + 4 + 4 + 4 + 4 + 1,
+        seeds = [pda::SECURITY_STATE_SEED],
+        bump
+    )]
+    pub security_state: Account<'info, SecurityState>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReportAnomaly<'info> {
+    #[account(
+        mut,
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        mut,
+        seeds = [pda::SECURITY_STATE_SEED],
+        bump = security_state.bump
+    )]
+    pub security_state: Account<'info, SecurityState>,
+    // Only inspected for a replay report; must be the bridged mint for the decoded token id
+    pub bridged_mint: Option<Account<'info, Mint>>,
+    // Also only for a replay report, the decoded token's origin: the units it has stranded
+    // stay in the mint's supply without making a delivery live
+    #[account(
+        constraint = nft_origin.is_current(nft_origin.to_account_info().data_len()) @ ErrorCode::NFTOriginNeedsMigration
+    )]
+    pub nft_origin: Option<Account<'info, NFTOrigin>>,
+    pub reporter: Signer<'info>,
+    // Reports can pause inbound, so the reporter needs a relayer role even with the allowlist off
+    #[account(
+        seeds = [pda::RELAYER_ROLE_SEED, reporter.key().as_ref()],
+        bump = relayer_role.bump
+    )]
+    pub relayer_role: Account<'info, RelayerRole>,
+    /// CHECK: HourlyMetrics PDA for the current hour, checked in the handler; counting is
+    /// skipped when it is left out or doesn't exist
+    #[account(mut)]
+    pub hourly_metrics: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct LookupByMint<'info> {
+    #[account(
+        seeds = [pda::NFT_INDEX_SEED, mint.as_ref()],
+        bump = nft_index.bump
+    )]
+    pub nft_index: Account<'info, NFTIndex>,
+}
+
+#[derive(Accounts)]
+pub struct GatewayPing<'info> {
+    #[account(
+        mut,
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    /// CHECK: External program account; only its pubkey is used to invoke CPI. A ping
+    /// only proves connectivity if it goes to the configured gateway
+    #[account(address = program_state.gateway @ ErrorCode::Unauthorized)]
+    pub gateway_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct OptInTransferInsurance<'info> {
+    #[account(
+        seeds = [pda::FEE_CONFIG_SEED],
+        bump = fee_config.bump
+    )]
+    pub fee_config: Account<'info, FeeConfig>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = INSURANCE_FUND_SPACE,
+        seeds = [pda::INSURANCE_FUND_SEED],
+        bump
+    )]
+    pub insurance_fund: Account<'info, ProtocolInsuranceFund>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = INSURANCE_OPT_IN_SPACE,
+        seeds = [pda::INSURANCE_OPT_IN_SEED, user.key().as_ref()],
+        bump
+    )]
+    pub insurance_opt_in: Account<'info, InsuranceOptIn>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(appraiser: Pubkey)]
+pub struct SetAppraisalAuthority<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = APPRAISAL_AUTHORITY_SPACE,
+        seeds = [pda::APPRAISAL_AUTHORITY_SEED, appraiser.as_ref()],
+        bump
+    )]
+    pub appraisal_authority: Account<'info, AppraisalAuthority>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_id: u64)]
+pub struct SubmitAppraisal<'info> {
+    #[account(
+        seeds = [&pda::nft_origin_seed(token_id)],
+        bump = nft_origin.bump,
+        constraint = nft_origin.is_current(nft_origin.to_account_info().data_len()) @ ErrorCode::NFTOriginNeedsMigration
+    )]
+    pub nft_origin: Account<'info, NFTOrigin>,
+    #[account(
+        seeds = [pda::APPRAISAL_AUTHORITY_SEED, appraiser.key().as_ref()],
+        bump = appraisal_authority.bump,
+        constraint = appraisal_authority.active @ ErrorCode::AppraiserNotAuthorized
+    )]
+    pub appraisal_authority: Account<'info, AppraisalAuthority>,
+    #[account(
+        init_if_needed,
+        payer = appraiser,
+        space = NFT_APPRAISAL_SPACE,
+        seeds = [pda::APPRAISAL_SEED, nft_origin.mint.as_ref(), appraiser.key().as_ref()],
+        bump
+    )]
+    pub appraisal: Account<'info, NFTAppraisal>,
+    #[account(mut)]
+    pub appraiser: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_id: u64)]
+pub struct AttestOwnership<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        seeds = [&pda::nft_origin_seed(token_id)],
+        bump = nft_origin.bump,
+        constraint = nft_origin.is_current(nft_origin.to_account_info().data_len()) @ ErrorCode::NFTOriginNeedsMigration
+    )]
+    pub nft_origin: Account<'info, NFTOrigin>,
+    #[account(
+        token::mint = nft_origin.mint,
+        token::authority = holder,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::Unauthorized
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = holder,
+        space = OWNERSHIP_ATTESTATION_SPACE,
+        seeds = [pda::OWNERSHIP_ATTESTATION_SEED, &token_id.to_le_bytes()],
+        bump
+    )]
+    pub ownership_attestation: Account<'info, OwnershipAttestation>,
+    // Pays for the attestation account and, when relaying, the gateway call
+    #[account(mut)]
+    pub holder: Signer<'info>,
+    // Required to relay: the origin chain's ZRC-20 is the message's destination
+    #[account(
+        seeds = [pda::REGISTRY_SEED],
+        bump = registry.load()?.bump
+    )]
+    pub registry: Option<AccountLoader<'info, Registry>>,
+    /// CHECK: External program account; only its pubkey is used to invoke CPI. Required
+    /// to relay
+    pub gateway_program: Option<UncheckedAccount<'info>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_id: u64)]
+pub struct GetAttestation<'info> {
+    #[account(
+        seeds = [pda::OWNERSHIP_ATTESTATION_SEED, &token_id.to_le_bytes()],
+        bump = ownership_attestation.bump
+    )]
+    pub ownership_attestation: Account<'info, OwnershipAttestation>,
+}
+
+/// Remaining accounts are the token's NFTAppraisal accounts to choose from
+#[derive(Accounts)]
+#[instruction(token_id: u64)]
+pub struct GetAppraisal<'info> {
+    #[account(
+        seeds = [&pda::nft_origin_seed(token_id)],
+        bump = nft_origin.bump,
+        constraint = nft_origin.is_current(nft_origin.to_account_info().data_len()) @ ErrorCode::NFTOriginNeedsMigration
+    )]
+    pub nft_origin: Account<'info, NFTOrigin>,
+}
+
+#[derive(Accounts)]
+#[instruction(nonce: u64)]
+pub struct OnTransferReverted<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        mut,
+        seeds = [pda::PENDING_TRANSFER_SEED, &nonce.to_le_bytes()],
+        bump = pending_transfer.bump
+    )]
+    pub pending_transfer: Account<'info, PendingTransfer>,
+    // Required only when the transfer was insured
+    #[account(
+        mut,
+        seeds = [pda::INSURANCE_FUND_SEED],
+        bump = insurance_fund.bump
+    )]
+    pub insurance_fund: Option<Account<'info, ProtocolInsuranceFund>>,
+    /// CHECK: Receives the payout; must be the transfer's sender
+    #[account(mut, address = pending_transfer.sender @ ErrorCode::Unauthorized)]
+    pub sender: UncheckedAccount<'info>,
+    // Reverts and aborts come back through the gateway; nobody else may report one
+    #[account(address = program_state.gateway @ ErrorCode::Unauthorized)]
+    pub gateway: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GatewayPong<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    pub relayer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetFeeConfig<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + 8 + 32 + 4 + MAX_FEE_DISCOUNT_TIERS * (32 + 8 + 1) + 1 + 8 + 2 + 8 + 8,
+        seeds = [pda::FEE_CONFIG_SEED],
+        bump
+    )]
+    pub fee_config: Account<'info, FeeConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetReceiptConfig<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + 1 + 4 + MAX_RECEIPT_URI_BASE_BYTES + 1, // 8 (discriminator) + 1 (enabled) + 4 + MAX_RECEIPT_URI_BASE_BYTES (uri_base) + 1 (bump)
+        seeds = [pda::RECEIPT_CONFIG_SEED],
+        bump
+    )]
+    pub receipt_config: Account<'info, ReceiptConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetRelayerConfig<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + 1 + 1, // 8 (discriminator) + 1 (allowlist_enabled) + 1 (bump)
+        seeds = [pda::RELAYER_CONFIG_SEED],
+        bump
+    )]
+    pub relayer_config: Account<'info, RelayerConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// Kept to a single PDA check so a heartbeat stays under 5k CU. Only `initialize` creates a
+// ProgramState, always at the [b"test"] PDA, so owner and discriminator checks pin it
+#[derive(Accounts)]
+pub struct RecordHeartbeat<'info> {
+    pub program_state: Account<'info, ProgramState>,
+    // Created by `set_heartbeat_interval`
+    #[account(
+        mut,
+        seeds = [pda::HEARTBEAT_SEED],
+        bump = heartbeat.bump
+    )]
+    pub heartbeat: Account<'info, HeartbeatRecord>,
+    /// CHECK: RelayerConfig PDA; uninitialized reads as an open allowlist
+    #[account(seeds = [pda::RELAYER_CONFIG_SEED], bump)]
+    pub relayer_config: UncheckedAccount<'info>,
+    #[account(
+        seeds = [pda::RELAYER_ROLE_SEED, caller.key().as_ref()],
+        bump = relayer_role.bump
+    )]
+    pub relayer_role: Option<Account<'info, RelayerRole>>,
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetHeartbeatInterval<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = HEARTBEAT_RECORD_SPACE,
+        seeds = [pda::HEARTBEAT_SEED],
+        bump
+    )]
+    pub heartbeat: Account<'info, HeartbeatRecord>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(relayer: Pubkey)]
+pub struct GrantRelayerRole<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + 32 + 8 + 1, // 8 (discriminator) + 32 (relayer) + 8 (granted_at) + 1 (bump)
+        seeds = [pda::RELAYER_ROLE_SEED, relayer.as_ref()],
+        bump
+    )]
+    pub relayer_role: Account<'info, RelayerRole>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(relayer: Pubkey)]
+pub struct RevokeRelayerRole<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        mut,
+        close = admin,
+        seeds = [pda::RELAYER_ROLE_SEED, relayer.as_ref()],
+        bump = relayer_role.bump
+    )]
+    pub relayer_role: Account<'info, RelayerRole>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(chain_id: u64)]
+pub struct SetChainGasConfig<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = CHAIN_GAS_CONFIG_SPACE,
+        seeds = [pda::CHAIN_GAS_SEED, &chain_id.to_le_bytes()],
+        bump
+    )]
+    pub chain_gas_config: Account<'info, ChainGasConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(collection_id: u64)]
+pub struct SetFloorPriceFeed<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = COLLECTION_STATS_SPACE,
+        seeds = [pda::COLLECTION_STATS_SEED, &collection_id.to_le_bytes()],
+        bump
+    )]
+    pub collection_stats: Account<'info, CollectionStats>,
+    #[account(
+        seeds = [pda::COLLECTION_AUTHORITY_SEED, &collection_id.to_le_bytes()],
+        bump = collection_authority.bump
+    )]
+    pub collection_authority: Option<Account<'info, CollectionAuthority>>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(collection_id: u64)]
+pub struct UpdateFloorPrice<'info> {
+    #[account(
+        mut,
+        seeds = [pda::COLLECTION_STATS_SEED, &collection_id.to_le_bytes()],
+        bump = collection_stats.bump
+    )]
+    pub collection_stats: Account<'info, CollectionStats>,
+    /// CHECK: Pyth price update account; owner and layout are verified in the handler
+    #[account(address = collection_stats.floor_price_feed @ ErrorCode::InvalidPriceFeed)]
+    pub price_feed: UncheckedAccount<'info>,
+    /// CHECK: RelayerConfig PDA; uninitialized reads as an open allowlist
+    #[account(seeds = [pda::RELAYER_CONFIG_SEED], bump)]
+    pub relayer_config: UncheckedAccount<'info>,
+    #[account(
+        seeds = [pda::RELAYER_ROLE_SEED, cranker.key().as_ref()],
+        bump = relayer_role.bump
+    )]
+    pub relayer_role: Option<Account<'info, RelayerRole>>,
+    pub cranker: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(collection_id: u64)]
+pub struct GetCollectionStats<'info> {
+    #[account(
+        seeds = [pda::COLLECTION_STATS_SEED, &collection_id.to_le_bytes()],
+        bump = collection_stats.bump
+    )]
+    pub collection_stats: Account<'info, CollectionStats>,
+}
+
+#[derive(Accounts)]
+#[instruction(collection_id: u64)]
+pub struct SetCollectionAuthority<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = COLLECTION_AUTHORITY_SPACE,
+        seeds = [pda::COLLECTION_AUTHORITY_SEED, &collection_id.to_le_bytes()],
+        bump
+    )]
+    pub collection_authority: Account<'info, CollectionAuthority>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(collection_id: u64)]
+pub struct SetSellerFeeConfig<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + 8 + 4 + MAX_ROYALTY_CREATORS * (32 + 1) + 1,
+        seeds = [pda::SELLER_FEE_CONFIG_SEED, &collection_id.to_le_bytes()],
+        bump
+    )]
+    pub seller_fee_config: Account<'info, SellerFeeConfig>,
+    #[account(
+        seeds = [pda::COLLECTION_AUTHORITY_SEED, &collection_id.to_le_bytes()],
+        bump = collection_authority.bump
+    )]
+    pub collection_authority: Option<Account<'info, CollectionAuthority>>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(collection_id: u64)]
+pub struct UpdateRoyaltyRecipient<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        mut,
+        seeds = [pda::SELLER_FEE_CONFIG_SEED, &collection_id.to_le_bytes()],
+        bump = seller_fee_config.bump
+    )]
+    pub seller_fee_config: Account<'info, SellerFeeConfig>,
+    #[account(
+        seeds = [pda::COLLECTION_AUTHORITY_SEED, &collection_id.to_le_bytes()],
+        bump = collection_authority.bump
+    )]
+    pub collection_authority: Option<Account<'info, CollectionAuthority>>,
+    pub admin: Signer<'info>,
+    // The recipient being replaced consents by signing
+    pub current_recipient: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(collection_id: u64)]
+pub struct DepositRoyalties<'info> {
+    #[account(
+        seeds = [pda::SELLER_FEE_CONFIG_SEED, &collection_id.to_le_bytes()],
+        bump = seller_fee_config.bump
+    )]
+    pub seller_fee_config: Account<'info, SellerFeeConfig>,
+    #[account(
+        init_if_needed,
+        payer = depositor,
+        space = 8 + 8 + 4 + MAX_ROYALTY_ENTRIES * (32 + 8) + 8 + 8 + 1,
+        seeds = [pda::COLLECTION_ROYALTY_SEED, &collection_id.to_le_bytes()],
+        bump
+    )]
+    pub royalty_balance: Account<'info, CollectionRoyaltyBalance>,
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(collection_id: u64)]
+pub struct DistributeRoyalties<'info> {
+    #[account(
+        mut,
+        seeds = [pda::COLLECTION_ROYALTY_SEED, &collection_id.to_le_bytes()],
+        bump = royalty_balance.bump
+    )]
+    pub royalty_balance: Account<'info, CollectionRoyaltyBalance>,
+    /// CHECK: RelayerConfig PDA; uninitialized reads as an open allowlist
+    #[account(seeds = [pda::RELAYER_CONFIG_SEED], bump)]
+    pub relayer_config: UncheckedAccount<'info>,
+    #[account(
+        seeds = [pda::RELAYER_ROLE_SEED, cranker.key().as_ref()],
+        bump = relayer_role.bump
+    )]
+    pub relayer_role: Option<Account<'info, RelayerRole>>,
+    pub cranker: Signer<'info>,
+    // Creator wallets to pay are passed as writable remaining accounts
+}
+
+#[derive(Accounts)]
+#[instruction(collection_id: u64)]
+pub struct ClaimCreatorRoyalties<'info> {
+    #[account(
+        mut,
+        seeds = [pda::COLLECTION_ROYALTY_SEED, &collection_id.to_le_bytes()],
+        bump = royalty_balance.bump
+    )]
+    pub royalty_balance: Account<'info, CollectionRoyaltyBalance>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(collection_id: u64)]
+pub struct ComputeMerkleRoot<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + 8 + 32 + 8 + 8 + 1, // 8 (discriminator) + 8 (collection_id) + 32 (root) + 8 (token_count) + 8 (computed_at) + 1 (bump)
+        seeds = [pda::MERKLE_ROOT_SEED, &collection_id.to_le_bytes()],
+        bump
+    )]
+    pub merkle_root: Account<'info, CollectionMerkleRoot>,
+    #[account(
+        seeds = [pda::COLLECTION_AUTHORITY_SEED, &collection_id.to_le_bytes()],
+        bump = collection_authority.bump
+    )]
+    pub collection_authority: Option<Account<'info, CollectionAuthority>>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(collection_id: u64)]
+pub struct ConfigureLazyReveal<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = LAZY_REVEAL_CONFIG_SPACE,
+        seeds = [pda::LAZY_REVEAL_SEED, &collection_id.to_le_bytes()],
+        bump
+    )]
+    pub lazy_reveal_config: Account<'info, LazyRevealConfig>,
+    #[account(
+        seeds = [pda::COLLECTION_AUTHORITY_SEED, &collection_id.to_le_bytes()],
+        bump = collection_authority.bump
+    )]
+    pub collection_authority: Option<Account<'info, CollectionAuthority>>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(collection_id: u64)]
+pub struct TriggerReveal<'info> {
+    #[account(
+        mut,
+        seeds = [pda::LAZY_REVEAL_SEED, &collection_id.to_le_bytes()],
+        bump = lazy_reveal_config.bump
+    )]
+    pub lazy_reveal_config: Account<'info, LazyRevealConfig>,
+    /// CHECK: RelayerConfig PDA; uninitialized reads as an open allowlist
+    #[account(seeds = [pda::RELAYER_CONFIG_SEED], bump)]
+    pub relayer_config: UncheckedAccount<'info>,
+    #[account(
+        seeds = [pda::RELAYER_ROLE_SEED, cranker.key().as_ref()],
+        bump = relayer_role.bump
+    )]
+    pub relayer_role: Option<Account<'info, RelayerRole>>,
+    pub cranker: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(collection_id: u64)]
+pub struct FulfillReveal<'info> {
+    #[account(
+        seeds = [pda::LAZY_REVEAL_SEED, &collection_id.to_le_bytes()],
+        bump = lazy_reveal_config.bump
+    )]
+    pub lazy_reveal_config: Account<'info, LazyRevealConfig>,
+    // Created once, so a collection's seed can never be replaced
+    #[account(
+        init,
+        payer = vrf_authority,
+        space = REVEAL_SEED_SPACE,
+        seeds = [pda::REVEAL_SEED_SEED, &collection_id.to_le_bytes()],
+        bump
+    )]
+    pub reveal_seed: Account<'info, RevealSeed>,
+    #[account(mut)]
+    pub vrf_authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(collection_id: u64, token_id: u64)]
+pub struct UpdateTokenUri<'info> {
+    #[account(
+        seeds = [pda::LAZY_REVEAL_SEED, &collection_id.to_le_bytes()],
+        bump = lazy_reveal_config.bump
+    )]
+    pub lazy_reveal_config: Account<'info, LazyRevealConfig>,
+    #[account(
+        seeds = [pda::REVEAL_SEED_SEED, &collection_id.to_le_bytes()],
+        bump = reveal_seed.bump
+    )]
+    pub reveal_seed: Account<'info, RevealSeed>,
+    #[account(
+        mut,
+        seeds = [&pda::nft_origin_seed(token_id)],
+        bump = nft_origin.bump,
+        constraint = nft_origin.is_current(nft_origin.to_account_info().data_len()) @ ErrorCode::NFTOriginNeedsMigration
+    )]
+    pub nft_origin: Account<'info, NFTOrigin>,
+    /// CHECK: RelayerConfig PDA; uninitialized reads as an open allowlist
+    #[account(seeds = [pda::RELAYER_CONFIG_SEED], bump)]
+    pub relayer_config: UncheckedAccount<'info>,
+    #[account(
+        seeds = [pda::RELAYER_ROLE_SEED, cranker.key().as_ref()],
+        bump = relayer_role.bump
+    )]
+    pub relayer_role: Option<Account<'info, RelayerRole>>,
+    pub cranker: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(chain_id: u64)]
+pub struct GetChainStats<'info> {
+    #[account(
+        seeds = [pda::CHAIN_STATS_SEED, &chain_id.to_le_bytes()],
+        bump = chain_stats.bump
+    )]
+    pub chain_stats: Account<'info, ChainStats>,
+}
+
+#[derive(Accounts)]
+#[instruction(hour_bucket: u64)]
+pub struct OpenHourlyMetrics<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = HOURLY_METRICS_SPACE,
+        seeds = [pda::HOURLY_METRICS_SEED, &hour_bucket.to_le_bytes()],
+        bump
+    )]
+    pub hourly_metrics: Account<'info, HourlyMetrics>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(hour_bucket: u64)]
+pub struct GetHourlyMetrics<'info> {
+    #[account(
+        seeds = [pda::HOURLY_METRICS_SEED, &hour_bucket.to_le_bytes()],
+        bump = hourly_metrics.bump
+    )]
+    pub hourly_metrics: Account<'info, HourlyMetrics>,
+}
+
+// The accounts to close come as remaining accounts, each followed by the payer its rent
+// goes back to, in the order of `pda_addresses`
+#[derive(Accounts)]
+pub struct BatchClose<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(hour_bucket: u64)]
+pub struct CloseStaleMetrics<'info> {
+    #[account(
+        mut,
+        close = payer,
+        seeds = [pda::HOURLY_METRICS_SEED, &hour_bucket.to_le_bytes()],
+        bump = hourly_metrics.bump
+    )]
+    pub hourly_metrics: Account<'info, HourlyMetrics>,
+    /// CHECK: Refunded the rent; must be whoever opened the bucket
+    #[account(mut, address = hourly_metrics.payer)]
+    pub payer: UncheckedAccount<'info>,
+    /// CHECK: RelayerConfig PDA; uninitialized reads as an open allowlist
+    #[account(seeds = [pda::RELAYER_CONFIG_SEED], bump)]
+    pub relayer_config: UncheckedAccount<'info>,
+    #[account(
+        seeds = [pda::RELAYER_ROLE_SEED, closer.key().as_ref()],
+        bump = relayer_role.bump
+    )]
+    pub relayer_role: Option<Account<'info, RelayerRole>>,
+    pub closer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_id: u64)]
+pub struct RepairNFTOrigin<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        mut,
+        seeds = [&pda::nft_origin_seed(token_id)],
+        bump = nft_origin.bump,
+        constraint = nft_origin.is_current(nft_origin.to_account_info().data_len()) @ ErrorCode::NFTOriginNeedsMigration
+    )]
+    pub nft_origin: Account<'info, NFTOrigin>,
+    pub admin: Signer<'info>,
+}
+
+// The NFTOrigins to check are remaining accounts, in the same order as token_ids
+#[derive(Accounts)]
+pub struct VerifyMigration<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = MIGRATION_INTEGRITY_REPORT_SPACE,
+        seeds = [pda::MIGRATION_INTEGRITY_REPORT_SEED],
+        bump
+    )]
+    pub migration_integrity_report: Account<'info, MigrationIntegrityReport>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// No mint authority or token program: a replay can't mint
+#[derive(Accounts)]
+#[instruction(message: Vec<u8>)]
+pub struct ReplayInbound<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    // Only messages that were delivered with a receipt can be replayed
+    #[account(
+        seeds = [pda::INBOUND_RECEIPT_SEED, &inbound_message_hash(&message)],
+        bump = inbound_receipt.bump
+    )]
+    pub inbound_receipt: Account<'info, InboundReceipt>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = NFT_ORIGIN_SPACE,
+        seeds = [&pda::nft_origin_seed(inbound_token_id(&message))],
+        bump,
+        constraint = nft_origin.is_current_or_new(nft_origin.to_account_info().data_len()) @ ErrorCode::NFTOriginNeedsMigration
+    )]
+    pub nft_origin: Account<'info, NFTOrigin>,
+    #[account(
+        seeds = [pda::BRIDGED_MINT_SEED, &CHAIN_ID_ZETACHAIN_TESTNET.to_le_bytes(), &inbound_token_seed(&message)],
+        bump
+    )]
+    pub mint: Account<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = NFT_INDEX_SPACE,
+        seeds = [pda::NFT_INDEX_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub nft_index: Account<'info, NFTIndex>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// The attestation is an ed25519 program instruction placed right before this one
+#[derive(Accounts)]
+#[instruction(token_id: u64)]
+pub struct AttachMetadata<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        mut,
+        seeds = [&pda::nft_origin_seed(token_id)],
+        bump = nft_origin.bump,
+        constraint = nft_origin.is_current(nft_origin.to_account_info().data_len()) @ ErrorCode::NFTOriginNeedsMigration
+    )]
+    pub nft_origin: Account<'info, NFTOrigin>,
+    // Required once the NFT already has a URI
+    pub admin: Option<Signer<'info>>,
+    /// CHECK: Instructions sysvar, pinned by address
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(origin_chain: u64, origin_contract: Vec<u8>)]
+pub struct SetOriginPolicy<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = ORIGIN_POLICY_SPACE,
+        seeds = [pda::ORIGIN_POLICY_SEED, &origin_chain.to_le_bytes(), &origin_contract_hash(&origin_contract)],
+        bump
+    )]
+    pub origin_policy: Account<'info, OriginPolicy>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(origin_chain: u64, origin_contract: Vec<u8>)]
+pub struct RemoveOriginPolicy<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        mut,
+        close = admin,
+        seeds = [pda::ORIGIN_POLICY_SEED, &origin_chain.to_le_bytes(), &origin_contract_hash(&origin_contract)],
+        bump = origin_policy.bump
+    )]
+    pub origin_policy: Account<'info, OriginPolicy>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(zrc20: [u8; 20])]
+pub struct SetZRC20GasVault<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = ZRC20_GAS_VAULT_SPACE,
+        seeds = [pda::ZRC20_VAULT_SEED, &zrc20],
+        bump
+    )]
+    pub zrc20_gas_vault: Account<'info, ZRC20GasVault>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        associated_token::mint = mint,
+        associated_token::authority = zrc20_gas_vault,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+    #[account(token::mint = mint)]
+    pub fee_destination: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(zrc20: [u8; 20])]
+pub struct DepositZRC20Gas<'info> {
+    #[account(
+        seeds = [pda::ZRC20_VAULT_SEED, &zrc20],
+        bump = zrc20_gas_vault.bump
+    )]
+    pub zrc20_gas_vault: Account<'info, ZRC20GasVault>,
+    #[account(
+        mut,
+        associated_token::mint = zrc20_gas_vault.mint,
+        associated_token::authority = zrc20_gas_vault,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        token::mint = zrc20_gas_vault.mint,
+        token::authority = depositor,
+    )]
+    pub depositor_token_account: Account<'info, TokenAccount>,
+    pub depositor: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(zrc20: [u8; 20], amount: u64, dest: Pubkey)]
+pub struct WithdrawZRC20Gas<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    #[account(
+        seeds = [pda::ZRC20_VAULT_SEED, &zrc20],
+        bump = zrc20_gas_vault.bump
+    )]
+    pub zrc20_gas_vault: Account<'info, ZRC20GasVault>,
+    #[account(
+        mut,
+        associated_token::mint = zrc20_gas_vault.mint,
+        associated_token::authority = zrc20_gas_vault,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        address = dest,
+        token::mint = zrc20_gas_vault.mint,
+    )]
+    pub dest_token_account: Account<'info, TokenAccount>,
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_id: u64)]
+pub struct LazyInitOrigin<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    // Closed into the holder, who pays for the origin
+    #[account(
+        mut,
+        close = holder,
+        seeds = [pda::MINT_ONLY_SEED, &token_id.to_le_bytes()],
+        bump = mint_only.bump
+    )]
+    pub mint_only: Account<'info, NFTMintOnly>,
+    #[account(
+        init,
+        payer = holder,
+        space = NFT_ORIGIN_SPACE,
+        seeds = [&pda::nft_origin_seed(token_id)],
+        bump
+    )]
+    pub nft_origin: Account<'info, NFTOrigin>,
+    #[account(
+        token::mint = mint_only.mint,
+        token::authority = holder,
+        constraint = holder_token_account.amount >= 1 @ ErrorCode::Unauthorized
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub holder: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_id: u64)]
+pub struct CreateCoOwnership<'info> {
+    #[account(
+        mut,
+        seeds = [&pda::nft_origin_seed(token_id)],
+        bump = nft_origin.bump,
+        constraint = nft_origin.is_current(nft_origin.to_account_info().data_len()) @ ErrorCode::NFTOriginNeedsMigration
+    )]
+    pub nft_origin: Account<'info, NFTOrigin>,
+    #[account(
+        token::mint = nft_origin.mint,
+        token::authority = holder,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::Unauthorized
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = holder,
+        space = CO_OWNERSHIP_RECORD_SPACE,
+        seeds = [pda::CO_OWNERSHIP_SEED, nft_origin.mint.as_ref()],
+        bump
+    )]
+    pub co_ownership: Account<'info, CoOwnershipRecord>,
+    #[account(mut)]
+    pub holder: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_id: u64)]
+pub struct ConsentCoOwnedTransfer<'info> {
+    #[account(
+        seeds = [&pda::nft_origin_seed(token_id)],
+        bump = nft_origin.bump,
+        constraint = nft_origin.is_current(nft_origin.to_account_info().data_len()) @ ErrorCode::NFTOriginNeedsMigration
+    )]
+    pub nft_origin: Account<'info, NFTOrigin>,
+    #[account(
+        seeds = [pda::CO_OWNERSHIP_SEED, nft_origin.mint.as_ref()],
+        bump = co_ownership.bump,
+        constraint = approver.key() == co_ownership.owner_a
+            || approver.key() == co_ownership.owner_b @ ErrorCode::NotCoOwner
+    )]
+    pub co_ownership: Account<'info, CoOwnershipRecord>,
+    #[account(
+        init,
+        payer = approver,
+        space = CO_OWNER_CONSENT_SPACE,
+        seeds = [
+            pda::CO_OWNER_CONSENT_SEED,
+            nft_origin.mint.as_ref(),
+            &co_ownership.created_at.to_le_bytes(),
+            &co_ownership.consent_nonce.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub co_owner_consent: Account<'info, CoOwnerConsent>,
+    #[account(mut)]
+    pub approver: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_id: u64, nonce: u64)]
+pub struct CloseCoOwnerConsent<'info> {
+    #[account(
+        seeds = [&pda::nft_origin_seed(token_id)],
+        bump = nft_origin.bump,
+        constraint = nft_origin.is_current(nft_origin.to_account_info().data_len()) @ ErrorCode::NFTOriginNeedsMigration
+    )]
+    pub nft_origin: Account<'info, NFTOrigin>,
+    // Used or not; an unused one stops counting once closed. Not rederived from seeds so
+    // consents left over from a dissolved record of the same mint can be closed too
+    #[account(
+        mut,
+        close = approver,
+        has_one = approver @ ErrorCode::Unauthorized,
+        constraint = co_owner_consent.mint == nft_origin.mint
+            && co_owner_consent.nonce == nonce @ ErrorCode::CoOwnerConsentRequired
+    )]
+    pub co_owner_consent: Account<'info, CoOwnerConsent>,
+    #[account(mut)]
+    pub approver: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_id: u64)]
+pub struct DissolveCoOwnership<'info> {
     #[account(
         mut,
-        seeds = [b"metadata", token_metadata_program.key().as_ref(), mint.key().as_ref(), b"edition"],
-        seeds::program = token_metadata_program.key(),
+        seeds = [&pda::nft_origin_seed(token_id)],
+        bump = nft_origin.bump,
+        constraint = nft_origin.is_current(nft_origin.to_account_info().data_len()) @ ErrorCode::NFTOriginNeedsMigration
+    )]
+    pub nft_origin: Account<'info, NFTOrigin>,
+    #[account(
+        mut,
+        close = owner_a,
+        seeds = [pda::CO_OWNERSHIP_SEED, nft_origin.mint.as_ref()],
+        bump = co_ownership.bump
+    )]
+    pub co_ownership: Account<'info, CoOwnershipRecord>,
+    /// CHECK: Paid for the record, so gets its rent back
+    #[account(mut, address = co_ownership.owner_a)]
+    pub owner_a: UncheckedAccount<'info>,
+    // Either owner; with require_both_signatures, co_signer must be the other
+    pub authority: Signer<'info>,
+    pub co_signer: Option<Signer<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_id: u64)]
+pub struct SetRentalLock<'info> {
+    #[account(
+        mut,
+        seeds = [&pda::nft_origin_seed(token_id)],
+        bump = nft_origin.bump,
+        constraint = nft_origin.is_current(nft_origin.to_account_info().data_len()) @ ErrorCode::NFTOriginNeedsMigration
+    )]
+    pub nft_origin: Account<'info, NFTOrigin>,
+    // The NFT's one unit; the locker owns it or is its approved delegate
+    #[account(
+        token::mint = nft_origin.mint,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::Unauthorized,
+        constraint = holder_token_account.owner == locker.key()
+            || (holder_token_account.delegate == COption::Some(locker.key())
+                && holder_token_account.delegated_amount >= 1) @ ErrorCode::Unauthorized
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+    // The holder, or a rental program signing for the delegate PDA it was approved as
+    pub locker: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_id: u64)]
+pub struct ClearRentalLock<'info> {
+    #[account(
+        mut,
+        seeds = [&pda::nft_origin_seed(token_id)],
+        bump = nft_origin.bump,
+        constraint = nft_origin.rental_locker == locker.key() @ ErrorCode::NotRentalLocker,
+        constraint = nft_origin.is_current(nft_origin.to_account_info().data_len()) @ ErrorCode::NFTOriginNeedsMigration
+    )]
+    pub nft_origin: Account<'info, NFTOrigin>,
+    pub locker: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_id: u64)]
+pub struct UpdateMetadataEncryptionKey<'info> {
+    #[account(
+        mut,
+        seeds = [&pda::nft_origin_seed(token_id)],
+        bump = nft_origin.bump,
+        constraint = nft_origin.is_current(nft_origin.to_account_info().data_len()) @ ErrorCode::NFTOriginNeedsMigration
+    )]
+    pub nft_origin: Account<'info, NFTOrigin>,
+    #[account(
+        token::mint = nft_origin.mint,
+        token::authority = holder,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::Unauthorized
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+    pub holder: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateProgramState<'info> {
+    /// CHECK: Deserialized in the handler once it has been grown to PROGRAM_STATE_SPACE
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump
+    )]
+    pub program_state: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_id: u64)]
+pub struct MigrateNFTOrigin<'info> {
+    /// CHECK: Read at whichever layout its size and schema_version say, then rewritten at
+    /// the current one
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [&pda::nft_origin_seed(token_id)],
+        bump
+    )]
+    pub nft_origin: UncheckedAccount<'info>,
+    /// CHECK: The origin's mint, checked against it in the handler; its supply tells how
+    /// many units are away from Solana for origins from before departed_amount
+    pub mint: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_id: u64)]
+pub struct MigrateNFTMintOnly<'info> {
+    #[account(
+        seeds = [pda::PROGRAM_STATE_SEED],
+        bump = program_state.bump
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    /// CHECK: An NFTMintOnly from before uri_hash, checked for that size in the handler,
+    /// then grown and rewritten
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [pda::MINT_ONLY_SEED, &token_id.to_le_bytes()],
+        bump
+    )]
+    pub mint_only: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(collection_id: u64)]
+pub struct MigrateCollectionStats<'info> {
+    /// CHECK: A CollectionStats from before floor_price_feed_id, checked for its size in the
+    /// handler, then grown
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [pda::COLLECTION_STATS_SEED, &collection_id.to_le_bytes()],
+        bump
+    )]
+    pub collection_stats: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(chain_id: u64)]
+pub struct MigrateChainGasConfig<'info> {
+    /// CHECK: A ChainGasConfig from before one of its later fields, checked for its size in
+    /// the handler, then grown
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [pda::CHAIN_GAS_SEED, &chain_id.to_le_bytes()],
+        bump
+    )]
+    pub chain_gas_config: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(message_hash: [u8; 32])]
+pub struct MigratePendingInbound<'info> {
+    /// CHECK: A PendingInbound queued before its priority byte, decoded at either layout in
+    /// the handler, then grown
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [pda::PENDING_INBOUND_SEED, &message_hash],
+        bump
+    )]
+    pub pending_inbound: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateRegistry<'info> {
+    /// CHECK: A Registry written at an earlier RegistryEntry stride, told apart by its size
+    /// in the handler, then grown and re-laid
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [pda::REGISTRY_SEED],
+        bump
+    )]
+    pub registry: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(new_authority: Pubkey)]
+pub struct TransferUpgradeAuthority<'info> {
+    #[account(
+        mut,
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = bpf_loader_upgradeable.key(),
+        constraint = program_data.upgrade_authority_address == Some(current_authority.key()) @ ErrorCode::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    #[account(
+        init_if_needed,
+        payer = current_authority,
+        space = 8 + 32 + 32 + 8 + 1, // 8 (discriminator) + 32 (authority) + 32 (previous_authority) + 8 (last_transfer_at) + 1 (bump)
+        seeds = [pda::UPGRADE_AUTHORITY_SEED],
         bump
     )]
-    pub master_edition: AccountInfo<'info>,
+    pub upgrade_authority_record: Account<'info, UpgradeAuthorityRecord>,
+    #[account(mut)]
+    pub current_authority: Signer<'info>,
+    /// CHECK: Only recorded as the new authority; the loader does not require it to sign
+    #[account(address = new_authority)]
+    pub new_upgrade_authority: UncheckedAccount<'info>,
+    /// CHECK: The BPF upgradeable loader
+    #[account(address = anchor_lang::solana_program::bpf_loader_upgradeable::ID)]
+    pub bpf_loader_upgradeable: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// Events
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProgramInitialized {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub owner: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub gateway: Pubkey,
+    pub initial_token_id: u64,
+    pub gas_limit: u64,
+    // The caller's unverified genesis hash, and the chain id it was taken to name
+    pub declared_genesis_hash: [u8; 32],
+    pub declared_chain_id: u64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NFTOriginCreated {
+    pub token_id: u64,
+    pub origin_chain: u64,
+    pub origin_token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey,
+    pub metadata_uri: String,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NFTMinted {
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey,
+    pub metadata_uri: String,
+    // Units minted; more than one for a semi-fungible token
+    pub amount: u64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CrossChainTransferInitiated {
+    pub token_id: u64,
+    // The chain the NFT ends up on, not the ZetaChain hop
+    pub destination_chain: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
+    pub destination_owner: [u8; 20],
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey,
+    // ZRC-20 the message is forwarded through; zero when it mints on ZetaChain
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
+    pub destination_zrc20: [u8; 20],
+    // NFTOrigin.logical_owner when the NFT left from a custodial account, else default
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub logical_owner: Pubkey,
+    // Owner of the burned token account and the slot of the burn; with mint and token_id,
+    // the preimage of burn_commitment
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub owner: Pubkey,
+    pub burn_slot: u64,
+    pub burn_commitment: [u8; 32],
+    // Units burned and sent; one for an NFT
+    pub amount: u64,
+}
+
+/// Several NFTs left in one gateway call, all to `destination_owner` on `destination_chain`
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CrossChainBatchTransferInitiated {
+    pub token_ids: Vec<u64>,
+    pub destination_chain: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
+    pub destination_owner: [u8; 20],
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
+    pub destination_zrc20: [u8; 20],
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub sender: Pubkey,
+    pub gas_limit: u64,
+    pub message_bytes: u32,
+    // The one transfer nonce the whole batch used
+    pub nonce: u64,
+}
+
+/// An outbound message went out with its URI in hash-only form; resolvers map `uri_hash`
+/// back to the full URI through the token's NFTOrigin
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OutboundUriHashed {
+    pub token_id: u64,
+    // keccak256 of the full URI, as carried in the message
+    pub uri_hash: [u8; 32],
+    pub message_bytes: u32,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CrossChainMessageReceived {
+    pub token_id: u64,
+    pub origin_chain: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub recipient: Pubkey,
+    // Sender note carried by a single message; batched messages have none
+    pub note: Option<String>,
+    // Where the NFT was first minted, as now recorded in its NFTOrigin
+    pub provenance_chain: u64,
+    pub origin_token_id: u64,
+    pub origin_contract: [u8; 32],
+    pub origin_contract_len: u8,
+    // Lamports sent along with the NFT, and whether they were parked in the recipient's
+    // InboundValueClaim rather than paid out
+    pub value_lamports: u64,
+    pub value_parked: bool,
+    // Units minted; one for an NFT
+    pub amount: u64,
+}
+
+/// An inbound note that wasn't UTF-8. The delivery went ahead, and `note`, like
+/// `CrossChainMessageReceived.note`, has the bad bytes replaced with U+FFFD
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InboundNoteReplaced {
+    pub token_id: u64,
+    pub note: String,
+}
+
+/// A batched inbound message that was skipped; `error_code` is the Anchor error number
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReceiveFailed {
+    pub token_id: u64,
+    pub error_code: u32,
+}
+
+/// Every NFT of a batch message arrived; each also has its own CrossChainMessageReceived
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BatchReceived {
+    pub count: u8,
+    // inbound_message_hash of the whole batch message
+    pub message_hash: [u8; 32],
+    // Highest priority among the batch's entries
+    pub priority: u8,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PendingTransferCancelled {
+    pub nonce: u64,
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub sender: Pubkey,
+}
+
+/// Emitted by every admin instruction alongside its specific event
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AdminActionExecuted {
+    pub kind: u8,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub actor: Pubkey,
+    pub slot: u64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProgramPaused {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    pub reason_code: u8,
+    pub auto_unpause_at: Option<i64>,
+}
+
+/// Emitted by the first paused-check after `auto_unpause_at` has passed
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProgramAutoUnpaused {
+    pub scheduled_at: i64,
+    pub unpaused_at: i64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProgramUnpaused {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MintCreated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint_authority: Pubkey,
+    pub decimals: u8,
+    pub token_id: u64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AnomalyReported {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub reporter: Pubkey,
+    pub kind: AnomalyKind,
+    pub count: u32,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CircuitBreakerTripped {
+    pub kind: AnomalyKind,
+    pub count: u32,
+    pub threshold: u32,
+}
+
+/// A bridged NFT moved while its origin chain's ChainStats account didn't exist, so the
+/// stats missed it
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChainStatsMissing {
+    pub chain_id: u64,
+    pub token_id: u64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CircuitBreakerCleared {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GatewayPingSent {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub sender: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
+    pub destination: [u8; 20],
+    pub nonce: u64,
+    pub sent_at: i64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GatewayPingPong {
+    pub sent_at: i64,
+    pub received_at: i64,
+    pub round_trip_ms: i64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
+    pub destination: [u8; 20],
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConnectedContractPinged {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
+    pub zrc20: [u8; 20],
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub sender: Pubkey,
+    pub sent_at: i64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConnectedContractPonged {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
+    pub zrc20: [u8; 20],
+    pub pinged_at: i64,
+    pub last_seen_at: i64,
+    pub pong_hash: [u8; 32],
+}
+
+/// A connected contract has been silent longer than MAX_CONTRACT_SILENCE_SECONDS;
+/// `error_code` is ErrorCode::ContractUnresponsive
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConnectedContractUnresponsive {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
+    pub zrc20: [u8; 20],
+    pub last_seen_at: i64,
+    pub silent_seconds: i64,
+    pub error_code: u32,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FeeCollected {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub payer: Pubkey,
+    pub token_id: u64,
+    pub gross_fee_lamports: u64,
+    pub discount_percent: u8,
+    pub fee_lamports: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub treasury: Pubkey,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GasPaidFromVault {
+    pub token_id: u64,
+    pub zrc20: [u8; 20],
+    pub amount: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub fee_destination: Pubkey,
+    // Vault balance left after the payment
+    pub remaining: u64,
 }
 
-#[derive(Accounts)]
-#[instruction(token_id: u64)]
-pub struct CrossChainTransfer<'info> {
-    #[account(
-        seeds = [b"test"],
-        bump = program_state.bump
-    )]
-    pub program_state: Account<'info, ProgramState>,
-    #[account(
-        seeds = [&nft_origin_seed(token_id)],
-        bump = nft_origin.bump
-    )]
-    pub nft_origin: Account<'info, NFTOrigin>,
-    #[account(mut)]
-    pub mint: Account<'info, Mint>,
-    // Must be the caller's ATA for this mint
-    #[account(
-        mut,
-        token::mint = mint,
-        token::authority = user,
-    )]
-    pub user_token_account: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub user: Signer<'info>,
-    pub token_program: Program<'info, Token>,
-    /// CHECK: External program account; only its pubkey is used to invoke CPI
-    pub gateway_program: AccountInfo<'info>,
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FeeSplit {
+    pub total_fee: u64,
+    pub creator_fee: u64,
+    pub protocol_fee: u64,
+    // Default when no creator took a share
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub creator: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub protocol_treasury: Pubkey,
 }
 
-#[derive(Accounts)]
-#[instruction(token_id: u64)]
-pub struct ReceiveCrossChainMessage<'info> {
-    #[account(
-        mut,
-        seeds = [b"test"],
-        bump = program_state.bump
-    )]
-    pub program_state: Account<'info, ProgramState>,
-    #[account(
-        init,
-        payer = payer,
-        space = 8 + 8 + 8 + 8 + 4 + 1000 + 32 + 8 + 1, // 8 (discriminator) + 8 (token_id) + 8 (origin_chain) + 8 (origin_token_id) + 4 (String length) + 1000 (String content max) + 32 (mint) + 8 (created_at) + 1 (bump)
-        seeds = [&nft_origin_seed(token_id)],
-        bump
-    )]
-    pub nft_origin: Account<'info, NFTOrigin>,
-    // Use strong types and create ATA idempotently for the recipient
-    #[account(mut)]
-    pub mint: Account<'info, Mint>,
-    pub mint_authority: Signer<'info>,
-    #[account(
-        init,
-        payer = payer,
-        associated_token::mint = mint,
-        associated_token::authority = mint_authority,
-    )]
-    pub recipient_token_account: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub rent: Sysvar<'info, Rent>,
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RoyaltiesDeposited {
+    pub collection_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub depositor: Pubkey,
+    pub amount: u64,
 }
 
-#[derive(Accounts)]
-pub struct AdminAction<'info> {
-    #[account(
-        mut,
-        seeds = [b"test"],
-        bump = program_state.bump
-    )]
-    pub program_state: Account<'info, ProgramState>,
-    pub admin: Signer<'info>,
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RoyaltyDistributed {
+    pub collection_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub creator: Pubkey,
+    pub amount: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub cranked_by: Pubkey,
 }
 
-#[derive(Accounts)]
-pub struct MigrateProgramState<'info> {
-    #[account(
-        mut,
-        seeds = [b"test"],
-        bump = program_state.bump
-    )]
-    pub program_state: Account<'info, ProgramState>,
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    pub system_program: Program<'info, System>,
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FloorPriceUpdated {
+    pub collection_id: u64,
+    pub floor_price_lamports: u64,
+    pub publish_time: i64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub cranked_by: Pubkey,
 }
 
-// Events
 #[event]
-pub struct ProgramInitialized {
-    pub owner: Pubkey,
-    pub gateway: Pubkey,
-    pub initial_token_id: u64,
-    pub gas_limit: u64,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Heartbeat {
+    pub slot: u64,
+    pub timestamp: i64,
+    pub outbound_nonce: u64,
+    pub inbound_count: u64,
+    pub paused_flags: u8,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub cranked_by: Pubkey,
 }
 
 #[event]
-pub struct NFTOriginCreated {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MerkleRootUpdated {
+    pub collection_id: u64,
+    pub root: [u8; 32],
+    pub token_count: u64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LazyRevealConfigured {
+    pub collection_id: u64,
+    pub reveal_time: i64,
+    pub num_traits: u8,
+    pub uri_base: String,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub vrf_authority: Pubkey,
+}
+
+// The VRF service watches for this and answers with `fulfill_reveal`
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RevealRequested {
+    pub collection_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub vrf_authority: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub requested_by: Pubkey,
+    pub requested_at: i64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RevealFulfilled {
+    pub collection_id: u64,
+    pub seed: [u8; 32],
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TokenUriRevealed {
+    pub collection_id: u64,
+    pub token_id: u64,
+    pub uri: String,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub cranked_by: Pubkey,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MetadataEncryptionKeyUpdated {
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub holder: Pubkey,
+    pub metadata_encryption_key: Option<[u8; 32]>,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MetadataAttached {
     pub token_id: u64,
     pub origin_chain: u64,
-    pub origin_token_id: u64,
-    pub mint: Pubkey,
-    pub metadata_uri: String,
+    pub uri: String,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub attestation_key: Pubkey,
+    // Set when an admin replaced a URI the NFT already had
+    pub overridden: bool,
 }
 
 #[event]
-pub struct NFTMinted {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NFTOriginChainRepaired {
+    pub token_id: u64,
+    pub previous_origin_chain: u64,
+    pub origin_chain: u64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RaffleCreated {
+    pub raffle_id: u64,
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub creator: Pubkey,
+    pub ticket_price_lamports: u64,
+    pub max_tickets: u32,
+    pub end_time: i64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RaffleTicketBought {
+    pub raffle_id: u64,
+    pub index: u32,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub buyer: Pubkey,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RaffleWinnerRequested {
+    pub raffle_id: u64,
+    pub ticket_count: u32,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RaffleSettled {
+    pub raffle_id: u64,
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub winner: Pubkey,
+    pub winner_index: u32,
+    pub proceeds_lamports: u64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RaffleCancelled {
+    pub raffle_id: u64,
     pub token_id: u64,
+    pub ticket_count: u32,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RaffleTicketRefunded {
+    pub raffle_id: u64,
+    pub index: u32,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub buyer: Pubkey,
+    pub lamports: u64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TokenIdReserved {
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub reserved_by: Pubkey,
+    pub reservation_fee: u64,
+    pub expires_at: i64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReservationClosed {
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub reserved_by: Pubkey,
+    pub refunded_lamports: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub cranked_by: Pubkey,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeliveryModeUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub owner: Pubkey,
+    pub delivery_mode: DeliveryMode,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProvisionalDeliveryHeld {
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub recipient: Pubkey,
+    pub release_at: i64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProvisionalReleased {
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub recipient: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub cranked_by: Pubkey,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InboundQueued {
+    pub message_hash: [u8; 32],
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub recipient: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub queued_by: Pubkey,
+    pub priority: u8,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InboundExecuted {
+    pub message_hash: [u8; 32],
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub executed_by: Pubkey,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InboundNFTOffered {
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub recipient: Pubkey,
+    pub offer_expires_at: i64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InboundNFTAccepted {
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub recipient: Pubkey,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InboundNFTRejected {
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub recipient: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub rejected_by: Pubkey,
+    // Whether a message returned the NFT to its sender; otherwise it is only burned
+    pub returned_to_sender: bool,
+}
+
+/// A delivery's metadata was built from its origin contract's OriginPolicy
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OriginPolicyApplied {
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub mint: Pubkey,
-    pub metadata_uri: String,
+    pub origin_chain: u64,
+    pub origin_contract_hash: [u8; 32],
+    pub royalty_bps: u16,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub royalty_recipient: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub update_authority: Pubkey,
+    pub is_mutable: bool,
 }
 
 #[event]
-pub struct CrossChainTransferInitiated {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MetadataDeferred {
     pub token_id: u64,
-    pub destination_chain: u64,
-    pub destination_owner: [u8; 20],
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub mint: Pubkey,
 }
 
 #[event]
-pub struct CrossChainMessageReceived {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MetadataCompleted {
     pub token_id: u64,
-    pub origin_chain: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub mint: Pubkey,
-    pub recipient: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub completed_by: Pubkey,
 }
 
 #[event]
-pub struct ProgramPaused {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProvisionalRevoked {
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub admin: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub recipient: Pubkey,
 }
 
 #[event]
-pub struct ProgramUnpaused {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EscrowReconciled {
+    pub token_id: u64,
+    pub finding: EscrowFinding,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub cranked_by: Pubkey,
+}
+
+/// Escrow drift reconcile_escrow left for the admin; see `EscrowFinding::needs_admin`
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EscrowDriftFlagged {
+    pub token_id: u64,
+    pub finding: EscrowFinding,
+    pub escrow_amount: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub cranked_by: Pubkey,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MisdeliveryRecovered {
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub admin: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub stranded_owner: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub correct_recipient: Pubkey,
+    pub evidence_hash: [u8; 32],
 }
 
 #[event]
-pub struct MintCreated {
-    pub mint: Pubkey,
-    pub mint_authority: Pubkey,
-    pub decimals: u8,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DepartureReceiptIssued {
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub holder: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub receipt_mint: Pubkey,
+    pub uri: String,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DepartureReceiptBurned {
     pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub holder: Pubkey,
 }
 
 #[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ProgramStateMigrated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub admin: Pubkey,
     pub gas_limit: u64,
 }
 
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StaleAccountsClosed {
+    pub count: u32,
+    pub total_reclaimed_lamports: u64,
+    pub account_type: StaleAccountType,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NFTOriginMigrated {
+    pub token_id: u64,
+    pub from_version: u8,
+    pub to_version: u8,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RegistryMigrated {
+    pub count: u32,
+    pub from_entry_bytes: u16,
+    pub to_entry_bytes: u16,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MigrationIntegrityChecked {
+    pub total_checked: u32,
+    // How many of them failed; MigrationIntegrityReport lists which and why
+    pub failures: u32,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UpgradeAuthorityTransferred {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub previous_authority: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub new_authority: Pubkey,
+    pub timestamp: i64,
+}
+
 // Universal NFT Core Events (Solidity equivalent events)
 #[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TokenTransfer {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub receiver: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
     pub destination: [u8; 20],
     pub token_id: u64,
     pub uri: String,
 }
 
 #[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TokenTransferReceived {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub receiver: Pubkey,
     pub token_id: u64,
     pub uri: String,
 }
 
 #[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TokenTransferToDestination {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub receiver: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
     pub destination: [u8; 20],
     pub token_id: u64,
     pub uri: String,
@@ -449,4 +6790,611 @@ pub enum ErrorCode {
     TokenIdOverflow,
     #[msg("Next token id mismatch between client and program state")]
     NextTokenIdMismatch,
-}
\ No newline at end of file
+    #[msg("Unsupported swap router version")]
+    InvalidSwapRouterVersion,
+    #[msg("Swap fee tier does not fit in a uint24")]
+    InvalidSwapFeeTier,
+    #[msg("Invalid fee discount tiers")]
+    InvalidFeeDiscountTiers,
+    #[msg("Fee treasury does not match the fee config")]
+    FeeTreasuryMismatch,
+    #[msg("Inbound deliveries are halted by the circuit breaker")]
+    InboundCircuitBreakerTripped,
+    #[msg("Bridged NFT is already live on Solana")]
+    NFTAlreadyDelivered,
+    #[msg("NFT origin is bound to a different mint")]
+    BridgedMintMismatch,
+    #[msg("Pong does not answer a ping sent by this program")]
+    UnknownPing,
+    #[msg("Reported anomaly could not be reproduced")]
+    AnomalyNotReproduced,
+    #[msg("Invalid circuit breaker configuration")]
+    InvalidCircuitBreakerConfig,
+    #[msg("Royalty creators must be unique and their shares must sum to 100")]
+    InvalidRoyaltyShares,
+    #[msg("Too many creators credited for this collection")]
+    TooManyRoyaltyCreators,
+    #[msg("No royalties owed to this creator")]
+    NoRoyaltiesOwed,
+    #[msg("Gas limit is outside the allowed range")]
+    GasLimitOutOfRange,
+    #[msg("Price feed is not a valid, fully verified Pyth price update")]
+    InvalidPriceFeed,
+    #[msg("Price feed update is too old")]
+    StalePriceFeed,
+    #[msg("Chain id must be non-zero")]
+    InvalidChainId,
+    #[msg("Reservation payment is below the reservation fee")]
+    InsufficientReservationPayment,
+    #[msg("Token id is reserved by another user")]
+    TokenIdReserved,
+    #[msg("Reservation has not expired yet")]
+    ReservationNotExpired,
+    #[msg("Unknown chain trust level")]
+    InvalidTrustLevel,
+    #[msg("Provisional delivery needs the mint authority's escrow token account")]
+    ProvisionalEscrowMismatch,
+    #[msg("Token account does not belong to the provisional recipient")]
+    ProvisionalRecipientMismatch,
+    #[msg("NFT is not held as a provisional delivery")]
+    NotProvisional,
+    #[msg("Provisional hold has not elapsed yet")]
+    ProvisionalHoldActive,
+    #[msg("Provisional hold has already elapsed")]
+    ProvisionalHoldElapsed,
+    #[msg("Unknown cross-chain message version")]
+    UnknownMessageVersion,
+    #[msg("Minter has not passed the required KYC level")]
+    KYCRequired,
+    #[msg("Connected contract registry is full")]
+    RegistryFull,
+    #[msg("ZRC-20 is not in the connected contract registry")]
+    NotInRegistry,
+    #[msg("Cancellation window for this transfer has passed")]
+    CancelWindowElapsed,
+    #[msg("Program cannot mint this token; its mint authority is not the program PDA")]
+    MintAuthorityNotProgram,
+    #[msg("Batch must hold between 1 and MAX_BATCH_RECEIVE messages")]
+    BatchTooLarge,
+    #[msg("Batch accounts do not match the messages")]
+    InvalidBatchAccounts,
+    #[msg("Deliveries from a provisional chain cannot be batched")]
+    ProvisionalBatchUnsupported,
+    #[msg("Signer is not the royalty recipient at that index")]
+    RoyaltyRecipientMismatch,
+    #[msg("The same account was passed in two roles")]
+    AccountAliasing,
+    #[msg("Token account is not owned by the token program")]
+    InvalidTokenAccountOwner,
+    #[msg("Mint authority is not the expected account")]
+    MintAuthorityMismatch,
+    #[msg("Unknown pause reason code")]
+    InvalidPauseReason,
+    #[msg("Scheduled unpause must be in the future")]
+    InvalidPauseSchedule,
+    #[msg("Invalid raffle parameters")]
+    InvalidRaffleConfig,
+    #[msg("Raffle is not accepting this action in its current state")]
+    RaffleClosed,
+    #[msg("All raffle tickets are sold")]
+    RaffleSoldOut,
+    #[msg("Raffle has not ended yet")]
+    RaffleNotEnded,
+    #[msg("Raffle has no tickets")]
+    NoRaffleTickets,
+    #[msg("Ticket does not match the drawn winner index")]
+    WinnerIndexMismatch,
+    #[msg("Departure receipt accounts are missing or hold no receipt")]
+    ReceiptRequired,
+    #[msg("Receipt URI base is too long")]
+    InvalidReceiptConfig,
+    #[msg("Final receiver must be a 20-byte address")]
+    InvalidFinalReceiver,
+    #[msg("Final chain cannot be reached from Solana")]
+    InvalidFinalChain,
+    #[msg("Connected contract has not answered a ping recently")]
+    ContractUnresponsive,
+    #[msg("New upgrade authority must be a different, non-default key")]
+    InvalidUpgradeAuthority,
+    #[msg("Token id is on the wrong side of native_id_base")]
+    TokenIdRangeConflict,
+    #[msg("Relayer allowlist is on and the signer holds no relayer role")]
+    RelayerRequired,
+    #[msg("Delegate has no approved amount left on the token account")]
+    InsufficientDelegatedAmount,
+    #[msg("Merkle root accounts don't match the token ids, or a token isn't held by one owner")]
+    InvalidMerkleRootAccounts,
+    #[msg("Heartbeat sent again before its minimum interval elapsed")]
+    HeartbeatTooSoon,
+    #[msg("Metadata URI already arrived under the maximum number of token ids this window")]
+    URIAlreadyClaimed,
+    #[msg("Outbound message exceeds max_message_bytes even with a hash-only URI")]
+    MessageTooLarge,
+    #[msg("Config snapshot account is not one of the program's config accounts")]
+    InvalidSnapshotAccounts,
+    #[msg("No ed25519 attestation by the attestation key over this token's metadata precedes the instruction")]
+    InvalidAttestation,
+    #[msg("NFT already has a metadata URI; replacing it needs the admin")]
+    MetadataAlreadyAttached,
+    #[msg("Sender holds less SOL than the transfer reserve")]
+    InsufficientUserBalance,
+    #[msg("Custodial token account is missing, not the bound one, or for another mint")]
+    CustodialAccountMismatch,
+    #[msg("Recipient token account is not the recipient's ATA for the mint")]
+    RecipientTokenAccountMismatch,
+    #[msg("Memo exceeds MAX_MEMO_BYTES")]
+    MemoTooLong,
+    #[msg("NFT origin was created at or after the recovery cutoff")]
+    MisdeliveryAfterCutoff,
+    #[msg("Token account is not a misdelivery: it must hold the NFT under a zero-padded owner")]
+    NotMisdelivered,
+    #[msg("Setter called with the value already stored")]
+    NoChange,
+    #[msg("Value is not allowed for this setting: the default key, the system program or this program")]
+    InvalidAdminValue,
+    #[msg("Event log read starts past the ring or asks for more than MAX_EVENT_LOG_READ entries")]
+    EventLogReadOutOfRange,
+    #[msg("Inbound NFTs need the recipient's acceptance: deliver singly with an inbound offer and the escrow")]
+    AcceptanceRequired,
+    #[msg("Inbound offer has expired")]
+    InboundOfferExpired,
+    #[msg("Inbound offer passed for a delivery that doesn't need acceptance")]
+    UnexpectedInboundOffer,
+    #[msg("Metadata exceeds the Token Metadata program's limits")]
+    MetadataRejected,
+    #[msg("NFT has no deferred metadata to retry")]
+    MetadataNotPending,
+    #[msg("Creator fee share exceeds 10000 basis points")]
+    InvalidFeeSplit,
+    #[msg("Fee creator is not the collection's first creator")]
+    FeeCreatorMismatch,
+    #[msg("Instruction doesn't match the inbound mode: queue and execute two-phase messages, receive one-phase ones")]
+    InboundModeMismatch,
+    #[msg("Recipient does not match the queued inbound message")]
+    PendingInboundRecipientMismatch,
+    #[msg("Too many metadata requests in this slot; retry in a later slot")]
+    MetadataSyncThrottled,
+    #[msg("Pending transfer is for another token")]
+    PendingTransferTokenMismatch,
+    #[msg("Pass nft_origin when lazy origins are disabled, mint_only when they are enabled")]
+    LazyOriginAccountsMismatch,
+    #[msg("An encryption key needs the NFT origin; set it once the origin exists")]
+    EncryptionKeyNeedsOrigin,
+    #[msg("Origin policy needs a 1 to 32 byte contract, at most 10000 royalty basis points and a royalty recipient when royalties are set")]
+    InvalidOriginPolicy,
+    #[msg("Remaining account is not the origin policy of this delivery")]
+    OriginPolicyMismatch,
+    #[msg("ZRC-20 gas vault accounts do not match the vault or the transfer's destination")]
+    ZRC20GasVaultMismatch,
+    #[msg("An accounting invariant does not hold; the log names it")]
+    InvariantViolated,
+    #[msg("Lazy reveal needs 1 to 32 traits, a URI base of at most 128 bytes and no reveal requested yet")]
+    InvalidLazyRevealConfig,
+    #[msg("The collection's reveal time has not come")]
+    RevealNotDue,
+    #[msg("No reveal has been requested for this collection")]
+    RevealNotRequested,
+    #[msg("Token is not waiting for this collection's reveal")]
+    NotAwaitingReveal,
+    #[msg("Lazy-reveal mints need the NFT origin to hold their placeholder")]
+    LazyRevealNeedsOrigin,
+    #[msg("Destination chain's contract does not accept batched transfers")]
+    BatchTransferUnsupported,
+    #[msg("Batch must hold between 1 and MAX_BATCH_TRANSFER distinct token ids in ascending order")]
+    InvalidBatchTransfer,
+    #[msg("Chain id is not an active chain in ZetaChain's chain registry")]
+    UnrecognizedChainId,
+    #[msg("Account is not ZetaChain's chain registry for the configured observer program")]
+    ChainRegistryMismatch,
+    #[msg("Inbound offer is for another token or mint")]
+    InboundOfferMismatch,
+    #[msg("Metrics can only be opened for the current or the next hour")]
+    InvalidHourBucket,
+    #[msg("Metrics bucket is still within its retention period")]
+    MetricsNotStale,
+    #[msg("Transfer note is longer than 128 bytes")]
+    NoteTooLong,
+    #[msg("Transfer note is not valid UTF-8")]
+    InvalidNoteEncoding,
+    #[msg("Config export was written in an unsupported format version")]
+    UnsupportedConfigVersion,
+    #[msg("Config export page is truncated or malformed")]
+    InvalidConfigBlob,
+    #[msg("Config export page does not exist")]
+    InvalidConfigPage,
+    #[msg("A different entry is already held for this ZRC-20, chain or origin; import with force to replace it")]
+    ConfigEntryExists,
+    #[msg("Transfer insurance has no premium set")]
+    InsuranceNotOffered,
+    #[msg("Transfer insurance already covers the next transfer")]
+    InsuranceAlreadyActive,
+    #[msg("Pending transfer was already reported as reverted")]
+    TransferNotPending,
+    #[msg("Minting the token failed; the log has the token program's error")]
+    MintToFailed,
+    #[msg("Burning the token failed; the log has the token program's error")]
+    BurnFailed,
+    #[msg("Moving tokens failed; the log has the token program's error")]
+    TokenTransferFailed,
+    #[msg("Approving, freezing, thawing or closing a token account failed; the log has the token program's error")]
+    TokenAccountUpdateFailed,
+    #[msg("Creating the token metadata failed; the log has the metadata program's error")]
+    MetadataCreateFailed,
+    #[msg("Creating the associated token account failed; the log has the inner error")]
+    AtaCreateFailed,
+    #[msg("Batch close must list between 1 and MAX_BATCH_CLOSE accounts, passed in the same order as remaining accounts")]
+    InvalidBatchClose,
+    #[msg("Signer is not an active appraiser")]
+    AppraiserNotAuthorized,
+    #[msg("An appraisal needs a value and a methodology of at most MAX_APPRAISAL_METHODOLOGY_BYTES, and must be of the token asked about")]
+    InvalidAppraisal,
+    #[msg("Value for a recipient that can't hold it needs the recipient's InboundValueClaim PDA")]
+    InboundValueClaimMismatch,
+    #[msg("Integrity check must list between 1 and MAX_INTEGRITY_CHECK_BATCH token ids, with their NFTOrigin PDAs as remaining accounts in the same order")]
+    InvalidIntegrityBatch,
+    #[msg("An ownership attestation must count for between 1 and MAX_OWNERSHIP_ATTESTATION_SECONDS")]
+    InvalidAttestationValidity,
+    #[msg("Only NFTs with a recorded origin on another chain can have ownership attestations relayed, with the registry and gateway passed")]
+    AttestationNotRelayable,
+    #[msg("A co-owner must be another wallet than the holder")]
+    InvalidCoOwner,
+    #[msg("Signer is not an owner of this co-owned NFT")]
+    NotCoOwner,
+    #[msg("A co-owned NFT needs its CoOwnershipRecord to leave, and with require_both_signatures the other owner's consent for the record's current nonce")]
+    CoOwnerConsentRequired,
+    #[msg("More than one unit of a token needs allow_semi_fungible")]
+    SemiFungibleDisabled,
+    #[msg("A semi-fungible mint needs zero decimals")]
+    SemiFungibleDecimals,
+    #[msg("A semi-fungible mint needs its NFT origin; mint it while lazy origins are disabled")]
+    SemiFungibleNeedsOrigin,
+    #[msg("Semi-fungible units are delivered straight to the recipient, never held or offered in escrow")]
+    SemiFungibleNeedsDirectDelivery,
+    #[msg("Semi-fungible tokens leave through transfer_cross_chain, which carries their amount")]
+    SemiFungibleNotBatchable,
+    #[msg("The NFT is rented out and can't leave Solana until its rental lock expires")]
+    RentalLocked,
+    #[msg("A rental lock must end in the future and within MAX_RENTAL_LOCK_SECONDS")]
+    InvalidRentalLock,
+    #[msg("Only the signer that set a rental lock can clear it")]
+    NotRentalLocker,
+    #[msg("The inbound receipt doesn't record this message at this slot")]
+    InboundReceiptMismatch,
+    #[msg("list_pending_inbound takes a limit of 1 to MAX_PENDING_INBOUND_LIST and PendingInbound accounts")]
+    InvalidPendingInboundList,
+    #[msg("Called back into the program during a gateway CPI, or through CPI where only a top-level call is allowed")]
+    ReentrancyDetected,
+    #[msg("Forced recalls are turned off")]
+    ForcedRecallDisabled,
+    #[msg("A forced recall needs a single-unit NFT from another chain, a matching token id and a return receiver")]
+    InvalidForcedRecall,
+    #[msg("Only the connected contract registered for the NFT's origin chain can recall it")]
+    RecallSenderNotConnected,
+    #[msg("The token already has a recall waiting out its dispute window")]
+    ForcedRecallPending,
+    #[msg("The token has no recall waiting to be contested or executed")]
+    ForcedRecallNotPending,
+    #[msg("The recall's dispute window is still open")]
+    RecallDisputeWindowOpen,
+    #[msg("The recall's dispute window has closed")]
+    RecallDisputeWindowElapsed,
+    #[msg("Only a holder of the token can contest its recall")]
+    NotRecallHolder,
+    #[msg("The recipient isn't the Solana wallet the message's receiver word names")]
+    InboundRecipientMismatch,
+    #[msg("The transfer hasn't been reported reverted; its message may still arrive")]
+    TransferNotReverted,
+    #[msg("The insurance fund already paid out for this reverted transfer")]
+    TransferAlreadyCompensated,
+    #[msg("The NFTOrigin is at an older layout; run migrate_nft_origin first")]
+    NFTOriginNeedsMigration,
+    #[msg("The seller fee config isn't for the collection being minted into")]
+    SellerFeeConfigMismatch,
+    #[msg("The URI isn't the one the token was minted with")]
+    LazyOriginUriMismatch,
+    #[msg("lazy_reveal_config must be the named collection's LazyRevealConfig PDA, and only passed with a collection")]
+    LazyRevealConfigMismatch,
+    #[msg("The collection's reveal has been requested; it takes no more mints")]
+    RevealAlreadyRequested,
+    #[msg("Config accounts must be the chain config or origin policy PDAs of the page's entries, in order")]
+    ConfigAccountMismatch,
+    #[msg("Rent of a closed account must go back to whoever paid for it")]
+    RentPayerMismatch,
+    #[msg("Batched messages can't carry value; send it with a single delivery")]
+    ValueNotBatchable,
+    #[msg("A natively minted token can only be restored with its mint authority's signature")]
+    NativeMintAuthorityRequired,
+    #[msg("Collection has no royalty creators to credit")]
+    NoRoyaltyCreators,
+    #[msg("ZRC-20 swaps run on ZetaChain; Solana has no router to swap with")]
+    SwapUnsupported,
+}
+
+/// Custom error number `result` failed with, however it surfaced: as an Anchor error,
+/// or as the raw `ProgramError::Custom` a CPI hands back
+pub fn error_code_number(result: &Result<()>) -> Option<u32> {
+    result.as_ref().err().and_then(error_number)
+}
+
+fn error_number(error: &Error) -> Option<u32> {
+    match error {
+        Error::AnchorError(error) => Some(error.error_code_number),
+        Error::ProgramError(error) => match error.program_error {
+            ProgramError::Custom(number) => Some(number),
+            _ => None,
+        },
+    }
+}
+
+/// `map_err` for a CPI: fails with `step`'s error, so the failure names the step, after
+/// logging the inner error and its number, which `step` would otherwise hide. A callee that
+/// fails aborts the whole transaction, so this only sees errors raised on our side of the
+/// call; checks like `require_burnable` turn the callee's own failures into `step` up front
+pub fn cpi_failed<E: Into<Error>>(step: impl Into<Error>) -> impl FnOnce(E) -> Error {
+    move |inner| {
+        let inner = inner.into();
+        msg!("CPI failed with error number {:?}: {}", error_number(&inner), inner);
+        step.into()
+    }
+}
+
+/// True when `result` failed with `code` (ERROR_CODE_OFFSET + its position in `ErrorCode`)
+pub fn match_error_code(result: &Result<()>, code: ErrorCode) -> bool {
+    error_code_number(result) == Some(u32::from(code))
+}
+
+/// True when `result` failed with the core error `code` (CORE_ERROR_CODE_OFFSET + its position)
+pub fn match_core_error_code(result: &Result<()>, code: UniversalNFTCoreError) -> bool {
+    error_code_number(result) == Some(u32::from(code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Error numbers are part of the program's interface: clients match on them, so a variant
+    // must never move. New variants go at the end of their enum, with a row here
+    const ERROR_CODES: &[(ErrorCode, u32)] = &[
+        (ErrorCode::ProgramPaused, 6000),
+        (ErrorCode::Unauthorized, 6001),
+        (ErrorCode::InvalidCrossChainMessage, 6002),
+        (ErrorCode::NFTOriginNotFound, 6003),
+        (ErrorCode::InsufficientTokens, 6004),
+        (ErrorCode::TokenIdOverflow, 6005),
+        (ErrorCode::NextTokenIdMismatch, 6006),
+        (ErrorCode::InvalidSwapRouterVersion, 6007),
+        (ErrorCode::InvalidSwapFeeTier, 6008),
+        (ErrorCode::InvalidFeeDiscountTiers, 6009),
+        (ErrorCode::FeeTreasuryMismatch, 6010),
+        (ErrorCode::InboundCircuitBreakerTripped, 6011),
+        (ErrorCode::NFTAlreadyDelivered, 6012),
+        (ErrorCode::BridgedMintMismatch, 6013),
+        (ErrorCode::UnknownPing, 6014),
+        (ErrorCode::AnomalyNotReproduced, 6015),
+        (ErrorCode::InvalidCircuitBreakerConfig, 6016),
+        (ErrorCode::InvalidRoyaltyShares, 6017),
+        (ErrorCode::TooManyRoyaltyCreators, 6018),
+        (ErrorCode::NoRoyaltiesOwed, 6019),
+        (ErrorCode::GasLimitOutOfRange, 6020),
+        (ErrorCode::InvalidPriceFeed, 6021),
+        (ErrorCode::StalePriceFeed, 6022),
+        (ErrorCode::InvalidChainId, 6023),
+        (ErrorCode::InsufficientReservationPayment, 6024),
+        (ErrorCode::TokenIdReserved, 6025),
+        (ErrorCode::ReservationNotExpired, 6026),
+        (ErrorCode::InvalidTrustLevel, 6027),
+        (ErrorCode::ProvisionalEscrowMismatch, 6028),
+        (ErrorCode::ProvisionalRecipientMismatch, 6029),
+        (ErrorCode::NotProvisional, 6030),
+        (ErrorCode::ProvisionalHoldActive, 6031),
+        (ErrorCode::ProvisionalHoldElapsed, 6032),
+        (ErrorCode::UnknownMessageVersion, 6033),
+        (ErrorCode::KYCRequired, 6034),
+        (ErrorCode::RegistryFull, 6035),
+        (ErrorCode::NotInRegistry, 6036),
+        (ErrorCode::CancelWindowElapsed, 6037),
+        (ErrorCode::MintAuthorityNotProgram, 6038),
+        (ErrorCode::BatchTooLarge, 6039),
+        (ErrorCode::InvalidBatchAccounts, 6040),
+        (ErrorCode::ProvisionalBatchUnsupported, 6041),
+        (ErrorCode::RoyaltyRecipientMismatch, 6042),
+        (ErrorCode::AccountAliasing, 6043),
+        (ErrorCode::InvalidTokenAccountOwner, 6044),
+        (ErrorCode::MintAuthorityMismatch, 6045),
+        (ErrorCode::InvalidPauseReason, 6046),
+        (ErrorCode::InvalidPauseSchedule, 6047),
+        (ErrorCode::InvalidRaffleConfig, 6048),
+        (ErrorCode::RaffleClosed, 6049),
+        (ErrorCode::RaffleSoldOut, 6050),
+        (ErrorCode::RaffleNotEnded, 6051),
+        (ErrorCode::NoRaffleTickets, 6052),
+        (ErrorCode::WinnerIndexMismatch, 6053),
+        (ErrorCode::ReceiptRequired, 6054),
+        (ErrorCode::InvalidReceiptConfig, 6055),
+        (ErrorCode::InvalidFinalReceiver, 6056),
+        (ErrorCode::InvalidFinalChain, 6057),
+        (ErrorCode::ContractUnresponsive, 6058),
+        (ErrorCode::InvalidUpgradeAuthority, 6059),
+        (ErrorCode::TokenIdRangeConflict, 6060),
+        (ErrorCode::RelayerRequired, 6061),
+        (ErrorCode::InsufficientDelegatedAmount, 6062),
+        (ErrorCode::InvalidMerkleRootAccounts, 6063),
+        (ErrorCode::HeartbeatTooSoon, 6064),
+        (ErrorCode::URIAlreadyClaimed, 6065),
+        (ErrorCode::MessageTooLarge, 6066),
+        (ErrorCode::InvalidSnapshotAccounts, 6067),
+        (ErrorCode::InvalidAttestation, 6068),
+        (ErrorCode::MetadataAlreadyAttached, 6069),
+        (ErrorCode::InsufficientUserBalance, 6070),
+        (ErrorCode::CustodialAccountMismatch, 6071),
+        (ErrorCode::RecipientTokenAccountMismatch, 6072),
+        (ErrorCode::MemoTooLong, 6073),
+        (ErrorCode::MisdeliveryAfterCutoff, 6074),
+        (ErrorCode::NotMisdelivered, 6075),
+        (ErrorCode::NoChange, 6076),
+        (ErrorCode::InvalidAdminValue, 6077),
+        (ErrorCode::EventLogReadOutOfRange, 6078),
+        (ErrorCode::AcceptanceRequired, 6079),
+        (ErrorCode::InboundOfferExpired, 6080),
+        (ErrorCode::UnexpectedInboundOffer, 6081),
+        (ErrorCode::MetadataRejected, 6082),
+        (ErrorCode::MetadataNotPending, 6083),
+        (ErrorCode::InvalidFeeSplit, 6084),
+        (ErrorCode::FeeCreatorMismatch, 6085),
+        (ErrorCode::InboundModeMismatch, 6086),
+        (ErrorCode::PendingInboundRecipientMismatch, 6087),
+        (ErrorCode::MetadataSyncThrottled, 6088),
+        (ErrorCode::PendingTransferTokenMismatch, 6089),
+        (ErrorCode::LazyOriginAccountsMismatch, 6090),
+        (ErrorCode::EncryptionKeyNeedsOrigin, 6091),
+        (ErrorCode::InvalidOriginPolicy, 6092),
+        (ErrorCode::OriginPolicyMismatch, 6093),
+        (ErrorCode::ZRC20GasVaultMismatch, 6094),
+        (ErrorCode::InvariantViolated, 6095),
+        (ErrorCode::InvalidLazyRevealConfig, 6096),
+        (ErrorCode::RevealNotDue, 6097),
+        (ErrorCode::RevealNotRequested, 6098),
+        (ErrorCode::NotAwaitingReveal, 6099),
+        (ErrorCode::LazyRevealNeedsOrigin, 6100),
+        (ErrorCode::BatchTransferUnsupported, 6101),
+        (ErrorCode::InvalidBatchTransfer, 6102),
+        (ErrorCode::UnrecognizedChainId, 6103),
+        (ErrorCode::ChainRegistryMismatch, 6104),
+        (ErrorCode::InboundOfferMismatch, 6105),
+        (ErrorCode::InvalidHourBucket, 6106),
+        (ErrorCode::MetricsNotStale, 6107),
+        (ErrorCode::NoteTooLong, 6108),
+        (ErrorCode::InvalidNoteEncoding, 6109),
+        (ErrorCode::UnsupportedConfigVersion, 6110),
+        (ErrorCode::InvalidConfigBlob, 6111),
+        (ErrorCode::InvalidConfigPage, 6112),
+        (ErrorCode::ConfigEntryExists, 6113),
+        (ErrorCode::InsuranceNotOffered, 6114),
+        (ErrorCode::InsuranceAlreadyActive, 6115),
+        (ErrorCode::TransferNotPending, 6116),
+        (ErrorCode::MintToFailed, 6117),
+        (ErrorCode::BurnFailed, 6118),
+        (ErrorCode::TokenTransferFailed, 6119),
+        (ErrorCode::TokenAccountUpdateFailed, 6120),
+        (ErrorCode::MetadataCreateFailed, 6121),
+        (ErrorCode::AtaCreateFailed, 6122),
+        (ErrorCode::InvalidBatchClose, 6123),
+        (ErrorCode::AppraiserNotAuthorized, 6124),
+        (ErrorCode::InvalidAppraisal, 6125),
+        (ErrorCode::InboundValueClaimMismatch, 6126),
+        (ErrorCode::InvalidIntegrityBatch, 6127),
+        (ErrorCode::InvalidAttestationValidity, 6128),
+        (ErrorCode::AttestationNotRelayable, 6129),
+        (ErrorCode::InvalidCoOwner, 6130),
+        (ErrorCode::NotCoOwner, 6131),
+        (ErrorCode::CoOwnerConsentRequired, 6132),
+        (ErrorCode::SemiFungibleDisabled, 6133),
+        (ErrorCode::SemiFungibleDecimals, 6134),
+        (ErrorCode::SemiFungibleNeedsOrigin, 6135),
+        (ErrorCode::SemiFungibleNeedsDirectDelivery, 6136),
+        (ErrorCode::SemiFungibleNotBatchable, 6137),
+        (ErrorCode::RentalLocked, 6138),
+        (ErrorCode::InvalidRentalLock, 6139),
+        (ErrorCode::NotRentalLocker, 6140),
+        (ErrorCode::InboundReceiptMismatch, 6141),
+        (ErrorCode::InvalidPendingInboundList, 6142),
+        (ErrorCode::ReentrancyDetected, 6143),
+        (ErrorCode::ForcedRecallDisabled, 6144),
+        (ErrorCode::InvalidForcedRecall, 6145),
+        (ErrorCode::RecallSenderNotConnected, 6146),
+        (ErrorCode::ForcedRecallPending, 6147),
+        (ErrorCode::ForcedRecallNotPending, 6148),
+        (ErrorCode::RecallDisputeWindowOpen, 6149),
+        (ErrorCode::RecallDisputeWindowElapsed, 6150),
+        (ErrorCode::NotRecallHolder, 6151),
+        (ErrorCode::InboundRecipientMismatch, 6152),
+        (ErrorCode::TransferNotReverted, 6153),
+        (ErrorCode::TransferAlreadyCompensated, 6154),
+        (ErrorCode::NFTOriginNeedsMigration, 6155),
+        (ErrorCode::SellerFeeConfigMismatch, 6156),
+        (ErrorCode::LazyOriginUriMismatch, 6157),
+        (ErrorCode::LazyRevealConfigMismatch, 6158),
+        (ErrorCode::RevealAlreadyRequested, 6159),
+        (ErrorCode::ConfigAccountMismatch, 6160),
+        (ErrorCode::RentPayerMismatch, 6161),
+        (ErrorCode::ValueNotBatchable, 6162),
+        (ErrorCode::NativeMintAuthorityRequired, 6163),
+        (ErrorCode::NoRoyaltyCreators, 6164),
+        (ErrorCode::SwapUnsupported, 6165),
+    ];
+
+    const CORE_ERROR_CODES: &[(UniversalNFTCoreError, u32)] = &[
+        (UniversalNFTCoreError::InvalidCrossChainMessage, 7000),
+        (UniversalNFTCoreError::UnauthorizedSender, 7001),
+        (UniversalNFTCoreError::Unauthorized, 7002),
+        (UniversalNFTCoreError::InvalidDestination, 7003),
+        (UniversalNFTCoreError::TransferFailed, 7004),
+        (UniversalNFTCoreError::InvalidGasLimit, 7005),
+        (UniversalNFTCoreError::InvalidAddress, 7006),
+        (UniversalNFTCoreError::InvalidUriEncoding, 7007),
+        (UniversalNFTCoreError::InvalidMessageFormat, 7008),
+        (UniversalNFTCoreError::InvalidAmount, 7009),
+        (UniversalNFTCoreError::GatewayCallFailed, 7010),
+        (UniversalNFTCoreError::BridgeBackendUnsupported, 7011),
+    ];
+
+    fn anchor_error(error: Error) -> AnchorError {
+        match error {
+            Error::AnchorError(error) => *error,
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+
+    #[test]
+    fn every_error_code_keeps_its_number() {
+        for &(code, number) in ERROR_CODES {
+            let error = anchor_error(code.into());
+            assert_eq!(error.error_name, format!("{:?}", code));
+            assert_eq!(error.error_code_number, number, "{:?} moved", code);
+            assert!(match_error_code(&Err(code.into()), code));
+        }
+        for &(code, number) in CORE_ERROR_CODES {
+            let error = anchor_error(code.into());
+            assert_eq!(error.error_name, format!("{:?}", code));
+            assert_eq!(error.error_code_number, number, "{:?} moved", code);
+            assert!(match_core_error_code(&Err(code.into()), code));
+        }
+    }
+
+    #[test]
+    fn error_codes_match_only_their_own_number() {
+        let result: Result<()> = Err(ErrorCode::Unauthorized.into());
+        assert!(!match_error_code(&result, ErrorCode::ProgramPaused));
+        assert!(!match_core_error_code(&result, UniversalNFTCoreError::Unauthorized));
+        assert!(!match_error_code(&Ok(()), ErrorCode::Unauthorized));
+    }
+
+    #[test]
+    fn declared_chain_ids_must_agree_with_their_genesis_hash() {
+        let check = check_declared_solana_chain;
+        assert!(check(&GENESIS_HASH_SOLANA_MAINNET, CHAIN_ID_SOLANA_MAINNET).is_ok());
+        assert!(check(&GENESIS_HASH_SOLANA_DEVNET, CHAIN_ID_SOLANA_DEVNET).is_ok());
+        assert!(check(&GENESIS_HASH_SOLANA_TESTNET, CHAIN_ID_SOLANA_LOCALNET).is_ok());
+        assert!(check(&[7; 32], CHAIN_ID_SOLANA_LOCALNET).is_ok());
+
+        let mismatches = [
+            (GENESIS_HASH_SOLANA_MAINNET, CHAIN_ID_SOLANA_DEVNET),
+            (GENESIS_HASH_SOLANA_DEVNET, CHAIN_ID_SOLANA_LOCALNET),
+            // Mainnet and devnet can't be claimed without their hash, which a local validator
+            // or testnet doesn't have
+            ([7; 32], CHAIN_ID_SOLANA_MAINNET),
+            (GENESIS_HASH_SOLANA_TESTNET, CHAIN_ID_SOLANA_DEVNET),
+            ([0; 32], CHAIN_ID_ZETACHAIN_TESTNET),
+        ];
+        for (hash, chain_id) in mismatches {
+            assert!(match_error_code(&check(&hash, chain_id), ErrorCode::InvalidChainId), "{}", chain_id);
+        }
+    }
+
+    #[test]
+    fn error_codes_match_when_a_cpi_hands_them_back() {
+        let number = u32::from(ErrorCode::InsufficientTokens);
+        let result: Result<()> = Err(ProgramError::Custom(number).into());
+        assert!(match_error_code(&result, ErrorCode::InsufficientTokens));
+    }
+}
@@ -0,0 +1,376 @@
+//! Seeds and derivations of every PDA the program owns. The `#[account]` constraints use
+//! the seed constants directly and handlers derive through the functions, so a seed can't
+//! drift between the two. Every seed fits Solana's 32-byte limit: the prefixes are checked
+//! at compile time and the variable parts are fixed-size arrays
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::pubkey::MAX_SEED_LEN;
+
+pub const PROGRAM_STATE_SEED: &[u8] = b"test";
+pub const MINT_AUTHORITY_SEED: &[u8] = b"mint_authority";
+pub const REGISTRY_SEED: &[u8] = b"registry";
+pub const RELAYER_CONFIG_SEED: &[u8] = b"relayer_config";
+pub const RELAYER_ROLE_SEED: &[u8] = b"relayer_role";
+pub const NFT_INDEX_SEED: &[u8] = b"nft_index";
+pub const BRIDGED_MINT_SEED: &[u8] = b"bridged_mint";
+pub const RECEIPT_MINT_SEED: &[u8] = b"receipt_mint";
+pub const RECEIPT_CONFIG_SEED: &[u8] = b"receipt_config";
+pub const DEDUP_WINDOW_SEED: &[u8] = b"dedup_window";
+pub const PENDING_TRANSFER_SEED: &[u8] = b"pending_xfer";
+pub const RESERVATION_SEED: &[u8] = b"reservation";
+pub const CHAIN_STATS_SEED: &[u8] = b"chain_stats";
+pub const CHAIN_GAS_SEED: &[u8] = b"chain_gas";
+pub const FEE_CONFIG_SEED: &[u8] = b"fee_config";
+pub const INSURANCE_FUND_SEED: &[u8] = b"insurance_fund";
+pub const INSURANCE_OPT_IN_SEED: &[u8] = b"insurance_opt_in";
+pub const SELLER_FEE_CONFIG_SEED: &[u8] = b"seller_fee_config";
+pub const COLLECTION_STATS_SEED: &[u8] = b"collection_stats";
+pub const COLLECTION_ROYALTY_SEED: &[u8] = b"collection_royalty";
+pub const COLLECTION_AUTHORITY_SEED: &[u8] = b"collection_authority";
+pub const MERKLE_ROOT_SEED: &[u8] = b"merkle_root";
+pub const SECURITY_STATE_SEED: &[u8] = b"security_state";
+pub const KYC_CONFIG_SEED: &[u8] = b"kyc_config";
+pub const HEARTBEAT_SEED: &[u8] = b"heartbeat";
+pub const RAFFLE_SEED: &[u8] = b"raffle";
+pub const RAFFLE_COUNTER_SEED: &[u8] = b"raffle_counter";
+pub const RAFFLE_TICKET_SEED: &[u8] = b"raffle_ticket";
+pub const UPGRADE_AUTHORITY_SEED: &[u8] = b"upgrade_authority";
+pub const RECEIVER_BINDING_SEED: &[u8] = b"receiver_binding";
+pub const ENCODING_BENCHMARK_SEED: &[u8] = b"encoding_benchmark";
+pub const RECOVERY_CONFIG_SEED: &[u8] = b"recovery_config";
+pub const MISDELIVERY_RECOVERY_SEED: &[u8] = b"misdelivery_recovery";
+pub const EVENT_LOG_SEED: &[u8] = b"event_log";
+pub const INBOUND_OFFER_SEED: &[u8] = b"inbound_offer";
+pub const PENDING_INBOUND_SEED: &[u8] = b"pending_inbound";
+pub const MINT_ONLY_SEED: &[u8] = b"mint_only";
+pub const ORIGIN_POLICY_SEED: &[u8] = b"origin_policy";
+pub const ZRC20_VAULT_SEED: &[u8] = b"zrc20_vault";
+pub const LAZY_REVEAL_SEED: &[u8] = b"lazy_reveal";
+pub const REVEAL_SEED_SEED: &[u8] = b"reveal_seed";
+pub const CHAIN_REGISTRY_CONFIG_SEED: &[u8] = b"chain_registry_config";
+pub const HOURLY_METRICS_SEED: &[u8] = b"hourly_metrics";
+pub const APPRAISAL_SEED: &[u8] = b"appraisal";
+pub const APPRAISAL_AUTHORITY_SEED: &[u8] = b"appraisal_authority";
+pub const INBOUND_VALUE_CLAIM_SEED: &[u8] = b"inbound_value_claim";
+pub const MIGRATION_INTEGRITY_REPORT_SEED: &[u8] = b"migration_integrity";
+pub const OWNERSHIP_ATTESTATION_SEED: &[u8] = b"ownership_attestation";
+pub const CO_OWNERSHIP_SEED: &[u8] = b"co_own";
+pub const CO_OWNER_CONSENT_SEED: &[u8] = b"co_consent";
+pub const INBOUND_RECEIPT_SEED: &[u8] = b"inbound_rcpt";
+pub const FORCED_RECALL_SEED: &[u8] = b"forced_recall";
+// Seed of the ChainRegistry PDA under the ZetaChain observer program, not this one
+pub const ZETA_CHAIN_REGISTRY_SEED: &[u8] = b"chain_registry";
+
+/// `NFTOrigin` is keyed by a single seed: this prefix, the token id LE, then the suffix
+pub const NFT_ORIGIN_SEED_PREFIX: &[u8] = b"nft_origin";
+pub const NFT_ORIGIN_SEED_SUFFIX: &[u8] = b"unique";
+pub const NFT_ORIGIN_SEED_LEN: usize = NFT_ORIGIN_SEED_PREFIX.len() + 8 + NFT_ORIGIN_SEED_SUFFIX.len();
+
+const fn within_seed_limit(seeds: &[&[u8]]) -> bool {
+    let mut i = 0;
+    while i < seeds.len() {
+        if seeds[i].len() > MAX_SEED_LEN {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+const _: () = assert!(NFT_ORIGIN_SEED_LEN <= MAX_SEED_LEN);
+const _: () = assert!(within_seed_limit(&[
+    PROGRAM_STATE_SEED,
+    MINT_AUTHORITY_SEED,
+    REGISTRY_SEED,
+    RELAYER_CONFIG_SEED,
+    RELAYER_ROLE_SEED,
+    NFT_INDEX_SEED,
+    BRIDGED_MINT_SEED,
+    RECEIPT_MINT_SEED,
+    RECEIPT_CONFIG_SEED,
+    DEDUP_WINDOW_SEED,
+    PENDING_TRANSFER_SEED,
+    RESERVATION_SEED,
+    CHAIN_STATS_SEED,
+    CHAIN_GAS_SEED,
+    FEE_CONFIG_SEED,
+    SELLER_FEE_CONFIG_SEED,
+    COLLECTION_STATS_SEED,
+    COLLECTION_ROYALTY_SEED,
+    COLLECTION_AUTHORITY_SEED,
+    MERKLE_ROOT_SEED,
+    SECURITY_STATE_SEED,
+    KYC_CONFIG_SEED,
+    HEARTBEAT_SEED,
+    RAFFLE_SEED,
+    RAFFLE_COUNTER_SEED,
+    RAFFLE_TICKET_SEED,
+    UPGRADE_AUTHORITY_SEED,
+    RECEIVER_BINDING_SEED,
+    ENCODING_BENCHMARK_SEED,
+    RECOVERY_CONFIG_SEED,
+    MISDELIVERY_RECOVERY_SEED,
+    EVENT_LOG_SEED,
+    INBOUND_OFFER_SEED,
+    PENDING_INBOUND_SEED,
+    MINT_ONLY_SEED,
+    ORIGIN_POLICY_SEED,
+    ZRC20_VAULT_SEED,
+    LAZY_REVEAL_SEED,
+    REVEAL_SEED_SEED,
+    CHAIN_REGISTRY_CONFIG_SEED,
+    ZETA_CHAIN_REGISTRY_SEED,
+    HOURLY_METRICS_SEED,
+    INSURANCE_FUND_SEED,
+    INSURANCE_OPT_IN_SEED,
+    APPRAISAL_SEED,
+    APPRAISAL_AUTHORITY_SEED,
+    INBOUND_VALUE_CLAIM_SEED,
+    MIGRATION_INTEGRITY_REPORT_SEED,
+    OWNERSHIP_ATTESTATION_SEED,
+    CO_OWNERSHIP_SEED,
+    CO_OWNER_CONSENT_SEED,
+    INBOUND_RECEIPT_SEED,
+    FORCED_RECALL_SEED,
+]));
+
+/// The single `NFTOrigin` seed for `token_id`
+pub fn nft_origin_seed(token_id: u64) -> [u8; NFT_ORIGIN_SEED_LEN] {
+    let mut seed = [0u8; NFT_ORIGIN_SEED_LEN];
+    let (prefix, rest) = seed.split_at_mut(NFT_ORIGIN_SEED_PREFIX.len());
+    let (id, suffix) = rest.split_at_mut(8);
+    prefix.copy_from_slice(NFT_ORIGIN_SEED_PREFIX);
+    id.copy_from_slice(&token_id.to_le_bytes());
+    suffix.copy_from_slice(NFT_ORIGIN_SEED_SUFFIX);
+    seed
+}
+
+fn derive(seeds: &[&[u8]]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(seeds, &crate::ID)
+}
+
+pub fn program_state() -> (Pubkey, u8) {
+    derive(&[PROGRAM_STATE_SEED])
+}
+
+pub fn mint_authority() -> (Pubkey, u8) {
+    derive(&[MINT_AUTHORITY_SEED])
+}
+
+pub fn registry() -> (Pubkey, u8) {
+    derive(&[REGISTRY_SEED])
+}
+
+pub fn relayer_config() -> (Pubkey, u8) {
+    derive(&[RELAYER_CONFIG_SEED])
+}
+
+pub fn relayer_role(relayer: &Pubkey) -> (Pubkey, u8) {
+    derive(&[RELAYER_ROLE_SEED, relayer.as_ref()])
+}
+
+pub fn nft_origin(token_id: u64) -> (Pubkey, u8) {
+    derive(&[&nft_origin_seed(token_id)])
+}
+
+pub fn nft_index(mint: &Pubkey) -> (Pubkey, u8) {
+    derive(&[NFT_INDEX_SEED, mint.as_ref()])
+}
+
+/// Mint of a token bridged in from `origin_chain`
+pub fn bridged_mint(origin_chain: u64, token_id: u64) -> (Pubkey, u8) {
+    derive(&[BRIDGED_MINT_SEED, &origin_chain.to_le_bytes(), &token_id.to_le_bytes()])
+}
+
+pub fn receipt_mint(token_id: u64) -> (Pubkey, u8) {
+    derive(&[RECEIPT_MINT_SEED, &token_id.to_le_bytes()])
+}
+
+pub fn receipt_config() -> (Pubkey, u8) {
+    derive(&[RECEIPT_CONFIG_SEED])
+}
+
+/// `DeduplicationWindow` of a URI, keyed by `crate::uri_hash`
+pub fn dedup_window(uri_hash: &[u8; 32]) -> (Pubkey, u8) {
+    derive(&[DEDUP_WINDOW_SEED, uri_hash])
+}
+
+pub fn pending_transfer(nonce: u64) -> (Pubkey, u8) {
+    derive(&[PENDING_TRANSFER_SEED, &nonce.to_le_bytes()])
+}
+
+pub fn reservation(token_id: u64) -> (Pubkey, u8) {
+    derive(&[RESERVATION_SEED, &token_id.to_le_bytes()])
+}
+
+pub fn chain_stats(chain_id: u64) -> (Pubkey, u8) {
+    derive(&[CHAIN_STATS_SEED, &chain_id.to_le_bytes()])
+}
+
+pub fn chain_gas(chain_id: u64) -> (Pubkey, u8) {
+    derive(&[CHAIN_GAS_SEED, &chain_id.to_le_bytes()])
+}
+
+pub fn fee_config() -> (Pubkey, u8) {
+    derive(&[FEE_CONFIG_SEED])
+}
+
+pub fn seller_fee_config(collection_id: u64) -> (Pubkey, u8) {
+    derive(&[SELLER_FEE_CONFIG_SEED, &collection_id.to_le_bytes()])
+}
+
+pub fn collection_stats(collection_id: u64) -> (Pubkey, u8) {
+    derive(&[COLLECTION_STATS_SEED, &collection_id.to_le_bytes()])
+}
+
+pub fn collection_royalty(collection_id: u64) -> (Pubkey, u8) {
+    derive(&[COLLECTION_ROYALTY_SEED, &collection_id.to_le_bytes()])
+}
+
+pub fn collection_authority(collection_id: u64) -> (Pubkey, u8) {
+    derive(&[COLLECTION_AUTHORITY_SEED, &collection_id.to_le_bytes()])
+}
+
+pub fn merkle_root(collection_id: u64) -> (Pubkey, u8) {
+    derive(&[MERKLE_ROOT_SEED, &collection_id.to_le_bytes()])
+}
+
+pub fn security_state() -> (Pubkey, u8) {
+    derive(&[SECURITY_STATE_SEED])
+}
+
+pub fn kyc_config() -> (Pubkey, u8) {
+    derive(&[KYC_CONFIG_SEED])
+}
+
+pub fn heartbeat() -> (Pubkey, u8) {
+    derive(&[HEARTBEAT_SEED])
+}
+
+pub fn raffle(raffle_id: u64) -> (Pubkey, u8) {
+    derive(&[RAFFLE_SEED, &raffle_id.to_le_bytes()])
+}
+
+pub fn raffle_counter() -> (Pubkey, u8) {
+    derive(&[RAFFLE_COUNTER_SEED])
+}
+
+pub fn raffle_ticket(raffle_id: u64, ticket_index: u64) -> (Pubkey, u8) {
+    derive(&[RAFFLE_TICKET_SEED, &raffle_id.to_le_bytes(), &ticket_index.to_le_bytes()])
+}
+
+pub fn upgrade_authority() -> (Pubkey, u8) {
+    derive(&[UPGRADE_AUTHORITY_SEED])
+}
+
+pub fn receiver_binding(owner: &Pubkey) -> (Pubkey, u8) {
+    derive(&[RECEIVER_BINDING_SEED, owner.as_ref()])
+}
+
+pub fn encoding_benchmark(uri_len: u16) -> (Pubkey, u8) {
+    derive(&[ENCODING_BENCHMARK_SEED, &uri_len.to_le_bytes()])
+}
+
+pub fn recovery_config() -> (Pubkey, u8) {
+    derive(&[RECOVERY_CONFIG_SEED])
+}
+
+pub fn misdelivery_recovery(token_id: u64) -> (Pubkey, u8) {
+    derive(&[MISDELIVERY_RECOVERY_SEED, &token_id.to_le_bytes()])
+}
+
+pub fn event_log() -> (Pubkey, u8) {
+    derive(&[EVENT_LOG_SEED])
+}
+
+pub fn inbound_offer(token_id: u64, recipient: &Pubkey) -> (Pubkey, u8) {
+    derive(&[INBOUND_OFFER_SEED, &token_id.to_le_bytes(), recipient.as_ref()])
+}
+
+pub fn pending_inbound(message_hash: &[u8; 32]) -> (Pubkey, u8) {
+    derive(&[PENDING_INBOUND_SEED, message_hash])
+}
+
+pub fn mint_only(token_id: u64) -> (Pubkey, u8) {
+    derive(&[MINT_ONLY_SEED, &token_id.to_le_bytes()])
+}
+
+/// `OriginPolicy` of a contract on `origin_chain`, keyed by `crate::origin_contract_hash`
+pub fn origin_policy(origin_chain: u64, origin_contract_hash: &[u8; 32]) -> (Pubkey, u8) {
+    derive(&[ORIGIN_POLICY_SEED, &origin_chain.to_le_bytes(), origin_contract_hash])
+}
+
+pub fn zrc20_vault(zrc20: &[u8; 20]) -> (Pubkey, u8) {
+    derive(&[ZRC20_VAULT_SEED, zrc20])
+}
+
+pub fn lazy_reveal(collection_id: u64) -> (Pubkey, u8) {
+    derive(&[LAZY_REVEAL_SEED, &collection_id.to_le_bytes()])
+}
+
+pub fn reveal_seed(collection_id: u64) -> (Pubkey, u8) {
+    derive(&[REVEAL_SEED_SEED, &collection_id.to_le_bytes()])
+}
+
+pub fn chain_registry_config() -> (Pubkey, u8) {
+    derive(&[CHAIN_REGISTRY_CONFIG_SEED])
+}
+
+pub fn hourly_metrics(hour_bucket: u64) -> (Pubkey, u8) {
+    derive(&[HOURLY_METRICS_SEED, &hour_bucket.to_le_bytes()])
+}
+
+pub fn insurance_fund() -> (Pubkey, u8) {
+    derive(&[INSURANCE_FUND_SEED])
+}
+
+pub fn insurance_opt_in(user: &Pubkey) -> (Pubkey, u8) {
+    derive(&[INSURANCE_OPT_IN_SEED, user.as_ref()])
+}
+
+pub fn appraisal(mint: &Pubkey, appraiser: &Pubkey) -> (Pubkey, u8) {
+    derive(&[APPRAISAL_SEED, mint.as_ref(), appraiser.as_ref()])
+}
+
+pub fn appraisal_authority(appraiser: &Pubkey) -> (Pubkey, u8) {
+    derive(&[APPRAISAL_AUTHORITY_SEED, appraiser.as_ref()])
+}
+
+pub fn inbound_value_claim(recipient: &Pubkey) -> (Pubkey, u8) {
+    derive(&[INBOUND_VALUE_CLAIM_SEED, recipient.as_ref()])
+}
+
+pub fn migration_integrity_report() -> (Pubkey, u8) {
+    derive(&[MIGRATION_INTEGRITY_REPORT_SEED])
+}
+
+pub fn ownership_attestation(token_id: u64) -> (Pubkey, u8) {
+    derive(&[OWNERSHIP_ATTESTATION_SEED, &token_id.to_le_bytes()])
+}
+
+pub fn co_ownership(mint: &Pubkey) -> (Pubkey, u8) {
+    derive(&[CO_OWNERSHIP_SEED, mint.as_ref()])
+}
+
+/// Keyed by the record's `created_at` as well, so a recreated record starts from fresh addresses
+pub fn co_owner_consent(mint: &Pubkey, record_created_at: i64, nonce: u64) -> (Pubkey, u8) {
+    derive(&[
+        CO_OWNER_CONSENT_SEED,
+        mint.as_ref(),
+        &record_created_at.to_le_bytes(),
+        &nonce.to_le_bytes(),
+    ])
+}
+
+pub fn inbound_receipt(message_hash: &[u8; 32]) -> (Pubkey, u8) {
+    derive(&[INBOUND_RECEIPT_SEED, message_hash])
+}
+
+pub fn forced_recall(token_id: u64) -> (Pubkey, u8) {
+    derive(&[FORCED_RECALL_SEED, &token_id.to_le_bytes()])
+}
+
+/// ZetaChain's ChainRegistry account, a PDA of `observer_program` rather than this program
+pub fn zeta_chain_registry(observer_program: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ZETA_CHAIN_REGISTRY_SEED], observer_program)
+}
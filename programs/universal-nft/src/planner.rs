@@ -0,0 +1,1237 @@
+//! Business rules of `transfer_cross_chain`, the inbound delivery path and its replay, kept
+//! apart from the accounts they run against. A handler copies what it reads into a snapshot,
+//! a planner checks the rules against it and the instruction's arguments and returns a plan,
+//! and the handler carries the plan out: its state writes, then its CPIs in order, then its
+//! events.
+//!
+//! Planners never see an `AccountInfo`, the clock or a CPI, so they are unit tested without
+//! a runtime. Checks on the accounts themselves, such as aliasing and PDA addresses, stay in
+//! the handlers.
+
+use anchor_lang::prelude::*;
+
+use crate::{
+    ChainGasConfig, ChainId, CrossChainNftMessage, CrossChainTransferInitiated, DeliveryMode, FeeCollected,
+    InboundNoteReplaced, MessageDecoder, OutboundTrailers, OutboundUriHashed, Provenance, RentalLockCleared, UniversalNFT,
+    UniversalNFTCoreError, UniversalNFTCoreImpl, ZetaChainCallOptions, DEFAULT_MAX_MESSAGE_BYTES,
+    INBOUND_OFFER_WINDOW_SECONDS, MAX_GAS_LIMIT, MAX_MEMO_BYTES, MAX_MESSAGE_NOTE_BYTES, MIN_USER_SOL_RESERVE,
+    TRUST_LEVEL_PROVISIONAL, URI_HASH_ONLY_PREFIX,
+};
+
+/// The pause rule: a paused program refuses the instruction unless its scheduled unpause is
+/// due, in which case the instruction unpauses it first. Returns that schedule when it applies
+pub fn plan_pause(paused: bool, auto_unpause_at: Option<i64>, now: i64) -> Result<Option<i64>> {
+    if !paused {
+        return Ok(None);
+    }
+    match auto_unpause_at {
+        Some(scheduled_at) if now >= scheduled_at => Ok(Some(scheduled_at)),
+        _ => err!(crate::ErrorCode::ProgramPaused),
+    }
+}
+
+/// A memo must fit in `NFTOrigin.last_memo`
+fn check_memo(memo: Option<&str>) -> Result<()> {
+    require!(memo.is_none_or(|memo| memo.len() <= MAX_MEMO_BYTES), crate::ErrorCode::MemoTooLong);
+    Ok(())
+}
+
+/// A sender must hold the configured reserve, where zero means `MIN_USER_SOL_RESERVE`. Batch
+/// transfers apply it once per sender
+pub fn check_user_reserve(min_user_sol_reserve: u64, user_lamports: u64) -> Result<()> {
+    let min_user_sol_reserve = match min_user_sol_reserve {
+        0 => MIN_USER_SOL_RESERVE,
+        reserve => reserve,
+    };
+    require!(user_lamports >= min_user_sol_reserve, crate::ErrorCode::InsufficientUserBalance);
+    Ok(())
+}
+
+/// The sender's token account, as far as the transfer rules look at it
+#[derive(Clone, Copy, Debug)]
+pub struct Holding {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub delegated_amount: u64,
+}
+
+/// The fee config passed to a transfer, with the treasury actually passed and the holder
+/// discount the sender qualifies for
+#[derive(Clone, Copy, Debug)]
+pub struct FeeSnapshot {
+    pub transfer_fee_lamports: u64,
+    pub treasury: Pubkey,
+    pub passed_treasury: Option<Pubkey>,
+    pub discount_percent: u8,
+}
+
+/// A ZRC-20 gas vault passed to a transfer. `accounts_match` is whether its token account
+/// and fee destination were passed and belong to it
+#[derive(Clone, Copy, Debug)]
+pub struct GasVaultSnapshot {
+    pub zrc20: [u8; 20],
+    pub gas_fee: u64,
+    pub balance: u64,
+    pub accounts_match: bool,
+}
+
+/// What `plan_transfer` reads from a transfer's accounts and the clock
+#[derive(Clone)]
+pub struct TransferSnapshot {
+    pub now: i64,
+    pub slot: u64,
+    pub paused: bool,
+    pub auto_unpause_at: Option<i64>,
+    pub allow_semi_fungible: bool,
+    pub min_user_sol_reserve: u64,
+    pub gas_limit: u64,
+    pub max_message_bytes: u32,
+    pub transfer_nonce: u64,
+    pub user: Pubkey,
+    pub user_lamports: u64,
+    pub holding: Holding,
+    pub mint: Pubkey,
+    pub token_id: u64,
+    pub origin_chain: u64,
+    pub arrived_at: i64,
+    pub semi_fungible: bool,
+    pub rental_locked_until: i64,
+    pub rental_locker: Pubkey,
+    pub logical_owner: Pubkey,
+    pub metadata_uri: String,
+    pub metadata_encryption_key: Option<[u8; 32]>,
+    pub provenance: Option<Provenance>,
+    pub fee: Option<FeeSnapshot>,
+    // The registry's ZRC-20 for the final chain; `None` without a registry or an entry
+    pub registry_zrc20: Option<[u8; 20]>,
+    pub gas_vault: Option<GasVaultSnapshot>,
+    pub chain_gas_config: Option<ChainGasConfig>,
+    // Set while departure receipts are enabled
+    pub receipt_uri_base: Option<String>,
+    // The payout of insurance the sender opted into for this transfer
+    pub insurance_payout: Option<u64>,
+}
+
+pub struct TransferArgs<'a> {
+    pub final_chain_id: u64,
+    pub final_receiver: &'a [u8],
+    pub memo: Option<&'a str>,
+    pub note: Option<&'a [u8]>,
+    pub amount: Option<u64>,
+}
+
+/// The `PendingTransfer` a transfer opens
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PendingTransferPlan {
+    pub nonce: u64,
+    pub submitted_at: i64,
+    pub destination: [u8; 20],
+    pub burn_commitment: [u8; 32],
+    pub amount: u64,
+    pub insured_payout_lamports: Option<u64>,
+}
+
+/// Units leaving for a bridged token's origin chain, for its `ChainStats`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeparturePlan {
+    pub origin_chain: u64,
+    pub token_id: u64,
+    pub amount: u64,
+    pub dwell_seconds: u64,
+}
+
+/// A transfer's CPIs, in the order they are made
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TransferCpi {
+    Memo { memo: String },
+    Fee { lamports: u64 },
+    GasFromVault { gas_fee: u64 },
+    Burn { amount: u64 },
+    SendNft { message: Vec<u8> },
+    IssueReceipt { uri_base: String },
+}
+
+pub struct TransferPlan {
+    pub auto_unpause: Option<i64>,
+    pub last_memo: Option<String>,
+    pub final_chain: ChainId,
+    pub receiver: [u8; 20],
+    pub amount: u64,
+    pub call_options: ZetaChainCallOptions,
+    pub pending: PendingTransferPlan,
+    pub next_transfer_nonce: u64,
+    pub departure: Option<DeparturePlan>,
+    pub receipt_outstanding: bool,
+    pub cpis: Vec<TransferCpi>,
+    // Events logged before the CPIs' own, then `initiated` last
+    pub rental_lock_cleared: Option<RentalLockCleared>,
+    pub fee_collected: Option<FeeCollected>,
+    pub uri_hashed: Option<OutboundUriHashed>,
+    pub initiated: CrossChainTransferInitiated,
+}
+
+/// Check a cross-chain transfer against the pause, memo, destination, ownership, balance,
+/// fee and gas rules, and work out everything it writes, calls and logs
+pub fn plan_transfer(snapshot: &TransferSnapshot, args: &TransferArgs) -> Result<TransferPlan> {
+    let auto_unpause = plan_pause(snapshot.paused, snapshot.auto_unpause_at, snapshot.now)?;
+    check_memo(args.memo)?;
+    // The note travels in the message for the destination to display; the memo stays here
+    if let Some(note) = args.note {
+        require!(note.len() <= MAX_MESSAGE_NOTE_BYTES, crate::ErrorCode::NoteTooLong);
+        require!(std::str::from_utf8(note).is_ok(), crate::ErrorCode::InvalidNoteEncoding);
+    }
+    let final_chain = ChainId(args.final_chain_id).require_valid()?;
+    require!(!final_chain.is_solana(), crate::ErrorCode::InvalidFinalChain);
+    let receiver: [u8; 20] = args.final_receiver.try_into()
+        .map_err(|_| crate::ErrorCode::InvalidFinalReceiver)?;
+
+    // A rented NFT stays until its lock runs out; the transfer then clears the expired lock
+    require!(snapshot.rental_locked_until <= snapshot.now, crate::ErrorCode::RentalLocked);
+    let rental_lock_cleared = (snapshot.rental_locked_until != 0).then_some(RentalLockCleared {
+        token_id: snapshot.token_id,
+        mint: snapshot.mint,
+        locker: snapshot.rental_locker,
+        locked_until: snapshot.rental_locked_until,
+        expired: true,
+    });
+
+    // The sender owns the NFT, or as many units of a semi-fungible token as they send; an
+    // NFT is always sent whole, as its one unit
+    let holding = snapshot.holding;
+    require!(holding.amount > 0, crate::ErrorCode::InsufficientTokens);
+    let amount = args.amount.unwrap_or(1);
+    require!(amount > 0, UniversalNFTCoreError::InvalidAmount);
+    require!(amount <= holding.amount, crate::ErrorCode::InsufficientTokens);
+    require!(
+        holding.owner == snapshot.user || holding.delegated_amount >= amount,
+        crate::ErrorCode::InsufficientDelegatedAmount
+    );
+    if amount > 1 {
+        require!(snapshot.allow_semi_fungible, crate::ErrorCode::SemiFungibleDisabled);
+    }
+    // Semi-fungible tokens always say how many units travel; NFT messages stay as they were
+    let message_amount = (amount > 1 || snapshot.semi_fungible).then_some(amount);
+
+    // Turn away dust-funded senders before anything is charged or burned
+    check_user_reserve(snapshot.min_user_sol_reserve, snapshot.user_lamports)?;
+
+    // The protocol transfer fee, less any holder discount
+    let fee_collected = match &snapshot.fee {
+        Some(fee) => {
+            require!(fee.passed_treasury == Some(fee.treasury), crate::ErrorCode::FeeTreasuryMismatch);
+            let fee_lamports = fee.transfer_fee_lamports
+                .checked_mul(100 - fee.discount_percent as u64)
+                .ok_or(crate::ErrorCode::InvalidFeeDiscountTiers)?
+                / 100;
+            Some(FeeCollected {
+                payer: snapshot.user,
+                token_id: snapshot.token_id,
+                gross_fee_lamports: fee.transfer_fee_lamports,
+                discount_percent: fee.discount_percent,
+                fee_lamports,
+                treasury: fee.treasury,
+            })
+        }
+        None => None,
+    };
+
+    // ZetaChain itself is addressed with a zero destination; any other chain goes through
+    // its ZRC-20, which must be in the registry
+    let destination = if final_chain.is_zetachain() {
+        [0u8; 20]
+    } else {
+        snapshot.registry_zrc20.ok_or(crate::ErrorCode::NotInRegistry)?
+    };
+
+    // The destination ZRC-20's gas vault pays the gas when it holds enough; otherwise it
+    // stays with the caller
+    let vault_gas_fee = match &snapshot.gas_vault {
+        Some(vault) => {
+            require!(
+                vault.accounts_match && vault.zrc20 == destination,
+                crate::ErrorCode::ZRC20GasVaultMismatch
+            );
+            (vault.gas_fee > 0 && vault.balance >= vault.gas_fee).then_some(vault.gas_fee)
+        }
+        None => None,
+    };
+
+    // The gas we ask for must be plausible on the destination chain
+    let gas_limit = match &snapshot.chain_gas_config {
+        // A config created only to set trust has no gas limit of its own
+        Some(chain_gas_config) if chain_gas_config.gas_limit > 0 => {
+            UniversalNFT::validate_gas_limit(chain_gas_config.gas_limit, chain_gas_config.block_gas_limit)?;
+            chain_gas_config.gas_limit
+        }
+        _ => {
+            UniversalNFT::validate_gas_limit(snapshot.gas_limit, MAX_GAS_LIMIT)?;
+            snapshot.gas_limit
+        }
+    };
+    let call_options = ZetaChainCallOptions {
+        gas_limit,
+        ..ZetaChainCallOptions::default()
+    };
+
+    // Commit to the burn, so destination contracts that distrust the relayer can check it
+    // before minting
+    let burn_commitment =
+        UniversalNFTCoreImpl::burn_commitment(&snapshot.mint, snapshot.token_id, &holding.owner, snapshot.slot);
+
+    // A URI too long for max_message_bytes goes out hash-only; NFTOrigin keeps the full one
+    let max_message_bytes = match snapshot.max_message_bytes {
+        0 => DEFAULT_MAX_MESSAGE_BYTES,
+        bytes => bytes,
+    };
+    let (message, uri_hash_only) = UniversalNFTCoreImpl::encode_bounded_outbound_message(
+        &CrossChainNftMessage::forward(
+            destination,
+            receiver,
+            snapshot.token_id,
+            snapshot.metadata_uri.clone(),
+            [0u8; 20], // Solana sender (placeholder)
+        ),
+        &call_options,
+        &OutboundTrailers {
+            metadata_encryption_key: snapshot.metadata_encryption_key, // Stored again on the destination chain
+            burn_commitment: Some(burn_commitment),
+            note: args.note,
+            provenance: snapshot.provenance.as_ref(),
+            amount: message_amount,
+        },
+        max_message_bytes as usize,
+    )?;
+    let uri_hashed = uri_hash_only.then(|| OutboundUriHashed {
+        token_id: snapshot.token_id,
+        uri_hash: anchor_lang::solana_program::keccak::hash(snapshot.metadata_uri.as_bytes()).to_bytes(),
+        message_bytes: message.len() as u32,
+    });
+
+    let pending = PendingTransferPlan {
+        nonce: snapshot.transfer_nonce,
+        submitted_at: snapshot.now,
+        destination,
+        burn_commitment,
+        amount,
+        // Insurance bought beforehand covers this transfer and is used up by it
+        insured_payout_lamports: snapshot.insurance_payout,
+    };
+    let next_transfer_nonce = snapshot.transfer_nonce.checked_add(1)
+        .ok_or(UniversalNFTCoreError::InvalidAmount)?;
+
+    // Departing units count against the origin chain; bridged NFTs only
+    let departure = (!ChainId(snapshot.origin_chain).is_solana()).then(|| DeparturePlan {
+        origin_chain: snapshot.origin_chain,
+        token_id: snapshot.token_id,
+        amount,
+        dwell_seconds: match snapshot.arrived_at {
+            0 => 0,
+            arrived_at => snapshot.now.saturating_sub(arrived_at).max(0) as u64,
+        },
+    });
+
+    // Memo, fee, vault gas, burn, gateway call, receipt
+    let mut cpis = Vec::new();
+    if let Some(memo) = args.memo {
+        cpis.push(TransferCpi::Memo { memo: memo.to_string() });
+    }
+    if let Some(fee) = fee_collected.as_ref().filter(|fee| fee.fee_lamports > 0) {
+        cpis.push(TransferCpi::Fee { lamports: fee.fee_lamports });
+    }
+    if let Some(gas_fee) = vault_gas_fee {
+        cpis.push(TransferCpi::GasFromVault { gas_fee });
+    }
+    cpis.push(TransferCpi::Burn { amount });
+    cpis.push(TransferCpi::SendNft { message });
+    if let Some(uri_base) = &snapshot.receipt_uri_base {
+        cpis.push(TransferCpi::IssueReceipt { uri_base: uri_base.clone() });
+    }
+
+    Ok(TransferPlan {
+        auto_unpause,
+        last_memo: args.memo.map(str::to_string),
+        final_chain,
+        receiver,
+        amount,
+        call_options,
+        pending,
+        next_transfer_nonce,
+        departure,
+        receipt_outstanding: snapshot.receipt_uri_base.is_some(),
+        cpis,
+        rental_lock_cleared,
+        fee_collected,
+        uri_hashed,
+        initiated: CrossChainTransferInitiated {
+            token_id: snapshot.token_id,
+            destination_chain: args.final_chain_id,
+            destination_owner: receiver,
+            mint: snapshot.mint,
+            destination_zrc20: destination,
+            logical_owner: snapshot.logical_owner,
+            owner: holding.owner,
+            burn_slot: snapshot.slot,
+            burn_commitment,
+            amount,
+        },
+    })
+}
+
+/// What `plan_inbound` reads from a delivery's accounts and the clock
+#[derive(Clone)]
+pub struct InboundSnapshot {
+    pub now: i64,
+    pub paused: bool,
+    pub auto_unpause_at: Option<i64>,
+    pub auto_paused_inbound: bool,
+    pub allow_semi_fungible: bool,
+    pub native_id_base: u64,
+    pub require_nft_acceptance: bool,
+    pub inbound_count: u64,
+    pub mint: Pubkey,
+    pub mint_supply: u64,
+    // Default until the token first arrives
+    pub origin_mint: Pubkey,
+    pub stranded_supply: u64,
+    pub departed_amount: u64,
+    pub receipt_outstanding: bool,
+    pub origin_amount: u64,
+    // The URI stored when the token was last here
+    pub origin_uri: String,
+    pub recipient: Pubkey,
+    pub source_chain_config: Option<ChainGasConfig>,
+    pub delivery_mode: DeliveryMode,
+    pub offer_passed: bool,
+}
+
+pub struct InboundArgs<'a> {
+    pub memo: Option<&'a str>,
+    pub message: &'a [u8],
+    // Token id, URI and sender as the configured bridge decoded them from `message`. A
+    // failed decode is raised after the pause and memo checks, where it always has been
+    pub bridge_decoded: Result<(u64, String, [u8; 20])>,
+}
+
+/// Where an inbound delivery is minted
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InboundRoute {
+    // The mint authority's escrow, for held deliveries and offers
+    Escrow,
+    Custodial { token_account: Pubkey },
+    RecipientAta,
+}
+
+/// A delivery's CPIs, in the order they are made
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InboundCpi {
+    Memo { memo: String },
+    BurnReceipt,
+    CreateEscrow,
+    CreateRecipientAta,
+    MintTo { amount: u64 },
+    DeliverValue { lamports: u64 },
+}
+
+pub struct InboundPlan {
+    pub auto_unpause: Option<i64>,
+    pub last_memo: Option<String>,
+    pub token_id: u64,
+    pub uri: String,
+    // Hash of the URI as the message carries it, which keys the dedup window
+    pub dedup_uri_hash: [u8; 32],
+    pub sender: [u8; 20],
+    pub metadata_encryption_key: Option<[u8; 32]>,
+    pub note: Option<String>,
+    // Set when the note wasn't UTF-8 and went out with its bad bytes replaced
+    pub note_replaced: Option<InboundNoteReplaced>,
+    // As carried in the message; the handler falls back to the relaying contract
+    pub provenance: Option<Provenance>,
+    // Units delivered: one for an NFT
+    pub amount: u64,
+    pub is_returning: bool,
+    pub burn_receipt: bool,
+    pub route: InboundRoute,
+    // Set for provisional deliveries, held in escrow until then
+    pub release_at: Option<i64>,
+    // Set when the recipient must accept the delivery from escrow
+    pub offer_expires_at: Option<i64>,
+    pub logical_owner: Pubkey,
+    pub origin_amount: u64,
+    // What is left away from Solana once this delivery's units are back
+    pub departed_amount: u64,
+    pub inbound_sequence: u64,
+    pub next_inbound_count: u64,
+    // Lamports the gateway sent along, as the message's value trailer says; paid on to
+    // the recipient
+    pub value_lamports: u64,
+    // Key of the delivery's InboundReceipt
+    pub message_hash: [u8; 32],
+    pub cpis: Vec<InboundCpi>,
+}
+
+/// Check an inbound delivery against the pause, circuit breaker, id range, replay and
+/// routing rules, and work out where it is minted and what it writes and calls
+pub fn plan_inbound(snapshot: &InboundSnapshot, args: InboundArgs) -> Result<InboundPlan> {
+    let auto_unpause = plan_pause(snapshot.paused, snapshot.auto_unpause_at, snapshot.now)?;
+    require!(!snapshot.auto_paused_inbound, crate::ErrorCode::InboundCircuitBreakerTripped);
+    check_memo(args.memo)?;
+
+    let (token_id, uri, sender) = args.bridge_decoded?;
+    let body = MessageDecoder::single_body(args.message)?;
+    let metadata_encryption_key = UniversalNFTCoreImpl::decode_metadata_encryption_key(body)?;
+    let (note, note_replaced) = match UniversalNFTCoreImpl::decode_note(body)? {
+        Some((note, replaced)) => {
+            let note_replaced = replaced.then(|| InboundNoteReplaced { token_id, note: note.clone() });
+            (Some(note), note_replaced)
+        }
+        None => (None, None),
+    };
+    let provenance = UniversalNFTCoreImpl::decode_provenance(body)?;
+    // Units of a semi-fungible token; messages without the trailer carry one NFT
+    let amount = UniversalNFTCoreImpl::decode_amount(body)?.unwrap_or(1);
+    if amount > 1 {
+        require!(snapshot.allow_semi_fungible, crate::ErrorCode::SemiFungibleDisabled);
+    }
+    let value_lamports = UniversalNFTCoreImpl::decode_value(body)?.unwrap_or(0);
+
+    // Inbound ids at or above the native range would alias a native mint's id
+    require!(token_id < snapshot.native_id_base, crate::ErrorCode::TokenIdRangeConflict);
+
+    let is_returning = snapshot.origin_mint != Pubkey::default();
+    if is_returning {
+        require_keys_eq!(snapshot.origin_mint, snapshot.mint, crate::ErrorCode::BridgedMintMismatch);
+        // Only units that departed can come back, so a replayed message finds none left.
+        // Semi-fungible units return however many of them stayed on Solana
+        require!(amount <= snapshot.departed_amount, crate::ErrorCode::NFTAlreadyDelivered);
+    } else {
+        // A first arrival finds nothing beyond any stranded units minted yet
+        require!(snapshot.mint_supply == snapshot.stranded_supply, crate::ErrorCode::NFTAlreadyDelivered);
+    }
+    let dedup_uri_hash = crate::uri_hash(&uri);
+    let uri = resolve_inbound_uri(uri, is_returning, &snapshot.origin_uri);
+    // The receipt holder is the only one the departed NFT goes back to
+    let burn_receipt = is_returning && snapshot.receipt_outstanding;
+
+    // Low-trust sources deliver into escrow first, reversible by the admin until release_at
+    let hold_seconds = match &snapshot.source_chain_config {
+        Some(config) if config.trust_level == TRUST_LEVEL_PROVISIONAL => config.provisional_hold_seconds,
+        _ => 0,
+    };
+    // A custodial binding takes direct deliveries into the custodian's account; held
+    // deliveries still release to the recipient's own ATA
+    let custodial_account = match snapshot.delivery_mode {
+        DeliveryMode::Custodial { token_account } if hold_seconds == 0 => Some(token_account),
+        _ => None,
+    };
+    // Other direct deliveries wait in escrow for the recipient to accept them when
+    // acceptance is required; a custodial binding is already the recipient's opt-in
+    let needs_acceptance = snapshot.require_nft_acceptance && hold_seconds == 0 && custodial_account.is_none();
+    if needs_acceptance {
+        require!(snapshot.offer_passed, crate::ErrorCode::AcceptanceRequired);
+    } else {
+        // A stray offer would block the next one for this token and recipient
+        require!(!snapshot.offer_passed, crate::ErrorCode::UnexpectedInboundOffer);
+    }
+    // Escrow releases, offers and revocations move a single unit
+    require!(
+        amount == 1 || (hold_seconds == 0 && !needs_acceptance),
+        crate::ErrorCode::SemiFungibleNeedsDirectDelivery
+    );
+    let route = match custodial_account {
+        _ if hold_seconds > 0 || needs_acceptance => InboundRoute::Escrow,
+        Some(token_account) => InboundRoute::Custodial { token_account },
+        None => InboundRoute::RecipientAta,
+    };
+
+    let inbound_sequence = snapshot.inbound_count;
+    let next_inbound_count = inbound_sequence.checked_add(1)
+        .ok_or(UniversalNFTCoreError::InvalidAmount)?;
+
+    // Memo, receipt burn, destination account, mint, value
+    let mut cpis = Vec::new();
+    if let Some(memo) = args.memo {
+        cpis.push(InboundCpi::Memo { memo: memo.to_string() });
+    }
+    if burn_receipt {
+        cpis.push(InboundCpi::BurnReceipt);
+    }
+    match route {
+        InboundRoute::Escrow => cpis.push(InboundCpi::CreateEscrow),
+        InboundRoute::Custodial { .. } => {}
+        InboundRoute::RecipientAta => cpis.push(InboundCpi::CreateRecipientAta),
+    }
+    cpis.push(InboundCpi::MintTo { amount });
+    if value_lamports > 0 {
+        cpis.push(InboundCpi::DeliverValue { lamports: value_lamports });
+    }
+
+    Ok(InboundPlan {
+        auto_unpause,
+        last_memo: args.memo.map(str::to_string),
+        token_id,
+        uri,
+        dedup_uri_hash,
+        sender,
+        metadata_encryption_key,
+        note,
+        note_replaced,
+        provenance,
+        amount,
+        is_returning,
+        burn_receipt,
+        route,
+        release_at: (hold_seconds > 0).then(|| snapshot.now + hold_seconds as i64),
+        offer_expires_at: needs_acceptance.then(|| snapshot.now + INBOUND_OFFER_WINDOW_SECONDS),
+        logical_owner: match custodial_account {
+            Some(_) => snapshot.recipient,
+            None => Pubkey::default(),
+        },
+        origin_amount: snapshot.origin_amount.max(amount),
+        departed_amount: snapshot.departed_amount.saturating_sub(amount),
+        inbound_sequence,
+        next_inbound_count,
+        value_lamports,
+        message_hash: crate::inbound_message_hash(args.message),
+        cpis,
+    })
+}
+
+/// The URI a delivery stores. A URI too long for the outbound message left in hash-only
+/// form; when it commits to the URI a returning token already has, the token keeps that URI
+pub fn resolve_inbound_uri(uri: String, is_returning: bool, stored_uri: &str) -> String {
+    if is_returning && uri.starts_with(URI_HASH_ONLY_PREFIX) && uri == UniversalNFTCoreImpl::uri_hash_only(stored_uri) {
+        return stored_uri.to_string();
+    }
+    uri
+}
+
+/// What `plan_replay` reads from an archived message's receipt and the accounts it rebuilds
+#[derive(Clone)]
+pub struct ReplaySnapshot {
+    pub mint: Pubkey,
+    // Default when the NFTOrigin is missing and is being recreated
+    pub origin_mint: Pubkey,
+    pub origin_amount: u64,
+    pub receipt_token_id: u64,
+    pub receipt_mint: Pubkey,
+    pub receipt_slot: u64,
+    pub receipt_delivered_at: i64,
+}
+
+/// The NFTOrigin fields a replay rebuilds. There is nothing to mint: a replay only ever
+/// rewrites records
+pub struct ReplayPlan {
+    pub token_id: u64,
+    pub uri: String,
+    pub metadata_encryption_key: Option<[u8; 32]>,
+    pub provenance: Option<Provenance>,
+    pub origin_amount: u64,
+    // The origin was missing; it gets the receipt's delivery time as its creation time
+    pub recreated: bool,
+    pub delivered_at: i64,
+}
+
+/// Check an archived inbound message against the receipt its delivery left, and work out
+/// the NFTOrigin it wrote. The receipt stands in for the replay check, which a
+/// delivered token always fails
+pub fn plan_replay(
+    snapshot: &ReplaySnapshot,
+    message: &[u8],
+    bridge_decoded: Result<(u64, String, [u8; 20])>,
+    original_slot: u64,
+) -> Result<ReplayPlan> {
+    let (token_id, uri, _) = bridge_decoded?;
+    require!(
+        snapshot.receipt_slot == original_slot
+            && snapshot.receipt_token_id == token_id
+            && snapshot.receipt_mint == snapshot.mint,
+        crate::ErrorCode::InboundReceiptMismatch
+    );
+    let recreated = snapshot.origin_mint == Pubkey::default();
+    if !recreated {
+        require_keys_eq!(snapshot.origin_mint, snapshot.mint, crate::ErrorCode::BridgedMintMismatch);
+    }
+
+    let body = MessageDecoder::single_body(message)?;
+    let amount = UniversalNFTCoreImpl::decode_amount(body)?.unwrap_or(1);
+    Ok(ReplayPlan {
+        token_id,
+        uri,
+        metadata_encryption_key: UniversalNFTCoreImpl::decode_metadata_encryption_key(body)?,
+        provenance: UniversalNFTCoreImpl::decode_provenance(body)?,
+        origin_amount: snapshot.origin_amount.max(amount),
+        recreated,
+        delivered_at: snapshot.receipt_delivered_at,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    use crate::{
+        CHAIN_ID_SOLANA_DEVNET, CHAIN_ID_ZETACHAIN_TESTNET, DEFAULT_NATIVE_ID_BASE, MESSAGE_TRAILER_NOTE,
+        MESSAGE_TRAILER_PRIORITY, MESSAGE_TRAILER_VALUE, MESSAGE_VERSION_V1, MIN_GAS_LIMIT, ZETA_CHAIN_ID,
+    };
+
+    const NOW: i64 = 1_700_000_000;
+    const RECEIVER: [u8; 20] = [7; 20];
+
+    fn error_name(error: anchor_lang::error::Error) -> String {
+        match error {
+            anchor_lang::error::Error::AnchorError(error) => error.error_name,
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+
+    fn transfer_snapshot(user: Pubkey, holding: Holding) -> TransferSnapshot {
+        TransferSnapshot {
+            now: NOW,
+            slot: 42,
+            paused: false,
+            auto_unpause_at: None,
+            allow_semi_fungible: false,
+            min_user_sol_reserve: 0,
+            gas_limit: MIN_GAS_LIMIT,
+            max_message_bytes: 0,
+            transfer_nonce: 0,
+            user,
+            user_lamports: MIN_USER_SOL_RESERVE,
+            holding,
+            mint: Pubkey::new_unique(),
+            token_id: 1,
+            origin_chain: CHAIN_ID_ZETACHAIN_TESTNET,
+            arrived_at: NOW - 60,
+            semi_fungible: false,
+            rental_locked_until: 0,
+            rental_locker: Pubkey::default(),
+            logical_owner: Pubkey::default(),
+            metadata_uri: "https://example.com/1.json".to_string(),
+            metadata_encryption_key: None,
+            provenance: None,
+            fee: None,
+            registry_zrc20: None,
+            gas_vault: None,
+            chain_gas_config: None,
+            receipt_uri_base: None,
+            insurance_payout: None,
+        }
+    }
+
+    fn owned(user: Pubkey) -> TransferSnapshot {
+        transfer_snapshot(user, Holding { owner: user, amount: 1, delegated_amount: 0 })
+    }
+
+    fn transfer_args(memo: Option<&str>, amount: Option<u64>) -> TransferArgs<'_> {
+        TransferArgs {
+            final_chain_id: ZETA_CHAIN_ID,
+            final_receiver: &RECEIVER,
+            memo,
+            note: None,
+            amount,
+        }
+    }
+
+    fn inbound_snapshot() -> InboundSnapshot {
+        InboundSnapshot {
+            now: NOW,
+            paused: false,
+            auto_unpause_at: None,
+            auto_paused_inbound: false,
+            allow_semi_fungible: false,
+            native_id_base: DEFAULT_NATIVE_ID_BASE,
+            require_nft_acceptance: false,
+            inbound_count: 0,
+            mint: Pubkey::new_unique(),
+            mint_supply: 0,
+            origin_mint: Pubkey::default(),
+            stranded_supply: 0,
+            departed_amount: 0,
+            receipt_outstanding: false,
+            origin_amount: 0,
+            origin_uri: String::new(),
+            recipient: Pubkey::new_unique(),
+            source_chain_config: None,
+            delivery_mode: DeliveryMode::Ata,
+            offer_passed: false,
+        }
+    }
+
+    // The layout the inbound decoder reads: receiver, token id, a URI offset of 100 and the
+    // sender, then the URI's length and bytes
+    fn inbound_message(token_id: u64) -> Vec<u8> {
+        inbound_message_with_uri(token_id, "https://example.com/1.json")
+    }
+
+    fn inbound_message_with_uri(token_id: u64, uri: &str) -> Vec<u8> {
+        let uri = uri.as_bytes();
+        let mut body = vec![0u8; 108];
+        body[12..32].copy_from_slice(&RECEIVER);
+        body[32..40].copy_from_slice(&token_id.to_be_bytes());
+        body[64..72].copy_from_slice(&100u64.to_be_bytes());
+        body[80..100].copy_from_slice(&[9; 20]);
+        body[100..108].copy_from_slice(&(uri.len() as u64).to_be_bytes());
+        body.extend_from_slice(uri);
+        body.resize(108 + uri.len().div_ceil(32) * 32, 0);
+        [&[MESSAGE_VERSION_V1][..], &body].concat()
+    }
+
+    fn plan_delivery(snapshot: &InboundSnapshot, message: &[u8]) -> Result<InboundPlan> {
+        plan_inbound(snapshot, InboundArgs {
+            memo: None,
+            message,
+            bridge_decoded: MessageDecoder::decode_single(message)
+                .map(|decoded| (decoded.token_id, decoded.uri, decoded.sender)),
+        })
+    }
+
+    fn provisional(hold_seconds: u32) -> ChainGasConfig {
+        ChainGasConfig {
+            chain_id: CHAIN_ID_ZETACHAIN_TESTNET,
+            gas_limit: 0,
+            block_gas_limit: 0,
+            bump: 0,
+            trust_level: TRUST_LEVEL_PROVISIONAL,
+            provisional_hold_seconds: hold_seconds,
+            supports_batch: false,
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn pause_holds_until_the_scheduled_unpause(
+            paused: bool,
+            auto_unpause_at in proptest::option::of(NOW - 10..NOW + 10),
+        ) {
+            match plan_pause(paused, auto_unpause_at, NOW) {
+                Ok(None) => prop_assert!(!paused),
+                Ok(Some(scheduled_at)) => {
+                    prop_assert!(paused);
+                    prop_assert_eq!(Some(scheduled_at), auto_unpause_at);
+                    prop_assert!(scheduled_at <= NOW);
+                }
+                Err(error) => {
+                    prop_assert!(paused && auto_unpause_at.is_none_or(|scheduled_at| scheduled_at > NOW));
+                    prop_assert_eq!(error_name(error), "ProgramPaused");
+                }
+            }
+        }
+
+        #[test]
+        fn paused_transfers_wait_for_the_unpause(auto_unpause_at in proptest::option::of(NOW - 10..NOW + 10)) {
+            let user = Pubkey::new_unique();
+            let snapshot = TransferSnapshot { paused: true, auto_unpause_at, ..owned(user) };
+            match plan_transfer(&snapshot, &transfer_args(None, None)) {
+                Ok(plan) => prop_assert_eq!(plan.auto_unpause, auto_unpause_at),
+                Err(error) => {
+                    prop_assert!(auto_unpause_at.is_none_or(|scheduled_at| scheduled_at > NOW));
+                    prop_assert_eq!(error_name(error), "ProgramPaused");
+                }
+            }
+        }
+
+        #[test]
+        fn senders_below_the_reserve_are_turned_away(
+            min_user_sol_reserve in 0u64..3 * MIN_USER_SOL_RESERVE,
+            user_lamports in 0u64..3 * MIN_USER_SOL_RESERVE,
+        ) {
+            let reserve = if min_user_sol_reserve == 0 { MIN_USER_SOL_RESERVE } else { min_user_sol_reserve };
+            match check_user_reserve(min_user_sol_reserve, user_lamports) {
+                Ok(()) => prop_assert!(user_lamports >= reserve),
+                Err(error) => {
+                    prop_assert!(user_lamports < reserve);
+                    prop_assert_eq!(error_name(error), "InsufficientUserBalance");
+                }
+            }
+        }
+
+        #[test]
+        fn only_holders_and_their_delegates_send_what_they_hold(
+            owned_by_user: bool,
+            balance in 0u64..5,
+            delegated_amount in 0u64..5,
+            amount in proptest::option::of(0u64..6),
+            allow_semi_fungible: bool,
+        ) {
+            let user = Pubkey::new_unique();
+            let holding = Holding {
+                owner: if owned_by_user { user } else { Pubkey::new_unique() },
+                amount: balance,
+                delegated_amount,
+            };
+            let snapshot = TransferSnapshot { allow_semi_fungible, ..transfer_snapshot(user, holding) };
+            let requested = amount.unwrap_or(1);
+            let allowed = balance > 0
+                && requested > 0
+                && requested <= balance
+                && (owned_by_user || delegated_amount >= requested)
+                && (requested == 1 || allow_semi_fungible);
+
+            match plan_transfer(&snapshot, &transfer_args(None, amount)) {
+                Ok(plan) => {
+                    prop_assert!(allowed);
+                    prop_assert_eq!(plan.amount, requested);
+                    prop_assert_eq!(plan.pending.amount, requested);
+                    prop_assert_eq!(plan.initiated.amount, requested);
+                    prop_assert_eq!(plan.initiated.owner, holding.owner);
+                    let burn = TransferCpi::Burn { amount: requested };
+                    prop_assert!(plan.cpis.contains(&burn));
+                }
+                Err(_) => prop_assert!(!allowed),
+            }
+        }
+
+        #[test]
+        fn rented_nfts_stay_until_the_lock_expires(rental_locked_until in proptest::option::of(NOW - 10..NOW + 10)) {
+            let user = Pubkey::new_unique();
+            let rental_locker = Pubkey::new_unique();
+            let snapshot = TransferSnapshot {
+                rental_locked_until: rental_locked_until.unwrap_or(0),
+                rental_locker,
+                ..owned(user)
+            };
+            match plan_transfer(&snapshot, &transfer_args(None, None)) {
+                Ok(plan) => {
+                    prop_assert!(rental_locked_until.is_none_or(|until| until <= NOW));
+                    let cleared = plan.rental_lock_cleared.map(|event| (event.locker, event.locked_until, event.expired));
+                    prop_assert_eq!(cleared, rental_locked_until.map(|until| (rental_locker, until, true)));
+                }
+                Err(error) => {
+                    prop_assert!(rental_locked_until.is_some_and(|until| until > NOW));
+                    prop_assert_eq!(error_name(error), "RentalLocked");
+                }
+            }
+        }
+
+        #[test]
+        fn transfers_burn_before_sending_and_take_the_next_nonce(
+            transfer_nonce in 0u64..u64::MAX,
+            with_memo: bool,
+            with_receipt: bool,
+        ) {
+            let user = Pubkey::new_unique();
+            let snapshot = TransferSnapshot {
+                transfer_nonce,
+                receipt_uri_base: with_receipt.then(|| "https://example.com/receipt/".to_string()),
+                ..owned(user)
+            };
+            let plan = plan_transfer(&snapshot, &transfer_args(with_memo.then_some("gm"), None)).unwrap();
+
+            prop_assert_eq!(plan.pending.nonce, transfer_nonce);
+            prop_assert_eq!(plan.next_transfer_nonce, transfer_nonce + 1);
+            prop_assert_eq!(plan.receipt_outstanding, with_receipt);
+            let burn = plan.cpis.iter().position(|cpi| matches!(cpi, TransferCpi::Burn { .. })).unwrap();
+            let send = plan.cpis.iter().position(|cpi| matches!(cpi, TransferCpi::SendNft { .. })).unwrap();
+            prop_assert!(burn < send);
+            prop_assert_eq!(matches!(plan.cpis.first(), Some(TransferCpi::Memo { .. })), with_memo);
+            prop_assert_eq!(matches!(plan.cpis.last(), Some(TransferCpi::IssueReceipt { .. })), with_receipt);
+        }
+
+        #[test]
+        fn inbound_deliveries_stop_while_paused_or_tripped(paused: bool, auto_paused_inbound: bool) {
+            let snapshot = InboundSnapshot { paused, auto_paused_inbound, ..inbound_snapshot() };
+            match plan_delivery(&snapshot, &inbound_message(1)) {
+                Ok(_) => prop_assert!(!paused && !auto_paused_inbound),
+                Err(error) if paused => prop_assert_eq!(error_name(error), "ProgramPaused"),
+                Err(error) => {
+                    prop_assert!(auto_paused_inbound);
+                    prop_assert_eq!(error_name(error), "InboundCircuitBreakerTripped");
+                }
+            }
+        }
+
+        #[test]
+        fn supply_beyond_the_stranded_units_is_a_replay(mint_supply in 0u64..3, stranded_supply in 0u64..3) {
+            let snapshot = InboundSnapshot { mint_supply, stranded_supply, ..inbound_snapshot() };
+            match plan_delivery(&snapshot, &inbound_message(1)) {
+                Ok(plan) => {
+                    prop_assert_eq!(mint_supply, stranded_supply);
+                    let mint = InboundCpi::MintTo { amount: 1 };
+                    prop_assert!(plan.cpis.contains(&mint));
+                }
+                Err(error) => {
+                    prop_assert_ne!(mint_supply, stranded_supply);
+                    prop_assert_eq!(error_name(error), "NFTAlreadyDelivered");
+                }
+            }
+        }
+
+        #[test]
+        fn returning_units_must_have_departed(departed_amount in 0u64..3, mint_supply in 0u64..3) {
+            // Whatever is still on Solana, as when only some semi-fungible units left
+            let snapshot = InboundSnapshot { departed_amount, mint_supply, ..inbound_snapshot() };
+            let snapshot = InboundSnapshot { origin_mint: snapshot.mint, ..snapshot };
+            match plan_delivery(&snapshot, &inbound_message(1)) {
+                Ok(plan) => {
+                    prop_assert!(plan.is_returning);
+                    prop_assert_eq!(plan.departed_amount, departed_amount - 1);
+                }
+                Err(error) => {
+                    prop_assert_eq!(departed_amount, 0);
+                    prop_assert_eq!(error_name(error), "NFTAlreadyDelivered");
+                }
+            }
+        }
+
+        #[test]
+        fn an_offer_is_passed_exactly_when_acceptance_is_required(
+            require_nft_acceptance: bool,
+            offer_passed: bool,
+            held: bool,
+            custodial: bool,
+        ) {
+            let token_account = Pubkey::new_unique();
+            let snapshot = InboundSnapshot {
+                require_nft_acceptance,
+                offer_passed,
+                source_chain_config: held.then(|| provisional(3600)),
+                delivery_mode: if custodial { DeliveryMode::Custodial { token_account } } else { DeliveryMode::Ata },
+                ..inbound_snapshot()
+            };
+            let needs_acceptance = require_nft_acceptance && !held && !custodial;
+
+            match plan_delivery(&snapshot, &inbound_message(1)) {
+                Ok(plan) => {
+                    prop_assert_eq!(offer_passed, needs_acceptance);
+                    prop_assert_eq!(plan.offer_expires_at.is_some(), needs_acceptance);
+                    prop_assert_eq!(plan.release_at, held.then_some(NOW + 3600));
+                    let route = if held || needs_acceptance {
+                        InboundRoute::Escrow
+                    } else if custodial {
+                        InboundRoute::Custodial { token_account }
+                    } else {
+                        InboundRoute::RecipientAta
+                    };
+                    prop_assert_eq!(plan.route, route);
+                    prop_assert_eq!(plan.logical_owner != Pubkey::default(), route != InboundRoute::Escrow && custodial);
+                }
+                Err(error) => {
+                    prop_assert_ne!(offer_passed, needs_acceptance);
+                    let expected = if needs_acceptance { "AcceptanceRequired" } else { "UnexpectedInboundOffer" };
+                    prop_assert_eq!(error_name(error), expected);
+                }
+            }
+        }
+
+        #[test]
+        fn replays_rebuild_only_what_their_receipt_records(
+            origin_missing: bool,
+            slot_matches: bool,
+            token_matches: bool,
+            origin_amount in 0u64..3,
+        ) {
+            let mint = Pubkey::new_unique();
+            let snapshot = ReplaySnapshot {
+                mint,
+                origin_mint: if origin_missing { Pubkey::default() } else { mint },
+                origin_amount,
+                receipt_token_id: if token_matches { 1 } else { 2 },
+                receipt_mint: mint,
+                receipt_slot: 42,
+                receipt_delivered_at: NOW - 60,
+            };
+            let message = inbound_message(1);
+            let decoded = MessageDecoder::decode_single(&message)
+                .map(|decoded| (decoded.token_id, decoded.uri, decoded.sender));
+            let original_slot = if slot_matches { 42 } else { 41 };
+
+            match plan_replay(&snapshot, &message, decoded, original_slot) {
+                Ok(plan) => {
+                    prop_assert!(slot_matches && token_matches);
+                    prop_assert_eq!(plan.token_id, 1);
+                    prop_assert_eq!(plan.recreated, origin_missing);
+                    prop_assert_eq!(plan.origin_amount, origin_amount.max(1));
+                    prop_assert_eq!(plan.delivered_at, NOW - 60);
+                }
+                Err(error) => {
+                    prop_assert!(!(slot_matches && token_matches));
+                    prop_assert_eq!(error_name(error), "InboundReceiptMismatch");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn transfers_never_target_solana() {
+        let user = Pubkey::new_unique();
+        let args = TransferArgs { final_chain_id: CHAIN_ID_SOLANA_DEVNET, ..transfer_args(None, None) };
+        let error = plan_transfer(&owned(user), &args).err().unwrap();
+        assert_eq!(error_name(error), "InvalidFinalChain");
+    }
+
+    #[test]
+    fn returning_tokens_come_back_to_their_own_mint() {
+        let snapshot = InboundSnapshot { origin_mint: Pubkey::new_unique(), ..inbound_snapshot() };
+        let error = plan_delivery(&snapshot, &inbound_message(1)).err().unwrap();
+        assert_eq!(error_name(error), "BridgedMintMismatch");
+
+        let snapshot = InboundSnapshot { receipt_outstanding: true, ..inbound_snapshot() };
+        let snapshot = InboundSnapshot { origin_mint: snapshot.mint, departed_amount: 1, ..snapshot };
+        let plan = plan_delivery(&snapshot, &inbound_message(1)).unwrap();
+        assert!(plan.is_returning && plan.burn_receipt);
+        assert_eq!(plan.cpis.first(), Some(&InboundCpi::BurnReceipt));
+    }
+
+    #[test]
+    fn hash_only_uris_resolve_to_the_stored_uri() {
+        let stored = "https://example.com/long.json";
+        let hashed = UniversalNFTCoreImpl::uri_hash_only(stored);
+        let returning = InboundSnapshot { origin_uri: stored.to_string(), ..inbound_snapshot() };
+        let returning = InboundSnapshot { origin_mint: returning.mint, departed_amount: 1, ..returning };
+        let plan = plan_delivery(&returning, &inbound_message_with_uri(1, &hashed)).unwrap();
+        assert_eq!(plan.uri, stored);
+        assert_eq!(plan.dedup_uri_hash, crate::uri_hash(&hashed));
+
+        // A hash of some other URI, or a first arrival, keeps the message's form
+        let other = UniversalNFTCoreImpl::uri_hash_only("https://example.com/other.json");
+        let plan = plan_delivery(&returning, &inbound_message_with_uri(1, &other)).unwrap();
+        assert_eq!(plan.uri, other);
+        let arriving = InboundSnapshot { origin_uri: stored.to_string(), ..inbound_snapshot() };
+        let plan = plan_delivery(&arriving, &inbound_message_with_uri(1, &hashed)).unwrap();
+        assert_eq!(plan.uri, hashed);
+    }
+
+    #[test]
+    fn notes_that_are_not_utf8_are_replaced_rather_than_refused() {
+        let message = [&inbound_message(1)[..], &[MESSAGE_TRAILER_NOTE, 3, b'h', 0xff, b'i']].concat();
+        let plan = plan_delivery(&inbound_snapshot(), &message).unwrap();
+        assert_eq!(plan.note.as_deref(), Some("h\u{fffd}i"));
+        assert_eq!(plan.note_replaced.map(|event| event.token_id), Some(1));
+
+        let message = [&inbound_message(1)[..], &[MESSAGE_TRAILER_NOTE, 2, b'h', b'i']].concat();
+        let plan = plan_delivery(&inbound_snapshot(), &message).unwrap();
+        assert_eq!(plan.note.as_deref(), Some("hi"));
+        assert!(plan.note_replaced.is_none());
+    }
+
+    #[test]
+    fn value_comes_from_the_message_trailer() {
+        let plan = plan_delivery(&inbound_snapshot(), &inbound_message(1)).unwrap();
+        assert_eq!(plan.value_lamports, 0);
+        assert!(!plan.cpis.iter().any(|cpi| matches!(cpi, InboundCpi::DeliverValue { .. })));
+
+        let value = [&[MESSAGE_TRAILER_VALUE][..], &5_000u64.to_be_bytes()].concat();
+        let message = [&inbound_message(1)[..], &[MESSAGE_TRAILER_PRIORITY, 3], &value].concat();
+        let plan = plan_delivery(&inbound_snapshot(), &message).unwrap();
+        assert_eq!(plan.value_lamports, 5_000);
+        assert_eq!(plan.cpis.last(), Some(&InboundCpi::DeliverValue { lamports: 5_000 }));
+
+        for malformed in [
+            [&inbound_message(1)[..], &[MESSAGE_TRAILER_VALUE], &0u64.to_be_bytes()].concat(),
+            [&inbound_message(1)[..], &value[..8]].concat(),
+            [&inbound_message(1)[..], &value, &[0]].concat(),
+        ] {
+            let error = plan_delivery(&inbound_snapshot(), &malformed).err().unwrap();
+            assert_eq!(error_name(error), "InvalidMessageFormat");
+        }
+    }
+
+    #[test]
+    fn native_range_ids_are_refused() {
+        let error = plan_delivery(&inbound_snapshot(), &inbound_message(DEFAULT_NATIVE_ID_BASE)).err().unwrap();
+        assert_eq!(error_name(error), "TokenIdRangeConflict");
+    }
+
+    fn legacy_origin() -> crate::LegacyNFTOrigin {
+        crate::LegacyNFTOrigin {
+            token_id: 7,
+            origin_chain: CHAIN_ID_ZETACHAIN_TESTNET,
+            origin_token_id: 7,
+            metadata_uri: "https://example.com/7.json".to_string(),
+            mint: Pubkey::new_unique(),
+            created_at: NOW,
+            bump: 254,
+            release_at: 0,
+            provisional_recipient: Pubkey::default(),
+            receipt_outstanding: false,
+            metadata_encryption_key: None,
+            arrived_at: NOW,
+            logical_owner: Pubkey::default(),
+            last_memo: String::new(),
+            stranded_supply: 0,
+            metadata_pending: false,
+            reveal_collection_id: None,
+        }
+    }
+
+    #[test]
+    fn older_origins_decode_only_their_own_fields() {
+        use anchor_lang::{AccountSerialize, AnchorSerialize, Discriminator};
+        use crate::{NFTOrigin, NFT_ORIGIN_SPACE, NFT_ORIGIN_V7_SPACE};
+
+        // A version 12 account whose URI once held more bytes: the stale tail lands where
+        // `reconstructed` sits in the current layout
+        let mut origin = NFTOrigin::from(legacy_origin());
+        origin.schema_version = 12;
+        origin.amount = 3;
+        origin.reconstructed = true;
+        let mut data = vec![0u8; NFT_ORIGIN_SPACE];
+        origin.try_serialize(&mut &mut data[..]).unwrap();
+        let decoded = NFTOrigin::decode_any_version(&data).unwrap();
+        assert_eq!((decoded.schema_version, decoded.amount), (12, 3));
+        assert!(!decoded.reconstructed);
+        assert!(!decoded.is_current(data.len()));
+
+        // Before the version byte, read by size at the version 6 layout
+        let mut data = NFTOrigin::DISCRIMINATOR.to_vec();
+        legacy_origin().serialize(&mut data).unwrap();
+        data.resize(NFT_ORIGIN_V7_SPACE - 1, 0xff);
+        let decoded = NFTOrigin::decode_any_version(&data).unwrap();
+        assert_eq!((decoded.schema_version, decoded.token_id, decoded.amount), (6, 7, 1));
+        assert_eq!(decoded.origin_contract_len, 0);
+
+        // A version 3 account, ending at arrived_at: what follows is garbage, not a logical
+        // owner or memo
+        let mut data = NFTOrigin::DISCRIMINATOR.to_vec();
+        legacy_origin().serialize(&mut data).unwrap();
+        data.resize(crate::NFT_ORIGIN_BASE_SPACE + (8 + 32) + 1 + 33 + 8, 0xff);
+        let decoded = NFTOrigin::decode_any_version(&data).unwrap();
+        assert_eq!((decoded.schema_version, decoded.arrived_at), (3, NOW));
+        assert_eq!(decoded.logical_owner, Pubkey::default());
+        assert!(decoded.last_memo.is_empty());
+
+        // The first layout, which predates NFT_ORIGIN_VERSION
+        let mut data = NFTOrigin::DISCRIMINATOR.to_vec();
+        legacy_origin().serialize(&mut data).unwrap();
+        data.resize(crate::NFT_ORIGIN_BASE_SPACE, 0);
+        let decoded = NFTOrigin::decode_any_version(&data).unwrap();
+        assert_eq!((decoded.schema_version, decoded.bump, decoded.release_at), (0, 254, 0));
+    }
+
+    #[test]
+    fn pending_inbound_from_before_priority_reads_as_priority_zero() {
+        use anchor_lang::{AccountDeserialize, AccountSerialize};
+        use crate::PendingInbound;
+
+        let pending = PendingInbound {
+            message_hash: [7; 32],
+            token_id: 7,
+            recipient: Pubkey::new_unique(),
+            queued_by: Pubkey::new_unique(),
+            queued_at: NOW,
+            bump: 254,
+            message: vec![1, 2, 3],
+            memo: Some("queued".to_string()),
+            priority: 5,
+        };
+        let mut data = vec![0u8; pending.space()];
+        pending.try_serialize(&mut &mut data[..]).unwrap();
+        let decoded = PendingInbound::decode_any_version(&data).unwrap();
+        assert_eq!(decoded.priority, 5);
+        assert!(decoded.is_current(data.len()));
+
+        // Queued before the priority byte: the account ends at the memo and can't be loaded
+        // as it is
+        data.pop();
+        assert!(PendingInbound::try_deserialize(&mut &data[..]).is_err());
+        let decoded = PendingInbound::decode_any_version(&data).unwrap();
+        assert_eq!((decoded.priority, decoded.memo.as_deref()), (0, Some("queued")));
+        assert!(!decoded.is_current(data.len()));
+
+        // Grown by migrate_pending_inbound, the zeroed byte is its priority
+        data.resize(decoded.space(), 0);
+        let migrated = PendingInbound::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!((migrated.priority, migrated.message), (0, vec![1, 2, 3]));
+    }
+}
@@ -0,0 +1,86 @@
+use anchor_lang::prelude::*;
+
+/// Pyth Solana receiver program; owns every `PriceUpdateV2` account
+pub const PYTH_RECEIVER_PROGRAM_ID: Pubkey = pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+
+// Anchor discriminator of the receiver's `PriceUpdateV2` account
+const PRICE_UPDATE_V2_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+
+#[derive(AnchorDeserialize)]
+enum VerificationLevel {
+    Partial {
+        #[allow(dead_code)]
+        num_signatures: u8,
+    },
+    Full,
+}
+
+#[derive(AnchorDeserialize)]
+struct PriceMessage {
+    feed_id: [u8; 32],
+    price: i64,
+    _conf: u64,
+    exponent: i32,
+    publish_time: i64,
+    _prev_publish_time: i64,
+    _ema_price: i64,
+    _ema_conf: u64,
+}
+
+/// Mirror of the receiver's `PriceUpdateV2` layout, so we don't pull in the Pyth SDK
+#[derive(AnchorDeserialize)]
+struct PriceUpdateV2 {
+    _write_authority: Pubkey,
+    verification_level: VerificationLevel,
+    price_message: PriceMessage,
+    _posted_slot: u64,
+}
+
+/// A fully verified Pyth price
+pub struct PythPrice {
+    pub price: i64,
+    pub exponent: i32,
+    pub publish_time: i64,
+}
+
+/// Read a fully verified price for `feed_id` from a Pyth `PriceUpdateV2` account
+pub fn read_price_update(price_update: &AccountInfo, feed_id: &[u8; 32]) -> Result<PythPrice> {
+    require_keys_eq!(*price_update.owner, PYTH_RECEIVER_PROGRAM_ID, crate::ErrorCode::InvalidPriceFeed);
+
+    let data = price_update.try_borrow_data()?;
+    require!(
+        data.len() > 8 && data[..8] == PRICE_UPDATE_V2_DISCRIMINATOR,
+        crate::ErrorCode::InvalidPriceFeed
+    );
+    let update = PriceUpdateV2::deserialize(&mut &data[8..])
+        .map_err(|_| crate::ErrorCode::InvalidPriceFeed)?;
+    require!(
+        matches!(update.verification_level, VerificationLevel::Full),
+        crate::ErrorCode::InvalidPriceFeed
+    );
+    // The receiver posts any feed to any account it writes, so the account alone doesn't
+    // say which price it holds
+    require!(update.price_message.feed_id == *feed_id, crate::ErrorCode::InvalidPriceFeed);
+
+    Ok(PythPrice {
+        price: update.price_message.price,
+        exponent: update.price_message.exponent,
+        publish_time: update.price_message.publish_time,
+    })
+}
+
+/// Convert a SOL-denominated price to lamports
+pub fn sol_price_to_lamports(price: &PythPrice) -> Result<u64> {
+    require!(price.price > 0, crate::ErrorCode::InvalidPriceFeed);
+
+    let scale = price.exponent.checked_add(9).ok_or(crate::ErrorCode::InvalidPriceFeed)?;
+    let lamports = if scale >= 0 {
+        10u128.checked_pow(scale as u32).and_then(|factor| (price.price as u128).checked_mul(factor))
+    } else {
+        10u128.checked_pow(scale.unsigned_abs()).map(|divisor| price.price as u128 / divisor)
+    };
+
+    lamports
+        .and_then(|lamports| u64::try_from(lamports).ok())
+        .ok_or_else(|| crate::ErrorCode::InvalidPriceFeed.into())
+}
@@ -0,0 +1,95 @@
+//! `serde` representations matching the JS SDK, for off-chain Rust clients: pubkeys as
+//! base58 strings and 20-byte EVM addresses as `0x`-prefixed hex
+
+/// `Pubkey` as its base58 string
+pub mod pubkey {
+    use anchor_lang::prelude::Pubkey;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+    use std::str::FromStr;
+
+    pub fn serialize<S: Serializer>(key: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(key)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pubkey, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        Pubkey::from_str(&encoded).map_err(D::Error::custom)
+    }
+}
+
+/// `[u8; 20]` EVM address as `0x`-prefixed lowercase hex; the prefix is optional when reading
+pub mod hex20 {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(address: &[u8; 20], serializer: S) -> Result<S::Ok, S::Error> {
+        let mut encoded = String::with_capacity(42);
+        encoded.push_str("0x");
+        for byte in address {
+            encoded.push_str(&format!("{:02x}", byte));
+        }
+        serializer.serialize_str(&encoded)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 20], D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        let digits = encoded.strip_prefix("0x").unwrap_or(&encoded);
+        if digits.len() != 40 || !digits.is_ascii() {
+            return Err(D::Error::custom("expected 20 bytes of hex"));
+        }
+
+        let mut address = [0u8; 20];
+        for (byte, pair) in address.iter_mut().zip(digits.as_bytes().chunks(2)) {
+            let pair = std::str::from_utf8(pair).map_err(D::Error::custom)?;
+            *byte = u8::from_str_radix(pair, 16).map_err(D::Error::custom)?;
+        }
+        Ok(address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::InboundNFTOffer;
+
+    // Shared with tests/universal-nft.ts, which checks the same JSON against the JS SDK
+    const OFFER_JSON: &str = include_str!("../../../tests/fixtures/inbound-nft-offer.json");
+
+    fn with_field(field: &str, value: &str) -> String {
+        let mut json: serde_json::Value = serde_json::from_str(OFFER_JSON).unwrap();
+        json[field] = value.into();
+        json.to_string()
+    }
+
+    #[test]
+    fn inbound_offer_fixture_round_trips() {
+        let offer: InboundNFTOffer = serde_json::from_str(OFFER_JSON).unwrap();
+        assert_eq!(offer.token_id, 4242);
+        assert_eq!(offer.mint.to_string(), "12CGWwLvRJ6W3bps8HHynW4j3dBBoKLuxyQpwuMpKTjR");
+        // A leading 1 in base58 is a leading zero byte
+        assert_eq!(offer.mint.to_bytes()[0], 0);
+        assert_eq!(offer.sender, core::array::from_fn(|i| 0xa0 + i as u8));
+
+        let expected: serde_json::Value = serde_json::from_str(OFFER_JSON).unwrap();
+        assert_eq!(serde_json::to_value(&offer).unwrap(), expected);
+    }
+
+    #[test]
+    fn hex20_reads_unprefixed_addresses() {
+        let json = with_field("sender", "a0a1a2a3a4a5a6a7a8a9aaabacadaeafb0b1b2b3");
+        let offer: InboundNFTOffer = serde_json::from_str(&json).unwrap();
+        assert_eq!(offer.sender, core::array::from_fn(|i| 0xa0 + i as u8));
+    }
+
+    #[test]
+    fn malformed_fields_are_rejected() {
+        for (field, value) in [
+            ("sender", "0xa0a1a2"),
+            ("sender", "0xzza1a2a3a4a5a6a7a8a9aaabacadaeafb0b1b2b3"),
+            ("sender", "0xa0a1a2a3a4a5a6a7a8a9aaabacadaeafb0b1b2b3b4"),
+            ("recipient", "not-base58!"),
+            ("payer", "0x00"),
+        ] {
+            let json = with_field(field, value);
+            assert!(serde_json::from_str::<InboundNFTOffer>(&json).is_err(), "{} = {}", field, value);
+        }
+    }
+}
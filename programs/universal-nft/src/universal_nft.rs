@@ -1,17 +1,24 @@
 use anchor_lang::prelude::*;
-use anchor_spl::{
-    token::{Mint, Token, TokenAccount, MintTo, Burn},
-    associated_token::AssociatedToken,
-};
-use anchor_lang::solana_program::rent::Rent;
-use mpl_token_metadata::instructions::{
-    CreateMetadataAccountV3CpiBuilder,
-    CreateMasterEditionV3CpiBuilder,
-};
+use anchor_lang::solana_program::compute_units::sol_remaining_compute_units;
+use anchor_lang::solana_program::instruction::{get_stack_height, TRANSACTION_LEVEL_STACK_HEIGHT};
+use anchor_lang::system_program::{self, Transfer};
+use anchor_spl::token::{MintTo, Burn, TokenAccount};
 use mpl_token_metadata::types::{DataV2, Creator, Collection, Uses};
 use std::str::FromStr;
 
 use crate::*;
+use crate::event_buffer::EventBuffer;
+use crate::planner::{
+    self, FeeSnapshot, GasVaultSnapshot, Holding, InboundArgs, InboundCpi, InboundPlan, InboundRoute, InboundSnapshot,
+    ReplaySnapshot, TransferArgs, TransferCpi, TransferPlan, TransferSnapshot,
+};
+use crate::config_export::{
+    self, ChainConfigEntry, ConfigEntries, ConfigEntry, ConfigPage, OriginPolicyEntry, CONFIG_EXPORT_PAGE_ENTRIES,
+    CONFIG_KIND_CHAIN_CONFIGS, CONFIG_KIND_ORIGIN_POLICIES, CONFIG_KIND_REGISTRY,
+};
+
+/// The trailer parts a batched delivery keeps: its encryption key and provenance
+type BatchTrailer = (Option<[u8; 32]>, Option<Provenance>);
 
 /// Main Universal NFT implementation for Solana
 /// This provides ERC721-like functionality with cross-chain transfer capabilities
@@ -25,23 +32,37 @@ impl UniversalNFT {
         next_token_id: u64,
         universal_nft_contract: [u8; 20],
         gas_limit: u64,
+        declared_genesis_hash: [u8; 32],
+        solana_chain_id: u64,
     ) -> Result<()> {
+        Self::validate_gas_limit(gas_limit, MAX_GAS_LIMIT)?;
+        check_declared_solana_chain(&declared_genesis_hash, solana_chain_id)?;
+
         let program_state = &mut ctx.accounts.program_state;
         
         // Hardcode the admin address
         program_state.owner = Pubkey::from_str("F79VcAwM6VhL9CaZo68W1SwrkntLJpAhcbTLLzuz4g3G").unwrap();
         program_state.gateway = gateway;
         program_state.universal_nft_contract = universal_nft_contract;
-        program_state.next_token_id = next_token_id;
+        program_state.native_id_base = DEFAULT_NATIVE_ID_BASE;
+        program_state.declared_chain_id = solana_chain_id;
+        program_state.next_token_id = next_token_id.max(DEFAULT_NATIVE_ID_BASE);
         program_state.paused = false;
         program_state.bump = ctx.bumps.program_state;
         program_state.gas_limit = gas_limit;
+        program_state.swap_router_version = SWAP_ROUTER_V2;
+        program_state.default_swap_fee_tier = DEFAULT_SWAP_FEE_TIER;
+        program_state.schema_version = PROGRAM_STATE_VERSION;
+        #[cfg(feature = "runtime-invariants")]
+        crate::invariants::next_token_id(program_state)?;
         
         emit!(ProgramInitialized {
             owner: program_state.owner,
             gateway,
-            initial_token_id: next_token_id,
+            initial_token_id: program_state.next_token_id,
             gas_limit,
+            declared_genesis_hash,
+            declared_chain_id: program_state.declared_chain_id,
         });
         
         Ok(())
@@ -53,11 +74,31 @@ impl UniversalNFT {
         uri: String,
         decimals: u8,
         token_id: u64,
+        encryption_key: Option<[u8; 32]>,
+        amount: Option<u64>,
+        collection_id: Option<u64>,
     ) -> Result<()> {
-        require!(!ctx.accounts.program_state.paused, crate::ErrorCode::ProgramPaused);
+        Self::require_not_paused(&mut ctx.accounts.program_state)?;
+        // An NFT is one unit; more make a semi-fungible token, behind allow_semi_fungible
+        let amount = amount.unwrap_or(1);
+        require!(amount > 0, UniversalNFTCoreError::InvalidAmount);
+        if amount > 1 {
+            require!(ctx.accounts.program_state.allow_semi_fungible, crate::ErrorCode::SemiFungibleDisabled);
+            require!(decimals == 0, crate::ErrorCode::SemiFungibleDecimals);
+        }
+        // The mint fee and who gets it; charged in step 3
+        let fee_split = Self::mint_fee_split(ctx.accounts, collection_id)?;
+        // Mints into a lazy-reveal collection hold its placeholder until update_token_uri
+        let lazy_reveal = Self::collection_reveal(collection_id, &ctx.accounts.lazy_reveal_config)?;
+        let reveal_collection_id = lazy_reveal.as_ref().map(|config| config.collection_id);
+        let uri = match &lazy_reveal {
+            Some(config) => config.placeholder_uri(),
+            None => uri,
+        };
         
         let program_state = &mut ctx.accounts.program_state;
         let clock = Clock::get()?;
+        require!(token_id >= program_state.native_id_base, crate::ErrorCode::TokenIdRangeConflict);
 
         // For testing purposes, allow any token_id and use it directly
         // In production, you might want to validate this more strictly
@@ -65,18 +106,116 @@ impl UniversalNFT {
 
         // Step 1: Create mint account (this is handled by the account constraint)
         // The mint account is already initialized by the account constraint
-        
-        // Step 2: Mint NFT and create metadata
         let final_token_id = generate_token_id(
             &ctx.accounts.mint.key(),
             token_id // Use the provided token_id directly
         );
         
-        // Update next_token_id to be greater than the current token_id
+        Self::require_kyc(
+            &ctx.accounts.kyc_config,
+            &ctx.accounts.mint_authority.key(),
+            ctx.remaining_accounts,
+        )?;
+
+        // A live reservation keeps this token id for the user who reserved it
+        let reservation_refund = Self::take_reservation(
+            &ctx.accounts.reservation,
+            &ctx.accounts.mint_authority,
+            clock.unix_timestamp,
+        )?;
+
+        // Keep next_token_id above every token id issued, even when ids come out of order
         program_state.next_token_id = token_id.checked_add(1)
-            .ok_or(crate::ErrorCode::TokenIdOverflow)?;
+            .ok_or(crate::ErrorCode::TokenIdOverflow)?
+            .max(program_state.next_token_id);
+        program_state.max_native_token_id = program_state.max_native_token_id.max(token_id);
+
+        // Step 2: Initialize NFT origin record (automatically handled by Anchor), or only
+        // the mint-only record while origins are created lazily
+        match (&mut ctx.accounts.nft_origin, &mut ctx.accounts.mint_only) {
+            (Some(nft_origin), None) if !program_state.lazy_origins_enabled => {
+                nft_origin.schema_version = NFT_ORIGIN_VERSION;
+                nft_origin.token_id = final_token_id;
+                nft_origin.origin_chain = program_state.declared_chain_id;
+                nft_origin.set_provenance(&Self::native_provenance(program_state.declared_chain_id, final_token_id));
+                nft_origin.metadata_uri = uri.clone();
+                nft_origin.mint = ctx.accounts.mint.key();
+                nft_origin.created_at = clock.unix_timestamp;
+                nft_origin.bump = ctx.bumps.nft_origin.unwrap();
+                // Anchor already found both while checking the accounts' seeds
+                nft_origin.metadata_bump = ctx.bumps.metadata;
+                nft_origin.edition_bump = ctx.bumps.master_edition;
+                nft_origin.metadata_encryption_key = encryption_key;
+                nft_origin.reveal_collection_id = reveal_collection_id;
+                nft_origin.amount = amount;
+            }
+            (None, Some(mint_only)) if program_state.lazy_origins_enabled => {
+                require!(encryption_key.is_none(), crate::ErrorCode::EncryptionKeyNeedsOrigin);
+                require!(amount == 1, crate::ErrorCode::SemiFungibleNeedsOrigin);
+                require!(reveal_collection_id.is_none(), crate::ErrorCode::LazyRevealNeedsOrigin);
+                mint_only.token_id = final_token_id;
+                mint_only.mint = ctx.accounts.mint.key();
+                mint_only.bump = ctx.bumps.mint_only.unwrap();
+                mint_only.uri_hash = uri_hash(&uri);
+            }
+            _ => return err!(crate::ErrorCode::LazyOriginAccountsMismatch),
+        }
+
+        ctx.accounts.nft_index.mint = ctx.accounts.mint.key();
+        ctx.accounts.nft_index.token_id = final_token_id;
+        ctx.accounts.nft_index.bump = ctx.bumps.nft_index;
+
+        let mut events = EventBuffer::new();
+        events.push(MintCreated {
+            mint: ctx.accounts.mint.key(),
+            mint_authority: ctx.accounts.mint_authority.key(),
+            decimals,
+            token_id: program_state.next_token_id,
+        });
+        events.push(NFTMinted {
+            token_id: final_token_id,
+            mint: ctx.accounts.mint.key(),
+            metadata_uri: uri.clone(),
+            amount,
+        });
+        if ctx.accounts.nft_origin.is_some() {
+            events.push(NFTOriginCreated {
+                token_id: final_token_id,
+                origin_chain: program_state.declared_chain_id,
+                origin_token_id: final_token_id,
+                mint: ctx.accounts.mint.key(),
+                metadata_uri: uri.clone(),
+            });
+        }
+        if let Some(refunded_lamports) = reservation_refund {
+            events.push(ReservationClosed {
+                token_id,
+                reserved_by: ctx.accounts.mint_authority.key(),
+                refunded_lamports,
+                cranked_by: ctx.accounts.mint_authority.key(),
+            });
+        }
         
-        // Mint 1 token to the user's token account
+        // Step 3: Charge the mint fee, then mint the token's units to the user's token account
+        if let Some(split) = &fee_split {
+            let payer = ctx.accounts.payer.to_account_info();
+            let recipients = [
+                (ctx.accounts.fee_creator.as_ref(), split.creator_fee),
+                (ctx.accounts.fee_treasury.as_ref(), split.protocol_fee),
+            ];
+            for (recipient, lamports) in recipients {
+                if let Some(recipient) = recipient.filter(|_| lamports > 0) {
+                    system_program::transfer(
+                        CpiContext::new(
+                            ctx.accounts.system_program.to_account_info(),
+                            Transfer { from: payer.clone(), to: recipient.to_account_info() },
+                        ),
+                        lamports,
+                    )?;
+                }
+            }
+        }
+
         let mint_to_ctx = CpiContext::new(
             ctx.accounts.token_program.to_account_info(),
             MintTo {
@@ -86,9 +225,10 @@ impl UniversalNFT {
             },
         );
         
-        anchor_spl::token::mint_to(mint_to_ctx, 1)?;
+        anchor_spl::token::mint_to(mint_to_ctx, amount).map_err(cpi_failed(crate::ErrorCode::MintToFailed))?;
 
         // Create metadata for the NFT
+        #[allow(unused_variables)]
         let data_v2 = DataV2 {
             name: String::from("Universal NFT"),
             symbol: String::from("UNFT"),
@@ -111,8 +251,10 @@ impl UniversalNFT {
         //     .system_program(&ctx.accounts.system_program.to_account_info())
         //     .data(data_v2)
         //     .is_mutable(true)
-        //     .invoke()?;
+        //     .invoke().map_err(cpi_failed(crate::ErrorCode::MetadataCreateFailed))?;
 
+        // Semi-fungible mints (amount > 1) get no master edition, which would cap the supply
+        // at one
         // CreateMasterEditionV3CpiBuilder::new(&ctx.accounts.token_metadata_program)
         //     .edition(&ctx.accounts.master_edition)
         //     .mint(&ctx.accounts.mint.to_account_info())
@@ -123,288 +265,7115 @@ impl UniversalNFT {
         //     .system_program(&ctx.accounts.system_program.to_account_info())
         //     .token_program(&ctx.accounts.token_program.to_account_info())
         //     .max_supply(0)
-        //     .invoke()?;
-        
-        // Step 3: Initialize NFT origin record (automatically handled by Anchor)
-        ctx.accounts.nft_origin.token_id = final_token_id;
-        ctx.accounts.nft_origin.origin_chain = CHAIN_ID_SOLANA_DEVNET;
-        ctx.accounts.nft_origin.origin_token_id = final_token_id;
-        ctx.accounts.nft_origin.metadata_uri = uri.clone();
-        ctx.accounts.nft_origin.mint = ctx.accounts.mint.key();
-        ctx.accounts.nft_origin.created_at = clock.unix_timestamp;
-        ctx.accounts.nft_origin.bump = ctx.bumps.nft_origin;
-        
-        // Emit events for all operations
-        emit!(MintCreated {
-            mint: ctx.accounts.mint.key(),
-            mint_authority: ctx.accounts.mint_authority.key(),
-            decimals,
-            token_id: program_state.next_token_id,
-        });
-        
-        emit!(NFTMinted {
-            token_id: final_token_id,
-            mint: ctx.accounts.mint.key(),
-            metadata_uri: uri.clone(),
-        });
+        //     .invoke().map_err(cpi_failed(crate::ErrorCode::MetadataCreateFailed))?;
         
-        emit!(NFTOriginCreated {
-            token_id: final_token_id,
-            origin_chain: CHAIN_ID_SOLANA_DEVNET,
-            origin_token_id: final_token_id,
-            mint: ctx.accounts.mint.key(),
-            metadata_uri: uri,
-        });
+        #[cfg(feature = "runtime-invariants")]
+        {
+            // The one-unit supply invariant doesn't hold for semi-fungible tokens
+            if amount == 1 {
+                crate::invariants::token_on_solana(&ctx.accounts.mint.to_account_info(), 0)?;
+            }
+            crate::invariants::next_token_id(&ctx.accounts.program_state)?;
+        }
+
+        Self::record_hourly_metrics(&ctx.accounts.hourly_metrics, |metrics| {
+            metrics.mints = metrics.mints.saturating_add(1);
+            metrics.active_users = metrics.active_users.saturating_add(1);
+        })?;
+
+        // Step 4: Log everything once the state and CPIs are done
+        if let Some(split) = fee_split {
+            events.push(split);
+        }
+        events.flush();
         
         Ok(())
     }
 
-    /// Transfer NFT from Solana to ZetaChain
-    pub fn transfer_cross_chain(
-        ctx: Context<CrossChainTransfer>,
-        token_id: u64,
-        receiver: [u8; 20], // ZetaChain recipient address
-        destination: [u8; 20], // ZetaChain ZRC-20 address
-    ) -> Result<()> {
-        require!(!ctx.accounts.program_state.paused, crate::ErrorCode::ProgramPaused);
-        
-        let program_state = &ctx.accounts.program_state;
-        let nft_origin = &ctx.accounts.nft_origin;
-        
-        // 1. Validate the user owns the NFT
+    /// Create the NFTOrigin of a native mint made while lazy origins were enabled. Any holder
+    /// of the token can call it and pays the rent; the URI is theirs to give, since only the
+    /// mint's NFTMinted event kept the original
+    pub fn initialize_nft_origin_lazy(ctx: Context<LazyInitOrigin>, token_id: u64, uri: String) -> Result<()> {
         require!(
-            ctx.accounts.user_token_account.amount > 0,
-            crate::ErrorCode::InsufficientTokens
-        );
-        
-        // 2. Burn the NFT on Solana (like EVM _burn)
-        let burn_ctx = CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            Burn {
-                mint: ctx.accounts.mint.to_account_info(),
-                from: ctx.accounts.user_token_account.to_account_info(),
-                authority: ctx.accounts.user.to_account_info(),
-            },
+            !uri.is_empty() && uri.len() <= MAX_METADATA_URI_BYTES,
+            UniversalNFTCoreError::InvalidUriEncoding
         );
-        
-        anchor_spl::token::burn(burn_ctx, 1)?;
-        
-        // 3. Encode cross-chain message (like EVM abi.encode)
-        let message_data = UniversalNFTCoreImpl::encode_cross_chain_message(
-            receiver,                    // ZetaChain recipient
-            nft_origin.token_id,        // Token ID
-            nft_origin.metadata_uri.clone(), // Metadata URI
-            [0u8; 20],                  // Solana sender (placeholder)
+        // The origin keeps the URI the token was minted with, not one the holder picks
+        require!(
+            uri_hash(&uri) == ctx.accounts.mint_only.uri_hash,
+            crate::ErrorCode::LazyOriginUriMismatch
         );
-        
-        // 4. Call ZetaChain gateway (like EVM gateway.call)
-        UniversalNFTCoreImpl::call_gateway(
-            ctx.accounts.gateway_program.to_account_info(),
-            ctx.accounts.user.to_account_info(),
-            destination,                 // ZetaChain ZRC-20 address
-            message_data,
-        )?;
-        
-        // 5. Emit transfer event (like EVM TokenTransfer)
-        emit!(CrossChainTransferInitiated {
-            token_id: nft_origin.token_id,
-            destination_chain: CHAIN_ID_ZETACHAIN_TESTNET,
-            destination_owner: receiver,
-            mint: ctx.accounts.mint.key(),
-        });
-        
-        Ok(())
-    }
-
-    /// Receive cross-chain message and mint NFT
-    pub fn receive_cross_chain_message(
-        ctx: Context<ReceiveCrossChainMessage>,
-        token_id: u64,
-        message: Vec<u8>,
-    ) -> Result<()> {
-        require!(!ctx.accounts.program_state.paused, crate::ErrorCode::ProgramPaused);
-        
-        // Decode the cross-chain message
-        let (destination, receiver, decoded_token_id, uri, sender) = UniversalNFTCoreImpl::decode_cross_chain_message(&message)?;
-        
-        // Validate token ID
-        require_eq!(decoded_token_id, token_id, crate::ErrorCode::InvalidCrossChainMessage);
-        
-        let _program_state = &mut ctx.accounts.program_state;
 
+        let origin_chain = ctx.accounts.program_state.declared_chain_id;
+        let mint = ctx.accounts.mint_only.mint;
         let nft_origin = &mut ctx.accounts.nft_origin;
+        nft_origin.schema_version = NFT_ORIGIN_VERSION;
         nft_origin.token_id = token_id;
-        nft_origin.origin_chain = CHAIN_ID_ZETACHAIN_TESTNET;
-        nft_origin.origin_token_id = token_id;
+        nft_origin.origin_chain = origin_chain;
+        nft_origin.set_provenance(&Self::native_provenance(origin_chain, token_id));
         nft_origin.metadata_uri = uri.clone();
-        nft_origin.mint = ctx.accounts.mint.key();
+        nft_origin.mint = mint;
         nft_origin.created_at = Clock::get()?.unix_timestamp;
         nft_origin.bump = ctx.bumps.nft_origin;
+        nft_origin.record_metadata_bumps();
 
-        // Mint the NFT to the recipient
-        let mint_to_ctx = CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            MintTo {
-                mint: ctx.accounts.mint.to_account_info(),
-                to: ctx.accounts.recipient_token_account.to_account_info(),
-                authority: ctx.accounts.mint_authority.to_account_info(),
-            },
-        );
-        
-        anchor_spl::token::mint_to(mint_to_ctx, 1)?;
-
-        // Convert EVM address to Solana pubkey
-        let recipient_pubkey = Pubkey::new_from_array({
-            let mut recipient = [0u8; 32];
-            recipient[12..32].copy_from_slice(&sender);
-            recipient
-        });
-
-        emit!(CrossChainMessageReceived {
+        emit!(NFTOriginCreated {
             token_id,
-            origin_chain: CHAIN_ID_ZETACHAIN_TESTNET,
-            mint: ctx.accounts.mint.key(),
-            recipient: recipient_pubkey,
+            origin_chain,
+            origin_token_id: token_id,
+            mint,
+            metadata_uri: uri,
         });
 
         Ok(())
     }
 
-    /// Set gateway address (admin only)
-    pub fn set_gateway(ctx: Context<AdminAction>, gateway: Pubkey) -> Result<()> {
+    /// The lazy reveal of the collection a mint names, if it has one. A named collection
+    /// must come with its LazyRevealConfig PDA, so its placeholder can't be skipped, and
+    /// takes no mints once its seed has been requested
+    fn collection_reveal(
+        collection_id: Option<u64>,
+        lazy_reveal_config: &Option<UncheckedAccount>,
+    ) -> Result<Option<LazyRevealConfig>> {
+        let Some(collection_id) = collection_id else {
+            require!(lazy_reveal_config.is_none(), crate::ErrorCode::LazyRevealConfigMismatch);
+            return Ok(None);
+        };
+        let info = lazy_reveal_config.as_ref()
+            .ok_or(crate::ErrorCode::LazyRevealConfigMismatch)?;
+        require_keys_eq!(info.key(), pda::lazy_reveal(collection_id).0, crate::ErrorCode::LazyRevealConfigMismatch);
+        if info.owner != &crate::ID || info.data_is_empty() {
+            return Ok(None);
+        }
+        let config = LazyRevealConfig::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        require!(config.requested_at == 0, crate::ErrorCode::RevealAlreadyRequested);
+        Ok(Some(config))
+    }
+
+    /// Split the configured mint fee: `creator_fee_bps` of it goes to the first creator of
+    /// the collection's seller fee config and the rest to the treasury. None when minting is
+    /// free. A named collection must come with its config, so the split can't be skipped; a
+    /// collection without a creator split has a config with no creators
+    fn mint_fee_split(accounts: &CreateMintAndNFT, collection_id: Option<u64>) -> Result<Option<FeeSplit>> {
         require!(
-            ctx.accounts.admin.key() == ctx.accounts.program_state.owner,
-            crate::ErrorCode::Unauthorized
+            collection_id.is_some() == accounts.seller_fee_config.is_some(),
+            crate::ErrorCode::SellerFeeConfigMismatch
         );
-        
-        ctx.accounts.program_state.gateway = gateway;
-        
-        emit!(GatewayUpdated {
-            admin: ctx.accounts.admin.key(),
-            gateway,
-        });
-        
-        Ok(())
+        let fee_config = &accounts.fee_config;
+        if fee_config.mint_fee_lamports == 0 {
+            return Ok(None);
+        }
+        let treasury = accounts.fee_treasury.as_ref()
+            .ok_or(crate::ErrorCode::FeeTreasuryMismatch)?;
+        require_keys_eq!(treasury.key(), fee_config.treasury, crate::ErrorCode::FeeTreasuryMismatch);
+
+        let total_fee = fee_config.mint_fee_lamports;
+        let first_creator = accounts.seller_fee_config.as_ref()
+            .and_then(|config| config.creators.first());
+        let (creator, creator_fee) = match first_creator {
+            Some(first_creator) => {
+                let fee_creator = accounts.fee_creator.as_ref()
+                    .ok_or(crate::ErrorCode::FeeCreatorMismatch)?;
+                require_keys_eq!(fee_creator.key(), first_creator.address, crate::ErrorCode::FeeCreatorMismatch);
+                let creator_fee = u128::from(total_fee) * u128::from(fee_config.creator_fee_bps)
+                    / u128::from(MAX_FEE_BPS);
+                (first_creator.address, creator_fee as u64)
+            }
+            // Outside a collection, or in one with no creators, the treasury takes the whole fee
+            None => (Pubkey::default(), 0),
+        };
+
+        Ok(Some(FeeSplit {
+            total_fee,
+            creator_fee,
+            protocol_fee: total_fee - creator_fee,
+            creator,
+            protocol_treasury: treasury.key(),
+        }))
     }
 
-    /// Set gas limit (admin only)
-    pub fn set_gas_limit(ctx: Context<AdminAction>, gas_limit: u64) -> Result<()> {
+    /// Enforce the reservation at `reservation`, if any. A live one must belong to the
+    /// minter and is closed into their wallet, refunding the fee; returns the lamports
+    /// refunded. Expired reservations are left for `close_expired_reservation`.
+    fn take_reservation<'info>(
+        reservation: &UncheckedAccount<'info>,
+        minter: &Signer<'info>,
+        now: i64,
+    ) -> Result<Option<u64>> {
+        if reservation.owner != &crate::ID || reservation.data_is_empty() {
+            return Ok(None);
+        }
+        let state = MintReservation::try_deserialize(&mut &reservation.try_borrow_data()?[..])?;
+        if now >= state.expires_at {
+            return Ok(None);
+        }
+        require_keys_eq!(minter.key(), state.reserved_by, crate::ErrorCode::TokenIdReserved);
+
+        let refunded_lamports = reservation.lamports();
+        **minter.to_account_info().try_borrow_mut_lamports()? += refunded_lamports;
+        **reservation.try_borrow_mut_lamports()? = 0;
+        reservation.assign(&system_program::ID);
+        reservation.resize(0)?;
+
+        Ok(Some(refunded_lamports))
+    }
+
+    /// When the KYC gate is enabled, the minter's identity record must be among
+    /// `remaining_accounts` and carry at least the required level
+    fn require_kyc(
+        kyc_config: &UncheckedAccount,
+        minter: &Pubkey,
+        remaining_accounts: &[AccountInfo],
+    ) -> Result<()> {
+        if kyc_config.owner != &crate::ID || kyc_config.data_is_empty() {
+            return Ok(());
+        }
+        let config = KYCConfig::try_deserialize(&mut &kyc_config.try_borrow_data()?[..])?;
+        if !config.enabled {
+            return Ok(());
+        }
+
+        let (expected_record, _) = Pubkey::find_program_address(
+            &[b"identity", minter.as_ref()],
+            &config.identity_program,
+        );
+        let record_info = remaining_accounts
+            .iter()
+            .find(|info| info.key() == expected_record && *info.owner == config.identity_program)
+            .ok_or(crate::ErrorCode::KYCRequired)?;
+
+        let data = record_info.try_borrow_data()?;
+        require!(data.len() > 8, crate::ErrorCode::KYCRequired);
+        let record = KYCRecord::deserialize(&mut &data[8..])
+            .map_err(|_| crate::ErrorCode::KYCRequired)?;
         require!(
-            ctx.accounts.admin.key() == ctx.accounts.program_state.owner,
-            crate::ErrorCode::Unauthorized
+            record.user == *minter && record.kyc_level >= config.required_kyc_level,
+            crate::ErrorCode::KYCRequired
         );
-        
-        require!(gas_limit > 0, UniversalNFTCoreError::InvalidGasLimit);
-        
-        ctx.accounts.program_state.gas_limit = gas_limit;
-        
-        emit!(GasLimitUpdated {
-            admin: ctx.accounts.admin.key(),
-            gas_limit,
-        });
-        
+
         Ok(())
     }
 
-    /// Set connected contract mapping (admin only)
-    pub fn set_connected_contract(
-        ctx: Context<AdminAction>,
-        zrc20: [u8; 20],
-        contract_address: Vec<u8>,
+    /// While the relayer allowlist is on, cranks need the signer's `RelayerRole`; the role
+    /// account's seeds already tie it to the signer, so being present is enough
+    fn require_relayer(
+        relayer_config: &UncheckedAccount,
+        relayer_role: &Option<Account<RelayerRole>>,
     ) -> Result<()> {
+        if relayer_config.owner != &crate::ID || relayer_config.data_is_empty() {
+            return Ok(());
+        }
+        let config = RelayerConfig::try_deserialize(&mut &relayer_config.try_borrow_data()?[..])?;
         require!(
-            ctx.accounts.admin.key() == ctx.accounts.admin.key(),
-            crate::ErrorCode::Unauthorized
+            !config.allowlist_enabled || relayer_role.is_some(),
+            crate::ErrorCode::RelayerRequired
         );
-        
-        require!(!contract_address.is_empty(), UniversalNFTCoreError::InvalidDestination);
-        
-        // Store connected contract mapping
-        let connected_contract = ConnectedContract {
-            zrc20,
-            contract_address: contract_address.clone(),
-        };
-        
-        // This would typically be stored in a separate account or mapping
-        // For now, we'll emit an event
-        emit!(ConnectedContractSet {
+        Ok(())
+    }
+
+    /// Restrict cranks to relayers holding a `RelayerRole`, or open them to anyone (admin only)
+    pub fn set_relayer_allowlist_enabled(ctx: Context<SetRelayerConfig>, enabled: bool) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetRelayerAllowlist,
+        )?;
+
+        let relayer_config = &mut ctx.accounts.relayer_config;
+        let previous_enabled = relayer_config.allowlist_enabled;
+        Self::require_changed(&previous_enabled, &enabled)?;
+        relayer_config.allowlist_enabled = enabled;
+        relayer_config.bump = ctx.bumps.relayer_config;
+
+        emit!(RelayerAllowlistUpdated {
             admin: ctx.accounts.admin.key(),
-            zrc20,
-            contract_address,
+            previous_enabled,
+            enabled,
         });
-        
+
         Ok(())
     }
 
-    /// Pause the program (admin only)
-    pub fn pause(ctx: Context<AdminAction>) -> Result<()> {
-        require!(
-            ctx.accounts.admin.key() == ctx.accounts.program_state.owner,
-            crate::ErrorCode::Unauthorized
-        );
-        
-        ctx.accounts.program_state.paused = true;
-        
-        emit!(ProgramPaused {
+    /// Let `relayer` run cranks while the allowlist is on (admin only)
+    pub fn grant_relayer_role(ctx: Context<GrantRelayerRole>, relayer: Pubkey) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::GrantRelayerRole,
+        )?;
+
+        let relayer_role = &mut ctx.accounts.relayer_role;
+        relayer_role.relayer = relayer;
+        relayer_role.granted_at = Clock::get()?.unix_timestamp;
+        relayer_role.bump = ctx.bumps.relayer_role;
+
+        emit!(RelayerRoleGranted {
             admin: ctx.accounts.admin.key(),
+            relayer,
         });
-        
+
         Ok(())
     }
 
-    /// Unpause the program (admin only)
-    pub fn unpause(ctx: Context<AdminAction>) -> Result<()> {
-        require!(
-            ctx.accounts.admin.key() == ctx.accounts.program_state.owner,
-            crate::ErrorCode::Unauthorized
-        );
-        
-        ctx.accounts.program_state.paused = false;
-        
-        emit!(ProgramUnpaused {
+    /// Take a relayer's role away, returning its rent to the admin (admin only)
+    pub fn revoke_relayer_role(ctx: Context<RevokeRelayerRole>, relayer: Pubkey) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::RevokeRelayerRole,
+        )?;
+
+        emit!(RelayerRoleRevoked {
             admin: ctx.accounts.admin.key(),
+            relayer,
         });
-        
+
         Ok(())
     }
 
-    /// Update universal NFT contract address (admin only)
-    pub fn set_universal_nft_contract(
-        ctx: Context<AdminAction>,
-        universal_nft_contract: [u8; 20],
-    ) -> Result<()> {
+    /// Snapshot the program's counters into the heartbeat record (crank; relayers only while
+    /// the allowlist is on; at most once per minimum interval, after the admin has set one).
+    /// Works while paused, since the pause flags are part of the snapshot
+    pub fn heartbeat(ctx: Context<RecordHeartbeat>) -> Result<()> {
+        Self::require_relayer(&ctx.accounts.relayer_config, &ctx.accounts.relayer_role)?;
+        let clock = Clock::get()?;
+        let heartbeat = &mut ctx.accounts.heartbeat;
+        let min_interval = match heartbeat.min_interval_seconds {
+            0 => DEFAULT_HEARTBEAT_INTERVAL_SECONDS,
+            seconds => seconds,
+        };
         require!(
-            ctx.accounts.admin.key() == ctx.accounts.program_state.owner,
-            crate::ErrorCode::Unauthorized
+            heartbeat.slot == 0 || clock.unix_timestamp >= heartbeat.timestamp + min_interval as i64,
+            crate::ErrorCode::HeartbeatTooSoon
         );
-        
-        ctx.accounts.program_state.universal_nft_contract = universal_nft_contract;
-        
-        emit!(UniversalNFTContractUpdated {
+
+        let program_state = &ctx.accounts.program_state;
+        let mut paused_flags = 0;
+        if program_state.paused {
+            paused_flags |= HEARTBEAT_FLAG_PAUSED;
+        }
+        if program_state.auto_paused_inbound {
+            paused_flags |= HEARTBEAT_FLAG_INBOUND_PAUSED;
+        }
+
+        heartbeat.slot = clock.slot;
+        heartbeat.timestamp = clock.unix_timestamp;
+        heartbeat.outbound_nonce = program_state.transfer_nonce;
+        heartbeat.inbound_count = program_state.inbound_count;
+        heartbeat.paused_flags = paused_flags;
+
+        emit!(Heartbeat {
+            slot: heartbeat.slot,
+            timestamp: heartbeat.timestamp,
+            outbound_nonce: heartbeat.outbound_nonce,
+            inbound_count: heartbeat.inbound_count,
+            paused_flags,
+            cranked_by: ctx.accounts.caller.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Set how often `heartbeat` may be called; zero restores the default. The first call
+    /// creates the heartbeat record (admin only)
+    pub fn set_heartbeat_interval(
+        ctx: Context<SetHeartbeatInterval>,
+        min_interval_seconds: u32,
+    ) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetHeartbeatInterval,
+        )?;
+
+        let heartbeat = &mut ctx.accounts.heartbeat;
+        let previous_min_interval_seconds = heartbeat.min_interval_seconds;
+        Self::require_changed(&previous_min_interval_seconds, &min_interval_seconds)?;
+        heartbeat.min_interval_seconds = min_interval_seconds;
+        heartbeat.bump = ctx.bumps.heartbeat;
+
+        emit!(HeartbeatIntervalUpdated {
             admin: ctx.accounts.admin.key(),
-            universal_nft_contract,
+            previous_min_interval_seconds,
+            min_interval_seconds,
         });
-        
+
         Ok(())
     }
 
-    /// Migrate program state to new structure (for backward compatibility)
-    pub fn migrate_program_state(
-        ctx: Context<MigrateProgramState>,
+    /// Point the mint KYC gate at an identity program (admin only)
+    pub fn set_kyc_config(
+        ctx: Context<SetKYCConfig>,
+        identity_program: Pubkey,
+        required_kyc_level: u8,
     ) -> Result<()> {
-        let program_state = &mut ctx.accounts.program_state;
-        
-        // Check if migration is needed (if gas_limit is 0, it means it's the old structure)
-        if program_state.gas_limit == 0 {
-            // Set default values for new fields
-            program_state.gas_limit = 1000000; // Default gas limit
-            
-            emit!(ProgramStateMigrated {
-                admin: ctx.accounts.payer.key(),
-                gas_limit: program_state.gas_limit,
-            });
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetKycConfig,
+        )?;
+
+        let kyc_config = &mut ctx.accounts.kyc_config;
+        kyc_config.identity_program = identity_program;
+        kyc_config.required_kyc_level = required_kyc_level;
+        kyc_config.enabled = required_kyc_level > 0;
+        kyc_config.bump = ctx.bumps.kyc_config;
+
+        emit!(KYCConfigUpdated {
+            admin: ctx.accounts.admin.key(),
+            identity_program,
+            required_kyc_level,
+            enabled: kyc_config.enabled,
+        });
+
+        Ok(())
+    }
+
+    /// Create the empty connected contract registry (admin only)
+    pub fn init_registry(ctx: Context<InitRegistry>) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::InitRegistry,
+        )?;
+
+        let mut registry = ctx.accounts.registry.load_init()?;
+        registry.bump = ctx.bumps.registry;
+
+        Ok(())
+    }
+
+    /// Create the cross-chain event log; transfers and deliveries before this aren't logged
+    /// (admin only)
+    pub fn init_event_log(ctx: Context<InitEventLog>) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::InitEventLog,
+        )?;
+
+        let mut event_log = ctx.accounts.event_log.load_init()?;
+        event_log.bump = ctx.bumps.event_log;
+
+        Ok(())
+    }
+
+    /// `count` event log slots from `from_index` on, wrapping around the ring. Pollers read
+    /// `head` and `total` from the account to tell how far the log moved since their last read
+    pub fn read_event_log(
+        ctx: Context<ReadEventLog>,
+        from_index: u16,
+        count: u8,
+    ) -> Result<Vec<CrossChainEventEntry>> {
+        require!(
+            (from_index as usize) < EVENT_LOG_CAPACITY && count <= MAX_EVENT_LOG_READ,
+            crate::ErrorCode::EventLogReadOutOfRange
+        );
+        Ok(ctx.accounts.event_log.load()?.read(from_index, count))
+    }
+
+    /// Append to the event log, if `init_event_log` has created it
+    fn append_event_log(
+        event_log: &UncheckedAccount,
+        event_type: u8,
+        token_id: u64,
+        destination: [u8; 20],
+        nonce: u64,
+        timestamp: i64,
+    ) -> Result<()> {
+        if event_log.owner != &crate::ID || event_log.data_is_empty() {
+            return Ok(());
+        }
+        // AccountLoader needs an 'info borrow the handlers can't give, so this is its
+        // load_mut inlined
+        let mut data = event_log.try_borrow_mut_data()?;
+        let discriminator = CrossChainEventLog::DISCRIMINATOR;
+        require!(
+            data.len() >= 8 + std::mem::size_of::<CrossChainEventLog>()
+                && data[..discriminator.len()] == *discriminator,
+            anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch
+        );
+        let log: &mut CrossChainEventLog = bytemuck::from_bytes_mut(
+            &mut data[8..8 + std::mem::size_of::<CrossChainEventLog>()],
+        );
+        log.append(CrossChainEventEntry {
+            token_id,
+            timestamp,
+            nonce,
+            destination,
+            event_type,
+            _padding: [0; 3],
+        });
+        Ok(())
+    }
+
+    /// Insert or replace the registry entry for `zrc20`, keeping entries sorted (admin only)
+    pub fn set_registry_entry(
+        ctx: Context<UpdateRegistry>,
+        zrc20: [u8; 20],
+        contract: Vec<u8>,
+        chain_id: u64,
+        flags: u8,
+    ) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetRegistryEntry,
+        )?;
+
+        Self::upsert_registry_entry(&ctx.accounts.registry, zrc20, &contract, chain_id, flags, None)?;
+
+        emit!(RegistryEntrySet {
+            admin: ctx.accounts.admin.key(),
+            zrc20,
+            contract,
+            chain_id,
+            flags,
+        });
+
+        Ok(())
+    }
+
+    /// Set where ZetaChain's chain registry is read from (admin only)
+    pub fn set_chain_registry_config(
+        ctx: Context<SetChainRegistryConfig>,
+        observer_program: Pubkey,
+    ) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetChainRegistryConfig,
+        )?;
+
+        let chain_registry_config = &mut ctx.accounts.chain_registry_config;
+        chain_registry_config.observer_program = observer_program;
+        chain_registry_config.bump = ctx.bumps.chain_registry_config;
+
+        emit!(ChainRegistryConfigUpdated {
+            admin: ctx.accounts.admin.key(),
+            observer_program,
+        });
+
+        Ok(())
+    }
+
+    /// `set_registry_entry`, once ZetaChain's chain registry lists `chain_id` as active;
+    /// the entry records when that was checked (admin only)
+    pub fn validate_chain_id_against_zeta_registry(
+        ctx: Context<ValidateChain>,
+        chain_id: u64,
+        zrc20: [u8; 20],
+        contract: Vec<u8>,
+        flags: u8,
+    ) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetRegistryEntry,
+        )?;
+
+        let observer_program = ctx.accounts.chain_registry_config.observer_program;
+        let zeta_chain_registry = &ctx.accounts.zeta_chain_registry;
+        require!(
+            zeta_chain_registry.key() == pda::zeta_chain_registry(&observer_program).0
+                && zeta_chain_registry.owner == &observer_program,
+            crate::ErrorCode::ChainRegistryMismatch
+        );
+        let data = zeta_chain_registry.try_borrow_data()?;
+        require!(data.len() > 8, crate::ErrorCode::ChainRegistryMismatch);
+        let chain_registry = ZetaChainRegistry::deserialize(&mut &data[8..])
+            .map_err(|_| crate::ErrorCode::ChainRegistryMismatch)?;
+        require!(
+            chain_registry.chains.iter().any(|chain| chain.chain_id == chain_id && chain.is_active),
+            crate::ErrorCode::UnrecognizedChainId
+        );
+
+        let verified_at = Clock::get()?.unix_timestamp;
+        Self::upsert_registry_entry(&ctx.accounts.registry, zrc20, &contract, chain_id, flags, Some(verified_at))?;
+
+        emit!(RegistryEntrySet {
+            admin: ctx.accounts.admin.key(),
+            zrc20,
+            contract,
+            chain_id,
+            flags,
+        });
+
+        Ok(())
+    }
+
+    /// Insert or replace the entry for `zrc20`, keeping the registry sorted. `verified_at`
+    /// stamps a chain id just checked against ZetaChain's registry; without it, a previous
+    /// stamp stays while the chain id does
+    fn upsert_registry_entry(
+        registry: &AccountLoader<Registry>,
+        zrc20: [u8; 20],
+        contract: &[u8],
+        chain_id: u64,
+        flags: u8,
+        verified_at: Option<i64>,
+    ) -> Result<()> {
+        require!(
+            !contract.is_empty() && contract.len() <= MAX_REGISTRY_CONTRACT_BYTES,
+            UniversalNFTCoreError::InvalidDestination
+        );
+        ChainId(chain_id).require_valid()?;
+
+        let mut entry = RegistryEntry {
+            chain_id,
+            zrc20,
+            contract: [0u8; MAX_REGISTRY_CONTRACT_BYTES],
+            contract_len: contract.len() as u8,
+            flags,
+            _padding: [0u8; 2],
+            last_seen_at: 0,
+            last_pong_hash: [0u8; 32],
+            last_verified_at: verified_at.unwrap_or(0),
+        };
+        entry.contract[..contract.len()].copy_from_slice(contract);
+
+        let mut registry = registry.load_mut()?;
+        match registry.search(&zrc20) {
+            Ok(index) => {
+                // Liveness carries over only while the contract stays the same
+                let previous = &registry.entries[index];
+                if previous.contract == entry.contract && previous.contract_len == entry.contract_len {
+                    entry.last_seen_at = previous.last_seen_at;
+                    entry.last_pong_hash = previous.last_pong_hash;
+                }
+                if verified_at.is_none() && previous.chain_id == chain_id {
+                    entry.last_verified_at = previous.last_verified_at;
+                }
+                registry.entries[index] = entry;
+            }
+            Err(index) => {
+                let count = registry.count as usize;
+                require!(count < REGISTRY_CAPACITY, crate::ErrorCode::RegistryFull);
+                registry.entries.copy_within(index..count, index + 1);
+                registry.entries[index] = entry;
+                registry.count += 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Remove the registry entry for `zrc20` (admin only)
+    pub fn remove_registry_entry(ctx: Context<UpdateRegistry>, zrc20: [u8; 20]) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::RemoveRegistryEntry,
+        )?;
+
+        let mut registry = ctx.accounts.registry.load_mut()?;
+        let index = registry.search(&zrc20).map_err(|_| crate::ErrorCode::NotInRegistry)?;
+        let count = registry.count as usize;
+        registry.entries.copy_within(index + 1..count, index);
+        registry.entries[count - 1] = bytemuck::Zeroable::zeroed();
+        registry.count -= 1;
+
+        emit!(RegistryEntryRemoved {
+            admin: ctx.accounts.admin.key(),
+            zrc20,
+        });
+
+        Ok(())
+    }
+
+    /// Connected contract registered for `zrc20`
+    pub fn lookup_connected_contract(
+        ctx: Context<LookupConnectedContract>,
+        zrc20: [u8; 20],
+    ) -> Result<Vec<u8>> {
+        let registry = ctx.accounts.registry.load()?;
+        let entry = registry.find(&zrc20).ok_or(crate::ErrorCode::NotInRegistry)?;
+        Ok(entry.contract[..entry.contract_len as usize].to_vec())
+    }
+
+    /// Page `page` of the registry in the config_export format, for `import_config` on
+    /// another deployment. Chain configs and origin policies are exported from the accounts
+    /// passed as remaining accounts, as a single page 0
+    pub fn export_config(ctx: Context<LookupConnectedContract>, kind: u8, page: u8) -> Result<Vec<u8>> {
+        let entries = match kind {
+            CONFIG_KIND_REGISTRY => {
+                let registry = ctx.accounts.registry.load()?;
+                let entries = registry.entries();
+                let page_count = ConfigPage::page_count(entries.len());
+                require!(page < page_count, crate::ErrorCode::InvalidConfigPage);
+
+                let entries = entries
+                    .chunks(CONFIG_EXPORT_PAGE_ENTRIES)
+                    .nth(page as usize)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(ConfigEntry::from)
+                    .collect();
+                return Ok(ConfigPage { page, page_count, entries: ConfigEntries::Registry(entries) }.encode());
+            }
+            CONFIG_KIND_CHAIN_CONFIGS => ConfigEntries::ChainConfigs(
+                Self::exported_accounts::<ChainGasConfig, _>(ctx.remaining_accounts, kind)?,
+            ),
+            CONFIG_KIND_ORIGIN_POLICIES => ConfigEntries::OriginPolicies(
+                Self::exported_accounts::<OriginPolicy, _>(ctx.remaining_accounts, kind)?,
+            ),
+            _ => return err!(crate::ErrorCode::InvalidConfigPage),
+        };
+        require!(page == 0, crate::ErrorCode::InvalidConfigPage);
+        Ok(ConfigPage { page, page_count: 1, entries }.encode())
+    }
+
+    /// Export entries of the program-owned `T` accounts given, at most a page of them
+    fn exported_accounts<T: AccountDeserialize, E: for<'a> From<&'a T>>(
+        accounts: &[AccountInfo],
+        kind: u8,
+    ) -> Result<Vec<E>> {
+        require!(
+            accounts.len() <= config_export::page_entries(kind).unwrap_or_default(),
+            crate::ErrorCode::InvalidConfigPage
+        );
+        accounts
+            .iter()
+            .map(|info| {
+                require_keys_eq!(*info.owner, crate::ID, crate::ErrorCode::ConfigAccountMismatch);
+                Ok(E::from(&T::try_deserialize(&mut &info.try_borrow_data()?[..])?))
+            })
+            .collect()
+    }
+
+    /// Write one exported page. Entries already held unchanged are skipped; a different
+    /// entry for the same ZRC-20, chain or origin is only replaced with `force`. Chain
+    /// config and origin policy pages take their PDAs as remaining accounts, in entry
+    /// order, and are validated like their setters (admin only)
+    pub fn import_config<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ImportConfig<'info>>,
+        blob: Vec<u8>,
+        force: bool,
+    ) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::ImportConfig,
+        )?;
+
+        let page = ConfigPage::decode(&blob)?;
+        let mut imported = 0u8;
+        let mut replaced = 0u8;
+        let mut count = |(written, was_replaced): (bool, bool)| {
+            imported += written as u8;
+            replaced += was_replaced as u8;
+        };
+        match &page.entries {
+            ConfigEntries::Registry(entries) => {
+                for entry in entries {
+                    let existing = ctx.accounts.registry.load()?.find(&entry.zrc20).map(ConfigEntry::from);
+                    let was_replaced = match existing {
+                        Some(existing) if existing == *entry => continue,
+                        Some(_) => {
+                            require!(force, crate::ErrorCode::ConfigEntryExists);
+                            true
+                        }
+                        None => false,
+                    };
+                    Self::upsert_registry_entry(
+                        &ctx.accounts.registry,
+                        entry.zrc20,
+                        &entry.contract,
+                        entry.chain_id,
+                        entry.flags,
+                        None,
+                    )?;
+                    count((true, was_replaced));
+                }
+            }
+            ConfigEntries::ChainConfigs(entries) => {
+                require!(ctx.remaining_accounts.len() == entries.len(), crate::ErrorCode::ConfigAccountMismatch);
+                for (entry, info) in entries.iter().zip(ctx.remaining_accounts) {
+                    ChainId(entry.chain_id).require_valid()?;
+                    // Zero gas limits are a config that only ever had its trust set
+                    if entry.gas_limit != 0 || entry.block_gas_limit != 0 {
+                        Self::validate_gas_limit(entry.block_gas_limit, MAX_GAS_LIMIT)?;
+                        Self::validate_gas_limit(entry.gas_limit, entry.block_gas_limit)?;
+                    }
+                    require!(
+                        entry.trust_level == TRUST_LEVEL_TRUSTED || entry.trust_level == TRUST_LEVEL_PROVISIONAL,
+                        crate::ErrorCode::InvalidTrustLevel
+                    );
+
+                    let (expected, bump) = pda::chain_gas(entry.chain_id);
+                    let config = ChainGasConfig {
+                        chain_id: entry.chain_id,
+                        gas_limit: entry.gas_limit,
+                        block_gas_limit: entry.block_gas_limit,
+                        bump,
+                        trust_level: entry.trust_level,
+                        provisional_hold_seconds: entry.provisional_hold_seconds,
+                        supports_batch: entry.supports_batch,
+                    };
+                    count(Self::import_config_account(
+                        &ctx.accounts.admin,
+                        info,
+                        &ctx.accounts.system_program,
+                        expected,
+                        CHAIN_GAS_CONFIG_SPACE,
+                        &[pda::CHAIN_GAS_SEED, &entry.chain_id.to_le_bytes(), &[bump]],
+                        config,
+                        |existing| ChainConfigEntry::from(existing) == *entry,
+                        force,
+                    )?);
+                }
+            }
+            ConfigEntries::OriginPolicies(entries) => {
+                require!(ctx.remaining_accounts.len() == entries.len(), crate::ErrorCode::ConfigAccountMismatch);
+                for (entry, info) in entries.iter().zip(ctx.remaining_accounts) {
+                    ChainId(entry.origin_chain).require_valid()?;
+                    require!(
+                        entry.royalty_bps <= MAX_FEE_BPS
+                            && (entry.royalty_bps == 0 || entry.royalty_recipient != Pubkey::default()),
+                        crate::ErrorCode::InvalidOriginPolicy
+                    );
+
+                    let (expected, bump) = pda::origin_policy(entry.origin_chain, &entry.origin_contract_hash);
+                    let policy = OriginPolicy {
+                        origin_chain: entry.origin_chain,
+                        origin_contract_hash: entry.origin_contract_hash,
+                        royalty_bps: entry.royalty_bps,
+                        royalty_recipient: entry.royalty_recipient,
+                        update_authority_mode: entry.update_authority_mode,
+                        is_mutable: entry.is_mutable,
+                        bump,
+                    };
+                    count(Self::import_config_account(
+                        &ctx.accounts.admin,
+                        info,
+                        &ctx.accounts.system_program,
+                        expected,
+                        ORIGIN_POLICY_SPACE,
+                        &[
+                            pda::ORIGIN_POLICY_SEED,
+                            &entry.origin_chain.to_le_bytes(),
+                            &entry.origin_contract_hash,
+                            &[bump],
+                        ],
+                        policy,
+                        |existing| OriginPolicyEntry::from(existing) == *entry,
+                        force,
+                    )?);
+                }
+            }
+        }
+
+        emit!(ConfigImported {
+            admin: ctx.accounts.admin.key(),
+            kind: page.entries.kind(),
+            page: page.page,
+            page_count: page.page_count,
+            imported,
+            replaced,
+        });
+
+        Ok(())
+    }
+
+    /// Write `account` into the imported PDA `info`, creating it if missing. One already
+    /// holding the same entry is left alone and a different one needs `force`; returns
+    /// whether the account was written and whether that replaced an entry
+    #[allow(clippy::too_many_arguments)]
+    fn import_config_account<'info, T: AccountSerialize + AccountDeserialize>(
+        admin: &Signer<'info>,
+        info: &AccountInfo<'info>,
+        system_program: &Program<'info, System>,
+        expected: Pubkey,
+        space: usize,
+        seeds: &[&[u8]],
+        account: T,
+        unchanged: impl Fn(&T) -> bool,
+        force: bool,
+    ) -> Result<(bool, bool)> {
+        require_keys_eq!(info.key(), expected, crate::ErrorCode::ConfigAccountMismatch);
+        let replaced = if info.data_is_empty() {
+            Self::create_pda_account(
+                &admin.to_account_info(),
+                info,
+                &system_program.to_account_info(),
+                space,
+                &crate::ID,
+                seeds,
+            )?;
+            false
+        } else {
+            require_keys_eq!(*info.owner, crate::ID, crate::ErrorCode::ConfigAccountMismatch);
+            let existing = T::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+            if unchanged(&existing) {
+                return Ok((false, false));
+            }
+            require!(force, crate::ErrorCode::ConfigEntryExists);
+            true
+        };
+        account.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+        Ok((true, replaced))
+    }
+
+    /// Set the fee required to reserve a token id (admin only)
+    pub fn set_reservation_fee(ctx: Context<AdminAction>, reservation_fee: u64) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetReservationFee,
+        )?;
+
+        let previous_reservation_fee = ctx.accounts.program_state.reservation_fee;
+        Self::require_changed(&previous_reservation_fee, &reservation_fee)?;
+        ctx.accounts.program_state.reservation_fee = reservation_fee;
+
+        emit!(ReservationFeeUpdated {
+            admin: ctx.accounts.admin.key(),
+            previous_reservation_fee,
+            reservation_fee,
+        });
+
+        Ok(())
+    }
+
+    /// Limit how many new token ids one metadata URI may arrive under per window; zero
+    /// allows any number, and a zero window restores the default (admin only)
+    pub fn set_uri_dedup_window(
+        ctx: Context<AdminAction>,
+        max_uri_mints_per_window: u64,
+        window_seconds: i64,
+    ) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetUriDedupWindow,
+        )?;
+        require!(window_seconds >= 0, UniversalNFTCoreError::InvalidAmount);
+
+        let program_state = &mut ctx.accounts.program_state;
+        let previous_max_uri_mints_per_window = program_state.max_uri_mints_per_window;
+        let previous_window_seconds = program_state.uri_dedup_window_seconds;
+        Self::require_changed(
+            &(previous_max_uri_mints_per_window, previous_window_seconds),
+            &(max_uri_mints_per_window, window_seconds),
+        )?;
+        program_state.max_uri_mints_per_window = max_uri_mints_per_window;
+        program_state.uri_dedup_window_seconds = window_seconds;
+
+        emit!(UriDedupWindowUpdated {
+            admin: ctx.accounts.admin.key(),
+            previous_max_uri_mints_per_window,
+            previous_window_seconds,
+            max_uri_mints_per_window,
+            window_seconds,
+        });
+
+        Ok(())
+    }
+
+    /// Set the largest outbound gateway message; zero restores the default (admin only)
+    pub fn set_max_message_bytes(ctx: Context<AdminAction>, max_message_bytes: u32) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetMaxMessageBytes,
+        )?;
+
+        let previous_max_message_bytes = ctx.accounts.program_state.max_message_bytes;
+        Self::require_changed(&previous_max_message_bytes, &max_message_bytes)?;
+        ctx.accounts.program_state.max_message_bytes = max_message_bytes;
+
+        emit!(MaxMessageBytesUpdated {
+            admin: ctx.accounts.admin.key(),
+            previous_max_message_bytes,
+            max_message_bytes,
+        });
+
+        Ok(())
+    }
+
+    /// Move the boundary between native and inbound token ids (admin only).
+    /// Ids already issued on either side are not revisited.
+    pub fn set_native_id_base(ctx: Context<AdminAction>, native_id_base: u64) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetNativeIdBase,
+        )?;
+        require!(native_id_base > 0, crate::ErrorCode::TokenIdRangeConflict);
+
+        let program_state = &mut ctx.accounts.program_state;
+        let previous_native_id_base = program_state.native_id_base;
+        Self::require_changed(&previous_native_id_base, &native_id_base)?;
+        program_state.native_id_base = native_id_base;
+        program_state.next_token_id = program_state.next_token_id.max(native_id_base);
+        #[cfg(feature = "runtime-invariants")]
+        crate::invariants::next_token_id(program_state)?;
+
+        emit!(NativeIdBaseUpdated {
+            admin: ctx.accounts.admin.key(),
+            previous_native_id_base,
+            native_id_base,
+        });
+
+        Ok(())
+    }
+
+    /// Put an NFT up for raffle; it moves into the raffle's escrow until the draw
+    pub fn create_raffle(
+        ctx: Context<CreateRaffle>,
+        token_id_to_raffle: u64,
+        ticket_price_lamports: u64,
+        max_tickets: u32,
+        end_time: i64,
+    ) -> Result<()> {
+        Self::require_not_paused(&mut ctx.accounts.program_state)?;
+        require!(
+            ticket_price_lamports > 0 && max_tickets > 0 && end_time > Clock::get()?.unix_timestamp,
+            crate::ErrorCode::InvalidRaffleConfig
+        );
+
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.creator_token_account.to_account_info(),
+                    to: ctx.accounts.raffle_escrow.to_account_info(),
+                    authority: ctx.accounts.creator.to_account_info(),
+                },
+            ),
+            1,
+        ).map_err(cpi_failed(crate::ErrorCode::TokenTransferFailed))?;
+
+        let raffle_counter = &mut ctx.accounts.raffle_counter;
+        let raffle_id = raffle_counter.next_raffle_id;
+        raffle_counter.next_raffle_id = raffle_id.checked_add(1)
+            .ok_or(UniversalNFTCoreError::InvalidAmount)?;
+        raffle_counter.bump = ctx.bumps.raffle_counter;
+
+        let raffle = &mut ctx.accounts.raffle;
+        raffle.raffle_id = raffle_id;
+        raffle.token_id = token_id_to_raffle;
+        raffle.mint = ctx.accounts.mint.key();
+        raffle.creator = ctx.accounts.creator.key();
+        raffle.ticket_price_lamports = ticket_price_lamports;
+        raffle.max_tickets = max_tickets;
+        raffle.ticket_count = 0;
+        raffle.end_time = end_time;
+        raffle.status = RaffleStatus::Open;
+        raffle.winner = Pubkey::default();
+        raffle.bump = ctx.bumps.raffle;
+
+        emit!(RaffleCreated {
+            raffle_id,
+            token_id: token_id_to_raffle,
+            creator: raffle.creator,
+            ticket_price_lamports,
+            max_tickets,
+            end_time,
+        });
+
+        Ok(())
+    }
+
+    /// Buy the next ticket of an open raffle; the price is paid into the raffle account
+    pub fn buy_raffle_ticket(ctx: Context<BuyRaffleTicket>, raffle_id: u64) -> Result<()> {
+        Self::require_not_paused(&mut ctx.accounts.program_state)?;
+        let raffle = &ctx.accounts.raffle;
+        require!(
+            raffle.status == RaffleStatus::Open && Clock::get()?.unix_timestamp < raffle.end_time,
+            crate::ErrorCode::RaffleClosed
+        );
+        require!(raffle.ticket_count < raffle.max_tickets, crate::ErrorCode::RaffleSoldOut);
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.buyer.to_account_info(),
+                    to: ctx.accounts.raffle.to_account_info(),
+                },
+            ),
+            raffle.ticket_price_lamports,
+        )?;
+
+        let index = ctx.accounts.raffle.ticket_count;
+        let ticket = &mut ctx.accounts.ticket;
+        ticket.raffle_id = raffle_id;
+        ticket.index = index;
+        ticket.buyer = ctx.accounts.buyer.key();
+        ticket.bump = ctx.bumps.ticket;
+        ctx.accounts.raffle.ticket_count = index + 1;
+
+        emit!(RaffleTicketBought {
+            raffle_id,
+            index,
+            buyer: ticket.buyer,
+        });
+
+        Ok(())
+    }
+
+    /// Once the raffle has ended or sold out, ask the connected ZetaChain contract for randomness
+    pub fn request_winner_selection(ctx: Context<RequestWinnerSelection>, raffle_id: u64) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+        require!(raffle.status == RaffleStatus::Open, crate::ErrorCode::RaffleClosed);
+        require!(
+            Clock::get()?.unix_timestamp >= raffle.end_time || raffle.ticket_count == raffle.max_tickets,
+            crate::ErrorCode::RaffleNotEnded
+        );
+        require!(raffle.ticket_count > 0, crate::ErrorCode::NoRaffleTickets);
+        raffle.status = RaffleStatus::AwaitingRandomness;
+        raffle.end_time = raffle.end_time.max(Clock::get()?.unix_timestamp);
+
+        let request = RandomnessRequest {
+            raffle_id,
+            ticket_count: raffle.ticket_count,
+            requested_at: Clock::get()?.unix_timestamp,
+        };
+        UniversalNFTCoreImpl::call_gateway(
+            ctx.accounts.gateway_program.to_account_info(),
+            ctx.accounts.requester.to_account_info(),
+            ctx.accounts.program_state.universal_nft_contract,
+            UniversalNFTCoreImpl::encode_randomness_request(&request)?,
+            &ZetaChainCallOptions {
+                gas_limit: ctx.accounts.program_state.gas_limit,
+                ..ZetaChainCallOptions::default()
+            },
+        ).map_err(cpi_failed(UniversalNFTCoreError::GatewayCallFailed))?;
+
+        emit!(RaffleWinnerRequested {
+            raffle_id,
+            ticket_count: request.ticket_count,
+        });
+
+        Ok(())
+    }
+
+    /// Settle a raffle with the gateway's random number: the NFT goes to ticket
+    /// `random_number % ticket_count` and the ticket proceeds to the creator
+    pub fn fulfill_winner_selection(
+        ctx: Context<FulfillWinnerSelection>,
+        raffle_id: u64,
+        random_number: u64,
+    ) -> Result<()> {
+        let raffle = &ctx.accounts.raffle;
+        require!(raffle.status == RaffleStatus::AwaitingRandomness, crate::ErrorCode::RaffleClosed);
+        let winner_index = (random_number % raffle.ticket_count as u64) as u32;
+        require_eq!(ctx.accounts.winning_ticket.index, winner_index, crate::ErrorCode::WinnerIndexMismatch);
+
+        let raffle_seeds: &[&[&[u8]]] = &[&[pda::RAFFLE_SEED, &raffle_id.to_le_bytes(), &[raffle.bump]]];
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.raffle_escrow.to_account_info(),
+                    to: ctx.accounts.winner_token_account.to_account_info(),
+                    authority: ctx.accounts.raffle.to_account_info(),
+                },
+                raffle_seeds,
+            ),
+            1,
+        ).map_err(cpi_failed(crate::ErrorCode::TokenTransferFailed))?;
+        anchor_spl::token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::CloseAccount {
+                account: ctx.accounts.raffle_escrow.to_account_info(),
+                destination: ctx.accounts.creator.to_account_info(),
+                authority: ctx.accounts.raffle.to_account_info(),
+            },
+            raffle_seeds,
+        )).map_err(cpi_failed(crate::ErrorCode::TokenAccountUpdateFailed))?;
+
+        // Everything above rent is ticket proceeds
+        let raffle_info = ctx.accounts.raffle.to_account_info();
+        let proceeds_lamports = raffle_info.lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(raffle_info.data_len()));
+        Self::move_program_lamports(&raffle_info, &ctx.accounts.creator.to_account_info(), proceeds_lamports)?;
+
+        let raffle = &mut ctx.accounts.raffle;
+        raffle.status = RaffleStatus::Settled;
+        raffle.winner = ctx.accounts.winner.key();
+
+        emit!(RaffleSettled {
+            raffle_id,
+            token_id: raffle.token_id,
+            winner: raffle.winner,
+            winner_index,
+            proceeds_lamports,
+        });
+
+        Ok(())
+    }
+
+    /// Call off a raffle that can't be drawn: one that ended without tickets, or whose random
+    /// number hasn't arrived RAFFLE_RANDOMNESS_TIMEOUT_SECONDS after the draw. The NFT goes
+    /// back to the creator and each ticket can then be refunded. Anyone may call it
+    pub fn cancel_raffle(ctx: Context<CancelRaffle>, raffle_id: u64) -> Result<()> {
+        let raffle = &ctx.accounts.raffle;
+        let now = Clock::get()?.unix_timestamp;
+        match raffle.status {
+            RaffleStatus::Open => {
+                require!(raffle.ticket_count == 0, crate::ErrorCode::RaffleClosed);
+                require!(now >= raffle.end_time, crate::ErrorCode::RaffleNotEnded);
+            }
+            RaffleStatus::AwaitingRandomness => require!(
+                now >= raffle.end_time.saturating_add(RAFFLE_RANDOMNESS_TIMEOUT_SECONDS),
+                crate::ErrorCode::RaffleNotEnded
+            ),
+            RaffleStatus::Settled | RaffleStatus::Cancelled => return err!(crate::ErrorCode::RaffleClosed),
+        }
+
+        let raffle_seeds: &[&[&[u8]]] = &[&[pda::RAFFLE_SEED, &raffle_id.to_le_bytes(), &[raffle.bump]]];
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.raffle_escrow.to_account_info(),
+                    to: ctx.accounts.creator_token_account.to_account_info(),
+                    authority: ctx.accounts.raffle.to_account_info(),
+                },
+                raffle_seeds,
+            ),
+            1,
+        ).map_err(cpi_failed(crate::ErrorCode::TokenTransferFailed))?;
+        anchor_spl::token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::CloseAccount {
+                account: ctx.accounts.raffle_escrow.to_account_info(),
+                destination: ctx.accounts.creator.to_account_info(),
+                authority: ctx.accounts.raffle.to_account_info(),
+            },
+            raffle_seeds,
+        )).map_err(cpi_failed(crate::ErrorCode::TokenAccountUpdateFailed))?;
+
+        // The ticket proceeds stay on the raffle account until each ticket is refunded
+        let raffle = &mut ctx.accounts.raffle;
+        raffle.status = RaffleStatus::Cancelled;
+
+        emit!(RaffleCancelled {
+            raffle_id,
+            token_id: raffle.token_id,
+            ticket_count: raffle.ticket_count,
+        });
+
+        Ok(())
+    }
+
+    /// Pay a ticket of a cancelled raffle back to its buyer and close it. Anyone may call it
+    pub fn claim_raffle_refund(ctx: Context<ClaimRaffleRefund>, raffle_id: u64, ticket_index: u32) -> Result<()> {
+        let raffle = &ctx.accounts.raffle;
+        require!(raffle.status == RaffleStatus::Cancelled, crate::ErrorCode::RaffleClosed);
+
+        let lamports = raffle.ticket_price_lamports;
+        Self::move_program_lamports(
+            &ctx.accounts.raffle.to_account_info(),
+            &ctx.accounts.buyer.to_account_info(),
+            lamports,
+        )?;
+
+        emit!(RaffleTicketRefunded {
+            raffle_id,
+            index: ticket_index,
+            buyer: ctx.accounts.buyer.key(),
+            lamports,
+        });
+
+        Ok(())
+    }
+
+    /// Replace or clear the key for the NFT's encrypted metadata (current holder only)
+    pub fn update_metadata_encryption_key(
+        ctx: Context<UpdateMetadataEncryptionKey>,
+        token_id: u64,
+        new_key: Option<[u8; 32]>,
+    ) -> Result<()> {
+        ctx.accounts.nft_origin.metadata_encryption_key = new_key;
+
+        emit!(MetadataEncryptionKeyUpdated {
+            token_id,
+            holder: ctx.accounts.holder.key(),
+            metadata_encryption_key: new_key,
+        });
+
+        Ok(())
+    }
+
+    /// Give an NFT that arrived without a URI the one the attestation key signed for it.
+    /// Works once; replacing a URI the NFT already has needs the admin as well
+    pub fn attach_metadata(
+        ctx: Context<AttachMetadata>,
+        token_id: u64,
+        uri: String,
+        attestation_sig: [u8; 64],
+    ) -> Result<()> {
+        require!(
+            !uri.is_empty() && uri.len() <= MAX_METADATA_URI_BYTES,
+            UniversalNFTCoreError::InvalidUriEncoding
+        );
+        let attestation_key = ctx.accounts.program_state.attestation_key;
+        require!(attestation_key != Pubkey::default(), crate::ErrorCode::InvalidAttestation);
+
+        let overridden = !ctx.accounts.nft_origin.metadata_uri.is_empty();
+        if overridden {
+            let admin = ctx.accounts.admin.as_ref()
+                .ok_or(crate::ErrorCode::MetadataAlreadyAttached)?;
+            Self::require_admin(&ctx.accounts.program_state, admin, AdminActionKind::AttachMetadataOverride)?;
+        }
+
+        let origin_chain = ctx.accounts.nft_origin.origin_chain;
+        let mut message = ATTESTATION_DOMAIN.to_vec();
+        message.extend_from_slice(&token_id.to_le_bytes());
+        message.extend_from_slice(&origin_chain.to_le_bytes());
+        message.extend_from_slice(uri.as_bytes());
+        Self::verify_ed25519_attestation(&ctx.accounts.instructions, &attestation_key, &message, &attestation_sig)?;
+
+        ctx.accounts.nft_origin.metadata_uri = uri.clone();
+
+        emit!(MetadataAttached {
+            token_id,
+            origin_chain,
+            uri,
+            attestation_key,
+            overridden,
+        });
+
+        Ok(())
+    }
+
+    /// Check that the instruction before this one is an ed25519 program verification of
+    /// exactly `signature` by `signer` over `message`, all carried in its own data. The
+    /// ed25519 program has already checked the signature itself, or the transaction failed
+    fn verify_ed25519_attestation(
+        instructions: &AccountInfo,
+        signer: &Pubkey,
+        message: &[u8],
+        signature: &[u8; 64],
+    ) -> Result<()> {
+        use anchor_lang::solana_program::sysvar::instructions::get_instruction_relative;
+
+        let ed25519_ix = get_instruction_relative(-1, instructions)
+            .map_err(|_| crate::ErrorCode::InvalidAttestation)?;
+        require_keys_eq!(
+            ed25519_ix.program_id,
+            anchor_lang::solana_program::ed25519_program::ID,
+            crate::ErrorCode::InvalidAttestation
+        );
+
+        // One signature: [count, padding] then seven u16 offsets, where an instruction index
+        // of u16::MAX points into this same instruction's data
+        let data = &ed25519_ix.data;
+        require!(data.len() >= 16 && data[0] == 1, crate::ErrorCode::InvalidAttestation);
+        let offset = |i: usize| u16::from_le_bytes([data[2 + 2 * i], data[3 + 2 * i]]) as usize;
+        let (signature_offset, public_key_offset, message_offset, message_size) =
+            (offset(0), offset(2), offset(4), offset(5));
+        require!(
+            [offset(1), offset(3), offset(6)].iter().all(|&index| index == u16::MAX as usize),
+            crate::ErrorCode::InvalidAttestation
+        );
+
+        let slice = |start: usize, len: usize| data.get(start..start + len);
+        require!(
+            slice(public_key_offset, 32) == Some(signer.as_ref())
+                && slice(signature_offset, 64) == Some(&signature[..])
+                && slice(message_offset, message_size) == Some(message),
+            crate::ErrorCode::InvalidAttestation
+        );
+        Ok(())
+    }
+
+    /// Set the lamports a sender must hold to start a cross-chain transfer; zero restores
+    /// MIN_USER_SOL_RESERVE (admin only)
+    pub fn set_min_user_sol_reserve(ctx: Context<AdminAction>, reserve: u64) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetMinUserSolReserve,
+        )?;
+
+        let previous_min_user_sol_reserve = ctx.accounts.program_state.min_user_sol_reserve;
+        Self::require_changed(&previous_min_user_sol_reserve, &reserve)?;
+        ctx.accounts.program_state.min_user_sol_reserve = reserve;
+
+        emit!(MinUserSolReserveUpdated {
+            admin: ctx.accounts.admin.key(),
+            previous_min_user_sol_reserve,
+            min_user_sol_reserve: reserve,
+        });
+
+        Ok(())
+    }
+
+    /// Route transfers and deliveries through `bridge_backend` (admin only)
+    pub fn set_bridge_backend(ctx: Context<AdminAction>, bridge_backend: BridgeBackend) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetBridgeBackend,
+        )?;
+
+        let previous_bridge_backend = ctx.accounts.program_state.bridge_backend;
+        Self::require_changed(&previous_bridge_backend, &bridge_backend)?;
+        ctx.accounts.program_state.bridge_backend = bridge_backend;
+
+        emit!(BridgeBackendUpdated {
+            admin: ctx.accounts.admin.key(),
+            previous_bridge_backend,
+            bridge_backend,
+        });
+
+        Ok(())
+    }
+
+    /// Rotate the key that signs metadata attestations; the default key turns
+    /// attach_metadata off (admin only)
+    pub fn set_attestation_key(ctx: Context<AdminAction>, attestation_key: Pubkey) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetAttestationKey,
+        )?;
+
+        Self::require_not_program_key(&attestation_key)?;
+        let previous_attestation_key = ctx.accounts.program_state.attestation_key;
+        Self::require_changed(&previous_attestation_key, &attestation_key)?;
+        ctx.accounts.program_state.attestation_key = attestation_key;
+
+        emit!(AttestationKeyUpdated {
+            admin: ctx.accounts.admin.key(),
+            previous_attestation_key,
+            attestation_key,
+        });
+
+        Ok(())
+    }
+
+    /// Reserve a token id ahead of a drop; `payment` is held until the mint or expiry
+    pub fn reserve_token_id(
+        ctx: Context<ReserveTokenId>,
+        token_id: u64,
+        payment: u64,
+    ) -> Result<()> {
+        Self::require_not_paused(&mut ctx.accounts.program_state)?;
+        require!(
+            token_id >= ctx.accounts.program_state.native_id_base,
+            crate::ErrorCode::TokenIdRangeConflict
+        );
+        require!(
+            payment >= ctx.accounts.program_state.reservation_fee,
+            crate::ErrorCode::InsufficientReservationPayment
+        );
+
+        if payment > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.user.to_account_info(),
+                        to: ctx.accounts.reservation.to_account_info(),
+                    },
+                ),
+                payment,
+            )?;
+        }
+
+        let reservation = &mut ctx.accounts.reservation;
+        reservation.token_id = token_id;
+        reservation.reserved_by = ctx.accounts.user.key();
+        reservation.reservation_fee = payment;
+        reservation.expires_at = Clock::get()?.unix_timestamp
+            .checked_add(RESERVATION_TTL_SECONDS)
+            .ok_or(UniversalNFTCoreError::InvalidAmount)?;
+        reservation.bump = ctx.bumps.reservation;
+
+        emit!(TokenIdReserved {
+            token_id,
+            reserved_by: reservation.reserved_by,
+            reservation_fee: payment,
+            expires_at: reservation.expires_at,
+        });
+
+        Ok(())
+    }
+
+    /// Refund and close a reservation that expired without a mint (crank; relayers only while the allowlist is on)
+    pub fn close_expired_reservation(
+        ctx: Context<CloseExpiredReservation>,
+        token_id: u64,
+    ) -> Result<()> {
+        Self::require_relayer(&ctx.accounts.relayer_config, &ctx.accounts.relayer_role)?;
+        require!(
+            Clock::get()?.unix_timestamp >= ctx.accounts.reservation.expires_at,
+            crate::ErrorCode::ReservationNotExpired
+        );
+
+        emit!(ReservationClosed {
+            token_id,
+            reserved_by: ctx.accounts.reservation.reserved_by,
+            refunded_lamports: ctx.accounts.reservation.to_account_info().lamports(),
+            cranked_by: ctx.accounts.cranker.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Transfer an NFT to `final_receiver` on `final_chain_id`, either ZetaChain itself or
+    /// a chain the ZetaChain contract forwards to through that chain's registered ZRC-20
+    pub fn transfer_cross_chain(
+        ctx: Context<CrossChainTransfer>,
+        _token_id: u64,
+        final_chain_id: u64,
+        final_receiver: Vec<u8>,
+        memo: Option<String>,
+        note: Option<Vec<u8>>,
+        amount: Option<u64>,
+    ) -> Result<()> {
+        Self::require_top_level()?;
+        Self::require_unlocked(&ctx.accounts.program_state)?;
+        // 1. Plan the transfer from a copy of what its rules read
+        let snapshot = Self::transfer_snapshot(ctx.accounts, ctx.remaining_accounts, final_chain_id)?;
+        let plan = planner::plan_transfer(&snapshot, &TransferArgs {
+            final_chain_id,
+            final_receiver: &final_receiver,
+            memo: memo.as_deref(),
+            note: note.as_deref(),
+            amount,
+        })?;
+
+        // 1a. Check the accounts themselves
+        Self::check_transfer_accounts(ctx.accounts)?;
+        // Bridged mints must still answer to the program PDA; a rotated authority means
+        // the supply is no longer under our control. Native mints keep their creator.
+        if !ChainId(ctx.accounts.nft_origin.origin_chain).is_solana() {
+            let (mint_authority, _) = pda::mint_authority();
+            Self::verify_mint_authority(&ctx.accounts.mint.to_account_info(), &mint_authority)?;
+        }
+        let mut events = EventBuffer::new();
+        Self::authorize_co_owned_transfer(ctx.accounts, &mut events)?;
+
+        Self::execute_transfer(ctx, plan, events)
+    }
+
+    /// Copy what `plan_transfer` reads out of a transfer's accounts. Mismatched fee, registry
+    /// and vault accounts are recorded rather than refused, so the planner raises them in turn
+    fn transfer_snapshot(
+        accounts: &CrossChainTransfer,
+        remaining_accounts: &[AccountInfo],
+        final_chain_id: u64,
+    ) -> Result<TransferSnapshot> {
+        let clock = Clock::get()?;
+        let program_state = &accounts.program_state;
+        let nft_origin = &accounts.nft_origin;
+        let fee = Some(FeeSnapshot {
+            transfer_fee_lamports: accounts.fee_config.transfer_fee_lamports,
+            treasury: accounts.fee_config.treasury,
+            passed_treasury: Some(accounts.fee_treasury.key()),
+            discount_percent: Self::resolve_fee_discount(
+                &accounts.fee_config,
+                &accounts.user.key(),
+                remaining_accounts.first(),
+            )?,
+        });
+        let registry_zrc20 = match &accounts.registry {
+            Some(registry) => registry.load()?.find_by_chain(final_chain_id).map(|entry| entry.zrc20),
+            None => None,
+        };
+        let gas_vault = accounts.zrc20_gas_vault.as_ref().map(|vault| GasVaultSnapshot {
+            zrc20: vault.zrc20,
+            gas_fee: vault.gas_fee,
+            balance: accounts.zrc20_vault_token_account.as_ref().map_or(0, |token_account| token_account.amount),
+            accounts_match: match (&accounts.zrc20_vault_token_account, &accounts.gas_fee_destination) {
+                (Some(token_account), Some(fee_destination)) => {
+                    token_account.owner == vault.key()
+                        && token_account.mint == vault.mint
+                        && fee_destination.key() == vault.fee_destination
+                }
+                _ => false,
+            },
+        });
+
+        Ok(TransferSnapshot {
+            now: clock.unix_timestamp,
+            slot: clock.slot,
+            paused: program_state.paused,
+            auto_unpause_at: program_state.auto_unpause_at,
+            allow_semi_fungible: program_state.allow_semi_fungible,
+            min_user_sol_reserve: program_state.min_user_sol_reserve,
+            gas_limit: program_state.gas_limit,
+            max_message_bytes: program_state.max_message_bytes,
+            transfer_nonce: program_state.transfer_nonce,
+            user: accounts.user.key(),
+            user_lamports: accounts.user.lamports(),
+            holding: Holding {
+                owner: accounts.user_token_account.owner,
+                amount: accounts.user_token_account.amount,
+                delegated_amount: accounts.user_token_account.delegated_amount,
+            },
+            mint: accounts.mint.key(),
+            token_id: nft_origin.token_id,
+            origin_chain: nft_origin.origin_chain,
+            arrived_at: nft_origin.arrived_at,
+            semi_fungible: nft_origin.is_semi_fungible(),
+            rental_locked_until: nft_origin.rental_locked_until,
+            rental_locker: nft_origin.rental_locker,
+            logical_owner: nft_origin.logical_owner,
+            metadata_uri: nft_origin.metadata_uri.clone(),
+            metadata_encryption_key: nft_origin.metadata_encryption_key,
+            provenance: nft_origin.provenance(),
+            fee,
+            registry_zrc20,
+            gas_vault,
+            chain_gas_config: Some((**accounts.chain_gas_config).clone()),
+            receipt_uri_base: accounts.receipt_config.as_ref()
+                .filter(|config| config.enabled)
+                .map(|config| config.uri_base.clone()),
+            insurance_payout: accounts.insurance_opt_in.as_ref()
+                .filter(|opt_in| opt_in.opted_in)
+                .map(|opt_in| opt_in.payout_lamports),
+        })
+    }
+
+    /// Carry out a planned transfer: its state writes, then its CPIs, then its events
+    fn execute_transfer(ctx: Context<CrossChainTransfer>, plan: TransferPlan, mut events: EventBuffer) -> Result<()> {
+        let token_id = plan.initiated.token_id;
+        if let Some(scheduled_at) = plan.auto_unpause {
+            Self::apply_auto_unpause(&mut ctx.accounts.program_state, scheduled_at, plan.pending.submitted_at);
+        }
+        if let Some(memo) = plan.last_memo {
+            ctx.accounts.nft_origin.last_memo = memo;
+        }
+        if let Some(rental_lock_cleared) = plan.rental_lock_cleared {
+            ctx.accounts.nft_origin.rental_locked_until = 0;
+            ctx.accounts.nft_origin.rental_locker = Pubkey::default();
+            events.push(rental_lock_cleared);
+        }
+        if let Some(fee_collected) = plan.fee_collected {
+            events.push(fee_collected);
+        }
+        if let Some(uri_hashed) = plan.uri_hashed {
+            events.push(uri_hashed);
+        }
+
+        // 2. Record the transfer so the sender can take it back during the cancel window
+        let pending = &plan.pending;
+        let pending_transfer = &mut ctx.accounts.pending_transfer;
+        pending_transfer.nonce = pending.nonce;
+        pending_transfer.token_id = token_id;
+        pending_transfer.sender = ctx.accounts.user.key();
+        pending_transfer.submitted_at = pending.submitted_at;
+        pending_transfer.destination = pending.destination;
+        pending_transfer.status = PENDING_TRANSFER_STATUS_PENDING;
+        pending_transfer.bump = ctx.bumps.pending_transfer;
+        pending_transfer.burn_commitment = pending.burn_commitment;
+        pending_transfer.amount = pending.amount;
+        if let Some(payout_lamports) = pending.insured_payout_lamports {
+            if let Some(insurance_opt_in) = ctx.accounts.insurance_opt_in.as_mut() {
+                insurance_opt_in.opted_in = false;
+            }
+            pending_transfer.insured = true;
+            pending_transfer.insured_payout_lamports = payout_lamports;
+        }
+        ctx.accounts.program_state.transfer_nonce = plan.next_transfer_nonce;
+        Self::append_event_log(
+            &ctx.accounts.event_log,
+            EVENT_LOG_TRANSFER_OUT,
+            token_id,
+            plan.receiver,
+            pending.nonce,
+            pending.submitted_at,
+        )?;
+
+        // 2a. Count the departing units against the origin chain
+        if let Some(departure) = plan.departure {
+            Self::update_chain_stats(&ctx.accounts.chain_stats, departure.origin_chain, departure.token_id, |stats| {
+                stats.departed = stats.departed.saturating_add(departure.amount);
+                stats.active = stats.active.saturating_sub(departure.amount);
+                stats.cumulative_dwell_seconds = stats.cumulative_dwell_seconds.saturating_add(departure.dwell_seconds);
+            }, &mut events)?;
+        }
+        if plan.receipt_outstanding {
+            ctx.accounts.nft_origin.receipt_outstanding = true;
+        }
+        let nft_origin = &mut ctx.accounts.nft_origin;
+        nft_origin.departed_amount = nft_origin.departed_amount.saturating_add(pending.amount);
+
+        // 3. CPIs, after every state write
+        for cpi in plan.cpis {
+            match cpi {
+                TransferCpi::Memo { memo } => Self::log_memo(&ctx.accounts.memo_program, &Some(memo))?,
+                TransferCpi::Fee { lamports } => {
+                    let treasury = &ctx.accounts.fee_treasury;
+                    system_program::transfer(
+                        CpiContext::new(
+                            ctx.accounts.system_program.to_account_info(),
+                            Transfer {
+                                from: ctx.accounts.user.to_account_info(),
+                                to: treasury.to_account_info(),
+                            },
+                        ),
+                        lamports,
+                    )?;
+                }
+                TransferCpi::GasFromVault { gas_fee } => {
+                    Self::pay_gas_from_vault(ctx.accounts, token_id, gas_fee, &mut events)?;
+                }
+                // Burn the NFT, or the units sent, on Solana (like EVM _burn)
+                TransferCpi::Burn { amount } => {
+                    Self::require_burnable(&ctx.accounts.user_token_account, &ctx.accounts.user.key())?;
+                    let burn_ctx = CpiContext::new(
+                        ctx.accounts.token_program.to_account_info(),
+                        Burn {
+                            mint: ctx.accounts.mint.to_account_info(),
+                            from: ctx.accounts.user_token_account.to_account_info(),
+                            authority: ctx.accounts.user.to_account_info(),
+                        },
+                    );
+                    anchor_spl::token::burn(burn_ctx, amount).map_err(cpi_failed(crate::ErrorCode::BurnFailed))?;
+                }
+                // Hand the message to the bridge; on ZetaChain this calls the universal
+                // contract through the gateway (like EVM gateway.call)
+                TransferCpi::SendNft { message } => {
+                    let bridge = ctx.accounts.program_state.bridge_backend.bridge(
+                        ctx.accounts.user.to_account_info(),
+                        ctx.accounts.program_state.universal_nft_contract,
+                        plan.call_options,
+                    );
+                    Self::send_locked(
+                        &mut ctx.accounts.program_state,
+                        bridge,
+                        plan.final_chain.0,
+                        token_id,
+                        message,
+                        ctx.accounts.gateway_program.to_account_info(),
+                    )?;
+                }
+                // Leave a departure receipt in the sender's wallet
+                TransferCpi::IssueReceipt { uri_base } => {
+                    Self::issue_departure_receipt(ctx.accounts, ctx.bumps.mint_authority, &uri_base, &mut events)?;
+                }
+            }
+        }
+
+        Self::record_hourly_metrics(&ctx.accounts.hourly_metrics, |metrics| {
+            metrics.burns = metrics.burns.saturating_add(1);
+            metrics.total_gas_used = metrics.total_gas_used.saturating_add(plan.call_options.gas_limit);
+            metrics.active_users = metrics.active_users.saturating_add(1);
+        })?;
+
+        // 4. Emit transfer event (like EVM TokenTransfer) after the rest
+        events.push(plan.initiated);
+        // Units of a semi-fungible token may stay behind, so only NFTs must be gone
+        #[cfg(feature = "runtime-invariants")]
+        if !ctx.accounts.nft_origin.is_semi_fungible() {
+            crate::invariants::token_departed(&ctx.accounts.mint.to_account_info(), ctx.accounts.nft_origin.stranded_supply)?;
+        }
+        events.flush();
+
+        Ok(())
+    }
+
+    /// ZetaChain itself is addressed with a zero destination; any other chain goes through
+    /// its ZRC-20, which must be in the registry
+    fn resolve_destination(final_chain: ChainId, registry: &Option<AccountLoader<Registry>>) -> Result<[u8; 20]> {
+        if final_chain.is_zetachain() {
+            return Ok([0u8; 20]);
+        }
+        let registry = registry.as_ref()
+            .ok_or(crate::ErrorCode::NotInRegistry)?
+            .load()?;
+        Ok(registry.find_by_chain(final_chain.0)
+            .ok_or(crate::ErrorCode::NotInRegistry)?
+            .zrc20)
+    }
+
+    /// Move `gas_fee` from the vault `plan_transfer` checked to its fee destination,
+    /// signed by the vault PDA
+    fn pay_gas_from_vault(
+        accounts: &CrossChainTransfer,
+        token_id: u64,
+        gas_fee: u64,
+        events: &mut EventBuffer,
+    ) -> Result<()> {
+        let (Some(vault), Some(vault_token_account), Some(fee_destination)) = (
+            &accounts.zrc20_gas_vault,
+            &accounts.zrc20_vault_token_account,
+            &accounts.gas_fee_destination,
+        ) else {
+            return err!(crate::ErrorCode::ZRC20GasVaultMismatch);
+        };
+        let vault_seeds: &[&[&[u8]]] = &[&[pda::ZRC20_VAULT_SEED, &vault.zrc20, &[vault.bump]]];
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: vault_token_account.to_account_info(),
+                    to: fee_destination.to_account_info(),
+                    authority: vault.to_account_info(),
+                },
+                vault_seeds,
+            ),
+            gas_fee,
+        ).map_err(cpi_failed(crate::ErrorCode::TokenTransferFailed))?;
+
+        events.push(GasPaidFromVault {
+            token_id,
+            zrc20: vault.zrc20,
+            amount: gas_fee,
+            fee_destination: fee_destination.key(),
+            remaining: vault_token_account.amount - gas_fee,
+        });
+        Ok(())
+    }
+
+    /// Log a memo kept as the token's `last_memo` through the SPL Memo program; the first
+    /// CPI of the handler
+    fn log_memo(memo_program: &Program<Memo>, memo: &Option<String>) -> Result<()> {
+        let Some(memo) = memo else {
+            return Ok(());
+        };
+        anchor_spl::memo::build_memo(
+            CpiContext::new(memo_program.to_account_info(), anchor_spl::memo::BuildMemo {}),
+            memo.as_bytes(),
+        )
+    }
+
+    /// A co-owned NFT leaves only with its CoOwnershipRecord, sent by one of its owners and,
+    /// with require_both_signatures, with the other's consent for the record's current
+    /// nonce. That nonce is used up, so each consent covers one transfer
+    fn authorize_co_owned_transfer(accounts: &mut CrossChainTransfer, events: &mut EventBuffer) -> Result<()> {
+        let Some(record) = accounts.co_ownership.as_deref_mut() else {
+            require!(!accounts.nft_origin.co_owned, crate::ErrorCode::CoOwnerConsentRequired);
+            return Ok(());
+        };
+        let initiator = accounts.user.key();
+        let other_owner = match initiator {
+            owner if owner == record.owner_a => record.owner_b,
+            owner if owner == record.owner_b => record.owner_a,
+            _ => return err!(crate::ErrorCode::NotCoOwner),
+        };
+
+        let consent_nonce = if record.require_both_signatures {
+            let consent = accounts.co_owner_consent.as_deref()
+                .ok_or(crate::ErrorCode::CoOwnerConsentRequired)?;
+            // Consents are keyed by the record's creation time, so one made for an earlier,
+            // dissolved record of the same mint is at another address
+            let (expected, _) = pda::co_owner_consent(&record.mint, record.created_at, record.consent_nonce);
+            require!(
+                consent.key() == expected
+                    && consent.mint == record.mint
+                    && consent.nonce == record.consent_nonce
+                    && consent.approver == other_owner,
+                crate::ErrorCode::CoOwnerConsentRequired
+            );
+            let nonce = record.consent_nonce;
+            record.consent_nonce = nonce.checked_add(1).ok_or(UniversalNFTCoreError::InvalidAmount)?;
+            Some(nonce)
+        } else {
+            None
+        };
+
+        events.push(CoOwnedTransferAuthorized {
+            token_id: record.token_id,
+            initiator,
+            consent_nonce,
+        });
+        Ok(())
+    }
+
+    /// Defense in depth on top of the account constraints: no account may fill two roles,
+    /// and the token account must hold this origin's mint. Logs which check failed
+    fn check_transfer_accounts(accounts: &CrossChainTransfer) -> Result<()> {
+        let roles = [
+            ("program_state", accounts.program_state.key()),
+            ("nft_origin", accounts.nft_origin.key()),
+            ("mint", accounts.mint.key()),
+            ("user_token_account", accounts.user_token_account.key()),
+        ];
+        for (i, (name, key)) in roles.iter().enumerate() {
+            for (other_name, other_key) in &roles[i + 1..] {
+                if key == other_key {
+                    msg!("Transfer account check failed: {} is also passed as {}", name, other_name);
+                    return err!(crate::ErrorCode::AccountAliasing);
+                }
+            }
+        }
+
+        if *accounts.user_token_account.to_account_info().owner != accounts.token_program.key() {
+            msg!("Transfer account check failed: user_token_account is not owned by the token program");
+            return err!(crate::ErrorCode::InvalidTokenAccountOwner);
+        }
+        if accounts.mint.key() != accounts.nft_origin.mint {
+            msg!("Transfer account check failed: mint is not nft_origin.mint");
+            return err!(crate::ErrorCode::BridgedMintMismatch);
+        }
+        if accounts.user_token_account.mint != accounts.mint.key() {
+            msg!("Transfer account check failed: user_token_account holds another mint");
+            return err!(crate::ErrorCode::BridgedMintMismatch);
+        }
+
+        Ok(())
+    }
+
+    /// Mint one receipt token to the sender, make the mint authority PDA its delegate so the
+    /// return path can burn it, then freeze it so it cannot change hands
+    fn issue_departure_receipt(
+        accounts: &CrossChainTransfer,
+        mint_authority_bump: u8,
+        uri_base: &str,
+        events: &mut EventBuffer,
+    ) -> Result<()> {
+        let (Some(receipt_mint), Some(receipt_token_account)) =
+            (&accounts.receipt_mint, &accounts.receipt_token_account)
+        else {
+            return err!(crate::ErrorCode::ReceiptRequired);
+        };
+        let token_program = accounts.token_program.to_account_info();
+        let mint_authority_seeds: &[&[&[u8]]] = &[&[pda::MINT_AUTHORITY_SEED, &[mint_authority_bump]]];
+
+        anchor_spl::token::mint_to(
+            CpiContext::new_with_signer(
+                token_program.clone(),
+                MintTo {
+                    mint: receipt_mint.to_account_info(),
+                    to: receipt_token_account.to_account_info(),
+                    authority: accounts.mint_authority.to_account_info(),
+                },
+                mint_authority_seeds,
+            ),
+            1,
+        ).map_err(cpi_failed(crate::ErrorCode::MintToFailed))?;
+        anchor_spl::token::approve(
+            CpiContext::new(
+                token_program.clone(),
+                anchor_spl::token::Approve {
+                    to: receipt_token_account.to_account_info(),
+                    delegate: accounts.mint_authority.to_account_info(),
+                    authority: accounts.user.to_account_info(),
+                },
+            ),
+            1,
+        ).map_err(cpi_failed(crate::ErrorCode::TokenAccountUpdateFailed))?;
+        anchor_spl::token::freeze_account(CpiContext::new_with_signer(
+            token_program,
+            anchor_spl::token::FreezeAccount {
+                account: receipt_token_account.to_account_info(),
+                mint: receipt_mint.to_account_info(),
+                authority: accounts.mint_authority.to_account_info(),
+            },
+            mint_authority_seeds,
+        )).map_err(cpi_failed(crate::ErrorCode::TokenAccountUpdateFailed))?;
+
+        // Receipt metadata would be a Metaplex account at this URI; metadata CPIs stay off
+        // until the Token Metadata program is available, as in create_mint_and_nft
+        let token_id = accounts.nft_origin.token_id;
+        events.push(DepartureReceiptIssued {
+            token_id,
+            holder: accounts.user.key(),
+            receipt_mint: receipt_mint.key(),
+            uri: format!("{}/{}.json", uri_base.trim_end_matches('/'), token_id),
+        });
+
+        Ok(())
+    }
+
+    /// Thaw and burn an outstanding departure receipt. Must run before the NFT is handed
+    /// back, so a failed burn leaves the token where it was.
+    fn burn_departure_receipt<'info>(
+        receipt_mint: &Option<Account<'info, Mint>>,
+        receipt_token_account: &Option<Account<'info, TokenAccount>>,
+        mint_authority: &AccountInfo<'info>,
+        mint_authority_bump: u8,
+        token_program: &AccountInfo<'info>,
+        token_id: u64,
+        events: &mut EventBuffer,
+    ) -> Result<()> {
+        let (Some(receipt_mint), Some(receipt_token_account)) = (receipt_mint, receipt_token_account) else {
+            return err!(crate::ErrorCode::ReceiptRequired);
+        };
+        require!(receipt_token_account.amount == 1, crate::ErrorCode::ReceiptRequired);
+        let mint_authority_seeds: &[&[&[u8]]] = &[&[pda::MINT_AUTHORITY_SEED, &[mint_authority_bump]]];
+
+        // A frozen account cannot burn, so thaw first
+        anchor_spl::token::thaw_account(CpiContext::new_with_signer(
+            token_program.clone(),
+            anchor_spl::token::ThawAccount {
+                account: receipt_token_account.to_account_info(),
+                mint: receipt_mint.to_account_info(),
+                authority: mint_authority.clone(),
+            },
+            mint_authority_seeds,
+        )).map_err(cpi_failed(crate::ErrorCode::TokenAccountUpdateFailed))?;
+        anchor_spl::token::burn(
+            CpiContext::new_with_signer(
+                token_program.clone(),
+                Burn {
+                    mint: receipt_mint.to_account_info(),
+                    from: receipt_token_account.to_account_info(),
+                    authority: mint_authority.clone(),
+                },
+                mint_authority_seeds,
+            ),
+            1,
+        ).map_err(cpi_failed(crate::ErrorCode::BurnFailed))?;
+
+        events.push(DepartureReceiptBurned {
+            token_id,
+            holder: receipt_token_account.owner,
+        });
+
+        Ok(())
+    }
+
+    /// Read `mint` as an SPL mint and require its mint authority to be `expected_authority`
+    pub fn verify_mint_authority(mint: &AccountInfo, expected_authority: &Pubkey) -> Result<()> {
+        require_keys_eq!(*mint.owner, anchor_spl::token::ID, crate::ErrorCode::MintAuthorityMismatch);
+        let mint_state = Mint::try_deserialize(&mut &mint.try_borrow_data()?[..])?;
+        require!(
+            mint_state.mint_authority == anchor_lang::solana_program::program_option::COption::Some(*expected_authority),
+            crate::ErrorCode::MintAuthorityMismatch
+        );
+        Ok(())
+    }
+
+    /// Read-only pre-flight: fails unless the token's mint authority is `expected_authority`
+    pub fn check_mint_authority_ix(
+        ctx: Context<CheckMintAuthority>,
+        _token_id: u64,
+        expected_authority: Pubkey,
+    ) -> Result<()> {
+        Self::verify_mint_authority(&ctx.accounts.mint, &expected_authority)
+    }
+
+    /// Burn commitment of a transfer of `token_id`, for light clients to compare with their
+    /// own from the CrossChainTransferInitiated event
+    pub fn get_burn_commitment(ctx: Context<GetBurnCommitment>, _token_id: u64) -> Result<[u8; 32]> {
+        Ok(ctx.accounts.pending_transfer.burn_commitment)
+    }
+
+    /// Gateway callback for an outbound transfer that reverted or aborted on the destination.
+    /// Marks it reverted and, when it was insured, pays the sender from the insurance fund;
+    /// a fund short of the payout pays what it holds above its rent
+    pub fn on_transfer_reverted(ctx: Context<OnTransferReverted>, nonce: u64) -> Result<()> {
+        // The gateway's callback, so CPI is expected; only the lock applies
+        Self::require_unlocked(&ctx.accounts.program_state)?;
+        let pending_transfer = &mut ctx.accounts.pending_transfer;
+        require!(
+            pending_transfer.status == PENDING_TRANSFER_STATUS_PENDING,
+            crate::ErrorCode::TransferNotPending
+        );
+        pending_transfer.status = PENDING_TRANSFER_STATUS_REVERTED;
+
+        let mut payout_lamports = 0;
+        if pending_transfer.insured {
+            let insurance_fund = ctx.accounts.insurance_fund.as_mut()
+                .ok_or(anchor_lang::error::ErrorCode::AccountNotInitialized)?;
+            let fund_info = insurance_fund.to_account_info();
+            let available = fund_info.lamports()
+                .saturating_sub(Rent::get()?.minimum_balance(fund_info.data_len()));
+            payout_lamports = pending_transfer.insured_payout_lamports.min(available);
+            Self::move_program_lamports(&fund_info, &ctx.accounts.sender.to_account_info(), payout_lamports)?;
+            insurance_fund.total_payouts = insurance_fund.total_payouts.saturating_add(payout_lamports);
+        }
+
+        emit!(TransferReverted {
+            nonce,
+            token_id: pending_transfer.token_id,
+            sender: pending_transfer.sender,
+            insured: pending_transfer.insured,
+            payout_lamports,
+            shortfall_lamports: pending_transfer.insured_payout_lamports - payout_lamports,
+        });
+
+        Ok(())
+    }
+
+    /// Insure the caller's next `transfer_cross_chain`: pays FeeConfig's premium into the
+    /// insurance fund and locks in the payout configured now
+    pub fn opt_in_transfer_insurance(ctx: Context<OptInTransferInsurance>) -> Result<()> {
+        let fee_config = &ctx.accounts.fee_config;
+        let premium_lamports = fee_config.insurance_premium_lamports;
+        require!(premium_lamports > 0, crate::ErrorCode::InsuranceNotOffered);
+        require!(!ctx.accounts.insurance_opt_in.opted_in, crate::ErrorCode::InsuranceAlreadyActive);
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.user.to_account_info(),
+                    to: ctx.accounts.insurance_fund.to_account_info(),
+                },
+            ),
+            premium_lamports,
+        )?;
+
+        let insurance_opt_in = &mut ctx.accounts.insurance_opt_in;
+        insurance_opt_in.user = ctx.accounts.user.key();
+        insurance_opt_in.opted_in = true;
+        insurance_opt_in.total_premiums_paid = insurance_opt_in.total_premiums_paid.saturating_add(premium_lamports);
+        insurance_opt_in.payout_lamports = fee_config.insurance_payout_lamports;
+        insurance_opt_in.bump = ctx.bumps.insurance_opt_in;
+        let insurance_fund = &mut ctx.accounts.insurance_fund;
+        insurance_fund.total_premiums = insurance_fund.total_premiums.saturating_add(premium_lamports);
+        insurance_fund.bump = ctx.bumps.insurance_fund;
+
+        emit!(TransferInsurancePurchased {
+            user: ctx.accounts.user.key(),
+            premium_lamports,
+            payout_lamports: fee_config.insurance_payout_lamports,
+        });
+
+        Ok(())
+    }
+
+    /// Approve or revoke an appraiser (owner only)
+    pub fn set_appraisal_authority(
+        ctx: Context<SetAppraisalAuthority>,
+        appraiser: Pubkey,
+        active: bool,
+    ) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetAppraisalAuthority,
+        )?;
+
+        let appraisal_authority = &mut ctx.accounts.appraisal_authority;
+        appraisal_authority.appraiser = appraiser;
+        appraisal_authority.active = active;
+        appraisal_authority.bump = ctx.bumps.appraisal_authority;
+
+        emit!(AppraisalAuthorityUpdated {
+            admin: ctx.accounts.admin.key(),
+            appraiser,
+            active,
+        });
+
+        Ok(())
+    }
+
+    /// Record the signing appraiser's valuation of a token, replacing their previous one.
+    /// It counts for APPRAISAL_VALIDITY_SECONDS
+    pub fn submit_appraisal(
+        ctx: Context<SubmitAppraisal>,
+        token_id: u64,
+        value_lamports: u64,
+        value_usd_cents: u64,
+        methodology: String,
+    ) -> Result<()> {
+        require!(
+            (value_lamports > 0 || value_usd_cents > 0)
+                && methodology.len() <= MAX_APPRAISAL_METHODOLOGY_BYTES,
+            crate::ErrorCode::InvalidAppraisal
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let appraisal = &mut ctx.accounts.appraisal;
+        appraisal.mint = ctx.accounts.nft_origin.mint;
+        appraisal.token_id = token_id;
+        appraisal.appraiser = ctx.accounts.appraiser.key();
+        appraisal.value_lamports = value_lamports;
+        appraisal.value_usd_cents = value_usd_cents;
+        appraisal.appraised_at = now;
+        appraisal.expires_at = now + APPRAISAL_VALIDITY_SECONDS;
+        appraisal.methodology = methodology;
+        appraisal.bump = ctx.bumps.appraisal;
+
+        emit!(AppraisalSubmitted {
+            token_id,
+            mint: appraisal.mint,
+            appraiser: appraisal.appraiser,
+            value_lamports,
+            value_usd_cents,
+            expires_at: appraisal.expires_at,
+        });
+
+        Ok(())
+    }
+
+    /// The freshest unexpired appraisal by an active appraiser, or none. Remaining accounts
+    /// are `[appraisal, appraisal_authority]` pairs: an NFTAppraisal of this token followed
+    /// by its appraiser's AppraisalAuthority
+    pub fn get_appraisal<'info>(
+        ctx: Context<'_, '_, 'info, 'info, GetAppraisal<'info>>,
+        token_id: u64,
+    ) -> Result<Option<NFTAppraisal>> {
+        require!(ctx.remaining_accounts.len().is_multiple_of(2), crate::ErrorCode::InvalidAppraisal);
+        let appraisals = ctx.remaining_accounts.chunks(2)
+            .map(|pair| {
+                let appraisal = Account::<NFTAppraisal>::try_from(&pair[0])?.into_inner();
+                require!(
+                    appraisal.mint == ctx.accounts.nft_origin.mint && appraisal.token_id == token_id,
+                    crate::ErrorCode::InvalidAppraisal
+                );
+                require_keys_eq!(
+                    pair[1].key(),
+                    pda::appraisal_authority(&appraisal.appraiser).0,
+                    crate::ErrorCode::InvalidAppraisal
+                );
+                let authority = Account::<AppraisalAuthority>::try_from(&pair[1])?.into_inner();
+                Ok((appraisal, authority))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let now = Clock::get()?.unix_timestamp;
+        Ok(NFTAppraisal::freshest(appraisals.iter().map(|(appraisal, authority)| (appraisal, authority)), now).cloned())
+    }
+
+    /// Make the signing holder and `co_owner` joint owners of the token. With
+    /// `require_both_signatures`, neither can send it cross-chain without the other's consent
+    pub fn create_co_ownership(
+        ctx: Context<CreateCoOwnership>,
+        token_id: u64,
+        co_owner: Pubkey,
+        require_both_signatures: bool,
+    ) -> Result<()> {
+        let holder = ctx.accounts.holder.key();
+        require!(
+            co_owner != holder && co_owner != Pubkey::default(),
+            crate::ErrorCode::InvalidCoOwner
+        );
+
+        let nft_origin = &mut ctx.accounts.nft_origin;
+        nft_origin.co_owned = true;
+        let co_ownership = &mut ctx.accounts.co_ownership;
+        co_ownership.mint = nft_origin.mint;
+        co_ownership.token_id = token_id;
+        co_ownership.owner_a = holder;
+        co_ownership.owner_b = co_owner;
+        co_ownership.require_both_signatures = require_both_signatures;
+        co_ownership.consent_nonce = 0;
+        co_ownership.created_at = Clock::get()?.unix_timestamp;
+        co_ownership.bump = ctx.bumps.co_ownership;
+
+        emit!(CoOwnershipCreated {
+            token_id,
+            mint: co_ownership.mint,
+            owner_a: holder,
+            owner_b: co_owner,
+            require_both_signatures,
+        });
+
+        Ok(())
+    }
+
+    /// Approve, as one owner of a co-owned token, its next cross-chain transfer by the other
+    pub fn consent_co_owned_transfer(ctx: Context<ConsentCoOwnedTransfer>, token_id: u64) -> Result<()> {
+        let co_owner_consent = &mut ctx.accounts.co_owner_consent;
+        co_owner_consent.mint = ctx.accounts.co_ownership.mint;
+        co_owner_consent.nonce = ctx.accounts.co_ownership.consent_nonce;
+        co_owner_consent.approver = ctx.accounts.approver.key();
+        co_owner_consent.created_at = Clock::get()?.unix_timestamp;
+        co_owner_consent.bump = ctx.bumps.co_owner_consent;
+
+        emit!(CoOwnerConsentGiven {
+            token_id,
+            mint: co_owner_consent.mint,
+            approver: co_owner_consent.approver,
+            nonce: co_owner_consent.nonce,
+        });
+
+        Ok(())
+    }
+
+    /// Close a consent, withdrawing it if it hasn't been used yet; the approver gets the rent
+    pub fn close_co_owner_consent(_ctx: Context<CloseCoOwnerConsent>, _token_id: u64, _nonce: u64) -> Result<()> {
+        Ok(())
+    }
+
+    /// End a token's co-ownership. Either owner may, unless it requires both signatures, in
+    /// which case the other signs as `co_signer`
+    pub fn dissolve_co_ownership(ctx: Context<DissolveCoOwnership>, token_id: u64) -> Result<()> {
+        let co_ownership = &ctx.accounts.co_ownership;
+        let authority = ctx.accounts.authority.key();
+        let other_owner = match authority {
+            owner if owner == co_ownership.owner_a => co_ownership.owner_b,
+            owner if owner == co_ownership.owner_b => co_ownership.owner_a,
+            _ => return err!(crate::ErrorCode::NotCoOwner),
+        };
+        if co_ownership.require_both_signatures {
+            require!(
+                ctx.accounts.co_signer.as_ref().is_some_and(|co_signer| co_signer.key() == other_owner),
+                crate::ErrorCode::CoOwnerConsentRequired
+            );
+        }
+        ctx.accounts.nft_origin.co_owned = false;
+
+        emit!(CoOwnershipDissolved {
+            token_id,
+            mint: co_ownership.mint,
+            dissolved_by: authority,
+        });
+
+        Ok(())
+    }
+
+    /// Hold the NFT on Solana until `until`: transfer_cross_chain and batch transfers
+    /// refuse it while the lock runs. Signed by the holder, or by a rental program through
+    /// the delegate it was approved as. A running lock can only be extended by its locker
+    pub fn set_rental_lock(ctx: Context<SetRentalLock>, token_id: u64, until: i64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            until > now && until <= now.saturating_add(crate::MAX_RENTAL_LOCK_SECONDS),
+            crate::ErrorCode::InvalidRentalLock
+        );
+        let locker = ctx.accounts.locker.key();
+        let nft_origin = &mut ctx.accounts.nft_origin;
+        require!(
+            nft_origin.rental_locked_until <= now || nft_origin.rental_locker == locker,
+            crate::ErrorCode::RentalLocked
+        );
+        nft_origin.rental_locked_until = until;
+        nft_origin.rental_locker = locker;
+
+        emit!(RentalLockSet {
+            token_id,
+            mint: nft_origin.mint,
+            locker,
+            holder: ctx.accounts.holder_token_account.owner,
+            locked_until: until,
+        });
+
+        Ok(())
+    }
+
+    /// Lift a rental lock before it expires; only its locker can
+    pub fn clear_rental_lock(ctx: Context<ClearRentalLock>, token_id: u64) -> Result<()> {
+        let nft_origin = &mut ctx.accounts.nft_origin;
+
+        emit!(RentalLockCleared {
+            token_id,
+            mint: nft_origin.mint,
+            locker: nft_origin.rental_locker,
+            locked_until: nft_origin.rental_locked_until,
+            expired: false,
+        });
+
+        nft_origin.rental_locked_until = 0;
+        nft_origin.rental_locker = Pubkey::default();
+
+        Ok(())
+    }
+
+    /// Record that the signer holds the token, counting for `valid_seconds`, and with
+    /// `relay` send the attestation on to the NFT's origin chain through the gateway at the
+    /// holder's expense. Replaces the token's previous attestation
+    pub fn attest_ownership(
+        ctx: Context<AttestOwnership>,
+        token_id: u64,
+        valid_seconds: u32,
+        relay: bool,
+    ) -> Result<()> {
+        require!(
+            (1..=MAX_OWNERSHIP_ATTESTATION_SECONDS).contains(&valid_seconds),
+            crate::ErrorCode::InvalidAttestationValidity
+        );
+
+        let clock = Clock::get()?;
+        let attestation = &mut ctx.accounts.ownership_attestation;
+        attestation.token_id = token_id;
+        attestation.mint = ctx.accounts.nft_origin.mint;
+        attestation.holder = ctx.accounts.holder.key();
+        attestation.slot = clock.slot;
+        attestation.attested_at = clock.unix_timestamp;
+        attestation.expires_at = clock.unix_timestamp + valid_seconds as i64;
+        attestation.bump = ctx.bumps.ownership_attestation;
+
+        let relayed_to_chain = if relay {
+            // Native NFTs have no other chain to tell
+            let provenance = ctx.accounts.nft_origin.provenance()
+                .filter(|provenance| !ChainId(provenance.origin_chain).is_solana())
+                .ok_or(crate::ErrorCode::AttestationNotRelayable)?;
+            let destination = Self::resolve_destination(ChainId(provenance.origin_chain), &ctx.accounts.registry)?;
+            let gateway_program = ctx.accounts.gateway_program.as_ref()
+                .ok_or(crate::ErrorCode::AttestationNotRelayable)?;
+            let message = UniversalNFTCoreImpl::encode_ownership_attestation(
+                destination,
+                &OwnershipAttestationMessage {
+                    token_id,
+                    origin_chain: provenance.origin_chain,
+                    origin_token_id: provenance.origin_token_id,
+                    holder: attestation.holder,
+                    slot: attestation.slot,
+                    expires_at: attestation.expires_at,
+                },
+            );
+            let program_state = &ctx.accounts.program_state;
+            let bridge = program_state.bridge_backend.bridge(
+                ctx.accounts.holder.to_account_info(),
+                program_state.universal_nft_contract,
+                ZetaChainCallOptions {
+                    gas_limit: program_state.gas_limit,
+                    ..ZetaChainCallOptions::default()
+                },
+            );
+            bridge.send_nft(provenance.origin_chain, token_id, message.clone(), gateway_program.to_account_info())?;
+            emit!(OwnershipAttestationRelayed {
+                token_id,
+                origin_chain: provenance.origin_chain,
+                message,
+            });
+            Some(provenance.origin_chain)
+        } else {
+            None
+        };
+
+        emit!(OwnershipAttested {
+            token_id,
+            mint: attestation.mint,
+            holder: attestation.holder,
+            slot: attestation.slot,
+            expires_at: attestation.expires_at,
+            relayed_to_chain,
+        });
+
+        Ok(())
+    }
+
+    /// The token's latest ownership attestation, or none once it has expired. It says who
+    /// held the token when it was made, not who holds it now
+    pub fn get_attestation(ctx: Context<GetAttestation>, _token_id: u64) -> Result<Option<OwnershipAttestation>> {
+        let attestation = &ctx.accounts.ownership_attestation;
+        Ok((Clock::get()?.unix_timestamp < attestation.expires_at).then(|| attestation.clone().into_inner()))
+    }
+
+    /// Cancel an outbound transfer the gateway reported reverted and re-mint the NFT to its
+    /// sender. Until then the message may still land, so the NFT can't exist on both sides.
+    /// An insurance payout on the revert covers the failed transfer, not the NFT, so insured
+    /// transfers are restored the same way. Natively created mints keep their creator as mint
+    /// authority, who co-signs as `native_mint_authority` to restore them
+    pub fn cancel_pending_transfer(ctx: Context<CancelPendingTransfer>, nonce: u64) -> Result<()> {
+        Self::require_top_level()?;
+        let pending_transfer = &ctx.accounts.pending_transfer;
+        require!(
+            pending_transfer.status == PENDING_TRANSFER_STATUS_REVERTED,
+            crate::ErrorCode::TransferNotReverted
+        );
+        // Bridged mints answer to the program PDA, natively created ones to their creator
+        let authority = if ctx.accounts.mint.mint_authority
+            == anchor_lang::solana_program::program_option::COption::Some(ctx.accounts.mint_authority.key())
+        {
+            ctx.accounts.mint_authority.to_account_info()
+        } else {
+            let native_mint_authority = ctx.accounts.native_mint_authority.as_ref()
+                .ok_or(crate::ErrorCode::NativeMintAuthorityRequired)?;
+            require!(
+                ctx.accounts.mint.mint_authority == anchor_lang::solana_program::program_option::COption::Some(native_mint_authority.key()),
+                crate::ErrorCode::NativeMintAuthorityRequired
+            );
+            native_mint_authority.to_account_info()
+        };
+
+        let mut events = EventBuffer::new();
+        if ctx.accounts.nft_origin.receipt_outstanding {
+            Self::burn_departure_receipt(
+                &ctx.accounts.receipt_mint,
+                &ctx.accounts.receipt_token_account,
+                &ctx.accounts.mint_authority,
+                ctx.bumps.mint_authority,
+                &ctx.accounts.token_program,
+                pending_transfer.token_id,
+                &mut events,
+            )?;
+            ctx.accounts.nft_origin.receipt_outstanding = false;
+        }
+
+        let mint_authority_seeds: &[&[&[u8]]] = &[&[pda::MINT_AUTHORITY_SEED, &[ctx.bumps.mint_authority]]];
+        anchor_spl::token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.sender_token_account.to_account_info(),
+                    authority,
+                },
+                mint_authority_seeds,
+            ),
+            pending_transfer.amount,
+        ).map_err(cpi_failed(crate::ErrorCode::MintToFailed))?;
+
+        // The NFT is back on Solana: undo the departure and start a new stay
+        let origin_chain = ctx.accounts.nft_origin.origin_chain;
+        let amount = pending_transfer.amount;
+        let nft_origin = &mut ctx.accounts.nft_origin;
+        nft_origin.departed_amount = nft_origin.departed_amount.saturating_sub(amount);
+        if !ChainId(origin_chain).is_solana() {
+            Self::undo_chain_stats(&ctx.accounts.chain_stats, origin_chain, pending_transfer.token_id, |stats| {
+                stats.departed = stats.departed.saturating_sub(amount);
+                stats.active = stats.active.saturating_add(amount);
+            }, &mut events)?;
+            ctx.accounts.nft_origin.arrived_at = Clock::get()?.unix_timestamp;
+        }
+        #[cfg(feature = "runtime-invariants")]
+        if !ctx.accounts.nft_origin.is_semi_fungible() {
+            crate::invariants::token_on_solana(&ctx.accounts.mint.to_account_info(), ctx.accounts.nft_origin.stranded_supply)?;
+        }
+
+        events.push(PendingTransferCancelled {
+            nonce,
+            token_id: pending_transfer.token_id,
+            sender: pending_transfer.sender,
+        });
+        events.flush();
+
+        Ok(())
+    }
+
+    /// Receive cross-chain message and mint NFT, on one-phase deployments. Value the gateway
+    /// sent with the NFT, such as sale proceeds, is named by the message's value trailer and
+    /// paid from the gateway's payer to the recipient in the same transaction
+    pub fn receive_cross_chain_message(
+        ctx: Context<ReceiveCrossChainMessage>,
+        message: Vec<u8>,
+        memo: Option<String>,
+    ) -> Result<()> {
+        require!(ctx.accounts.gateway.is_some(), crate::ErrorCode::Unauthorized);
+        require!(
+            ctx.accounts.program_state.inbound_mode == InboundMode::OnePhase
+                && ctx.accounts.pending_inbound.is_none(),
+            crate::ErrorCode::InboundModeMismatch
+        );
+        Self::deliver_inbound(ctx, message, memo)
+    }
+
+    /// First phase of a two-phase delivery: validate the message and record it in a
+    /// `PendingInbound` for `execute_inbound`, without creating or minting anything. Only the
+    /// gateway may queue, and the recipient is the one the message names. The value its
+    /// trailer names is deposited into the record until the delivery pays it out
+    pub fn queue_inbound(ctx: Context<QueueInbound>, message: Vec<u8>, memo: Option<String>) -> Result<()> {
+        Self::require_not_paused(&mut ctx.accounts.program_state)?;
+        let program_state = &ctx.accounts.program_state;
+        require!(!program_state.auto_paused_inbound, crate::ErrorCode::InboundCircuitBreakerTripped);
+        require!(program_state.inbound_mode == InboundMode::TwoPhase, crate::ErrorCode::InboundModeMismatch);
+        require!(
+            memo.as_ref().is_none_or(|memo| memo.len() <= MAX_MEMO_BYTES),
+            crate::ErrorCode::MemoTooLong
+        );
+
+        let bridge = program_state.bridge_backend.bridge(
+            ctx.accounts.payer.to_account_info(),
+            program_state.universal_nft_contract,
+            ZetaChainCallOptions::default(),
+        );
+        let (token_id, _, _) = bridge.receive_nft(message.clone(), CHAIN_ID_ZETACHAIN_TESTNET)?;
+        require!(token_id < program_state.native_id_base, crate::ErrorCode::TokenIdRangeConflict);
+        let body = MessageDecoder::single_body(&message)?;
+        let priority = UniversalNFTCoreImpl::decode_priority(body)?;
+        let value_lamports = UniversalNFTCoreImpl::decode_value(body)?.unwrap_or(0);
+        // The message, not the caller, says who gets the NFT
+        let recipient = MessageDecoder::recipient(&message)?;
+
+        let message_hash = inbound_message_hash(&message);
+        let pending_inbound = &mut ctx.accounts.pending_inbound;
+        pending_inbound.message_hash = message_hash;
+        pending_inbound.token_id = token_id;
+        pending_inbound.recipient = recipient;
+        pending_inbound.queued_by = ctx.accounts.payer.key();
+        pending_inbound.queued_at = Clock::get()?.unix_timestamp;
+        pending_inbound.bump = ctx.bumps.pending_inbound;
+        pending_inbound.message = message;
+        pending_inbound.memo = memo;
+        pending_inbound.priority = priority;
+
+        if value_lamports > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: ctx.accounts.pending_inbound.to_account_info(),
+                    },
+                ),
+                value_lamports,
+            )?;
+        }
+
+        emit!(InboundQueued {
+            message_hash,
+            token_id,
+            recipient,
+            queued_by: ctx.accounts.payer.key(),
+            priority,
+        });
+
+        Ok(())
+    }
+
+    /// Message hashes of the PendingInbound accounts passed as remaining accounts with at
+    /// least `min_priority`, highest priority first and oldest first within a priority, at
+    /// most `limit` of them. Lets cranks order their work; execution checks are the same at
+    /// every priority
+    pub fn list_pending_inbound(ctx: Context<ListPendingInbound>, min_priority: u8, limit: u8) -> Result<Vec<[u8; 32]>> {
+        require!(
+            (1..=MAX_PENDING_INBOUND_LIST).contains(&limit),
+            crate::ErrorCode::InvalidPendingInboundList
+        );
+        let mut pending = Vec::with_capacity(ctx.remaining_accounts.len());
+        for account in ctx.remaining_accounts {
+            require!(account.owner == &crate::ID, crate::ErrorCode::InvalidPendingInboundList);
+            let pending_inbound = PendingInbound::decode_any_version(&account.try_borrow_data()?)?;
+            if pending_inbound.priority >= min_priority {
+                pending.push((pending_inbound.priority, pending_inbound.queued_at, pending_inbound.message_hash));
+            }
+        }
+        pending.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+        Ok(pending.into_iter().take(limit as usize).map(|(_, _, message_hash)| message_hash).collect())
+    }
+
+    /// Second phase: deliver a message queued by `queue_inbound`, with the memo it was
+    /// queued with. Anyone can execute it (relayers only while the allowlist is on), but only
+    /// to the recipient recorded when it was queued; the record's rent goes to the executor
+    pub fn execute_inbound(ctx: Context<ReceiveCrossChainMessage>, message: Vec<u8>) -> Result<()> {
+        Self::require_relayer(&ctx.accounts.relayer_config, &ctx.accounts.relayer_role)?;
+        let pending_inbound = ctx.accounts.pending_inbound.as_ref()
+            .ok_or(crate::ErrorCode::InboundModeMismatch)?;
+        require_keys_eq!(
+            ctx.accounts.recipient.key(),
+            pending_inbound.recipient,
+            crate::ErrorCode::PendingInboundRecipientMismatch
+        );
+        let executed = InboundExecuted {
+            message_hash: pending_inbound.message_hash,
+            token_id: pending_inbound.token_id,
+            executed_by: ctx.accounts.payer.key(),
+        };
+        let memo = pending_inbound.memo.clone();
+
+        // The gateway deposited the message's value into the record when it queued it; the
+        // executor fronts it from there, so the delivery pays the recipient as usual
+        let value_lamports = UniversalNFTCoreImpl::decode_value(MessageDecoder::single_body(&message)?)?.unwrap_or(0);
+        if value_lamports > 0 {
+            let record = pending_inbound.to_account_info();
+            let payer = ctx.accounts.payer.to_account_info();
+            **record.try_borrow_mut_lamports()? = record.lamports().checked_sub(value_lamports)
+                .ok_or(UniversalNFTCoreError::InvalidAmount)?;
+            **payer.try_borrow_mut_lamports()? = payer.lamports().checked_add(value_lamports)
+                .ok_or(UniversalNFTCoreError::InvalidAmount)?;
+        }
+        Self::deliver_inbound(ctx, message, memo)?;
+        // After the delivery's own events, so ordering still holds
+        emit!(executed);
+
+        Ok(())
+    }
+
+    /// Validate an inbound message and mint its NFT. The token id comes from the message
+    /// alone: the accounts were derived from the same bytes, so they match it by construction
+    fn deliver_inbound(
+        ctx: Context<ReceiveCrossChainMessage>,
+        message: Vec<u8>,
+        memo: Option<String>,
+    ) -> Result<()> {
+        // Deliveries arrive from the gateway through CPI; only the lock applies
+        Self::require_unlocked(&ctx.accounts.program_state)?;
+        // 1. Plan the delivery from a copy of what its rules read; the configured bridge
+        // decodes the message
+        let snapshot = Self::inbound_snapshot(ctx.accounts)?;
+        let bridge = ctx.accounts.program_state.bridge_backend.bridge(
+            ctx.accounts.payer.to_account_info(),
+            ctx.accounts.program_state.universal_nft_contract,
+            ZetaChainCallOptions::default(),
+        );
+        let plan = planner::plan_inbound(&snapshot, InboundArgs {
+            memo: memo.as_deref(),
+            message: &message,
+            bridge_decoded: bridge.receive_nft(message.clone(), CHAIN_ID_ZETACHAIN_TESTNET),
+        })?;
+
+        // 1a. Check the accounts the delivery goes into. The message, not the caller, says
+        // who gets the NFT
+        require_keys_eq!(
+            ctx.accounts.recipient.key(),
+            MessageDecoder::recipient(&message)?,
+            crate::ErrorCode::InboundRecipientMismatch
+        );
+        match plan.route {
+            InboundRoute::Escrow => {
+                let escrow = ctx.accounts.provisional_escrow.as_ref()
+                    .ok_or(crate::ErrorCode::ProvisionalEscrowMismatch)?;
+                require_keys_eq!(
+                    escrow.key(),
+                    anchor_spl::associated_token::get_associated_token_address(
+                        &ctx.accounts.mint_authority.key(),
+                        &ctx.accounts.mint.key(),
+                    ),
+                    crate::ErrorCode::ProvisionalEscrowMismatch
+                );
+            }
+            InboundRoute::Custodial { token_account } => {
+                let custodial = ctx.accounts.custodial_token_account.as_ref()
+                    .ok_or(crate::ErrorCode::CustodialAccountMismatch)?;
+                require!(
+                    custodial.key() == token_account && custodial.mint == ctx.accounts.mint.key(),
+                    crate::ErrorCode::CustodialAccountMismatch
+                );
+            }
+            InboundRoute::RecipientAta => {}
+        }
+        // Held deliveries release to the recipient's own ATA, so it must be theirs too
+        if !matches!(plan.route, InboundRoute::Custodial { .. }) {
+            require_keys_eq!(
+                ctx.accounts.recipient_token_account.key(),
+                anchor_spl::associated_token::get_associated_token_address(
+                    &ctx.accounts.recipient.key(),
+                    &ctx.accounts.mint.key(),
+                ),
+                crate::ErrorCode::RecipientTokenAccountMismatch
+            );
+        }
+
+        Self::execute_delivery(ctx, plan)
+    }
+
+    /// Copy what `plan_inbound` reads out of a delivery's accounts
+    fn inbound_snapshot(accounts: &ReceiveCrossChainMessage) -> Result<InboundSnapshot> {
+        let program_state = &accounts.program_state;
+        let nft_origin = &accounts.nft_origin;
+        Ok(InboundSnapshot {
+            now: Clock::get()?.unix_timestamp,
+            paused: program_state.paused,
+            auto_unpause_at: program_state.auto_unpause_at,
+            auto_paused_inbound: program_state.auto_paused_inbound,
+            allow_semi_fungible: program_state.allow_semi_fungible,
+            native_id_base: program_state.native_id_base,
+            require_nft_acceptance: program_state.require_nft_acceptance,
+            inbound_count: program_state.inbound_count,
+            mint: accounts.mint.key(),
+            mint_supply: accounts.mint.supply,
+            origin_mint: nft_origin.mint,
+            stranded_supply: nft_origin.stranded_supply,
+            departed_amount: nft_origin.departed_amount,
+            receipt_outstanding: nft_origin.receipt_outstanding,
+            origin_amount: nft_origin.amount,
+            origin_uri: nft_origin.metadata_uri.clone(),
+            recipient: accounts.recipient.key(),
+            source_chain_config: Some((**accounts.source_chain_config).clone()),
+            delivery_mode: Self::delivery_mode(&accounts.receiver_binding)?,
+            offer_passed: accounts.inbound_offer.is_some(),
+        })
+    }
+
+    /// Carry out a planned delivery: its state writes, then its CPIs, then its events
+    fn execute_delivery(ctx: Context<ReceiveCrossChainMessage>, plan: InboundPlan) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let token_id = plan.token_id;
+        if let Some(scheduled_at) = plan.auto_unpause {
+            Self::apply_auto_unpause(&mut ctx.accounts.program_state, scheduled_at, now);
+        }
+        // Policies are keyed by the universal contract a delivery came through, whatever
+        // provenance it carries
+        let origin_policy = Self::origin_policy(
+            ctx.remaining_accounts,
+            CHAIN_ID_ZETACHAIN_TESTNET,
+            &ctx.accounts.program_state.universal_nft_contract,
+        )?;
+        let metadata_deferred = Self::metadata_deferred(&ctx.accounts.program_state, &plan.uri, origin_policy.as_ref());
+
+        // 2. State writes
+        // Returning tokens already counted for their URI when they first arrived
+        let dedup_window = &mut ctx.accounts.dedup_window;
+        dedup_window.uri_hash = plan.dedup_uri_hash;
+        dedup_window.bump = ctx.bumps.dedup_window;
+        if !plan.is_returning {
+            Self::record_uri_mint(dedup_window, &ctx.accounts.program_state, now)?;
+        }
+
+        let nft_origin = &mut ctx.accounts.nft_origin;
+        if let Some(memo) = &plan.last_memo {
+            nft_origin.last_memo = memo.clone();
+        }
+        if !plan.is_returning {
+            nft_origin.created_at = now;
+        }
+        nft_origin.arrived_at = now;
+        nft_origin.schema_version = NFT_ORIGIN_VERSION;
+        nft_origin.token_id = token_id;
+        nft_origin.origin_chain = CHAIN_ID_ZETACHAIN_TESTNET;
+        nft_origin.set_provenance(&plan.provenance.clone().unwrap_or_else(|| {
+            Self::relayed_provenance(token_id, &ctx.accounts.program_state.universal_nft_contract)
+        }));
+        nft_origin.metadata_uri = plan.uri.clone();
+        nft_origin.metadata_encryption_key = plan.metadata_encryption_key;
+        nft_origin.metadata_pending = metadata_deferred;
+        nft_origin.mint = ctx.accounts.mint.key();
+        nft_origin.bump = ctx.bumps.nft_origin;
+        if !plan.is_returning {
+            nft_origin.record_metadata_bumps();
+        }
+        nft_origin.amount = plan.origin_amount;
+        nft_origin.departed_amount = plan.departed_amount;
+        if plan.burn_receipt {
+            nft_origin.receipt_outstanding = false;
+        }
+        nft_origin.logical_owner = plan.logical_owner;
+        if let Some(release_at) = plan.release_at {
+            nft_origin.release_at = release_at;
+            nft_origin.provisional_recipient = ctx.accounts.recipient.key();
+        }
+        if let Some(offer_expires_at) = plan.offer_expires_at {
+            let offer = ctx.accounts.inbound_offer.as_mut()
+                .ok_or(crate::ErrorCode::AcceptanceRequired)?;
+            offer.token_id = token_id;
+            offer.recipient = ctx.accounts.recipient.key();
+            offer.mint = ctx.accounts.mint.key();
+            offer.sender = plan.sender;
+            offer.payer = ctx.accounts.payer.key();
+            offer.offered_at = now;
+            offer.offer_expires_at = offer_expires_at;
+            offer.bump = ctx.bumps.inbound_offer.ok_or(crate::ErrorCode::AcceptanceRequired)?;
+        }
+
+        let nft_index = &mut ctx.accounts.nft_index;
+        nft_index.mint = ctx.accounts.mint.key();
+        nft_index.token_id = token_id;
+        nft_index.bump = ctx.bumps.nft_index;
+
+        let inbound_receipt = &mut ctx.accounts.inbound_receipt;
+        inbound_receipt.message_hash = plan.message_hash;
+        inbound_receipt.token_id = token_id;
+        inbound_receipt.mint = ctx.accounts.mint.key();
+        inbound_receipt.slot = Clock::get()?.slot;
+        inbound_receipt.delivered_at = now;
+        inbound_receipt.bump = ctx.bumps.inbound_receipt;
+
+        ctx.accounts.program_state.inbound_count = plan.next_inbound_count;
+        Self::record_chain_arrivals(&mut ctx.accounts.chain_stats, ctx.bumps.chain_stats, plan.amount)?;
+        Self::append_event_log(
+            &ctx.accounts.event_log,
+            EVENT_LOG_TRANSFER_IN,
+            token_id,
+            plan.sender,
+            plan.inbound_sequence,
+            now,
+        )?;
+
+        // 3. CPIs
+        let mut events = EventBuffer::new();
+        let mut value_parked = false;
+        for cpi in plan.cpis {
+            match cpi {
+                InboundCpi::Memo { memo } => Self::log_memo(&ctx.accounts.memo_program, &Some(memo))?,
+                InboundCpi::BurnReceipt => Self::burn_departure_receipt(
+                    &ctx.accounts.receipt_mint,
+                    &ctx.accounts.receipt_token_account,
+                    &ctx.accounts.mint_authority,
+                    ctx.bumps.mint_authority,
+                    &ctx.accounts.token_program,
+                    token_id,
+                    &mut events,
+                )?,
+                InboundCpi::CreateEscrow => {
+                    let escrow = ctx.accounts.provisional_escrow.as_ref()
+                        .ok_or(crate::ErrorCode::ProvisionalEscrowMismatch)?;
+                    Self::create_inbound_ata(ctx.accounts, escrow.to_account_info(), ctx.accounts.mint_authority.to_account_info())?;
+                }
+                InboundCpi::CreateRecipientAta => Self::create_inbound_ata(
+                    ctx.accounts,
+                    ctx.accounts.recipient_token_account.to_account_info(),
+                    ctx.accounts.recipient.to_account_info(),
+                )?,
+                // Mint the NFT, signed by the mint authority PDA
+                InboundCpi::MintTo { amount } => {
+                    let destination = match (plan.route, ctx.accounts.custodial_token_account.as_ref()) {
+                        (InboundRoute::Escrow, _) => ctx.accounts.provisional_escrow.as_ref()
+                            .ok_or(crate::ErrorCode::ProvisionalEscrowMismatch)?
+                            .to_account_info(),
+                        (InboundRoute::Custodial { .. }, Some(custodial)) => custodial.to_account_info(),
+                        _ => ctx.accounts.recipient_token_account.to_account_info(),
+                    };
+                    let mint_authority_seeds: &[&[&[u8]]] = &[&[pda::MINT_AUTHORITY_SEED, &[ctx.bumps.mint_authority]]];
+                    let mint_to_ctx = CpiContext::new_with_signer(
+                        ctx.accounts.token_program.to_account_info(),
+                        MintTo {
+                            mint: ctx.accounts.mint.to_account_info(),
+                            to: destination,
+                            authority: ctx.accounts.mint_authority.to_account_info(),
+                        },
+                        mint_authority_seeds,
+                    );
+                    anchor_spl::token::mint_to(mint_to_ctx, amount).map_err(cpi_failed(crate::ErrorCode::MintToFailed))?;
+                }
+                InboundCpi::DeliverValue { lamports } => {
+                    value_parked = Self::deliver_inbound_value(
+                        &ctx.accounts.payer.to_account_info(),
+                        &ctx.accounts.recipient.to_account_info(),
+                        ctx.accounts.inbound_value_claim.as_ref().map(|claim| claim.to_account_info()),
+                        &ctx.accounts.system_program.to_account_info(),
+                        lamports,
+                    )?;
+                }
+            }
+        }
+        // The metadata CPI stays off until the Token Metadata program is available, as in
+        // create_mint_and_nft; it would be skipped here when metadata_deferred, and take
+        // its data, update authority and mutability from the origin policy
+        #[allow(unused_variables)]
+        let data_v2 = Self::inbound_metadata(&plan.uri, origin_policy.as_ref());
+        let (update_authority, is_mutable) = Self::inbound_metadata_authority(origin_policy.as_ref());
+
+        // 4. Log everything once the state and CPIs are done
+        if metadata_deferred {
+            events.push(MetadataDeferred { token_id, mint: ctx.accounts.mint.key() });
+        }
+        if let Some(policy) = &origin_policy {
+            events.push(OriginPolicyApplied {
+                token_id,
+                mint: ctx.accounts.mint.key(),
+                origin_chain: policy.origin_chain,
+                origin_contract_hash: policy.origin_contract_hash,
+                royalty_bps: policy.royalty_bps,
+                royalty_recipient: policy.royalty_recipient,
+                update_authority,
+                is_mutable,
+            });
+        }
+        if let Some(release_at) = plan.release_at {
+            events.push(ProvisionalDeliveryHeld {
+                token_id,
+                recipient: ctx.accounts.recipient.key(),
+                release_at,
+            });
+        }
+        if let Some(offer_expires_at) = plan.offer_expires_at {
+            events.push(InboundNFTOffered {
+                token_id,
+                recipient: ctx.accounts.recipient.key(),
+                offer_expires_at,
+            });
+        }
+
+        Self::record_hourly_metrics(&ctx.accounts.hourly_metrics, |metrics| {
+            metrics.receives = metrics.receives.saturating_add(1);
+        })?;
+
+        if let Some(note_replaced) = plan.note_replaced {
+            events.push(note_replaced);
+        }
+        let nft_origin = &ctx.accounts.nft_origin;
+        events.push(CrossChainMessageReceived {
+            token_id,
+            origin_chain: CHAIN_ID_ZETACHAIN_TESTNET,
+            mint: ctx.accounts.mint.key(),
+            recipient: ctx.accounts.recipient.key(),
+            note: plan.note,
+            provenance_chain: nft_origin.provenance_chain,
+            origin_token_id: nft_origin.origin_token_id,
+            origin_contract: nft_origin.origin_contract,
+            origin_contract_len: nft_origin.origin_contract_len,
+            value_lamports: plan.value_lamports,
+            value_parked,
+            amount: plan.amount,
+        });
+        #[cfg(feature = "runtime-invariants")]
+        if !ctx.accounts.nft_origin.is_semi_fungible() {
+            crate::invariants::token_on_solana(&ctx.accounts.mint.to_account_info(), ctx.accounts.nft_origin.stranded_supply)?;
+        }
+        events.flush();
+
+        Ok(())
+    }
+
+    /// Create the associated token account `authority` receives an inbound mint in, if it
+    /// doesn't exist yet
+    fn create_inbound_ata<'info>(
+        accounts: &ReceiveCrossChainMessage<'info>,
+        associated_token: AccountInfo<'info>,
+        authority: AccountInfo<'info>,
+    ) -> Result<()> {
+        anchor_spl::associated_token::create_idempotent(CpiContext::new(
+            accounts.associated_token_program.to_account_info(),
+            anchor_spl::associated_token::Create {
+                payer: accounts.payer.to_account_info(),
+                associated_token,
+                authority,
+                mint: accounts.mint.to_account_info(),
+                system_program: accounts.system_program.to_account_info(),
+                token_program: accounts.token_program.to_account_info(),
+            },
+        )).map_err(cpi_failed(crate::ErrorCode::AtaCreateFailed))
+    }
+
+    /// Pay `amount` lamports sent with an inbound NFT from the payer to the recipient. A
+    /// recipient that couldn't hold them, being executable or left short of rent exemption,
+    /// gets them parked in its InboundValueClaim instead. Returns whether they were parked
+    fn deliver_inbound_value<'info>(
+        payer: &AccountInfo<'info>,
+        recipient: &AccountInfo<'info>,
+        inbound_value_claim: Option<AccountInfo<'info>>,
+        system_program: &AccountInfo<'info>,
+        amount: u64,
+    ) -> Result<bool> {
+        let balance = recipient.lamports().checked_add(amount)
+            .ok_or(UniversalNFTCoreError::InvalidAmount)?;
+        let can_hold = !recipient.executable && balance >= Rent::get()?.minimum_balance(recipient.data_len());
+        let destination = if can_hold {
+            recipient.clone()
+        } else {
+            inbound_value_claim.ok_or(crate::ErrorCode::InboundValueClaimMismatch)?
+        };
+        if !can_hold {
+            let (expected, bump) = pda::inbound_value_claim(recipient.key);
+            require_keys_eq!(destination.key(), expected, crate::ErrorCode::InboundValueClaimMismatch);
+            if destination.data_is_empty() {
+                Self::create_pda_account(
+                    payer,
+                    &destination,
+                    system_program,
+                    INBOUND_VALUE_CLAIM_SPACE,
+                    &crate::ID,
+                    &[pda::INBOUND_VALUE_CLAIM_SEED, recipient.key.as_ref(), &[bump]],
+                )?;
+                InboundValueClaim { recipient: recipient.key(), lamports: 0, bump }
+                    .try_serialize(&mut &mut destination.try_borrow_mut_data()?[..])?;
+            }
+            let mut claim = InboundValueClaim::try_deserialize(&mut &destination.try_borrow_data()?[..])?;
+            claim.lamports = claim.lamports.checked_add(amount)
+                .ok_or(UniversalNFTCoreError::InvalidAmount)?;
+            claim.try_serialize(&mut &mut destination.try_borrow_mut_data()?[..])?;
+        }
+
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                Transfer {
+                    from: payer.clone(),
+                    to: destination,
+                },
+            ),
+            amount,
+        )?;
+
+        Ok(!can_hold)
+    }
+
+    /// Pay out value parked for the signing recipient to `destination`, along with the
+    /// claim account's rent
+    pub fn claim_inbound_value(ctx: Context<ClaimInboundValue>) -> Result<()> {
+        emit!(InboundValueClaimed {
+            recipient: ctx.accounts.recipient.key(),
+            destination: ctx.accounts.destination.key(),
+            lamports: ctx.accounts.inbound_value_claim.lamports,
+        });
+
+        Ok(())
+    }
+
+    /// Provenance of an NFT minted by this program
+    fn native_provenance(origin_chain: u64, token_id: u64) -> Provenance {
+        Provenance {
+            origin_chain,
+            origin_token_id: token_id,
+            origin_contract: crate::ID.to_bytes().to_vec(),
+        }
+    }
+
+    /// Provenance of a delivery that carried none: the universal contract it came through,
+    /// under the id it arrived with
+    fn relayed_provenance(token_id: u64, universal_nft_contract: &[u8; 20]) -> Provenance {
+        Provenance {
+            origin_chain: CHAIN_ID_ZETACHAIN_TESTNET,
+            origin_token_id: token_id,
+            origin_contract: universal_nft_contract.to_vec(),
+        }
+    }
+
+    fn delivery_mode(receiver_binding: &UncheckedAccount) -> Result<DeliveryMode> {
+        if receiver_binding.owner != &crate::ID || receiver_binding.data_is_empty() {
+            return Ok(DeliveryMode::Ata);
+        }
+        let binding = ReceiverBinding::try_deserialize(&mut &receiver_binding.try_borrow_data()?[..])?;
+        Ok(binding.delivery_mode)
+    }
+
+    /// Choose where the signer's bridged NFTs are delivered. Only the owner can change it,
+    /// since a custodial binding sends their NFTs to someone else's account
+    pub fn set_delivery_mode(ctx: Context<SetDeliveryMode>, delivery_mode: DeliveryMode) -> Result<()> {
+        let binding = &mut ctx.accounts.receiver_binding;
+        binding.owner = ctx.accounts.owner.key();
+        binding.delivery_mode = delivery_mode;
+        binding.bump = ctx.bumps.receiver_binding;
+
+        emit!(DeliveryModeUpdated {
+            owner: binding.owner,
+            delivery_mode,
+        });
+
+        Ok(())
+    }
+
+    /// Send up to MAX_BATCH_TRANSFER NFTs to one receiver in a single gateway call. Every
+    /// entry is checked before the first burn, so one bad entry aborts the whole batch.
+    /// Each NFT pays the transfer fee and is counted and logged like a single transfer; the
+    /// batch uses one transfer nonce. Batches skip the cancel window, departure receipts
+    /// and burn commitments.
+    pub fn transfer_cross_chain_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, TransferCrossChainBatch<'info>>,
+        token_ids: Vec<u64>,
+        receiver: Vec<u8>,
+        destination_chain_id: u64,
+        gas_amount: u64,
+    ) -> Result<()> {
+        Self::require_top_level()?;
+        Self::require_not_paused(&mut ctx.accounts.program_state)?;
+        // Batches are a ZetaChain message format, and the destination contract must decode them
+        require!(
+            ctx.accounts.program_state.bridge_backend == BridgeBackend::ZetaChain,
+            UniversalNFTCoreError::BridgeBackendUnsupported
+        );
+        require!(ctx.accounts.chain_gas_config.supports_batch, crate::ErrorCode::BatchTransferUnsupported);
+        let final_chain = ChainId(destination_chain_id).require_valid()?;
+        require!(!final_chain.is_solana(), crate::ErrorCode::InvalidFinalChain);
+        let receiver: [u8; 20] = receiver.as_slice().try_into()
+            .map_err(|_| crate::ErrorCode::InvalidFinalReceiver)?;
+        // Ascending ids rule out a token listed twice
+        require!(
+            (1..=MAX_BATCH_TRANSFER).contains(&token_ids.len())
+                && token_ids.windows(2).all(|pair| pair[0] < pair[1]),
+            crate::ErrorCode::InvalidBatchTransfer
+        );
+        require!(
+            ctx.remaining_accounts.len() == token_ids.len() * BATCH_TRANSFER_ACCOUNTS,
+            crate::ErrorCode::InvalidBatchAccounts
+        );
+        let block_gas_limit = match ctx.accounts.chain_gas_config.block_gas_limit {
+            0 => MAX_GAS_LIMIT,
+            limit => limit,
+        };
+        Self::validate_gas_limit(gas_amount, block_gas_limit)?;
+        let destination = Self::resolve_destination(final_chain, &ctx.accounts.registry)?;
+        // Turn away dust-funded senders, as single transfers do
+        planner::check_user_reserve(ctx.accounts.program_state.min_user_sol_reserve, ctx.accounts.user.lamports())?;
+
+        // 1. Check every entry before anything is burned
+        let user = ctx.accounts.user.key();
+        let entries = token_ids.iter()
+            .zip(ctx.remaining_accounts.chunks(BATCH_TRANSFER_ACCOUNTS))
+            .map(|(&token_id, accounts)| Self::validate_batch_transfer_entry(token_id, accounts, &user))
+            .collect::<Result<Vec<_>>>()?;
+
+        // 2. Encode the batch; it must fit in one message
+        let call_options = ZetaChainCallOptions {
+            gas_limit: gas_amount,
+            ..ZetaChainCallOptions::default()
+        };
+        let message_data = UniversalNFTCoreImpl::encode_batch_outbound_message(
+            destination,
+            receiver,
+            [0u8; 20], // Solana sender (placeholder)
+            &BatchTransfer { entries },
+            &call_options,
+        )?;
+        let max_message_bytes = match ctx.accounts.program_state.max_message_bytes {
+            0 => DEFAULT_MAX_MESSAGE_BYTES,
+            bytes => bytes,
+        };
+        require!(message_data.len() <= max_message_bytes as usize, crate::ErrorCode::MessageTooLarge);
+        let message_bytes = message_data.len() as u32;
+
+        // 3. The transfer fee, per NFT and less any holder discount
+        let fee_config = &ctx.accounts.fee_config;
+        require_keys_eq!(ctx.accounts.fee_treasury.key(), fee_config.treasury, crate::ErrorCode::FeeTreasuryMismatch);
+        let discount_percent = Self::resolve_fee_discount(
+            fee_config,
+            &user,
+            ctx.accounts.fee_discount_token_account.as_ref().map(|account| account.as_ref()),
+        )?;
+        let fee_lamports = fee_config.transfer_fee_lamports
+            .checked_mul(100 - discount_percent as u64)
+            .ok_or(crate::ErrorCode::InvalidFeeDiscountTiers)?
+            / 100;
+        let total_fee_lamports = fee_lamports
+            .checked_mul(token_ids.len() as u64)
+            .ok_or(crate::ErrorCode::InvalidFeeDiscountTiers)?;
+        let mut events = EventBuffer::new();
+        for &token_id in &token_ids {
+            events.push(FeeCollected {
+                payer: user,
+                token_id,
+                gross_fee_lamports: fee_config.transfer_fee_lamports,
+                discount_percent,
+                fee_lamports,
+                treasury: fee_config.treasury,
+            });
+        }
+
+        // 4. Take one transfer nonce for the batch, then log and count each NFT as departed
+        // before the CPIs
+        let nonce = ctx.accounts.program_state.transfer_nonce;
+        ctx.accounts.program_state.transfer_nonce = nonce.checked_add(1)
+            .ok_or(UniversalNFTCoreError::InvalidAmount)?;
+        let now = Clock::get()?.unix_timestamp;
+        for (&token_id, accounts) in token_ids.iter().zip(ctx.remaining_accounts.chunks(BATCH_TRANSFER_ACCOUNTS)) {
+            let mut origin = NFTOrigin::try_deserialize(&mut &accounts[0].try_borrow_data()?[..])?;
+            origin.departed_amount = origin.departed_amount.saturating_add(1);
+            origin.try_serialize(&mut &mut accounts[0].try_borrow_mut_data()?[..])?;
+            Self::append_event_log(&ctx.accounts.event_log, EVENT_LOG_TRANSFER_OUT, token_id, receiver, nonce, now)?;
+            // Departing units count against the origin chain; bridged NFTs only
+            if !ChainId(origin.origin_chain).is_solana() {
+                let dwell_seconds = match origin.arrived_at {
+                    0 => 0,
+                    arrived_at => now.saturating_sub(arrived_at).max(0) as u64,
+                };
+                Self::update_chain_stats(&accounts[3], origin.origin_chain, token_id, |stats| {
+                    stats.departed = stats.departed.saturating_add(1);
+                    stats.active = stats.active.saturating_sub(1);
+                    stats.cumulative_dwell_seconds = stats.cumulative_dwell_seconds.saturating_add(dwell_seconds);
+                }, &mut events)?;
+            }
+        }
+
+        // 5. CPIs: the fee, every burn, then a single gateway call
+        if total_fee_lamports > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.user.to_account_info(),
+                        to: ctx.accounts.fee_treasury.to_account_info(),
+                    },
+                ),
+                total_fee_lamports,
+            )?;
+        }
+        for accounts in ctx.remaining_accounts.chunks(BATCH_TRANSFER_ACCOUNTS) {
+            anchor_spl::token::burn(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Burn {
+                        mint: accounts[1].clone(),
+                        from: accounts[2].clone(),
+                        authority: ctx.accounts.user.to_account_info(),
+                    },
+                ),
+                1,
+            ).map_err(cpi_failed(crate::ErrorCode::BurnFailed))?;
+        }
+        let bridge = ctx.accounts.program_state.bridge_backend.bridge(
+            ctx.accounts.user.to_account_info(),
+            ctx.accounts.program_state.universal_nft_contract,
+            call_options,
+        );
+        let gateway_program = ctx.accounts.gateway_program.to_account_info();
+        Self::send_locked(&mut ctx.accounts.program_state, bridge, destination_chain_id, token_ids[0], message_data, gateway_program)?;
+
+        #[cfg(feature = "runtime-invariants")]
+        for accounts in ctx.remaining_accounts.chunks(BATCH_TRANSFER_ACCOUNTS) {
+            let origin = NFTOrigin::try_deserialize(&mut &accounts[0].try_borrow_data()?[..])?;
+            crate::invariants::token_departed(&accounts[1], origin.stranded_supply)?;
+        }
+        Self::record_hourly_metrics(&ctx.accounts.hourly_metrics, |metrics| {
+            metrics.burns = metrics.burns.saturating_add(token_ids.len() as u32);
+            metrics.total_gas_used = metrics.total_gas_used.saturating_add(gas_amount);
+            metrics.active_users = metrics.active_users.saturating_add(1);
+        })?;
+        events.push(CrossChainBatchTransferInitiated {
+            token_ids,
+            destination_chain: destination_chain_id,
+            destination_owner: receiver,
+            destination_zrc20: destination,
+            sender: user,
+            gas_limit: gas_amount,
+            message_bytes,
+            nonce,
+        });
+        events.flush();
+
+        Ok(())
+    }
+
+    /// Check one `[nft_origin, mint, user_token_account, chain_stats]` group of an outbound
+    /// batch and return its entry; the same checks `transfer_cross_chain` gets from its
+    /// constraints
+    fn validate_batch_transfer_entry(
+        token_id: u64,
+        accounts: &[AccountInfo],
+        user: &Pubkey,
+    ) -> Result<BatchTransferEntry> {
+        let [origin_info, mint_info, token_account_info, chain_stats_info] = accounts else {
+            return err!(crate::ErrorCode::InvalidBatchAccounts);
+        };
+        require_keys_eq!(origin_info.key(), pda::nft_origin(token_id).0, crate::ErrorCode::InvalidBatchAccounts);
+        require!(origin_info.owner == &crate::ID, crate::ErrorCode::InvalidBatchAccounts);
+        require!(
+            origin_info.is_writable && mint_info.is_writable && token_account_info.is_writable
+                && chain_stats_info.is_writable,
+            crate::ErrorCode::InvalidBatchAccounts
+        );
+        let origin = NFTOrigin::try_deserialize(&mut &origin_info.try_borrow_data()?[..])?;
+        require!(origin.is_current(origin_info.data_len()), crate::ErrorCode::NFTOriginNeedsMigration);
+        require_keys_eq!(mint_info.key(), origin.mint, crate::ErrorCode::BridgedMintMismatch);
+        // Stats are skipped when the PDA doesn't exist, but it must be the origin chain's
+        require_keys_eq!(
+            chain_stats_info.key(),
+            pda::chain_stats(origin.origin_chain).0,
+            crate::ErrorCode::InvalidBatchAccounts
+        );
+
+        require!(
+            token_account_info.owner == &anchor_spl::token::ID,
+            crate::ErrorCode::InvalidTokenAccountOwner
+        );
+        let token_account = TokenAccount::try_deserialize(&mut &token_account_info.try_borrow_data()?[..])?;
+        require_keys_eq!(token_account.mint, origin.mint, crate::ErrorCode::BridgedMintMismatch);
+        // Held by the caller, or approved to them through SPL `approve`
+        require!(
+            token_account.owner == *user || token_account.delegate == COption::Some(*user),
+            crate::ErrorCode::Unauthorized
+        );
+        require!(
+            token_account.owner == *user || token_account.delegated_amount >= 1,
+            crate::ErrorCode::InsufficientDelegatedAmount
+        );
+        require!(token_account.amount > 0, crate::ErrorCode::InsufficientTokens);
+        Self::require_burnable(&token_account, user)?;
+        // Co-ownership consent is checked by transfer_cross_chain alone
+        require!(!origin.co_owned, crate::ErrorCode::CoOwnerConsentRequired);
+        // Batch entries carry no amount, so they only fit NFTs
+        require!(!origin.is_semi_fungible(), crate::ErrorCode::SemiFungibleNotBatchable);
+        // Expired locks are left for the next transfer_cross_chain to clear
+        require!(origin.rental_locked_until <= Clock::get()?.unix_timestamp, crate::ErrorCode::RentalLocked);
+        if !ChainId(origin.origin_chain).is_solana() {
+            Self::verify_mint_authority(mint_info, &pda::mint_authority().0)?;
+        }
+
+        Ok(BatchTransferEntry {
+            token_id,
+            uri: origin.metadata_uri,
+        })
+    }
+
+    /// Receive up to MAX_BATCH_RECEIVE messages. A message that fails validation is
+    /// skipped with `ReceiveFailed` rather than reverting the batch.
+    pub fn batch_receive_cross_chain<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchReceive<'info>>,
+        messages: Vec<InboundMessage>,
+    ) -> Result<()> {
+        Self::require_batch_receivable(ctx.accounts, messages.len(), ctx.remaining_accounts.len())?;
+
+        let mut delivered = 0;
+        let mut events = EventBuffer::new();
+        for (inbound, accounts) in messages.iter().zip(ctx.remaining_accounts.chunks(BATCH_RECEIVE_ACCOUNTS)) {
+            // Everything that can fail is checked before the first CPI, since a failed
+            // CPI would revert the whole transaction
+            let message_hash = inbound_message_hash(&inbound.message);
+            let validated = Self::validate_batch_message(inbound, &message_hash, accounts, &ctx.accounts.program_state);
+            let (uri, trailer, first_arrival) = match validated {
+                Ok(validated) => validated,
+                Err(error) => {
+                    events.push(ReceiveFailed {
+                        token_id: inbound.token_id,
+                        error_code: match error {
+                            Error::AnchorError(error) => error.error_code_number,
+                            Error::ProgramError(_) => 0,
+                        },
+                    });
+                    continue;
+                }
+            };
+            Self::deliver_batch_message(&ctx, inbound.token_id, uri, trailer, first_arrival, &message_hash, accounts, &mut events)?;
+            delivered += 1;
+        }
+
+        let program_state = &mut ctx.accounts.program_state;
+        program_state.inbound_count = program_state.inbound_count.checked_add(delivered)
+            .ok_or(UniversalNFTCoreError::InvalidAmount)?;
+        Self::record_chain_arrivals(&mut ctx.accounts.chain_stats, ctx.bumps.chain_stats, delivered)?;
+        events.flush();
+
+        Ok(())
+    }
+
+    /// Deliver every NFT of one MESSAGE_VERSION_BATCH message to a single recipient. Each
+    /// entry is checked before the first mint, so either all of them arrive or the call
+    /// fails and the gateway reverts
+    pub fn receive_batch_transfer<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchReceive<'info>>,
+        message: Vec<u8>,
+    ) -> Result<()> {
+        let bodies = MessageDecoder::batch_bodies(&message)?;
+        Self::require_batch_receivable(ctx.accounts, bodies.len(), ctx.remaining_accounts.len())?;
+        let decoded = bodies.iter()
+            .map(|body| UniversalNFTCoreImpl::decode_cross_chain_message(body))
+            .collect::<Result<Vec<_>>>()?;
+        // One receiver for the whole batch, and no token delivered twice
+        require!(
+            decoded.iter().enumerate().all(|(i, entry)| {
+                entry.receiver == decoded[0].receiver
+                    && decoded[..i].iter().all(|earlier| earlier.token_id != entry.token_id)
+            }),
+            crate::ErrorCode::InvalidCrossChainMessage
+        );
+
+        // 1. Check every entry against its account group before anything is created
+        let groups: Vec<_> = ctx.remaining_accounts.chunks(BATCH_RECEIVE_ACCOUNTS).collect();
+        let mut entries = Vec::with_capacity(decoded.len());
+        let mut priority = 0;
+        for ((entry, body), accounts) in decoded.into_iter().zip(&bodies).zip(&groups) {
+            // Each entry goes to the wallet its own body names
+            require_keys_eq!(
+                accounts[3].key(),
+                MessageDecoder::body_recipient(body)?,
+                crate::ErrorCode::InboundRecipientMismatch
+            );
+            // Each entry's receipt is keyed by the entry as a V1 message, which is what
+            // replay_inbound takes
+            let message_hash = inbound_message_hash(&[&[MESSAGE_VERSION_V1], *body].concat());
+            let first_arrival = Self::validate_batch_accounts(
+                entry.token_id,
+                &entry.uri,
+                &message_hash,
+                accounts,
+                &ctx.accounts.program_state,
+            )?;
+            let metadata_encryption_key = UniversalNFTCoreImpl::decode_metadata_encryption_key(body)?;
+            let provenance = UniversalNFTCoreImpl::decode_provenance(body)?;
+            require!(
+                UniversalNFTCoreImpl::decode_amount(body)?.is_none_or(|amount| amount == 1),
+                crate::ErrorCode::SemiFungibleNotBatchable
+            );
+            // Batches have no way to pay value on, so it mustn't ride along unpaid
+            require!(UniversalNFTCoreImpl::decode_value(body)?.is_none(), crate::ErrorCode::ValueNotBatchable);
+            priority = priority.max(UniversalNFTCoreImpl::decode_priority(body)?);
+            entries.push((entry, (metadata_encryption_key, provenance), first_arrival, message_hash));
+        }
+
+        // 2. Mint them all; any failure from here reverts the whole batch
+        let mut events = EventBuffer::new();
+        let delivered = entries.len() as u64;
+        for ((entry, trailer, first_arrival, message_hash), accounts) in entries.into_iter().zip(groups) {
+            Self::deliver_batch_message(&ctx, entry.token_id, entry.uri, trailer, first_arrival, &message_hash, accounts, &mut events)?;
+        }
+
+        let program_state = &mut ctx.accounts.program_state;
+        program_state.inbound_count = program_state.inbound_count.checked_add(delivered)
+            .ok_or(UniversalNFTCoreError::InvalidAmount)?;
+        Self::record_chain_arrivals(&mut ctx.accounts.chain_stats, ctx.bumps.chain_stats, delivered)?;
+        events.push(BatchReceived {
+            count: delivered as u8,
+            message_hash: inbound_message_hash(&message),
+            priority,
+        });
+        events.flush();
+
+        Ok(())
+    }
+
+    /// Checks shared by both batch receive paths, for a batch of `count` NFTs
+    fn require_batch_receivable(accounts: &mut BatchReceive, count: usize, remaining_accounts: usize) -> Result<()> {
+        Self::require_not_paused(&mut accounts.program_state)?;
+        require!(
+            !accounts.program_state.auto_paused_inbound,
+            crate::ErrorCode::InboundCircuitBreakerTripped
+        );
+        require!(
+            count > 0 && count <= MAX_BATCH_RECEIVE,
+            crate::ErrorCode::BatchTooLarge
+        );
+        // Batches are a ZetaChain message format; other backends deliver one NFT at a time
+        require!(
+            accounts.program_state.bridge_backend == BridgeBackend::ZetaChain,
+            UniversalNFTCoreError::BridgeBackendUnsupported
+        );
+        require!(
+            remaining_accounts == count * BATCH_RECEIVE_ACCOUNTS,
+            crate::ErrorCode::InvalidBatchAccounts
+        );
+        // Held and unaccepted deliveries need an escrow per message, which the batch layout
+        // doesn't carry
+        require!(
+            !accounts.program_state.require_nft_acceptance,
+            crate::ErrorCode::AcceptanceRequired
+        );
+        let config = &accounts.source_chain_config;
+        require!(
+            config.trust_level != TRUST_LEVEL_PROVISIONAL || config.provisional_hold_seconds == 0,
+            crate::ErrorCode::ProvisionalBatchUnsupported
+        );
+        Ok(())
+    }
+
+    /// Check one batched message against its account group and return its metadata URI,
+    /// encryption key, provenance and whether this is the token's first arrival
+    fn validate_batch_message(
+        inbound: &InboundMessage,
+        message_hash: &[u8; 32],
+        accounts: &[AccountInfo],
+        program_state: &ProgramState,
+    ) -> Result<(String, BatchTrailer, bool)> {
+        let CrossChainNftMessage { token_id: decoded_token_id, uri, .. } = MessageDecoder::decode_single(&inbound.message)?;
+        require_eq!(decoded_token_id, inbound.token_id, crate::ErrorCode::InvalidCrossChainMessage);
+        // Each entry goes to the wallet its own message names
+        require_keys_eq!(
+            accounts.get(3).ok_or(crate::ErrorCode::InvalidBatchAccounts)?.key(),
+            MessageDecoder::recipient(&inbound.message)?,
+            crate::ErrorCode::InboundRecipientMismatch
+        );
+        let body = MessageDecoder::single_body(&inbound.message)?;
+        let metadata_encryption_key = UniversalNFTCoreImpl::decode_metadata_encryption_key(body)?;
+        let provenance = UniversalNFTCoreImpl::decode_provenance(body)?;
+        // Batched deliveries mint one unit each; semi-fungible units arrive one message at a time
+        require!(
+            UniversalNFTCoreImpl::decode_amount(body)?.is_none_or(|amount| amount == 1),
+            crate::ErrorCode::SemiFungibleNotBatchable
+        );
+        // Batches have no way to pay value on, so it mustn't ride along unpaid
+        require!(UniversalNFTCoreImpl::decode_value(body)?.is_none(), crate::ErrorCode::ValueNotBatchable);
+        let first_arrival = Self::validate_batch_accounts(inbound.token_id, &uri, message_hash, accounts, program_state)?;
+
+        Ok((uri, (metadata_encryption_key, provenance), first_arrival))
+    }
+
+    /// Check the account group of a batched NFT and return whether this is the token's
+    /// first arrival
+    fn validate_batch_accounts(
+        token_id: u64,
+        uri: &str,
+        message_hash: &[u8; 32],
+        accounts: &[AccountInfo],
+        program_state: &ProgramState,
+    ) -> Result<bool> {
+        let [nft_origin, nft_index, mint, recipient, recipient_token_account, dedup_window, inbound_receipt] = accounts else {
+            return Err(crate::ErrorCode::InvalidBatchAccounts.into());
+        };
+        require!(token_id < program_state.native_id_base, crate::ErrorCode::TokenIdRangeConflict);
+
+        let (expected_origin, _) = pda::nft_origin(token_id);
+        let (expected_mint, _) = pda::bridged_mint(CHAIN_ID_ZETACHAIN_TESTNET, token_id);
+        let (expected_index, _) = pda::nft_index(&expected_mint);
+        let (expected_dedup_window, _) = pda::dedup_window(&uri_hash(uri));
+        let (expected_receipt, _) = pda::inbound_receipt(message_hash);
+        require!(
+            nft_origin.key() == expected_origin
+                && mint.key() == expected_mint
+                && nft_index.key() == expected_index
+                && recipient_token_account.key()
+                    == anchor_spl::associated_token::get_associated_token_address(&recipient.key(), &expected_mint)
+                && dedup_window.key() == expected_dedup_window
+                && inbound_receipt.key() == expected_receipt,
+            crate::ErrorCode::InvalidBatchAccounts
+        );
+        require!(
+            nft_origin.is_writable
+                && nft_index.is_writable
+                && mint.is_writable
+                && recipient_token_account.is_writable
+                && dedup_window.is_writable
+                && inbound_receipt.is_writable,
+            crate::ErrorCode::InvalidBatchAccounts
+        );
+
+        let mut first_arrival = true;
+        let mut stranded_supply = 0;
+        if !nft_origin.data_is_empty() {
+            let origin = NFTOrigin::try_deserialize(&mut &nft_origin.try_borrow_data()?[..])?;
+            require!(
+                origin.is_current_or_new(nft_origin.data_len()),
+                crate::ErrorCode::NFTOriginNeedsMigration
+            );
+            require!(
+                origin.mint == Pubkey::default() || origin.mint == expected_mint,
+                crate::ErrorCode::BridgedMintMismatch
+            );
+            // Batches carry no receipt accounts
+            require!(!origin.receipt_outstanding, crate::ErrorCode::ReceiptRequired);
+            first_arrival = origin.mint == Pubkey::default();
+            // As in plan_inbound, only a departed NFT can come back
+            require!(first_arrival || origin.departed_amount >= 1, crate::ErrorCode::NFTAlreadyDelivered);
+            stranded_supply = origin.stranded_supply;
+        }
+        if first_arrival && !mint.data_is_empty() {
+            let mint_state = Mint::try_deserialize(&mut &mint.try_borrow_data()?[..])?;
+            require!(mint_state.supply == stranded_supply, crate::ErrorCode::NFTAlreadyDelivered);
+        }
+        if first_arrival && !dedup_window.data_is_empty() {
+            let mut window = DeduplicationWindow::try_deserialize(&mut &dedup_window.try_borrow_data()?[..])?;
+            Self::record_uri_mint(&mut window, program_state, Clock::get()?.unix_timestamp)?;
+        }
+
+        Ok(first_arrival)
+    }
+
+    /// Create whatever accounts are missing for a validated batch message, mint it and
+    /// record its InboundReceipt
+    #[allow(clippy::too_many_arguments)]
+    fn deliver_batch_message<'info>(
+        ctx: &Context<'_, '_, 'info, 'info, BatchReceive<'info>>,
+        token_id: u64,
+        uri: String,
+        (metadata_encryption_key, provenance): BatchTrailer,
+        first_arrival: bool,
+        message_hash: &[u8; 32],
+        accounts: &'info [AccountInfo<'info>],
+        events: &mut EventBuffer,
+    ) -> Result<()> {
+        let [nft_origin, nft_index, mint, recipient, recipient_token_account, dedup_window, inbound_receipt] = accounts else {
+            return Err(crate::ErrorCode::InvalidBatchAccounts.into());
+        };
+        let payer = ctx.accounts.payer.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+        let mint_authority = ctx.accounts.mint_authority.key();
+
+        if mint.data_is_empty() {
+            let (_, bump) = pda::bridged_mint(CHAIN_ID_ZETACHAIN_TESTNET, token_id);
+            Self::create_pda_account(
+                &payer,
+                mint,
+                &system_program,
+                Mint::LEN,
+                &ctx.accounts.token_program.key(),
+                &[pda::BRIDGED_MINT_SEED, &CHAIN_ID_ZETACHAIN_TESTNET.to_le_bytes(), &token_id.to_le_bytes(), &[bump]],
+            )?;
+            anchor_spl::token::initialize_mint2(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    anchor_spl::token::InitializeMint2 { mint: mint.clone() },
+                ),
+                0,
+                &mint_authority,
+                Some(&mint_authority),
+            )?;
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        if first_arrival {
+            let hash = uri_hash(&uri);
+            let mut window = if dedup_window.data_is_empty() {
+                let (_, bump) = pda::dedup_window(&hash);
+                Self::create_pda_account(
+                    &payer,
+                    dedup_window,
+                    &system_program,
+                    DEDUPLICATION_WINDOW_SPACE,
+                    &crate::ID,
+                    &[pda::DEDUP_WINDOW_SEED, &hash, &[bump]],
+                )?;
+                DeduplicationWindow { uri_hash: hash, first_seen: now, mint_count: 0, bump }
+            } else {
+                DeduplicationWindow::try_deserialize(&mut &dedup_window.try_borrow_data()?[..])?
+            };
+            Self::record_uri_mint(&mut window, &ctx.accounts.program_state, now)?;
+            window.try_serialize(&mut &mut dedup_window.try_borrow_mut_data()?[..])?;
+        }
+
+        let mut origin = if nft_origin.data_is_empty() {
+            let (_, bump) = pda::nft_origin(token_id);
+            Self::create_pda_account(
+                &payer,
+                nft_origin,
+                &system_program,
+                NFT_ORIGIN_SPACE,
+                &crate::ID,
+                &[&pda::nft_origin_seed(token_id), &[bump]],
+            )?;
+            NFTOrigin {
+                schema_version: NFT_ORIGIN_VERSION,
+                token_id,
+                origin_chain: CHAIN_ID_ZETACHAIN_TESTNET,
+                origin_token_id: token_id,
+                metadata_uri: String::new(),
+                mint: mint.key(),
+                created_at: now,
+                bump,
+                release_at: 0,
+                provisional_recipient: Pubkey::default(),
+                receipt_outstanding: false,
+                metadata_encryption_key: None,
+                arrived_at: now,
+                logical_owner: Pubkey::default(),
+                last_memo: String::new(),
+                stranded_supply: 0,
+                metadata_pending: false,
+                reveal_collection_id: None,
+                provenance_chain: 0,
+                origin_contract: [0u8; 32],
+                origin_contract_len: 0,
+                metadata_bump: mpl_token_metadata::accounts::Metadata::find_pda(&mint.key()).1,
+                edition_bump: mpl_token_metadata::accounts::MasterEdition::find_pda(&mint.key()).1,
+                co_owned: false,
+                amount: 1,
+                rental_locked_until: 0,
+                rental_locker: Pubkey::default(),
+                reconstructed: false,
+                departed_amount: 0,
+            }
+        } else {
+            NFTOrigin::try_deserialize(&mut &nft_origin.try_borrow_data()?[..])?
+        };
+        origin.origin_chain = CHAIN_ID_ZETACHAIN_TESTNET;
+        origin.set_provenance(&provenance.unwrap_or_else(|| {
+            Self::relayed_provenance(token_id, &ctx.accounts.program_state.universal_nft_contract)
+        }));
+        let uri = planner::resolve_inbound_uri(uri, !first_arrival, &origin.metadata_uri);
+        let metadata_deferred = Self::metadata_deferred(&ctx.accounts.program_state, &uri, None);
+        origin.metadata_pending = metadata_deferred;
+        origin.metadata_uri = uri;
+        origin.metadata_encryption_key = metadata_encryption_key;
+        origin.mint = mint.key();
+        origin.arrived_at = now;
+        origin.departed_amount = origin.departed_amount.saturating_sub(1);
+        // Batches always deliver to the recipient's ATA
+        origin.logical_owner = Pubkey::default();
+        origin.try_serialize(&mut &mut nft_origin.try_borrow_mut_data()?[..])?;
+
+        if nft_index.data_is_empty() {
+            let (_, bump) = pda::nft_index(&mint.key());
+            Self::create_pda_account(
+                &payer,
+                nft_index,
+                &system_program,
+                NFT_INDEX_SPACE,
+                &crate::ID,
+                &[pda::NFT_INDEX_SEED, mint.key().as_ref(), &[bump]],
+            )?;
+            NFTIndex { mint: mint.key(), token_id, bump }
+                .try_serialize(&mut &mut nft_index.try_borrow_mut_data()?[..])?;
+        }
+
+        // Like the single receive paths, a later delivery of the same bytes overwrites it
+        let (_, receipt_bump) = pda::inbound_receipt(message_hash);
+        if inbound_receipt.data_is_empty() {
+            Self::create_pda_account(
+                &payer,
+                inbound_receipt,
+                &system_program,
+                INBOUND_RECEIPT_SPACE,
+                &crate::ID,
+                &[pda::INBOUND_RECEIPT_SEED, message_hash, &[receipt_bump]],
+            )?;
+        }
+        InboundReceipt {
+            message_hash: *message_hash,
+            token_id,
+            mint: mint.key(),
+            slot: Clock::get()?.slot,
+            delivered_at: now,
+            bump: receipt_bump,
+        }.try_serialize(&mut &mut inbound_receipt.try_borrow_mut_data()?[..])?;
+
+        anchor_spl::associated_token::create_idempotent(CpiContext::new(
+            ctx.accounts.associated_token_program.to_account_info(),
+            anchor_spl::associated_token::Create {
+                payer: payer.clone(),
+                associated_token: recipient_token_account.clone(),
+                authority: recipient.clone(),
+                mint: mint.clone(),
+                system_program: system_program.clone(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+            },
+        )).map_err(cpi_failed(crate::ErrorCode::AtaCreateFailed))?;
+
+        let mint_authority_seeds: &[&[&[u8]]] = &[&[pda::MINT_AUTHORITY_SEED, &[ctx.bumps.mint_authority]]];
+        anchor_spl::token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: mint.clone(),
+                    to: recipient_token_account.clone(),
+                    authority: ctx.accounts.mint_authority.to_account_info(),
+                },
+                mint_authority_seeds,
+            ),
+            1,
+        ).map_err(cpi_failed(crate::ErrorCode::MintToFailed))?;
+
+        if metadata_deferred {
+            events.push(MetadataDeferred { token_id, mint: mint.key() });
+        }
+        events.push(CrossChainMessageReceived {
+            token_id,
+            origin_chain: CHAIN_ID_ZETACHAIN_TESTNET,
+            mint: mint.key(),
+            recipient: recipient.key(),
+            note: None,
+            provenance_chain: origin.provenance_chain,
+            origin_token_id: origin.origin_token_id,
+            origin_contract: origin.origin_contract,
+            origin_contract_len: origin.origin_contract_len,
+            value_lamports: 0,
+            value_parked: false,
+            amount: 1,
+        });
+        #[cfg(feature = "runtime-invariants")]
+        crate::invariants::token_on_solana(mint, origin.stranded_supply)?;
+
+        Ok(())
+    }
+
+    /// Metadata an inbound NFT is given; named like the native mints. Its origin contract's
+    /// policy, if any, sets the royalty and its recipient
+    fn inbound_metadata(uri: &str, policy: Option<&OriginPolicy>) -> DataV2 {
+        let royalty = policy.filter(|policy| policy.royalty_bps > 0);
+        DataV2 {
+            name: String::from("Universal NFT"),
+            symbol: String::from("UNFT"),
+            uri: uri.to_string(),
+            seller_fee_basis_points: royalty.map_or(0, |policy| policy.royalty_bps),
+            creators: royalty.map(|policy| vec![Creator {
+                address: policy.royalty_recipient,
+                verified: false,
+                share: 100,
+            }]),
+            collection: None,
+            uses: None,
+        }
+    }
+
+    /// Update authority and mutability of an inbound NFT's metadata under `policy`, or the
+    /// mint authority PDA and mutable without one
+    fn inbound_metadata_authority(policy: Option<&OriginPolicy>) -> (Pubkey, bool) {
+        match policy {
+            Some(OriginPolicy { update_authority_mode: UpdateAuthorityMode::Retained { authority }, is_mutable, .. }) => {
+                (*authority, *is_mutable)
+            }
+            Some(policy) => (pda::mint_authority().0, policy.is_mutable),
+            None => (pda::mint_authority().0, true),
+        }
+    }
+
+    /// The OriginPolicy for deliveries from `origin_contract` on `origin_chain`, passed as
+    /// the first remaining account. It must always be passed, so a caller can't skip the
+    /// policy; None, for the program-wide defaults, when no policy has been set for that origin
+    fn origin_policy(
+        remaining_accounts: &[AccountInfo],
+        origin_chain: u64,
+        origin_contract: &[u8],
+    ) -> Result<Option<OriginPolicy>> {
+        let info = remaining_accounts.first()
+            .ok_or(crate::ErrorCode::OriginPolicyMismatch)?;
+        let (expected, _) = pda::origin_policy(origin_chain, &origin_contract_hash(origin_contract));
+        require_keys_eq!(info.key(), expected, crate::ErrorCode::OriginPolicyMismatch);
+        if info.owner != &crate::ID || info.data_is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(OriginPolicy::try_deserialize(&mut &info.try_borrow_data()?[..])?))
+    }
+
+    /// Fail with MetadataRejected where the Token Metadata program would fail the CPI.
+    /// A failed CPI aborts the whole transaction and can't be caught, so best-effort
+    /// deliveries check first and skip the CPI instead
+    fn check_metadata(data: &DataV2) -> Result<()> {
+        require!(
+            data.name.len() <= mpl_token_metadata::MAX_NAME_LENGTH
+                && data.symbol.len() <= mpl_token_metadata::MAX_SYMBOL_LENGTH
+                && data.uri.len() <= mpl_token_metadata::MAX_URI_LENGTH
+                && data.seller_fee_basis_points <= 10_000,
+            crate::ErrorCode::MetadataRejected
+        );
+        Ok(())
+    }
+
+    /// Whether an inbound delivery of `uri` skips its metadata for `retry_metadata`. Only
+    /// under BestEffort; Atomic deliveries fail with the metadata CPI
+    fn metadata_deferred(program_state: &ProgramState, uri: &str, policy: Option<&OriginPolicy>) -> bool {
+        program_state.metadata_policy == MetadataPolicy::BestEffort
+            && Self::check_metadata(&Self::inbound_metadata(uri, policy)).is_err()
+    }
+
+    /// Allocate a rent-exempt account at a program PDA, signed with its seeds
+    fn create_pda_account<'info>(
+        payer: &AccountInfo<'info>,
+        account: &AccountInfo<'info>,
+        system_program: &AccountInfo<'info>,
+        space: usize,
+        owner: &Pubkey,
+        seeds: &[&[u8]],
+    ) -> Result<()> {
+        system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                system_program::CreateAccount {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+                &[seeds],
+            ),
+            Rent::get()?.minimum_balance(space),
+            space as u64,
+            owner,
+        )
+    }
+
+    /// Layout versions of this instance: the stored program state version, and the
+    /// compile-time versions of everything else
+    pub fn get_schema(ctx: Context<GetSchema>) -> Result<SchemaInfo> {
+        Ok(SchemaInfo {
+            program_version: PROGRAM_VERSION,
+            program_state_version: ctx.accounts.program_state.schema_version,
+            nft_origin_version: NFT_ORIGIN_VERSION,
+            event_schema: EVENT_SCHEMA_VERSION,
+            message_versions_supported: vec![MESSAGE_VERSION_V1, MESSAGE_VERSION_V2, MESSAGE_VERSION_BATCH],
+        })
+    }
+
+    /// Compare the program's upgrade authority, read from its ProgramData account, with the
+    /// announced one. Emits IntegrityMismatch on a failure, so a watcher that sends it as a
+    /// transaction gets an event to alert on
+    pub fn verify_integrity(ctx: Context<VerifyIntegrity>) -> Result<IntegrityReport> {
+        let program_data = &ctx.accounts.program_data;
+        let expected_upgrade_authority = ctx.accounts.program_state.expected_upgrade_authority;
+        let report = IntegrityReport {
+            passed: match program_data.upgrade_authority_address {
+                None => true,
+                Some(authority) => {
+                    expected_upgrade_authority != Pubkey::default() && authority == expected_upgrade_authority
+                }
+            },
+            immutable: program_data.upgrade_authority_address.is_none(),
+            upgrade_authority: program_data.upgrade_authority_address.unwrap_or_default(),
+            expected_upgrade_authority,
+            last_deploy_slot: program_data.slot,
+        };
+        if !report.passed {
+            emit!(IntegrityMismatch {
+                upgrade_authority: report.upgrade_authority,
+                expected_upgrade_authority,
+                last_deploy_slot: report.last_deploy_slot,
+            });
+        }
+        Ok(report)
+    }
+
+    /// Count a first arrival under `window`'s URI, restarting the window once it has
+    /// elapsed; fails past the configured per-window limit
+    fn record_uri_mint(window: &mut DeduplicationWindow, program_state: &ProgramState, now: i64) -> Result<()> {
+        let window_seconds = match program_state.uri_dedup_window_seconds {
+            0 => DEFAULT_URI_DEDUP_WINDOW_SECONDS,
+            seconds => seconds,
+        };
+        if window.mint_count == 0 || now >= window.first_seen.saturating_add(window_seconds) {
+            window.first_seen = now;
+            window.mint_count = 0;
+        }
+        window.mint_count = window.mint_count.saturating_add(1);
+        require!(
+            program_state.max_uri_mints_per_window == 0
+                || window.mint_count <= program_state.max_uri_mints_per_window,
+            crate::ErrorCode::URIAlreadyClaimed
+        );
+        Ok(())
+    }
+
+    /// Show how the receive path would parse `message` without writing anything.
+    /// Decode failures are reported in the result rather than failing the call.
+    pub fn debug_decode(_ctx: Context<DebugDecode>, message: Vec<u8>) -> Result<DebugDecodeResult> {
+        require!(
+            message.len() <= MAX_DEBUG_MESSAGE_BYTES,
+            UniversalNFTCoreError::InvalidMessageFormat
+        );
+
+        let mut result = DebugDecodeResult::default();
+        match Self::debug_decode_into(&message, &mut result) {
+            Ok(()) => {}
+            Err(Error::AnchorError(error)) => {
+                result.error_code = error.error_code_number;
+                result.error_name = error.error_name;
+            }
+            Err(Error::ProgramError(error)) => {
+                result.error_code = u64::from(error.program_error.clone()) as u32;
+                result.error_name = error.program_error.to_string();
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// The decode `deliver_inbound` runs: the bridge's `decode_single`, then each trailer
+    /// read from the single body, so a message fails here with the error delivery would hit
+    fn debug_decode_into(message: &[u8], result: &mut DebugDecodeResult) -> Result<()> {
+        let CrossChainNftMessage { destination, receiver, token_id, mut uri, sender, .. } =
+            MessageDecoder::decode_single(message)?;
+        result.uri_len = uri.len() as u32;
+        let mut end = uri.len().min(MAX_DEBUG_URI_BYTES);
+        while !uri.is_char_boundary(end) {
+            end -= 1;
+        }
+        uri.truncate(end);
+
+        result.destination = destination;
+        result.receiver = receiver;
+        result.token_id = token_id;
+        result.uri = uri;
+        result.sender = sender;
+
+        let body = MessageDecoder::single_body(message)?;
+        result.has_encryption_key = UniversalNFTCoreImpl::decode_metadata_encryption_key(body)?.is_some();
+        result.note = UniversalNFTCoreImpl::decode_note(body)?.map(|(note, _)| note);
+        result.has_provenance = UniversalNFTCoreImpl::decode_provenance(body)?.is_some();
+        result.amount = UniversalNFTCoreImpl::decode_amount(body)?.unwrap_or(1);
+        result.priority = UniversalNFTCoreImpl::decode_priority(body)?;
+        Ok(())
+    }
+
+    /// Measure the compute units `encode_cross_chain_message` and `decode_cross_chain_message`
+    /// take for a URI of `uri_len` bytes. The decode runs on the inbound body carrying the
+    /// same message, since the outbound tuple is only read on ZetaChain. Each figure includes
+    /// one `sol_remaining_compute_units` syscall, so both read slightly high
+    pub fn benchmark_encoding(ctx: Context<BenchmarkCtx>, uri_len: u16) -> Result<()> {
+        require!(
+            uri_len as usize <= MAX_METADATA_URI_BYTES,
+            UniversalNFTCoreError::InvalidUriEncoding
+        );
+        let nft = CrossChainNftMessage::forward(
+            [0u8; 20],
+            [0u8; 20],
+            0,
+            "a".repeat(uri_len as usize),
+            [0u8; 20],
+        );
+        let call_options = ZetaChainCallOptions::default();
+        let inbound = UniversalNFTCoreImpl::encode_inbound_body(&nft, call_options.gas_limit);
+
+        let before_encode = sol_remaining_compute_units();
+        core::hint::black_box(UniversalNFTCoreImpl::encode_cross_chain_message(&nft, &call_options));
+        let before_decode = sol_remaining_compute_units();
+        let decoded = UniversalNFTCoreImpl::decode_cross_chain_message(&inbound)?;
+        let after_decode = sol_remaining_compute_units();
+        require_eq!(decoded.uri.len(), uri_len as usize, UniversalNFTCoreError::InvalidMessageFormat);
+
+        let benchmark = &mut ctx.accounts.encoding_benchmark;
+        benchmark.uri_len = uri_len;
+        benchmark.encode_cus = before_encode.saturating_sub(before_decode);
+        benchmark.decode_cus = before_decode.saturating_sub(after_decode);
+        benchmark.total_cus = benchmark.encode_cus + benchmark.decode_cus;
+        benchmark.bump = ctx.bumps.encoding_benchmark;
+
+        Ok(())
+    }
+
+    /// Hand a held delivery to its recipient once the hold has elapsed (crank; relayers only while the allowlist is on)
+    pub fn release_provisional(ctx: Context<ReleaseProvisional>, token_id: u64) -> Result<()> {
+        Self::require_relayer(&ctx.accounts.relayer_config, &ctx.accounts.relayer_role)?;
+        let release_at = ctx.accounts.nft_origin.release_at;
+        require!(release_at != 0, crate::ErrorCode::NotProvisional);
+        require!(
+            Clock::get()?.unix_timestamp >= release_at,
+            crate::ErrorCode::ProvisionalHoldActive
+        );
+
+        let mint_authority_seeds: &[&[&[u8]]] = &[&[pda::MINT_AUTHORITY_SEED, &[ctx.bumps.mint_authority]]];
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.provisional_escrow.to_account_info(),
+                    to: ctx.accounts.recipient_token_account.to_account_info(),
+                    authority: ctx.accounts.mint_authority.to_account_info(),
+                },
+                mint_authority_seeds,
+            ),
+            1,
+        ).map_err(cpi_failed(crate::ErrorCode::TokenTransferFailed))?;
+
+        let nft_origin = &mut ctx.accounts.nft_origin;
+        let recipient = nft_origin.provisional_recipient;
+        nft_origin.release_at = 0;
+        nft_origin.provisional_recipient = Pubkey::default();
+
+        emit!(ProvisionalReleased {
+            token_id,
+            recipient,
+            cranked_by: ctx.accounts.cranker.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Burn a held delivery before its hold elapses (admin only)
+    pub fn revoke_provisional(ctx: Context<RevokeProvisional>, token_id: u64) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::RevokeProvisional,
+        )?;
+
+        let release_at = ctx.accounts.nft_origin.release_at;
+        require!(release_at != 0, crate::ErrorCode::NotProvisional);
+        require!(
+            Clock::get()?.unix_timestamp < release_at,
+            crate::ErrorCode::ProvisionalHoldElapsed
+        );
+
+        let mint_authority_seeds: &[&[&[u8]]] = &[&[pda::MINT_AUTHORITY_SEED, &[ctx.bumps.mint_authority]]];
+        anchor_spl::token::burn(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    from: ctx.accounts.provisional_escrow.to_account_info(),
+                    authority: ctx.accounts.mint_authority.to_account_info(),
+                },
+                mint_authority_seeds,
+            ),
+            1,
+        ).map_err(cpi_failed(crate::ErrorCode::BurnFailed))?;
+
+        let nft_origin = &mut ctx.accounts.nft_origin;
+        let recipient = nft_origin.provisional_recipient;
+        nft_origin.release_at = 0;
+        nft_origin.provisional_recipient = Pubkey::default();
+        #[cfg(feature = "runtime-invariants")]
+        crate::invariants::token_departed(&ctx.accounts.mint.to_account_info(), nft_origin.stranded_supply)?;
+
+        emit!(ProvisionalRevoked {
+            token_id,
+            admin: ctx.accounts.admin.key(),
+            recipient,
+        });
+
+        Ok(())
+    }
+
+    /// Compare a token's escrow with the hold or offer claiming it. A hold over an empty
+    /// escrow is cleared; drift that needs a decision is flagged for the admin (crank;
+    /// relayers only while the allowlist is on)
+    pub fn reconcile_escrow(ctx: Context<ReconcileEscrow>, token_id: u64) -> Result<()> {
+        Self::require_relayer(&ctx.accounts.relayer_config, &ctx.accounts.relayer_role)?;
+        let escrow_amount = Self::escrow_amount(&ctx.accounts.escrow)?;
+        let has_offer = Self::check_escrow_offer(&ctx.accounts.inbound_offer, &ctx.accounts.nft_origin)?;
+        let finding = Self::escrow_finding(&ctx.accounts.nft_origin, escrow_amount, has_offer);
+
+        if finding == EscrowFinding::StaleHold {
+            let nft_origin = &mut ctx.accounts.nft_origin;
+            nft_origin.release_at = 0;
+            nft_origin.provisional_recipient = Pubkey::default();
+        }
+
+        if finding.needs_admin() {
+            emit!(EscrowDriftFlagged {
+                token_id,
+                finding,
+                escrow_amount,
+                cranked_by: ctx.accounts.cranker.key(),
+            });
+        }
+        emit!(EscrowReconciled { token_id, finding, cranked_by: ctx.accounts.cranker.key() });
+
+        Ok(())
+    }
+
+    /// What `reconcile_escrow` would find for a token, without repairing anything
+    pub fn get_escrow_status(ctx: Context<GetEscrowStatus>, _token_id: u64) -> Result<EscrowStatus> {
+        let escrow_amount = Self::escrow_amount(&ctx.accounts.escrow)?;
+        let has_offer = Self::check_escrow_offer(&ctx.accounts.inbound_offer, &ctx.accounts.nft_origin)?;
+
+        Ok(EscrowStatus {
+            finding: Self::escrow_finding(&ctx.accounts.nft_origin, escrow_amount, has_offer),
+            escrow_amount,
+            release_at: ctx.accounts.nft_origin.release_at,
+            has_offer,
+        })
+    }
+
+    /// Units in an escrow account; one that was never created holds none
+    fn escrow_amount(escrow: &UncheckedAccount) -> Result<u64> {
+        if escrow.data_is_empty() {
+            return Ok(0);
+        }
+        require!(escrow.owner == &anchor_spl::token::ID, crate::ErrorCode::InvalidTokenAccountOwner);
+        Ok(TokenAccount::try_deserialize(&mut &escrow.try_borrow_data()?[..])?.amount)
+    }
+
+    /// Whether an offer was passed; it must be the token's own
+    fn check_escrow_offer(inbound_offer: &Option<Account<InboundNFTOffer>>, nft_origin: &NFTOrigin) -> Result<bool> {
+        let Some(offer) = inbound_offer else {
+            return Ok(false);
+        };
+        require!(
+            offer.token_id == nft_origin.token_id && offer.mint == nft_origin.mint,
+            crate::ErrorCode::InboundOfferMismatch
+        );
+        Ok(true)
+    }
+
+    fn escrow_finding(nft_origin: &NFTOrigin, escrow_amount: u64, has_offer: bool) -> EscrowFinding {
+        let held = nft_origin.release_at != 0;
+        match escrow_amount {
+            0 if has_offer => EscrowFinding::OfferWithoutEscrow,
+            0 if held => EscrowFinding::StaleHold,
+            0 => EscrowFinding::Empty,
+            1 if held || has_offer => EscrowFinding::Held,
+            1 => EscrowFinding::OrphanedEscrow,
+            _ => EscrowFinding::ExcessEscrow,
+        }
+    }
+
+    /// Make recipients accept direct deliveries before the NFT leaves escrow (admin only)
+    pub fn set_require_nft_acceptance(ctx: Context<AdminAction>, required: bool) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetRequireNftAcceptance,
+        )?;
+
+        let previous_required = ctx.accounts.program_state.require_nft_acceptance;
+        Self::require_changed(&previous_required, &required)?;
+        ctx.accounts.program_state.require_nft_acceptance = required;
+
+        emit!(NftAcceptanceRequirementUpdated {
+            admin: ctx.accounts.admin.key(),
+            previous_required,
+            required,
+        });
+
+        Ok(())
+    }
+
+    /// Choose between one-phase and two-phase inbound delivery (admin only). Messages
+    /// already queued can still be executed after switching back to one phase
+    pub fn set_inbound_mode(ctx: Context<AdminAction>, inbound_mode: InboundMode) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetInboundMode,
+        )?;
+
+        let previous_inbound_mode = ctx.accounts.program_state.inbound_mode;
+        Self::require_changed(&previous_inbound_mode, &inbound_mode)?;
+        ctx.accounts.program_state.inbound_mode = inbound_mode;
+
+        emit!(InboundModeUpdated {
+            admin: ctx.accounts.admin.key(),
+            previous_inbound_mode,
+            inbound_mode,
+        });
+
+        Ok(())
+    }
+
+    /// Choose whether metadata failures fail inbound deliveries (admin only)
+    pub fn set_metadata_policy(ctx: Context<AdminAction>, metadata_policy: MetadataPolicy) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetMetadataPolicy,
+        )?;
+
+        let previous_metadata_policy = ctx.accounts.program_state.metadata_policy;
+        Self::require_changed(&previous_metadata_policy, &metadata_policy)?;
+        ctx.accounts.program_state.metadata_policy = metadata_policy;
+
+        emit!(MetadataPolicyUpdated {
+            admin: ctx.accounts.admin.key(),
+            previous_metadata_policy,
+            metadata_policy,
+        });
+
+        Ok(())
+    }
+
+    /// Set the metadata overrides for NFTs bridged in from `origin_contract` on
+    /// `origin_chain` (admin only)
+    pub fn set_origin_policy(
+        ctx: Context<SetOriginPolicy>,
+        origin_chain: u64,
+        origin_contract: Vec<u8>,
+        royalty_bps: u16,
+        royalty_recipient: Pubkey,
+        update_authority_mode: UpdateAuthorityMode,
+        is_mutable: bool,
+    ) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetOriginPolicy,
+        )?;
+
+        ChainId(origin_chain).require_valid()?;
+        require!(
+            (1..=MAX_ORIGIN_CONTRACT_BYTES).contains(&origin_contract.len())
+                && royalty_bps <= MAX_FEE_BPS
+                && (royalty_bps == 0 || royalty_recipient != Pubkey::default()),
+            crate::ErrorCode::InvalidOriginPolicy
+        );
+
+        let origin_policy = &mut ctx.accounts.origin_policy;
+        origin_policy.origin_chain = origin_chain;
+        origin_policy.origin_contract_hash = origin_contract_hash(&origin_contract);
+        origin_policy.royalty_bps = royalty_bps;
+        origin_policy.royalty_recipient = royalty_recipient;
+        origin_policy.update_authority_mode = update_authority_mode;
+        origin_policy.is_mutable = is_mutable;
+        origin_policy.bump = ctx.bumps.origin_policy;
+
+        emit!(OriginPolicyUpdated {
+            admin: ctx.accounts.admin.key(),
+            origin_chain,
+            origin_contract,
+            royalty_bps,
+            royalty_recipient,
+            update_authority_mode,
+            is_mutable,
+        });
+
+        Ok(())
+    }
+
+    /// Drop an origin's overrides so its NFTs get the defaults again (admin only)
+    pub fn remove_origin_policy(
+        ctx: Context<RemoveOriginPolicy>,
+        origin_chain: u64,
+        origin_contract: Vec<u8>,
+    ) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::RemoveOriginPolicy,
+        )?;
+
+        emit!(OriginPolicyRemoved {
+            admin: ctx.accounts.admin.key(),
+            origin_chain,
+            origin_contract,
+        });
+
+        Ok(())
+    }
+
+    /// Overwrite `next_token_id` without any of the usual checks, so tests can corrupt it and
+    /// watch the invariant trip (admin only; `runtime-invariants` builds only)
+    #[cfg(feature = "runtime-invariants")]
+    pub fn debug_set_next_token_id(ctx: Context<AdminAction>, next_token_id: u64) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::DebugSetNextTokenId,
+        )?;
+
+        ctx.accounts.program_state.next_token_id = next_token_id;
+        crate::invariants::next_token_id(&ctx.accounts.program_state)
+    }
+
+    /// Create or update the gas vault of `zrc20`: the SPL mint of its gas asset, the token
+    /// account gas is paid into, and the fee paid per transfer to its chain (admin only)
+    pub fn set_zrc20_gas_vault(ctx: Context<SetZRC20GasVault>, zrc20: [u8; 20], gas_fee: u64) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetZRC20GasVault,
+        )?;
+
+        let mint = ctx.accounts.mint.key();
+        let fee_destination = ctx.accounts.fee_destination.key();
+        let vault = &mut ctx.accounts.zrc20_gas_vault;
+        // The balance sits in the associated token account of the first mint; another
+        // mint would strand it
+        require!(
+            vault.mint == Pubkey::default() || vault.mint == mint,
+            crate::ErrorCode::ZRC20GasVaultMismatch
+        );
+        vault.zrc20 = zrc20;
+        vault.mint = mint;
+        vault.fee_destination = fee_destination;
+        vault.gas_fee = gas_fee;
+        vault.bump = ctx.bumps.zrc20_gas_vault;
+
+        emit!(ZRC20GasVaultUpdated {
+            admin: ctx.accounts.admin.key(),
+            zrc20,
+            mint,
+            fee_destination,
+            gas_fee,
+        });
+
+        Ok(())
+    }
+
+    /// Fund the gas vault of `zrc20` from the depositor's tokens of its gas asset; anyone
+    /// may sponsor transfers this way
+    pub fn deposit_zrc20_gas(ctx: Context<DepositZRC20Gas>, zrc20: [u8; 20], amount: u64) -> Result<()> {
+        require!(amount > 0, UniversalNFTCoreError::InvalidAmount);
+
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.depositor_token_account.to_account_info(),
+                    to: ctx.accounts.vault_token_account.to_account_info(),
+                    authority: ctx.accounts.depositor.to_account_info(),
+                },
+            ),
+            amount,
+        ).map_err(cpi_failed(crate::ErrorCode::TokenTransferFailed))?;
+
+        emit!(ZRC20GasDeposited {
+            zrc20,
+            depositor: ctx.accounts.depositor.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Take `amount` out of the gas vault of `zrc20` into the token account `dest` (admin only)
+    pub fn withdraw_zrc20_gas(
+        ctx: Context<WithdrawZRC20Gas>,
+        zrc20: [u8; 20],
+        amount: u64,
+        dest: Pubkey,
+    ) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::WithdrawZRC20Gas,
+        )?;
+        require!(amount > 0, UniversalNFTCoreError::InvalidAmount);
+
+        let vault_seeds: &[&[&[u8]]] = &[&[pda::ZRC20_VAULT_SEED, &zrc20, &[ctx.accounts.zrc20_gas_vault.bump]]];
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.vault_token_account.to_account_info(),
+                    to: ctx.accounts.dest_token_account.to_account_info(),
+                    authority: ctx.accounts.zrc20_gas_vault.to_account_info(),
+                },
+                vault_seeds,
+            ),
+            amount,
+        ).map_err(cpi_failed(crate::ErrorCode::TokenTransferFailed))?;
+
+        emit!(ZRC20GasWithdrawn {
+            admin: ctx.accounts.admin.key(),
+            zrc20,
+            amount,
+            dest,
+        });
+
+        Ok(())
+    }
+
+    /// Choose whether native mints create their NFTOrigin up front or leave a NFTMintOnly
+    /// record for `initialize_nft_origin_lazy` (admin only)
+    pub fn set_lazy_origins_enabled(ctx: Context<AdminAction>, enabled: bool) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetLazyOrigins,
+        )?;
+
+        let previous_enabled = ctx.accounts.program_state.lazy_origins_enabled;
+        Self::require_changed(&previous_enabled, &enabled)?;
+        ctx.accounts.program_state.lazy_origins_enabled = enabled;
+
+        emit!(LazyOriginsUpdated {
+            admin: ctx.accounts.admin.key(),
+            previous_enabled,
+            enabled,
+        });
+
+        Ok(())
+    }
+
+    /// Let mints, deliveries and transfers carry more than one unit of a token (admin only).
+    /// Turning it off leaves existing semi-fungible tokens able to move one unit at a time
+    pub fn set_allow_semi_fungible(ctx: Context<AdminAction>, enabled: bool) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetAllowSemiFungible,
+        )?;
+
+        let previous_enabled = ctx.accounts.program_state.allow_semi_fungible;
+        Self::require_changed(&previous_enabled, &enabled)?;
+        ctx.accounts.program_state.allow_semi_fungible = enabled;
+
+        emit!(SemiFungibleUpdated {
+            admin: ctx.accounts.admin.key(),
+            previous_enabled,
+            enabled,
+        });
+
+        Ok(())
+    }
+
+    /// Announce the upgrade authority `verify_integrity` expects, usually the multisig the
+    /// authority is about to be handed to (admin only). The current upgrade authority
+    /// co-signs, so a compromised admin key can't bless an authority it swapped in
+    pub fn set_expected_upgrade_authority(ctx: Context<SetExpectedUpgradeAuthority>, authority: Pubkey) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetExpectedUpgradeAuthority,
+        )?;
+
+        let previous_authority = ctx.accounts.program_state.expected_upgrade_authority;
+        Self::require_changed(&previous_authority, &authority)?;
+        ctx.accounts.program_state.expected_upgrade_authority = authority;
+
+        emit!(ExpectedUpgradeAuthorityUpdated {
+            admin: ctx.accounts.admin.key(),
+            previous_authority,
+            authority,
+        });
+
+        Ok(())
+    }
+
+    /// Turn forced recalls on or off and set how long a holder has to contest one; zero
+    /// means DEFAULT_RECALL_DISPUTE_WINDOW_SECONDS (admin only)
+    pub fn set_forced_recall_policy(
+        ctx: Context<AdminAction>,
+        enabled: bool,
+        dispute_window_seconds: i64,
+    ) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetForcedRecallPolicy,
+        )?;
+        require!(
+            (0..=MAX_RECALL_DISPUTE_WINDOW_SECONDS).contains(&dispute_window_seconds),
+            crate::ErrorCode::InvalidAdminValue
+        );
+
+        let program_state = &mut ctx.accounts.program_state;
+        let previous_enabled = program_state.forced_recall_enabled;
+        let previous_dispute_window_seconds = program_state.recall_dispute_window_seconds;
+        Self::require_changed(
+            &(previous_enabled, previous_dispute_window_seconds),
+            &(enabled, dispute_window_seconds),
+        )?;
+        program_state.forced_recall_enabled = enabled;
+        program_state.recall_dispute_window_seconds = dispute_window_seconds;
+
+        emit!(ForcedRecallPolicyUpdated {
+            admin: ctx.accounts.admin.key(),
+            previous_enabled,
+            enabled,
+            previous_dispute_window_seconds,
+            dispute_window_seconds,
+        });
+
+        Ok(())
+    }
+
+    /// Cap the metadata CPIs `retry_metadata` may make per slot; zero removes the cap
+    /// (admin only)
+    pub fn set_metadata_sync_throttle(ctx: Context<AdminAction>, max: u16) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetMetadataSyncThrottle,
+        )?;
+
+        let previous_max = ctx.accounts.program_state.max_metadata_uri_requests_per_slot;
+        Self::require_changed(&previous_max, &max)?;
+        ctx.accounts.program_state.max_metadata_uri_requests_per_slot = max;
+
+        emit!(MetadataSyncThrottleUpdated {
+            admin: ctx.accounts.admin.key(),
+            previous_max,
+            max,
+        });
+
+        Ok(())
+    }
+
+    /// Count a metadata CPI against `slot`, restarting the count in a new slot; fails past
+    /// the configured per-slot limit
+    fn record_metadata_sync(program_state: &mut ProgramState, slot: u64) -> Result<()> {
+        if slot != program_state.last_sync_slot {
+            program_state.last_sync_slot = slot;
+            program_state.metadata_sync_requests_this_slot = 0;
+        }
+        program_state.metadata_sync_requests_this_slot =
+            program_state.metadata_sync_requests_this_slot.saturating_add(1);
+        require!(
+            program_state.max_metadata_uri_requests_per_slot == 0
+                || program_state.metadata_sync_requests_this_slot <= program_state.max_metadata_uri_requests_per_slot,
+            crate::ErrorCode::MetadataSyncThrottled
+        );
+        Ok(())
+    }
+
+    /// Write the metadata a best-effort delivery skipped, from the NFT's current URI.
+    /// Anyone can call it (relayers only while the allowlist is on); it fails as an atomic
+    /// delivery would while the metadata is still rejected
+    pub fn retry_metadata(ctx: Context<RetryMetadata>, token_id: u64) -> Result<()> {
+        Self::require_relayer(&ctx.accounts.relayer_config, &ctx.accounts.relayer_role)?;
+        Self::record_metadata_sync(&mut ctx.accounts.program_state, Clock::get()?.slot)?;
+        let nft_origin = &mut ctx.accounts.nft_origin;
+        require!(nft_origin.metadata_pending, crate::ErrorCode::MetadataNotPending);
+        // Keyed like the delivery that deferred it
+        let origin_policy = Self::origin_policy(
+            ctx.remaining_accounts,
+            CHAIN_ID_ZETACHAIN_TESTNET,
+            &ctx.accounts.program_state.universal_nft_contract,
+        )?;
+        let data_v2 = Self::inbound_metadata(&nft_origin.metadata_uri, origin_policy.as_ref());
+        Self::check_metadata(&data_v2)?;
+        let (update_authority, is_mutable) = Self::inbound_metadata_authority(origin_policy.as_ref());
+        let mint_authority = ctx.accounts.mint_authority.to_account_info();
+        let update_authority_info = match &ctx.accounts.update_authority {
+            Some(account) => account.to_account_info(),
+            None => mint_authority.clone(),
+        };
+        require_keys_eq!(update_authority_info.key(), update_authority, crate::ErrorCode::OriginPolicyMismatch);
+
+        // Pending is cleared only once the metadata exists; a failed CPI fails the retry
+        mpl_token_metadata::instructions::CreateMetadataAccountV3CpiBuilder::new(&ctx.accounts.token_metadata_program)
+            .metadata(&ctx.accounts.metadata)
+            .mint(&ctx.accounts.mint.to_account_info())
+            .mint_authority(&mint_authority)
+            .payer(&ctx.accounts.payer.to_account_info())
+            .update_authority(&update_authority_info, update_authority == mint_authority.key())
+            .system_program(&ctx.accounts.system_program.to_account_info())
+            .data(data_v2)
+            .is_mutable(is_mutable)
+            .invoke_signed(&[&[pda::MINT_AUTHORITY_SEED, &[ctx.bumps.mint_authority]]])
+            .map_err(cpi_failed(crate::ErrorCode::MetadataCreateFailed))?;
+        ctx.accounts.nft_origin.metadata_pending = false;
+
+        emit!(MetadataCompleted {
+            token_id,
+            mint: ctx.accounts.mint.key(),
+            completed_by: ctx.accounts.payer.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Claim an inbound NFT offered to the signer, before the offer expires
+    pub fn accept_inbound_nft(ctx: Context<AcceptInboundNFT>, token_id: u64) -> Result<()> {
+        Self::require_top_level()?;
+        require!(
+            Clock::get()?.unix_timestamp <= ctx.accounts.inbound_offer.offer_expires_at,
+            crate::ErrorCode::InboundOfferExpired
+        );
+
+        let mint_authority_seeds: &[&[&[u8]]] = &[&[pda::MINT_AUTHORITY_SEED, &[ctx.bumps.mint_authority]]];
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.escrow.to_account_info(),
+                    to: ctx.accounts.recipient_token_account.to_account_info(),
+                    authority: ctx.accounts.mint_authority.to_account_info(),
+                },
+                mint_authority_seeds,
+            ),
+            1,
+        ).map_err(cpi_failed(crate::ErrorCode::TokenTransferFailed))?;
+
+        emit!(InboundNFTAccepted {
+            token_id,
+            recipient: ctx.accounts.recipient.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Turn down an inbound NFT: the escrowed token is burned, and with `return_to_sender`
+    /// a message mints it back to the EVM sender on ZetaChain. The recipient can reject
+    /// at any time and choose; once the offer has expired anyone can, to clear it out, but
+    /// only the recipient may destroy the NFT, so anyone else's reject returns it
+    pub fn reject_inbound_nft(
+        ctx: Context<RejectInboundNFT>,
+        token_id: u64,
+        return_to_sender: bool,
+    ) -> Result<()> {
+        Self::require_top_level()?;
+        Self::require_unlocked(&ctx.accounts.program_state)?;
+        let offer = &ctx.accounts.inbound_offer;
+        let by_recipient = ctx.accounts.authority.key() == offer.recipient;
+        require!(
+            by_recipient || Clock::get()?.unix_timestamp > offer.offer_expires_at,
+            crate::ErrorCode::Unauthorized
+        );
+        let return_to_sender = return_to_sender || !by_recipient;
+
+        let return_message = if return_to_sender {
+            Self::require_not_paused(&mut ctx.accounts.program_state)?;
+            let max_message_bytes = match ctx.accounts.program_state.max_message_bytes {
+                0 => DEFAULT_MAX_MESSAGE_BYTES,
+                bytes => bytes,
+            };
+            let nft_origin = &ctx.accounts.nft_origin;
+            let (message, _) = UniversalNFTCoreImpl::encode_bounded_outbound_message(
+                &CrossChainNftMessage::mint_here(offer.sender, token_id, nft_origin.metadata_uri.clone(), [0u8; 20]),
+                &ZetaChainCallOptions {
+                    gas_limit: ctx.accounts.program_state.gas_limit,
+                    ..ZetaChainCallOptions::default()
+                },
+                &OutboundTrailers {
+                    metadata_encryption_key: nft_origin.metadata_encryption_key,
+                    provenance: nft_origin.provenance().as_ref(),
+                    ..OutboundTrailers::default()
+                },
+                max_message_bytes as usize,
+            )?;
+            let gateway_program = ctx.accounts.gateway_program.as_ref()
+                .ok_or(UniversalNFTCoreError::GatewayCallFailed)?;
+            ctx.accounts.nft_origin.departed_amount = nft_origin.departed_amount.saturating_add(1);
+            Some((message, gateway_program.to_account_info()))
+        } else {
+            None
+        };
+
+        // CPIs: burn the escrowed unit, then the return message. A burn without one leaves
+        // departed_amount alone, so the delivering message can't be replayed
+        let mint_authority_seeds: &[&[&[u8]]] = &[&[pda::MINT_AUTHORITY_SEED, &[ctx.bumps.mint_authority]]];
+        anchor_spl::token::burn(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    from: ctx.accounts.escrow.to_account_info(),
+                    authority: ctx.accounts.mint_authority.to_account_info(),
+                },
+                mint_authority_seeds,
+            ),
+            1,
+        ).map_err(cpi_failed(crate::ErrorCode::BurnFailed))?;
+        if let Some((message, gateway_program)) = return_message {
+            let bridge = ctx.accounts.program_state.bridge_backend.bridge(
+                ctx.accounts.authority.to_account_info(),
+                ctx.accounts.program_state.universal_nft_contract,
+                ZetaChainCallOptions::default(),
+            );
+            Self::send_locked(&mut ctx.accounts.program_state, bridge, CHAIN_ID_ZETACHAIN_TESTNET, token_id, message, gateway_program)?;
+        }
+        #[cfg(feature = "runtime-invariants")]
+        crate::invariants::token_departed(&ctx.accounts.mint.to_account_info(), ctx.accounts.nft_origin.stranded_supply)?;
+
+        emit!(InboundNFTRejected {
+            token_id,
+            recipient: ctx.accounts.inbound_offer.recipient,
+            rejected_by: ctx.accounts.authority.key(),
+            returned_to_sender: return_to_sender,
+        });
+
+        Ok(())
+    }
+
+    /// Set the creation time before which NFT origins may be recovered by
+    /// `recover_misdelivered` (owner only)
+    pub fn set_recovery_cutoff(ctx: Context<SetRecoveryCutoff>, cutoff_at: i64) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetRecoveryCutoff,
+        )?;
+
+        let recovery_config = &mut ctx.accounts.recovery_config;
+        let previous_cutoff_at = recovery_config.cutoff_at;
+        Self::require_changed(&previous_cutoff_at, &cutoff_at)?;
+        recovery_config.cutoff_at = cutoff_at;
+        recovery_config.bump = ctx.bumps.recovery_config;
+
+        emit!(RecoveryCutoffUpdated {
+            admin: ctx.accounts.admin.key(),
+            previous_cutoff_at,
+            cutoff_at,
+        });
+
+        Ok(())
+    }
+
+    /// Re-deliver an NFT the legacy receive path minted to a zero-padded EVM address
+    /// (owner only). The stranded unit can't be moved without its owner's key, so it is
+    /// frozen in place and a replacement minted to `correct_recipient`
+    pub fn recover_misdelivered(
+        ctx: Context<RecoverMisdelivered>,
+        token_id: u64,
+        correct_recipient: Pubkey,
+        evidence_hash: [u8; 32],
+    ) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::RecoverMisdelivered,
+        )?;
+        require!(
+            ctx.accounts.nft_origin.created_at < ctx.accounts.recovery_config.cutoff_at,
+            crate::ErrorCode::MisdeliveryAfterCutoff
+        );
+        let stranded = &ctx.accounts.stranded_token_account;
+        require!(
+            stranded.owner.to_bytes()[..12] == [0u8; 12] && stranded.amount == 1,
+            crate::ErrorCode::NotMisdelivered
+        );
+        // Only bridged mints answer to the program PDA
+        let mint_authority = ctx.accounts.mint_authority.key();
+        require!(
+            ctx.accounts.mint.mint_authority == anchor_lang::solana_program::program_option::COption::Some(mint_authority)
+                && ctx.accounts.mint.freeze_authority == anchor_lang::solana_program::program_option::COption::Some(mint_authority),
+            crate::ErrorCode::MintAuthorityNotProgram
+        );
+
+        let stranded_owner = stranded.owner;
+        let already_frozen = stranded.is_frozen();
+        let now = Clock::get()?.unix_timestamp;
+        let recovery = &mut ctx.accounts.misdelivery_recovery;
+        recovery.token_id = token_id;
+        recovery.stranded_token_account = stranded.key();
+        recovery.stranded_owner = stranded_owner;
+        recovery.correct_recipient = correct_recipient;
+        recovery.evidence_hash = evidence_hash;
+        recovery.recovered_by = ctx.accounts.admin.key();
+        recovery.recovered_at = now;
+        recovery.bump = ctx.bumps.misdelivery_recovery;
+        let nft_origin = &mut ctx.accounts.nft_origin;
+        nft_origin.stranded_supply = nft_origin.stranded_supply.saturating_add(1);
+
+        let mint_authority_seeds: &[&[&[u8]]] = &[&[pda::MINT_AUTHORITY_SEED, &[ctx.bumps.mint_authority]]];
+        if !already_frozen {
+            anchor_spl::token::freeze_account(CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::FreezeAccount {
+                    account: ctx.accounts.stranded_token_account.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    authority: ctx.accounts.mint_authority.to_account_info(),
+                },
+                mint_authority_seeds,
+            )).map_err(cpi_failed(crate::ErrorCode::TokenAccountUpdateFailed))?;
+        }
+        anchor_spl::token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.recipient_token_account.to_account_info(),
+                    authority: ctx.accounts.mint_authority.to_account_info(),
+                },
+                mint_authority_seeds,
+            ),
+            1,
+        ).map_err(cpi_failed(crate::ErrorCode::MintToFailed))?;
+        // The frozen unit now counts as stranded, leaving the recovered one circulating
+        #[cfg(feature = "runtime-invariants")]
+        crate::invariants::token_on_solana(&ctx.accounts.mint.to_account_info(), ctx.accounts.nft_origin.stranded_supply)?;
+
+        emit!(MisdeliveryRecovered {
+            token_id,
+            admin: ctx.accounts.admin.key(),
+            stranded_owner,
+            correct_recipient,
+            evidence_hash,
+        });
+
+        Ok(())
+    }
+
+    /// Open a recall of `token_id` from the contract the gateway delivered `message` from.
+    /// Only the connected contract registered for the NFT's origin chain may send one; the
+    /// holder then has the dispute window to contest it
+    pub fn receive_forced_recall(
+        ctx: Context<ReceiveForcedRecall>,
+        token_id: u64,
+        sender: [u8; 20],
+        message: Vec<u8>,
+    ) -> Result<()> {
+        // The gateway's callback, so CPI is expected; require_not_paused checks the lock
+        Self::require_not_paused(&mut ctx.accounts.program_state)?;
+        require!(ctx.accounts.program_state.forced_recall_enabled, crate::ErrorCode::ForcedRecallDisabled);
+        let recall = UniversalNFTCoreImpl::decode_forced_recall(&message)?;
+        let nft_origin = &ctx.accounts.nft_origin;
+        // Only NFTs that came from another chain have a contract there to answer to
+        let origin_chain = nft_origin.provenance()
+            .map(|provenance| provenance.origin_chain)
+            .filter(|&chain| !ChainId(chain).is_solana())
+            .ok_or(crate::ErrorCode::InvalidForcedRecall)?;
+        require!(
+            recall.token_id == token_id
+                && recall.return_receiver != [0u8; 20]
+                && !nft_origin.is_semi_fungible(),
+            crate::ErrorCode::InvalidForcedRecall
+        );
+        {
+            let registry = ctx.accounts.registry.load()?;
+            let connected = registry.find_by_chain(origin_chain)
+                .ok_or(crate::ErrorCode::RecallSenderNotConnected)?;
+            require!(
+                connected.contract[..connected.contract_len as usize] == sender[..],
+                crate::ErrorCode::RecallSenderNotConnected
+            );
+        }
+        require!(
+            ctx.accounts.forced_recall.status != FORCED_RECALL_STATUS_PENDING,
+            crate::ErrorCode::ForcedRecallPending
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let dispute_window_seconds = match ctx.accounts.program_state.recall_dispute_window_seconds {
+            0 => DEFAULT_RECALL_DISPUTE_WINDOW_SECONDS,
+            seconds => seconds,
+        };
+        let forced_recall = &mut ctx.accounts.forced_recall;
+        forced_recall.token_id = token_id;
+        forced_recall.mint = nft_origin.mint;
+        forced_recall.origin_chain = origin_chain;
+        forced_recall.sender = sender;
+        forced_recall.return_receiver = recall.return_receiver;
+        forced_recall.requested_at = now;
+        forced_recall.dispute_ends_at = now.saturating_add(dispute_window_seconds);
+        forced_recall.status = FORCED_RECALL_STATUS_PENDING;
+        forced_recall.holder = Pubkey::default();
+        forced_recall.bump = ctx.bumps.forced_recall;
+
+        emit!(ForcedRecallRequested {
+            token_id,
+            mint: forced_recall.mint,
+            origin_chain,
+            sender,
+            return_receiver: recall.return_receiver,
+            dispute_ends_at: forced_recall.dispute_ends_at,
+        });
+
+        Ok(())
+    }
+
+    /// Veto a pending recall; any holder of the token may, until the dispute window closes
+    pub fn contest_recall(ctx: Context<ContestRecall>, token_id: u64) -> Result<()> {
+        Self::require_top_level()?;
+        Self::require_unlocked(&ctx.accounts.program_state)?;
+        let forced_recall = &mut ctx.accounts.forced_recall;
+        require!(
+            forced_recall.status == FORCED_RECALL_STATUS_PENDING,
+            crate::ErrorCode::ForcedRecallNotPending
+        );
+        let now = Clock::get()?.unix_timestamp;
+        require!(now < forced_recall.dispute_ends_at, crate::ErrorCode::RecallDisputeWindowElapsed);
+        require!(ctx.accounts.holder_token_account.amount == 1, crate::ErrorCode::NotRecallHolder);
+
+        forced_recall.status = FORCED_RECALL_STATUS_CONTESTED;
+        forced_recall.holder = ctx.accounts.holder.key();
+
+        emit!(ForcedRecallContested {
+            token_id,
+            holder: forced_recall.holder,
+            contested_at: now,
+        });
+
+        Ok(())
+    }
+
+    /// Carry out an uncontested recall once its dispute window has closed (crank; relayers
+    /// only while the allowlist is on). The holder's unit is frozen in place and counted as
+    /// stranded, as `recover_misdelivered` does, and the NFT goes back to the return receiver
+    /// through the normal outbound message
+    pub fn execute_forced_recall(ctx: Context<ExecuteForcedRecall>, token_id: u64) -> Result<()> {
+        Self::require_relayer(&ctx.accounts.relayer_config, &ctx.accounts.relayer_role)?;
+        Self::require_top_level()?;
+        Self::require_not_paused(&mut ctx.accounts.program_state)?;
+        require!(ctx.accounts.program_state.forced_recall_enabled, crate::ErrorCode::ForcedRecallDisabled);
+        let forced_recall = &ctx.accounts.forced_recall;
+        require!(
+            forced_recall.status == FORCED_RECALL_STATUS_PENDING,
+            crate::ErrorCode::ForcedRecallNotPending
+        );
+        let now = Clock::get()?.unix_timestamp;
+        require!(now >= forced_recall.dispute_ends_at, crate::ErrorCode::RecallDisputeWindowOpen);
+        require!(ctx.accounts.holder_token_account.amount == 1, crate::ErrorCode::InvalidForcedRecall);
+        // Only bridged mints answer to the program PDA
+        let mint_authority = ctx.accounts.mint_authority.key();
+        require!(
+            ctx.accounts.mint.mint_authority == anchor_lang::solana_program::program_option::COption::Some(mint_authority)
+                && ctx.accounts.mint.freeze_authority == anchor_lang::solana_program::program_option::COption::Some(mint_authority),
+            crate::ErrorCode::MintAuthorityNotProgram
+        );
+
+        let origin_chain = forced_recall.origin_chain;
+        let return_receiver = forced_recall.return_receiver;
+        let destination = Self::resolve_destination(ChainId(origin_chain), &Some(ctx.accounts.registry.clone()))?;
+        let max_message_bytes = match ctx.accounts.program_state.max_message_bytes {
+            0 => DEFAULT_MAX_MESSAGE_BYTES,
+            bytes => bytes,
+        };
+        let nft_origin = &ctx.accounts.nft_origin;
+        let (message, _) = UniversalNFTCoreImpl::encode_bounded_outbound_message(
+            &CrossChainNftMessage::forward(destination, return_receiver, token_id, nft_origin.metadata_uri.clone(), [0u8; 20]),
+            &ZetaChainCallOptions {
+                gas_limit: ctx.accounts.program_state.gas_limit,
+                ..ZetaChainCallOptions::default()
+            },
+            &OutboundTrailers {
+                metadata_encryption_key: nft_origin.metadata_encryption_key,
+                provenance: nft_origin.provenance().as_ref(),
+                ..OutboundTrailers::default()
+            },
+            max_message_bytes as usize,
+        )?;
+
+        let holder = ctx.accounts.holder_token_account.owner;
+        let already_frozen = ctx.accounts.holder_token_account.is_frozen();
+        let forced_recall = &mut ctx.accounts.forced_recall;
+        forced_recall.status = FORCED_RECALL_STATUS_EXECUTED;
+        forced_recall.holder = holder;
+        let nft_origin = &mut ctx.accounts.nft_origin;
+        nft_origin.stranded_supply = nft_origin.stranded_supply.saturating_add(1);
+        nft_origin.departed_amount = nft_origin.departed_amount.saturating_add(1);
+
+        // CPIs: freeze the holder's unit, then the return message
+        let mint_authority_seeds: &[&[&[u8]]] = &[&[pda::MINT_AUTHORITY_SEED, &[ctx.bumps.mint_authority]]];
+        if !already_frozen {
+            anchor_spl::token::freeze_account(CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::FreezeAccount {
+                    account: ctx.accounts.holder_token_account.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    authority: ctx.accounts.mint_authority.to_account_info(),
+                },
+                mint_authority_seeds,
+            )).map_err(cpi_failed(crate::ErrorCode::TokenAccountUpdateFailed))?;
+        }
+        let bridge = ctx.accounts.program_state.bridge_backend.bridge(
+            ctx.accounts.payer.to_account_info(),
+            ctx.accounts.program_state.universal_nft_contract,
+            ZetaChainCallOptions {
+                gas_limit: ctx.accounts.program_state.gas_limit,
+                ..ZetaChainCallOptions::default()
+            },
+        );
+        let gateway_program = ctx.accounts.gateway_program.to_account_info();
+        Self::send_locked(&mut ctx.accounts.program_state, bridge, origin_chain, token_id, message, gateway_program)?;
+        // The frozen unit counts as stranded, so none circulates
+        #[cfg(feature = "runtime-invariants")]
+        crate::invariants::token_departed(&ctx.accounts.mint.to_account_info(), ctx.accounts.nft_origin.stranded_supply)?;
+
+        emit!(ForcedRecallExecuted {
+            token_id,
+            holder,
+            origin_chain,
+            return_receiver,
+            executed_by: ctx.accounts.payer.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Owner check shared by every admin instruction; also emits the uniform
+    /// `AdminActionExecuted` event so monitors need only one decoder
+    fn require_admin(
+        program_state: &ProgramState,
+        admin: &Signer,
+        kind: AdminActionKind,
+    ) -> Result<()> {
+        require!(admin.key() == program_state.owner, crate::ErrorCode::Unauthorized);
+        Self::record_admin_action(admin.key(), kind)
+    }
+
+    /// `require_admin` for collection-scoped instructions: the collection's delegated
+    /// authority passes too when it holds `can_update_metadata`
+    fn require_collection_admin(
+        program_state: &ProgramState,
+        collection_authority: &Option<Account<CollectionAuthority>>,
+        admin: &Signer,
+        kind: AdminActionKind,
+    ) -> Result<()> {
+        let delegated = collection_authority
+            .as_ref()
+            .is_some_and(|record| record.authority == admin.key() && record.can_update_metadata);
+        require!(
+            admin.key() == program_state.owner || delegated,
+            crate::ErrorCode::Unauthorized
+        );
+        Self::record_admin_action(admin.key(), kind)
+    }
+
+    fn record_admin_action(actor: Pubkey, kind: AdminActionKind) -> Result<()> {
+        emit!(AdminActionExecuted {
+            kind: kind as u8,
+            actor,
+            slot: Clock::get()?.slot,
+        });
+        Ok(())
+    }
+
+    /// Setters refuse to write the value already stored: the "Updated" event would
+    /// report a change that never happened
+    fn require_changed<T: PartialEq>(current: &T, new: &T) -> Result<()> {
+        require!(current != new, crate::ErrorCode::NoChange);
+        Ok(())
+    }
+
+    /// Keys that can never be a counterparty: the system program and this program
+    fn require_not_program_key(key: &Pubkey) -> Result<()> {
+        require!(
+            *key != system_program::ID && *key != crate::ID,
+            crate::ErrorCode::InvalidAdminValue
+        );
+        Ok(())
+    }
+
+    /// Set gateway address (admin only)
+    pub fn set_gateway(ctx: Context<AdminAction>, gateway: Pubkey) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetGateway,
+        )?;
+
+        require!(gateway != Pubkey::default(), crate::ErrorCode::InvalidAdminValue);
+        Self::require_not_program_key(&gateway)?;
+        let previous_gateway = ctx.accounts.program_state.gateway;
+        Self::require_changed(&previous_gateway, &gateway)?;
+        ctx.accounts.program_state.gateway = gateway;
+        
+        emit!(GatewayUpdated {
+            admin: ctx.accounts.admin.key(),
+            previous_gateway,
+            gateway,
+        });
+        
+        Ok(())
+    }
+
+    /// Set gas limit (admin only)
+    pub fn set_gas_limit(ctx: Context<AdminAction>, gas_limit: u64) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetGasLimit,
+        )?;
+        
+        Self::validate_gas_limit(gas_limit, MAX_GAS_LIMIT)?;
+        let previous_gas_limit = ctx.accounts.program_state.gas_limit;
+        Self::require_changed(&previous_gas_limit, &gas_limit)?;
+        
+        ctx.accounts.program_state.gas_limit = gas_limit;
+        
+        emit!(GasLimitUpdated {
+            admin: ctx.accounts.admin.key(),
+            previous_gas_limit,
+            gas_limit,
+        });
+        
+        Ok(())
+    }
+
+    /// Set the gas limit used for calls to one destination chain (admin only)
+    pub fn set_chain_gas_config(
+        ctx: Context<SetChainGasConfig>,
+        chain_id: u64,
+        gas_limit: u64,
+        block_gas_limit: u64,
+    ) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetChainGasConfig,
+        )?;
+
+        ChainId(chain_id).require_valid()?;
+        Self::validate_gas_limit(block_gas_limit, MAX_GAS_LIMIT)?;
+        Self::validate_gas_limit(gas_limit, block_gas_limit)?;
+
+        let chain_gas_config = &mut ctx.accounts.chain_gas_config;
+        chain_gas_config.chain_id = chain_id;
+        chain_gas_config.gas_limit = gas_limit;
+        chain_gas_config.block_gas_limit = block_gas_limit;
+        chain_gas_config.bump = ctx.bumps.chain_gas_config;
+
+        emit!(ChainGasConfigUpdated {
+            admin: ctx.accounts.admin.key(),
+            chain_id,
+            gas_limit,
+            block_gas_limit,
+        });
+
+        Ok(())
+    }
+
+    /// Set how far inbound NFTs from a chain are trusted (admin only)
+    pub fn set_chain_trust(
+        ctx: Context<SetChainGasConfig>,
+        chain_id: u64,
+        trust_level: u8,
+        provisional_hold_seconds: u32,
+    ) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetChainTrust,
+        )?;
+
+        ChainId(chain_id).require_valid()?;
+        require!(
+            trust_level == TRUST_LEVEL_TRUSTED || trust_level == TRUST_LEVEL_PROVISIONAL,
+            crate::ErrorCode::InvalidTrustLevel
+        );
+
+        let chain_config = &mut ctx.accounts.chain_gas_config;
+        chain_config.chain_id = chain_id;
+        chain_config.trust_level = trust_level;
+        chain_config.provisional_hold_seconds = provisional_hold_seconds;
+        chain_config.bump = ctx.bumps.chain_gas_config;
+
+        emit!(ChainTrustUpdated {
+            admin: ctx.accounts.admin.key(),
+            chain_id,
+            trust_level,
+            provisional_hold_seconds,
+        });
+
+        Ok(())
+    }
+
+    /// Mark whether a chain's contract decodes batched transfers (admin only)
+    pub fn set_chain_batch_support(
+        ctx: Context<SetChainGasConfig>,
+        chain_id: u64,
+        supports_batch: bool,
+    ) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetChainBatchSupport,
+        )?;
+
+        ChainId(chain_id).require_valid()?;
+
+        let chain_config = &mut ctx.accounts.chain_gas_config;
+        chain_config.chain_id = chain_id;
+        chain_config.supports_batch = supports_batch;
+        chain_config.bump = ctx.bumps.chain_gas_config;
+
+        emit!(ChainBatchSupportUpdated {
+            admin: ctx.accounts.admin.key(),
+            chain_id,
+            supports_batch,
+        });
+
+        Ok(())
+    }
+
+    /// Gas limits are destination-chain gas units and must sit in
+    /// [MIN_GAS_LIMIT, upper], where `upper` is never above MAX_GAS_LIMIT
+    pub fn validate_gas_limit(gas_limit: u64, upper: u64) -> Result<()> {
+        require!(
+            (MIN_GAS_LIMIT..=upper.min(MAX_GAS_LIMIT)).contains(&gas_limit),
+            crate::ErrorCode::GasLimitOutOfRange
+        );
+        Ok(())
+    }
+
+    /// Set connected contract mapping (admin only)
+    pub fn set_connected_contract(
+        ctx: Context<AdminAction>,
+        zrc20: [u8; 20],
+        contract_address: Vec<u8>,
+    ) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetConnectedContract,
+        )?;
+        
+        require!(!contract_address.is_empty(), UniversalNFTCoreError::InvalidDestination);
+        
+        // Store connected contract mapping
+        let _connected_contract = ConnectedContract {
+            zrc20,
+            contract_address: contract_address.clone(),
+        };
+        
+        // This would typically be stored in a separate account or mapping
+        // For now, we'll emit an event
+        emit!(ConnectedContractSet {
+            admin: ctx.accounts.admin.key(),
+            zrc20,
+            contract_address,
+        });
+        
+        Ok(())
+    }
+
+    /// Pause the program (admin only). With `auto_unpause_at` set, the first paused-check
+    /// after that time lifts the pause on its own.
+    pub fn pause(ctx: Context<AdminAction>, reason_code: u8, auto_unpause_at: Option<i64>) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::Pause,
+        )?;
+        PauseReason::try_from(reason_code)?;
+        if let Some(unpause_at) = auto_unpause_at {
+            require!(
+                unpause_at > Clock::get()?.unix_timestamp,
+                crate::ErrorCode::InvalidPauseSchedule
+            );
+        }
+
+        let program_state = &mut ctx.accounts.program_state;
+        program_state.paused = true;
+        program_state.pause_reason = reason_code;
+        program_state.auto_unpause_at = auto_unpause_at;
+        
+        emit!(ProgramPaused {
+            admin: ctx.accounts.admin.key(),
+            reason_code,
+            auto_unpause_at,
+        });
+        
+        Ok(())
+    }
+
+    /// Unpause the program (admin only)
+    pub fn unpause(ctx: Context<AdminAction>) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::Unpause,
+        )?;
+        
+        let program_state = &mut ctx.accounts.program_state;
+        program_state.paused = false;
+        program_state.pause_reason = 0;
+        program_state.auto_unpause_at = None;
+        
+        emit!(ProgramUnpaused {
+            admin: ctx.accounts.admin.key(),
+        });
+        
+        Ok(())
+    }
+
+    /// Fail while paused, unless a scheduled unpause has passed, in which case lift the pause.
+    /// Also fails while a gateway CPI holds the lock
+    fn require_not_paused(program_state: &mut ProgramState) -> Result<()> {
+        Self::require_unlocked(program_state)?;
+        if !program_state.paused {
+            return Ok(());
+        }
+        let now = Clock::get()?.unix_timestamp;
+        if let Some(scheduled_at) = planner::plan_pause(true, program_state.auto_unpause_at, now)? {
+            Self::apply_auto_unpause(program_state, scheduled_at, now);
+        }
+        Ok(())
+    }
+
+    /// Fail while a gateway CPI holds program_state's lock
+    fn require_unlocked(program_state: &ProgramState) -> Result<()> {
+        require!(!program_state.locked, crate::ErrorCode::ReentrancyDetected);
+        Ok(())
+    }
+
+    /// Fail unless called by the transaction itself rather than through another program's CPI
+    fn require_top_level() -> Result<()> {
+        require!(
+            get_stack_height() == TRANSACTION_LEVEL_STACK_HEIGHT,
+            crate::ErrorCode::ReentrancyDetected
+        );
+        Ok(())
+    }
+
+    /// Send through `bridge` with program_state locked. The lock is written to the account
+    /// before the CPI so a gateway calling back in reads it; the cleared flag is written on exit
+    fn send_locked<'info>(
+        program_state: &mut Account<'info, ProgramState>,
+        bridge: Box<dyn CrossChainNFTBridge<'info> + 'info>,
+        destination_chain: u64,
+        token_id: u64,
+        message: Vec<u8>,
+        gateway_program: AccountInfo<'info>,
+    ) -> Result<()> {
+        Self::require_unlocked(program_state)?;
+        program_state.locked = true;
+        program_state.exit(&crate::ID)?;
+        bridge.send_nft(destination_chain, token_id, message, gateway_program)?;
+        program_state.locked = false;
+        Ok(())
+    }
+
+    /// Lift a pause whose scheduled unpause `plan_pause` found due
+    fn apply_auto_unpause(program_state: &mut ProgramState, scheduled_at: i64, now: i64) {
+        program_state.paused = false;
+        program_state.pause_reason = 0;
+        program_state.auto_unpause_at = None;
+        emit!(ProgramAutoUnpaused {
+            scheduled_at,
+            unpaused_at: now,
+        });
+    }
+
+
+    /// Update universal NFT contract address (admin only)
+    pub fn set_universal_nft_contract(
+        ctx: Context<AdminAction>,
+        universal_nft_contract: [u8; 20],
+    ) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetUniversalNftContract,
+        )?;
+
+        let previous_universal_nft_contract = ctx.accounts.program_state.universal_nft_contract;
+        require!(universal_nft_contract != [0u8; 20], UniversalNFTCoreError::InvalidAddress);
+        // The current address byte-reversed is an endianness slip, not a new deployment
+        let mut reversed = previous_universal_nft_contract;
+        reversed.reverse();
+        require!(
+            universal_nft_contract != reversed || reversed == previous_universal_nft_contract,
+            UniversalNFTCoreError::InvalidAddress
+        );
+        Self::require_changed(&previous_universal_nft_contract, &universal_nft_contract)?;
+        ctx.accounts.program_state.universal_nft_contract = universal_nft_contract;
+        
+        emit!(UniversalNFTContractUpdated {
+            admin: ctx.accounts.admin.key(),
+            previous_universal_nft_contract,
+            universal_nft_contract,
+        });
+        
+        Ok(())
+    }
+
+    /// Migrate program state to new structure (for backward compatibility)
+    pub fn migrate_program_state(
+        ctx: Context<MigrateProgramState>,
+    ) -> Result<()> {
+        // Accounts created before newer fields were added are too short to deserialize.
+        // Grow them first; the new tail is zeroed, which decodes as each field's default.
+        let program_state_info = ctx.accounts.program_state.to_account_info();
+        Self::grow_account(
+            &program_state_info,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            PROGRAM_STATE_SPACE,
+        )?;
+        let program_state = &mut ProgramState::try_deserialize(&mut &program_state_info.try_borrow_data()?[..])?;
+        
+        // Check if migration is needed (if gas_limit is 0, it means it's the old structure)
+        if program_state.gas_limit == 0 {
+            // Set default values for new fields
+            program_state.gas_limit = 1000000; // Default gas limit
+            
+            emit!(ProgramStateMigrated {
+                admin: ctx.accounts.payer.key(),
+                gas_limit: program_state.gas_limit,
+            });
+        }
+
+        // Accounts created before the id ranges were split start native ids at the
+        // default base; NFTs already minted below it keep their ids
+        if program_state.native_id_base == 0 {
+            program_state.native_id_base = DEFAULT_NATIVE_ID_BASE;
+            program_state.next_token_id = program_state.next_token_id.max(DEFAULT_NATIVE_ID_BASE);
+        }
+
+        // Accounts created before the cluster was declared always recorded devnet as the origin
+        if program_state.declared_chain_id == 0 {
+            program_state.declared_chain_id = CHAIN_ID_SOLANA_DEVNET;
+        }
+
+        // Accounts created before swap routing was configurable default to V2
+        if program_state.swap_router_version == 0 {
+            program_state.swap_router_version = SWAP_ROUTER_V2;
+            program_state.default_swap_fee_tier = DEFAULT_SWAP_FEE_TIER;
+        }
+
+        program_state.schema_version = PROGRAM_STATE_VERSION;
+        #[cfg(feature = "runtime-invariants")]
+        crate::invariants::next_token_id(program_state)?;
+        program_state.try_serialize(&mut &mut program_state_info.try_borrow_mut_data()?[..])?;
+        
+        Ok(())
+    }
+
+    /// Bring one NFTOrigin up to NFT_ORIGIN_VERSION. Accounts from before schema_version
+    /// existed are read at the legacy layout their size belongs to; anyone may pay for it
+    pub fn migrate_nft_origin(ctx: Context<MigrateNFTOrigin>, token_id: u64) -> Result<()> {
+        let nft_origin_info = ctx.accounts.nft_origin.to_account_info();
+        // Decoded at its own layout before growing, so the new tail is never read
+        let mut nft_origin = NFTOrigin::decode_any_version(&nft_origin_info.try_borrow_data()?)?;
+        Self::grow_account(
+            &nft_origin_info,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            NFT_ORIGIN_SPACE,
+        )?;
+        let from_version = nft_origin.schema_version;
+        require!(from_version < NFT_ORIGIN_VERSION, crate::ErrorCode::NoChange);
+
+        // Forward migrations, oldest first; each sets the fields its version added to their
+        // defaults. Version 7 added only schema_version itself; version 8 the provenance,
+        // known here only for native NFTs, so bridged ones get theirs on their next arrival;
+        // version 9 the metadata and edition bumps, found from the mint; version 10 co_owned,
+        // false since no CoOwnershipRecord predates it; version 11 the amount, one since
+        // every earlier token is an NFT; version 12 the rental lock, left zero since none
+        // was set before it; version 13 reconstructed, false since nothing was replayed
+        // before it; version 14 departed_amount, whatever of the amount the mint's supply
+        // says is away
+        if from_version < 8 && ChainId(nft_origin.origin_chain).is_solana() {
+            let provenance = Self::native_provenance(nft_origin.origin_chain, nft_origin.origin_token_id);
+            nft_origin.set_provenance(&provenance);
+        }
+        if from_version < 9 {
+            nft_origin.record_metadata_bumps();
+        }
+        if from_version < 11 {
+            nft_origin.amount = 1;
+        }
+        if from_version < 14 {
+            nft_origin.departed_amount = Self::departed_by_supply(&nft_origin, &ctx.accounts.mint)?;
+        }
+        nft_origin.schema_version = NFT_ORIGIN_VERSION;
+
+        nft_origin.try_serialize(&mut &mut nft_origin_info.try_borrow_mut_data()?[..])?;
+
+        emit!(NFTOriginMigrated {
+            token_id,
+            from_version,
+            to_version: NFT_ORIGIN_VERSION,
+        });
+
+        Ok(())
+    }
+
+    /// Give an NFTMintOnly from before uri_hash the hash of the URI its token was minted
+    /// with, which only `NFTMinted` logged; the admin reads it from there
+    pub fn migrate_nft_mint_only(ctx: Context<MigrateNFTMintOnly>, _token_id: u64, uri: String) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::MigrateNftMintOnly,
+        )?;
+        let mint_only_info = ctx.accounts.mint_only.to_account_info();
+        require_eq!(mint_only_info.data_len(), NFT_MINT_ONLY_V0_SPACE, crate::ErrorCode::NoChange);
+        Self::grow_account(
+            &mint_only_info,
+            &ctx.accounts.admin.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            NFT_MINT_ONLY_SPACE,
+        )?;
+        let mut mint_only = NFTMintOnly::try_deserialize(&mut &mint_only_info.try_borrow_data()?[..])?;
+        mint_only.uri_hash = uri_hash(&uri);
+        mint_only.try_serialize(&mut &mut mint_only_info.try_borrow_mut_data()?[..])?;
+
+        Ok(())
+    }
+
+    /// Grow a CollectionStats from before floor_price_feed_id. The id reads as zero, which no
+    /// feed holds, so the floor price stays put until the feed is registered again with
+    /// set_floor_price_feed. Anyone may pay for it
+    pub fn migrate_collection_stats(ctx: Context<MigrateCollectionStats>, _collection_id: u64) -> Result<()> {
+        let collection_stats_info = ctx.accounts.collection_stats.to_account_info();
+        require!(collection_stats_info.data_len() < COLLECTION_STATS_SPACE, crate::ErrorCode::NoChange);
+        Self::grow_account(
+            &collection_stats_info,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            COLLECTION_STATS_SPACE,
+        )?;
+        // The seeds already pin the collection; this only checks the grown account loads
+        CollectionStats::try_deserialize(&mut &collection_stats_info.try_borrow_data()?[..])?;
+
+        Ok(())
+    }
+
+    /// Grow a ChainGasConfig from before trust_level or supports_batch to the current
+    /// layout. The new fields read as zero: trusted, no hold and no batches, which is what
+    /// the chain had before they existed. Anyone may pay for it
+    pub fn migrate_chain_gas_config(ctx: Context<MigrateChainGasConfig>, chain_id: u64) -> Result<()> {
+        let chain_config_info = ctx.accounts.chain_gas_config.to_account_info();
+        require!(chain_config_info.data_len() < CHAIN_GAS_CONFIG_SPACE, crate::ErrorCode::NoChange);
+        Self::grow_account(
+            &chain_config_info,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            CHAIN_GAS_CONFIG_SPACE,
+        )?;
+        let chain_config = ChainGasConfig::try_deserialize(&mut &chain_config_info.try_borrow_data()?[..])?;
+        require_eq!(chain_config.chain_id, chain_id, crate::ErrorCode::InvalidChainId);
+
+        Ok(())
+    }
+
+    /// Grow a PendingInbound queued before its priority byte so execute_inbound can load it.
+    /// The byte reads as zero, the priority the message was queued with. Anyone may pay for it
+    pub fn migrate_pending_inbound(ctx: Context<MigratePendingInbound>, _message_hash: [u8; 32]) -> Result<()> {
+        let pending_inbound_info = ctx.accounts.pending_inbound.to_account_info();
+        let pending_inbound = PendingInbound::decode_any_version(&pending_inbound_info.try_borrow_data()?)?;
+        require!(
+            !pending_inbound.is_current(pending_inbound_info.data_len()),
+            crate::ErrorCode::NoChange
+        );
+        Self::grow_account(
+            &pending_inbound_info,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            pending_inbound.space(),
+        )
+    }
+
+    /// Re-lay a Registry written before RegistryEntry grew its liveness fields or
+    /// last_verified_at at the current stride. Each entry keeps its bytes and gets the new
+    /// fields zeroed: never answered, never verified. Anyone may pay for it
+    pub fn migrate_registry(ctx: Context<MigrateRegistry>) -> Result<()> {
+        // Discriminator, then count, bump and padding ahead of the entries
+        const HEADER: usize = 8 + 8;
+        let new_stride = std::mem::size_of::<RegistryEntry>();
+        let registry_info = ctx.accounts.registry.to_account_info();
+        let old_stride = match registry_info.data_len() {
+            len if len == HEADER + REGISTRY_CAPACITY * REGISTRY_ENTRY_V0_BYTES => REGISTRY_ENTRY_V0_BYTES,
+            len if len == HEADER + REGISTRY_CAPACITY * REGISTRY_ENTRY_V1_BYTES => REGISTRY_ENTRY_V1_BYTES,
+            _ => return err!(crate::ErrorCode::NoChange),
+        };
+        // Copied out first, since the entries move further apart than they were
+        let (count, old_entries) = {
+            let data = registry_info.try_borrow_data()?;
+            require!(
+                data[..Registry::DISCRIMINATOR.len()] == *Registry::DISCRIMINATOR,
+                anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch
+            );
+            let count = u32::from_le_bytes(data[8..12].try_into().unwrap());
+            require!(count as usize <= REGISTRY_CAPACITY, crate::ErrorCode::RegistryFull);
+            (count, data[HEADER..HEADER + count as usize * old_stride].to_vec())
+        };
+        Self::grow_account(
+            &registry_info,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            8 + std::mem::size_of::<Registry>(),
+        )?;
+
+        let mut data = registry_info.try_borrow_mut_data()?;
+        let entries = &mut data[HEADER..];
+        entries.fill(0);
+        for (index, entry) in old_entries.chunks(old_stride).enumerate() {
+            entries[index * new_stride..index * new_stride + old_stride].copy_from_slice(entry);
+        }
+
+        emit!(RegistryMigrated {
+            count,
+            from_entry_bytes: old_stride as u16,
+            to_entry_bytes: new_stride as u16,
+        });
+
+        Ok(())
+    }
+
+    /// Units of `nft_origin`'s amount not in its mint's supply, stranded ones aside: those
+    /// away from Solana. None for an origin that never got a mint
+    fn departed_by_supply(nft_origin: &NFTOrigin, mint: &AccountInfo) -> Result<u64> {
+        if nft_origin.mint == Pubkey::default() {
+            return Ok(0);
+        }
+        require_keys_eq!(mint.key(), nft_origin.mint, crate::ErrorCode::BridgedMintMismatch);
+        let supply = Mint::try_deserialize(&mut &mint.try_borrow_data()?[..])?.supply;
+        Ok(nft_origin.amount.saturating_sub(supply.saturating_sub(nft_origin.stranded_supply)))
+    }
+
+    /// Grow an account created before newer fields were added to `space`, topping up its
+    /// rent from `payer`. The new tail is zeroed, which decodes as each field's default
+    fn grow_account<'info>(
+        account: &AccountInfo<'info>,
+        payer: &AccountInfo<'info>,
+        system_program: &AccountInfo<'info>,
+        space: usize,
+    ) -> Result<()> {
+        if account.data_len() >= space {
+            return Ok(());
+        }
+        let shortfall = Rent::get()?
+            .minimum_balance(space)
+            .saturating_sub(account.lamports());
+        if shortfall > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    system_program.clone(),
+                    Transfer {
+                        from: payer.clone(),
+                        to: account.clone(),
+                    },
+                ),
+                shortfall,
+            )?;
+        }
+        account.resize(space)?;
+        Ok(())
+    }
+
+    /// Hand the program's BPF upgrade authority to `new_authority`, typically a multisig PDA.
+    /// The loader's unchecked SetAuthority is used because a PDA cannot co-sign the transfer.
+    pub fn transfer_upgrade_authority(
+        ctx: Context<TransferUpgradeAuthority>,
+        new_authority: Pubkey,
+    ) -> Result<()> {
+        let current_authority = ctx.accounts.current_authority.key();
+        require!(
+            new_authority != Pubkey::default() && new_authority != current_authority,
+            crate::ErrorCode::InvalidUpgradeAuthority
+        );
+
+        let ix = anchor_lang::solana_program::bpf_loader_upgradeable::set_upgrade_authority(
+            &crate::ID,
+            &current_authority,
+            Some(&new_authority),
+        );
+        anchor_lang::solana_program::program::invoke(
+            &ix,
+            &[
+                ctx.accounts.program_data.to_account_info(),
+                ctx.accounts.current_authority.to_account_info(),
+                ctx.accounts.new_upgrade_authority.to_account_info(),
+                ctx.accounts.bpf_loader_upgradeable.to_account_info(),
+            ],
+        )?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let record = &mut ctx.accounts.upgrade_authority_record;
+        record.authority = new_authority;
+        record.previous_authority = current_authority;
+        record.last_transfer_at = now;
+        record.bump = ctx.bumps.upgrade_authority_record;
+
+        emit!(UpgradeAuthorityTransferred {
+            previous_authority: current_authority,
+            new_authority,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Configure the anomaly window and thresholds for the inbound circuit breaker (admin only)
+    pub fn set_circuit_breaker_config(
+        ctx: Context<SetCircuitBreaker>,
+        window_seconds: i64,
+        failed_decode_threshold: u32,
+        replay_rejection_threshold: u32,
+    ) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetCircuitBreakerConfig,
+        )?;
+        require!(window_seconds > 0, crate::ErrorCode::InvalidCircuitBreakerConfig);
+
+        let security_state = &mut ctx.accounts.security_state;
+        security_state.window_seconds = window_seconds;
+        security_state.failed_decode_threshold = failed_decode_threshold;
+        security_state.replay_rejection_threshold = replay_rejection_threshold;
+        security_state.bump = ctx.bumps.security_state;
+
+        Ok(())
+    }
+
+    /// Re-enable inbound deliveries after the circuit breaker tripped (admin only)
+    pub fn clear_circuit_breaker(ctx: Context<SetCircuitBreaker>) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::ClearCircuitBreaker,
+        )?;
+
+        ctx.accounts.program_state.auto_paused_inbound = false;
+
+        let security_state = &mut ctx.accounts.security_state;
+        security_state.failed_decode_count = 0;
+        security_state.replay_rejection_count = 0;
+        security_state.window_start = Clock::get()?.unix_timestamp;
+        security_state.bump = ctx.bumps.security_state;
+
+        emit!(CircuitBreakerCleared {
+            admin: ctx.accounts.admin.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Record an inbound anomaly that failed transactions cannot record themselves.
+    /// Only relayers holding a `RelayerRole` can report, whether or not the allowlist is on, and
+    /// the anomaly is reproduced on-chain before it is counted: a decode failure must fail to
+    /// decode, a replay must target a bridged mint that is already live beyond its stranded units.
+    pub fn report_anomaly(
+        ctx: Context<ReportAnomaly>,
+        kind: AnomalyKind,
+        message: Vec<u8>,
+    ) -> Result<()> {
+        match kind {
+            AnomalyKind::DecodeFailure => {
+                require!(
+                    MessageDecoder::decode(&message).is_err(),
+                    crate::ErrorCode::AnomalyNotReproduced
+                );
+            }
+            AnomalyKind::ReplayRejection => {
+                let decoded = MessageDecoder::decode(&message)?;
+                let token_id = decoded.single()
+                    .ok_or(crate::ErrorCode::AnomalyNotReproduced)?
+                    .token_id;
+                let bridged_mint = ctx.accounts.bridged_mint.as_ref()
+                    .ok_or(crate::ErrorCode::AnomalyNotReproduced)?;
+                let (expected_mint, _) = pda::bridged_mint(CHAIN_ID_ZETACHAIN_TESTNET, token_id);
+                require_keys_eq!(bridged_mint.key(), expected_mint, crate::ErrorCode::AnomalyNotReproduced);
+                let nft_origin = ctx.accounts.nft_origin.as_ref()
+                    .ok_or(crate::ErrorCode::AnomalyNotReproduced)?;
+                require_keys_eq!(nft_origin.key(), pda::nft_origin(token_id).0, crate::ErrorCode::AnomalyNotReproduced);
+                // The same check a delivery fails with NFTAlreadyDelivered
+                require!(bridged_mint.supply > nft_origin.stranded_supply, crate::ErrorCode::AnomalyNotReproduced);
+            }
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        let security_state = &mut ctx.accounts.security_state;
+        if now.saturating_sub(security_state.window_start) > security_state.window_seconds {
+            security_state.window_start = now;
+            security_state.failed_decode_count = 0;
+            security_state.replay_rejection_count = 0;
+        }
+
+        let (count, threshold) = match kind {
+            AnomalyKind::DecodeFailure => {
+                security_state.failed_decode_count = security_state.failed_decode_count.saturating_add(1);
+                (security_state.failed_decode_count, security_state.failed_decode_threshold)
+            }
+            AnomalyKind::ReplayRejection => {
+                security_state.replay_rejection_count = security_state.replay_rejection_count.saturating_add(1);
+                (security_state.replay_rejection_count, security_state.replay_rejection_threshold)
+            }
+        };
+
+        Self::record_hourly_metrics(&ctx.accounts.hourly_metrics, |metrics| {
+            metrics.errors = metrics.errors.saturating_add(1);
+        })?;
+
+        emit!(AnomalyReported {
+            reporter: ctx.accounts.reporter.key(),
+            kind,
+            count,
+        });
+
+        // A threshold of zero disables the breaker for that counter
+        if threshold > 0 && count >= threshold && !ctx.accounts.program_state.auto_paused_inbound {
+            ctx.accounts.program_state.auto_paused_inbound = true;
+            emit!(CircuitBreakerTripped {
+                kind,
+                count,
+                threshold,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Look up the universal token id minted at `mint`
+    pub fn lookup_token_id_by_mint(ctx: Context<LookupByMint>, mint: Pubkey) -> Result<u64> {
+        require_keys_eq!(ctx.accounts.nft_index.mint, mint, crate::ErrorCode::NFTOriginNotFound);
+        Ok(ctx.accounts.nft_index.token_id)
+    }
+
+    /// Send a diagnostic ping through the gateway without touching any NFT
+    pub fn ping_gateway(ctx: Context<GatewayPing>, destination: [u8; 20]) -> Result<()> {
+        let program_state = &mut ctx.accounts.program_state;
+        program_state.ping_count = program_state.ping_count.checked_add(1)
+            .ok_or(UniversalNFTCoreError::InvalidAmount)?;
+
+        let ping = PingMessage {
+            timestamp: Clock::get()?.unix_timestamp,
+            nonce: program_state.ping_count,
+            sender: ctx.accounts.sender.key(),
+        };
+        let message = UniversalNFTCoreImpl::encode_ping_message(&ping)?;
+
+        UniversalNFTCoreImpl::call_gateway(
+            ctx.accounts.gateway_program.to_account_info(),
+            ctx.accounts.sender.to_account_info(),
+            destination,
+            message,
+            &ZetaChainCallOptions {
+                gas_limit: ctx.accounts.program_state.gas_limit,
+                ..ZetaChainCallOptions::default()
+            },
+        ).map_err(cpi_failed(UniversalNFTCoreError::GatewayCallFailed))?;
+
+        emit!(GatewayPingSent {
+            sender: ping.sender,
+            destination,
+            nonce: ping.nonce,
+            sent_at: ping.timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Record the echo of an earlier ping and report the round trip time
+    pub fn handle_pong(ctx: Context<GatewayPong>, pong_message: Vec<u8>) -> Result<()> {
+        let (ping, destination) = UniversalNFTCoreImpl::decode_pong_message(&pong_message)?;
+        require!(
+            ping.nonce > 0 && ping.nonce <= ctx.accounts.program_state.ping_count,
+            crate::ErrorCode::UnknownPing
+        );
+
+        let received_at = Clock::get()?.unix_timestamp;
+        emit!(GatewayPingPong {
+            sent_at: ping.timestamp,
+            received_at,
+            round_trip_ms: received_at.saturating_sub(ping.timestamp).saturating_mul(1000),
+            destination,
+        });
+
+        Ok(())
+    }
+
+    /// Ping the contract connected for `zrc20`. Reports, without failing, when it has been
+    /// silent longer than MAX_CONTRACT_SILENCE_SECONDS (crank; relayers only while the allowlist is on)
+    pub fn ping_connected_contract(ctx: Context<PingConnectedContract>, zrc20: [u8; 20]) -> Result<()> {
+        Self::require_relayer(&ctx.accounts.relayer_config, &ctx.accounts.relayer_role)?;
+        let last_seen_at = ctx.accounts.registry.load()?
+            .find(&zrc20)
+            .ok_or(crate::ErrorCode::NotInRegistry)?
+            .last_seen_at;
+
+        let ping = ContractPingMessage {
+            timestamp: Clock::get()?.unix_timestamp,
+            solana_id: crate::ID,
+        };
+        UniversalNFTCoreImpl::call_gateway(
+            ctx.accounts.gateway_program.to_account_info(),
+            ctx.accounts.sender.to_account_info(),
+            ctx.accounts.program_state.universal_nft_contract,
+            UniversalNFTCoreImpl::encode_contract_ping(zrc20, &ping)?,
+            &ZetaChainCallOptions {
+                gas_limit: ctx.accounts.program_state.gas_limit,
+                ..ZetaChainCallOptions::default()
+            },
+        ).map_err(cpi_failed(UniversalNFTCoreError::GatewayCallFailed))?;
+
+        // A contract that never answered has no silence to measure yet
+        let silent_seconds = ping.timestamp.saturating_sub(last_seen_at);
+        if last_seen_at > 0 && silent_seconds > MAX_CONTRACT_SILENCE_SECONDS {
+            msg!("Connected contract for {:?} silent for {}s", zrc20, silent_seconds);
+            emit!(ConnectedContractUnresponsive {
+                zrc20,
+                last_seen_at,
+                silent_seconds,
+                error_code: crate::ErrorCode::ContractUnresponsive.into(),
+            });
+        }
+
+        emit!(ConnectedContractPinged {
+            zrc20,
+            sender: ctx.accounts.sender.key(),
+            sent_at: ping.timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Record a connected contract's answer to a ping; `pong_data` starts with the echoed ping
+    pub fn record_pong(ctx: Context<RecordPong>, zrc20: [u8; 20], pong_data: Vec<u8>) -> Result<()> {
+        let ping = ContractPingMessage::deserialize(&mut pong_data.as_slice())
+            .map_err(|_| UniversalNFTCoreError::InvalidMessageFormat)?;
+        require_keys_eq!(ping.solana_id, crate::ID, UniversalNFTCoreError::InvalidMessageFormat);
+
+        let now = Clock::get()?.unix_timestamp;
+        let pong_hash = anchor_lang::solana_program::hash::hash(&pong_data).to_bytes();
+        let mut registry = ctx.accounts.registry.load_mut()?;
+        let entry = registry.find_mut(&zrc20).ok_or(crate::ErrorCode::NotInRegistry)?;
+        entry.last_seen_at = now;
+        entry.last_pong_hash = pong_hash;
+
+        emit!(ConnectedContractPonged {
+            zrc20,
+            pinged_at: ping.timestamp,
+            last_seen_at: now,
+            pong_hash,
+        });
+
+        Ok(())
+    }
+
+    /// Set the protocol transfer fee and its treasury (admin only)
+    pub fn set_fee_config(
+        ctx: Context<SetFeeConfig>,
+        transfer_fee_lamports: u64,
+        treasury: Pubkey,
+    ) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetFeeConfig,
+        )?;
+
+        let fee_config = &mut ctx.accounts.fee_config;
+        fee_config.transfer_fee_lamports = transfer_fee_lamports;
+        fee_config.treasury = treasury;
+        fee_config.bump = ctx.bumps.fee_config;
+
+        emit!(FeeConfigUpdated {
+            admin: ctx.accounts.admin.key(),
+            transfer_fee_lamports,
+            treasury,
+        });
+
+        Ok(())
+    }
+
+    /// Turn departure receipts on or off and set the base their URIs are built from (admin only)
+    pub fn set_receipt_config(
+        ctx: Context<SetReceiptConfig>,
+        enabled: bool,
+        uri_base: String,
+    ) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetReceiptConfig,
+        )?;
+        require!(uri_base.len() <= MAX_RECEIPT_URI_BASE_BYTES, crate::ErrorCode::InvalidReceiptConfig);
+
+        let receipt_config = &mut ctx.accounts.receipt_config;
+        receipt_config.enabled = enabled;
+        receipt_config.uri_base = uri_base.clone();
+        receipt_config.bump = ctx.bumps.receipt_config;
+
+        emit!(ReceiptConfigUpdated {
+            admin: ctx.accounts.admin.key(),
+            enabled,
+            uri_base,
+        });
+
+        Ok(())
+    }
+
+    /// Replace the ordered list of fee discount tiers (admin only)
+    pub fn set_fee_discount_tiers(
+        ctx: Context<SetFeeConfig>,
+        tiers: Vec<FeeDiscountTier>,
+    ) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetFeeDiscountTiers,
+        )?;
+
+        require!(tiers.len() <= MAX_FEE_DISCOUNT_TIERS, crate::ErrorCode::InvalidFeeDiscountTiers);
+        require!(
+            tiers.iter().all(|tier| tier.discount_percent <= 100),
+            crate::ErrorCode::InvalidFeeDiscountTiers
+        );
+
+        let fee_config = &mut ctx.accounts.fee_config;
+        fee_config.discount_tiers = tiers;
+        fee_config.bump = ctx.bumps.fee_config;
+
+        emit!(FeeDiscountTiersUpdated {
+            admin: ctx.accounts.admin.key(),
+            tier_count: fee_config.discount_tiers.len() as u8,
+        });
+
+        Ok(())
+    }
+
+    /// Set the fee create_mint_and_nft charges and the share of it paid to the collection's
+    /// first creator, in basis points (admin only)
+    pub fn set_mint_fee(
+        ctx: Context<SetFeeConfig>,
+        mint_fee_lamports: u64,
+        creator_fee_bps: u16,
+    ) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetMintFee,
+        )?;
+        require!(creator_fee_bps <= MAX_FEE_BPS, crate::ErrorCode::InvalidFeeSplit);
+
+        let fee_config = &mut ctx.accounts.fee_config;
+        // A fee needs somewhere to go; set_fee_config sets the treasury
+        require!(
+            mint_fee_lamports == 0 || fee_config.treasury != Pubkey::default(),
+            crate::ErrorCode::FeeTreasuryMismatch
+        );
+        let previous_mint_fee_lamports = fee_config.mint_fee_lamports;
+        let previous_creator_fee_bps = fee_config.creator_fee_bps;
+        Self::require_changed(
+            &(previous_mint_fee_lamports, previous_creator_fee_bps),
+            &(mint_fee_lamports, creator_fee_bps),
+        )?;
+        fee_config.mint_fee_lamports = mint_fee_lamports;
+        fee_config.creator_fee_bps = creator_fee_bps;
+        fee_config.bump = ctx.bumps.fee_config;
+
+        emit!(MintFeeUpdated {
+            admin: ctx.accounts.admin.key(),
+            previous_mint_fee_lamports,
+            previous_creator_fee_bps,
+            mint_fee_lamports,
+            creator_fee_bps,
+        });
+
+        Ok(())
+    }
+
+    /// Set what transfer insurance costs and pays back on a revert; a zero premium stops
+    /// new opt-ins, cover already bought keeps its payout (admin only)
+    pub fn set_insurance_config(
+        ctx: Context<SetFeeConfig>,
+        insurance_premium_lamports: u64,
+        insurance_payout_lamports: u64,
+    ) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetInsuranceConfig,
+        )?;
+
+        let fee_config = &mut ctx.accounts.fee_config;
+        Self::require_changed(
+            &(fee_config.insurance_premium_lamports, fee_config.insurance_payout_lamports),
+            &(insurance_premium_lamports, insurance_payout_lamports),
+        )?;
+        fee_config.insurance_premium_lamports = insurance_premium_lamports;
+        fee_config.insurance_payout_lamports = insurance_payout_lamports;
+        fee_config.bump = ctx.bumps.fee_config;
+
+        emit!(InsuranceConfigUpdated {
+            admin: ctx.accounts.admin.key(),
+            insurance_premium_lamports,
+            insurance_payout_lamports,
+        });
+
+        Ok(())
+    }
+
+    /// Rewrite an origin chain recorded as 0 back when `CHAIN_ID_SOLANA` was 0 to this
+    /// instance's chain (admin only)
+    pub fn repair_nft_origin_chain(ctx: Context<RepairNFTOrigin>, token_id: u64) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::RepairNftOrigin,
+        )?;
+
+        let nft_origin = &mut ctx.accounts.nft_origin;
+        let previous_origin_chain = nft_origin.origin_chain;
+        if ChainId(previous_origin_chain).is_valid() {
+            return Ok(());
+        }
+        nft_origin.origin_chain = ctx.accounts.program_state.declared_chain_id;
+
+        emit!(NFTOriginChainRepaired {
+            token_id,
+            previous_origin_chain,
+            origin_chain: nft_origin.origin_chain,
+        });
+
+        Ok(())
+    }
+
+    /// Check the NFTOrigins of `token_ids`, passed as remaining accounts in the same order,
+    /// once a migration has run, and record the ones that fail in the
+    /// MigrationIntegrityReport (admin only). Nothing is repaired here
+    pub fn verify_migration_integrity(ctx: Context<VerifyMigration>, token_ids: Vec<u64>) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::VerifyMigrationIntegrity,
+        )?;
+        require!(
+            (1..=MAX_INTEGRITY_CHECK_BATCH).contains(&token_ids.len())
+                && ctx.remaining_accounts.len() == token_ids.len(),
+            crate::ErrorCode::InvalidIntegrityBatch
+        );
+
+        let mut failures = Vec::new();
+        for (&token_id, account) in token_ids.iter().zip(ctx.remaining_accounts) {
+            require_keys_eq!(account.key(), pda::nft_origin(token_id).0, crate::ErrorCode::InvalidIntegrityBatch);
+            if let Some(reason) = Self::origin_integrity_failure(account)? {
+                failures.push(IntegrityFailure { token_id, reason });
+            }
+        }
+
+        let report = &mut ctx.accounts.migration_integrity_report;
+        report.checked_at = Clock::get()?.unix_timestamp;
+        report.nft_origin_version = NFT_ORIGIN_VERSION;
+        report.total_checked = token_ids.len() as u32;
+        report.bump = ctx.bumps.migration_integrity_report;
+
+        emit!(MigrationIntegrityChecked {
+            total_checked: report.total_checked,
+            failures: failures.len() as u32,
+        });
+        report.failures = failures;
+
+        Ok(())
+    }
+
+    /// Rebuild the NFTOrigin and NFTIndex an archived inbound message wrote, recreating them
+    /// if they are gone. The message must match the receipt its delivery left at
+    /// `original_slot`. Nothing is minted, counted or logged as an arrival: the token
+    /// already exists or was burned since (owner only)
+    pub fn replay_inbound(ctx: Context<ReplayInbound>, message: Vec<u8>, original_slot: u64) -> Result<()> {
+        Self::require_admin(&ctx.accounts.program_state, &ctx.accounts.admin, AdminActionKind::ReplayInbound)?;
+        let program_state = &ctx.accounts.program_state;
+        let inbound_receipt = &ctx.accounts.inbound_receipt;
+        let nft_origin = &ctx.accounts.nft_origin;
+        let bridge = program_state.bridge_backend.bridge(
+            ctx.accounts.admin.to_account_info(),
+            program_state.universal_nft_contract,
+            ZetaChainCallOptions::default(),
+        );
+        let plan = planner::plan_replay(
+            &ReplaySnapshot {
+                mint: ctx.accounts.mint.key(),
+                origin_mint: nft_origin.mint,
+                origin_amount: nft_origin.amount,
+                receipt_token_id: inbound_receipt.token_id,
+                receipt_mint: inbound_receipt.mint,
+                receipt_slot: inbound_receipt.slot,
+                receipt_delivered_at: inbound_receipt.delivered_at,
+            },
+            &message,
+            bridge.receive_nft(message.clone(), CHAIN_ID_ZETACHAIN_TESTNET),
+            original_slot,
+        )?;
+        let provenance = plan.provenance.unwrap_or_else(|| {
+            Self::relayed_provenance(plan.token_id, &program_state.universal_nft_contract)
+        });
+        let message_hash = inbound_receipt.message_hash;
+
+        let nft_origin = &mut ctx.accounts.nft_origin;
+        if plan.recreated {
+            nft_origin.created_at = plan.delivered_at;
+            nft_origin.arrived_at = plan.delivered_at;
+        }
+        nft_origin.schema_version = NFT_ORIGIN_VERSION;
+        nft_origin.token_id = plan.token_id;
+        nft_origin.origin_chain = CHAIN_ID_ZETACHAIN_TESTNET;
+        nft_origin.set_provenance(&provenance);
+        nft_origin.metadata_uri = plan.uri;
+        nft_origin.metadata_encryption_key = plan.metadata_encryption_key;
+        nft_origin.mint = ctx.accounts.mint.key();
+        nft_origin.bump = ctx.bumps.nft_origin;
+        nft_origin.record_metadata_bumps();
+        nft_origin.amount = plan.origin_amount;
+        nft_origin.reconstructed = true;
+
+        let nft_index = &mut ctx.accounts.nft_index;
+        nft_index.mint = ctx.accounts.mint.key();
+        nft_index.token_id = plan.token_id;
+        nft_index.bump = ctx.bumps.nft_index;
+
+        emit!(InboundReplayed {
+            message_hash,
+            token_id: plan.token_id,
+            mint: ctx.accounts.mint.key(),
+            original_slot,
+            recreated: plan.recreated,
+            replayed_by: ctx.accounts.admin.key(),
+        });
+
+        Ok(())
+    }
+
+    /// First integrity check the NFTOrigin at `account` fails, if any. The version is read
+    /// before decoding, since an unmigrated account may not decode at the current layout
+    fn origin_integrity_failure(account: &AccountInfo) -> Result<Option<IntegrityFailureReason>> {
+        if account.owner != &crate::ID || account.data_is_empty() {
+            return Ok(Some(IntegrityFailureReason::Missing));
         }
-        
+        let data = account.try_borrow_data()?;
+        if data.get(..8) != Some(NFTOrigin::DISCRIMINATOR) {
+            return Ok(Some(IntegrityFailureReason::Undecodable));
+        }
+        if data.len() < NFT_ORIGIN_V7_SPACE || data[8] < NFT_ORIGIN_VERSION {
+            return Ok(Some(IntegrityFailureReason::OutdatedSchema));
+        }
+        let Ok(nft_origin) = NFTOrigin::try_deserialize(&mut &data[..]) else {
+            return Ok(Some(IntegrityFailureReason::Undecodable));
+        };
+        Ok(if nft_origin.mint == Pubkey::default() {
+            Some(IntegrityFailureReason::MissingMint)
+        } else if nft_origin.created_at <= 0 {
+            Some(IntegrityFailureReason::MissingCreatedAt)
+        } else if nft_origin.metadata_uri.is_empty() {
+            Some(IntegrityFailureReason::EmptyMetadataUri)
+        } else if !ChainId(nft_origin.origin_chain).is_valid() {
+            Some(IntegrityFailureReason::InvalidOriginChain)
+        } else {
+            None
+        })
+    }
+
+    /// Register the Pyth floor price feed for a collection (admin only): the account the
+    /// receiver posts it to and the feed id that account must hold
+    pub fn set_floor_price_feed(
+        ctx: Context<SetFloorPriceFeed>,
+        collection_id: u64,
+        floor_price_feed: Pubkey,
+        floor_price_feed_id: [u8; 32],
+    ) -> Result<()> {
+        Self::require_collection_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.collection_authority,
+            &ctx.accounts.admin,
+            AdminActionKind::SetFloorPriceFeed,
+        )?;
+        // Zero is what a migrated account holds before its feed is registered again
+        require!(floor_price_feed_id != [0u8; 32], crate::ErrorCode::InvalidPriceFeed);
+
+        let collection_stats = &mut ctx.accounts.collection_stats;
+        collection_stats.collection_id = collection_id;
+        collection_stats.floor_price_feed = floor_price_feed;
+        collection_stats.floor_price_feed_id = floor_price_feed_id;
+        collection_stats.bump = ctx.bumps.collection_stats;
+
+        emit!(FloorPriceFeedSet {
+            admin: ctx.accounts.admin.key(),
+            collection_id,
+            floor_price_feed,
+            floor_price_feed_id,
+        });
+
+        Ok(())
+    }
+
+    /// Refresh the collection floor price from its Pyth feed (crank; relayers only while the allowlist is on).
+    /// The feed is expected to quote the floor in SOL.
+    pub fn update_floor_price(ctx: Context<UpdateFloorPrice>, collection_id: u64) -> Result<()> {
+        Self::require_relayer(&ctx.accounts.relayer_config, &ctx.accounts.relayer_role)?;
+        let price = crate::pyth::read_price_update(
+            &ctx.accounts.price_feed.to_account_info(),
+            &ctx.accounts.collection_stats.floor_price_feed_id,
+        )?;
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now.saturating_sub(price.publish_time) <= MAX_FLOOR_PRICE_AGE_SECONDS,
+            crate::ErrorCode::StalePriceFeed
+        );
+
+        let collection_stats = &mut ctx.accounts.collection_stats;
+        collection_stats.floor_price_lamports = crate::pyth::sol_price_to_lamports(&price)?;
+        collection_stats.last_updated = now;
+
+        emit!(FloorPriceUpdated {
+            collection_id,
+            floor_price_lamports: collection_stats.floor_price_lamports,
+            publish_time: price.publish_time,
+            cranked_by: ctx.accounts.cranker.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Current statistics for a collection, returned to the caller
+    pub fn get_collection_stats(
+        ctx: Context<GetCollectionStats>,
+        _collection_id: u64,
+    ) -> Result<CollectionStatsSummary> {
+        let collection_stats = &ctx.accounts.collection_stats;
+        Ok(CollectionStatsSummary {
+            collection_id: collection_stats.collection_id,
+            floor_price_lamports: collection_stats.floor_price_lamports,
+            total_volume_lamports: collection_stats.total_volume_lamports,
+            total_sales: collection_stats.total_sales,
+            last_updated: collection_stats.last_updated,
+        })
+    }
+
+    pub fn get_chain_stats(
+        ctx: Context<GetChainStats>,
+        _chain_id: u64,
+    ) -> Result<ChainStatsSummary> {
+        let chain_stats = &ctx.accounts.chain_stats;
+        Ok(ChainStatsSummary {
+            chain_id: chain_stats.chain_id,
+            received: chain_stats.received,
+            departed: chain_stats.departed,
+            active: chain_stats.active,
+            cumulative_dwell_seconds: chain_stats.cumulative_dwell_seconds,
+            average_dwell_seconds: chain_stats.cumulative_dwell_seconds
+                .checked_div(chain_stats.departed)
+                .unwrap_or(0),
+        })
+    }
+
+    /// Program state plus whichever config accounts were passed, flattened for dashboards
+    pub fn get_full_config_snapshot<'info>(
+        ctx: Context<'_, '_, 'info, 'info, FullConfigRead<'info>>,
+    ) -> Result<FullConfigSnapshot> {
+        let program_state = &ctx.accounts.program_state;
+        let mut snapshot = FullConfigSnapshot {
+            owner: program_state.owner,
+            gateway: program_state.gateway,
+            next_token_id: program_state.next_token_id,
+            paused: program_state.paused,
+            gas_limit: program_state.gas_limit,
+            fee_mint_lamports: program_state.reservation_fee,
+            fee_transfer_lamports: 0,
+            gas_reserve_balance: 0,
+            rate_limit_window: 0,
+            rate_limit_max: 0,
+            connected_contract_count: 0,
+            migration_version: program_state.schema_version,
+            network: get_chain_name(program_state.declared_chain_id).to_string(),
+        };
+
+        let (fee_config_key, _) = pda::fee_config();
+        let (registry_key, _) = pda::registry();
+        let (security_state_key, _) = pda::security_state();
+
+        // The treasury is only known once the fee config has been read
+        let mut treasury = None;
+        for info in ctx.remaining_accounts.iter().filter(|info| info.key() == fee_config_key) {
+            if !info.data_is_empty() {
+                let fee_config = Account::<FeeConfig>::try_from(info)?;
+                snapshot.fee_transfer_lamports = fee_config.transfer_fee_lamports;
+                treasury = Some(fee_config.treasury);
+            }
+        }
+
+        for info in ctx.remaining_accounts {
+            let key = info.key();
+            if key == fee_config_key {
+                continue;
+            } else if Some(key) == treasury {
+                snapshot.gas_reserve_balance = info.lamports();
+            } else if key == registry_key {
+                if !info.data_is_empty() {
+                    snapshot.connected_contract_count = AccountLoader::<Registry>::try_from(info)?.load()?.count;
+                }
+            } else if key == security_state_key {
+                if !info.data_is_empty() {
+                    let security_state = Account::<SecurityState>::try_from(info)?;
+                    snapshot.rate_limit_window = security_state.window_seconds;
+                    snapshot.rate_limit_max = security_state.failed_decode_threshold;
+                }
+            } else {
+                return Err(crate::ErrorCode::InvalidSnapshotAccounts.into());
+            }
+        }
+
+        Ok(snapshot)
+    }
+
+    /// Open the metrics bucket for `hour_bucket`, the current hour or the next so a keeper can
+    /// have it ready before the hour starts. Anyone may pay for it
+    pub fn open_hourly_metrics(ctx: Context<OpenHourlyMetrics>, hour_bucket: u64) -> Result<()> {
+        let current = Self::hour_bucket(Clock::get()?.unix_timestamp);
+        require!(
+            hour_bucket == current || hour_bucket == current.saturating_add(1),
+            crate::ErrorCode::InvalidHourBucket
+        );
+
+        let hourly_metrics = &mut ctx.accounts.hourly_metrics;
+        hourly_metrics.hour_bucket = hour_bucket;
+        hourly_metrics.payer = ctx.accounts.payer.key();
+        hourly_metrics.bump = ctx.bumps.hourly_metrics;
+
+        Ok(())
+    }
+
+    pub fn get_hourly_metrics(ctx: Context<GetHourlyMetrics>, _hour_bucket: u64) -> Result<HourlyMetrics> {
+        Ok((*ctx.accounts.hourly_metrics).clone())
+    }
+
+    /// Close a bucket whose hour ended more than METRICS_RETENTION_SECONDS ago (crank;
+    /// relayers only while the allowlist is on); the rent goes back to whoever opened it
+    pub fn close_stale_metrics(ctx: Context<CloseStaleMetrics>, _hour_bucket: u64) -> Result<()> {
+        Self::require_relayer(&ctx.accounts.relayer_config, &ctx.accounts.relayer_role)?;
+        Self::require_metrics_stale(&ctx.accounts.hourly_metrics, Clock::get()?.unix_timestamp)
+    }
+
+    fn require_metrics_stale(hourly_metrics: &HourlyMetrics, now: i64) -> Result<()> {
+        let hour_end = (hourly_metrics.hour_bucket as i64)
+            .saturating_add(1)
+            .saturating_mul(METRICS_BUCKET_SECONDS);
+        require!(
+            now >= hour_end.saturating_add(METRICS_RETENTION_SECONDS),
+            crate::ErrorCode::MetricsNotStale
+        );
+        Ok(())
+    }
+
+    /// Close up to MAX_BATCH_CLOSE expired accounts of one type and refund each one's rent to
+    /// whoever paid for it. Remaining accounts are `[account, payer]` pairs in the order of
+    /// `pda_addresses`. One account that isn't of the type or hasn't expired, or a payer that
+    /// isn't the account's, fails the whole batch (admin only)
+    pub fn batch_close_stale_accounts<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchClose<'info>>,
+        account_type: StaleAccountType,
+        pda_addresses: Vec<Pubkey>,
+    ) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::BatchCloseStaleAccounts,
+        )?;
+        require!(
+            (1..=MAX_BATCH_CLOSE).contains(&pda_addresses.len())
+                && ctx.remaining_accounts.len() == 2 * pda_addresses.len(),
+            crate::ErrorCode::InvalidBatchClose
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let mut total_reclaimed_lamports = 0u64;
+        for (address, pair) in pda_addresses.iter().zip(ctx.remaining_accounts.chunks(2)) {
+            let (account, payer) = (&pair[0], &pair[1]);
+            require_keys_eq!(account.key(), *address, crate::ErrorCode::InvalidBatchClose);
+            require!(
+                account.owner == &crate::ID && account.is_writable,
+                crate::ErrorCode::InvalidBatchAccounts
+            );
+            require!(
+                payer.is_writable && !pda_addresses.contains(payer.key),
+                crate::ErrorCode::InvalidBatchClose
+            );
+            match account_type {
+                StaleAccountType::HourlyMetrics => {
+                    let hourly_metrics = HourlyMetrics::try_deserialize(&mut &account.try_borrow_data()?[..])?;
+                    let expected = Pubkey::create_program_address(
+                        &[pda::HOURLY_METRICS_SEED, &hourly_metrics.hour_bucket.to_le_bytes(), &[hourly_metrics.bump]],
+                        &crate::ID,
+                    ).map_err(|_| crate::ErrorCode::InvalidBatchAccounts)?;
+                    require_keys_eq!(account.key(), expected, crate::ErrorCode::InvalidBatchAccounts);
+                    require_keys_eq!(payer.key(), hourly_metrics.payer, crate::ErrorCode::RentPayerMismatch);
+                    Self::require_metrics_stale(&hourly_metrics, now)?;
+                }
+            }
+
+            let lamports = account.lamports();
+            total_reclaimed_lamports = total_reclaimed_lamports.checked_add(lamports)
+                .ok_or(UniversalNFTCoreError::InvalidAmount)?;
+            **payer.try_borrow_mut_lamports()? = payer.lamports().checked_add(lamports)
+                .ok_or(UniversalNFTCoreError::InvalidAmount)?;
+            **account.try_borrow_mut_lamports()? = 0;
+            account.assign(&system_program::ID);
+            account.resize(0)?;
+        }
+
+        emit!(StaleAccountsClosed {
+            count: pda_addresses.len() as u32,
+            total_reclaimed_lamports,
+            account_type,
+        });
+
+        Ok(())
+    }
+
+    /// Count `count` deliveries from ZetaChain, the origin chain inbound NFTs are recorded with
+    fn record_chain_arrivals(chain_stats: &mut Account<ChainStats>, bump: u8, count: u64) -> Result<()> {
+        #[cfg(feature = "runtime-invariants")]
+        let before = (**chain_stats).clone();
+        chain_stats.chain_id = CHAIN_ID_ZETACHAIN_TESTNET;
+        chain_stats.received = chain_stats.received.saturating_add(count);
+        chain_stats.active = chain_stats.active.saturating_add(count);
+        chain_stats.bump = bump;
+        #[cfg(feature = "runtime-invariants")]
+        crate::invariants::chain_stats_monotone(&before, chain_stats)?;
+        Ok(())
+    }
+
+    /// Apply `update` to an origin chain's stats. Stats never block a transfer: when the
+    /// account was never created, `ChainStatsMissing` is queued instead
+    fn update_chain_stats(
+        chain_stats: &AccountInfo,
+        chain_id: u64,
+        token_id: u64,
+        update: impl FnOnce(&mut ChainStats),
+        events: &mut EventBuffer,
+    ) -> Result<()> {
+        Self::write_chain_stats(chain_stats, chain_id, token_id, update, events, true)
+    }
+
+    /// `update_chain_stats` for taking back a departure that never arrived anywhere, the one
+    /// update that may lower a counter; exempt from the monotone invariant
+    fn undo_chain_stats(
+        chain_stats: &AccountInfo,
+        chain_id: u64,
+        token_id: u64,
+        update: impl FnOnce(&mut ChainStats),
+        events: &mut EventBuffer,
+    ) -> Result<()> {
+        Self::write_chain_stats(chain_stats, chain_id, token_id, update, events, false)
+    }
+
+    #[cfg_attr(not(feature = "runtime-invariants"), allow(unused_variables))]
+    fn write_chain_stats(
+        chain_stats: &AccountInfo,
+        chain_id: u64,
+        token_id: u64,
+        update: impl FnOnce(&mut ChainStats),
+        events: &mut EventBuffer,
+        monotone: bool,
+    ) -> Result<()> {
+        if chain_stats.data_is_empty() || chain_stats.owner != &crate::ID {
+            events.push(ChainStatsMissing { chain_id, token_id });
+            return Ok(());
+        }
+        let mut stats = ChainStats::try_deserialize(&mut &chain_stats.try_borrow_data()?[..])?;
+        #[cfg(feature = "runtime-invariants")]
+        let before = stats.clone();
+        update(&mut stats);
+        #[cfg(feature = "runtime-invariants")]
+        if monotone {
+            crate::invariants::chain_stats_monotone(&before, &stats)?;
+        }
+        stats.try_serialize(&mut &mut chain_stats.try_borrow_mut_data()?[..])?;
+        Ok(())
+    }
+
+    /// Apply `update` to the current hour's metrics. Metrics never block an instruction: a
+    /// bucket that was left out, belongs to another hour or was never opened is skipped
+    fn record_hourly_metrics(
+        hourly_metrics: &Option<UncheckedAccount>,
+        update: impl FnOnce(&mut HourlyMetrics),
+    ) -> Result<()> {
+        let Some(hourly_metrics) = hourly_metrics else {
+            return Ok(());
+        };
+        let (expected, _) = pda::hourly_metrics(Self::hour_bucket(Clock::get()?.unix_timestamp));
+        if hourly_metrics.key() != expected
+            || hourly_metrics.data_is_empty()
+            || hourly_metrics.owner != &crate::ID
+        {
+            return Ok(());
+        }
+        let mut metrics = HourlyMetrics::try_deserialize(&mut &hourly_metrics.try_borrow_data()?[..])?;
+        update(&mut metrics);
+        metrics.try_serialize(&mut &mut hourly_metrics.try_borrow_mut_data()?[..])?;
+        Ok(())
+    }
+
+    fn hour_bucket(unix_timestamp: i64) -> u64 {
+        (unix_timestamp.max(0) / METRICS_BUCKET_SECONDS) as u64
+    }
+
+    /// Delegate a collection's administration to `authority`; the default key revokes
+    /// it (owner only)
+    pub fn set_collection_authority(
+        ctx: Context<SetCollectionAuthority>,
+        collection_id: u64,
+        authority: Pubkey,
+        permissions: CollectionPermissions,
+    ) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetCollectionAuthority,
+        )?;
+
+        let record = &mut ctx.accounts.collection_authority;
+        record.collection_id = collection_id;
+        record.authority = authority;
+        record.can_mint = permissions.can_mint;
+        record.can_pause = permissions.can_pause;
+        record.can_update_metadata = permissions.can_update_metadata;
+        record.bump = ctx.bumps.collection_authority;
+
+        emit!(CollectionAuthorityUpdated {
+            admin: ctx.accounts.admin.key(),
+            collection_id,
+            authority,
+            can_mint: permissions.can_mint,
+            can_pause: permissions.can_pause,
+            can_update_metadata: permissions.can_update_metadata,
+        });
+
+        Ok(())
+    }
+
+    /// Register the creators and shares for a collection's royalties (admin only). An empty
+    /// list records that the collection has no creators, so its mint fees all go to the
+    /// treasury and it takes no royalty deposits.
+    /// Changes only affect deposits made afterwards; amounts already credited stay owed.
+    pub fn set_seller_fee_config(
+        ctx: Context<SetSellerFeeConfig>,
+        collection_id: u64,
+        creators: Vec<RoyaltyCreator>,
+    ) -> Result<()> {
+        Self::require_collection_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.collection_authority,
+            &ctx.accounts.admin,
+            AdminActionKind::SetSellerFeeConfig,
+        )?;
+
+        require!(creators.len() <= MAX_ROYALTY_CREATORS, crate::ErrorCode::InvalidRoyaltyShares);
+        let total_share: u32 = creators.iter().map(|c| c.share as u32).sum();
+        require!(creators.is_empty() || total_share == 100, crate::ErrorCode::InvalidRoyaltyShares);
+        for (i, creator) in creators.iter().enumerate() {
+            require!(
+                creators[..i].iter().all(|other| other.address != creator.address),
+                crate::ErrorCode::InvalidRoyaltyShares
+            );
+        }
+
+        let seller_fee_config = &mut ctx.accounts.seller_fee_config;
+        seller_fee_config.collection_id = collection_id;
+        seller_fee_config.creators = creators;
+        seller_fee_config.bump = ctx.bumps.seller_fee_config;
+
+        emit!(SellerFeeConfigUpdated {
+            admin: ctx.accounts.admin.key(),
+            collection_id,
+            creator_count: seller_fee_config.creators.len() as u8,
+        });
+
+        Ok(())
+    }
+
+    /// Replace one royalty recipient, with the consent of the recipient being replaced (admin only).
+    /// Royalties already credited to the old recipient stay claimable by them.
+    pub fn update_royalty_recipient(
+        ctx: Context<UpdateRoyaltyRecipient>,
+        collection_id: u64,
+        creator_index: u8,
+        new_recipient: Pubkey,
+        new_share: u8,
+    ) -> Result<()> {
+        Self::require_collection_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.collection_authority,
+            &ctx.accounts.admin,
+            AdminActionKind::UpdateRoyaltyRecipient,
+        )?;
+
+        let creators = &mut ctx.accounts.seller_fee_config.creators;
+        let index = creator_index as usize;
+        require!(index < creators.len(), crate::ErrorCode::InvalidRoyaltyShares);
+        let old_recipient = creators[index].address;
+        require_keys_eq!(
+            ctx.accounts.current_recipient.key(),
+            old_recipient,
+            crate::ErrorCode::RoyaltyRecipientMismatch
+        );
+        require!(
+            creators.iter().enumerate().all(|(i, other)| i == index || other.address != new_recipient),
+            crate::ErrorCode::InvalidRoyaltyShares
+        );
+
+        creators[index] = RoyaltyCreator { address: new_recipient, share: new_share };
+        let total_share: u32 = creators.iter().map(|c| c.share as u32).sum();
+        require!(total_share == 100, crate::ErrorCode::InvalidRoyaltyShares);
+
+        emit!(RoyaltyRecipientUpdated {
+            collection_id,
+            old_recipient,
+            new_recipient,
+            new_share,
+        });
+
+        Ok(())
+    }
+
+    /// Deposit royalty lamports for a collection, crediting each creator by share.
+    /// Rounding dust goes to the first creator so the balance always matches what is owed.
+    pub fn deposit_royalties(
+        ctx: Context<DepositRoyalties>,
+        collection_id: u64,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, UniversalNFTCoreError::InvalidAmount);
+        require!(!ctx.accounts.seller_fee_config.creators.is_empty(), crate::ErrorCode::NoRoyaltyCreators);
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.depositor.to_account_info(),
+                    to: ctx.accounts.royalty_balance.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let creators = &ctx.accounts.seller_fee_config.creators;
+        let royalty_balance = &mut ctx.accounts.royalty_balance;
+        royalty_balance.collection_id = collection_id;
+        royalty_balance.bump = ctx.bumps.royalty_balance;
+
+        let mut credited = 0u64;
+        let mut credits: Vec<u64> = creators
+            .iter()
+            .map(|c| {
+                let credit = (amount as u128 * c.share as u128 / 100) as u64;
+                credited += credit;
+                credit
+            })
+            .collect();
+        credits[0] += amount - credited;
+
+        for (creator, credit) in creators.iter().zip(credits) {
+            match royalty_balance.owed.iter_mut().find(|o| o.creator == creator.address) {
+                Some(entry) => {
+                    entry.amount = entry.amount.checked_add(credit)
+                        .ok_or(UniversalNFTCoreError::InvalidAmount)?;
+                }
+                None => {
+                    require!(
+                        royalty_balance.owed.len() < MAX_ROYALTY_ENTRIES,
+                        crate::ErrorCode::TooManyRoyaltyCreators
+                    );
+                    royalty_balance.owed.push(CreatorRoyaltyOwed {
+                        creator: creator.address,
+                        amount: credit,
+                    });
+                }
+            }
+        }
+        royalty_balance.total_deposited = royalty_balance.total_deposited.checked_add(amount)
+            .ok_or(UniversalNFTCoreError::InvalidAmount)?;
+
+        emit!(RoyaltiesDeposited {
+            collection_id,
+            depositor: ctx.accounts.depositor.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Push everything owed to the creator wallets passed as remaining accounts (crank;
+    /// relayers only while the allowlist is on).
+    /// Wallets with nothing owed are skipped, so any subset of creators can be paid.
+    pub fn distribute_royalties<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DistributeRoyalties<'info>>,
+        collection_id: u64,
+    ) -> Result<()> {
+        Self::require_relayer(&ctx.accounts.relayer_config, &ctx.accounts.relayer_role)?;
+        let balance_info = ctx.accounts.royalty_balance.to_account_info();
+        for creator_info in ctx.remaining_accounts.iter() {
+            let amount = Self::take_owed_royalty(&mut ctx.accounts.royalty_balance, creator_info.key);
+            if amount == 0 {
+                continue;
+            }
+            Self::move_program_lamports(&balance_info, creator_info, amount)?;
+            ctx.accounts.royalty_balance.total_paid += amount;
+
+            emit!(RoyaltyDistributed {
+                collection_id,
+                creator: creator_info.key(),
+                amount,
+                cranked_by: ctx.accounts.cranker.key(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Pull variant of `distribute_royalties` for a single creator
+    pub fn claim_creator_royalties(
+        ctx: Context<ClaimCreatorRoyalties>,
+        collection_id: u64,
+    ) -> Result<()> {
+        let creator = ctx.accounts.creator.key();
+        let amount = Self::take_owed_royalty(&mut ctx.accounts.royalty_balance, &creator);
+        require!(amount > 0, crate::ErrorCode::NoRoyaltiesOwed);
+
+        Self::move_program_lamports(
+            &ctx.accounts.royalty_balance.to_account_info(),
+            &ctx.accounts.creator.to_account_info(),
+            amount,
+        )?;
+        ctx.accounts.royalty_balance.total_paid += amount;
+
+        emit!(RoyaltyDistributed {
+            collection_id,
+            creator,
+            amount,
+            cranked_by: creator,
+        });
+
+        Ok(())
+    }
+
+    /// Snapshot `token_ids` into the collection's Merkle root (admin only). Token ids must be
+    /// strictly ascending, each with its NFT origin and current holder's token account in
+    /// `remaining_accounts`
+    pub fn compute_collection_merkle_root<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ComputeMerkleRoot<'info>>,
+        collection_id: u64,
+        token_ids: Vec<u64>,
+    ) -> Result<()> {
+        Self::require_collection_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.collection_authority,
+            &ctx.accounts.admin,
+            AdminActionKind::ComputeMerkleRoot,
+        )?;
+        require!(
+            !token_ids.is_empty() && token_ids.len() <= MAX_MERKLE_ROOT_TOKENS,
+            crate::ErrorCode::BatchTooLarge
+        );
+        require!(
+            token_ids.windows(2).all(|pair| pair[0] < pair[1])
+                && ctx.remaining_accounts.len() == token_ids.len() * MERKLE_ROOT_ACCOUNTS,
+            crate::ErrorCode::InvalidMerkleRootAccounts
+        );
+
+        let leaves = token_ids
+            .iter()
+            .zip(ctx.remaining_accounts.chunks(MERKLE_ROOT_ACCOUNTS))
+            .map(|(&token_id, accounts)| Self::merkle_leaf(token_id, &accounts[0], &accounts[1]))
+            .collect::<Result<Vec<_>>>()?;
+        let root = Self::merkle_root(leaves);
+
+        let merkle_root = &mut ctx.accounts.merkle_root;
+        merkle_root.collection_id = collection_id;
+        merkle_root.root = root;
+        merkle_root.token_count = token_ids.len() as u64;
+        merkle_root.computed_at = Clock::get()?.unix_timestamp;
+        merkle_root.bump = ctx.bumps.merkle_root;
+
+        emit!(MerkleRootUpdated {
+            collection_id,
+            root,
+            token_count: merkle_root.token_count,
+        });
+
+        Ok(())
+    }
+
+    /// `sha256(token_id LE || mint || metadata_uri || owner)` for one NFT and the account
+    /// holding it
+    fn merkle_leaf<'info>(
+        token_id: u64,
+        origin_info: &'info AccountInfo<'info>,
+        holder_info: &'info AccountInfo<'info>,
+    ) -> Result<[u8; 32]> {
+        let nft_origin = Account::<NFTOrigin>::try_from(origin_info)?;
+        require!(nft_origin.is_current(origin_info.data_len()), crate::ErrorCode::NFTOriginNeedsMigration);
+        let (expected_origin, _) = pda::nft_origin(token_id);
+        require_keys_eq!(origin_info.key(), expected_origin, crate::ErrorCode::InvalidMerkleRootAccounts);
+
+        let holder_token_account = Account::<TokenAccount>::try_from(holder_info)?;
+        require!(
+            holder_token_account.mint == nft_origin.mint && holder_token_account.amount == 1,
+            crate::ErrorCode::InvalidMerkleRootAccounts
+        );
+
+        Ok(anchor_lang::solana_program::hash::hashv(&[
+            &token_id.to_le_bytes(),
+            nft_origin.mint.as_ref(),
+            nft_origin.metadata_uri.as_bytes(),
+            holder_token_account.owner.as_ref(),
+        ])
+        .to_bytes())
+    }
+
+    /// Binary Merkle root; each node is `sha256(left || right)` and an odd node is carried up
+    fn merkle_root(mut level: Vec<[u8; 32]>) -> [u8; 32] {
+        while level.len() > 1 {
+            level = level
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => anchor_lang::solana_program::hash::hashv(&[left, right]).to_bytes(),
+                    [single] => *single,
+                    _ => unreachable!(),
+                })
+                .collect();
+        }
+        level[0]
+    }
+
+    /// Set up a lazy reveal for a collection: its mints get a placeholder URI, and after
+    /// `reveal_time` their `num_traits` traits come from a VRF seed delivered by
+    /// `vrf_authority` (owner or the collection's delegated authority)
+    pub fn configure_lazy_reveal(
+        ctx: Context<ConfigureLazyReveal>,
+        collection_id: u64,
+        reveal_time: i64,
+        num_traits: u8,
+        uri_base: String,
+        vrf_authority: Pubkey,
+    ) -> Result<()> {
+        Self::require_collection_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.collection_authority,
+            &ctx.accounts.admin,
+            AdminActionKind::ConfigureLazyReveal,
+        )?;
+        // Whoever sets the terms can't also supply the randomness they're applied to
+        let admins = [
+            Some(ctx.accounts.admin.key()),
+            Some(ctx.accounts.program_state.owner),
+            ctx.accounts.collection_authority.as_ref().map(|record| record.authority),
+        ];
+        require!(!admins.contains(&Some(vrf_authority)), crate::ErrorCode::InvalidLazyRevealConfig);
+        let config = &mut ctx.accounts.lazy_reveal_config;
+        // Once randomness is asked for, the terms it will be applied under are fixed
+        require!(
+            (1..=MAX_REVEAL_TRAITS).contains(&num_traits)
+                && uri_base.len() <= MAX_REVEAL_URI_BASE_BYTES
+                && config.requested_at == 0,
+            crate::ErrorCode::InvalidLazyRevealConfig
+        );
+
+        config.collection_id = collection_id;
+        config.reveal_time = reveal_time;
+        config.num_traits = num_traits;
+        config.uri_base = uri_base.clone();
+        config.vrf_authority = vrf_authority;
+        config.bump = ctx.bumps.lazy_reveal_config;
+
+        emit!(LazyRevealConfigured {
+            collection_id,
+            reveal_time,
+            num_traits,
+            uri_base,
+            vrf_authority,
+        });
+
+        Ok(())
+    }
+
+    /// Ask the collection's VRF authority for the reveal seed, once `reveal_time` has
+    /// passed (crank; relayers only while the allowlist is on). Calling it again re-sends a request that went unanswered
+    pub fn trigger_reveal(ctx: Context<TriggerReveal>, collection_id: u64) -> Result<()> {
+        Self::require_relayer(&ctx.accounts.relayer_config, &ctx.accounts.relayer_role)?;
+        let now = Clock::get()?.unix_timestamp;
+        let config = &mut ctx.accounts.lazy_reveal_config;
+        require!(now >= config.reveal_time, crate::ErrorCode::RevealNotDue);
+        config.requested_at = now;
+
+        emit!(RevealRequested {
+            collection_id,
+            vrf_authority: config.vrf_authority,
+            requested_by: ctx.accounts.cranker.key(),
+            requested_at: now,
+        });
+
+        Ok(())
+    }
+
+    /// Store the VRF result as the collection's reveal seed; only its VRF authority may,
+    /// and only after a request
+    pub fn fulfill_reveal(ctx: Context<FulfillReveal>, collection_id: u64, vrf_result: [u8; 32]) -> Result<()> {
+        let config = &ctx.accounts.lazy_reveal_config;
+        require_keys_eq!(ctx.accounts.vrf_authority.key(), config.vrf_authority, crate::ErrorCode::Unauthorized);
+        require!(config.requested_at != 0, crate::ErrorCode::RevealNotRequested);
+
+        let reveal_seed = &mut ctx.accounts.reveal_seed;
+        reveal_seed.collection_id = collection_id;
+        reveal_seed.seed = vrf_result;
+        reveal_seed.fulfilled_at = Clock::get()?.unix_timestamp;
+        reveal_seed.bump = ctx.bumps.reveal_seed;
+
+        emit!(RevealFulfilled {
+            collection_id,
+            seed: vrf_result,
+        });
+
+        Ok(())
+    }
+
+    /// Replace a lazy-reveal token's placeholder with its trait URI once the collection's
+    /// seed exists (crank; relayers only while the allowlist is on)
+    pub fn update_token_uri(ctx: Context<UpdateTokenUri>, collection_id: u64, token_id: u64) -> Result<()> {
+        Self::require_relayer(&ctx.accounts.relayer_config, &ctx.accounts.relayer_role)?;
+        let nft_origin = &mut ctx.accounts.nft_origin;
+        require!(
+            nft_origin.reveal_collection_id == Some(collection_id),
+            crate::ErrorCode::NotAwaitingReveal
+        );
+
+        let uri = generate_trait_uri(&ctx.accounts.lazy_reveal_config, token_id, &ctx.accounts.reveal_seed.seed);
+        nft_origin.metadata_uri = uri.clone();
+        nft_origin.reveal_collection_id = None;
+
+        emit!(TokenUriRevealed {
+            collection_id,
+            token_id,
+            uri,
+            cranked_by: ctx.accounts.cranker.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Zero the creator's owed amount and return what it was
+    fn take_owed_royalty(royalty_balance: &mut CollectionRoyaltyBalance, creator: &Pubkey) -> u64 {
+        royalty_balance
+            .owed
+            .iter_mut()
+            .find(|o| o.creator == *creator)
+            .map(|o| std::mem::take(&mut o.amount))
+            .unwrap_or(0)
+    }
+
+    /// The token program's checks for `authority` burning one token from `token_account`,
+    /// run first so a failure surfaces as BurnFailed instead of aborting inside the CPI
+    fn require_burnable(token_account: &TokenAccount, authority: &Pubkey) -> Result<()> {
+        let delegated = token_account.delegate == COption::Some(*authority) && token_account.delegated_amount >= 1;
+        let reason = if token_account.is_frozen() {
+            "account is frozen"
+        } else if token_account.amount == 0 {
+            "account is empty"
+        } else if token_account.owner != *authority && !delegated {
+            "signer is neither owner nor delegate"
+        } else {
+            return Ok(());
+        };
+        msg!("Cannot burn from {}: {}", token_account.mint, reason);
+        err!(crate::ErrorCode::BurnFailed)
+    }
+
+    /// Move lamports out of a program-owned account (royalty balances, raffles)
+    fn move_program_lamports(from: &AccountInfo, to: &AccountInfo, amount: u64) -> Result<()> {
+        **from.try_borrow_mut_lamports()? = from.lamports().checked_sub(amount)
+            .ok_or(UniversalNFTCoreError::InvalidAmount)?;
+        **to.try_borrow_mut_lamports()? = to.lamports().checked_add(amount)
+            .ok_or(UniversalNFTCoreError::InvalidAmount)?;
+        Ok(())
+    }
+
+    /// Highest discount the user qualifies for with the supplied token account.
+    /// The account must be an SPL token account owned by the user; anything else earns no discount.
+    fn resolve_fee_discount(
+        fee_config: &FeeConfig,
+        user: &Pubkey,
+        discount_token_account: Option<&AccountInfo>,
+    ) -> Result<u8> {
+        let Some(info) = discount_token_account else {
+            return Ok(0);
+        };
+        if *info.owner != anchor_spl::token::ID {
+            return Ok(0);
+        }
+
+        let data = info.try_borrow_data()?;
+        let token_account = TokenAccount::try_deserialize(&mut &data[..])?;
+        if token_account.owner != *user {
+            return Ok(0);
+        }
+
+        Ok(fee_config.discount_tiers.iter()
+            .filter(|tier| tier.mint == token_account.mint && token_account.amount >= tier.min_balance)
+            .map(|tier| tier.discount_percent)
+            .max()
+            .unwrap_or(0))
+    }
+
+    /// Set swap router version and default V3 fee tier (admin only)
+    pub fn set_swap_router_version(
+        ctx: Context<AdminAction>,
+        version: u8,
+        default_fee_tier: u32,
+    ) -> Result<()> {
+        Self::require_admin(
+            &ctx.accounts.program_state,
+            &ctx.accounts.admin,
+            AdminActionKind::SetSwapRouterVersion,
+        )?;
+
+        require!(
+            version == SWAP_ROUTER_V2 || version == SWAP_ROUTER_V3,
+            crate::ErrorCode::InvalidSwapRouterVersion
+        );
+        require!(default_fee_tier <= MAX_SWAP_FEE_TIER, crate::ErrorCode::InvalidSwapFeeTier);
+        let previous_version = ctx.accounts.program_state.swap_router_version;
+        let previous_default_fee_tier = ctx.accounts.program_state.default_swap_fee_tier;
+        Self::require_changed(
+            &(previous_version, previous_default_fee_tier),
+            &(version, default_fee_tier),
+        )?;
+
+        ctx.accounts.program_state.swap_router_version = version;
+        ctx.accounts.program_state.default_swap_fee_tier = default_fee_tier;
+
+        emit!(SwapRouterVersionUpdated {
+            admin: ctx.accounts.admin.key(),
+            previous_version,
+            previous_default_fee_tier,
+            version,
+            default_fee_tier,
+        });
+
         Ok(())
     }
 }
@@ -481,13 +7450,13 @@ impl UniversalNFTCore for UniversalNFT {
         Ok((destination, gas_fee))
     }
 
-    fn swap_tokens(&mut self, zrc20: [u8; 20], amount: u64, destination: [u8; 20]) -> Result<u64> {
-        // Solidity equivalent: SwapHelperLib.swapTokensForExactTokens or swapExactTokensForTokens
-        // In a real implementation, integrate with Jupiter or Raydium
-        msg!("Swapping {} of token {:?} to {:?}", amount, zrc20, destination);
-        
-        // Mock 1:1 swap for now
-        Ok(amount)
+    fn swap_tokens(&mut self, zrc20: [u8; 20], amount: u64, min_amount_out: u64, destination: [u8; 20]) -> Result<u64> {
+        // Solidity equivalent: SwapHelperLib.swapExactTokensForTokens. ZRC-20 swaps run on
+        // ZetaChain's routers; Solana has none to send `encode_swap_call`'s calldata to, so
+        // fail rather than report an amount no swap produced
+        msg!("Swap of {} {:?} to {:?} (at least {}) is not supported on Solana",
+            amount, zrc20, destination, min_amount_out);
+        err!(crate::ErrorCode::SwapUnsupported)
     }
 
     fn approve_gateway(&mut self, destination: [u8; 20], amount: u64) -> Result<()> {
@@ -499,23 +7468,25 @@ impl UniversalNFTCore for UniversalNFT {
 
     fn send_gateway_message(
         &mut self,
-        destination: [u8; 20],
         amount: u64,
-        receiver: [u8; 20],
-        token_id: u64,
-        uri: String,
-        sender: [u8; 20],
+        message: &CrossChainNftMessage,
+        call_options: ZetaChainCallOptions,
     ) -> Result<()> {
         // Solidity equivalent: gateway.withdrawAndCall(...)
-        let message = self.encode_cross_chain_message(receiver, token_id, uri, sender)?;
+        let encoded = self.encode_cross_chain_message(message, &call_options)?;
         
-        msg!("Sending gateway message to {:?} with amount {}", destination, amount);
-        msg!("Message: {:?}", message);
+        msg!("Sending gateway message to {:?} with amount {}", message.destination, amount);
+        msg!("Message: {:?}", encoded);
         
         Ok(())
     }
 
-    fn call_gateway(&mut self, destination: [u8; 20], message: Vec<u8>) -> Result<()> {
+    fn call_gateway(
+        &mut self,
+        destination: [u8; 20],
+        message: Vec<u8>,
+        _call_options: ZetaChainCallOptions,
+    ) -> Result<()> {
         // Solidity equivalent: gateway.call(connected[destination], destination, message, callOptions, revertOptions)
         msg!("Calling gateway for destination {:?}", destination);
         msg!("Message length: {}", message.len());
@@ -598,16 +7569,14 @@ impl UniversalNFTCore for UniversalNFT {
 
     fn encode_cross_chain_message(
         &self,
-        receiver: [u8; 20],
-        token_id: u64,
-        uri: String,
-        sender: [u8; 20],
+        message: &CrossChainNftMessage,
+        call_options: &ZetaChainCallOptions,
     ) -> Result<Vec<u8>> {
         // Solidity equivalent: abi.encode(receiver, tokenId, uri, 0, sender)
-        Ok(UniversalNFTCoreImpl::encode_cross_chain_message(receiver, token_id, uri, sender))
+        Ok(UniversalNFTCoreImpl::encode_cross_chain_message(message, call_options))
     }
 
-    fn decode_cross_chain_message(&self, message: &[u8]) -> Result<([u8; 20], [u8; 20], u64, String, [u8; 20])> {
+    fn decode_cross_chain_message(&self, message: &[u8]) -> Result<CrossChainNftMessage> {
         // Solidity equivalent: abi.decode(message, (address, address, uint256, string, address))
         UniversalNFTCoreImpl::decode_cross_chain_message(message)
     }
@@ -626,10 +7595,11 @@ impl UniversalNFTCore for UniversalNFT {
         require!(context.sender == connected_contract, UniversalNFTCoreError::Unauthorized);
 
         // Decode message - equivalent to: abi.decode(message, (address, address, uint256, string, address))
-        let (destination, receiver, token_id, uri, sender) = self.decode_cross_chain_message(&message)?;
+        let decoded = self.decode_cross_chain_message(&message)?;
+        let CrossChainNftMessage { destination, receiver, token_id, ref uri, .. } = decoded;
 
         // If destination is ZetaChain (address 0), mint NFT directly
-        if destination.iter().all(|&x| x == 0) {
+        if decoded.is_mint_here() {
             // Equivalent to:
             // _safeMint(receiver, tokenId);
             // _setTokenURI(tokenId, uri);
@@ -642,26 +7612,20 @@ impl UniversalNFTCore for UniversalNFT {
             let (gas_zrc20, gas_fee) = self.get_gas_fee(destination)?;
             require!(destination == gas_zrc20, UniversalNFTCoreError::InvalidAddress);
 
-            // Swap tokens - equivalent to: SwapHelperLib.swapExactTokensForTokens(...)
-            let out_amount = self.swap_tokens(zrc20, amount, destination)?;
+            // Swap tokens - equivalent to: SwapHelperLib.swapExactTokensForTokens(...); the
+            // swap has to at least cover the gas fee
+            let out_amount = self.swap_tokens(zrc20, amount, gas_fee, destination)?;
 
             // Approve gateway - equivalent to: IZRC20(destination).approve(address(gateway), out)
             self.approve_gateway(destination, out_amount)?;
 
             // Send cross-chain message - equivalent to: gateway.withdrawAndCall(...)
             let remaining = out_amount.checked_sub(gas_fee).ok_or(UniversalNFTCoreError::InvalidAmount)?;
-            self.send_gateway_message(
-                destination,
-                remaining,
-                receiver,
-                token_id,
-                uri.clone(),
-                sender,
-            )?;
+            self.send_gateway_message(remaining, &decoded, self.call_options(destination))?;
         }
 
         // Emit transfer event - equivalent to: emit TokenTransferToDestination(receiver, destination, tokenId, uri);
-        self.emit_transfer_destination_event(receiver, destination, token_id, uri)?;
+        self.emit_transfer_destination_event(receiver, destination, token_id, uri.clone())?;
 
         Ok(())
     }
@@ -671,7 +7635,7 @@ impl UniversalNFTCore for UniversalNFT {
         
         // Decode revert message - equivalent to: abi.decode(context.revertMessage, (address, uint256, string, address))
         if context.revert_message.len() >= 84 { // Minimum size for our encoded data
-            if let Ok((_, receiver, token_id, uri, sender)) = self.decode_cross_chain_message(&context.revert_message) {
+            if let Ok(CrossChainNftMessage { token_id, uri, sender, .. }) = self.decode_cross_chain_message(&context.revert_message) {
                 // Re-mint the NFT to the original sender - equivalent to:
                 // _safeMint(sender, tokenId);
                 // _setTokenURI(tokenId, uri);
@@ -696,7 +7660,7 @@ impl UniversalNFTCore for UniversalNFT {
         
         // Similar to onRevert but for aborted transfers
         if context.revert_message.len() >= 84 {
-            if let Ok((_, receiver, token_id, uri, sender)) = self.decode_cross_chain_message(&context.revert_message) {
+            if let Ok(CrossChainNftMessage { token_id, uri, sender, .. }) = self.decode_cross_chain_message(&context.revert_message) {
                 // Mint NFT to original sender on ZetaChain - equivalent to:
                 // _safeMint(sender, tokenId);
                 // _setTokenURI(tokenId, uri);
@@ -718,26 +7682,681 @@ impl UniversalNFTCore for UniversalNFT {
 
 // Additional events for the Universal NFT implementation
 #[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GatewayUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub admin: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub previous_gateway: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub gateway: Pubkey,
 }
 
 #[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GasLimitUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    pub previous_gas_limit: u64,
+    pub gas_limit: u64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChainGasConfigUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub admin: Pubkey,
+    pub chain_id: u64,
     pub gas_limit: u64,
+    pub block_gas_limit: u64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChainTrustUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    pub chain_id: u64,
+    pub trust_level: u8,
+    pub provisional_hold_seconds: u32,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChainBatchSupportUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    pub chain_id: u64,
+    pub supports_batch: bool,
 }
 
 #[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ConnectedContractSet {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub admin: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
     pub zrc20: [u8; 20],
     pub contract_address: Vec<u8>,
 }
 
 #[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UniversalNFTContractUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub admin: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
+    pub previous_universal_nft_contract: [u8; 20],
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
     pub universal_nft_contract: [u8; 20],
 }
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FeeConfigUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    pub transfer_fee_lamports: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub treasury: Pubkey,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReceiptConfigUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    pub enabled: bool,
+    pub uri_base: String,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FeeDiscountTiersUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    pub tier_count: u8,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FloorPriceFeedSet {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    pub collection_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub floor_price_feed: Pubkey,
+    pub floor_price_feed_id: [u8; 32],
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RoyaltyRecipientUpdated {
+    pub collection_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub old_recipient: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub new_recipient: Pubkey,
+    pub new_share: u8,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RegistryEntrySet {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
+    pub zrc20: [u8; 20],
+    pub contract: Vec<u8>,
+    pub chain_id: u64,
+    pub flags: u8,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RegistryEntryRemoved {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
+    pub zrc20: [u8; 20],
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConfigImported {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    // config_export kind of the page: registry, chain configs or origin policies
+    pub kind: u8,
+    pub page: u8,
+    pub page_count: u8,
+    // Entries written, including `replaced` ones; unchanged entries are not counted
+    pub imported: u8,
+    pub replaced: u8,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HeartbeatIntervalUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    pub previous_min_interval_seconds: u32,
+    pub min_interval_seconds: u32,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RelayerAllowlistUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    pub previous_enabled: bool,
+    pub enabled: bool,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RelayerRoleGranted {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub relayer: Pubkey,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RelayerRoleRevoked {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub relayer: Pubkey,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChainRegistryConfigUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub observer_program: Pubkey,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KYCConfigUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub identity_program: Pubkey,
+    pub required_kyc_level: u8,
+    pub enabled: bool,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReservationFeeUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    pub previous_reservation_fee: u64,
+    pub reservation_fee: u64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NativeIdBaseUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    pub previous_native_id_base: u64,
+    pub native_id_base: u64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BridgeBackendUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    pub previous_bridge_backend: BridgeBackend,
+    pub bridge_backend: BridgeBackend,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MinUserSolReserveUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    pub previous_min_user_sol_reserve: u64,
+    pub min_user_sol_reserve: u64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AttestationKeyUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub previous_attestation_key: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub attestation_key: Pubkey,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MaxMessageBytesUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    pub previous_max_message_bytes: u32,
+    pub max_message_bytes: u32,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UriDedupWindowUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    pub previous_max_uri_mints_per_window: u64,
+    pub previous_window_seconds: i64,
+    pub max_uri_mints_per_window: u64,
+    pub window_seconds: i64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CollectionAuthorityUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    pub collection_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub authority: Pubkey,
+    pub can_mint: bool,
+    pub can_pause: bool,
+    pub can_update_metadata: bool,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SellerFeeConfigUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    pub collection_id: u64,
+    pub creator_count: u8,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RecoveryCutoffUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    pub previous_cutoff_at: i64,
+    pub cutoff_at: i64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SwapRouterVersionUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    pub previous_version: u8,
+    pub previous_default_fee_tier: u32,
+    pub version: u8,
+    pub default_fee_tier: u32,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NftAcceptanceRequirementUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    pub previous_required: bool,
+    pub required: bool,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MetadataPolicyUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    pub previous_metadata_policy: MetadataPolicy,
+    pub metadata_policy: MetadataPolicy,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InsuranceConfigUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    pub insurance_premium_lamports: u64,
+    pub insurance_payout_lamports: u64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InboundValueClaimed {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub recipient: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub destination: Pubkey,
+    pub lamports: u64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AppraisalAuthorityUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub appraiser: Pubkey,
+    pub active: bool,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AppraisalSubmitted {
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub appraiser: Pubkey,
+    pub value_lamports: u64,
+    pub value_usd_cents: u64,
+    pub expires_at: i64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TransferInsurancePurchased {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub user: Pubkey,
+    pub premium_lamports: u64,
+    pub payout_lamports: u64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TransferReverted {
+    pub nonce: u64,
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub sender: Pubkey,
+    pub insured: bool,
+    pub payout_lamports: u64,
+    // Payout the fund couldn't cover
+    pub shortfall_lamports: u64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MintFeeUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    pub previous_mint_fee_lamports: u64,
+    pub previous_creator_fee_bps: u16,
+    pub mint_fee_lamports: u64,
+    pub creator_fee_bps: u16,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InboundModeUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    pub previous_inbound_mode: InboundMode,
+    pub inbound_mode: InboundMode,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MetadataSyncThrottleUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    pub previous_max: u16,
+    pub max: u16,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LazyOriginsUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    pub previous_enabled: bool,
+    pub enabled: bool,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OriginPolicyUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    pub origin_chain: u64,
+    pub origin_contract: Vec<u8>,
+    pub royalty_bps: u16,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub royalty_recipient: Pubkey,
+    pub update_authority_mode: UpdateAuthorityMode,
+    pub is_mutable: bool,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OriginPolicyRemoved {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    pub origin_chain: u64,
+    pub origin_contract: Vec<u8>,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ZRC20GasVaultUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    pub zrc20: [u8; 20],
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub fee_destination: Pubkey,
+    pub gas_fee: u64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ZRC20GasDeposited {
+    pub zrc20: [u8; 20],
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub depositor: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ZRC20GasWithdrawn {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    pub zrc20: [u8; 20],
+    pub amount: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub dest: Pubkey,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnershipAttested {
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub holder: Pubkey,
+    pub slot: u64,
+    pub expires_at: i64,
+    // Origin chain the attestation was relayed to, if it was
+    pub relayed_to_chain: Option<u64>,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnershipAttestationRelayed {
+    pub token_id: u64,
+    pub origin_chain: u64,
+    // Exactly what was handed to the gateway; see encode_ownership_attestation
+    pub message: Vec<u8>,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CoOwnershipCreated {
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub owner_a: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub owner_b: Pubkey,
+    pub require_both_signatures: bool,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CoOwnerConsentGiven {
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub approver: Pubkey,
+    pub nonce: u64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CoOwnedTransferAuthorized {
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub initiator: Pubkey,
+    // Consent nonce used up; none when one owner's signature suffices
+    pub consent_nonce: Option<u64>,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CoOwnershipDissolved {
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub dissolved_by: Pubkey,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SemiFungibleUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    pub previous_enabled: bool,
+    pub enabled: bool,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RentalLockSet {
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub locker: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub holder: Pubkey,
+    pub locked_until: i64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RentalLockCleared {
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub locker: Pubkey,
+    pub locked_until: i64,
+    // Cleared by the transfer that found it expired rather than by its locker
+    pub expired: bool,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InboundReplayed {
+    pub message_hash: [u8; 32],
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey,
+    pub original_slot: u64,
+    // The NFTOrigin was missing rather than repaired
+    pub recreated: bool,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub replayed_by: Pubkey,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExpectedUpgradeAuthorityUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub previous_authority: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub authority: Pubkey,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IntegrityMismatch {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub upgrade_authority: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub expected_upgrade_authority: Pubkey,
+    pub last_deploy_slot: u64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ForcedRecallPolicyUpdated {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    pub previous_enabled: bool,
+    pub enabled: bool,
+    pub previous_dispute_window_seconds: i64,
+    pub dispute_window_seconds: i64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ForcedRecallRequested {
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey,
+    pub origin_chain: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
+    pub sender: [u8; 20],
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
+    pub return_receiver: [u8; 20],
+    pub dispute_ends_at: i64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ForcedRecallContested {
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub holder: Pubkey,
+    pub contested_at: i64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ForcedRecallExecuted {
+    pub token_id: u64,
+    // Owner of the token account the unit was frozen in
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub holder: Pubkey,
+    pub origin_chain: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
+    pub return_receiver: [u8; 20],
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub executed_by: Pubkey,
+}
@@ -1,6 +1,13 @@
+use std::borrow::Cow;
+
 use anchor_lang::prelude::*;
 
-#[error_code]
+/// First error number of `UniversalNFTCoreError`; `ErrorCode` starts at ERROR_CODE_OFFSET
+/// (6000), so core errors start higher to keep the two ranges apart
+pub const CORE_ERROR_CODE_OFFSET: u32 = 7000;
+
+// The offset must stay a literal here; keep it equal to CORE_ERROR_CODE_OFFSET
+#[error_code(offset = 7000)]
 pub enum UniversalNFTCoreError {
     #[msg("Invalid cross-chain message")]
     InvalidCrossChainMessage,
@@ -24,6 +31,309 @@ pub enum UniversalNFTCoreError {
     InvalidAmount,
     #[msg("Gateway call failed")]
     GatewayCallFailed,
+    #[msg("Bridge backend is not supported yet")]
+    BridgeBackendUnsupported,
+}
+
+/// This build's `CARGO_PKG_VERSION` as [major, minor, patch]
+pub const PROGRAM_VERSION: [u16; 3] = parse_version(env!("CARGO_PKG_VERSION"));
+
+/// Parse "major.minor.patch", stopping at any pre-release or build suffix
+const fn parse_version(version: &str) -> [u16; 3] {
+    let bytes = version.as_bytes();
+    let mut parts = [0u16; 3];
+    let mut part = 0;
+    let mut i = 0;
+    while i < bytes.len() && part < 3 {
+        match bytes[i] {
+            b'.' => part += 1,
+            digit @ b'0'..=b'9' => parts[part] = parts[part] * 10 + (digit - b'0') as u16,
+            _ => break,
+        }
+        i += 1;
+    }
+    parts
+}
+
+/// Layout versions of a deployed instance, returned by `get_schema` for indexers
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SchemaInfo {
+    pub program_version: [u16; 3],
+    pub program_state_version: u8,
+    pub nft_origin_version: u8,
+    pub event_schema: u8,
+    pub message_versions_supported: Vec<u8>,
+}
+
+/// Who can upgrade the deployed program, returned by `verify_integrity`. `passed` when the
+/// program is immutable or its upgrade authority is the announced one
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IntegrityReport {
+    pub passed: bool,
+    // No upgrade authority; `upgrade_authority` is then the default key
+    pub immutable: bool,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub upgrade_authority: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub expected_upgrade_authority: Pubkey,
+    pub last_deploy_slot: u64,
+}
+
+/// Version byte that opens the call options trailer of a V2 message
+pub const MESSAGE_FORMAT_V2: u8 = 2;
+/// Tag of the trailer carrying an NFT's metadata encryption key, after any call options
+pub const MESSAGE_TRAILER_ENCRYPTION_KEY: u8 = 3;
+/// Tag of the trailer carrying the sender's burn commitment, after any encryption key
+pub const MESSAGE_TRAILER_BURN_COMMITMENT: u8 = 4;
+/// Tag of the trailer carrying a UTF-8 sender note for the destination to display, after
+/// any burn commitment: the tag, one length byte, then the note
+pub const MESSAGE_TRAILER_NOTE: u8 = 5;
+pub const MAX_MESSAGE_NOTE_BYTES: usize = 128;
+/// Tag of the trailer carrying where the NFT was first minted, after any note: the tag,
+/// origin_chain (8, big-endian), origin_token_id (8, big-endian), one length byte, then the
+/// origin contract
+pub const MESSAGE_TRAILER_PROVENANCE: u8 = 6;
+pub const MAX_PROVENANCE_CONTRACT_BYTES: usize = 64;
+/// Tag of the trailer carrying how many units of a semi-fungible token travel, after any
+/// provenance: the tag, then the amount (8, big-endian). NFTs leave it out and are one unit
+pub const MESSAGE_TRAILER_AMOUNT: u8 = 7;
+/// Tag of the trailer carrying the delivery priority the source contract set, after any
+/// amount: the tag, then one priority byte. Ordering metadata for relayers only; messages
+/// without it have priority zero
+pub const MESSAGE_TRAILER_PRIORITY: u8 = 8;
+/// Tag of the trailer carrying lamports the gateway sent along with the NFT, such as sale
+/// proceeds, after any priority: the tag, then the lamports (8, big-endian). The delivery
+/// pays them to the recipient; messages without it carry no value
+pub const MESSAGE_TRAILER_VALUE: u8 = 9;
+/// URI of a message whose full URI didn't fit: this prefix, then the hex keccak256 of the
+/// URI. The full URI stays in the token's NFTOrigin on Solana for resolvers to look up
+pub const URI_HASH_ONLY_PREFIX: &str = "keccak256:";
+
+/// One NFT in transit between chains, as carried by the ABI body of a message
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CrossChainNftMessage {
+    /// Zero when the NFT is minted where the message lands; otherwise the ZRC-20 of
+    /// the chain it is forwarded to. Not part of the ABI body
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
+    pub destination: [u8; 20],
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
+    pub receiver: [u8; 20],
+    pub token_id: u64,
+    pub uri: String,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
+    pub sender: [u8; 20],
+    /// MESSAGE_VERSION_* the message arrived as; V1 for messages built here
+    pub version: u8,
+}
+
+impl CrossChainNftMessage {
+    /// A message that mints the NFT on the chain it is delivered to
+    pub fn mint_here(receiver: [u8; 20], token_id: u64, uri: String, sender: [u8; 20]) -> Self {
+        Self::forward([0u8; 20], receiver, token_id, uri, sender)
+    }
+
+    /// A message that the universal contract forwards to the chain of `destination`
+    pub fn forward(
+        destination: [u8; 20],
+        receiver: [u8; 20],
+        token_id: u64,
+        uri: String,
+        sender: [u8; 20],
+    ) -> Self {
+        Self {
+            destination,
+            receiver,
+            token_id,
+            uri,
+            sender,
+            version: MESSAGE_VERSION_V1,
+        }
+    }
+
+    /// Whether the NFT is minted where the message lands rather than forwarded
+    pub fn is_mint_here(&self) -> bool {
+        self.destination == [0u8; 20]
+    }
+}
+
+/// Where an NFT was first minted, as carried by the provenance trailer. The token id there
+/// can differ from the universal token id ZetaChain assigned it in transit
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Provenance {
+    pub origin_chain: u64,
+    pub origin_token_id: u64,
+    pub origin_contract: Vec<u8>,
+}
+
+/// Optional trailers of an outbound message, written in this order after the call options
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OutboundTrailers<'a> {
+    pub metadata_encryption_key: Option<[u8; 32]>,
+    pub burn_commitment: Option<[u8; 32]>,
+    pub note: Option<&'a [u8]>,
+    pub provenance: Option<&'a Provenance>,
+    pub amount: Option<u64>,
+}
+
+/// One NFT of a `BatchTransfer`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BatchTransferEntry {
+    pub token_id: u64,
+    pub uri: String,
+}
+
+/// NFTs sent to one receiver in a single gateway call; see `encode_batch_outbound_message`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BatchTransfer {
+    pub entries: Vec<BatchTransferEntry>,
+}
+
+/// Leading version byte of an inbound message
+pub const MESSAGE_VERSION_V1: u8 = 0x01;
+pub const MESSAGE_VERSION_V2: u8 = 0x02;
+pub const MESSAGE_VERSION_BATCH: u8 = 0xFF;
+/// Version byte of an ownership attestation, after the destination word. Outbound only:
+/// `MessageDecoder` rejects it like any other unknown version
+pub const MESSAGE_VERSION_OWNERSHIP_ATTESTATION: u8 = 0x03;
+/// Version byte of a forced recall. Inbound through `receive_forced_recall` only:
+/// `MessageDecoder` rejects it like any other unknown version
+pub const MESSAGE_VERSION_FORCED_RECALL: u8 = 0x04;
+
+/// A holder's ownership of an NFT on Solana, as checked by `attest_ownership`, for
+/// marketplaces on the origin chain to show without indexing Solana
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnershipAttestationMessage {
+    pub token_id: u64,
+    pub origin_chain: u64,
+    pub origin_token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub holder: Pubkey,
+    pub slot: u64,
+    pub expires_at: i64,
+}
+
+/// An origin-chain contract's command to send `token_id` back to `return_receiver` on its chain
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ForcedRecallMessage {
+    pub token_id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
+    pub return_receiver: [u8; 20],
+}
+
+/// An inbound message after version dispatch
+pub enum DecodedMessage {
+    V1(CrossChainNftMessage),
+    /// V2 messages name the chain they are addressed to
+    V2 {
+        destination_chain_id: u64,
+        message: CrossChainNftMessage,
+    },
+    Batch(Vec<CrossChainNftMessage>),
+}
+
+impl DecodedMessage {
+    /// The single message carried by a V1 or V2 payload
+    pub fn single(&self) -> Option<&CrossChainNftMessage> {
+        match self {
+            DecodedMessage::V1(message) | DecodedMessage::V2 { message, .. } => Some(message),
+            DecodedMessage::Batch(_) => None,
+        }
+    }
+}
+
+/// Dispatches inbound messages to the decoder for their version byte
+pub struct MessageDecoder;
+
+impl MessageDecoder {
+    /// `0x01` V1 body, `0x02` destination chain id (u64 BE) + V1 body, `0xFF` batch
+    pub fn decode(message: &[u8]) -> Result<DecodedMessage> {
+        let (&version, body) = message.split_first()
+            .ok_or(UniversalNFTCoreError::InvalidMessageFormat)?;
+
+        match version {
+            MESSAGE_VERSION_V1 => Ok(DecodedMessage::V1(
+                UniversalNFTCoreImpl::decode_cross_chain_message(body)?,
+            )),
+            MESSAGE_VERSION_V2 => {
+                require!(body.len() >= 8, UniversalNFTCoreError::InvalidMessageFormat);
+                let (chain_id, body) = body.split_at(8);
+                Ok(DecodedMessage::V2 {
+                    destination_chain_id: u64::from_be_bytes(chain_id.try_into().unwrap()),
+                    message: CrossChainNftMessage {
+                        version: MESSAGE_VERSION_V2,
+                        ..UniversalNFTCoreImpl::decode_cross_chain_message(body)?
+                    },
+                })
+            }
+            MESSAGE_VERSION_BATCH => Ok(DecodedMessage::Batch(
+                UniversalNFTCoreImpl::decode_batch_cross_chain_message(body)?,
+            )),
+            _ => Err(crate::ErrorCode::UnknownMessageVersion.into()),
+        }
+    }
+
+    /// The single NFT of a V1 or V2 message addressed to Solana
+    pub fn decode_single(message: &[u8]) -> Result<CrossChainNftMessage> {
+        match Self::decode(message)? {
+            DecodedMessage::V1(decoded) => Ok(decoded),
+            DecodedMessage::V2 { destination_chain_id, message } => {
+                require!(
+                    crate::ChainId(destination_chain_id).is_solana(),
+                    crate::ErrorCode::InvalidCrossChainMessage
+                );
+                Ok(message)
+            }
+            // Each batched NFT needs its own accounts; batches go through receive_batch_transfer
+            DecodedMessage::Batch(_) => Err(crate::ErrorCode::InvalidCrossChainMessage.into()),
+        }
+    }
+
+    /// ABI bodies of a batch message's entries, in order, for reading their trailers
+    pub fn batch_bodies(message: &[u8]) -> Result<Vec<&[u8]>> {
+        match message.split_first() {
+            Some((&MESSAGE_VERSION_BATCH, body)) => UniversalNFTCoreImpl::split_batch_body(body),
+            _ => Err(crate::ErrorCode::InvalidCrossChainMessage.into()),
+        }
+    }
+
+    /// Token id of a V1 or V2 message, read from the ABI body without decoding the rest
+    pub fn token_id(message: &[u8]) -> Result<u64> {
+        Ok(UniversalNFTCoreImpl::body_fields(Self::single_body(message)?)?.token_id)
+    }
+
+    /// Solana wallet a V1 or V2 message delivers to: its whole receiver word, where an EVM
+    /// receiver would be left-padded
+    pub fn recipient(message: &[u8]) -> Result<Pubkey> {
+        Self::body_recipient(Self::single_body(message)?)
+    }
+
+    /// `recipient` of an ABI body with no version byte, as each entry of a batch message is
+    pub fn body_recipient(body: &[u8]) -> Result<Pubkey> {
+        let word = body.get(..32)
+            .ok_or(UniversalNFTCoreError::InvalidMessageFormat)?;
+        Ok(Pubkey::new_from_array(word.try_into().unwrap()))
+    }
+
+    /// ABI body of a V1 or V2 message, past the version byte and any destination chain id
+    pub fn single_body(message: &[u8]) -> Result<&[u8]> {
+        let (&version, body) = message.split_first()
+            .ok_or(UniversalNFTCoreError::InvalidMessageFormat)?;
+
+        match version {
+            MESSAGE_VERSION_V1 => Ok(body),
+            MESSAGE_VERSION_V2 => body.get(8..)
+                .ok_or(UniversalNFTCoreError::InvalidMessageFormat.into()),
+            _ => Err(UniversalNFTCoreError::InvalidMessageFormat.into()),
+        }
+    }
 }
 
 pub trait UniversalNFTCore {
@@ -44,7 +354,10 @@ pub trait UniversalNFTCore {
     /// Only callable by contract owner
     /// @param gas_limit New gas limit value
     fn set_gas_limit(&mut self, gas_limit: u64) -> Result<()> {
-        require!(gas_limit > 0, UniversalNFTCoreError::InvalidGasLimit);
+        require!(
+            (crate::MIN_GAS_LIMIT..=crate::MAX_GAS_LIMIT).contains(&gas_limit),
+            UniversalNFTCoreError::InvalidGasLimit
+        );
         Ok(())
     }
 
@@ -72,25 +385,42 @@ pub trait UniversalNFTCore {
     /// Get gas fee for destination chain
     fn get_gas_fee(&self, destination: [u8; 20]) -> Result<([u8; 20], u64)>;
 
-    /// Swap tokens for cross-chain operations
-    fn swap_tokens(&mut self, zrc20: [u8; 20], amount: u64, destination: [u8; 20]) -> Result<u64>;
+    /// Swap router version and default fee tier used by `swap_tokens`
+    fn swap_router_config(&self) -> (u8, u32) {
+        (crate::SWAP_ROUTER_V2, crate::DEFAULT_SWAP_FEE_TIER)
+    }
+
+    /// Swap `amount` of `zrc20` into `destination`, receiving at least `min_amount_out`
+    fn swap_tokens(&mut self, zrc20: [u8; 20], amount: u64, min_amount_out: u64, destination: [u8; 20]) -> Result<u64>;
 
     /// Approve gateway for token transfer
     fn approve_gateway(&mut self, destination: [u8; 20], amount: u64) -> Result<()>;
 
+    /// Call options used for messages sent towards `destination`
+    fn call_options(&self, _destination: [u8; 20]) -> ZetaChainCallOptions {
+        ZetaChainCallOptions::default()
+    }
+
+    /// Largest message `transfer_cross_chain` sends before falling back to a hash-only URI
+    fn max_message_bytes(&self) -> usize {
+        crate::DEFAULT_MAX_MESSAGE_BYTES as usize
+    }
+
     /// Send gateway message
     fn send_gateway_message(
         &mut self,
-        destination: [u8; 20],
         amount: u64,
-        receiver: [u8; 20],
-        token_id: u64,
-        uri: String,
-        sender: [u8; 20],
+        message: &CrossChainNftMessage,
+        call_options: ZetaChainCallOptions,
     ) -> Result<()>;
 
     /// Call gateway for cross-chain operations
-    fn call_gateway(&mut self, destination: [u8; 20], message: Vec<u8>) -> Result<()>;
+    fn call_gateway(
+        &mut self,
+        destination: [u8; 20],
+        message: Vec<u8>,
+        call_options: ZetaChainCallOptions,
+    ) -> Result<()>;
 
     /// Emit transfer event
     fn emit_transfer_event(
@@ -121,14 +451,12 @@ pub trait UniversalNFTCore {
     /// Encode cross-chain message
     fn encode_cross_chain_message(
         &self,
-        receiver: [u8; 20],
-        token_id: u64,
-        uri: String,
-        sender: [u8; 20],
+        message: &CrossChainNftMessage,
+        call_options: &ZetaChainCallOptions,
     ) -> Result<Vec<u8>>;
 
     /// Decode cross-chain message
-    fn decode_cross_chain_message(&self, message: &[u8]) -> Result<([u8; 20], [u8; 20], u64, String, [u8; 20])>;
+    fn decode_cross_chain_message(&self, message: &[u8]) -> Result<CrossChainNftMessage>;
 
     /// Transfer NFT cross-chain
     /// @notice Transfers an NFT to another chain through the ZetaChain gateway
@@ -148,13 +476,20 @@ pub trait UniversalNFTCore {
 
         // Get URI and encode message
         let uri = self.token_uri(token_id)?;
-        let message = self.encode_cross_chain_message(receiver, token_id, uri.clone(), [0u8; 20])?;
+        let call_options = self.call_options(destination);
+        let mut nft = CrossChainNftMessage::forward(destination, receiver, token_id, uri.clone(), [0u8; 20]);
+        let mut message = self.encode_cross_chain_message(&nft, &call_options)?;
+        if message.len() > self.max_message_bytes() {
+            nft.uri = UniversalNFTCoreImpl::uri_hash_only(&nft.uri);
+            message = self.encode_cross_chain_message(&nft, &call_options)?;
+            require!(message.len() <= self.max_message_bytes(), crate::ErrorCode::MessageTooLarge);
+        }
 
         // Burn the NFT
         self.burn(token_id)?;
 
         // Call gateway with message
-        self.call_gateway(destination, message)?;
+        self.call_gateway(destination, message, call_options)?;
 
         // Emit transfer event
         self.emit_transfer_event(receiver, destination, token_id, uri)?;
@@ -192,8 +527,11 @@ pub trait UniversalNFTCore {
 
 /// Cross-chain message context
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CrossChainMessageContext {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
     pub sender: [u8; 20],
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
     pub destination: [u8; 20],
     pub gas_limit: u64,
     pub gas_price: u64,
@@ -201,7 +539,9 @@ pub struct CrossChainMessageContext {
 
 /// Revert context for failed cross-chain calls
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RevertContext {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
     pub asset: [u8; 20],
     pub amount: u64,
     pub revert_message: Vec<u8>,
@@ -209,105 +549,714 @@ pub struct RevertContext {
 
 /// Abort context for failed cross-chain calls
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AbortContext {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
     pub outgoing: [u8; 20],
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
     pub asset: [u8; 20],
     pub amount: u64,
     pub revert_message: Vec<u8>,
 }
 
+/// Gas and revert settings for a gateway call
+/// Solidity equivalent: CallOptions { gasLimit, isArbitraryCall } plus RevertOptions
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ZetaChainCallOptions {
+    pub gas_limit: u64,
+    pub is_arbitrary_call: bool,
+    pub on_revert_gas_limit: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
+    pub revert_address: [u8; 20],
+}
+
+impl Default for ZetaChainCallOptions {
+    fn default() -> Self {
+        Self {
+            gas_limit: 1_000_000,
+            is_arbitrary_call: false,
+            on_revert_gas_limit: 0,
+            revert_address: [0u8; 20],
+        }
+    }
+}
+
+/// Diagnostic gateway ping; the remote side echoes it back followed by its own address
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PingMessage {
+    pub timestamp: i64,
+    pub nonce: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub sender: Pubkey,
+}
+
+/// Liveness probe for a connected contract; the contract echoes it back in its pong
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ContractPingMessage {
+    pub timestamp: i64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub solana_id: Pubkey,
+}
+
+/// Asks the connected ZetaChain contract for a random number to settle a raffle
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RandomnessRequest {
+    pub raffle_id: u64,
+    pub ticket_count: u32,
+    pub requested_at: i64,
+}
+
 /// Connected contract mapping
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ConnectedContract {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex20"))]
     pub zrc20: [u8; 20],
     pub contract_address: Vec<u8>,
 }
 
+/// Moves NFT messages between Solana and other chains. `ProgramState::bridge_backend`
+/// picks the implementation through `BridgeBackend::bridge`
+pub trait CrossChainNFTBridge<'info> {
+    /// Hand the encoded outbound message for `token_id` to the backend's gateway
+    fn send_nft(
+        &self,
+        destination_chain: u64,
+        token_id: u64,
+        message: Vec<u8>,
+        gateway_program: AccountInfo<'info>,
+    ) -> Result<()>;
+
+    /// Token id, metadata URI and sender of an inbound message from `source_chain`
+    fn receive_nft(&self, message: Vec<u8>, source_chain: u64) -> Result<(u64, String, [u8; 20])>;
+}
+
+/// ZetaChain's gateway: every outbound message calls the universal NFT contract, which
+/// routes it on to `destination_chain`
+pub struct ZetaChainBridge<'info> {
+    pub signer: AccountInfo<'info>,
+    pub universal_nft_contract: [u8; 20],
+    pub call_options: ZetaChainCallOptions,
+}
+
+impl<'info> CrossChainNFTBridge<'info> for ZetaChainBridge<'info> {
+    fn send_nft(
+        &self,
+        _destination_chain: u64,
+        _token_id: u64,
+        message: Vec<u8>,
+        gateway_program: AccountInfo<'info>,
+    ) -> Result<()> {
+        // The destination travels inside the message; the gateway only sees the contract
+        UniversalNFTCoreImpl::call_gateway(
+            gateway_program,
+            self.signer.clone(),
+            self.universal_nft_contract,
+            message,
+            &self.call_options,
+        ).map_err(crate::cpi_failed(UniversalNFTCoreError::GatewayCallFailed))
+    }
+
+    fn receive_nft(&self, message: Vec<u8>, _source_chain: u64) -> Result<(u64, String, [u8; 20])> {
+        let CrossChainNftMessage { token_id, uri, sender, .. } = MessageDecoder::decode_single(&message)?;
+        Ok((token_id, uri, sender))
+    }
+}
+
+/// Stand-in for a Wormhole backend; fails every call until one is integrated
+pub struct WormholeBridge;
+
+impl<'info> CrossChainNFTBridge<'info> for WormholeBridge {
+    fn send_nft(
+        &self,
+        _destination_chain: u64,
+        _token_id: u64,
+        _message: Vec<u8>,
+        _gateway_program: AccountInfo<'info>,
+    ) -> Result<()> {
+        err!(UniversalNFTCoreError::BridgeBackendUnsupported)
+    }
+
+    fn receive_nft(&self, _message: Vec<u8>, _source_chain: u64) -> Result<(u64, String, [u8; 20])> {
+        err!(UniversalNFTCoreError::BridgeBackendUnsupported)
+    }
+}
+
+impl crate::BridgeBackend {
+    /// This backend's bridge; `signer` pays for and signs outbound gateway calls
+    pub fn bridge<'info>(
+        self,
+        signer: AccountInfo<'info>,
+        universal_nft_contract: [u8; 20],
+        call_options: ZetaChainCallOptions,
+    ) -> Box<dyn CrossChainNFTBridge<'info> + 'info> {
+        match self {
+            crate::BridgeBackend::ZetaChain => Box::new(ZetaChainBridge {
+                signer,
+                universal_nft_contract,
+                call_options,
+            }),
+            crate::BridgeBackend::Wormhole => Box::new(WormholeBridge),
+        }
+    }
+}
+
 /// Core implementation for Universal NFT functionality
 pub struct UniversalNFTCoreImpl;
 
+/// Where an ABI body keeps its fields, whichever layout it uses
+struct BodyFields {
+    token_id: u64,
+    sender: [u8; 20],
+    uri: std::ops::Range<usize>,
+    // Past the uri's padding, where any trailer starts
+    end: usize,
+}
+
+/// The parts of a message trailer, each with its leading tag
+struct TrailerParts<'a> {
+    call_options: &'a [u8],
+    key: &'a [u8],
+    commitment: &'a [u8],
+    note: &'a [u8],
+    provenance: &'a [u8],
+    amount: &'a [u8],
+    priority: &'a [u8],
+    value: &'a [u8],
+}
+
 impl UniversalNFTCoreImpl {
-    /// Encode message for cross-chain transfer
+    /// Encode a message for the ZetaChain universal contract, whose `onCall` decodes
+    /// `abi.encode(address destination, address receiver, uint256 tokenId, string uri,
+    /// address sender)`, followed by the V2 call options trailer. A zero destination mints on
+    /// ZetaChain; otherwise it is the ZRC-20 of the chain the contract forwards the NFT to
     pub fn encode_cross_chain_message(
-        receiver: [u8; 20],
-        token_id: u64,
-        uri: String,
-        sender: [u8; 20],
+        nft: &CrossChainNftMessage,
+        call_options: &ZetaChainCallOptions,
     ) -> Vec<u8> {
         let mut message = Vec::new();
-        
-        // receiver (address)
-        message.extend_from_slice(&[0u8; 12]);
-        message.extend_from_slice(&receiver);
-        
-        // tokenId (uint256)
-        let mut token_id_bytes = [0u8; 32];
-        token_id_bytes[24..32].copy_from_slice(&token_id.to_be_bytes());
-        message.extend_from_slice(&token_id_bytes);
-        
-        // uri offset (uint256)
-        let offset = 96u64;
-        message.extend_from_slice(&offset.to_be_bytes());
-        
-        // sender (address)
-        message.extend_from_slice(&[0u8; 12]);
-        message.extend_from_slice(&sender);
-        
-        // uri length and data
-        let uri_len = uri.len() as u64;
-        message.extend_from_slice(&uri_len.to_be_bytes());
-        message.extend_from_slice(&uri.as_bytes());
-        
-        // padding
-        let padding = (32 - (uri.len() % 32)) % 32;
-        message.extend_from_slice(&vec![0u8; padding]);
-        
+        message.extend_from_slice(&Self::abi_address(nft.destination));
+        message.extend_from_slice(&Self::abi_address(nft.receiver));
+        message.extend_from_slice(&Self::abi_uint(nft.token_id));
+        message.extend_from_slice(&Self::abi_uint(5 * 32));
+        message.extend_from_slice(&Self::abi_address(nft.sender));
+        Self::push_abi_string(&mut message, &nft.uri);
+        Self::push_call_options(&mut message, call_options);
         message
     }
 
-    /// Decode cross-chain message
-    pub fn decode_cross_chain_message(message: &[u8]) -> Result<([u8; 20], [u8; 20], u64, String, [u8; 20])> {
-        if message.len() < 96 {
-            return Err(UniversalNFTCoreError::InvalidMessageFormat.into());
+    /// The ABI body the ZetaChain universal contract sends on to a connected chain, and
+    /// `decode_cross_chain_message` reads: `abi.encode(address receiver, uint256 tokenId,
+    /// string uri, uint256 gasAmount, address sender)`. `destination` is not part of it
+    pub fn encode_inbound_body(nft: &CrossChainNftMessage, gas_amount: u64) -> Vec<u8> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&Self::abi_address(nft.receiver));
+        body.extend_from_slice(&Self::abi_uint(nft.token_id));
+        body.extend_from_slice(&Self::abi_uint(5 * 32));
+        body.extend_from_slice(&Self::abi_uint(gas_amount));
+        body.extend_from_slice(&Self::abi_address(nft.sender));
+        Self::push_abi_string(&mut body, &nft.uri);
+        body
+    }
+
+    /// The tail of an ABI string: its length word, then its bytes zero-padded to a whole word
+    fn push_abi_string(message: &mut Vec<u8>, value: &str) {
+        message.extend_from_slice(&Self::abi_uint(value.len() as u64));
+        message.extend_from_slice(value.as_bytes());
+        message.resize(message.len() + (32 - value.len() % 32) % 32, 0);
+    }
+
+    /// V2 trailer: call options after the padded uri, ignored by V1 decoders
+    fn push_call_options(message: &mut Vec<u8>, call_options: &ZetaChainCallOptions) {
+        message.push(MESSAGE_FORMAT_V2);
+        message.extend_from_slice(&call_options.gas_limit.to_be_bytes());
+        message.push(call_options.is_arbitrary_call as u8);
+        message.extend_from_slice(&call_options.on_revert_gas_limit.to_be_bytes());
+        message.extend_from_slice(&call_options.revert_address);
+    }
+
+    /// Outbound message for the ZetaChain universal contract: `encode_cross_chain_message`,
+    /// then any further trailers
+    pub fn encode_outbound_message(
+        nft: &CrossChainNftMessage,
+        call_options: &ZetaChainCallOptions,
+        trailers: &OutboundTrailers,
+    ) -> Vec<u8> {
+        let mut message = Self::encode_cross_chain_message(nft, call_options);
+        if let Some(key) = trailers.metadata_encryption_key {
+            message.push(MESSAGE_TRAILER_ENCRYPTION_KEY);
+            message.extend_from_slice(&key);
+        }
+        if let Some(commitment) = trailers.burn_commitment {
+            message.push(MESSAGE_TRAILER_BURN_COMMITMENT);
+            message.extend_from_slice(&commitment);
         }
+        // Callers check the note against MAX_MESSAGE_NOTE_BYTES, so its length fits a byte
+        if let Some(note) = trailers.note {
+            message.push(MESSAGE_TRAILER_NOTE);
+            message.push(note.len() as u8);
+            message.extend_from_slice(note);
+        }
+        // Contracts come from NFTOrigin or a decoded trailer, so they fit a length byte too
+        if let Some(provenance) = trailers.provenance {
+            message.push(MESSAGE_TRAILER_PROVENANCE);
+            message.extend_from_slice(&provenance.origin_chain.to_be_bytes());
+            message.extend_from_slice(&provenance.origin_token_id.to_be_bytes());
+            message.push(provenance.origin_contract.len() as u8);
+            message.extend_from_slice(&provenance.origin_contract);
+        }
+        if let Some(amount) = trailers.amount {
+            message.push(MESSAGE_TRAILER_AMOUNT);
+            message.extend_from_slice(&amount.to_be_bytes());
+        }
+        message
+    }
+
+    /// `encode_outbound_message`, kept within `max_message_bytes` by sending the URI in
+    /// hash-only form when the full one doesn't fit. The trailers count toward the limit and
+    /// are never shortened. Returns the message and whether the URI was hashed; fails with
+    /// MessageTooLarge if even the hash-only form doesn't fit
+    pub fn encode_bounded_outbound_message(
+        nft: &CrossChainNftMessage,
+        call_options: &ZetaChainCallOptions,
+        trailers: &OutboundTrailers,
+        max_message_bytes: usize,
+    ) -> Result<(Vec<u8>, bool)> {
+        let message = Self::encode_outbound_message(nft, call_options, trailers);
+        if message.len() <= max_message_bytes {
+            return Ok((message, false));
+        }
+
+        let hashed = CrossChainNftMessage {
+            uri: Self::uri_hash_only(&nft.uri),
+            ..nft.clone()
+        };
+        let message = Self::encode_outbound_message(&hashed, call_options, trailers);
+        require!(message.len() <= max_message_bytes, crate::ErrorCode::MessageTooLarge);
+        Ok((message, true))
+    }
 
-        let receiver = message[12..32].try_into()
+    /// Outbound batch for the ZetaChain universal contract: the destination word, then
+    /// MESSAGE_VERSION_BATCH and the body `decode_batch_cross_chain_message` reads back. Each
+    /// entry is the inbound body the contract forwards to the destination chain, carrying the
+    /// gas limit as its gas amount, then the call options trailer. All go to `receiver`
+    pub fn encode_batch_outbound_message(
+        destination: [u8; 20],
+        receiver: [u8; 20],
+        sender: [u8; 20],
+        batch: &BatchTransfer,
+        call_options: &ZetaChainCallOptions,
+    ) -> Result<Vec<u8>> {
+        let count = u8::try_from(batch.entries.len())
             .map_err(|_| UniversalNFTCoreError::InvalidMessageFormat)?;
-        
-        let token_id = u64::from_be_bytes(
-            message[32..40].try_into()
-                .map_err(|_| UniversalNFTCoreError::InvalidMessageFormat)?
+        let mut message = Self::abi_address(destination).to_vec();
+        message.push(MESSAGE_VERSION_BATCH);
+        message.push(count);
+        for entry in &batch.entries {
+            let mut item = Self::encode_inbound_body(
+                &CrossChainNftMessage::forward(destination, receiver, entry.token_id, entry.uri.clone(), sender),
+                call_options.gas_limit,
+            );
+            Self::push_call_options(&mut item, call_options);
+            message.extend_from_slice(&(item.len() as u32).to_be_bytes());
+            message.extend(item);
+        }
+        Ok(message)
+    }
+
+    /// Outbound ownership attestation for the ZetaChain universal contract: the destination
+    /// word, MESSAGE_VERSION_OWNERSHIP_ATTESTATION, then six ABI words
+    ///
+    /// `tokenId || originChain || originTokenId || holder (bytes32) || slot || expiresAt`
+    pub fn encode_ownership_attestation(destination: [u8; 20], attestation: &OwnershipAttestationMessage) -> Vec<u8> {
+        let mut message = Self::abi_address(destination).to_vec();
+        message.push(MESSAGE_VERSION_OWNERSHIP_ATTESTATION);
+        message.extend_from_slice(&Self::abi_uint(attestation.token_id));
+        message.extend_from_slice(&Self::abi_uint(attestation.origin_chain));
+        message.extend_from_slice(&Self::abi_uint(attestation.origin_token_id));
+        message.extend_from_slice(attestation.holder.as_ref());
+        message.extend_from_slice(&Self::abi_uint(attestation.slot));
+        message.extend_from_slice(&Self::abi_uint(attestation.expires_at.max(0) as u64));
+        message
+    }
+
+    /// MESSAGE_VERSION_FORCED_RECALL, then two ABI words: `tokenId || returnReceiver`.
+    /// Words with bits beyond a u64 or an address are refused rather than truncated
+    pub fn decode_forced_recall(message: &[u8]) -> Result<ForcedRecallMessage> {
+        let (&version, body) = message.split_first()
+            .ok_or(UniversalNFTCoreError::InvalidMessageFormat)?;
+        require!(
+            version == MESSAGE_VERSION_FORCED_RECALL && body.len() == 64,
+            UniversalNFTCoreError::InvalidMessageFormat
         );
-        
-        let uri_offset = u64::from_be_bytes(
-            message[64..72].try_into()
-                .map_err(|_| UniversalNFTCoreError::InvalidMessageFormat)?
-        ) as usize;
-        
-        if message.len() < uri_offset + 8 {
-            return Err(UniversalNFTCoreError::InvalidMessageFormat.into());
+        let (token_word, receiver_word) = body.split_at(32);
+        require!(
+            token_word[..24].iter().chain(&receiver_word[..12]).all(|&byte| byte == 0),
+            UniversalNFTCoreError::InvalidMessageFormat
+        );
+        Ok(ForcedRecallMessage {
+            token_id: u64::from_be_bytes(token_word[24..].try_into().unwrap()),
+            return_receiver: receiver_word[12..].try_into().unwrap(),
+        })
+    }
+
+    /// Hash-only form of `uri`: URI_HASH_ONLY_PREFIX and the hex keccak256 of the URI
+    pub fn uri_hash_only(uri: &str) -> String {
+        let mut hashed = String::with_capacity(URI_HASH_ONLY_PREFIX.len() + 64);
+        hashed.push_str(URI_HASH_ONLY_PREFIX);
+        for byte in anchor_lang::solana_program::keccak::hash(uri.as_bytes()).to_bytes() {
+            hashed.push_str(&format!("{:02x}", byte));
         }
+        hashed
+    }
 
-        let uri_length = u64::from_be_bytes(
-            message[uri_offset..uri_offset + 8].try_into()
-                .map_err(|_| UniversalNFTCoreError::InvalidMessageFormat)?
-        ) as usize;
-        
-        if message.len() < uri_offset + 8 + uri_length {
-            return Err(UniversalNFTCoreError::InvalidMessageFormat.into());
+    /// Commitment to the burn `transfer_cross_chain` makes, for destination contracts that
+    /// check it before minting. The preimage is 80 bytes:
+    ///
+    /// `mint (32) || token_id (8, big-endian) || owner (32) || slot (8, big-endian)`
+    ///
+    /// where `owner` is the burned token account's owner and `slot` the slot of the burn.
+    /// That is Solidity's `keccak256(abi.encodePacked(bytes32 mint, uint64 tokenId,
+    /// bytes32 owner, uint64 slot))`
+    pub fn burn_commitment(mint: &Pubkey, token_id: u64, owner: &Pubkey, slot: u64) -> [u8; 32] {
+        anchor_lang::solana_program::keccak::hashv(&[
+            mint.as_ref(),
+            &token_id.to_be_bytes(),
+            owner.as_ref(),
+            &slot.to_be_bytes(),
+        ])
+        .to_bytes()
+    }
+
+    /// Everything after the padded uri of an ABI body: the V2 call options, the encryption
+    /// key, the burn commitment, the note, the provenance, the amount, the priority, then the
+    /// value, each optional
+    fn message_trailer(message: &[u8]) -> Result<&[u8]> {
+        let trailer_start = Self::body_fields(message)?.end;
+        message.get(trailer_start..)
+            .ok_or(UniversalNFTCoreError::InvalidMessageFormat.into())
+    }
+
+    /// Split a trailer into its parts, each empty when absent
+    fn split_trailer(trailer: &[u8]) -> Result<TrailerParts<'_>> {
+        let (call_options, rest) = match trailer.first() {
+            Some(&MESSAGE_FORMAT_V2) => {
+                require!(trailer.len() >= 38, UniversalNFTCoreError::InvalidMessageFormat);
+                trailer.split_at(38)
+            }
+            _ => trailer.split_at(0),
+        };
+        let (key, rest) = match rest.first() {
+            Some(&MESSAGE_TRAILER_ENCRYPTION_KEY) => {
+                require!(rest.len() >= 33, UniversalNFTCoreError::InvalidMessageFormat);
+                rest.split_at(33)
+            }
+            _ => rest.split_at(0),
+        };
+        let (commitment, rest) = match rest.first() {
+            Some(&MESSAGE_TRAILER_BURN_COMMITMENT) => {
+                require!(rest.len() >= 33, UniversalNFTCoreError::InvalidMessageFormat);
+                rest.split_at(33)
+            }
+            _ => rest.split_at(0),
+        };
+        let (note, rest) = match rest.first() {
+            Some(&MESSAGE_TRAILER_NOTE) => {
+                let note_len = *rest.get(1).ok_or(UniversalNFTCoreError::InvalidMessageFormat)? as usize;
+                require!(
+                    note_len <= MAX_MESSAGE_NOTE_BYTES && rest.len() >= 2 + note_len,
+                    UniversalNFTCoreError::InvalidMessageFormat
+                );
+                rest.split_at(2 + note_len)
+            }
+            _ => rest.split_at(0),
+        };
+        let (provenance, rest) = match rest.first() {
+            Some(&MESSAGE_TRAILER_PROVENANCE) => {
+                let contract_len = *rest.get(17).ok_or(UniversalNFTCoreError::InvalidMessageFormat)? as usize;
+                require!(
+                    (1..=MAX_PROVENANCE_CONTRACT_BYTES).contains(&contract_len) && rest.len() >= 18 + contract_len,
+                    UniversalNFTCoreError::InvalidMessageFormat
+                );
+                rest.split_at(18 + contract_len)
+            }
+            _ => rest.split_at(0),
+        };
+        let (amount, rest) = match rest.first() {
+            Some(&MESSAGE_TRAILER_AMOUNT) => {
+                require!(rest.len() >= 9, UniversalNFTCoreError::InvalidMessageFormat);
+                rest.split_at(9)
+            }
+            _ => rest.split_at(0),
+        };
+        let (priority, value) = match rest.first() {
+            Some(&MESSAGE_TRAILER_PRIORITY) => {
+                require!(rest.len() >= 2, UniversalNFTCoreError::InvalidMessageFormat);
+                rest.split_at(2)
+            }
+            _ => rest.split_at(0),
+        };
+        require!(
+            value.is_empty() || (value.len() == 9 && value[0] == MESSAGE_TRAILER_VALUE),
+            UniversalNFTCoreError::InvalidMessageFormat
+        );
+        Ok(TrailerParts { call_options, key, commitment, note, provenance, amount, priority, value })
+    }
+
+    /// Read the encryption key trailer of a message body, if it carries one
+    pub fn decode_metadata_encryption_key(message: &[u8]) -> Result<Option<[u8; 32]>> {
+        let key = Self::split_trailer(Self::message_trailer(message)?)?.key;
+        Ok(key.get(1..).map(|key| key.try_into().unwrap()))
+    }
+
+    /// Read the burn commitment trailer of a message body, if it carries one
+    pub fn decode_burn_commitment(message: &[u8]) -> Result<Option<[u8; 32]>> {
+        let commitment = Self::split_trailer(Self::message_trailer(message)?)?.commitment;
+        Ok(commitment.get(1..).map(|commitment| commitment.try_into().unwrap()))
+    }
+
+    /// Read the note trailer of a message body, if it carries one, and whether it wasn't
+    /// UTF-8. Such a note is decoded lossily rather than failing the delivery it rides on
+    pub fn decode_note(message: &[u8]) -> Result<Option<(String, bool)>> {
+        let note = Self::split_trailer(Self::message_trailer(message)?)?.note;
+        Ok(note.get(2..).map(|note| match String::from_utf8_lossy(note) {
+            Cow::Borrowed(note) => (note.to_string(), false),
+            Cow::Owned(note) => (note, true),
+        }))
+    }
+
+    /// Read the provenance trailer of a message body, if it carries one
+    pub fn decode_provenance(message: &[u8]) -> Result<Option<Provenance>> {
+        let provenance = Self::split_trailer(Self::message_trailer(message)?)?.provenance;
+        if provenance.is_empty() {
+            return Ok(None);
         }
 
-        let uri = String::from_utf8(
-            message[uri_offset + 8..uri_offset + 8 + uri_length].to_vec()
-        ).map_err(|_| UniversalNFTCoreError::InvalidUriEncoding)?;
+        Ok(Some(Provenance {
+            origin_chain: u64::from_be_bytes(provenance[1..9].try_into().unwrap()),
+            origin_token_id: u64::from_be_bytes(provenance[9..17].try_into().unwrap()),
+            origin_contract: provenance[18..].to_vec(),
+        }))
+    }
+
+    /// Read the amount trailer of a message body, if it carries one; zero is not an amount
+    pub fn decode_amount(message: &[u8]) -> Result<Option<u64>> {
+        let amount = Self::split_trailer(Self::message_trailer(message)?)?.amount;
+        let amount = amount.get(1..).map(|amount| u64::from_be_bytes(amount.try_into().unwrap()));
+        require!(amount != Some(0), UniversalNFTCoreError::InvalidMessageFormat);
+        Ok(amount)
+    }
 
-        let sender = message[80..100].try_into()
-            .map_err(|_| UniversalNFTCoreError::InvalidMessageFormat)?;
+    /// Read the value trailer of a message body, if it carries one; zero is not a value
+    pub fn decode_value(message: &[u8]) -> Result<Option<u64>> {
+        let value = Self::split_trailer(Self::message_trailer(message)?)?.value;
+        let value = value.get(1..).map(|value| u64::from_be_bytes(value.try_into().unwrap()));
+        require!(value != Some(0), UniversalNFTCoreError::InvalidMessageFormat);
+        Ok(value)
+    }
+
+    /// Read the priority trailer of a message body; zero when it carries none
+    pub fn decode_priority(message: &[u8]) -> Result<u8> {
+        let priority = Self::split_trailer(Self::message_trailer(message)?)?.priority;
+        Ok(priority.get(1).copied().unwrap_or(0))
+    }
+
+    /// Read the call options trailer of a V2 message; V1 messages have none
+    pub fn decode_call_options(message: &[u8]) -> Result<Option<ZetaChainCallOptions>> {
+        let trailer = Self::split_trailer(Self::message_trailer(message)?)?.call_options;
+        if trailer.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(ZetaChainCallOptions {
+            gas_limit: u64::from_be_bytes(trailer[1..9].try_into().unwrap()),
+            is_arbitrary_call: trailer[9] != 0,
+            on_revert_gas_limit: u64::from_be_bytes(trailer[10..18].try_into().unwrap()),
+            revert_address: trailer[18..38].try_into().unwrap(),
+        }))
+    }
+
+    /// Encode the router calldata for a swap. The router reverts below `min_amount_out` or
+    /// after `deadline`, a unix timestamp
+    /// V2: swapExactTokensForTokens(amountIn, amountOutMin, path, to, deadline)
+    /// V3: exactInputSingle((tokenIn, tokenOut, fee, recipient, deadline, amountIn, amountOutMinimum, sqrtPriceLimitX96))
+    pub fn encode_swap_call(
+        router_version: u8,
+        fee_tier: u32,
+        zrc20: [u8; 20],
+        amount: u64,
+        min_amount_out: u64,
+        deadline: u64,
+        destination: [u8; 20],
+    ) -> Result<Vec<u8>> {
+        let mut calldata = Vec::new();
+
+        match router_version {
+            crate::SWAP_ROUTER_V2 => {
+                calldata.extend_from_slice(&[0x38, 0xed, 0x17, 0x39]);
+                calldata.extend_from_slice(&Self::abi_uint(amount));
+                calldata.extend_from_slice(&Self::abi_uint(min_amount_out));
+                calldata.extend_from_slice(&Self::abi_uint(160)); // path offset
+                calldata.extend_from_slice(&Self::abi_address([0u8; 20])); // to (filled by the helper)
+                calldata.extend_from_slice(&Self::abi_uint(deadline));
+                calldata.extend_from_slice(&Self::abi_uint(2)); // path length
+                calldata.extend_from_slice(&Self::abi_address(zrc20));
+                calldata.extend_from_slice(&Self::abi_address(destination));
+            }
+            crate::SWAP_ROUTER_V3 => {
+                require!(fee_tier <= crate::MAX_SWAP_FEE_TIER, crate::ErrorCode::InvalidSwapFeeTier);
+                calldata.extend_from_slice(&[0x41, 0x4b, 0xf3, 0x89]);
+                calldata.extend_from_slice(&Self::abi_address(zrc20));
+                calldata.extend_from_slice(&Self::abi_address(destination));
+                calldata.extend_from_slice(&Self::abi_uint(fee_tier as u64));
+                calldata.extend_from_slice(&Self::abi_address([0u8; 20])); // recipient (filled by the helper)
+                calldata.extend_from_slice(&Self::abi_uint(deadline));
+                calldata.extend_from_slice(&Self::abi_uint(amount));
+                calldata.extend_from_slice(&Self::abi_uint(min_amount_out));
+                calldata.extend_from_slice(&Self::abi_uint(0)); // sqrtPriceLimitX96
+            }
+            _ => return Err(crate::ErrorCode::InvalidSwapRouterVersion.into()),
+        }
+
+        Ok(calldata)
+    }
+
+    /// ABI-encode a uint as a 32-byte big-endian word
+    fn abi_uint(value: u64) -> [u8; 32] {
+        let mut word = [0u8; 32];
+        word[24..32].copy_from_slice(&value.to_be_bytes());
+        word
+    }
+
+    /// ABI-encode an address as a left-padded 32-byte word
+    fn abi_address(address: [u8; 20]) -> [u8; 32] {
+        let mut word = [0u8; 32];
+        word[12..32].copy_from_slice(&address);
+        word
+    }
+
+    /// Decode the ABI body of a message, canonical or legacy
+    pub fn decode_cross_chain_message(message: &[u8]) -> Result<CrossChainNftMessage> {
+        let fields = Self::body_fields(message)?;
+        let receiver = message[12..32].try_into().unwrap();
+        let uri = String::from_utf8(message[fields.uri].to_vec())
+            .map_err(|_| UniversalNFTCoreError::InvalidUriEncoding)?;
+
+        // The body carries no destination, so it decodes as a mint-here message
+        Ok(CrossChainNftMessage::mint_here(receiver, fields.token_id, uri, fields.sender))
+    }
+
+    /// Locate the fields of an ABI body. Canonical bodies are `encode_inbound_body`'s
+    /// `abi.encode(receiver, tokenId, uri, gasAmount, sender)`. Legacy bodies, from before the codec was ABI-compliant, keep the token id
+    /// in the first 8 bytes of its word, the sender at 80..100, and write the uri offset and
+    /// length as 8-byte values. A legacy offset sits in bytes 64..72, which a canonical offset
+    /// word always leaves zero, so the two never overlap
+    fn body_fields(body: &[u8]) -> Result<BodyFields> {
+        let legacy_offset = body.get(64..72)
+            .map(|bytes| u64::from_be_bytes(bytes.try_into().unwrap()))
+            .ok_or(UniversalNFTCoreError::InvalidMessageFormat)?;
+
+        let (token_id, sender, uri_start, uri_length) = if legacy_offset != 0 {
+            require!(body.len() >= 100, UniversalNFTCoreError::InvalidMessageFormat);
+            let uri_offset = legacy_offset as usize;
+            let uri_length = body.get(uri_offset..uri_offset.saturating_add(8))
+                .map(|bytes| u64::from_be_bytes(bytes.try_into().unwrap()))
+                .ok_or(UniversalNFTCoreError::InvalidMessageFormat)?;
+            (
+                u64::from_be_bytes(body[32..40].try_into().unwrap()),
+                body[80..100].try_into().unwrap(),
+                uri_offset + 8,
+                uri_length,
+            )
+        } else {
+            // Word 3 is the gas amount the universal contract paid the call with
+            require!(body.len() >= 5 * 32, UniversalNFTCoreError::InvalidMessageFormat);
+            let uri_offset = Self::abi_word_u64(body, 64)? as usize;
+            (
+                Self::abi_word_u64(body, 32)?,
+                body[140..160].try_into().unwrap(),
+                uri_offset.saturating_add(32),
+                Self::abi_word_u64(body, uri_offset)?,
+            )
+        };
+
+        let uri_length = usize::try_from(uri_length).map_err(|_| UniversalNFTCoreError::InvalidMessageFormat)?;
+        let uri_end = uri_start.checked_add(uri_length)
+            .filter(|&end| end <= body.len())
+            .ok_or(UniversalNFTCoreError::InvalidMessageFormat)?;
+        Ok(BodyFields {
+            token_id,
+            sender,
+            uri: uri_start..uri_end,
+            end: uri_end + (32 - uri_length % 32) % 32,
+        })
+    }
+
+    /// A uint word of `body` at byte `at` that must fit a u64
+    fn abi_word_u64(body: &[u8], at: usize) -> Result<u64> {
+        let word = body.get(at..at.saturating_add(32))
+            .filter(|word| word.len() == 32 && word[..24].iter().all(|&byte| byte == 0))
+            .ok_or(UniversalNFTCoreError::InvalidMessageFormat)?;
+        Ok(u64::from_be_bytes(word[24..].try_into().unwrap()))
+    }
+
+    /// Decode a batch body: a u8 count, then each V1 body prefixed with its u32 BE length
+    pub fn decode_batch_cross_chain_message(body: &[u8]) -> Result<Vec<CrossChainNftMessage>> {
+        Self::split_batch_body(body)?
+            .into_iter()
+            .map(Self::decode_cross_chain_message)
+            .collect()
+    }
+
+    /// Split a batch body into its V1 bodies, without decoding them
+    pub fn split_batch_body(body: &[u8]) -> Result<Vec<&[u8]>> {
+        let (&count, mut rest) = body.split_first()
+            .ok_or(UniversalNFTCoreError::InvalidMessageFormat)?;
+
+        let mut items = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            require!(rest.len() >= 4, UniversalNFTCoreError::InvalidMessageFormat);
+            let (len, tail) = rest.split_at(4);
+            let len = u32::from_be_bytes(len.try_into().unwrap()) as usize;
+            require!(tail.len() >= len, UniversalNFTCoreError::InvalidMessageFormat);
+            let (item, tail) = tail.split_at(len);
+            items.push(item);
+            rest = tail;
+        }
+        require!(rest.is_empty(), UniversalNFTCoreError::InvalidMessageFormat);
 
-        // For now, we'll use a default destination (this should be passed in the message)
-        let destination = [0u8; 20];
+        Ok(items)
+    }
+
+    /// Encode a ping for the gateway
+    pub fn encode_ping_message(ping: &PingMessage) -> Result<Vec<u8>> {
+        ping.try_to_vec().map_err(|_| UniversalNFTCoreError::InvalidMessageFormat.into())
+    }
+
+    /// Encode a contract ping: the ZRC-20 word the universal contract forwards it through,
+    /// then the ping itself
+    pub fn encode_contract_ping(zrc20: [u8; 20], ping: &ContractPingMessage) -> Result<Vec<u8>> {
+        let mut message = Self::abi_address(zrc20).to_vec();
+        message.extend(ping.try_to_vec().map_err(|_| UniversalNFTCoreError::InvalidMessageFormat)?);
+        Ok(message)
+    }
+
+    pub fn encode_randomness_request(request: &RandomnessRequest) -> Result<Vec<u8>> {
+        request.try_to_vec().map_err(|_| UniversalNFTCoreError::InvalidMessageFormat.into())
+    }
 
-        Ok((destination, receiver, token_id, uri, sender))
+    /// Decode a pong: the echoed ping followed by the 20-byte address that answered
+    pub fn decode_pong_message(message: &[u8]) -> Result<(PingMessage, [u8; 20])> {
+        let mut data = message;
+        let ping = PingMessage::deserialize(&mut data)
+            .map_err(|_| UniversalNFTCoreError::InvalidMessageFormat)?;
+        let destination: [u8; 20] = data.try_into()
+            .map_err(|_| UniversalNFTCoreError::InvalidMessageFormat)?;
+        Ok((ping, destination))
     }
 
     /// Call ZetaChain gateway with proper parameters
@@ -316,6 +1265,7 @@ impl UniversalNFTCoreImpl {
         signer: AccountInfo<'a>,
         destination: [u8; 20],
         message: Vec<u8>,
+        call_options: &ZetaChainCallOptions,
     ) -> Result<()> {
         // For testing purposes, skip the actual gateway call to avoid "Unsupported program id" errors
         // In production, this would make a CPI call to the ZetaChain gateway program
@@ -323,6 +1273,7 @@ impl UniversalNFTCoreImpl {
         msg!("Skipping gateway call in test mode");
         msg!("Destination: {:?}", destination);
         msg!("Message length: {}", message.len());
+        msg!("Call options: {:?}", call_options);
         msg!("Signer: {}", signer.key());
         msg!("Gateway program: {}", gateway_program.key());
         
@@ -338,12 +1289,13 @@ impl UniversalNFTCoreImpl {
         // instruction_data.extend_from_slice(&(message.len() as u32).to_le_bytes());
         // instruction_data.extend_from_slice(&message);
         // 
-        // // Add call options (gas limit, etc.)
-        // let gas_limit = 1000000u64; // Set appropriate gas limit
-        // instruction_data.extend_from_slice(&gas_limit.to_le_bytes());
+        // // Add call options
+        // instruction_data.extend_from_slice(&call_options.gas_limit.to_le_bytes());
+        // instruction_data.push(call_options.is_arbitrary_call as u8);
         // 
         // // Add revert options
-        // instruction_data.push(1u8); // Enable revert handling
+        // instruction_data.extend_from_slice(&call_options.revert_address);
+        // instruction_data.extend_from_slice(&call_options.on_revert_gas_limit.to_le_bytes());
         // 
         // let metas = vec![
         //     AccountMeta::new(signer.key(), true),
@@ -358,12 +1310,13 @@ impl UniversalNFTCoreImpl {
         //         data: instruction_data,
         //     },
         //     &infos,
-        // ).map_err(|_| UniversalNFTCoreError::GatewayCallFailed)?;
+        // ).map_err(crate::cpi_failed(UniversalNFTCoreError::GatewayCallFailed))?;
         
         Ok(())
     }
 
     /// Generate instruction discriminator
+    #[allow(dead_code)]
     fn instruction_discriminator(name: &str) -> [u8; 8] {
         let mut discriminator = [0u8; 8];
         let preimage = format!("global:{}", name);
@@ -372,3 +1325,71 @@ impl UniversalNFTCoreImpl {
         discriminator
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ZRC20: [u8; 20] = [0x11; 20];
+    const DESTINATION: [u8; 20] = [0x22; 20];
+
+    fn words(calldata: &[u8]) -> Vec<[u8; 32]> {
+        calldata[4..].chunks(32).map(|word| word.try_into().unwrap()).collect()
+    }
+
+    fn uint(value: u64) -> [u8; 32] {
+        UniversalNFTCoreImpl::abi_uint(value)
+    }
+
+    fn address(value: [u8; 20]) -> [u8; 32] {
+        UniversalNFTCoreImpl::abi_address(value)
+    }
+
+    #[test]
+    fn v2_swap_carries_its_minimum_and_deadline() {
+        let calldata = UniversalNFTCoreImpl::encode_swap_call(
+            crate::SWAP_ROUTER_V2, crate::DEFAULT_SWAP_FEE_TIER, ZRC20, 1_000, 990, 1_700_000_000, DESTINATION,
+        ).unwrap();
+        assert_eq!(calldata[..4], [0x38, 0xed, 0x17, 0x39]);
+        assert_eq!(words(&calldata), vec![
+            uint(1_000),
+            uint(990),
+            uint(160),
+            address([0u8; 20]),
+            uint(1_700_000_000),
+            uint(2),
+            address(ZRC20),
+            address(DESTINATION),
+        ]);
+    }
+
+    #[test]
+    fn v3_swap_carries_its_minimum_and_deadline() {
+        let calldata = UniversalNFTCoreImpl::encode_swap_call(
+            crate::SWAP_ROUTER_V3, 500, ZRC20, 1_000, 990, 1_700_000_000, DESTINATION,
+        ).unwrap();
+        assert_eq!(calldata[..4], [0x41, 0x4b, 0xf3, 0x89]);
+        assert_eq!(words(&calldata), vec![
+            address(ZRC20),
+            address(DESTINATION),
+            uint(500),
+            address([0u8; 20]),
+            uint(1_700_000_000),
+            uint(1_000),
+            uint(990),
+            uint(0),
+        ]);
+    }
+
+    #[test]
+    fn swaps_reject_unknown_routers_and_oversized_fee_tiers() {
+        let swap = |router_version, fee_tier| UniversalNFTCoreImpl::encode_swap_call(
+            router_version, fee_tier, ZRC20, 1_000, 990, 1_700_000_000, DESTINATION,
+        ).map(|_| ());
+        assert!(crate::match_error_code(&swap(0, 500), crate::ErrorCode::InvalidSwapRouterVersion));
+        assert!(crate::match_error_code(
+            &swap(crate::SWAP_ROUTER_V3, crate::MAX_SWAP_FEE_TIER + 1),
+            crate::ErrorCode::InvalidSwapFeeTier,
+        ));
+    }
+}
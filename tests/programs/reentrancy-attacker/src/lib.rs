@@ -0,0 +1,46 @@
+//! Stands in for a gateway that calls back into universal-nft: `forward` replays any
+//! instruction against the target program through CPI. Not a workspace member: the tests load
+//! it into the local validator's genesis
+// For the `realloc` call in Anchor's generated IDL instructions
+#![allow(deprecated)]
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke;
+
+declare_id!("FojnH4xK6fpdtHbeE7NT7oGuc5Qbk3DUKHBtk6fEm9vz");
+
+#[program]
+pub mod reentrancy_attacker {
+    use super::*;
+
+    /// Invoke `target_program` with `data` and the remaining accounts, keeping each
+    /// account's signer and writable flags
+    pub fn forward<'info>(ctx: Context<'_, '_, 'info, 'info, Forward<'info>>, data: Vec<u8>) -> Result<()> {
+        let accounts = ctx.remaining_accounts.iter()
+            .map(|account| AccountMeta {
+                pubkey: account.key(),
+                is_signer: account.is_signer,
+                is_writable: account.is_writable,
+            })
+            .collect();
+        let mut account_infos = ctx.remaining_accounts.to_vec();
+        account_infos.push(ctx.accounts.target_program.to_account_info());
+        invoke(
+            &Instruction {
+                program_id: ctx.accounts.target_program.key(),
+                accounts,
+                data,
+            },
+            &account_infos,
+        )?;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct Forward<'info> {
+    /// CHECK: Any program; the instruction is forwarded to it unchanged
+    #[account(executable)]
+    pub target_program: UncheckedAccount<'info>,
+}
@@ -10,18 +10,40 @@ import {
   TOKEN_PROGRAM_ID, 
   ASSOCIATED_TOKEN_PROGRAM_ID, 
   SYSVAR_RENT_PUBKEY,
+  AddressLookupTableAccount,
+  PACKET_DATA_SIZE,
+  TransactionMessage,
+  VersionedTransaction,
+  Ed25519Program,
 } from "@solana/web3.js";
 import { 
   createMint, 
   createAccount, 
   mintTo, 
   getAccount, 
+  getMint,
   createAssociatedTokenAccount,
   getAssociatedTokenAddress,
-  burn
+  getAssociatedTokenAddressSync,
+  burn,
+  approve,
+  transfer,
+  freezeAccount
 } from "@solana/spl-token";
 import { assert } from "chai";
+import * as fs from "fs";
+import * as path from "path";
+import { createHash } from "crypto";
+import { keccak_256 } from "@noble/hashes/sha3";
 import { BN } from "bn.js";
+import {
+  buildTransaction,
+  COMPUTE_UNIT_DRIFT,
+  COMPUTE_UNIT_ESTIMATES,
+  getStaticLookupAddresses,
+  transactionSize,
+} from "../app/lib/transactions";
+import { UniversalNFTClient } from "../app/lib/program";
 
 describe("Universal NFT Program - Solana to ZetaChain Transfer", () => {
   const provider = anchor.AnchorProvider.env();
@@ -35,6 +57,25 @@ describe("Universal NFT Program - Solana to ZetaChain Transfer", () => {
   const admin = Keypair.generate();
   const user = Keypair.generate();
   const mintAuthority = Keypair.generate();
+  // Stands in for the ZetaChain gateway, which signs every inbound delivery
+  const gatewaySigner = Keypair.generate();
+  // Every transfer passes the protocol fee config and its treasury
+  const feeConfigPda = PublicKey.findProgramAddressSync([Buffer.from("fee_config")], program.programId)[0];
+  let feeTreasury = Keypair.generate().publicKey;
+  // A transfer passes its final chain's ChainGasConfig
+  const chainConfigPda = (chainId: BN) => PublicKey.findProgramAddressSync(
+    [Buffer.from("chain_gas"), chainId.toArrayLike(Buffer, 'le', 8)],
+    program.programId
+  )[0];
+  // Creates an empty config, which keeps the program's defaults, unless the chain has one
+  const ensureChainConfig = async (chainId: BN) => {
+    if (!(await connection.getAccountInfo(chainConfigPda(chainId)))) {
+      await program.methods
+        .setChainTrust(chainId, 0, 0)
+        .accountsPartial({ admin: wallet.publicKey, chainGasConfig: chainConfigPda(chainId) })
+        .rpc();
+    }
+  };
   
   // PDAs - Use the same seed as the program to avoid "already in use" errors
   const programStatePda = PublicKey.findProgramAddressSync(
@@ -42,6 +83,46 @@ describe("Universal NFT Program - Solana to ZetaChain Transfer", () => {
     program.programId
   )[0];
   
+  // Inbound messages open with a version byte; 0x01 is the plain ABI body
+  const MESSAGE_VERSION_V1 = 0x01;
+
+  // Native mints take ids from DEFAULT_NATIVE_ID_BASE (1 << 48) up; inbound ids stay below it
+  const NATIVE_ID_BASE = new BN(2).pow(new BN(48));
+
+  // The Solana chain id initialize must be given with a genesis hash: mainnet-beta and devnet
+  // have their own, and any other hash is a local validator's
+  const solanaChainIdFor = (genesisHash: string): number => ({
+    "5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d": 900,
+    "EtWTRABZaYq6iMfeYKouRu166VU2xqa1wcaWoxPkrZBG": 901,
+  }[genesisHash] ?? 902);
+
+  // Bridged (inbound) mints are PDAs derived from the origin chain and token id
+  const CHAIN_ID_ZETACHAIN_TESTNET = 7001;
+  const bridgedMintPda = (tokenId: number): PublicKey =>
+    PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("bridged_mint"),
+        new BN(CHAIN_ID_ZETACHAIN_TESTNET).toArrayLike(Buffer, 'le', 8),
+        new BN(tokenId).toArrayLike(Buffer, 'le', 8),
+      ],
+      program.programId
+    )[0];
+  const dedupWindowPda = (uri: string): PublicKey =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("dedup_window"), createHash("sha256").update(uri).digest()],
+      program.programId
+    )[0];
+  // Every delivery records an InboundReceipt keyed by the message's hash
+  const inboundReceiptPda = (message: Buffer): PublicKey =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("inbound_rcpt"), createHash("sha256").update(message).digest()],
+      program.programId
+    )[0];
+  const mintAuthorityPda = PublicKey.findProgramAddressSync(
+    [Buffer.from("mint_authority")],
+    program.programId
+  )[0];
+
   // Test state
   let isProgramInitialized = false;
   let existingOwner: PublicKey | null = null;
@@ -56,6 +137,7 @@ describe("Universal NFT Program - Solana to ZetaChain Transfer", () => {
   // ZetaChain test addresses (20-byte EVM addresses)
   const zetaChainRecipient = new Uint8Array(20).fill(1); // Test recipient on ZetaChain
   const zetaChainZRC20 = new Uint8Array(20).fill(2); // Test ZRC-20 address on ZetaChain
+  const zetaChainId = new BN(7001); // Final chain for NFTs minted on ZetaChain itself
   const solanaSender = new Uint8Array(20).fill(3); // Test Solana sender representation
   
   // ZetaChain integration test data
@@ -63,6 +145,24 @@ describe("Universal NFT Program - Solana to ZetaChain Transfer", () => {
   const zetaChainTestnetContract = "0x1234567890123456789012345678901234567890"; // Example testnet contract
   const zetaChainTestnetRPC = "https://zetachain-athens-3.blockscout.com"; // Example testnet RPC
 
+  // Every inbound delivery passes the OriginPolicy PDA of the universal contract it came
+  // through, set or not; refreshed before each test since a test may switch the contract
+  const originPolicyPda = (contract: number[] | Buffer) => PublicKey.findProgramAddressSync(
+    [
+      Buffer.from("origin_policy"),
+      new BN(CHAIN_ID_ZETACHAIN_TESTNET).toArrayLike(Buffer, 'le', 8),
+      createHash("sha256").update(Buffer.from(contract)).digest(),
+    ],
+    program.programId
+  )[0];
+  let originPolicyAccounts: anchor.web3.AccountMeta[] = [];
+  beforeEach(async () => {
+    const state = await program.account.programState.fetchNullable(programStatePda);
+    originPolicyAccounts = state
+      ? [{ pubkey: originPolicyPda(state.universalNftContract), isSigner: false, isWritable: false }]
+      : [];
+  });
+
   before(async () => {
     // Transfer SOL from existing wallet to test accounts
     const transferAmount = 2.0 * LAMPORTS_PER_SOL; // Increased for larger operations
@@ -141,6 +241,41 @@ describe("Universal NFT Program - Solana to ZetaChain Transfer", () => {
   });
 
   describe("Phase 1: Program Initialization & Setup", () => {
+    it("Should reject initialize with a gas limit outside the allowed range", async () => {
+      if (isProgramInitialized) {
+        console.log("Program already initialized, skipping initialize gas limit test");
+        return;
+      }
+
+      const gateway = new PublicKey("ZETAjseVjuFsxdRxo6MmTCvqFwb3ZHUx56Co3vCmGis");
+      const universalNftContract = Array.from(new Uint8Array(20).fill(1));
+
+      const genesisHash = await connection.getGenesisHash();
+
+      // MIN_GAS_LIMIT = 100_000, MAX_GAS_LIMIT = 30_000_000
+      for (const gasLimit of [new BN(1), new BN(99_999), new BN(30_000_001)]) {
+        try {
+          await program.methods
+            .initialize(
+              gateway,
+              new BN(1),
+              universalNftContract,
+              gasLimit,
+              Array.from(anchor.utils.bytes.bs58.decode(genesisHash)),
+              new BN(solanaChainIdFor(genesisHash)),
+            )
+            .accounts({
+              payer: admin.publicKey,
+            })
+            .signers([admin])
+            .rpc();
+          assert.fail(`initialize accepted gas limit ${gasLimit.toString()}`);
+        } catch (error) {
+          assert.include(error.toString(), "GasLimitOutOfRange");
+        }
+      }
+    });
+
     it("Should initialize program state for cross-chain operations", async () => {
       if (isProgramInitialized) {
         console.log("Program already initialized, verifying existing state...");
@@ -159,19 +294,30 @@ describe("Universal NFT Program - Solana to ZetaChain Transfer", () => {
       const initialTokenId = new BN(1);
       const universalNftContract = Buffer.from("0x536a1F02F944Fa673E4Aa693a717Fd8F69D4c1f8", "hex"); // ZetaChain contract as 20-byte array
       const gasLimit = new BN(1000000);
-
-      await program.methods
+      const genesisHash = await connection.getGenesisHash();
+      const chainId = solanaChainIdFor(genesisHash);
+      const initialize = (solanaChainId: number) => program.methods
         .initialize(
           gateway,
           initialTokenId,
           Array.from(universalNftContract), // Convert Buffer to array
           gasLimit,
+          Array.from(anchor.utils.bytes.bs58.decode(genesisHash)),
+          new BN(solanaChainId),
         )
         .accounts({
           payer: admin.publicKey,
         })
-        .signers([admin])
-        .rpc();
+        .signers([admin]);
+
+      // A chain id the declared genesis hash doesn't name is refused, not mapped
+      try {
+        await initialize(chainId === 900 ? 901 : 900).rpc();
+        assert.fail("initialize accepted a chain id that disagrees with the genesis hash");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidChainId");
+      }
+      const signature = await initialize(chainId).rpc({ commitment: "confirmed" });
 
       console.log("Program initialized successfully for cross-chain operations");
 
@@ -179,31 +325,113 @@ describe("Universal NFT Program - Solana to ZetaChain Transfer", () => {
       const programState = await program.account.programState.fetch(programStatePda);
       assert.equal(programState.owner.toString(), "F79VcAwM6VhL9CaZo68W1SwrkntLJpAhcbTLLzuz4g3G");
       assert.equal(programState.gateway.toString(), gateway.toString());
-      assert.equal(programState.nextTokenId.toNumber(), initialTokenId.toNumber());
+      // Native ids never start below the native range
+      assert.equal(programState.nativeIdBase.toString(), NATIVE_ID_BASE.toString());
+      assert.equal(programState.nextTokenId.toString(), NATIVE_ID_BASE.toString());
       assert.equal(programState.paused, false);
       assert.equal(programState.gasLimit.toNumber(), gasLimit.toNumber());
+      assert.equal(programState.declaredChainId.toNumber(), chainId);
+      // ProgramInitialized records what was declared
+      const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const initialized = Array.from(parser.parseLogs(tx.meta.logMessages)).find((event) => event.name === "programInitialized");
+      assert.ok(initialized, "Expected a ProgramInitialized event");
+      assert.deepEqual(Buffer.from(initialized.data.declaredGenesisHash), Buffer.from(anchor.utils.bytes.bs58.decode(genesisHash)));
+      assert.equal(initialized.data.declaredChainId.toNumber(), programState.declaredChainId.toNumber());
 
       isProgramInitialized = true;
       existingOwner = admin.publicKey;
     });
 
+    it("Should hand inbound deliveries to the gateway signer", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping gateway setup");
+        return;
+      }
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping gateway setup");
+        return;
+      }
+      if (!state.gateway.equals(gatewaySigner.publicKey)) {
+        await program.methods
+          .setGateway(gatewaySigner.publicKey)
+          .accounts({ programState: programStatePda, admin: wallet.publicKey })
+          .rpc();
+      }
+      const updated = await program.account.programState.fetch(programStatePda);
+      assert.equal(updated.gateway.toString(), gatewaySigner.publicKey.toString());
+    });
+
+    it("Should set up the fee and chain configs every transfer passes", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping fee config setup");
+        return;
+      }
+      const state = await program.account.programState.fetch(programStatePda);
+      const existing = await program.account.feeConfig.fetchNullable(feeConfigPda);
+      if (!existing && state.owner.equals(wallet.publicKey)) {
+        // No fee yet; transfers still need the account
+        await program.methods
+          .setFeeConfig(new BN(0), feeTreasury)
+          .accounts({ programState: programStatePda, feeConfig: feeConfigPda, admin: wallet.publicKey })
+          .rpc();
+      }
+      const config = await program.account.feeConfig.fetchNullable(feeConfigPda);
+      if (config) {
+        feeTreasury = config.treasury;
+      }
+      if (state.owner.equals(wallet.publicKey)) {
+        await ensureChainConfig(zetaChainId);
+      }
+    });
+
     it("Should set connected contract for ZetaChain integration", async () => {
       if (!isProgramInitialized) {
         console.log("Program not initialized, skipping connected contract test");
         return;
       }
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping connected contract test");
+        return;
+      }
 
       // Set connected contract mapping for ZetaChain
       const zrc20Address = Array.from(zetaChainZRC20);
       const contractAddress = Buffer.from(new Uint8Array(32).fill(4)); // ZetaChain UniversalNFT contract
 
-      await program.methods
+      try {
+        await program.methods
+          .setConnectedContract(zrc20Address, contractAddress)
+          .accounts({ admin: admin.publicKey })
+          .signers([admin])
+          .rpc();
+        assert.fail("Only the owner can set a connected contract");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "Unauthorized");
+      }
+
+      const signature = await program.methods
         .setConnectedContract(zrc20Address, contractAddress)
         .accounts({
-          admin: admin.publicKey,
+          admin: wallet.publicKey,
         })
-        .signers([admin])
-        .rpc();
+        .rpc({ commitment: "confirmed" });
+
+      // The uniform admin event must accompany the specific one
+      const tx = await connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const events = Array.from(parser.parseLogs(tx.meta.logMessages));
+      const adminEvent = events.find((event) => event.name === "adminActionExecuted");
+      assert.ok(events.find((event) => event.name === "connectedContractSet"));
+      assert.ok(adminEvent);
+      assert.equal(adminEvent.data.kind, 3); // AdminActionKind::SetConnectedContract
+      assert.equal(adminEvent.data.actor.toString(), wallet.publicKey.toString());
 
       console.log("Connected contract set successfully for ZetaChain integration");
     });
@@ -219,7 +447,10 @@ describe("Universal NFT Program - Solana to ZetaChain Transfer", () => {
       const uri = "https://arweave.net/test-nft-metadata.json";
       const decimals = 0;
       // Use a unique token ID for each test run to avoid "already in use" errors
-      const tokenId = new BN(Date.now() % 1000000 + 1000); // Use timestamp + offset to make it unique
+      const tokenId = NATIVE_ID_BASE.addn(Date.now() % 1000000 + 1000); // Use timestamp + offset to make it unique
+
+      // Stand-in for a key wrapped for the holder
+      const testEncryptionKey = Array.from(new Uint8Array(32).fill(8));
 
       // ✅ FIX: Create a NEW mint keypair for this instruction
       const newMint = Keypair.generate();
@@ -227,7 +458,7 @@ describe("Universal NFT Program - Solana to ZetaChain Transfer", () => {
       // Create mint and NFT using the program instruction
       // This ensures proper program ID and account initialization
       const nft = await program.methods
-        .createMintAndNft(uri, decimals, tokenId)
+        .createMintAndNft(uri, decimals, tokenId, testEncryptionKey, null, null)
         .accounts({
           nftOrigin: PublicKey.findProgramAddressSync(
             [Buffer.from("nft_origin"), tokenId.toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
@@ -236,6 +467,13 @@ describe("Universal NFT Program - Solana to ZetaChain Transfer", () => {
           mint: newMint.publicKey,
           mintAuthority: mintAuthority.publicKey,
           payer: admin.publicKey,
+          feeConfig: feeConfigPda,
+          feeTreasury: null,
+          sellerFeeConfig: null,
+          feeCreator: null,
+          mintOnly: null,
+          lazyRevealConfig: null,
+          hourlyMetrics: null,
         })
         .signers([admin, mintAuthority, newMint])
         .rpc();
@@ -277,6 +515,44 @@ describe("Universal NFT Program - Solana to ZetaChain Transfer", () => {
       console.log("User token account:", userTokenAccount.toString());
       console.log("Token balance:", tokenAccountInfo.amount.toString());
     });
+
+    it("Should let only the holder replace the metadata encryption key", async () => {
+      if (!isProgramInitialized || !testTokenId) {
+        console.log("Program not ready, skipping encryption key test");
+        return;
+      }
+
+      const nftOrigin = PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), new BN(testTokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      const updateKey = (key: number[] | null, holder: Keypair) =>
+        program.methods
+          .updateMetadataEncryptionKey(new BN(testTokenId), key)
+          .accounts({ nftOrigin, holderTokenAccount: testTokenAccount, holder: holder.publicKey })
+          .signers([holder])
+          .rpc();
+
+      // Set at mint time
+      let origin = await program.account.nftOrigin.fetch(nftOrigin);
+      assert.deepEqual(Array.from(origin.metadataEncryptionKey), new Array(32).fill(8));
+
+      const rotated = Array.from(new Uint8Array(32).fill(9));
+      await updateKey(rotated, user);
+      origin = await program.account.nftOrigin.fetch(nftOrigin);
+      assert.deepEqual(Array.from(origin.metadataEncryptionKey), rotated);
+
+      try {
+        await updateKey(null, admin);
+        assert.fail("Only the holder should replace the encryption key");
+      } catch (error) {
+        assert.include(error.toString(), "ConstraintTokenOwner");
+      }
+
+      await updateKey(null, user);
+      origin = await program.account.nftOrigin.fetch(nftOrigin);
+      assert.isNull(origin.metadataEncryptionKey);
+    });
   });
 
   describe("Phase 3: Cross-Chain Transfer Initiation", () => {
@@ -296,8 +572,11 @@ describe("Universal NFT Program - Solana to ZetaChain Transfer", () => {
       const transfer = await program.methods
         .transferCrossChain(
           new BN(testTokenId),
-          Array.from(zetaChainRecipient),
-          Array.from(zetaChainZRC20)
+          zetaChainId,
+          Buffer.from(zetaChainRecipient),
+          null,
+          null,
+          null
         )
         .accounts({
           nftOrigin: PublicKey.findProgramAddressSync(
@@ -308,6 +587,20 @@ describe("Universal NFT Program - Solana to ZetaChain Transfer", () => {
           userTokenAccount: testTokenAccount,
           user: user.publicKey,
           gatewayProgram: gateway,
+          feeConfig: feeConfigPda,
+          feeTreasury,
+          chainGasConfig: chainConfigPda(zetaChainId),
+          registry: null,
+          receiptConfig: null, // departure receipts off
+          receiptMint: null,
+          receiptTokenAccount: null,
+          zrc20GasVault: null, // no ZRC-20 gas vault pays
+          zrc20VaultTokenAccount: null,
+          gasFeeDestination: null,
+          hourlyMetrics: null,
+          insuranceOptIn: null,
+          coOwnership: null,
+          coOwnerConsent: null,
         })
         .signers([user])
         .rpc();
@@ -331,6 +624,120 @@ describe("Universal NFT Program - Solana to ZetaChain Transfer", () => {
       console.log("- TokenTransfer");
       console.log("- Proper destination chain and recipient information");
     });
+
+    it("Should mint on ZetaChain with a zero destination and forward through the ZRC-20 otherwise", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping final chain test");
+        return;
+      }
+      // Registering a ZRC-20 is owner-only
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping final chain test");
+        return;
+      }
+
+      const sepolia = new BN(11155111);
+      const registryPda = PublicKey.findProgramAddressSync([Buffer.from("registry")], program.programId)[0];
+      if (!(await connection.getAccountInfo(registryPda))) {
+        await program.methods.initRegistry().accounts({ admin: wallet.publicKey }).rpc();
+      }
+      await program.methods
+        .setRegistryEntry(Array.from(zetaChainZRC20), Buffer.from(new Uint8Array(20).fill(9)), sepolia, 0)
+        .accounts({ admin: wallet.publicKey })
+        .rpc();
+      await ensureChainConfig(sepolia);
+
+      const tokenId = Date.now() + 11;
+      const mint = bridgedMintPda(tokenId);
+      const nftOrigin = PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      const tokenAccount = getAssociatedTokenAddressSync(mint, admin.publicKey, false);
+      const deliver = () =>
+        program.methods
+          .receiveCrossChainMessage(
+            createZetaChainSuccessMessage(tokenId, "https://example.com/final-chain.json", admin.publicKey.toBytes(), solanaSender),
+            null
+          )
+          .accounts({
+            programState: programStatePda,
+            nftOrigin,
+            mint,
+            dedupWindow: dedupWindowPda("https://example.com/final-chain.json"),
+            mintAuthority: mintAuthorityPda,
+            recipient: admin.publicKey,
+            sourceChainConfig: chainConfigPda(zetaChainId),
+            provisionalEscrow: null,
+            custodialTokenAccount: null,
+            inboundOffer: null,
+            pendingInbound: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            recipientTokenAccount: tokenAccount,
+            payer: admin.publicKey,
+            gateway: gatewaySigner.publicKey,
+            hourlyMetrics: null,
+            inboundValueClaim: null,
+            inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, "https://example.com/final-chain.json", admin.publicKey.toBytes(), solanaSender)),
+          })
+          .remainingAccounts(originPolicyAccounts)
+          .signers([admin, gatewaySigner])
+          .rpc();
+      const depart = (finalChainId: BN, registry: PublicKey | null) =>
+        program.methods
+          .transferCrossChain(new BN(tokenId), finalChainId, Buffer.from(zetaChainRecipient), null, null, null)
+          .accounts({
+            nftOrigin,
+            mint,
+            userTokenAccount: tokenAccount,
+            user: admin.publicKey,
+            gatewayProgram: new PublicKey(zetaChainTestnetGateway),
+            feeConfig: feeConfigPda,
+            feeTreasury,
+            chainGasConfig: chainConfigPda(finalChainId),
+            registry,
+            receiptConfig: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            zrc20GasVault: null,
+            zrc20VaultTokenAccount: null,
+            gasFeeDestination: null,
+            hourlyMetrics: null,
+            insuranceOptIn: null,
+            coOwnership: null,
+            coOwnerConsent: null,
+          })
+          .signers([admin])
+          .rpc({ commitment: "confirmed" });
+      const transferEvent = async (signature: string) => {
+        const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+        const parser = new anchor.EventParser(program.programId, program.coder);
+        return Array.from(parser.parseLogs(tx.meta.logMessages))
+          .find((event) => event.name === "crossChainTransferInitiated").data;
+      };
+
+      // Minting on ZetaChain itself: zero destination word, no registry needed
+      await deliver();
+      const toZeta = await transferEvent(await depart(zetaChainId, null));
+      assert.equal(toZeta.destinationChain.toNumber(), 7001);
+      assert.deepEqual(Array.from(toZeta.destinationZrc20), new Array(20).fill(0));
+
+      // Forwarding to Sepolia: the destination word is Sepolia's ZRC-20 from the registry
+      await deliver();
+      try {
+        await depart(sepolia, null);
+        assert.fail("Forwarding without the registry should be rejected");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "NotInRegistry");
+      }
+      const toSepolia = await transferEvent(await depart(sepolia, registryPda));
+      assert.equal(toSepolia.destinationChain.toString(), sepolia.toString());
+      assert.deepEqual(Array.from(toSepolia.destinationZrc20), Array.from(zetaChainZRC20));
+      assert.notDeepEqual(Array.from(toSepolia.destinationZrc20), Array.from(toZeta.destinationZrc20));
+    });
   });
 
   describe("Phase 4: Cross-Chain Message Reception", () => {
@@ -349,9 +756,8 @@ describe("Universal NFT Program - Solana to ZetaChain Transfer", () => {
         
         const message = Buffer.alloc(totalSize);
         
-        // Receiver (address) - 12 bytes padding + 20 bytes address
-        message.fill(0, 0, 12); // 12 bytes padding
-        Buffer.from(receiver).copy(message, 12); // 20 bytes receiver address
+        // Receiver word - the recipient's full 32-byte Solana key
+        Buffer.from(receiver).copy(message, 0);
         
         // Token ID (u64) - 8 bytes at bytes 32-39
         const tokenIdBytes = Buffer.alloc(8);
@@ -378,14 +784,14 @@ describe("Universal NFT Program - Solana to ZetaChain Transfer", () => {
           message.fill(0, 108 + uriBytes.length, 108 + uriBytes.length + padding);
         }
         
-        return message;
+        return Buffer.concat([Buffer.from([MESSAGE_VERSION_V1]), message]);
       };
 
       // Simulate receiving a cross-chain message from ZetaChain
       const incomingTokenId = Date.now(); // Use unique token ID to avoid conflicts
       const incomingUri = "https://example.com/incoming-nft.json";
       const incomingMessage = createFormattedMessage(
-        zetaChainRecipient, // receiver (20 bytes)
+        admin.publicKey.toBytes(), // receiver (32 bytes)
         incomingTokenId,     // token ID
         incomingUri,         // URI
         solanaSender         // sender (20 bytes)
@@ -397,14 +803,8 @@ describe("Universal NFT Program - Solana to ZetaChain Transfer", () => {
         program.programId
       )[0];
 
-      // Create mint for incoming NFT with admin as mint authority
-      const incomingMint = await createMint(
-        connection,
-        admin, // payer
-        admin.publicKey, // mint authority (this should match the payer)
-        admin.publicKey, // freeze authority
-        0 // decimals
-      );
+      // Incoming NFTs land at a deterministic PDA mint created by the program
+      const incomingMint = bridgedMintPda(incomingTokenId);
 
       // Derive the ATA address (don't create it)
       const recipientTokenAccount = await getAssociatedTokenAddress(
@@ -415,20 +815,34 @@ describe("Universal NFT Program - Solana to ZetaChain Transfer", () => {
 
       // Process incoming cross-chain message
       await program.methods
-        .receiveCrossChainMessage(new BN(incomingTokenId), incomingMessage)
+        .receiveCrossChainMessage(incomingMessage, null)
         .accounts({
           programState: programStatePda,
           nftOrigin: incomingNftOriginPda,
           mint: incomingMint,
-          mintAuthority: admin.publicKey,
+          dedupWindow: dedupWindowPda(incomingUri),
+          mintAuthority: mintAuthorityPda,
+          recipient: admin.publicKey,
+          sourceChainConfig: chainConfigPda(zetaChainId),
+          provisionalEscrow: null,
+          custodialTokenAccount: null,
+          inboundOffer: null,
+          pendingInbound: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
           recipientTokenAccount: recipientTokenAccount, // Pass the derived ATA address
           payer: admin.publicKey,
+          gateway: gatewaySigner.publicKey,
           systemProgram: SystemProgram.programId,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           rent: SYSVAR_RENT_PUBKEY,
+          hourlyMetrics: null,
+          inboundValueClaim: null,
+          inboundReceipt: inboundReceiptPda(incomingMessage),
         })
-        .signers([admin])
+        .remainingAccounts(originPolicyAccounts)
+        .signers([admin, gatewaySigner])
         .rpc();
 
       console.log("Incoming cross-chain message processed successfully");
@@ -439,125 +853,616 @@ describe("Universal NFT Program - Solana to ZetaChain Transfer", () => {
 
       console.log("Incoming NFT successfully minted on Solana");
     });
-  });
 
-  describe("Phase 4.5: Real ZetaChain Integration & Cross-Chain Transfer", () => {
-    it("Should perform REAL cross-chain transfer: Solana → ZetaChain (mint → burn → mint)", async () => {
-      if (!isProgramInitialized || !testTokenId) {
-        console.log("Program not ready, skipping real ZetaChain integration test");
+    it("Should refuse deliveries the gateway didn't sign or that name another recipient", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping inbound authentication test");
         return;
       }
 
-      console.log("=== REAL ZETACHAIN CROSS-CHAIN TRANSFER TEST ===");
-      console.log("This test will perform ACTUAL cross-chain transfer with real blockchain interactions");
-      
-      // Step 1: Create and mint NFT on Solana (REAL)
-      console.log("1. Creating and minting NFT on Solana...");
-      const realTransferTokenId = Date.now() % 1000000 + 5000;
-      const realTransferMint = await createMint(
-        connection,
-        admin,
-        admin.publicKey,
-        admin.publicKey,
-        0
-      );
-      const realTransferTokenAccount = await createAssociatedTokenAccount(
-        connection,
-        admin,
-        realTransferMint,
-        admin.publicKey
-      );
-      
-      // Mint 1 token on Solana (REAL)
-      await mintTo(
-        connection,
-        admin,
-        realTransferMint,
-        realTransferTokenAccount,
-        admin,
-        1
-      );
-      
-      // Verify NFT exists on Solana (REAL verification)
-      const initialSolanaBalance = await getAccount(connection, realTransferTokenAccount);
-      assert.equal(Number(initialSolanaBalance.amount), 1, "NFT must exist on Solana before transfer");
-      console.log("✅ NFT successfully minted on Solana (verified)");
-      
-      // Step 2: Initiate REAL cross-chain transfer through Solana Gateway
-      console.log("2. Initiating REAL cross-chain transfer through Solana Gateway...");
-      
-      // Create the cross-chain transfer message (REAL message format)
-      const realCrossChainMessage = createZetaChainSuccessMessage(
-        realTransferTokenId,
-        "https://arweave.net/real-zetachain-nft.json",
-        zetaChainRecipient,
-        solanaSender
-      );
-      
-      // Call the REAL Solana Gateway program to initiate transfer
+      const tokenId = Date.now() + 13;
+      const uri = `https://example.com/forged/${tokenId}.json`;
+      const mint = bridgedMintPda(tokenId);
+      const deliver = (message: Buffer, recipient: PublicKey, gateway: Keypair | null) => program.methods
+        .receiveCrossChainMessage(message, null)
+        .accounts({
+          programState: programStatePda,
+          nftOrigin: PublicKey.findProgramAddressSync(
+            [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+            program.programId
+          )[0],
+          mint,
+          dedupWindow: dedupWindowPda(uri),
+          mintAuthority: mintAuthorityPda,
+          recipient,
+          sourceChainConfig: chainConfigPda(zetaChainId),
+          provisionalEscrow: null,
+          custodialTokenAccount: null,
+          inboundOffer: null,
+          pendingInbound: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          recipientTokenAccount: getAssociatedTokenAddressSync(mint, recipient, false),
+          payer: user.publicKey,
+          gateway: gateway?.publicKey ?? null,
+          hourlyMetrics: null,
+          inboundValueClaim: null,
+          inboundReceipt: inboundReceiptPda(message),
+        })
+        .remainingAccounts(originPolicyAccounts)
+        .signers(gateway ? [user, gateway] : [user])
+        .rpc();
+      const forged = createZetaChainSuccessMessage(tokenId, uri, user.publicKey.toBytes(), solanaSender);
+
+      for (const gateway of [null, user]) {
+        try {
+          await deliver(forged, user.publicKey, gateway);
+          assert.fail("Only the gateway can deliver an NFT");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "Unauthorized");
+        }
+      }
+      // Even the gateway's delivery goes only to the wallet its message names
       try {
-        // This would be the actual call to the Solana Gateway program
-        // For now, we'll simulate the gateway call but verify the message format
-        console.log("📡 Sending cross-chain message to Solana Gateway...");
-        console.log(`Gateway Address: ${zetaChainTestnetGateway}`);
-        console.log(`Message Size: ${realCrossChainMessage.length} bytes`);
-        
-        // Verify message format is correct for ZetaChain
-        // Note: In production, this would verify the message through the actual ZetaChain gateway
-        console.log("📋 Cross-chain message format verified for ZetaChain");
-        console.log(`Message contains: ${realCrossChainMessage.length} bytes of cross-chain data`);
-        console.log("✅ Cross-chain message format verified for ZetaChain");
-        
-        // Step 3: REAL NFT burn on Solana (through gateway)
-        console.log("3. Performing REAL NFT burn on Solana through gateway...");
-        
-        // In production, this would be handled by the Solana Gateway program
-        // For testing, we need to actually burn the token, not just set balance to 0
-        // We'll use the burn instruction to actually destroy the token
-        
-        // First, verify the token exists
-        const beforeBurnBalance = await getAccount(connection, realTransferTokenAccount);
-        assert.equal(Number(beforeBurnBalance.amount), 1, "NFT must exist before burning");
-        
-        // Actually burn the token using the burn instruction
-        await burn(
-          connection,
-          realTransferTokenAccount, // Token account to burn from
-          realTransferMint,         // Mint to burn
-          admin,                    // Authority (must be mint authority)
-          [],                       // No additional signers
-          1                         // Amount to burn (1 NFT)
-        );
-        
-        // Verify NFT is actually burned on Solana (REAL verification)
-        const afterBurnSolanaBalance = await getAccount(connection, realTransferTokenAccount);
-        assert.equal(Number(afterBurnSolanaBalance.amount), 0, "NFT must be completely burned on Solana after transfer");
-        console.log("✅ NFT successfully burned on Solana (REAL verification)");
-        
-        // Step 4: REAL ZetaChain integration verification
-        console.log("4. Verifying REAL ZetaChain integration...");
-        
-        // This is where we would normally wait for ZetaChain to process the message
-        // and mint the corresponding NFT. For now, we'll verify the infrastructure is ready
-        
-        // Verify Solana Gateway is properly configured
-        assert.notEqual(zetaChainTestnetGateway, "11111111111111111111111111111111", "Solana Gateway must be properly configured");
-        console.log("✅ Solana Gateway properly configured");
-        
-        // Verify cross-chain message was created and formatted correctly
-        assert(realCrossChainMessage.length > 0, "Cross-chain message must be created");
-        console.log("✅ Cross-chain message created and ready for ZetaChain");
-        
-        // Step 5: Complete transfer cycle verification
-        console.log("5. Verifying complete REAL cross-chain transfer cycle...");
-        
-        // Verify the complete real cycle:
-        // ✅ Solana: NFT minted (verified)
-        // ✅ Solana: NFT burned (verified) 
-        // ✅ Gateway: Message sent (verified)
-        // ✅ ZetaChain: Ready to receive (infrastructure verified)
-        
-        console.log("✅ Complete REAL cross-chain transfer cycle verified");
+        await deliver(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender), user.publicKey, gatewaySigner);
+        assert.fail("The recipient must be the message's receiver");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "InboundRecipientMismatch");
+      }
+      assert.isNull(await connection.getAccountInfo(mint));
+    });
+
+    it("Should deliver the same token to the same mint on every round trip", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping deterministic mint test");
+        return;
+      }
+
+      const tokenId = Date.now() + 7;
+      const uri = "https://example.com/round-trip-nft.json";
+      const nftOriginPda = PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      const mint = bridgedMintPda(tokenId);
+      const recipientTokenAccount = await getAssociatedTokenAddress(mint, admin.publicKey, false);
+
+      const deliver = () =>
+        program.methods
+          .receiveCrossChainMessage(
+            createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender),
+            null
+          )
+          .accounts({
+            programState: programStatePda,
+            nftOrigin: nftOriginPda,
+            mint,
+            dedupWindow: dedupWindowPda(uri),
+            mintAuthority: mintAuthorityPda,
+            recipient: admin.publicKey,
+            sourceChainConfig: chainConfigPda(zetaChainId),
+            provisionalEscrow: null,
+            custodialTokenAccount: null,
+            inboundOffer: null,
+            pendingInbound: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            recipientTokenAccount,
+            payer: admin.publicKey,
+            gateway: gatewaySigner.publicKey,
+            hourlyMetrics: null,
+            inboundValueClaim: null,
+            inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender)),
+          })
+          .remainingAccounts(originPolicyAccounts)
+          .signers([admin, gatewaySigner])
+          .rpc();
+
+      // First arrival
+      await deliver();
+      const firstOrigin = await program.account.nftOrigin.fetch(nftOriginPda);
+      assert.equal(firstOrigin.mint.toString(), mint.toString());
+
+      // Depart again (burns the token)
+      await program.methods
+        .transferCrossChain(new BN(tokenId), zetaChainId, Buffer.from(zetaChainRecipient), null, null, null)
+        .accounts({
+          nftOrigin: nftOriginPda,
+          mint,
+          userTokenAccount: recipientTokenAccount,
+          user: admin.publicKey,
+          gatewayProgram: new PublicKey(zetaChainTestnetGateway),
+          feeConfig: feeConfigPda,
+          feeTreasury,
+          chainGasConfig: chainConfigPda(zetaChainId),
+          registry: null,
+          receiptConfig: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          zrc20GasVault: null,
+          zrc20VaultTokenAccount: null,
+          gasFeeDestination: null,
+          hourlyMetrics: null,
+          insuranceOptIn: null,
+          coOwnership: null,
+          coOwnerConsent: null,
+        })
+        .signers([admin])
+        .rpc();
+      assert.equal(Number((await getAccount(connection, recipientTokenAccount)).amount), 0);
+
+      // Second arrival lands at the same mint
+      await deliver();
+      const secondOrigin = await program.account.nftOrigin.fetch(nftOriginPda);
+      assert.equal(secondOrigin.mint.toString(), mint.toString());
+      assert.equal(Number((await getAccount(connection, recipientTokenAccount)).amount), 1);
+
+      // Replaying the delivery while the token is live is rejected
+      try {
+        await deliver();
+        assert.fail("Replayed delivery should be rejected");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "NFTAlreadyDelivered");
+      }
+    });
+
+    it("Should reject messages with an unknown version byte", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping message version test");
+        return;
+      }
+
+      const message = createZetaChainSuccessMessage(Date.now() + 11, "https://example.com/v7.json", zetaChainRecipient, solanaSender);
+      message[0] = 0x07;
+      // A message that doesn't parse has no token id or URI, so its accounts derive from zero
+      const tokenId = 0;
+      const mint = bridgedMintPda(tokenId);
+
+      try {
+        await program.methods
+          .receiveCrossChainMessage(message, null)
+          .accounts({
+            programState: programStatePda,
+            nftOrigin: PublicKey.findProgramAddressSync(
+              [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+              program.programId
+            )[0],
+            mint,
+            dedupWindow: PublicKey.findProgramAddressSync(
+              [Buffer.from("dedup_window"), Buffer.alloc(32)],
+              program.programId
+            )[0],
+            mintAuthority: mintAuthorityPda,
+            recipient: admin.publicKey,
+            sourceChainConfig: chainConfigPda(zetaChainId),
+            provisionalEscrow: null,
+            custodialTokenAccount: null,
+            inboundOffer: null,
+            pendingInbound: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            recipientTokenAccount: await getAssociatedTokenAddress(mint, admin.publicKey, false),
+            payer: admin.publicKey,
+            gateway: gatewaySigner.publicKey,
+            hourlyMetrics: null,
+            inboundValueClaim: null,
+            inboundReceipt: inboundReceiptPda(message),
+          })
+          .remainingAccounts(originPolicyAccounts)
+          .signers([admin, gatewaySigner])
+          .rpc();
+        assert.fail("Unknown message version should be rejected");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "UnknownMessageVersion");
+      }
+    });
+
+    it("Should derive receive accounts from the token id inside the message", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping token id derivation test");
+        return;
+      }
+
+      const tokenId = Date.now() + 12;
+      const otherTokenId = tokenId + 1;
+      const message = createZetaChainSuccessMessage(tokenId, "https://example.com/source.json", admin.publicKey.toBytes(), solanaSender);
+      const receiveWith = (accountsTokenId: number) => {
+        const mint = bridgedMintPda(accountsTokenId);
+        return program.methods
+          .receiveCrossChainMessage(message, null)
+          .accounts({
+            programState: programStatePda,
+            nftOrigin: PublicKey.findProgramAddressSync(
+              [Buffer.from("nft_origin"), new BN(accountsTokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+              program.programId
+            )[0],
+            mint,
+            dedupWindow: dedupWindowPda("https://example.com/source.json"),
+            mintAuthority: mintAuthorityPda,
+            recipient: admin.publicKey,
+            sourceChainConfig: chainConfigPda(zetaChainId),
+            provisionalEscrow: null,
+            custodialTokenAccount: null,
+            inboundOffer: null,
+            pendingInbound: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            recipientTokenAccount: getAssociatedTokenAddressSync(mint, admin.publicKey, false),
+            payer: admin.publicKey,
+            gateway: gatewaySigner.publicKey,
+            hourlyMetrics: null,
+            inboundValueClaim: null,
+            inboundReceipt: inboundReceiptPda(message),
+          })
+          .remainingAccounts(originPolicyAccounts)
+          .signers([admin, gatewaySigner])
+          .rpc();
+      };
+
+      // Accounts for any other token id don't match the message, so nothing is created for them
+      try {
+        await receiveWith(otherTokenId);
+        assert.fail("Accounts for another token id should be rejected");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "ConstraintSeeds");
+      }
+      assert.isNull(await connection.getAccountInfo(bridgedMintPda(otherTokenId)));
+
+      await receiveWith(tokenId);
+      const nftOrigin = await program.account.nftOrigin.fetch(PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0]);
+      assert.equal(nftOrigin.tokenId.toNumber(), tokenId);
+    });
+
+    it("Should skip a bad message in a batch and deliver the rest", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping batch receive test");
+        return;
+      }
+
+      const goodTokenId = Date.now() + 13;
+      const badTokenId = goodTokenId + 1;
+      // The bad message names another wallet than the one its accounts deliver to
+      const messages = [
+        {
+          tokenId: new BN(goodTokenId),
+          message: createZetaChainSuccessMessage(goodTokenId, "https://example.com/batch.json", admin.publicKey.toBytes(), solanaSender),
+        },
+        {
+          tokenId: new BN(badTokenId),
+          message: createZetaChainSuccessMessage(badTokenId, "https://example.com/batch.json", Keypair.generate().publicKey.toBytes(), solanaSender),
+        },
+      ];
+      const batchAccounts = (tokenId: number, message: Buffer) => {
+        const mint = bridgedMintPda(tokenId);
+        return [
+          PublicKey.findProgramAddressSync(
+            [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+            program.programId
+          )[0],
+          PublicKey.findProgramAddressSync([Buffer.from("nft_index"), mint.toBuffer()], program.programId)[0],
+          mint,
+          admin.publicKey,
+          getAssociatedTokenAddressSync(mint, admin.publicKey, false),
+          dedupWindowPda("https://example.com/batch.json"),
+          inboundReceiptPda(message),
+        ].map((pubkey, i) => ({ pubkey, isSigner: false, isWritable: i !== 3 }));
+      };
+      const receiveBatch = (gateway: Keypair) =>
+        program.methods
+          .batchReceiveCrossChain(messages)
+          .accounts({
+            programState: programStatePda,
+            mintAuthority: mintAuthorityPda,
+            sourceChainConfig: chainConfigPda(zetaChainId),
+            payer: admin.publicKey,
+            gateway: gateway.publicKey,
+          })
+          .remainingAccounts(messages.flatMap(({ tokenId, message }) => batchAccounts(tokenId.toNumber(), message)))
+          .signers([admin, gateway])
+          .rpc({ commitment: "confirmed" });
+
+      // Only the gateway hands over batches
+      try {
+        await receiveBatch(Keypair.generate());
+        assert.fail("A batch the gateway didn't sign should be rejected");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "Unauthorized");
+      }
+
+      const signature = await receiveBatch(gatewaySigner);
+
+      const goodTokenAccount = getAssociatedTokenAddressSync(bridgedMintPda(goodTokenId), admin.publicKey, false);
+      assert.equal(Number((await getAccount(connection, goodTokenAccount)).amount), 1);
+      // Batched deliveries can be replayed like single ones
+      const receipt = await program.account.inboundReceipt.fetch(inboundReceiptPda(messages[0].message));
+      assert.equal(receipt.tokenId.toNumber(), goodTokenId);
+      assert.isNull(await connection.getAccountInfo(inboundReceiptPda(messages[1].message)));
+
+      const tx = await connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const events = Array.from(parser.parseLogs(tx.meta.logMessages));
+      const failed = events.filter((event) => event.name === "receiveFailed");
+      assert.equal(failed.length, 1);
+      assert.equal(failed[0].data.tokenId.toNumber(), badTokenId);
+    });
+
+    it("Should refuse value on an entry of a batch receive", async () => {
+      assert.isTrue(isProgramInitialized, "The setup tests should have initialized the program");
+
+      // Batches have no per-entry escrow, so value riding on an entry would never be paid
+      const tokenId = Date.now() + 1187;
+      const uri = `https://example.com/batch-value/${tokenId}.json`;
+      const mint = bridgedMintPda(tokenId);
+      const message = withValue(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender), 1000000);
+      const signature = await program.methods
+        .batchReceiveCrossChain([{ tokenId: new BN(tokenId), message }])
+        .accounts({
+          programState: programStatePda,
+          mintAuthority: mintAuthorityPda,
+          sourceChainConfig: chainConfigPda(zetaChainId),
+          payer: admin.publicKey,
+          gateway: gatewaySigner.publicKey,
+        })
+        .remainingAccounts([
+          nftOriginPda(tokenId),
+          PublicKey.findProgramAddressSync([Buffer.from("nft_index"), mint.toBuffer()], program.programId)[0],
+          mint,
+          admin.publicKey,
+          getAssociatedTokenAddressSync(mint, admin.publicKey, false),
+          dedupWindowPda(uri),
+          inboundReceiptPda(message),
+        ].map((pubkey, i) => ({ pubkey, isSigner: false, isWritable: i !== 3 })))
+        .signers([admin, gatewaySigner])
+        .rpc({ commitment: "confirmed" });
+
+      const tx = await connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const failed = Array.from(parser.parseLogs(tx.meta.logMessages)).filter((event) => event.name === "receiveFailed");
+      const expected = program.idl.errors.find((error) => error.name.toLowerCase() === "valuenotbatchable");
+      assert.equal(failed.length, 1);
+      assert.equal(failed[0].data.tokenId.toNumber(), tokenId);
+      assert.equal(failed[0].data.errorCode, expected.code);
+      assert.isNull(await connection.getAccountInfo(mint));
+    });
+
+    it("Should deliver every entry of a batch message or none of them", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping batch message test");
+        return;
+      }
+
+      // Empty URIs and the provider wallet as payer and recipient keep two entries and their
+      // receipts inside one legacy transaction. Each entry names its recipient's full 32-byte key
+      const batchMessage = (tokenIds: number[], recipients: PublicKey[] = tokenIds.map(() => wallet.publicKey)) => Buffer.concat([
+        Buffer.from([0xff, tokenIds.length]),
+        ...tokenIds.map((tokenId, i) => {
+          const body = createZetaChainSuccessMessage(tokenId, "", recipients[i].toBytes(), solanaSender).subarray(1);
+          const length = Buffer.alloc(4);
+          length.writeUInt32BE(body.length, 0);
+          return Buffer.concat([length, body]);
+        }),
+      ]);
+      const entryAccounts = (tokenId: number) => {
+        const mint = bridgedMintPda(tokenId);
+        return [
+          PublicKey.findProgramAddressSync(
+            [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+            program.programId
+          )[0],
+          PublicKey.findProgramAddressSync([Buffer.from("nft_index"), mint.toBuffer()], program.programId)[0],
+          mint,
+          wallet.publicKey,
+          getAssociatedTokenAddressSync(mint, wallet.publicKey, false),
+          dedupWindowPda(""),
+          // Each entry's receipt is keyed by the entry as a standalone V1 message
+          inboundReceiptPda(createZetaChainSuccessMessage(tokenId, "", wallet.publicKey.toBytes(), solanaSender)),
+        ].map((pubkey, i) => ({ pubkey, isSigner: false, isWritable: i !== 3 }));
+      };
+      const receiveBatch = (tokenIds: number[], message = batchMessage(tokenIds)) =>
+        program.methods
+          .receiveBatchTransfer(message)
+          .accounts({
+            programState: programStatePda,
+            mintAuthority: mintAuthorityPda,
+            sourceChainConfig: chainConfigPda(zetaChainId),
+            payer: wallet.publicKey,
+            gateway: gatewaySigner.publicKey,
+          })
+          .remainingAccounts(tokenIds.flatMap(entryAccounts))
+          .signers([gatewaySigner])
+          .rpc({ commitment: "confirmed" });
+
+      const baseId = Date.now() + 227;
+      const tokenIds = [baseId, baseId + 1];
+
+      // Entries naming another wallet can't be delivered to the one the accounts carry
+      try {
+        const stranger = Keypair.generate().publicKey;
+        await receiveBatch(tokenIds, batchMessage(tokenIds, tokenIds.map(() => stranger)));
+        assert.fail("Each entry should go to the wallet its own body names");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "InboundRecipientMismatch");
+      }
+      assert.isNull(await connection.getAccountInfo(bridgedMintPda(tokenIds[0])));
+
+      const signature = await receiveBatch(tokenIds);
+      for (const tokenId of tokenIds) {
+        const tokenAccount = getAssociatedTokenAddressSync(bridgedMintPda(tokenId), wallet.publicKey, false);
+        assert.equal(Number((await getAccount(connection, tokenAccount)).amount), 1);
+      }
+      const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const events = Array.from(parser.parseLogs(tx.meta.logMessages));
+      assert.deepEqual(
+        events.filter((event) => event.name === "crossChainMessageReceived").map((event) => event.data.tokenId.toNumber()),
+        tokenIds
+      );
+      const summary = events.find((event) => event.name === "batchReceived");
+      assert.ok(summary, "Expected a BatchReceived event");
+      assert.equal(summary.data.count, 2);
+      const receipt = await program.account.inboundReceipt.fetch(entryAccounts(tokenIds[1])[6].pubkey);
+      assert.equal(receipt.tokenId.toNumber(), tokenIds[1]);
+      assert.deepEqual(
+        Buffer.from(summary.data.messageHash),
+        createHash("sha256").update(batchMessage(tokenIds)).digest()
+      );
+
+      // One entry over MAX_BATCH_RECEIVE is refused before any account is looked at
+      const oversized = Array.from({ length: 6 }, (_, i) => baseId + 10 + i);
+      try {
+        await program.methods
+          .receiveBatchTransfer(batchMessage(oversized))
+          .accounts({
+            programState: programStatePda,
+            mintAuthority: mintAuthorityPda,
+            sourceChainConfig: chainConfigPda(zetaChainId),
+            payer: wallet.publicKey,
+            gateway: gatewaySigner.publicKey,
+          })
+          .signers([gatewaySigner])
+          .rpc();
+        assert.fail("A batch over MAX_BATCH_RECEIVE should be rejected");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "BatchTooLarge");
+      }
+      assert.isNull(await connection.getAccountInfo(bridgedMintPda(oversized[0])));
+    });
+  });
+
+  describe("Phase 4.5: Real ZetaChain Integration & Cross-Chain Transfer", () => {
+    it("Should perform REAL cross-chain transfer: Solana → ZetaChain (mint → burn → mint)", async () => {
+      if (!isProgramInitialized || !testTokenId) {
+        console.log("Program not ready, skipping real ZetaChain integration test");
+        return;
+      }
+
+      console.log("=== REAL ZETACHAIN CROSS-CHAIN TRANSFER TEST ===");
+      console.log("This test will perform ACTUAL cross-chain transfer with real blockchain interactions");
+      
+      // Step 1: Create and mint NFT on Solana (REAL)
+      console.log("1. Creating and minting NFT on Solana...");
+      const realTransferTokenId = Date.now() % 1000000 + 5000;
+      const realTransferMint = await createMint(
+        connection,
+        admin,
+        admin.publicKey,
+        admin.publicKey,
+        0
+      );
+      const realTransferTokenAccount = await createAssociatedTokenAccount(
+        connection,
+        admin,
+        realTransferMint,
+        admin.publicKey
+      );
+      
+      // Mint 1 token on Solana (REAL)
+      await mintTo(
+        connection,
+        admin,
+        realTransferMint,
+        realTransferTokenAccount,
+        admin,
+        1
+      );
+      
+      // Verify NFT exists on Solana (REAL verification)
+      const initialSolanaBalance = await getAccount(connection, realTransferTokenAccount);
+      assert.equal(Number(initialSolanaBalance.amount), 1, "NFT must exist on Solana before transfer");
+      console.log("✅ NFT successfully minted on Solana (verified)");
+      
+      // Step 2: Initiate REAL cross-chain transfer through Solana Gateway
+      console.log("2. Initiating REAL cross-chain transfer through Solana Gateway...");
+      
+      // Create the cross-chain transfer message (REAL message format)
+      const realCrossChainMessage = createZetaChainSuccessMessage(
+        realTransferTokenId,
+        "https://arweave.net/real-zetachain-nft.json",
+        zetaChainRecipient,
+        solanaSender
+      );
+      
+      // Call the REAL Solana Gateway program to initiate transfer
+      try {
+        // This would be the actual call to the Solana Gateway program
+        // For now, we'll simulate the gateway call but verify the message format
+        console.log("📡 Sending cross-chain message to Solana Gateway...");
+        console.log(`Gateway Address: ${zetaChainTestnetGateway}`);
+        console.log(`Message Size: ${realCrossChainMessage.length} bytes`);
+        
+        // Verify message format is correct for ZetaChain
+        // Note: In production, this would verify the message through the actual ZetaChain gateway
+        console.log("📋 Cross-chain message format verified for ZetaChain");
+        console.log(`Message contains: ${realCrossChainMessage.length} bytes of cross-chain data`);
+        console.log("✅ Cross-chain message format verified for ZetaChain");
+        
+        // Step 3: REAL NFT burn on Solana (through gateway)
+        console.log("3. Performing REAL NFT burn on Solana through gateway...");
+        
+        // In production, this would be handled by the Solana Gateway program
+        // For testing, we need to actually burn the token, not just set balance to 0
+        // We'll use the burn instruction to actually destroy the token
+        
+        // First, verify the token exists
+        const beforeBurnBalance = await getAccount(connection, realTransferTokenAccount);
+        assert.equal(Number(beforeBurnBalance.amount), 1, "NFT must exist before burning");
+        
+        // Actually burn the token using the burn instruction
+        await burn(
+          connection,
+          realTransferTokenAccount, // Token account to burn from
+          realTransferMint,         // Mint to burn
+          admin,                    // Authority (must be mint authority)
+          [],                       // No additional signers
+          1                         // Amount to burn (1 NFT)
+        );
+        
+        // Verify NFT is actually burned on Solana (REAL verification)
+        const afterBurnSolanaBalance = await getAccount(connection, realTransferTokenAccount);
+        assert.equal(Number(afterBurnSolanaBalance.amount), 0, "NFT must be completely burned on Solana after transfer");
+        console.log("✅ NFT successfully burned on Solana (REAL verification)");
+        
+        // Step 4: REAL ZetaChain integration verification
+        console.log("4. Verifying REAL ZetaChain integration...");
+        
+        // This is where we would normally wait for ZetaChain to process the message
+        // and mint the corresponding NFT. For now, we'll verify the infrastructure is ready
+        
+        // Verify Solana Gateway is properly configured
+        assert.notEqual(zetaChainTestnetGateway, "11111111111111111111111111111111", "Solana Gateway must be properly configured");
+        console.log("✅ Solana Gateway properly configured");
+        
+        // Verify cross-chain message was created and formatted correctly
+        assert(realCrossChainMessage.length > 0, "Cross-chain message must be created");
+        console.log("✅ Cross-chain message created and ready for ZetaChain");
+        
+        // Step 5: Complete transfer cycle verification
+        console.log("5. Verifying complete REAL cross-chain transfer cycle...");
+        
+        // Verify the complete real cycle:
+        // ✅ Solana: NFT minted (verified)
+        // ✅ Solana: NFT burned (verified) 
+        // ✅ Gateway: Message sent (verified)
+        // ✅ ZetaChain: Ready to receive (infrastructure verified)
+        
+        console.log("✅ Complete REAL cross-chain transfer cycle verified");
                  console.log("✅ NFT successfully transferred from Solana to ZetaChain");
          console.log("✅ Real blockchain interactions completed");
          
@@ -699,8 +1604,11 @@ describe("Universal NFT Program - Solana to ZetaChain Transfer", () => {
         await program.methods
           .transferCrossChain(
             new BN(failureTestTokenId),
-            Array.from(new Uint8Array(20).fill(0)), // Invalid recipient (zero address)
-            Array.from(zetaChainZRC20)
+            zetaChainId,
+            Buffer.from(new Uint8Array(20).fill(0)), // Invalid recipient (zero address)
+            null,
+            null,
+            null
           )
           .accounts({
             nftOrigin: PublicKey.findProgramAddressSync(
@@ -711,6 +1619,20 @@ describe("Universal NFT Program - Solana to ZetaChain Transfer", () => {
             userTokenAccount: failureTestTokenAccount,
             user: admin.publicKey,
             gatewayProgram: new PublicKey(zetaChainTestnetGateway),
+            feeConfig: feeConfigPda,
+            feeTreasury,
+            chainGasConfig: chainConfigPda(zetaChainId),
+            registry: null,
+            receiptConfig: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            zrc20GasVault: null,
+            zrc20VaultTokenAccount: null,
+            gasFeeDestination: null,
+            hourlyMetrics: null,
+            insuranceOptIn: null,
+            coOwnership: null,
+            coOwnerConsent: null,
           })
           .signers([admin])
           .rpc();
@@ -727,7 +1649,7 @@ describe("Universal NFT Program - Solana to ZetaChain Transfer", () => {
       const zetaChainFailureMessage = createZetaChainFailureMessage(
         failureTestTokenId,
         "Transfer failed: Invalid recipient address",
-        zetaChainRecipient,
+        admin.publicKey.toBytes(),
         solanaSender
       );
       
@@ -737,13 +1659,7 @@ describe("Universal NFT Program - Solana to ZetaChain Transfer", () => {
         program.programId
       )[0];
 
-      const failureMint = await createMint(
-        connection,
-        admin,
-        admin.publicKey,
-        admin.publicKey,
-        0
-      );
+      const failureMint = bridgedMintPda(failureTestTokenId);
 
       const failureTokenAccount = await getAssociatedTokenAddress(
         failureMint,
@@ -753,19 +1669,33 @@ describe("Universal NFT Program - Solana to ZetaChain Transfer", () => {
 
       try {
         await program.methods
-          .receiveCrossChainMessage(new BN(failureTestTokenId), zetaChainFailureMessage)
+          .receiveCrossChainMessage(zetaChainFailureMessage, null)
           .accounts({
             nftOrigin: failureNftOriginPda,
             mint: failureMint,
-            mintAuthority: admin.publicKey,
+            dedupWindow: dedupWindowPda("Transfer failed: Invalid recipient address"),
+            mintAuthority: mintAuthorityPda,
+            recipient: admin.publicKey,
+            sourceChainConfig: chainConfigPda(zetaChainId),
+            provisionalEscrow: null,
+            custodialTokenAccount: null,
+            inboundOffer: null,
+            pendingInbound: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
             recipientTokenAccount: failureTokenAccount,
             payer: admin.publicKey,
+            gateway: gatewaySigner.publicKey,
             systemProgram: SystemProgram.programId,
             tokenProgram: TOKEN_PROGRAM_ID,
             associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
             rent: SYSVAR_RENT_PUBKEY,
+            hourlyMetrics: null,
+            inboundValueClaim: null,
+            inboundReceipt: inboundReceiptPda(zetaChainFailureMessage),
           })
-          .signers([admin])
+          .remainingAccounts(originPolicyAccounts)
+          .signers([admin, gatewaySigner])
           .rpc();
 
         console.log("✅ ZetaChain failure message processed successfully");
@@ -897,227 +1827,7741 @@ describe("Universal NFT Program - Solana to ZetaChain Transfer", () => {
         program.programId
       )[0];
 
-      const returnMint = await createMint(
-        connection,
-        admin,
-        admin.publicKey,
-        admin.publicKey,
-        0
-      );
+      const returnMint = bridgedMintPda(roundTripTokenId);
+
+      const returnTokenAccount = await getAssociatedTokenAddress(
+        returnMint,
+        admin.publicKey,
+        false
+      );
+
+      // Create return message for the round-trip simulation
+      const returnMessage = createZetaChainSuccessMessage(
+        roundTripTokenId,
+        "https://arweave.net/returned-metadata.json",
+        admin.publicKey.toBytes(), // Return to original Solana address
+        zetaChainRecipient
+      );
+      
+      await program.methods
+        .receiveCrossChainMessage(returnMessage, null)
+        .accounts({
+          nftOrigin: returnNftOriginPda,
+          mint: returnMint,
+          dedupWindow: dedupWindowPda("https://arweave.net/returned-metadata.json"),
+          mintAuthority: mintAuthorityPda,
+          recipient: admin.publicKey,
+          sourceChainConfig: chainConfigPda(zetaChainId),
+          provisionalEscrow: null,
+          custodialTokenAccount: null,
+          inboundOffer: null,
+          pendingInbound: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          recipientTokenAccount: returnTokenAccount,
+          payer: admin.publicKey,
+          gateway: gatewaySigner.publicKey,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          rent: SYSVAR_RENT_PUBKEY,
+          hourlyMetrics: null,
+          inboundValueClaim: null,
+          inboundReceipt: inboundReceiptPda(returnMessage),
+        })
+        .remainingAccounts(originPolicyAccounts)
+        .signers([admin, gatewaySigner])
+        .rpc();
+
+      console.log("✅ NFT returned from ZetaChain to Solana");
+      
+      // Verify NFT exists on Solana again
+      const finalReturnBalance = await getAccount(connection, returnTokenAccount);
+      assert.equal(Number(finalReturnBalance.amount), 1, "Returned NFT should exist on Solana");
+      
+      console.log("=== ROUND-TRIP TRANSFER COMPLETE ===");
+      console.log("✅ Solana → ZetaChain → Solana transfer cycle completed");
+      console.log("✅ NFT successfully moved across chains and back");
+      console.log("✅ Cross-chain functionality fully verified");
+    });
+  });
+
+  // Helper function to create ZetaChain success messages
+  function createZetaChainSuccessMessage(tokenId: number, uri: string, receiver: Uint8Array, sender: Uint8Array): Buffer {
+    const uriBytes = Buffer.from(uri, 'utf8');
+    const padding = (32 - (uriBytes.length % 32)) % 32;
+    const totalSize = 100 + 8 + uriBytes.length + padding;
+    
+    const message = Buffer.alloc(totalSize);
+    
+    // Receiver word - a Solana recipient's full 32-byte key, or an EVM address after 12 zero bytes
+    Buffer.from(receiver).copy(message, 32 - receiver.length);
+    
+    // Token ID (u64) - 8 bytes at bytes 32-39
+    const tokenIdBytes = Buffer.alloc(8);
+    tokenIdBytes.writeBigUInt64BE(BigInt(tokenId), 0);
+    tokenIdBytes.copy(message, 32);
+    
+    // URI offset (u64) - 8 bytes at bytes 64-71, should be 100
+    const offsetBytes = Buffer.alloc(8);
+    offsetBytes.writeBigUInt64BE(BigInt(100), 0);
+    offsetBytes.copy(message, 64);
+    
+    // Sender (address) - 12 bytes padding + 20 bytes address at bytes 80-99
+    message.fill(0, 72, 80);
+    Buffer.from(sender).copy(message, 80);
+    
+    // URI length and data starting at byte 100
+    const uriLengthBytes = Buffer.alloc(8);
+    uriLengthBytes.writeBigUInt64BE(BigInt(uriBytes.length), 0);
+    uriLengthBytes.copy(message, 100);
+    uriBytes.copy(message, 108);
+    
+    // Padding to make total length multiple of 32
+    if (padding > 0) {
+      message.fill(0, 108 + uriBytes.length, 108 + uriBytes.length + padding);
+    }
+    
+    return Buffer.concat([Buffer.from([MESSAGE_VERSION_V1]), message]);
+  }
+
+  // Value trailer: tag 9, then the lamports the gateway sent along (8 bytes, big-endian)
+  function withValue(message: Buffer, lamports: number): Buffer {
+    const trailer = Buffer.alloc(9);
+    trailer[0] = 9;
+    trailer.writeBigUInt64BE(BigInt(lamports), 1);
+    return Buffer.concat([message, trailer]);
+  }
+
+  // NFTOrigin's single seed hashes the same as its three parts passed separately
+  function nftOriginPda(tokenId: BN | number): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+      program.programId
+    )[0];
+  }
+
+  // Delivers a bridged NFT from ZetaChain to the admin's ATA, signed by the test gateway
+  function deliverToAdmin(tokenId: number, uri: string): Promise<string> {
+    const mint = bridgedMintPda(tokenId);
+    return program.methods
+      .receiveCrossChainMessage(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender), null)
+      .accounts({
+        programState: programStatePda,
+        nftOrigin: nftOriginPda(tokenId),
+        mint,
+        dedupWindow: dedupWindowPda(uri),
+        mintAuthority: mintAuthorityPda,
+        recipient: admin.publicKey,
+        sourceChainConfig: chainConfigPda(zetaChainId),
+        provisionalEscrow: null,
+        custodialTokenAccount: null,
+        inboundOffer: null,
+        pendingInbound: null,
+        receiptMint: null,
+        receiptTokenAccount: null,
+        recipientTokenAccount: getAssociatedTokenAddressSync(mint, admin.publicKey, false),
+        payer: admin.publicKey,
+        gateway: gatewaySigner.publicKey,
+        hourlyMetrics: null,
+        inboundValueClaim: null,
+        inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender)),
+      })
+      .remainingAccounts(originPolicyAccounts)
+      .signers([admin, gatewaySigner])
+      .rpc({ commitment: "confirmed" });
+  }
+
+  // Mints a fee-free native NFT with `minter` as its mint authority and the admin paying
+  function mintNative(tokenId: BN, minter: Keypair, mint: Keypair) {
+    return program.methods
+      .createMintAndNft(`https://example.com/native/${tokenId.toString()}.json`, 0, tokenId, null, null, null)
+      .accounts({
+        nftOrigin: nftOriginPda(tokenId),
+        mint: mint.publicKey,
+        mintAuthority: minter.publicKey,
+        payer: admin.publicKey,
+        feeConfig: feeConfigPda,
+        feeTreasury: null,
+        sellerFeeConfig: null,
+        feeCreator: null,
+        mintOnly: null,
+        lazyRevealConfig: null,
+        hourlyMetrics: null,
+      })
+      .signers([admin, minter, mint]);
+  }
+
+  // Helper function to create ZetaChain failure messages
+  function createZetaChainFailureMessage(tokenId: number, errorMessage: string, receiver: Uint8Array, sender: Uint8Array): Buffer {
+    // For failure messages, we'll use the error message as the URI
+    return createZetaChainSuccessMessage(tokenId, errorMessage, receiver, sender);
+  }
+
+  describe("Phase 5: Gateway Integration Testing", () => {
+    it("Should report decode results through debug_decode", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping debug_decode test");
+        return;
+      }
+
+      const debugDecode = (message: Buffer) =>
+        program.methods
+          .debugDecode(message)
+          .accounts({ programState: programStatePda })
+          .view();
+
+      const valid = createZetaChainSuccessMessage(42, "https://example.com/debug.json", zetaChainRecipient, solanaSender);
+      const decoded = await debugDecode(valid);
+      assert.equal(decoded.errorCode, 0);
+      assert.equal(decoded.tokenId.toNumber(), 42);
+      assert.equal(decoded.uri, "https://example.com/debug.json");
+      assert.equal(decoded.amount.toNumber(), 1);
+
+      // Trailers are read the way delivery reads them: amount (tag 7) and priority (tag 8)
+      const withTrailers = await debugDecode(Buffer.concat([
+        valid,
+        Buffer.from([7]),
+        new BN(5).toArrayLike(Buffer, 'be', 8),
+        Buffer.from([8, 9]),
+      ]));
+      assert.equal(withTrailers.errorCode, 0);
+      assert.equal(withTrailers.amount.toNumber(), 5);
+      assert.equal(withTrailers.priority, 9);
+
+      // Corrupted fixtures fail with the same errors the receive path returns
+      const unknownVersion = Buffer.from(valid);
+      unknownVersion[0] = 0x07;
+      const fixtures: [string, Buffer][] = [
+        ["InvalidMessageFormat", Buffer.alloc(0)],
+        ["UnknownMessageVersion", unknownVersion],
+        ["InvalidMessageFormat", valid.subarray(0, 60)],
+        ["InvalidCrossChainMessage", Buffer.from([0xff, 0x00])],
+        // A zero amount fails delivery after the body decodes
+        ["InvalidMessageFormat", Buffer.concat([valid, Buffer.from([7]), Buffer.alloc(8)])],
+      ];
+      for (const [expected, message] of fixtures) {
+        const result = await debugDecode(message);
+        assert.notEqual(result.errorCode, 0);
+        assert.equal(result.errorName, expected);
+      }
+    });
+
+    it("Should decode fixed wire fixtures into the same message fields", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping wire fixture test");
+        return;
+      }
+
+      // Byte-for-byte fixture of the V1 body layout; it must keep decoding unchanged
+      const body =
+        "00".repeat(12) + "11".repeat(20) +                 // receiver
+        "00".repeat(24) + "0000000000000007" +              // token id
+        "0000000000000064" + "00".repeat(8) +               // uri offset (100)
+        "22".repeat(20) +                                   // sender
+        "000000000000000e" +                                // uri length
+        Buffer.from("ipfs://fixture").toString("hex") + "00".repeat(6);
+      const fixtures = [
+        Buffer.from("01" + body, "hex"),
+        Buffer.from("02" + "0000000000000385" + body, "hex"), // V2 addressed to chain 901
+      ];
+
+      for (const fixture of fixtures) {
+        const decoded = await program.methods
+          .debugDecode(fixture)
+          .accounts({ programState: programStatePda })
+          .view();
+        assert.equal(decoded.errorCode, 0);
+        assert.deepEqual(Array.from(decoded.destination), new Array(20).fill(0));
+        assert.deepEqual(Array.from(decoded.receiver), new Array(20).fill(0x11));
+        assert.equal(decoded.tokenId.toNumber(), 7);
+        assert.equal(decoded.uri, "ipfs://fixture");
+        assert.deepEqual(Array.from(decoded.sender), new Array(20).fill(0x22));
+      }
+    });
+
+    it("Should describe its layout versions, tracking the stored version after a migration", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping schema test");
+        return;
+      }
+      const constant = (name: string) => Number(
+        program.idl.constants.find((c) => c.name.replace(/_/g, "").toLowerCase() === name).value
+      );
+
+      // Migration stamps the stored program state version
+      await program.methods.migrateProgramState().accounts({ payer: wallet.publicKey }).rpc({ commitment: "confirmed" });
+      const schema = await program.methods
+        .getSchema()
+        .accounts({ programState: programStatePda })
+        .view();
+
+      assert.equal(schema.programStateVersion, constant("programstateversion"));
+      assert.equal(schema.nftOriginVersion, constant("nftoriginversion"));
+      assert.equal(schema.eventSchema, constant("eventschemaversion"));
+      assert.deepEqual(Array.from(schema.messageVersionsSupported), [0x01, 0x02, 0xff]);
+
+      const cargoToml = fs.readFileSync(path.join(__dirname, "../programs/universal-nft/Cargo.toml"), "utf8");
+      const [, packageVersion] = cargoToml.match(/^version = "([^"]+)"/m);
+      assert.deepEqual(Array.from(schema.programVersion), packageVersion.split(/[-+]/)[0].split(".").map(Number));
+    });
+
+    it("Should flatten program config into one snapshot for dashboards", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping config snapshot test");
+        return;
+      }
+
+      const pda = (seed: string) => PublicKey.findProgramAddressSync([Buffer.from(seed)], program.programId)[0];
+      const state = await program.account.programState.fetch(programStatePda);
+      const feeConfig = await program.account.feeConfig.fetchNullable(pda("fee_config"));
+      const registry = await program.account.registry.fetchNullable(pda("registry"));
+      const securityState = await program.account.securityState.fetchNullable(pda("security_state"));
+
+      const configAccounts = [pda("fee_config"), pda("registry"), pda("security_state")];
+      if (feeConfig) {
+        configAccounts.push(feeConfig.treasury);
+      }
+      const snapshot = await program.methods
+        .getFullConfigSnapshot()
+        .accounts({ programState: programStatePda })
+        .remainingAccounts(configAccounts.map((pubkey) => ({ pubkey, isSigner: false, isWritable: false })))
+        .view();
+
+      assert.equal(snapshot.owner.toString(), state.owner.toString());
+      assert.equal(snapshot.gateway.toString(), state.gateway.toString());
+      assert.equal(snapshot.nextTokenId.toString(), state.nextTokenId.toString());
+      assert.equal(snapshot.paused, state.paused);
+      assert.equal(snapshot.gasLimit.toString(), state.gasLimit.toString());
+      assert.equal(snapshot.feeMintLamports.toString(), state.reservationFee.toString());
+      assert.equal(snapshot.feeTransferLamports.toString(), (feeConfig?.transferFeeLamports ?? 0).toString());
+      assert.equal(snapshot.connectedContractCount, registry?.count ?? 0);
+      assert.equal(snapshot.rateLimitWindow.toString(), (securityState?.windowSeconds ?? 0).toString());
+      assert.equal(snapshot.rateLimitMax, securityState?.failedDecodeThreshold ?? 0);
+      assert.equal(snapshot.migrationVersion, state.schemaVersion);
+      assert.equal(snapshot.network, "Solana Localnet");
+
+      // Anything that isn't a config account is refused rather than ignored
+      try {
+        await program.methods
+          .getFullConfigSnapshot()
+          .accounts({ programState: programStatePda })
+          .remainingAccounts([{ pubkey: programStatePda, isSigner: false, isWritable: false }])
+          .view();
+        assert.fail("A non-config account should be rejected");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "InvalidSnapshotAccounts");
+      }
+    });
+
+    it("Should number program errors from 6000 and core errors from 7000", async () => {
+      // ErrorCode is append-only: each variant keeps ERROR_CODE_OFFSET + its position
+      program.idl.errors.forEach((error, index) => {
+        assert.equal(error.code, 6000 + index, `${error.name} moved`);
+      });
+
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping error number checks");
+        return;
+      }
+      const debugDecode = (message: Buffer) =>
+        program.methods
+          .debugDecode(message)
+          .accounts({ programState: programStatePda })
+          .view();
+
+      const unknownVersion = createZetaChainSuccessMessage(42, "https://example.com/debug.json", zetaChainRecipient, solanaSender);
+      unknownVersion[0] = 0x07;
+      const programError = await debugDecode(unknownVersion);
+      const expected = program.idl.errors.find((error) => error.name.toLowerCase() === "unknownmessageversion");
+      assert.equal(programError.errorCode, expected.code);
+
+      // UniversalNFTCoreError::InvalidMessageFormat is the ninth core variant
+      const coreError = await debugDecode(Buffer.alloc(0));
+      assert.equal(coreError.errorName, "InvalidMessageFormat");
+      assert.equal(coreError.errorCode, 7008);
+    });
+
+    it("Should ping a connected contract and only take pongs from the gateway", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping connected contract ping test");
+        return;
+      }
+      // Registering the contract is owner-only
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping connected contract ping test");
+        return;
+      }
+
+      const registryPda = PublicKey.findProgramAddressSync([Buffer.from("registry")], program.programId)[0];
+      if (!(await connection.getAccountInfo(registryPda))) {
+        await program.methods.initRegistry().accounts({ admin: wallet.publicKey }).rpc();
+      }
+      const zrc20 = Array.from(new Uint8Array(20).fill(5));
+      await program.methods
+        .setRegistryEntry(zrc20, Buffer.from(new Uint8Array(20).fill(6)), new BN(97), 0)
+        .accounts({ admin: wallet.publicKey })
+        .rpc();
+
+      const signature = await program.methods
+        .pingConnectedContract(zrc20)
+        .accounts({ sender: admin.publicKey, relayerRole: null, gatewayProgram: new PublicKey(zetaChainTestnetGateway) })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+      const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const events = Array.from(parser.parseLogs(tx.meta.logMessages));
+      assert.isDefined(events.find((event) => event.name === "connectedContractPinged"));
+      // Never answered yet, so there is no silence to report
+      assert.isUndefined(events.find((event) => event.name === "connectedContractUnresponsive"));
+
+      // A pong echoing our ping, but not signed by the gateway
+      const echo = Buffer.alloc(8 + 32);
+      echo.writeBigInt64LE(BigInt(Math.floor(Date.now() / 1000)), 0);
+      program.programId.toBuffer().copy(echo, 8);
+      try {
+        await program.methods
+          .recordPong(zrc20, echo)
+          .accounts({ gateway: admin.publicKey })
+          .signers([admin])
+          .rpc();
+        assert.fail("A pong from outside the gateway should be rejected");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "Unauthorized");
+      }
+    });
+
+    it("Should limit cranks to relayers while the relayer allowlist is on", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping relayer allowlist test");
+        return;
+      }
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping relayer allowlist test");
+        return;
+      }
+
+      const registryPda = PublicKey.findProgramAddressSync([Buffer.from("registry")], program.programId)[0];
+      if (!(await connection.getAccountInfo(registryPda))) {
+        await program.methods.initRegistry().accounts({ admin: wallet.publicKey }).rpc();
+      }
+      const zrc20 = Array.from(new Uint8Array(20).fill(5));
+      await program.methods
+        .setRegistryEntry(zrc20, Buffer.from(new Uint8Array(20).fill(6)), new BN(97), 0)
+        .accounts({ admin: wallet.publicKey })
+        .rpc();
+
+      const relayerRole = PublicKey.findProgramAddressSync(
+        [Buffer.from("relayer_role"), admin.publicKey.toBuffer()],
+        program.programId
+      )[0];
+      const ping = (role: PublicKey | null) =>
+        program.methods
+          .pingConnectedContract(zrc20)
+          .accounts({ sender: admin.publicKey, relayerRole: role, gatewayProgram: new PublicKey(zetaChainTestnetGateway) })
+          .signers([admin])
+          .rpc();
+
+      await program.methods.setRelayerAllowlistEnabled(true).accounts({ admin: wallet.publicKey }).rpc();
+      try {
+        try {
+          await ping(null);
+          assert.fail("A crank without a relayer role should be rejected while the allowlist is on");
+        } catch (error) {
+          assert.include(error.toString(), "RelayerRequired");
+        }
+
+        await program.methods.grantRelayerRole(admin.publicKey).accounts({ admin: wallet.publicKey }).rpc();
+        await ping(relayerRole);
+        await program.methods.revokeRelayerRole(admin.publicKey).accounts({ admin: wallet.publicKey }).rpc();
+      } finally {
+        await program.methods.setRelayerAllowlistEnabled(false).accounts({ admin: wallet.publicKey }).rpc();
+      }
+
+      // Permissionless again once the allowlist is off
+      await ping(null);
+    });
+
+    it("Should record heartbeats no more often than the minimum interval", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping heartbeat test");
+        return;
+      }
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping heartbeat test");
+        return;
+      }
+
+      const heartbeatPda = PublicKey.findProgramAddressSync([Buffer.from("heartbeat")], program.programId)[0];
+      const heartbeat = () =>
+        program.methods
+          .heartbeat()
+          .accounts({ programState: programStatePda, caller: admin.publicKey })
+          .signers([admin])
+          .rpc({ commitment: "confirmed" });
+      const waitInterval = () => new Promise((resolve) => setTimeout(resolve, 2_500));
+
+      await program.methods.setHeartbeatInterval(2).accounts({ admin: wallet.publicKey }).rpc();
+      try {
+        await waitInterval();
+        await heartbeat();
+
+        const record = await program.account.heartbeatRecord.fetch(heartbeatPda, "confirmed");
+        const current = await program.account.programState.fetch(programStatePda, "confirmed");
+        assert.isAbove(record.slot.toNumber(), 0);
+        assert.equal(record.outboundNonce.toString(), current.transferNonce.toString());
+        assert.equal(record.inboundCount.toString(), current.inboundCount.toString());
+        assert.equal(record.pausedFlags, (current.paused ? 1 : 0) | (current.autoPausedInbound ? 2 : 0));
+
+        try {
+          await heartbeat();
+          assert.fail("A second heartbeat inside the interval should be rejected");
+        } catch (error) {
+          assert.include(error.toString(), "HeartbeatTooSoon");
+        }
+
+        // Cheap enough for a cron to call every few minutes
+        await waitInterval();
+        const signature = await heartbeat();
+        const transaction = await connection.getTransaction(signature, {
+          commitment: "confirmed",
+          maxSupportedTransactionVersion: 0,
+        });
+        assert.isBelow(transaction.meta.computeUnitsConsumed, 5_000);
+        const next = await program.account.heartbeatRecord.fetch(heartbeatPda, "confirmed");
+        assert.isAbove(next.slot.toNumber(), record.slot.toNumber());
+        const parser = new anchor.EventParser(program.programId, program.coder);
+        const beat = Array.from(parser.parseLogs(transaction.meta.logMessages)).find((event) => event.name === "heartbeat");
+        assert.ok(beat.data.crankedBy.equals(admin.publicKey));
+
+        // Heartbeats are a crank, so the relayer allowlist applies to them
+        await waitInterval();
+        await program.methods.setRelayerAllowlistEnabled(true).accounts({ admin: wallet.publicKey }).rpc();
+        try {
+          await heartbeat();
+          assert.fail("A heartbeat without a relayer role should be rejected while the allowlist is on");
+        } catch (error) {
+          assert.include(error.toString(), "RelayerRequired");
+        } finally {
+          await program.methods.setRelayerAllowlistEnabled(false).accounts({ admin: wallet.publicKey }).rpc();
+        }
+      } finally {
+        await program.methods.setHeartbeatInterval(0).accounts({ admin: wallet.publicKey }).rpc();
+      }
+    });
+
+    it("Should properly encode cross-chain messages", async () => {
+      // Test message encoding functionality
+      const receiver = Array.from(zetaChainRecipient);
+      const tokenId = 123;
+      const uri = "https://example.com/test-nft.json";
+      const sender = Array.from(solanaSender);
+
+      // This would test the UniversalNFTCoreImpl.encode_cross_chain_message function
+      console.log("Testing cross-chain message encoding:");
+      console.log("- Receiver:", receiver);
+      console.log("- Token ID:", tokenId);
+      console.log("- URI:", uri);
+      console.log("- Sender:", sender);
+
+      // Verify encoding produces valid message format
+      assert.ok(receiver.length === 20, "Receiver should be 20 bytes");
+      assert.ok(sender.length === 20, "Sender should be 20 bytes");
+      assert.ok(uri.length > 0, "URI should not be empty");
+      assert.ok(tokenId > 0, "Token ID should be positive");
+    });
+
+    it("Should properly decode cross-chain messages", async () => {
+      // Test message decoding functionality
+      const testMessage = Buffer.alloc(100).fill(2); // Simulated encoded message
+
+      console.log("Testing cross-chain message decoding:");
+      console.log("- Message length:", testMessage.length);
+      console.log("- Message format: [destination, receiver, token_id, uri, sender]");
+
+      // Verify decoding can handle various message formats
+      assert.ok(testMessage.length >= 84, "Message should be at least 84 bytes for valid format");
+    });
+  });
+
+  describe("Phase 6: Error Handling & Edge Cases", () => {
+    it("Should reject unauthorized cross-chain transfers", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping authorization test");
+        return;
+      }
+
+      const unauthorizedUser = Keypair.generate();
+      
+      // Fund unauthorized user
+      const transferAmount = 0.1 * LAMPORTS_PER_SOL;
+      const transfer = await connection.sendTransaction(
+        new Transaction().add(
+          SystemProgram.transfer({
+            fromPubkey: wallet.publicKey,
+            toPubkey: unauthorizedUser.publicKey,
+            lamports: transferAmount,
+          })
+        ),
+        [wallet.payer]
+      );
+      await connection.confirmTransaction(transfer, 'confirmed');
+
+      try {
+        await program.methods
+          .transferCrossChain(
+            new BN(testTokenId || 1),
+            zetaChainId,
+            Buffer.from(zetaChainRecipient),
+            null,
+            null,
+            null
+          )
+          .accounts({
+            nftOrigin: PublicKey.findProgramAddressSync(
+              [Buffer.from("nft_origin"), new BN(testTokenId || 1).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+              program.programId
+            )[0],
+            mint: testMint,
+            userTokenAccount: testTokenAccount,
+            user: unauthorizedUser.publicKey,
+            gatewayProgram: Keypair.generate().publicKey,
+            feeConfig: feeConfigPda,
+            feeTreasury,
+            chainGasConfig: chainConfigPda(zetaChainId),
+            registry: null,
+            receiptConfig: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            zrc20GasVault: null,
+            zrc20VaultTokenAccount: null,
+            gasFeeDestination: null,
+            hourlyMetrics: null,
+            insuranceOptIn: null,
+            coOwnership: null,
+            coOwnerConsent: null,
+          })
+          .signers([unauthorizedUser])
+          .rpc();
+
+        assert.fail("Should not allow unauthorized user to initiate transfer");
+      } catch (error) {
+        console.log("Successfully rejected unauthorized cross-chain transfer");
+        // @ts-ignore
+        assert.include(error.message, "Error", "Should throw authorization error");
+      }
+    });
+
+    it("Should only let the current upgrade authority hand it over", async () => {
+      const bpfLoaderUpgradeable = new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111");
+      const [programData] = PublicKey.findProgramAddressSync([program.programId.toBuffer()], bpfLoaderUpgradeable);
+      const [upgradeAuthorityRecord] = PublicKey.findProgramAddressSync([Buffer.from("upgrade_authority")], program.programId);
+      const impostor = Keypair.generate();
+      const multisig = Keypair.generate().publicKey;
+
+      const funding = await connection.sendTransaction(
+        new Transaction().add(
+          SystemProgram.transfer({
+            fromPubkey: wallet.publicKey,
+            toPubkey: impostor.publicKey,
+            lamports: 0.05 * LAMPORTS_PER_SOL,
+          })
+        ),
+        [wallet.payer]
+      );
+      await connection.confirmTransaction(funding, 'confirmed');
+
+      try {
+        await program.methods
+          .transferUpgradeAuthority(multisig)
+          .accounts({
+            programData,
+            upgradeAuthorityRecord,
+            currentAuthority: impostor.publicKey,
+            newUpgradeAuthority: multisig,
+            bpfLoaderUpgradeable,
+          })
+          .signers([impostor])
+          .rpc();
+        assert.fail("Should not hand over the upgrade authority for a non-authority signer");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "Unauthorized");
+      }
+    });
+
+    it("Should keep native and inbound token ids on their own side of native_id_base", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping token id range test");
+        return;
+      }
+
+      const { nativeIdBase } = await program.account.programState.fetch(programStatePda);
+      const nftOriginFor = (tokenId: BN) => PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), tokenId.toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      const mintNative = (tokenId: BN) => {
+        const newMint = Keypair.generate();
+        return program.methods
+          .createMintAndNft("https://example.com/native.json", 0, tokenId, null, null, null)
+          .accounts({
+            nftOrigin: nftOriginFor(tokenId),
+            mint: newMint.publicKey,
+            mintAuthority: mintAuthority.publicKey,
+            payer: admin.publicKey,
+            feeConfig: feeConfigPda,
+            feeTreasury: null,
+            sellerFeeConfig: null,
+            feeCreator: null,
+            mintOnly: null,
+            lazyRevealConfig: null,
+            hourlyMetrics: null,
+          })
+          .signers([admin, mintAuthority, newMint])
+          .rpc();
+      };
+      const receiveInbound = (tokenId: BN) => {
+        const mint = bridgedMintPda(tokenId.toNumber());
+        return program.methods
+          .receiveCrossChainMessage(
+            createZetaChainSuccessMessage(tokenId.toNumber(), "https://example.com/inbound.json", admin.publicKey.toBytes(), solanaSender),
+            null
+          )
+          .accounts({
+            programState: programStatePda,
+            nftOrigin: nftOriginFor(tokenId),
+            mint,
+            dedupWindow: dedupWindowPda("https://example.com/inbound.json"),
+            mintAuthority: mintAuthorityPda,
+            recipient: admin.publicKey,
+            sourceChainConfig: chainConfigPda(zetaChainId),
+            provisionalEscrow: null,
+            custodialTokenAccount: null,
+            inboundOffer: null,
+            pendingInbound: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            recipientTokenAccount: getAssociatedTokenAddressSync(mint, admin.publicKey, false),
+            payer: admin.publicKey,
+            gateway: gatewaySigner.publicKey,
+            hourlyMetrics: null,
+            inboundValueClaim: null,
+            inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId.toNumber(), "https://example.com/inbound.json", admin.publicKey.toBytes(), solanaSender)),
+          })
+          .remainingAccounts(originPolicyAccounts)
+          .signers([admin, gatewaySigner])
+          .rpc();
+      };
+
+      // The base itself belongs to native mints, the id just below it to inbound transfers
+      await mintNative(nativeIdBase);
+      await receiveInbound(nativeIdBase.subn(1));
+
+      try {
+        await mintNative(nativeIdBase.subn(2));
+        assert.fail("Native mint below native_id_base should be rejected");
+      } catch (error) {
+        assert.include(error.toString(), "TokenIdRangeConflict");
+      }
+
+      // An inbound id inside the native range is flagged rather than delivered
+      try {
+        await receiveInbound(nativeIdBase.addn(1));
+        assert.fail("Inbound id inside the native range should be rejected");
+      } catch (error) {
+        assert.include(error.toString(), "TokenIdRangeConflict");
+      }
+    });
+
+    it("Should handle invalid destination addresses", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping invalid address test");
+        return;
+      }
+
+      const unconnectedChain = new BN(12345); // No ZRC-20 registered for it
+
+      try {
+        await program.methods
+          .transferCrossChain(
+            new BN(testTokenId || 1),
+            unconnectedChain,
+            Buffer.from(zetaChainRecipient),
+            null,
+            null,
+            null
+          )
+          .accounts({
+            nftOrigin: PublicKey.findProgramAddressSync(
+              [Buffer.from("nft_origin"), new BN(testTokenId || 1).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+              program.programId
+            )[0],
+            mint: testMint,
+            userTokenAccount: testTokenAccount,
+            user: user.publicKey,
+            gatewayProgram: Keypair.generate().publicKey,
+            feeConfig: feeConfigPda,
+            feeTreasury,
+            chainGasConfig: chainConfigPda(unconnectedChain),
+            registry: null,
+            receiptConfig: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            zrc20GasVault: null,
+            zrc20VaultTokenAccount: null,
+            gasFeeDestination: null,
+            hourlyMetrics: null,
+            insuranceOptIn: null,
+            coOwnership: null,
+            coOwnerConsent: null,
+          })
+          .signers([user])
+          .rpc();
+
+        console.log("Program accepted invalid destination address");
+      } catch (error) {
+        console.log("Program correctly rejected invalid destination address");
+        // @ts-ignore
+        assert.include(error.message, "Error", "Should throw validation error");
+      }
+    });
+
+    it("Should reject every permutation of transfer account roles", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping account aliasing test");
+        return;
+      }
+
+      // Deliver a bridged NFT so the honest account set is valid
+      const tokenId = Date.now() + 19;
+      const nftOriginPda = PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      const mint = bridgedMintPda(tokenId);
+      const userTokenAccount = getAssociatedTokenAddressSync(mint, admin.publicKey, false);
+      await program.methods
+        .receiveCrossChainMessage(
+          createZetaChainSuccessMessage(tokenId, "https://example.com/aliasing.json", admin.publicKey.toBytes(), solanaSender),
+          null
+        )
+        .accounts({
+          programState: programStatePda,
+          nftOrigin: nftOriginPda,
+          mint,
+          dedupWindow: dedupWindowPda("https://example.com/aliasing.json"),
+          mintAuthority: mintAuthorityPda,
+          recipient: admin.publicKey,
+          sourceChainConfig: chainConfigPda(zetaChainId),
+          provisionalEscrow: null,
+          custodialTokenAccount: null,
+          inboundOffer: null,
+          pendingInbound: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          recipientTokenAccount: userTokenAccount,
+          payer: admin.publicKey,
+          gateway: gatewaySigner.publicKey,
+          hourlyMetrics: null,
+          inboundValueClaim: null,
+          inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, "https://example.com/aliasing.json", admin.publicKey.toBytes(), solanaSender)),
+        })
+        .remainingAccounts(originPolicyAccounts)
+        .signers([admin, gatewaySigner])
+        .rpc();
+
+      const { transferNonce } = await program.account.programState.fetch(programStatePda);
+      const pendingTransfer = PublicKey.findProgramAddressSync(
+        [Buffer.from("pending_xfer"), transferNonce.toArrayLike(Buffer, 'le', 8)],
+        program.programId
+      )[0];
+      const honest = [programStatePda, nftOriginPda, mint, userTokenAccount];
+      const simulateTransfer = ([programState, nftOrigin, mintRole, tokenAccount]: PublicKey[]) =>
+        program.methods
+          .transferCrossChain(new BN(tokenId), zetaChainId, Buffer.from(zetaChainRecipient), null, null, null)
+          .accountsPartial({
+            programState,
+            nftOrigin,
+            mint: mintRole,
+            userTokenAccount: tokenAccount,
+            user: admin.publicKey,
+            gatewayProgram: new PublicKey(zetaChainTestnetGateway),
+            feeConfig: feeConfigPda,
+            feeTreasury,
+            chainGasConfig: chainConfigPda(zetaChainId),
+            registry: null,
+            receiptConfig: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            zrc20GasVault: null,
+            zrc20VaultTokenAccount: null,
+            gasFeeDestination: null,
+            pendingTransfer,
+            hourlyMetrics: null,
+            insuranceOptIn: null,
+            coOwnership: null,
+            coOwnerConsent: null,
+          })
+          .signers([admin])
+          .simulate();
+
+      // Every way of filling the four roles from the four honest accounts, except the honest one
+      let rejected = 0;
+      for (let assignment = 0; assignment < 4 ** 4; assignment++) {
+        const roles = [0, 1, 2, 3].map((role) => honest[Math.floor(assignment / 4 ** role) % 4]);
+        if (roles.every((key, role) => key.equals(honest[role]))) continue;
+        try {
+          await simulateTransfer(roles);
+          assert.fail(`Permutation ${roles.map((key) => honest.indexOf(key)).join(",")} was accepted`);
+        } catch (error) {
+          // @ts-ignore
+          assert.notInclude(error.message, "was accepted");
+          rejected++;
+        }
+      }
+      assert.equal(rejected, 4 ** 4 - 1);
+
+      // The honest set still simulates cleanly
+      await simulateTransfer(honest);
+    });
+
+    it("Should let an approved delegate bridge the NFT", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping delegate transfer test");
+        return;
+      }
+
+      const tokenId = Date.now() + 23;
+      const nftOriginPda = PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      const mint = bridgedMintPda(tokenId);
+      const holderTokenAccount = getAssociatedTokenAddressSync(mint, admin.publicKey, false);
+      await program.methods
+        .receiveCrossChainMessage(
+          createZetaChainSuccessMessage(tokenId, "https://example.com/delegate.json", admin.publicKey.toBytes(), solanaSender),
+          null
+        )
+        .accounts({
+          programState: programStatePda,
+          nftOrigin: nftOriginPda,
+          mint,
+          dedupWindow: dedupWindowPda("https://example.com/delegate.json"),
+          mintAuthority: mintAuthorityPda,
+          recipient: admin.publicKey,
+          sourceChainConfig: chainConfigPda(zetaChainId),
+          provisionalEscrow: null,
+          custodialTokenAccount: null,
+          inboundOffer: null,
+          pendingInbound: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          recipientTokenAccount: holderTokenAccount,
+          payer: admin.publicKey,
+          gateway: gatewaySigner.publicKey,
+          hourlyMetrics: null,
+          inboundValueClaim: null,
+          inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, "https://example.com/delegate.json", admin.publicKey.toBytes(), solanaSender)),
+        })
+        .remainingAccounts(originPolicyAccounts)
+        .signers([admin, gatewaySigner])
+        .rpc();
+
+      const transferAs = async (signer: Keypair) => {
+        const { transferNonce } = await program.account.programState.fetch(programStatePda);
+        return program.methods
+          .transferCrossChain(new BN(tokenId), zetaChainId, Buffer.from(zetaChainRecipient), null, null, null)
+          .accountsPartial({
+            programState: programStatePda,
+            nftOrigin: nftOriginPda,
+            mint,
+            userTokenAccount: holderTokenAccount,
+            user: signer.publicKey,
+            gatewayProgram: new PublicKey(zetaChainTestnetGateway),
+            feeConfig: feeConfigPda,
+            feeTreasury,
+            chainGasConfig: chainConfigPda(zetaChainId),
+            registry: null,
+            receiptConfig: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            zrc20GasVault: null,
+            zrc20VaultTokenAccount: null,
+            gasFeeDestination: null,
+            pendingTransfer: PublicKey.findProgramAddressSync(
+              [Buffer.from("pending_xfer"), transferNonce.toArrayLike(Buffer, 'le', 8)],
+              program.programId
+            )[0],
+            hourlyMetrics: null,
+            insuranceOptIn: null,
+            coOwnership: null,
+            coOwnerConsent: null,
+          })
+          .signers([signer])
+          .rpc();
+      };
+
+      try {
+        await transferAs(user);
+        assert.fail("A stranger should not bridge someone else's NFT");
+      } catch (error) {
+        assert.include(error.toString(), "Unauthorized");
+      }
+
+      // Delegated, but with nothing left to spend
+      await approve(connection, admin, holderTokenAccount, user.publicKey, admin, 0);
+      try {
+        await transferAs(user);
+        assert.fail("A delegate with no approved amount should be rejected");
+      } catch (error) {
+        assert.include(error.toString(), "InsufficientDelegatedAmount");
+      }
+
+      await approve(connection, admin, holderTokenAccount, user.publicKey, admin, 1);
+      await transferAs(user);
+      const account = await getAccount(connection, holderTokenAccount);
+      assert.equal(Number(account.amount), 0);
+    });
+
+    describe("Pause reasons & scheduled unpause", () => {
+      // Pausing is owner-only, so these run only when the provider wallet is the owner
+      let isOwner = false;
+      before(async () => {
+        if (!isProgramInitialized) return;
+        const state = await program.account.programState.fetch(programStatePda);
+        isOwner = state.owner.equals(wallet.publicKey);
+      });
+
+      const deliverFreshToken = (tokenId: number) => {
+        const mint = bridgedMintPda(tokenId);
+        return program.methods
+          .receiveCrossChainMessage(
+            createZetaChainSuccessMessage(tokenId, "https://example.com/pause.json", admin.publicKey.toBytes(), solanaSender),
+            null
+          )
+          .accounts({
+            programState: programStatePda,
+            nftOrigin: PublicKey.findProgramAddressSync(
+              [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+              program.programId
+            )[0],
+            mint,
+            dedupWindow: dedupWindowPda("https://example.com/pause.json"),
+            mintAuthority: mintAuthorityPda,
+            recipient: admin.publicKey,
+            sourceChainConfig: chainConfigPda(zetaChainId),
+            provisionalEscrow: null,
+            custodialTokenAccount: null,
+            inboundOffer: null,
+            pendingInbound: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            recipientTokenAccount: getAssociatedTokenAddressSync(mint, admin.publicKey, false),
+            payer: admin.publicKey,
+            gateway: gatewaySigner.publicKey,
+            hourlyMetrics: null,
+            inboundValueClaim: null,
+            inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, "https://example.com/pause.json", admin.publicKey.toBytes(), solanaSender)),
+          })
+          .remainingAccounts(originPolicyAccounts)
+          .signers([admin, gatewaySigner])
+          .rpc({ commitment: "confirmed" });
+      };
+
+      it("Should stay paused without an expiry until manually unpaused", async () => {
+        if (!isOwner) {
+          console.log("Provider wallet is not the owner, skipping pause test");
+          return;
+        }
+
+        await program.methods.pause(0, null).accounts({ admin: wallet.publicKey }).rpc();
+        let state = await program.account.programState.fetch(programStatePda);
+        assert.isTrue(state.paused);
+        assert.equal(state.pauseReason, 0);
+        assert.isNull(state.autoUnpauseAt);
+
+        try {
+          await deliverFreshToken(Date.now() + 23);
+          assert.fail("Delivery should be rejected while paused");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "ProgramPaused");
+        }
+
+        await program.methods.unpause().accounts({ admin: wallet.publicKey }).rpc();
+        state = await program.account.programState.fetch(programStatePda);
+        assert.isFalse(state.paused);
+        assert.equal(state.pauseReason, 0);
+        assert.isNull(state.autoUnpauseAt);
+      });
+
+      it("Should lift a scheduled pause on the first call after it expires", async () => {
+        if (!isOwner) {
+          console.log("Provider wallet is not the owner, skipping scheduled unpause test");
+          return;
+        }
+
+        const now = await connection.getBlockTime(await connection.getSlot());
+        const unpauseAt = now + 2;
+        await program.methods.pause(2, new BN(unpauseAt)).accounts({ admin: wallet.publicKey }).rpc();
+        const paused = await program.account.programState.fetch(programStatePda);
+        assert.equal(paused.pauseReason, 2);
+        assert.equal(paused.autoUnpauseAt.toNumber(), unpauseAt);
+
+        try {
+          await deliverFreshToken(Date.now() + 29);
+          assert.fail("Delivery should be rejected before the scheduled unpause");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "ProgramPaused");
+        }
+
+        await new Promise((resolve) => setTimeout(resolve, 4000));
+        const signature = await deliverFreshToken(Date.now() + 31);
+
+        const tx = await connection.getTransaction(signature, {
+          commitment: "confirmed",
+          maxSupportedTransactionVersion: 0,
+        });
+        const parser = new anchor.EventParser(program.programId, program.coder);
+        const events = Array.from(parser.parseLogs(tx.meta.logMessages));
+        assert.ok(events.find((event) => event.name === "programAutoUnpaused"));
+
+        const state = await program.account.programState.fetch(programStatePda);
+        assert.isFalse(state.paused);
+        assert.isNull(state.autoUnpauseAt);
+      });
+    });
+  });
+
+  describe("Phase 7: Integration Testing", () => {
+    it("Should run a raffle through to the randomness request", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping raffle test");
+        return;
+      }
+
+      // Deliver a bridged NFT to raffle off
+      const tokenId = Date.now() + 37;
+      const nftOrigin = PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      const mint = bridgedMintPda(tokenId);
+      const creatorTokenAccount = getAssociatedTokenAddressSync(mint, admin.publicKey, false);
+      await program.methods
+        .receiveCrossChainMessage(
+          createZetaChainSuccessMessage(tokenId, "https://example.com/raffle.json", admin.publicKey.toBytes(), solanaSender),
+          null
+        )
+        .accounts({
+          programState: programStatePda,
+          nftOrigin,
+          mint,
+          dedupWindow: dedupWindowPda("https://example.com/raffle.json"),
+          mintAuthority: mintAuthorityPda,
+          recipient: admin.publicKey,
+          sourceChainConfig: chainConfigPda(zetaChainId),
+          provisionalEscrow: null,
+          custodialTokenAccount: null,
+          inboundOffer: null,
+          pendingInbound: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          recipientTokenAccount: creatorTokenAccount,
+          payer: admin.publicKey,
+          gateway: gatewaySigner.publicKey,
+          hourlyMetrics: null,
+          inboundValueClaim: null,
+          inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, "https://example.com/raffle.json", admin.publicKey.toBytes(), solanaSender)),
+        })
+        .remainingAccounts(originPolicyAccounts)
+        .signers([admin, gatewaySigner])
+        .rpc();
+
+      const counterPda = PublicKey.findProgramAddressSync([Buffer.from("raffle_counter")], program.programId)[0];
+      const counter = await program.account.raffleCounter.fetchNullable(counterPda);
+      const raffleId = counter ? counter.nextRaffleId : new BN(0);
+      const rafflePda = PublicKey.findProgramAddressSync(
+        [Buffer.from("raffle"), raffleId.toArrayLike(Buffer, 'le', 8)],
+        program.programId
+      )[0];
+
+      const ticketPrice = new BN(1_000_000);
+      await program.methods
+        .createRaffle(new BN(tokenId), ticketPrice, 1, new BN(Math.floor(Date.now() / 1000) + 3600))
+        .accounts({
+          nftOrigin,
+          mint,
+          creatorTokenAccount,
+          creator: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+      assert.equal(Number((await getAccount(connection, creatorTokenAccount)).amount), 0, "NFT should be escrowed");
+
+      const buyTicket = () =>
+        program.methods
+          .buyRaffleTicket(raffleId)
+          .accounts({ buyer: user.publicKey })
+          .signers([user])
+          .rpc();
+      const raffleBalanceBefore = await connection.getBalance(rafflePda);
+      await buyTicket();
+      assert.equal(await connection.getBalance(rafflePda) - raffleBalanceBefore, ticketPrice.toNumber());
+
+      try {
+        await buyTicket();
+        assert.fail("Second ticket should be rejected");
+      } catch (error) {
+        // @ts-ignore
+        assert.isDefined(error.message);
+      }
+
+      // Sold out, so the draw can be requested before end_time
+      await program.methods
+        .requestWinnerSelection(raffleId)
+        .accounts({
+          requester: admin.publicKey,
+          gatewayProgram: new PublicKey(zetaChainTestnetGateway),
+        })
+        .signers([admin])
+        .rpc();
+      const raffle = await program.account.raffle.fetch(rafflePda);
+      assert.deepEqual(raffle.status, { awaitingRandomness: {} });
+      assert.equal(raffle.ticketCount, 1);
+
+      // The draw can only be called off once its random number is a day late, and tickets
+      // are refunded only after that
+      try {
+        await program.methods
+          .cancelRaffle(raffleId)
+          .accounts({ mint, creator: admin.publicKey, payer: user.publicKey })
+          .signers([user])
+          .rpc();
+        assert.fail("A raffle awaiting its random number should not be cancellable yet");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "RaffleNotEnded");
+      }
+      try {
+        await program.methods
+          .claimRaffleRefund(raffleId, 0)
+          .accounts({ buyer: user.publicKey })
+          .rpc();
+        assert.fail("Tickets of a raffle that wasn't cancelled should not be refunded");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "RaffleClosed");
+      }
+    });
+
+    describe("Departure receipts", () => {
+      // The receipt switch is owner-only, so these run only when the provider wallet is the owner
+      let isOwner = false;
+      const receiptConfigPda = PublicKey.findProgramAddressSync([Buffer.from("receipt_config")], program.programId)[0];
+      before(async () => {
+        if (!isProgramInitialized) return;
+        const state = await program.account.programState.fetch(programStatePda);
+        isOwner = state.owner.equals(wallet.publicKey);
+      });
+      after(async () => {
+        if (isOwner) {
+          await program.methods.setReceiptConfig(false, "").accounts({ admin: wallet.publicKey }).rpc();
+        }
+      });
+
+      const setReceipts = (enabled: boolean) =>
+        program.methods
+          .setReceiptConfig(enabled, "https://example.com/receipts/")
+          .accounts({ admin: wallet.publicKey })
+          .rpc({ commitment: "confirmed" });
+
+      const nftOriginFor = (tokenId: number) => PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      const receiptMintFor = (tokenId: number) => PublicKey.findProgramAddressSync(
+        [Buffer.from("receipt_mint"), new BN(tokenId).toArrayLike(Buffer, 'le', 8)],
+        program.programId
+      )[0];
+
+      const deliver = (tokenId: number, withReceipt: boolean) => {
+        const mint = bridgedMintPda(tokenId);
+        const receiptMint = receiptMintFor(tokenId);
+        return program.methods
+          .receiveCrossChainMessage(
+            createZetaChainSuccessMessage(tokenId, "https://example.com/receipt-nft.json", admin.publicKey.toBytes(), solanaSender),
+            null
+          )
+          .accounts({
+            programState: programStatePda,
+            nftOrigin: nftOriginFor(tokenId),
+            mint,
+            dedupWindow: dedupWindowPda("https://example.com/receipt-nft.json"),
+            mintAuthority: mintAuthorityPda,
+            recipient: admin.publicKey,
+            sourceChainConfig: chainConfigPda(zetaChainId),
+            provisionalEscrow: null,
+            custodialTokenAccount: null,
+            inboundOffer: null,
+            pendingInbound: null,
+            receiptMint: withReceipt ? receiptMint : null,
+            receiptTokenAccount: withReceipt ? getAssociatedTokenAddressSync(receiptMint, admin.publicKey, false) : null,
+            recipientTokenAccount: getAssociatedTokenAddressSync(mint, admin.publicKey, false),
+            payer: admin.publicKey,
+            gateway: gatewaySigner.publicKey,
+            hourlyMetrics: null,
+            inboundValueClaim: null,
+            inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, "https://example.com/receipt-nft.json", admin.publicKey.toBytes(), solanaSender)),
+          })
+          .remainingAccounts(originPolicyAccounts)
+          .signers([admin, gatewaySigner])
+          .rpc({ commitment: "confirmed" });
+      };
+
+      const depart = (tokenId: number, withReceipt: boolean) => {
+        const mint = bridgedMintPda(tokenId);
+        const receiptMint = receiptMintFor(tokenId);
+        return program.methods
+          .transferCrossChain(new BN(tokenId), zetaChainId, Buffer.from(zetaChainRecipient), null, null, null)
+          .accounts({
+            nftOrigin: nftOriginFor(tokenId),
+            mint,
+            userTokenAccount: getAssociatedTokenAddressSync(mint, admin.publicKey, false),
+            user: admin.publicKey,
+            gatewayProgram: new PublicKey(zetaChainTestnetGateway),
+            feeConfig: feeConfigPda,
+            feeTreasury,
+            chainGasConfig: chainConfigPda(zetaChainId),
+            registry: null,
+            receiptConfig: receiptConfigPda,
+            receiptMint: withReceipt ? receiptMint : null,
+            receiptTokenAccount: withReceipt ? getAssociatedTokenAddressSync(receiptMint, admin.publicKey, false) : null,
+            zrc20GasVault: null,
+            zrc20VaultTokenAccount: null,
+            gasFeeDestination: null,
+            hourlyMetrics: null,
+            insuranceOptIn: null,
+            coOwnership: null,
+            coOwnerConsent: null,
+          })
+          .signers([admin])
+          .rpc({ commitment: "confirmed" });
+      };
+
+      it("Should issue a frozen receipt on departure and burn it on return", async () => {
+        if (!isOwner) {
+          console.log("Provider wallet is not the program owner, skipping receipt test");
+          return;
+        }
+
+        await setReceipts(true);
+        const tokenId = Date.now() + 41;
+        const receiptTokenAccount = getAssociatedTokenAddressSync(receiptMintFor(tokenId), admin.publicKey, false);
+        await deliver(tokenId, false);
+
+        const departure = await depart(tokenId, true);
+        const receipt = await getAccount(connection, receiptTokenAccount);
+        assert.equal(Number(receipt.amount), 1);
+        assert.isTrue(receipt.isFrozen, "receipt should not be transferable");
+        assert.isTrue((await program.account.nftOrigin.fetch(nftOriginFor(tokenId))).receiptOutstanding);
+
+        const tx = await connection.getTransaction(departure, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+        const parser = new anchor.EventParser(program.programId, program.coder);
+        const issued = Array.from(parser.parseLogs(tx.meta.logMessages)).find((e) => e.name === "departureReceiptIssued");
+        assert.isDefined(issued);
+        assert.equal(issued.data.uri, `https://example.com/receipts/${tokenId}.json`);
+
+        // The return must present the receipt
+        try {
+          await deliver(tokenId, false);
+          assert.fail("Return without the receipt should be rejected");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "ReceiptRequired");
+        }
+
+        await deliver(tokenId, true);
+        assert.equal(Number((await getAccount(connection, receiptTokenAccount)).amount), 0);
+        assert.equal(
+          Number((await getAccount(connection, getAssociatedTokenAddressSync(bridgedMintPda(tokenId), admin.publicKey, false))).amount),
+          1
+        );
+        assert.isFalse((await program.account.nftOrigin.fetch(nftOriginFor(tokenId))).receiptOutstanding);
+      });
+
+      it("Should depart and return without a receipt while receipts are disabled", async () => {
+        if (!isOwner) {
+          console.log("Provider wallet is not the program owner, skipping receipt test");
+          return;
+        }
+
+        await setReceipts(false);
+        const tokenId = Date.now() + 43;
+        await deliver(tokenId, false);
+        await depart(tokenId, false);
+        assert.isFalse((await program.account.nftOrigin.fetch(nftOriginFor(tokenId))).receiptOutstanding);
+        assert.isNull(await connection.getAccountInfo(receiptMintFor(tokenId)));
+
+        await deliver(tokenId, false);
+        assert.equal(
+          Number((await getAccount(connection, getAssociatedTokenAddressSync(bridgedMintPda(tokenId), admin.publicKey, false))).amount),
+          1
+        );
+      });
+    });
+
+    it("Should snapshot a collection into a Merkle root light clients can recompute", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping Merkle root test");
+        return;
+      }
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping Merkle root test");
+        return;
+      }
+
+      // Three bridged NFTs held by the admin, so the tree has an odd node
+      const firstTokenId = Date.now() + 41;
+      const tokenIds = [firstTokenId, firstTokenId + 1, firstTokenId + 2];
+      const nftOriginPda = (tokenId: number) => PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      const remainingAccounts = [];
+      const leaves: Buffer[] = [];
+      for (const tokenId of tokenIds) {
+        const mint = bridgedMintPda(tokenId);
+        const uri = `https://example.com/merkle-${tokenId}.json`;
+        const holderTokenAccount = getAssociatedTokenAddressSync(mint, admin.publicKey, false);
+        await program.methods
+          .receiveCrossChainMessage(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender), null)
+          .accounts({
+            programState: programStatePda,
+            nftOrigin: nftOriginPda(tokenId),
+            mint,
+            dedupWindow: dedupWindowPda(uri),
+            mintAuthority: mintAuthorityPda,
+            recipient: admin.publicKey,
+            sourceChainConfig: chainConfigPda(zetaChainId),
+            provisionalEscrow: null,
+            custodialTokenAccount: null,
+            inboundOffer: null,
+            pendingInbound: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            recipientTokenAccount: holderTokenAccount,
+            payer: admin.publicKey,
+            gateway: gatewaySigner.publicKey,
+            hourlyMetrics: null,
+            inboundValueClaim: null,
+            inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender)),
+          })
+          .remainingAccounts(originPolicyAccounts)
+          .signers([admin, gatewaySigner])
+          .rpc();
+
+        remainingAccounts.push(
+          { pubkey: nftOriginPda(tokenId), isWritable: false, isSigner: false },
+          { pubkey: holderTokenAccount, isWritable: false, isSigner: false },
+        );
+        leaves.push(createHash("sha256").update(Buffer.concat([
+          new BN(tokenId).toArrayLike(Buffer, 'le', 8),
+          mint.toBuffer(),
+          Buffer.from(uri),
+          admin.publicKey.toBuffer(),
+        ])).digest());
+      }
+
+      let level = leaves;
+      while (level.length > 1) {
+        const next: Buffer[] = [];
+        for (let i = 0; i < level.length; i += 2) {
+          next.push(i + 1 < level.length
+            ? createHash("sha256").update(Buffer.concat([level[i], level[i + 1]])).digest()
+            : level[i]);
+        }
+        level = next;
+      }
+
+      const collectionId = new BN(Date.now() % 1_000_000);
+      const computeRoot = (ids: number[]) =>
+        program.methods
+          .computeCollectionMerkleRoot(collectionId, ids.map((id) => new BN(id)))
+          .accounts({ admin: wallet.publicKey, collectionAuthority: null })
+          .remainingAccounts(remainingAccounts)
+          .rpc({ commitment: "confirmed" });
+
+      // Token ids out of order would let one token appear twice
+      try {
+        await computeRoot([tokenIds[1], tokenIds[0], tokenIds[2]]);
+        assert.fail("Unsorted token ids should be rejected");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidMerkleRootAccounts");
+      }
+
+      await computeRoot(tokenIds);
+      const merkleRoot = await program.account.collectionMerkleRoot.fetch(
+        PublicKey.findProgramAddressSync(
+          [Buffer.from("merkle_root"), collectionId.toArrayLike(Buffer, 'le', 8)],
+          program.programId
+        )[0]
+      );
+      assert.deepEqual(Buffer.from(merkleRoot.root), level[0]);
+      assert.equal(merkleRoot.tokenCount.toNumber(), tokenIds.length);
+    });
+
+    it("Should keep per-origin-chain stats across arrivals and departures", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping chain stats test");
+        return;
+      }
+
+      // Inbound NFTs are recorded with ZetaChain as their origin chain
+      const chainStatsPda = PublicKey.findProgramAddressSync(
+        [Buffer.from("chain_stats"), zetaChainId.toArrayLike(Buffer, 'le', 8)],
+        program.programId
+      )[0];
+      const zero = { received: new BN(0), departed: new BN(0), active: new BN(0), cumulativeDwellSeconds: new BN(0) };
+      const before = (await program.account.chainStats.fetchNullable(chainStatsPda)) ?? zero;
+
+      const firstTokenId = Date.now() + 51;
+      const tokenIds = [firstTokenId, firstTokenId + 1];
+      const nftOriginPda = (tokenId: number) => PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      for (const tokenId of tokenIds) {
+        const mint = bridgedMintPda(tokenId);
+        await program.methods
+          .receiveCrossChainMessage(
+            createZetaChainSuccessMessage(tokenId, "https://example.com/chain-stats.json", admin.publicKey.toBytes(), solanaSender),
+            null
+          )
+          .accounts({
+            programState: programStatePda,
+            nftOrigin: nftOriginPda(tokenId),
+            mint,
+            dedupWindow: dedupWindowPda("https://example.com/chain-stats.json"),
+            mintAuthority: mintAuthorityPda,
+            recipient: admin.publicKey,
+            sourceChainConfig: chainConfigPda(zetaChainId),
+            provisionalEscrow: null,
+            custodialTokenAccount: null,
+            inboundOffer: null,
+            pendingInbound: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            recipientTokenAccount: getAssociatedTokenAddressSync(mint, admin.publicKey, false),
+            payer: admin.publicKey,
+            gateway: gatewaySigner.publicKey,
+            hourlyMetrics: null,
+            inboundValueClaim: null,
+            inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, "https://example.com/chain-stats.json", admin.publicKey.toBytes(), solanaSender)),
+          })
+          .remainingAccounts(originPolicyAccounts)
+          .signers([admin, gatewaySigner])
+          .rpc({ commitment: "confirmed" });
+      }
+
+      // One of them leaves again after a short stay
+      await new Promise((resolve) => setTimeout(resolve, 2_000));
+      const departing = tokenIds[0];
+      const { transferNonce } = await program.account.programState.fetch(programStatePda);
+      await program.methods
+        .transferCrossChain(new BN(departing), zetaChainId, Buffer.from(zetaChainRecipient), null, null, null)
+        .accountsPartial({
+          programState: programStatePda,
+          nftOrigin: nftOriginPda(departing),
+          mint: bridgedMintPda(departing),
+          userTokenAccount: getAssociatedTokenAddressSync(bridgedMintPda(departing), admin.publicKey, false),
+          user: admin.publicKey,
+          gatewayProgram: new PublicKey(zetaChainTestnetGateway),
+          feeConfig: feeConfigPda,
+          feeTreasury,
+          chainGasConfig: chainConfigPda(zetaChainId),
+          registry: null,
+          receiptConfig: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          zrc20GasVault: null,
+          zrc20VaultTokenAccount: null,
+          gasFeeDestination: null,
+          pendingTransfer: PublicKey.findProgramAddressSync(
+            [Buffer.from("pending_xfer"), transferNonce.toArrayLike(Buffer, 'le', 8)],
+            program.programId
+          )[0],
+          hourlyMetrics: null,
+          insuranceOptIn: null,
+          coOwnership: null,
+          coOwnerConsent: null,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+      const after = await program.account.chainStats.fetch(chainStatsPda, "confirmed");
+      assert.equal(after.received.sub(before.received).toNumber(), 2);
+      assert.equal(after.departed.sub(before.departed).toNumber(), 1);
+      assert.equal(after.active.sub(before.active).toNumber(), 1);
+      assert.isAtLeast(after.cumulativeDwellSeconds.sub(before.cumulativeDwellSeconds).toNumber(), 1);
+
+      const summary = await program.methods
+        .getChainStats(zetaChainId)
+        .accounts({})
+        .view();
+      assert.equal(summary.chainId.toString(), zetaChainId.toString());
+      assert.equal(summary.active.toString(), after.active.toString());
+      assert.equal(
+        summary.averageDwellSeconds.toString(),
+        after.cumulativeDwellSeconds.div(after.departed).toString()
+      );
+    });
+
+    it("Should cap how many token ids one URI arrives under per window", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping URI deduplication test");
+        return;
+      }
+      // Setting the limit is owner-only
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping URI deduplication test");
+        return;
+      }
+
+      const uri = `https://example.com/dedup-${Date.now()}.json`;
+      const receive = (tokenId: number) => {
+        const mint = bridgedMintPda(tokenId);
+        return program.methods
+          .receiveCrossChainMessage(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender), null)
+          .accounts({
+            programState: programStatePda,
+            nftOrigin: PublicKey.findProgramAddressSync(
+              [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+              program.programId
+            )[0],
+            mint,
+            dedupWindow: dedupWindowPda(uri),
+            mintAuthority: mintAuthorityPda,
+            recipient: admin.publicKey,
+            sourceChainConfig: chainConfigPda(zetaChainId),
+            provisionalEscrow: null,
+            custodialTokenAccount: null,
+            inboundOffer: null,
+            pendingInbound: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            recipientTokenAccount: getAssociatedTokenAddressSync(mint, admin.publicKey, false),
+            payer: admin.publicKey,
+            gateway: gatewaySigner.publicKey,
+            hourlyMetrics: null,
+            inboundValueClaim: null,
+            inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender)),
+          })
+          .remainingAccounts(originPolicyAccounts)
+          .signers([admin, gatewaySigner])
+          .rpc();
+      };
+
+      await program.methods
+        .setUriDedupWindow(new BN(2), new BN(3600))
+        .accounts({ programState: programStatePda, admin: wallet.publicKey })
+        .rpc();
+      try {
+        const firstTokenId = Date.now() + 61;
+        await receive(firstTokenId);
+        await receive(firstTokenId + 1);
+        try {
+          await receive(firstTokenId + 2);
+          assert.fail("A third token id under the same URI should be rejected");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "URIAlreadyClaimed");
+        }
+
+        const window = await program.account.deduplicationWindow.fetch(dedupWindowPda(uri));
+        assert.equal(window.mintCount.toNumber(), 2);
+        assert.deepEqual(
+          Buffer.from(window.uriHash),
+          createHash("sha256").update(uri).digest()
+        );
+      } finally {
+        await program.methods
+          .setUriDedupWindow(new BN(0), new BN(0))
+          .accounts({ programState: programStatePda, admin: wallet.publicKey })
+          .rpc();
+      }
+    });
+
+    it("Should fall back to a hash-only URI at the max_message_bytes boundary", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping message size test");
+        return;
+      }
+      // Setting the limit is owner-only
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping message size test");
+        return;
+      }
+
+      // Outbound message: the five ABI head words, destination first, and the uri's length word,
+      // the uri padded to 32 bytes, then the 38-byte call options and 33-byte burn commitment trailers, and the 38-byte
+      // provenance naming the 20-byte universal contract it arrived through. The hash-only
+      // uri is 74 bytes, padded to 96
+      const uri = `https://example.com/${"a".repeat(280)}.json`;
+      const fullBytes = 5 * 32 + 32 + Math.ceil(uri.length / 32) * 32 + 38 + 33 + 38;
+      const hashOnlyBytes = 5 * 32 + 32 + 96 + 38 + 33 + 38;
+      const nftOriginPda = (tokenId: number) => PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      const receiveAndTransfer = async (tokenId: number, maxMessageBytes: number) => {
+        const mint = bridgedMintPda(tokenId);
+        await program.methods
+          .receiveCrossChainMessage(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender), null)
+          .accounts({
+            programState: programStatePda,
+            nftOrigin: nftOriginPda(tokenId),
+            mint,
+            dedupWindow: dedupWindowPda(uri),
+            mintAuthority: mintAuthorityPda,
+            recipient: admin.publicKey,
+            sourceChainConfig: chainConfigPda(zetaChainId),
+            provisionalEscrow: null,
+            custodialTokenAccount: null,
+            inboundOffer: null,
+            pendingInbound: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            recipientTokenAccount: getAssociatedTokenAddressSync(mint, admin.publicKey, false),
+            payer: admin.publicKey,
+            gateway: gatewaySigner.publicKey,
+            hourlyMetrics: null,
+            inboundValueClaim: null,
+            inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender)),
+          })
+          .remainingAccounts(originPolicyAccounts)
+          .signers([admin, gatewaySigner])
+          .rpc({ commitment: "confirmed" });
+        await program.methods
+          .setMaxMessageBytes(maxMessageBytes)
+          .accounts({ programState: programStatePda, admin: wallet.publicKey })
+          .rpc({ commitment: "confirmed" });
+
+        const { transferNonce } = await program.account.programState.fetch(programStatePda, "confirmed");
+        const signature = await program.methods
+          .transferCrossChain(new BN(tokenId), zetaChainId, Buffer.from(zetaChainRecipient), null, null, null)
+          .accountsPartial({
+            programState: programStatePda,
+            nftOrigin: nftOriginPda(tokenId),
+            mint,
+            userTokenAccount: getAssociatedTokenAddressSync(mint, admin.publicKey, false),
+            user: admin.publicKey,
+            gatewayProgram: new PublicKey(zetaChainTestnetGateway),
+            feeConfig: feeConfigPda,
+            feeTreasury,
+            chainGasConfig: chainConfigPda(zetaChainId),
+            registry: null,
+            receiptConfig: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            zrc20GasVault: null,
+            zrc20VaultTokenAccount: null,
+            gasFeeDestination: null,
+            pendingTransfer: PublicKey.findProgramAddressSync(
+              [Buffer.from("pending_xfer"), transferNonce.toArrayLike(Buffer, 'le', 8)],
+              program.programId
+            )[0],
+            hourlyMetrics: null,
+            insuranceOptIn: null,
+            coOwnership: null,
+            coOwnerConsent: null,
+          })
+          .signers([admin])
+          .rpc({ commitment: "confirmed" });
+        const tx = await connection.getTransaction(signature, {
+          commitment: "confirmed",
+          maxSupportedTransactionVersion: 0,
+        });
+        const parser = new anchor.EventParser(program.programId, program.coder);
+        return Array.from(parser.parseLogs(tx.meta.logMessages)).find((event) => event.name === "outboundUriHashed");
+      };
+
+      const firstTokenId = Date.now() + 71;
+      try {
+        // Exactly at the limit the full URI still goes out
+        assert.isUndefined(await receiveAndTransfer(firstTokenId, fullBytes));
+
+        // One byte under it, the URI is sent hash-only and the full one stays in NFTOrigin
+        const hashed = await receiveAndTransfer(firstTokenId + 1, fullBytes - 1);
+        assert.ok(hashed, "Expected an OutboundUriHashed event");
+        assert.equal(hashed.data.tokenId.toNumber(), firstTokenId + 1);
+        assert.equal(hashed.data.messageBytes, hashOnlyBytes);
+        assert.lengthOf(hashed.data.uriHash, 32);
+        const origin = await program.account.nftOrigin.fetch(nftOriginPda(firstTokenId + 1));
+        assert.equal(origin.metadataUri, uri);
+
+        // Below the hash-only size nothing fits
+        try {
+          await receiveAndTransfer(firstTokenId + 2, hashOnlyBytes - 1);
+          assert.fail("A message over the limit even hash-only should be rejected");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "MessageTooLarge");
+        }
+      } finally {
+        await program.methods
+          .setMaxMessageBytes(0)
+          .accounts({ programState: programStatePda, admin: wallet.publicKey })
+          .rpc();
+      }
+    });
+
+    it("Should attach attested metadata once to an NFT that arrived without a URI", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping metadata attestation test");
+        return;
+      }
+      // Rotating the attestation key is owner-only
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping metadata attestation test");
+        return;
+      }
+
+      // The source chain keeps this NFT's metadata on-chain and sends no URI
+      const tokenId = Date.now() + 81;
+      const mint = bridgedMintPda(tokenId);
+      const nftOrigin = PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      await program.methods
+        .receiveCrossChainMessage(createZetaChainSuccessMessage(tokenId, "", admin.publicKey.toBytes(), solanaSender), null)
+        .accounts({
+          programState: programStatePda,
+          nftOrigin,
+          mint,
+          dedupWindow: dedupWindowPda(""),
+          mintAuthority: mintAuthorityPda,
+          recipient: admin.publicKey,
+          sourceChainConfig: chainConfigPda(zetaChainId),
+          provisionalEscrow: null,
+          custodialTokenAccount: null,
+          inboundOffer: null,
+          pendingInbound: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          recipientTokenAccount: getAssociatedTokenAddressSync(mint, admin.publicKey, false),
+          payer: admin.publicKey,
+          gateway: gatewaySigner.publicKey,
+          hourlyMetrics: null,
+          inboundValueClaim: null,
+          inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, "", admin.publicKey.toBytes(), solanaSender)),
+        })
+        .remainingAccounts(originPolicyAccounts)
+        .signers([admin, gatewaySigner])
+        .rpc();
+
+      const attestationKey = Keypair.generate();
+      await program.methods
+        .setAttestationKey(attestationKey.publicKey)
+        .accounts({ programState: programStatePda, admin: wallet.publicKey })
+        .rpc();
+
+      const attach = (signer: Keypair, uri: string, withAdmin = false) => {
+        const message = Buffer.concat([
+          Buffer.from("universal-nft:attach_metadata:"),
+          new BN(tokenId).toArrayLike(Buffer, 'le', 8),
+          new BN(CHAIN_ID_ZETACHAIN_TESTNET).toArrayLike(Buffer, 'le', 8),
+          Buffer.from(uri),
+        ]);
+        const ed25519Ix = Ed25519Program.createInstructionWithPrivateKey({ privateKey: signer.secretKey, message });
+        const signatureOffset = ed25519Ix.data.readUInt16LE(2);
+        const signature = Array.from(ed25519Ix.data.subarray(signatureOffset, signatureOffset + 64));
+        return program.methods
+          .attachMetadata(new BN(tokenId), uri, signature)
+          .accounts({
+            programState: programStatePda,
+            nftOrigin,
+            admin: withAdmin ? wallet.publicKey : null,
+          })
+          .preInstructions([ed25519Ix])
+          .rpc();
+      };
+
+      try {
+        // A valid signature by any other key is not an attestation
+        try {
+          await attach(Keypair.generate(), "https://example.com/forged.json");
+          assert.fail("An attestation by another key should be rejected");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "InvalidAttestation");
+        }
+
+        await attach(attestationKey, "https://example.com/attested.json");
+        assert.equal((await program.account.nftOrigin.fetch(nftOrigin)).metadataUri, "https://example.com/attested.json");
+
+        // Once attached, only the admin can replace it
+        try {
+          await attach(attestationKey, "https://example.com/second.json");
+          assert.fail("A second attachment without the admin should be rejected");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "MetadataAlreadyAttached");
+        }
+        await attach(attestationKey, "https://example.com/second.json", true);
+        assert.equal((await program.account.nftOrigin.fetch(nftOrigin)).metadataUri, "https://example.com/second.json");
+      } finally {
+        await program.methods
+          .setAttestationKey(PublicKey.default)
+          .accounts({ programState: programStatePda, admin: wallet.publicKey })
+          .rpc();
+      }
+    });
+
+    it("Should refuse transfers from senders below the SOL reserve", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping SOL reserve test");
+        return;
+      }
+      // Setting the reserve is owner-only
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping SOL reserve test");
+        return;
+      }
+
+      const tokenId = Date.now() + 91;
+      const uri = "https://example.com/sol-reserve.json";
+      const mint = bridgedMintPda(tokenId);
+      const nftOrigin = PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      const userTokenAccount = getAssociatedTokenAddressSync(mint, admin.publicKey, false);
+      await program.methods
+        .receiveCrossChainMessage(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender), null)
+        .accounts({
+          programState: programStatePda,
+          nftOrigin,
+          mint,
+          dedupWindow: dedupWindowPda(uri),
+          mintAuthority: mintAuthorityPda,
+          recipient: admin.publicKey,
+          sourceChainConfig: chainConfigPda(zetaChainId),
+          provisionalEscrow: null,
+          custodialTokenAccount: null,
+          inboundOffer: null,
+          pendingInbound: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          recipientTokenAccount: userTokenAccount,
+          payer: admin.publicKey,
+          gateway: gatewaySigner.publicKey,
+          hourlyMetrics: null,
+          inboundValueClaim: null,
+          inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender)),
+        })
+        .remainingAccounts(originPolicyAccounts)
+        .signers([admin, gatewaySigner])
+        .rpc();
+
+      // A reserve above the sender's whole balance
+      const balance = await connection.getBalance(admin.publicKey);
+      await program.methods
+        .setMinUserSolReserve(new BN(balance).add(new BN(LAMPORTS_PER_SOL)))
+        .accounts({ programState: programStatePda, admin: wallet.publicKey })
+        .rpc();
+      try {
+        const { transferNonce } = await program.account.programState.fetch(programStatePda);
+        await program.methods
+          .transferCrossChain(new BN(tokenId), zetaChainId, Buffer.from(zetaChainRecipient), null, null, null)
+          .accountsPartial({
+            programState: programStatePda,
+            nftOrigin,
+            mint,
+            userTokenAccount,
+            user: admin.publicKey,
+            gatewayProgram: new PublicKey(zetaChainTestnetGateway),
+            feeConfig: feeConfigPda,
+            feeTreasury,
+            chainGasConfig: chainConfigPda(zetaChainId),
+            registry: null,
+            receiptConfig: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            zrc20GasVault: null,
+            zrc20VaultTokenAccount: null,
+            gasFeeDestination: null,
+            pendingTransfer: PublicKey.findProgramAddressSync(
+              [Buffer.from("pending_xfer"), transferNonce.toArrayLike(Buffer, 'le', 8)],
+              program.programId
+            )[0],
+            hourlyMetrics: null,
+            insuranceOptIn: null,
+            coOwnership: null,
+            coOwnerConsent: null,
+          })
+          .signers([admin])
+          .rpc();
+        assert.fail("A sender below the reserve should be rejected");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "InsufficientUserBalance");
+      } finally {
+        await program.methods
+          .setMinUserSolReserve(new BN(0))
+          .accounts({ programState: programStatePda, admin: wallet.publicKey })
+          .rpc();
+      }
+
+      // The NFT was never burned
+      assert.equal(Number((await getAccount(connection, userTokenAccount)).amount), 1);
+    });
+
+    it("Should keep the last memo on the NFT origin and reject oversized memos", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping memo test");
+        return;
+      }
+
+      const tokenId = Date.now() + 101;
+      const uri = "https://example.com/memo.json";
+      const nftOrigin = PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      const receive = (memo: string) =>
+        program.methods
+          .receiveCrossChainMessage(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender), memo)
+          .accounts({
+            programState: programStatePda,
+            nftOrigin,
+            mint: bridgedMintPda(tokenId),
+            dedupWindow: dedupWindowPda(uri),
+            mintAuthority: mintAuthorityPda,
+            recipient: admin.publicKey,
+            sourceChainConfig: chainConfigPda(zetaChainId),
+            provisionalEscrow: null,
+            custodialTokenAccount: null,
+            inboundOffer: null,
+            pendingInbound: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            recipientTokenAccount: getAssociatedTokenAddressSync(bridgedMintPda(tokenId), admin.publicKey, false),
+            payer: admin.publicKey,
+            gateway: gatewaySigner.publicKey,
+            hourlyMetrics: null,
+            inboundValueClaim: null,
+            inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender)),
+          })
+          .remainingAccounts(originPolicyAccounts)
+          .signers([admin, gatewaySigner])
+          .rpc({ commitment: "confirmed" });
+
+      try {
+        await receive("x".repeat(129));
+        assert.fail("A memo over 128 bytes should be rejected");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "MemoTooLong");
+      }
+
+      const memo = "invoice 42: gallery consignment";
+      const signature = await receive(memo);
+      assert.equal((await program.account.nftOrigin.fetch(nftOrigin)).lastMemo, memo);
+      // The memo also lands in the transaction log through the SPL Memo program
+      const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+      assert.isTrue(tx.meta.logMessages.some((line) => line.includes(memo)));
+    });
+
+    it("Should route transfers and deliveries through the configured bridge backend", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping bridge backend test");
+        return;
+      }
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping bridge backend test");
+        return;
+      }
+      assert.deepEqual(state.bridgeBackend, { zetaChain: {} });
+
+      const tokenId = Date.now() + 131;
+      const uri = "https://example.com/bridge-backend.json";
+      const mint = bridgedMintPda(tokenId);
+      const nftOrigin = PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      const userTokenAccount = getAssociatedTokenAddressSync(mint, admin.publicKey, false);
+      const receive = () =>
+        program.methods
+          .receiveCrossChainMessage(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender), null)
+          .accounts({
+            programState: programStatePda,
+            nftOrigin,
+            mint,
+            dedupWindow: dedupWindowPda(uri),
+            mintAuthority: mintAuthorityPda,
+            recipient: admin.publicKey,
+            sourceChainConfig: chainConfigPda(zetaChainId),
+            provisionalEscrow: null,
+            custodialTokenAccount: null,
+            inboundOffer: null,
+            pendingInbound: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            recipientTokenAccount: userTokenAccount,
+            payer: admin.publicKey,
+            gateway: gatewaySigner.publicKey,
+            hourlyMetrics: null,
+            inboundValueClaim: null,
+            inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender)),
+          })
+          .remainingAccounts(originPolicyAccounts)
+          .signers([admin, gatewaySigner])
+          .rpc();
+      const transfer = async () => {
+        const { transferNonce } = await program.account.programState.fetch(programStatePda);
+        return program.methods
+          .transferCrossChain(new BN(tokenId), zetaChainId, Buffer.from(zetaChainRecipient), null, null, null)
+          .accountsPartial({
+            programState: programStatePda,
+            nftOrigin,
+            mint,
+            userTokenAccount,
+            user: admin.publicKey,
+            gatewayProgram: new PublicKey(zetaChainTestnetGateway),
+            feeConfig: feeConfigPda,
+            feeTreasury,
+            chainGasConfig: chainConfigPda(zetaChainId),
+            registry: null,
+            receiptConfig: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            zrc20GasVault: null,
+            zrc20VaultTokenAccount: null,
+            gasFeeDestination: null,
+            pendingTransfer: PublicKey.findProgramAddressSync(
+              [Buffer.from("pending_xfer"), transferNonce.toArrayLike(Buffer, 'le', 8)],
+              program.programId
+            )[0],
+            hourlyMetrics: null,
+            insuranceOptIn: null,
+            coOwnership: null,
+            coOwnerConsent: null,
+          })
+          .signers([admin])
+          .rpc();
+      };
+      const setBackend = (bridgeBackend: any) =>
+        program.methods
+          .setBridgeBackend(bridgeBackend)
+          .accounts({ programState: programStatePda, admin: wallet.publicKey })
+          .rpc();
+
+      await receive();
+      // The Wormhole backend is a stub: both directions fail until it is integrated
+      await setBackend({ wormhole: {} });
+      try {
+        for (const attempt of [transfer, receive]) {
+          try {
+            await attempt();
+            assert.fail("The Wormhole backend should refuse every call");
+          } catch (error) {
+            // @ts-ignore
+            assert.include(error.message, "BridgeBackendUnsupported");
+          }
+        }
+      } finally {
+        await setBackend({ zetaChain: {} });
+      }
+
+      await transfer();
+      assert.equal(Number((await getAccount(connection, userTokenAccount)).amount), 0);
+    });
+
+    it("Should recover an NFT misdelivered to a zero-padded recipient once, before the cutoff", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping misdelivery recovery test");
+        return;
+      }
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping misdelivery recovery test");
+        return;
+      }
+
+      const nftOriginFor = (tokenId: number) =>
+        PublicKey.findProgramAddressSync(
+          [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+          program.programId
+        )[0];
+      const deliver = (tokenId: number, uri: string, recipient: PublicKey) =>
+        program.methods
+          .receiveCrossChainMessage(createZetaChainSuccessMessage(tokenId, uri, recipient.toBytes(), solanaSender), null)
+          .accounts({
+            programState: programStatePda,
+            nftOrigin: nftOriginFor(tokenId),
+            mint: bridgedMintPda(tokenId),
+            dedupWindow: dedupWindowPda(uri),
+            mintAuthority: mintAuthorityPda,
+            recipient,
+            sourceChainConfig: chainConfigPda(zetaChainId),
+            provisionalEscrow: null,
+            custodialTokenAccount: null,
+            inboundOffer: null,
+            pendingInbound: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            recipientTokenAccount: getAssociatedTokenAddressSync(bridgedMintPda(tokenId), recipient, true),
+            payer: admin.publicKey,
+            gateway: gatewaySigner.publicKey,
+            hourlyMetrics: null,
+            inboundValueClaim: null,
+            inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, uri, recipient.toBytes(), solanaSender)),
+          })
+          .remainingAccounts(originPolicyAccounts)
+          .signers([admin, gatewaySigner])
+          .rpc();
+      const setCutoff = (cutoffAt: number) =>
+        program.methods
+          .setRecoveryCutoff(new BN(cutoffAt))
+          .accounts({ programState: programStatePda, admin: wallet.publicKey })
+          .rpc();
+      const recover = (tokenId: number, strandedTokenAccount: PublicKey) =>
+        program.methods
+          .recoverMisdelivered(new BN(tokenId), admin.publicKey, Array.from(new Uint8Array(32).fill(5)))
+          .accountsPartial({
+            programState: programStatePda,
+            nftOrigin: nftOriginFor(tokenId),
+            mint: bridgedMintPda(tokenId),
+            strandedTokenAccount,
+            recipient: admin.publicKey,
+            admin: wallet.publicKey,
+          })
+          .rpc({ commitment: "confirmed" });
+
+      // The legacy path minted to the EVM receiver left-padded into a pubkey
+      const padded = new PublicKey(Buffer.concat([Buffer.alloc(12), Buffer.from(zetaChainRecipient)]));
+      const tokenId = Date.now() + 121;
+      await deliver(tokenId, "https://example.com/misdelivered.json", padded);
+      const stranded = getAssociatedTokenAddressSync(bridgedMintPda(tokenId), padded, true);
+
+      try {
+        await setCutoff(1);
+        try {
+          await recover(tokenId, stranded);
+          assert.fail("Origins created after the cutoff should be refused");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "MisdeliveryAfterCutoff");
+        }
+
+        await setCutoff(Math.floor(Date.now() / 1000) + 3600);
+        // A delivery to a real wallet is not a misdelivery
+        const correctId = Date.now() + 122;
+        await deliver(correctId, "https://example.com/delivered.json", admin.publicKey);
+        try {
+          await recover(correctId, getAssociatedTokenAddressSync(bridgedMintPda(correctId), admin.publicKey, false));
+          assert.fail("A token held by a real wallet should not be recoverable");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "NotMisdelivered");
+        }
+
+        const signature = await recover(tokenId, stranded);
+        assert.isTrue((await getAccount(connection, stranded)).isFrozen);
+        assert.equal(
+          Number((await getAccount(connection, getAssociatedTokenAddressSync(bridgedMintPda(tokenId), admin.publicKey, false))).amount),
+          1
+        );
+        assert.equal((await program.account.nftOrigin.fetch(nftOriginFor(tokenId))).strandedSupply.toNumber(), 1);
+        const record = await program.account.misdeliveryRecovery.fetch(
+          PublicKey.findProgramAddressSync(
+            [Buffer.from("misdelivery_recovery"), new BN(tokenId).toArrayLike(Buffer, 'le', 8)],
+            program.programId
+          )[0]
+        );
+        assert.isTrue(record.strandedOwner.equals(padded));
+        assert.isTrue(record.correctRecipient.equals(admin.publicKey));
+        assert.deepEqual(Array.from(record.evidenceHash), Array.from(new Uint8Array(32).fill(5)));
+        const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+        const parser = new anchor.EventParser(program.programId, program.coder);
+        assert.isDefined(Array.from(parser.parseLogs(tx.meta.logMessages)).find((e) => e.name === "misdeliveryRecovered"));
+
+        try {
+          await recover(tokenId, stranded);
+          assert.fail("A token should only be recovered once");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "already in use");
+        }
+      } finally {
+        await setCutoff(0);
+      }
+    });
+
+    it("Should log each handler's events in a fixed order after all of its CPIs", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping event order test");
+        return;
+      }
+
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const eventOrder = async (signature: string) => {
+        const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+        const logs = tx.meta.logMessages;
+        const lastCpi = logs.map((line) => line.endsWith("invoke [2]")).lastIndexOf(true);
+        const firstEvent = logs.findIndex((line) => line.startsWith("Program data:"));
+        assert.isAbove(firstEvent, lastCpi, "events should follow every CPI");
+        return Array.from(parser.parseLogs(logs)).map((event) => event.name);
+      };
+
+      const nativeId = NATIVE_ID_BASE.addn(Date.now() % 1000000 + 2000);
+      const newMint = Keypair.generate();
+      const created = await program.methods
+        .createMintAndNft("https://example.com/event-order-native.json", 0, nativeId, null, null, null)
+        .accounts({
+          nftOrigin: PublicKey.findProgramAddressSync(
+            [Buffer.from("nft_origin"), nativeId.toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+            program.programId
+          )[0],
+          mint: newMint.publicKey,
+          mintAuthority: mintAuthority.publicKey,
+          payer: admin.publicKey,
+          feeConfig: feeConfigPda,
+          feeTreasury: null,
+          sellerFeeConfig: null,
+          feeCreator: null,
+          mintOnly: null,
+          lazyRevealConfig: null,
+          hourlyMetrics: null,
+        })
+        .signers([admin, mintAuthority, newMint])
+        .rpc({ commitment: "confirmed" });
+      assert.deepEqual(await eventOrder(created), ["mintCreated", "nftMinted", "nftOriginCreated"]);
+
+      const tokenId = Date.now() + 111;
+      const uri = "https://example.com/event-order.json";
+      const mint = bridgedMintPda(tokenId);
+      const nftOrigin = PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      const userTokenAccount = getAssociatedTokenAddressSync(mint, admin.publicKey, false);
+      const received = await program.methods
+        .receiveCrossChainMessage(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender), "event order")
+        .accounts({
+          programState: programStatePda,
+          nftOrigin,
+          mint,
+          dedupWindow: dedupWindowPda(uri),
+          mintAuthority: mintAuthorityPda,
+          recipient: admin.publicKey,
+          sourceChainConfig: chainConfigPda(zetaChainId),
+          provisionalEscrow: null,
+          custodialTokenAccount: null,
+          inboundOffer: null,
+          pendingInbound: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          recipientTokenAccount: userTokenAccount,
+          payer: admin.publicKey,
+          gateway: gatewaySigner.publicKey,
+          hourlyMetrics: null,
+          inboundValueClaim: null,
+          inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender)),
+        })
+        .remainingAccounts(originPolicyAccounts)
+        .signers([admin, gatewaySigner])
+        .rpc({ commitment: "confirmed" });
+      assert.deepEqual(await eventOrder(received), ["crossChainMessageReceived"]);
+
+      const { transferNonce } = await program.account.programState.fetch(programStatePda);
+      const departed = await program.methods
+        .transferCrossChain(new BN(tokenId), zetaChainId, Buffer.from(zetaChainRecipient), "event order", null, null)
+        .accountsPartial({
+          programState: programStatePda,
+          nftOrigin,
+          mint,
+          userTokenAccount,
+          user: admin.publicKey,
+          gatewayProgram: new PublicKey(zetaChainTestnetGateway),
+          feeConfig: feeConfigPda,
+          feeTreasury,
+          chainGasConfig: chainConfigPda(zetaChainId),
+          registry: null,
+          receiptConfig: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          zrc20GasVault: null,
+          zrc20VaultTokenAccount: null,
+          gasFeeDestination: null,
+          pendingTransfer: PublicKey.findProgramAddressSync(
+            [Buffer.from("pending_xfer"), transferNonce.toArrayLike(Buffer, 'le', 8)],
+            program.programId
+          )[0],
+          hourlyMetrics: null,
+          insuranceOptIn: null,
+          coOwnership: null,
+          coOwnerConsent: null,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+      assert.deepEqual(await eventOrder(departed), ["crossChainTransferInitiated"]);
+    });
+
+    it("Should deliver into a bound custodial account and reject one for another mint", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping custodial delivery test");
+        return;
+      }
+
+      const client = Keypair.generate();
+      await connection.confirmTransaction(
+        await connection.sendTransaction(
+          new Transaction().add(
+            SystemProgram.transfer({
+              fromPubkey: wallet.publicKey,
+              toPubkey: client.publicKey,
+              lamports: LAMPORTS_PER_SOL,
+            })
+          ),
+          [wallet.payer]
+        ),
+        'confirmed'
+      );
+      const receiverBinding = PublicKey.findProgramAddressSync(
+        [Buffer.from("receiver_binding"), client.publicKey.toBuffer()],
+        program.programId
+      )[0];
+      const nftOriginFor = (tokenId: number) =>
+        PublicKey.findProgramAddressSync(
+          [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+          program.programId
+        )[0];
+      const receive = (tokenId: number, uri: string, custodialTokenAccount: PublicKey | null) =>
+        program.methods
+          .receiveCrossChainMessage(createZetaChainSuccessMessage(tokenId, uri, client.publicKey.toBytes(), solanaSender), null)
+          .accounts({
+            programState: programStatePda,
+            nftOrigin: nftOriginFor(tokenId),
+            mint: bridgedMintPda(tokenId),
+            dedupWindow: dedupWindowPda(uri),
+            mintAuthority: mintAuthorityPda,
+            recipient: client.publicKey,
+            sourceChainConfig: chainConfigPda(zetaChainId),
+            provisionalEscrow: null,
+            custodialTokenAccount,
+            inboundOffer: null,
+            pendingInbound: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            recipientTokenAccount: getAssociatedTokenAddressSync(bridgedMintPda(tokenId), client.publicKey, false),
+            payer: admin.publicKey,
+            gateway: gatewaySigner.publicKey,
+            hourlyMetrics: null,
+            inboundValueClaim: null,
+            inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, uri, client.publicKey.toBytes(), solanaSender)),
+          })
+          .remainingAccounts(originPolicyAccounts)
+          .signers([admin, gatewaySigner])
+          .rpc();
+
+      // ATA mode: the NFT lands in the client's own ATA
+      const tokenId = Date.now() + 97;
+      const mint = bridgedMintPda(tokenId);
+      const clientTokenAccount = getAssociatedTokenAddressSync(mint, client.publicKey, false);
+      await program.methods
+        .setDeliveryMode({ ata: {} })
+        .accounts({ owner: client.publicKey })
+        .signers([client])
+        .rpc();
+      await receive(tokenId, "https://example.com/custody-ata.json", null);
+      assert.equal(Number((await getAccount(connection, clientTokenAccount)).amount), 1);
+      assert.isTrue((await program.account.nftOrigin.fetch(nftOriginFor(tokenId))).logicalOwner.equals(PublicKey.default));
+
+      // Send it out so it can come back into custody; the bridged mint persists
+      const { transferNonce } = await program.account.programState.fetch(programStatePda);
+      await program.methods
+        .transferCrossChain(new BN(tokenId), zetaChainId, Buffer.from(zetaChainRecipient), null, null, null)
+        .accountsPartial({
+          programState: programStatePda,
+          nftOrigin: nftOriginFor(tokenId),
+          mint,
+          userTokenAccount: clientTokenAccount,
+          user: client.publicKey,
+          gatewayProgram: new PublicKey(zetaChainTestnetGateway),
+          feeConfig: feeConfigPda,
+          feeTreasury,
+          chainGasConfig: chainConfigPda(zetaChainId),
+          registry: null,
+          receiptConfig: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          zrc20GasVault: null,
+          zrc20VaultTokenAccount: null,
+          gasFeeDestination: null,
+          pendingTransfer: PublicKey.findProgramAddressSync(
+            [Buffer.from("pending_xfer"), transferNonce.toArrayLike(Buffer, 'le', 8)],
+            program.programId
+          )[0],
+          hourlyMetrics: null,
+          insuranceOptIn: null,
+          coOwnership: null,
+          coOwnerConsent: null,
+        })
+        .signers([client])
+        .rpc();
+
+      // Custodial mode: the custodian's omnibus account for this mint takes the delivery
+      const omnibus = await createAccount(connection, user, mint, user.publicKey, Keypair.generate());
+      await program.methods
+        .setDeliveryMode({ custodial: { tokenAccount: omnibus } })
+        .accounts({ owner: client.publicKey })
+        .signers([client])
+        .rpc();
+      await receive(tokenId, "https://example.com/custody-return.json", omnibus);
+      assert.equal(Number((await getAccount(connection, omnibus)).amount), 1);
+      assert.equal(Number((await getAccount(connection, clientTokenAccount)).amount), 0);
+      assert.isTrue((await program.account.nftOrigin.fetch(nftOriginFor(tokenId))).logicalOwner.equals(client.publicKey));
+      const binding = await program.account.receiverBinding.fetch(receiverBinding);
+      assert.isTrue(binding.deliveryMode.custodial.tokenAccount.equals(omnibus));
+
+      // The bound account holds another mint, so a new token can't be delivered into it
+      try {
+        await receive(tokenId + 1, "https://example.com/custody-wrong-mint.json", omnibus);
+        assert.fail("A custodial account for another mint should be rejected");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "CustodialAccountMismatch");
+      }
+    });
+
+    it("Should let a collection authority administer only its own collection", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping collection authority test");
+        return;
+      }
+      // Delegating is owner-only
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping collection authority test");
+        return;
+      }
+
+      const collectionId = new BN(Date.now() % 1_000_000 + 7);
+      const otherCollectionId = collectionId.addn(1);
+      const collectionAuthorityPda = (id: BN) =>
+        PublicKey.findProgramAddressSync(
+          [Buffer.from("collection_authority"), id.toArrayLike(Buffer, 'le', 8)],
+          program.programId
+        )[0];
+      await program.methods
+        .setCollectionAuthority(collectionId, user.publicKey, {
+          canMint: false,
+          canPause: false,
+          canUpdateMetadata: true,
+        })
+        .accounts({ admin: wallet.publicKey })
+        .rpc();
+      const record = await program.account.collectionAuthority.fetch(collectionAuthorityPda(collectionId));
+      assert.isTrue(record.authority.equals(user.publicKey));
+      assert.isTrue(record.canUpdateMetadata);
+
+      const feed = Keypair.generate().publicKey;
+      const feedId = Array.from(Keypair.generate().publicKey.toBytes());
+      await program.methods
+        .setFloorPriceFeed(collectionId, feed, feedId)
+        .accounts({ admin: user.publicKey, collectionAuthority: collectionAuthorityPda(collectionId) })
+        .signers([user])
+        .rpc();
+      const stats = await program.account.collectionStats.fetch(
+        PublicKey.findProgramAddressSync(
+          [Buffer.from("collection_stats"), collectionId.toArrayLike(Buffer, 'le', 8)],
+          program.programId
+        )[0]
+      );
+      assert.isTrue(stats.floorPriceFeed.equals(feed));
+      assert.deepEqual(Array.from(stats.floorPriceFeedId), feedId);
+
+      // The delegation doesn't reach other collections
+      try {
+        await program.methods
+          .setFloorPriceFeed(otherCollectionId, feed, feedId)
+          .accounts({ admin: user.publicKey, collectionAuthority: null })
+          .signers([user])
+          .rpc();
+        assert.fail("A collection authority should not administer another collection");
+      } catch (error) {
+        assert.include(error.toString(), "Unauthorized");
+      }
+    });
+
+    it("Should reject admin setter calls that change nothing or set a nonsensical value", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping admin setter validation test");
+        return;
+      }
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping admin setter validation test");
+        return;
+      }
+
+      const adminAccounts = { programState: programStatePda, admin: wallet.publicKey };
+      const rejected: [string, () => Promise<string>, string][] = [
+        ["gateway unchanged", () => program.methods.setGateway(state.gateway).accounts(adminAccounts).rpc(), "NoChange"],
+        ["gateway default", () => program.methods.setGateway(PublicKey.default).accounts(adminAccounts).rpc(), "InvalidAdminValue"],
+        ["gateway system program", () => program.methods.setGateway(SystemProgram.programId).accounts(adminAccounts).rpc(), "InvalidAdminValue"],
+        ["gateway this program", () => program.methods.setGateway(program.programId).accounts(adminAccounts).rpc(), "InvalidAdminValue"],
+        ["gas limit", () => program.methods.setGasLimit(state.gasLimit).accounts(adminAccounts).rpc(), "NoChange"],
+        ["contract unchanged", () => program.methods.setUniversalNftContract(state.universalNftContract).accounts(adminAccounts).rpc(), "NoChange"],
+        ["contract zero", () => program.methods.setUniversalNftContract(Array(20).fill(0)).accounts(adminAccounts).rpc(), "InvalidAddress"],
+        [
+          "swap router",
+          () => program.methods.setSwapRouterVersion(state.swapRouterVersion, state.defaultSwapFeeTier).accounts(adminAccounts).rpc(),
+          "NoChange",
+        ],
+        ["reservation fee", () => program.methods.setReservationFee(state.reservationFee).accounts(adminAccounts).rpc(), "NoChange"],
+        [
+          "uri dedup window",
+          () => program.methods.setUriDedupWindow(state.maxUriMintsPerWindow, state.uriDedupWindowSeconds).accounts(adminAccounts).rpc(),
+          "NoChange",
+        ],
+        ["max message bytes", () => program.methods.setMaxMessageBytes(state.maxMessageBytes).accounts(adminAccounts).rpc(), "NoChange"],
+        ["native id base", () => program.methods.setNativeIdBase(state.nativeIdBase).accounts(adminAccounts).rpc(), "NoChange"],
+        ["min user reserve", () => program.methods.setMinUserSolReserve(state.minUserSolReserve).accounts(adminAccounts).rpc(), "NoChange"],
+        ["bridge backend", () => program.methods.setBridgeBackend(state.bridgeBackend).accounts(adminAccounts).rpc(), "NoChange"],
+        ["attestation key unchanged", () => program.methods.setAttestationKey(state.attestationKey).accounts(adminAccounts).rpc(), "NoChange"],
+        ["attestation key this program", () => program.methods.setAttestationKey(program.programId).accounts(adminAccounts).rpc(), "InvalidAdminValue"],
+        // The earlier tests leave these at their defaults
+        ["relayer allowlist", () => program.methods.setRelayerAllowlistEnabled(false).accounts({ admin: wallet.publicKey }).rpc(), "NoChange"],
+        ["heartbeat interval", () => program.methods.setHeartbeatInterval(0).accounts({ admin: wallet.publicKey }).rpc(), "NoChange"],
+        ["recovery cutoff", () => program.methods.setRecoveryCutoff(new BN(0)).accounts(adminAccounts).rpc(), "NoChange"],
+      ];
+      for (const [label, call, expected] of rejected) {
+        try {
+          await call();
+          assert.fail(`${label}: the setter should have been rejected`);
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, expected, label);
+        }
+      }
+
+      // A real change reports the value it replaced
+      const newGasLimit = state.gasLimit.subn(1);
+      const signature = await program.methods.setGasLimit(newGasLimit).accounts(adminAccounts).rpc({ commitment: "confirmed" });
+      try {
+        const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+        const parser = new anchor.EventParser(program.programId, program.coder);
+        const updated = Array.from(parser.parseLogs(tx!.meta!.logMessages!)).find((event) => event.name === "gasLimitUpdated");
+        assert.isOk(updated, "GasLimitUpdated should be emitted");
+        assert.isTrue(updated!.data.previousGasLimit.eq(state.gasLimit));
+        assert.isTrue(updated!.data.gasLimit.eq(newGasLimit));
+      } finally {
+        await program.methods.setGasLimit(state.gasLimit).accounts(adminAccounts).rpc();
+      }
+    });
+
+    it("Should append deliveries to the event log and read them back", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping event log test");
+        return;
+      }
+      const eventLogPda = PublicKey.findProgramAddressSync([Buffer.from("event_log")], program.programId)[0];
+      if (!(await connection.getAccountInfo(eventLogPda))) {
+        // Creating the log is owner-only
+        const state = await program.account.programState.fetch(programStatePda);
+        if (!state.owner.equals(wallet.publicKey)) {
+          console.log("Provider wallet is not the program owner, skipping event log test");
+          return;
+        }
+        await program.methods.initEventLog().accounts({ admin: wallet.publicKey }).rpc();
+      }
+      const before = await program.account.crossChainEventLog.fetch(eventLogPda);
+
+      const tokenId = Date.now() + 19;
+      const uri = "https://example.com/event-log.json";
+      const mint = bridgedMintPda(tokenId);
+      await program.methods
+        .receiveCrossChainMessage(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender), null)
+        .accounts({
+          programState: programStatePda,
+          nftOrigin: PublicKey.findProgramAddressSync(
+            [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+            program.programId
+          )[0],
+          mint,
+          dedupWindow: dedupWindowPda(uri),
+          mintAuthority: mintAuthorityPda,
+          recipient: admin.publicKey,
+          sourceChainConfig: chainConfigPda(zetaChainId),
+          provisionalEscrow: null,
+          custodialTokenAccount: null,
+          inboundOffer: null,
+          pendingInbound: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          recipientTokenAccount: getAssociatedTokenAddressSync(mint, admin.publicKey, false),
+          payer: admin.publicKey,
+          gateway: gatewaySigner.publicKey,
+          hourlyMetrics: null,
+          inboundValueClaim: null,
+          inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender)),
+        })
+        .remainingAccounts(originPolicyAccounts)
+        .signers([admin, gatewaySigner])
+        .rpc();
+
+      const after = await program.account.crossChainEventLog.fetch(eventLogPda);
+      assert.equal(after.total.toNumber(), before.total.toNumber() + 1);
+      assert.equal(after.head, (before.head + 1) % 64);
+      const entries = await program.methods.readEventLog(before.head, 1).accounts({ eventLog: eventLogPda }).view();
+      assert.equal(entries.length, 1);
+      assert.equal(entries[0].eventType, 2, "arrivals are logged as EVENT_LOG_TRANSFER_IN");
+      assert.equal(entries[0].tokenId.toNumber(), tokenId);
+      assert.deepEqual(Array.from(entries[0].destination), Array.from(solanaSender));
+
+      // Reads wrap around the end of the ring and are capped to fit in return data
+      assert.equal((await program.methods.readEventLog(63, 2).accounts({ eventLog: eventLogPda }).view()).length, 2);
+      for (const [fromIndex, count] of [[64, 1], [0, 21]]) {
+        try {
+          await program.methods.readEventLog(fromIndex, count).accounts({ eventLog: eventLogPda }).view();
+          assert.fail("The read should be out of range");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "EventLogReadOutOfRange");
+        }
+      }
+    });
+
+    it("Should hold inbound NFTs in escrow until the recipient accepts or rejects them", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping inbound acceptance test");
+        return;
+      }
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping inbound acceptance test");
+        return;
+      }
+
+      const recipient = Keypair.generate();
+      await connection.confirmTransaction(
+        await connection.sendTransaction(
+          new Transaction().add(
+            SystemProgram.transfer({
+              fromPubkey: wallet.publicKey,
+              toPubkey: recipient.publicKey,
+              lamports: LAMPORTS_PER_SOL,
+            })
+          ),
+          [wallet.payer]
+        ),
+        'confirmed'
+      );
+      const nftOriginFor = (tokenId: number) =>
+        PublicKey.findProgramAddressSync(
+          [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+          program.programId
+        )[0];
+      const offerFor = (tokenId: number) =>
+        PublicKey.findProgramAddressSync(
+          [Buffer.from("inbound_offer"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), recipient.publicKey.toBuffer()],
+          program.programId
+        )[0];
+      const escrowFor = (tokenId: number) => getAssociatedTokenAddressSync(bridgedMintPda(tokenId), mintAuthorityPda, true);
+      const receive = (tokenId: number, uri: string, withOffer: boolean) =>
+        program.methods
+          .receiveCrossChainMessage(createZetaChainSuccessMessage(tokenId, uri, recipient.publicKey.toBytes(), solanaSender), null)
+          .accounts({
+            programState: programStatePda,
+            nftOrigin: nftOriginFor(tokenId),
+            mint: bridgedMintPda(tokenId),
+            dedupWindow: dedupWindowPda(uri),
+            mintAuthority: mintAuthorityPda,
+            recipient: recipient.publicKey,
+            sourceChainConfig: chainConfigPda(zetaChainId),
+            provisionalEscrow: withOffer ? escrowFor(tokenId) : null,
+            custodialTokenAccount: null,
+            inboundOffer: withOffer ? offerFor(tokenId) : null,
+            pendingInbound: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            recipientTokenAccount: getAssociatedTokenAddressSync(bridgedMintPda(tokenId), recipient.publicKey, false),
+            payer: admin.publicKey,
+            gateway: gatewaySigner.publicKey,
+            hourlyMetrics: null,
+            inboundValueClaim: null,
+            inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, uri, recipient.publicKey.toBytes(), solanaSender)),
+          })
+          .remainingAccounts(originPolicyAccounts)
+          .signers([admin, gatewaySigner])
+          .rpc();
+
+      const adminAccounts = { programState: programStatePda, admin: wallet.publicKey };
+      await program.methods.setRequireNftAcceptance(true).accounts(adminAccounts).rpc();
+      try {
+        const acceptedId = Date.now() + 131;
+        try {
+          await receive(acceptedId, "https://example.com/offer-missing.json", false);
+          assert.fail("A delivery without an offer should be rejected while acceptance is required");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "AcceptanceRequired");
+        }
+
+        // The NFT waits in escrow under an offer the relayer paid for
+        await receive(acceptedId, "https://example.com/offer-accepted.json", true);
+        const offer = await program.account.inboundNftOffer.fetch(offerFor(acceptedId));
+        assert.isTrue(offer.recipient.equals(recipient.publicKey));
+        assert.isTrue(offer.payer.equals(admin.publicKey));
+        assert.equal(offer.offerExpiresAt.sub(offer.offeredAt).toNumber(), 7 * 86_400);
+        assert.equal(Number((await getAccount(connection, escrowFor(acceptedId))).amount), 1);
+
+        await program.methods
+          .acceptInboundNft(new BN(acceptedId))
+          .accounts({
+            mint: bridgedMintPda(acceptedId),
+            recipient: recipient.publicKey,
+            payer: admin.publicKey,
+          })
+          .signers([recipient])
+          .rpc();
+        const recipientTokenAccount = getAssociatedTokenAddressSync(bridgedMintPda(acceptedId), recipient.publicKey, false);
+        assert.equal(Number((await getAccount(connection, recipientTokenAccount)).amount), 1);
+        assert.equal(Number((await getAccount(connection, escrowFor(acceptedId))).amount), 0);
+        assert.isNull(await connection.getAccountInfo(offerFor(acceptedId)));
+
+        // A rejected NFT is burned out of escrow
+        const rejectedId = acceptedId + 1;
+        await receive(rejectedId, "https://example.com/offer-rejected.json", true);
+        const reject = (authority: Keypair) => program.methods
+          .rejectInboundNft(new BN(rejectedId), false)
+          .accountsPartial({
+            programState: programStatePda,
+            nftOrigin: nftOriginFor(rejectedId),
+            inboundOffer: offerFor(rejectedId),
+            mint: bridgedMintPda(rejectedId),
+            escrow: escrowFor(rejectedId),
+            authority: authority.publicKey,
+            payer: admin.publicKey,
+            gatewayProgram: null,
+          })
+          .signers([authority])
+          .rpc();
+        // Nobody else can reject before the offer expires
+        try {
+          await reject(admin);
+          assert.fail("Only the recipient should reject an open offer");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "Unauthorized");
+        }
+        await reject(recipient);
+        assert.equal(Number((await getAccount(connection, escrowFor(rejectedId))).amount), 0);
+        assert.equal(Number((await getMint(connection, bridgedMintPda(rejectedId))).supply), 0);
+        assert.isNull(await connection.getAccountInfo(offerFor(rejectedId)));
+        // The burn doesn't make the delivery replayable
+        try {
+          await receive(rejectedId, "https://example.com/offer-rejected.json", true);
+          assert.fail("A burned NFT's message should not deliver again");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "NFTAlreadyDelivered");
+        }
+      } finally {
+        await program.methods.setRequireNftAcceptance(false).accounts(adminAccounts).rpc();
+      }
+    });
+
+    it("Should defer rejected metadata under BestEffort and complete it with retry_metadata", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping metadata policy test");
+        return;
+      }
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping metadata policy test");
+        return;
+      }
+
+      // Token Metadata caps URIs at 200 bytes, so this one fails the metadata CPI
+      const longUriFor = (tokenId: number) => `https://example.com/${tokenId}/${"m".repeat(200)}.json`;
+      const tokenMetadataProgram = new PublicKey("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+      const nftOriginFor = (tokenId: number) =>
+        PublicKey.findProgramAddressSync(
+          [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+          program.programId
+        )[0];
+      const receive = (tokenId: number) =>
+        program.methods
+          .receiveCrossChainMessage(createZetaChainSuccessMessage(tokenId, longUriFor(tokenId), admin.publicKey.toBytes(), solanaSender), null)
+          .accounts({
+            programState: programStatePda,
+            nftOrigin: nftOriginFor(tokenId),
+            mint: bridgedMintPda(tokenId),
+            dedupWindow: dedupWindowPda(longUriFor(tokenId)),
+            mintAuthority: mintAuthorityPda,
+            recipient: admin.publicKey,
+            sourceChainConfig: chainConfigPda(zetaChainId),
+            provisionalEscrow: null,
+            custodialTokenAccount: null,
+            inboundOffer: null,
+            pendingInbound: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            recipientTokenAccount: getAssociatedTokenAddressSync(bridgedMintPda(tokenId), admin.publicKey, false),
+            payer: admin.publicKey,
+            gateway: gatewaySigner.publicKey,
+            hourlyMetrics: null,
+            inboundValueClaim: null,
+            inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, longUriFor(tokenId), admin.publicKey.toBytes(), solanaSender)),
+          })
+          .remainingAccounts(originPolicyAccounts)
+          .signers([admin, gatewaySigner])
+          .rpc({ commitment: "confirmed" });
+      const retry = (tokenId: number) =>
+        program.methods
+          .retryMetadata(new BN(tokenId))
+          .accountsPartial({
+            nftOrigin: nftOriginFor(tokenId),
+            mint: bridgedMintPda(tokenId),
+            tokenMetadataProgram,
+            metadata: PublicKey.findProgramAddressSync(
+              [Buffer.from("metadata"), tokenMetadataProgram.toBuffer(), bridgedMintPda(tokenId).toBuffer()],
+              tokenMetadataProgram
+            )[0],
+            payer: user.publicKey,
+          })
+          .remainingAccounts(originPolicyAccounts)
+          .signers([user])
+          .rpc();
+
+      const adminAccounts = { programState: programStatePda, admin: wallet.publicKey };
+      const attestationKey = Keypair.generate();
+      try {
+        // Atomic, the default: the metadata CPI decides, and it stays off in this build
+        const atomicId = Date.now() + 151;
+        await receive(atomicId);
+        assert.isFalse((await program.account.nftOrigin.fetch(nftOriginFor(atomicId))).metadataPending);
+
+        // BestEffort: the token is delivered and its metadata deferred
+        await program.methods.setMetadataPolicy({ bestEffort: {} }).accounts(adminAccounts).rpc();
+        const tokenId = atomicId + 1;
+        const signature = await receive(tokenId);
+        const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+        const parser = new anchor.EventParser(program.programId, program.coder);
+        const deferred = Array.from(parser.parseLogs(tx.meta.logMessages)).find((event) => event.name === "metadataDeferred");
+        assert.ok(deferred);
+        assert.equal(deferred.data.tokenId.toNumber(), tokenId);
+        assert.isTrue((await program.account.nftOrigin.fetch(nftOriginFor(tokenId))).metadataPending);
+        const recipientTokenAccount = getAssociatedTokenAddressSync(bridgedMintPda(tokenId), admin.publicKey, false);
+        assert.equal(Number((await getAccount(connection, recipientTokenAccount)).amount), 1);
+
+        // Retrying with the same URI fails as an atomic delivery would
+        try {
+          await retry(tokenId);
+          assert.fail("Retrying metadata that is still rejected should fail");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "MetadataRejected");
+        }
+
+        // Once an attested URI within the limits replaces it, anyone can complete the metadata
+        await program.methods.setAttestationKey(attestationKey.publicKey).accounts(adminAccounts).rpc();
+        const shortUri = "https://example.com/short.json";
+        const ed25519Ix = Ed25519Program.createInstructionWithPrivateKey({
+          privateKey: attestationKey.secretKey,
+          message: Buffer.concat([
+            Buffer.from("universal-nft:attach_metadata:"),
+            new BN(tokenId).toArrayLike(Buffer, 'le', 8),
+            new BN(CHAIN_ID_ZETACHAIN_TESTNET).toArrayLike(Buffer, 'le', 8),
+            Buffer.from(shortUri),
+          ]),
+        });
+        const signatureOffset = ed25519Ix.data.readUInt16LE(2);
+        await program.methods
+          .attachMetadata(new BN(tokenId), shortUri, Array.from(ed25519Ix.data.subarray(signatureOffset, signatureOffset + 64)))
+          .accounts({ programState: programStatePda, nftOrigin: nftOriginFor(tokenId), admin: wallet.publicKey })
+          .preInstructions([ed25519Ix])
+          .rpc();
+        await retry(tokenId);
+        assert.isFalse((await program.account.nftOrigin.fetch(nftOriginFor(tokenId))).metadataPending);
+        // Cleared only because the CPI created the metadata account
+        const [metadata] = PublicKey.findProgramAddressSync(
+          [Buffer.from("metadata"), tokenMetadataProgram.toBuffer(), bridgedMintPda(tokenId).toBuffer()],
+          tokenMetadataProgram
+        );
+        assert.isNotNull(await connection.getAccountInfo(metadata));
+
+        try {
+          await retry(tokenId);
+          assert.fail("Completed metadata should not be retried");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "MetadataNotPending");
+        }
+      } finally {
+        const current = await program.account.programState.fetch(programStatePda);
+        if (current.metadataPolicy.bestEffort) {
+          await program.methods.setMetadataPolicy({ atomic: {} }).accounts(adminAccounts).rpc();
+        }
+        if (!current.attestationKey.equals(PublicKey.default)) {
+          await program.methods.setAttestationKey(PublicKey.default).accounts(adminAccounts).rpc();
+        }
+      }
+    });
+
+    it("Should throttle retry_metadata per slot", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping metadata sync throttle test");
+        return;
+      }
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping metadata sync throttle test");
+        return;
+      }
+
+      const tokenMetadataProgram = new PublicKey("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+      const nftOriginFor = (tokenId: number) =>
+        PublicKey.findProgramAddressSync(
+          [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+          program.programId
+        )[0];
+      const longUriFor = (tokenId: number) => `https://example.com/${tokenId}/${"m".repeat(200)}.json`;
+      const retryIx = (tokenId: number) =>
+        program.methods
+          .retryMetadata(new BN(tokenId))
+          .accountsPartial({
+            nftOrigin: nftOriginFor(tokenId),
+            mint: bridgedMintPda(tokenId),
+            tokenMetadataProgram,
+            metadata: PublicKey.findProgramAddressSync(
+              [Buffer.from("metadata"), tokenMetadataProgram.toBuffer(), bridgedMintPda(tokenId).toBuffer()],
+              tokenMetadataProgram
+            )[0],
+            payer: user.publicKey,
+          })
+          .remainingAccounts(originPolicyAccounts)
+          .instruction();
+
+      const adminAccounts = { programState: programStatePda, admin: wallet.publicKey };
+      const attestationKey = Keypair.generate();
+      const firstId = Date.now() + 171;
+      const tokenIds = [firstId, firstId + 1];
+      try {
+        // Two deliveries with deferred metadata, then URIs the Token Metadata program accepts
+        await program.methods.setMetadataPolicy({ bestEffort: {} }).accounts(adminAccounts).rpc();
+        await program.methods.setAttestationKey(attestationKey.publicKey).accounts(adminAccounts).rpc();
+        for (const tokenId of tokenIds) {
+          await program.methods
+            .receiveCrossChainMessage(createZetaChainSuccessMessage(tokenId, longUriFor(tokenId), admin.publicKey.toBytes(), solanaSender), null)
+            .accounts({
+              programState: programStatePda,
+              nftOrigin: nftOriginFor(tokenId),
+              mint: bridgedMintPda(tokenId),
+              dedupWindow: dedupWindowPda(longUriFor(tokenId)),
+              mintAuthority: mintAuthorityPda,
+              recipient: admin.publicKey,
+              sourceChainConfig: chainConfigPda(zetaChainId),
+              provisionalEscrow: null,
+              custodialTokenAccount: null,
+              inboundOffer: null,
+              pendingInbound: null,
+              receiptMint: null,
+              receiptTokenAccount: null,
+              recipientTokenAccount: getAssociatedTokenAddressSync(bridgedMintPda(tokenId), admin.publicKey, false),
+              payer: admin.publicKey,
+              gateway: gatewaySigner.publicKey,
+              hourlyMetrics: null,
+              inboundValueClaim: null,
+              inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, longUriFor(tokenId), admin.publicKey.toBytes(), solanaSender)),
+            })
+            .remainingAccounts(originPolicyAccounts)
+            .signers([admin, gatewaySigner])
+            .rpc();
+          const shortUri = `https://example.com/${tokenId}.json`;
+          const ed25519Ix = Ed25519Program.createInstructionWithPrivateKey({
+            privateKey: attestationKey.secretKey,
+            message: Buffer.concat([
+              Buffer.from("universal-nft:attach_metadata:"),
+              new BN(tokenId).toArrayLike(Buffer, 'le', 8),
+              new BN(CHAIN_ID_ZETACHAIN_TESTNET).toArrayLike(Buffer, 'le', 8),
+              Buffer.from(shortUri),
+            ]),
+          });
+          const signatureOffset = ed25519Ix.data.readUInt16LE(2);
+          await program.methods
+            .attachMetadata(new BN(tokenId), shortUri, Array.from(ed25519Ix.data.subarray(signatureOffset, signatureOffset + 64)))
+            .accounts({ programState: programStatePda, nftOrigin: nftOriginFor(tokenId), admin: wallet.publicKey })
+            .preInstructions([ed25519Ix])
+            .rpc();
+        }
+
+        await program.methods.setMetadataSyncThrottle(1).accounts(adminAccounts).rpc();
+        assert.equal((await program.account.programState.fetch(programStatePda)).maxMetadataUriRequestsPerSlot, 1);
+
+        // Both retries in one transaction share a slot, so the second is over the limit
+        try {
+          const tx = new anchor.web3.Transaction().add(await retryIx(tokenIds[0]), await retryIx(tokenIds[1]));
+          await provider.sendAndConfirm(tx, [user]);
+          assert.fail("A second metadata request in the same slot should be throttled");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "MetadataSyncThrottled");
+        }
+        assert.isTrue((await program.account.nftOrigin.fetch(nftOriginFor(tokenIds[1]))).metadataPending);
+
+        // Lifting the cap lets them through together
+        await program.methods.setMetadataSyncThrottle(0).accounts(adminAccounts).rpc();
+        const tx = new anchor.web3.Transaction().add(await retryIx(tokenIds[0]), await retryIx(tokenIds[1]));
+        await provider.sendAndConfirm(tx, [user]);
+        for (const tokenId of tokenIds) {
+          assert.isFalse((await program.account.nftOrigin.fetch(nftOriginFor(tokenId))).metadataPending);
+        }
+      } finally {
+        const current = await program.account.programState.fetch(programStatePda);
+        if (current.maxMetadataUriRequestsPerSlot !== 0) {
+          await program.methods.setMetadataSyncThrottle(0).accounts(adminAccounts).rpc();
+        }
+        if (current.metadataPolicy.bestEffort) {
+          await program.methods.setMetadataPolicy({ atomic: {} }).accounts(adminAccounts).rpc();
+        }
+        if (!current.attestationKey.equals(PublicKey.default)) {
+          await program.methods.setAttestationKey(PublicKey.default).accounts(adminAccounts).rpc();
+        }
+      }
+    });
+
+    it("Should commit to the burn so destination contracts can verify it", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping burn commitment test");
+        return;
+      }
+
+      const tokenId = Date.now() + 181;
+      const uri = "https://example.com/burn-commitment.json";
+      const mint = bridgedMintPda(tokenId);
+      const nftOrigin = PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      const tokenAccount = getAssociatedTokenAddressSync(mint, admin.publicKey, false);
+      await program.methods
+        .receiveCrossChainMessage(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender), null)
+        .accounts({
+          programState: programStatePda,
+          nftOrigin,
+          mint,
+          dedupWindow: dedupWindowPda(uri),
+          mintAuthority: mintAuthorityPda,
+          recipient: admin.publicKey,
+          sourceChainConfig: chainConfigPda(zetaChainId),
+          provisionalEscrow: null,
+          custodialTokenAccount: null,
+          inboundOffer: null,
+          pendingInbound: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          recipientTokenAccount: tokenAccount,
+          payer: admin.publicKey,
+          gateway: gatewaySigner.publicKey,
+          hourlyMetrics: null,
+          inboundValueClaim: null,
+          inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender)),
+        })
+        .remainingAccounts(originPolicyAccounts)
+        .signers([admin, gatewaySigner])
+        .rpc();
+
+      const { transferNonce } = await program.account.programState.fetch(programStatePda);
+      const pendingTransfer = PublicKey.findProgramAddressSync(
+        [Buffer.from("pending_xfer"), transferNonce.toArrayLike(Buffer, 'le', 8)],
+        program.programId
+      )[0];
+      const signature = await program.methods
+        .transferCrossChain(new BN(tokenId), zetaChainId, Buffer.from(zetaChainRecipient), null, null, null)
+        .accounts({
+          nftOrigin,
+          mint,
+          userTokenAccount: tokenAccount,
+          user: admin.publicKey,
+          gatewayProgram: new PublicKey(zetaChainTestnetGateway),
+          feeConfig: feeConfigPda,
+          feeTreasury,
+          chainGasConfig: chainConfigPda(zetaChainId),
+          registry: null,
+          receiptConfig: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          zrc20GasVault: null,
+          zrc20VaultTokenAccount: null,
+          gasFeeDestination: null,
+          hourlyMetrics: null,
+          insuranceOptIn: null,
+          coOwnership: null,
+          coOwnerConsent: null,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+      const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const initiated = Array.from(parser.parseLogs(tx.meta.logMessages))
+        .find((event) => event.name === "crossChainTransferInitiated").data;
+
+      // keccak256(mint || token_id BE || owner || slot BE), recomputed from the event alone
+      assert.equal(initiated.burnSlot.toNumber(), tx.slot);
+      assert.ok(initiated.owner.equals(admin.publicKey));
+      const expected = keccak_256(Buffer.concat([
+        initiated.mint.toBuffer(),
+        initiated.tokenId.toArrayLike(Buffer, 'be', 8),
+        initiated.owner.toBuffer(),
+        initiated.burnSlot.toArrayLike(Buffer, 'be', 8),
+      ]));
+      assert.deepEqual(Array.from(initiated.burnCommitment), Array.from(expected));
+
+      // The same commitment is kept on the pending transfer and served by the view
+      const stored = await program.account.pendingTransfer.fetch(pendingTransfer);
+      assert.deepEqual(Array.from(stored.burnCommitment), Array.from(expected));
+      const viewed = await program.methods
+        .getBurnCommitment(new BN(tokenId))
+        .accounts({ pendingTransfer })
+        .view();
+      assert.deepEqual(Array.from(viewed), Array.from(expected));
+
+      // A pending transfer of another token is refused
+      try {
+        await program.methods.getBurnCommitment(new BN(tokenId + 1)).accounts({ pendingTransfer }).view();
+        assert.fail("A pending transfer for another token should be refused");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "PendingTransferTokenMismatch");
+      }
+    });
+
+    it("Should leave a mint-only record and create the NFT origin on first use under lazy origins", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping lazy origin test");
+        return;
+      }
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping lazy origin test");
+        return;
+      }
+
+      const adminAccounts = { programState: programStatePda, admin: wallet.publicKey };
+      const tokenId = NATIVE_ID_BASE.addn(Date.now() % 1000000 + 4000);
+      const nftOrigin = PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), tokenId.toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      const mintOnly = PublicKey.findProgramAddressSync(
+        [Buffer.from("mint_only"), tokenId.toArrayLike(Buffer, 'le', 8)],
+        program.programId
+      )[0];
+      const newMint = Keypair.generate();
+      const holderTokenAccount = getAssociatedTokenAddressSync(newMint.publicKey, mintAuthority.publicKey, false);
+      const initLazily = (holder: Keypair, tokenAccount: PublicKey, uri = "https://example.com/lazy.json") =>
+        program.methods
+          .initializeNftOriginLazy(tokenId, uri)
+          .accounts({ nftOrigin, mintOnly, holderTokenAccount: tokenAccount, holder: holder.publicKey })
+          .signers([holder])
+          .rpc();
+
+      await program.methods.setLazyOriginsEnabled(true).accounts(adminAccounts).rpc();
+      try {
+        // With lazy origins on, passing nft_origin is refused
+        try {
+          await program.methods
+            .createMintAndNft("https://example.com/lazy.json", 0, tokenId, null, null, null)
+            .accounts({
+              nftOrigin,
+              mint: newMint.publicKey,
+              mintAuthority: mintAuthority.publicKey,
+              payer: admin.publicKey,
+              feeConfig: feeConfigPda,
+              feeTreasury: null,
+              sellerFeeConfig: null,
+              feeCreator: null,
+              mintOnly: null,
+              lazyRevealConfig: null,
+              hourlyMetrics: null,
+            })
+            .signers([admin, mintAuthority, newMint])
+            .rpc();
+          assert.fail("A full origin should not be created while lazy origins are enabled");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "LazyOriginAccountsMismatch");
+        }
+
+        await program.methods
+          .createMintAndNft("https://example.com/lazy.json", 0, tokenId, null, null, null)
+          .accounts({
+            nftOrigin: null,
+            mint: newMint.publicKey,
+            mintAuthority: mintAuthority.publicKey,
+            payer: admin.publicKey,
+            feeConfig: feeConfigPda,
+            feeTreasury: null,
+            sellerFeeConfig: null,
+            feeCreator: null,
+            mintOnly,
+            lazyRevealConfig: null,
+            hourlyMetrics: null,
+          })
+          .signers([admin, mintAuthority, newMint])
+          .rpc();
+        assert.isNull(await connection.getAccountInfo(nftOrigin));
+        const record = await program.account.nftMintOnly.fetch(mintOnly);
+        assert.equal(record.tokenId.toString(), tokenId.toString());
+        assert.ok(record.mint.equals(newMint.publicKey));
+        assert.deepEqual(
+          Buffer.from(record.uriHash),
+          createHash("sha256").update("https://example.com/lazy.json").digest()
+        );
+        const originRent = await connection.getMinimumBalanceForRentExemption(program.account.nftOrigin.size);
+        assert.isBelow((await connection.getAccountInfo(mintOnly)).lamports, originRent);
+      } finally {
+        await program.methods.setLazyOriginsEnabled(false).accounts(adminAccounts).rpc();
+      }
+
+      // Only a holder of the token may create the origin
+      const stranger = Keypair.generate();
+      await connection.confirmTransaction(await connection.requestAirdrop(stranger.publicKey, LAMPORTS_PER_SOL));
+      const strangerTokenAccount = await createAssociatedTokenAccount(connection, stranger, newMint.publicKey, stranger.publicKey);
+      try {
+        await initLazily(stranger, strangerTokenAccount);
+        assert.fail("A wallet without the token should not create its origin");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "Unauthorized");
+      }
+
+      // The holder can't swap in a URI of their own
+      await connection.confirmTransaction(await connection.requestAirdrop(mintAuthority.publicKey, LAMPORTS_PER_SOL));
+      try {
+        await initLazily(mintAuthority, holderTokenAccount, "https://example.com/rare-traits.json");
+        assert.fail("An origin with a URI other than the minted one should be refused");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "LazyOriginUriMismatch");
+      }
+
+      // The holder pays the origin's rent and gets the mint-only record's back
+      await initLazily(mintAuthority, holderTokenAccount);
+      const origin = await program.account.nftOrigin.fetch(nftOrigin);
+      assert.equal(origin.tokenId.toString(), tokenId.toString());
+      assert.ok(origin.mint.equals(newMint.publicKey));
+      assert.equal(origin.metadataUri, "https://example.com/lazy.json");
+      assert.isNull(await connection.getAccountInfo(mintOnly));
+    });
+
+    it("Should apply each origin contract's policy to its deliveries", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping origin policy test");
+        return;
+      }
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping origin policy test");
+        return;
+      }
+
+      const adminAccounts = { programState: programStatePda, admin: wallet.publicKey };
+      const originChain = new BN(CHAIN_ID_ZETACHAIN_TESTNET);
+      // Two partner contracts; deliveries come from whichever is the configured universal contract
+      const contractA = Buffer.from(new Uint8Array(20).fill(0xa1));
+      const contractB = Buffer.from(new Uint8Array(20).fill(0xb2));
+      const policyPda = (contract: Buffer) => PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("origin_policy"),
+          originChain.toArrayLike(Buffer, 'le', 8),
+          createHash("sha256").update(contract).digest(),
+        ],
+        program.programId
+      )[0];
+      const royaltyRecipient = Keypair.generate().publicKey;
+      const partnerAuthority = Keypair.generate().publicKey;
+      const nftOriginFor = (tokenId: number) => PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      const deliver = async (tokenId: number, policy: PublicKey | null) => {
+        const uri = `https://example.com/origin-policy/${tokenId}.json`;
+        const signature = await program.methods
+          .receiveCrossChainMessage(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender), null)
+          .accounts({
+            programState: programStatePda,
+            nftOrigin: nftOriginFor(tokenId),
+            mint: bridgedMintPda(tokenId),
+            dedupWindow: dedupWindowPda(uri),
+            mintAuthority: mintAuthorityPda,
+            recipient: admin.publicKey,
+            sourceChainConfig: chainConfigPda(zetaChainId),
+            provisionalEscrow: null,
+            custodialTokenAccount: null,
+            inboundOffer: null,
+            pendingInbound: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            recipientTokenAccount: getAssociatedTokenAddressSync(bridgedMintPda(tokenId), admin.publicKey, false),
+            payer: admin.publicKey,
+            gateway: gatewaySigner.publicKey,
+            hourlyMetrics: null,
+            inboundValueClaim: null,
+            inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender)),
+          })
+          .remainingAccounts(policy ? [{ pubkey: policy, isSigner: false, isWritable: false }] : [])
+          .signers([admin, gatewaySigner])
+          .rpc({ commitment: "confirmed" });
+        const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+        const parser = new anchor.EventParser(program.programId, program.coder);
+        return Array.from(parser.parseLogs(tx.meta.logMessages)).find((event) => event.name === "originPolicyApplied");
+      };
+
+      // A takes 5% royalties under the program's authority; B keeps its own, immutable
+      await program.methods
+        .setOriginPolicy(originChain, contractA, 500, royaltyRecipient, { program: {} }, true)
+        .accounts({ admin: wallet.publicKey, originPolicy: policyPda(contractA) })
+        .rpc();
+      await program.methods
+        .setOriginPolicy(originChain, contractB, 0, PublicKey.default, { retained: { authority: partnerAuthority } }, false)
+        .accounts({ admin: wallet.publicKey, originPolicy: policyPda(contractB) })
+        .rpc();
+      try {
+        await program.methods
+          .setOriginPolicy(originChain, contractA, 10_001, royaltyRecipient, { program: {} }, true)
+          .accounts({ admin: wallet.publicKey, originPolicy: policyPda(contractA) })
+          .rpc();
+        assert.fail("Royalties over 10000 basis points should be rejected");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "InvalidOriginPolicy");
+      }
+
+      const firstId = Date.now() + 191;
+      try {
+        await program.methods.setUniversalNftContract(Array.from(contractA)).accounts(adminAccounts).rpc();
+        const fromA = await deliver(firstId, policyPda(contractA));
+        assert.ok(fromA, "Expected an OriginPolicyApplied event");
+        assert.equal(fromA.data.royaltyBps, 500);
+        assert.ok(fromA.data.royaltyRecipient.equals(royaltyRecipient));
+        assert.ok(fromA.data.updateAuthority.equals(mintAuthorityPda));
+        assert.isTrue(fromA.data.isMutable);
+
+        // Another origin's policy can't stand in for this one
+        try {
+          await deliver(firstId + 1, policyPda(contractB));
+          assert.fail("B's policy should not apply to deliveries from A");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "OriginPolicyMismatch");
+        }
+
+        await program.methods.setUniversalNftContract(Array.from(contractB)).accounts(adminAccounts).rpc();
+        const fromB = await deliver(firstId + 2, policyPda(contractB));
+        assert.ok(fromB, "Expected an OriginPolicyApplied event");
+        assert.equal(fromB.data.royaltyBps, 0);
+        assert.ok(fromB.data.updateAuthority.equals(partnerAuthority));
+        assert.isFalse(fromB.data.isMutable);
+
+        // The policy account can't be left out to skip the policy
+        try {
+          await deliver(firstId + 3, null);
+          assert.fail("A delivery without its origin policy account should be rejected");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "OriginPolicyMismatch");
+        }
+
+        // An origin without a policy passes its empty PDA and gets the defaults
+        const contractC = Buffer.from(new Uint8Array(20).fill(0xc3));
+        await program.methods.setUniversalNftContract(Array.from(contractC)).accounts(adminAccounts).rpc();
+        assert.isUndefined(await deliver(firstId + 4, policyPda(contractC)));
+      } finally {
+        await program.methods.setUniversalNftContract(state.universalNftContract).accounts(adminAccounts).rpc();
+        for (const contract of [contractA, contractB]) {
+          await program.methods
+            .removeOriginPolicy(originChain, contract)
+            .accounts({ admin: wallet.publicKey, originPolicy: policyPda(contract) })
+            .rpc();
+        }
+      }
+    });
+
+    it("Should pay the destination's gas from its ZRC-20 gas vault while it holds enough", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping ZRC-20 gas vault test");
+        return;
+      }
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping ZRC-20 gas vault test");
+        return;
+      }
+
+      // Transfers to ZetaChain itself carry the zero ZRC-20
+      const zrc20 = new Array(20).fill(0);
+      const vaultPda = PublicKey.findProgramAddressSync(
+        [Buffer.from("zrc20_vault"), Buffer.from(zrc20)],
+        program.programId
+      )[0];
+      // A vault keeps its first mint, so later runs reuse it
+      const existing = await program.account.zrc20GasVault.fetchNullable(vaultPda);
+      const gasMint = existing ? existing.mint : await createMint(connection, wallet.payer, wallet.publicKey, null, 6);
+      const vaultTokenAccount = getAssociatedTokenAddressSync(gasMint, vaultPda, true);
+      const feeDestination = await createAccount(connection, wallet.payer, gasMint, Keypair.generate().publicKey, Keypair.generate());
+      const funding = await createAccount(connection, wallet.payer, gasMint, wallet.publicKey, Keypair.generate());
+      await mintTo(connection, wallet.payer, gasMint, funding, wallet.payer, 7);
+
+      await program.methods
+        .setZrc20GasVault(zrc20, new BN(5))
+        .accountsPartial({ admin: wallet.publicKey, zrc20GasVault: vaultPda, mint: gasMint, vaultTokenAccount, feeDestination })
+        .rpc();
+      await program.methods
+        .depositZrc20Gas(zrc20, new BN(7))
+        .accountsPartial({ zrc20GasVault: vaultPda, vaultTokenAccount, depositorTokenAccount: funding, depositor: wallet.publicKey })
+        .rpc();
+      assert.equal(Number((await getAccount(connection, vaultTokenAccount)).amount), 7);
+
+      const nftOriginFor = (tokenId: number) => PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      const sendOut = async (tokenId: number) => {
+        const uri = `https://example.com/gas-vault/${tokenId}.json`;
+        const tokenAccount = getAssociatedTokenAddressSync(bridgedMintPda(tokenId), admin.publicKey, false);
+        await program.methods
+          .receiveCrossChainMessage(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender), null)
+          .accounts({
+            programState: programStatePda,
+            nftOrigin: nftOriginFor(tokenId),
+            mint: bridgedMintPda(tokenId),
+            dedupWindow: dedupWindowPda(uri),
+            mintAuthority: mintAuthorityPda,
+            recipient: admin.publicKey,
+            sourceChainConfig: chainConfigPda(zetaChainId),
+            provisionalEscrow: null,
+            custodialTokenAccount: null,
+            inboundOffer: null,
+            pendingInbound: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            recipientTokenAccount: tokenAccount,
+            payer: admin.publicKey,
+            gateway: gatewaySigner.publicKey,
+            hourlyMetrics: null,
+            inboundValueClaim: null,
+            inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender)),
+          })
+          .remainingAccounts(originPolicyAccounts)
+          .signers([admin, gatewaySigner])
+          .rpc();
+        const signature = await program.methods
+          .transferCrossChain(new BN(tokenId), zetaChainId, Buffer.from(zetaChainRecipient), null, null, null)
+          .accounts({
+            nftOrigin: nftOriginFor(tokenId),
+            mint: bridgedMintPda(tokenId),
+            userTokenAccount: tokenAccount,
+            user: admin.publicKey,
+            gatewayProgram: new PublicKey(zetaChainTestnetGateway),
+            feeConfig: feeConfigPda,
+            feeTreasury,
+            chainGasConfig: chainConfigPda(zetaChainId),
+            registry: null,
+            receiptConfig: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            zrc20GasVault: vaultPda,
+            zrc20VaultTokenAccount: vaultTokenAccount,
+            gasFeeDestination: feeDestination,
+            hourlyMetrics: null,
+            insuranceOptIn: null,
+            coOwnership: null,
+            coOwnerConsent: null,
+          })
+          .signers([admin])
+          .rpc({ commitment: "confirmed" });
+        const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+        const parser = new anchor.EventParser(program.programId, program.coder);
+        return Array.from(parser.parseLogs(tx.meta.logMessages)).find((event) => event.name === "gasPaidFromVault");
+      };
+
+      try {
+        const tokenId = Date.now() + 193;
+        const paid = await sendOut(tokenId);
+        assert.ok(paid, "Expected a GasPaidFromVault event");
+        assert.equal(paid.data.amount.toNumber(), 5);
+        assert.equal(paid.data.remaining.toNumber(), 2);
+        assert.equal(Number((await getAccount(connection, feeDestination)).amount), 5);
+
+        // Two left doesn't cover the fee, so the vault sits this one out
+        assert.isUndefined(await sendOut(tokenId + 1));
+        assert.equal(Number((await getAccount(connection, vaultTokenAccount)).amount), 2);
+      } finally {
+        const { amount } = await getAccount(connection, vaultTokenAccount);
+        if (Number(amount) > 0) {
+          await program.methods
+            .withdrawZrc20Gas(zrc20, new BN(amount.toString()), funding)
+            .accountsPartial({
+              programState: programStatePda,
+              zrc20GasVault: vaultPda,
+              vaultTokenAccount,
+              destTokenAccount: funding,
+              admin: wallet.publicKey,
+            })
+            .rpc();
+        }
+      }
+      assert.equal(Number((await getAccount(connection, vaultTokenAccount)).amount), 0);
+    });
+
+    it("Should trip the next_token_id invariant when the counter is corrupted", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping invariant test");
+        return;
+      }
+      // The corrupting instruction only exists in runtime-invariants builds
+      if (!program.idl.instructions.some((ix) => ix.name === "debugSetNextTokenId")) {
+        console.log("Program built without runtime-invariants, skipping invariant test");
+        return;
+      }
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping invariant test");
+        return;
+      }
+
+      const adminAccounts = { programState: programStatePda, admin: wallet.publicKey };
+      // The methods builder is typed from the default build's IDL, which lacks the instruction
+      const methods = program.methods as any;
+      // Back at or below a native id already issued, next_token_id would hand it out again
+      try {
+        await methods.debugSetNextTokenId(state.maxNativeTokenId).accounts(adminAccounts).rpc();
+        assert.fail("Corrupting next_token_id should trip the invariant");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "InvariantViolated");
+      }
+      assert.equal(
+        (await program.account.programState.fetch(programStatePda)).nextTokenId.toString(),
+        state.nextTokenId.toString()
+      );
+
+      // Above every issued id the counter is sound
+      await methods.debugSetNextTokenId(state.maxNativeTokenId.addn(1)).accounts(adminAccounts).rpc();
+      await methods.debugSetNextTokenId(state.nextTokenId).accounts(adminAccounts).rpc();
+    });
+
+    it("Should mint lazy-reveal tokens with a placeholder and reveal their traits from the VRF seed", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping lazy reveal test");
+        return;
+      }
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping lazy reveal test");
+        return;
+      }
+
+      const collectionId = new BN(Date.now());
+      const configPda = PublicKey.findProgramAddressSync(
+        [Buffer.from("lazy_reveal"), collectionId.toArrayLike(Buffer, 'le', 8)],
+        program.programId
+      )[0];
+      const seedPda = PublicKey.findProgramAddressSync(
+        [Buffer.from("reveal_seed"), collectionId.toArrayLike(Buffer, 'le', 8)],
+        program.programId
+      )[0];
+      const vrfAuthority = Keypair.generate();
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(vrfAuthority.publicKey, LAMPORTS_PER_SOL),
+        'confirmed'
+      );
+      const uriBase = "https://example.com/reveal";
+      const configure = (revealTime: number, vrf = vrfAuthority.publicKey) =>
+        program.methods
+          .configureLazyReveal(collectionId, new BN(revealTime), 3, uriBase, vrf)
+          .accountsPartial({ admin: wallet.publicKey, lazyRevealConfig: configPda, collectionAuthority: null })
+          .rpc();
+
+      // The admin setting the terms can't also supply the seed
+      try {
+        await configure(Math.floor(Date.now() / 1000) + 3600, wallet.publicKey);
+        assert.fail("The collection admin should not be its own VRF authority");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "InvalidLazyRevealConfig");
+      }
+      await configure(Math.floor(Date.now() / 1000) + 3600);
+      // Mints naming the collection pass its seller fee config; this one has no creator split
+      const sellerFeeConfig = PublicKey.findProgramAddressSync(
+        [Buffer.from("seller_fee_config"), collectionId.toArrayLike(Buffer, 'le', 8)],
+        program.programId
+      )[0];
+      await program.methods
+        .setSellerFeeConfig(collectionId, [])
+        .accounts({ programState: programStatePda, sellerFeeConfig, collectionAuthority: null, admin: wallet.publicKey })
+        .rpc();
+
+      const nftOriginFor = (tokenId: BN) => PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), tokenId.toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      const mintInto = (tokenId: BN, revealConfig: PublicKey | null) => {
+        const newMint = Keypair.generate();
+        return program.methods
+          .createMintAndNft("https://example.com/chosen-traits.json", 0, tokenId, null, null, collectionId)
+          .accounts({
+            nftOrigin: nftOriginFor(tokenId),
+            mint: newMint.publicKey,
+            mintAuthority: mintAuthority.publicKey,
+            payer: admin.publicKey,
+            feeConfig: feeConfigPda,
+            feeTreasury: null,
+            sellerFeeConfig,
+            feeCreator: null,
+            mintOnly: null,
+            lazyRevealConfig: revealConfig,
+            hourlyMetrics: null,
+          })
+          .signers([admin, mintAuthority, newMint])
+          .rpc();
+      };
+
+      // A mint into the collection can't leave its reveal config out to keep a chosen URI
+      const tokenId = NATIVE_ID_BASE.addn(Date.now() % 1000000 + 7000);
+      try {
+        await mintInto(tokenId, null);
+        assert.fail("A mint naming a reveal collection should need its config");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "LazyRevealConfigMismatch");
+      }
+
+      // The minter's URI is ignored while the collection is unrevealed
+      const nftOrigin = nftOriginFor(tokenId);
+      await mintInto(tokenId, configPda);
+      let origin = await program.account.nftOrigin.fetch(nftOrigin);
+      assert.equal(origin.metadataUri, `${uriBase}/${collectionId.toString()}/placeholder.json`);
+      assert.equal(origin.revealCollectionId.toString(), collectionId.toString());
+
+      try {
+        await program.methods.triggerReveal(collectionId).accounts({ cranker: wallet.publicKey }).rpc();
+        assert.fail("Triggering before reveal_time should be rejected");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "RevealNotDue");
+      }
+
+      // Nothing has been requested yet, so the reveal time can still move
+      await configure(Math.floor(Date.now() / 1000) - 60);
+      await program.methods.triggerReveal(collectionId).accounts({ cranker: wallet.publicKey }).rpc();
+
+      // Once the seed is on its way, nobody can pick token ids for the collection
+      try {
+        await mintInto(tokenId.addn(1), configPda);
+        assert.fail("A collection whose reveal was requested should take no more mints");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "RevealAlreadyRequested");
+      }
+
+      const seed = Array.from(new Uint8Array(32).fill(42));
+      try {
+        await program.methods
+          .fulfillReveal(collectionId, seed)
+          .accountsPartial({ lazyRevealConfig: configPda, revealSeed: seedPda, vrfAuthority: wallet.publicKey })
+          .rpc();
+        assert.fail("Only the VRF authority should fulfill the reveal");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "Unauthorized");
+      }
+      await program.methods
+        .fulfillReveal(collectionId, seed)
+        .accountsPartial({ lazyRevealConfig: configPda, revealSeed: seedPda, vrfAuthority: vrfAuthority.publicKey })
+        .signers([vrfAuthority])
+        .rpc();
+
+      await program.methods
+        .updateTokenUri(collectionId, tokenId)
+        .accountsPartial({ lazyRevealConfig: configPda, revealSeed: seedPda, nftOrigin, cranker: wallet.publicKey })
+        .rpc();
+      const traits = createHash("sha256")
+        .update(Buffer.concat([Buffer.from(seed), tokenId.toArrayLike(Buffer, 'le', 8)]))
+        .digest()
+        .subarray(0, 3)
+        .toString("hex");
+      origin = await program.account.nftOrigin.fetch(nftOrigin);
+      assert.equal(origin.metadataUri, `${uriBase}/${collectionId.toString()}/${traits}.json`);
+      assert.isNull(origin.revealCollectionId);
+
+      // Revealed once; the traits can't be re-rolled
+      try {
+        await program.methods
+          .updateTokenUri(collectionId, tokenId)
+          .accountsPartial({ lazyRevealConfig: configPda, revealSeed: seedPda, nftOrigin, cranker: wallet.publicKey })
+          .rpc();
+        assert.fail("A revealed token should not be revealed again");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "NotAwaitingReveal");
+      }
+    });
+
+    it("Should send several NFTs in one batched gateway call, checking every entry before burning", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping outbound batch test");
+        return;
+      }
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping outbound batch test");
+        return;
+      }
+
+      const chainConfig = PublicKey.findProgramAddressSync(
+        [Buffer.from("chain_gas"), zetaChainId.toArrayLike(Buffer, 'le', 8)],
+        program.programId
+      )[0];
+      const setBatchSupport = (supportsBatch: boolean) =>
+        program.methods
+          .setChainBatchSupport(zetaChainId, supportsBatch)
+          .accountsPartial({ admin: wallet.publicKey, chainGasConfig: chainConfig })
+          .rpc();
+      const nftOriginFor = (tokenId: number) => PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      const tokenAccountFor = (tokenId: number) =>
+        getAssociatedTokenAddressSync(bridgedMintPda(tokenId), admin.publicKey, false);
+
+      const baseId = Date.now() + 211;
+      const tokenIds = [baseId, baseId + 1, baseId + 2];
+      for (const tokenId of tokenIds) {
+        const uri = `https://example.com/batch-out/${tokenId}.json`;
+        await program.methods
+          .receiveCrossChainMessage(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender), null)
+          .accounts({
+            programState: programStatePda,
+            nftOrigin: nftOriginFor(tokenId),
+            mint: bridgedMintPda(tokenId),
+            dedupWindow: dedupWindowPda(uri),
+            mintAuthority: mintAuthorityPda,
+            recipient: admin.publicKey,
+            sourceChainConfig: chainConfigPda(zetaChainId),
+            provisionalEscrow: null,
+            custodialTokenAccount: null,
+            inboundOffer: null,
+            pendingInbound: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            recipientTokenAccount: tokenAccountFor(tokenId),
+            payer: admin.publicKey,
+            gateway: gatewaySigner.publicKey,
+            hourlyMetrics: null,
+            inboundValueClaim: null,
+            inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender)),
+          })
+          .remainingAccounts(originPolicyAccounts)
+          .signers([admin, gatewaySigner])
+          .rpc();
+      }
+
+      // Every NFT here arrived from ZetaChain, so that chain's stats count their departure
+      const chainStats = PublicKey.findProgramAddressSync(
+        [Buffer.from("chain_stats"), zetaChainId.toArrayLike(Buffer, 'le', 8)],
+        program.programId
+      )[0];
+      const entryAccounts = (ids: number[]) => ids.flatMap((tokenId) => [
+        { pubkey: nftOriginFor(tokenId), isSigner: false, isWritable: true },
+        { pubkey: bridgedMintPda(tokenId), isSigner: false, isWritable: true },
+        { pubkey: tokenAccountFor(tokenId), isSigner: false, isWritable: true },
+        { pubkey: chainStats, isSigner: false, isWritable: true },
+      ]);
+      const feeConfig = await program.account.feeConfig.fetch(feeConfigPda);
+      const sendBatch = (remainingAccounts: anchor.web3.AccountMeta[]) =>
+        program.methods
+          .transferCrossChainBatch(tokenIds.map((id) => new BN(id)), Buffer.from(zetaChainRecipient), zetaChainId, new BN(500000))
+          .accountsPartial({
+            programState: programStatePda,
+            chainGasConfig: chainConfig,
+            registry: null,
+            user: admin.publicKey,
+            gatewayProgram: new PublicKey(zetaChainTestnetGateway),
+            feeConfig: feeConfigPda,
+            feeTreasury: feeConfig.treasury,
+            feeDiscountTokenAccount: null,
+            hourlyMetrics: null,
+          })
+          .remainingAccounts(remainingAccounts)
+          .signers([admin])
+          .rpc({ commitment: "confirmed" });
+      const expectRejected = async (remainingAccounts: anchor.web3.AccountMeta[], errorName: string) => {
+        try {
+          await sendBatch(remainingAccounts);
+          assert.fail(`Batch should be rejected with ${errorName}`);
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, errorName);
+        }
+        for (const tokenId of tokenIds) {
+          assert.equal(Number((await getAccount(connection, tokenAccountFor(tokenId))).amount), 1);
+        }
+      };
+
+      try {
+        // The destination contract hasn't declared the batch kind
+        await setBatchSupport(false);
+        await expectRejected(entryAccounts(tokenIds), "BatchTransferUnsupported");
+
+        await setBatchSupport(true);
+        // One account short, and two entries swapped: nothing is burned either way
+        await expectRejected(entryAccounts(tokenIds).slice(0, -1), "InvalidBatchAccounts");
+        await expectRejected(entryAccounts([tokenIds[1], tokenIds[0], tokenIds[2]]), "InvalidBatchAccounts");
+
+        // A fee treasury other than the configured one is refused
+        try {
+          await program.methods
+            .transferCrossChainBatch(tokenIds.map((id) => new BN(id)), Buffer.from(zetaChainRecipient), zetaChainId, new BN(500000))
+            .accountsPartial({
+              programState: programStatePda,
+              chainGasConfig: chainConfig,
+              registry: null,
+              user: admin.publicKey,
+              gatewayProgram: new PublicKey(zetaChainTestnetGateway),
+              feeConfig: feeConfigPda,
+              feeTreasury: Keypair.generate().publicKey,
+              feeDiscountTokenAccount: null,
+              hourlyMetrics: null,
+            })
+            .remainingAccounts(entryAccounts(tokenIds))
+            .signers([admin])
+            .rpc();
+          assert.fail("A batch should pay its fee into the configured treasury");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "FeeTreasuryMismatch");
+        }
+
+        // Batching doesn't get a sender below the SOL reserve past it
+        const balance = await connection.getBalance(admin.publicKey);
+        await program.methods
+          .setMinUserSolReserve(new BN(balance).add(new BN(LAMPORTS_PER_SOL)))
+          .accounts({ programState: programStatePda, admin: wallet.publicKey })
+          .rpc();
+        try {
+          await expectRejected(entryAccounts(tokenIds), "InsufficientUserBalance");
+        } finally {
+          await program.methods
+            .setMinUserSolReserve(new BN(0))
+            .accounts({ programState: programStatePda, admin: wallet.publicKey })
+            .rpc();
+        }
+
+        const nonceBefore = (await program.account.programState.fetch(programStatePda)).transferNonce;
+        const treasuryBefore = await connection.getBalance(feeConfig.treasury);
+        const signature = await sendBatch(entryAccounts(tokenIds));
+        for (const tokenId of tokenIds) {
+          assert.equal(Number((await getAccount(connection, tokenAccountFor(tokenId))).amount), 0);
+        }
+        // One nonce for the batch, one fee per NFT
+        const nonceAfter = (await program.account.programState.fetch(programStatePda)).transferNonce;
+        assert.equal(nonceAfter.toString(), nonceBefore.addn(1).toString());
+        assert.equal(
+          (await connection.getBalance(feeConfig.treasury)) - treasuryBefore,
+          feeConfig.transferFeeLamports.toNumber() * tokenIds.length
+        );
+        const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+        const parser = new anchor.EventParser(program.programId, program.coder);
+        const events = Array.from(parser.parseLogs(tx.meta.logMessages));
+        assert.equal(events.filter((event) => event.name === "feeCollected").length, tokenIds.length);
+        const sent = events.find((event) => event.name === "crossChainBatchTransferInitiated");
+        assert.ok(sent, "Expected a CrossChainBatchTransferInitiated event");
+        assert.deepEqual(sent.data.tokenIds.map((id: BN) => id.toNumber()), tokenIds);
+        assert.equal(sent.data.destinationChain.toString(), zetaChainId.toString());
+        assert.equal(sent.data.nonce.toString(), nonceBefore.toString());
+      } finally {
+        await setBatchSupport(false);
+      }
+    });
+
+    it("Should only register a chain id that ZetaChain's chain registry vouches for", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping chain registry test");
+        return;
+      }
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping chain registry test");
+        return;
+      }
+
+      const registryPda = PublicKey.findProgramAddressSync([Buffer.from("registry")], program.programId)[0];
+      if (!(await connection.getAccountInfo(registryPda))) {
+        await program.methods.initRegistry().accounts({ admin: wallet.publicKey }).rpc();
+      }
+      // No observer program is deployed locally, so its registry account never exists here
+      const observerProgram = Keypair.generate().publicKey;
+      await program.methods.setChainRegistryConfig(observerProgram).accounts({ admin: wallet.publicKey }).rpc();
+      const zetaChainRegistry = PublicKey.findProgramAddressSync([Buffer.from("chain_registry")], observerProgram)[0];
+
+      const zrc20 = Array.from(new Uint8Array(20).fill(8));
+      const validate = (account: PublicKey) =>
+        program.methods
+          .validateChainIdAgainstZetaRegistry(new BN(97), zrc20, Buffer.from(new Uint8Array(20).fill(6)), 0)
+          .accounts({ zetaChainRegistry: account, admin: wallet.publicKey })
+          .rpc();
+      for (const account of [registryPda, zetaChainRegistry]) {
+        try {
+          await validate(account);
+          assert.fail("A chain registry not owned by the observer program should be rejected");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "ChainRegistryMismatch");
+        }
+      }
+      const registry = await program.account.registry.fetch(registryPda);
+      assert.isUndefined(
+        registry.entries.slice(0, registry.count).find((entry) => Buffer.from(entry.zrc20).equals(Buffer.from(zrc20)))
+      );
+
+      // A registry already at the current entry stride has nothing to migrate
+      try {
+        await program.methods.migrateRegistry().accounts({ payer: wallet.publicKey }).rpc();
+        assert.fail("A current registry should not be migrated");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "NoChange");
+      }
+    });
+
+    it("Should reconcile a token's escrow with the hold or offer that claims it", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping escrow reconciliation test");
+        return;
+      }
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping escrow reconciliation test");
+        return;
+      }
+
+      const recipient = Keypair.generate();
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(recipient.publicKey, LAMPORTS_PER_SOL),
+        'confirmed'
+      );
+      const nftOriginFor = (tokenId: number) => PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      const offerFor = (tokenId: number) => PublicKey.findProgramAddressSync(
+        [Buffer.from("inbound_offer"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), recipient.publicKey.toBuffer()],
+        program.programId
+      )[0];
+      const escrowFor = (tokenId: number) => getAssociatedTokenAddressSync(bridgedMintPda(tokenId), mintAuthorityPda, true);
+      const receive = (tokenId: number, withOffer: boolean) => {
+        const uri = `https://example.com/escrow/${tokenId}.json`;
+        return program.methods
+          .receiveCrossChainMessage(createZetaChainSuccessMessage(tokenId, uri, recipient.publicKey.toBytes(), solanaSender), null)
+          .accounts({
+            programState: programStatePda,
+            nftOrigin: nftOriginFor(tokenId),
+            mint: bridgedMintPda(tokenId),
+            dedupWindow: dedupWindowPda(uri),
+            mintAuthority: mintAuthorityPda,
+            recipient: recipient.publicKey,
+            sourceChainConfig: chainConfigPda(zetaChainId),
+            provisionalEscrow: withOffer ? escrowFor(tokenId) : null,
+            custodialTokenAccount: null,
+            inboundOffer: withOffer ? offerFor(tokenId) : null,
+            pendingInbound: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            recipientTokenAccount: getAssociatedTokenAddressSync(bridgedMintPda(tokenId), recipient.publicKey, false),
+            payer: admin.publicKey,
+            gateway: gatewaySigner.publicKey,
+            hourlyMetrics: null,
+            inboundValueClaim: null,
+            inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, uri, recipient.publicKey.toBytes(), solanaSender)),
+          })
+          .remainingAccounts(originPolicyAccounts)
+          .signers([admin, gatewaySigner])
+          .rpc();
+      };
+      const status = (tokenId: number, offer: PublicKey | null) =>
+        program.methods
+          .getEscrowStatus(new BN(tokenId))
+          .accountsPartial({ nftOrigin: nftOriginFor(tokenId), escrow: escrowFor(tokenId), inboundOffer: offer })
+          .view();
+      const reconcile = async (tokenId: number, offer: PublicKey | null) => {
+        const signature = await program.methods
+          .reconcileEscrow(new BN(tokenId))
+          .accountsPartial({ nftOrigin: nftOriginFor(tokenId), escrow: escrowFor(tokenId), inboundOffer: offer, cranker: wallet.publicKey })
+          .rpc({ commitment: "confirmed" });
+        const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+        const parser = new anchor.EventParser(program.programId, program.coder);
+        return Array.from(parser.parseLogs(tx.meta.logMessages));
+      };
+      const findingOf = (events: any[]) => Object.keys(events.find((event) => event.name === "escrowReconciled").data.finding)[0];
+
+      // Delivered straight to the recipient: no escrow, nothing claims one
+      const tokenId = Date.now() + 241;
+      await receive(tokenId, false);
+      assert.deepEqual((await status(tokenId, null)).finding, { empty: {} });
+      let events = await reconcile(tokenId, null);
+      assert.equal(findingOf(events), "empty");
+      assert.isUndefined(events.find((event) => event.name === "escrowDriftFlagged"));
+
+      // Sent into the escrow by hand, the token has no record claiming it
+      await createAssociatedTokenAccount(connection, wallet.payer, bridgedMintPda(tokenId), mintAuthorityPda, undefined, undefined, undefined, true);
+      await transfer(
+        connection,
+        wallet.payer,
+        getAssociatedTokenAddressSync(bridgedMintPda(tokenId), recipient.publicKey, false),
+        escrowFor(tokenId),
+        recipient,
+        1
+      );
+      events = await reconcile(tokenId, null);
+      assert.equal(findingOf(events), "orphanedEscrow");
+      const flagged = events.find((event) => event.name === "escrowDriftFlagged");
+      assert.ok(flagged, "Expected an EscrowDriftFlagged event");
+      assert.equal(flagged.data.escrowAmount.toNumber(), 1);
+
+      // An unaccepted offer accounts for its escrowed token
+      const adminAccounts = { programState: programStatePda, admin: wallet.publicKey };
+      await program.methods.setRequireNftAcceptance(true).accounts(adminAccounts).rpc();
+      try {
+        const offeredId = tokenId + 1;
+        await receive(offeredId, true);
+        const offered = await status(offeredId, offerFor(offeredId));
+        assert.deepEqual(offered.finding, { held: {} });
+        assert.isTrue(offered.hasOffer);
+        assert.equal(findingOf(await reconcile(offeredId, offerFor(offeredId))), "held");
+
+        try {
+          await reconcile(tokenId, offerFor(offeredId));
+          assert.fail("Another token's offer should be rejected");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "InboundOfferMismatch");
+        }
+      } finally {
+        await program.methods.setRequireNftAcceptance(false).accounts(adminAccounts).rpc();
+      }
+    });
+
+    it("Should count receives in the current hour's metrics bucket", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping hourly metrics test");
+        return;
+      }
+
+      const now = await connection.getBlockTime(await connection.getSlot());
+      const hourBucket = new BN(Math.floor(now / 3600));
+      const hourlyMetrics = PublicKey.findProgramAddressSync(
+        [Buffer.from("hourly_metrics"), hourBucket.toArrayLike(Buffer, 'le', 8)],
+        program.programId
+      )[0];
+      if (!(await connection.getAccountInfo(hourlyMetrics))) {
+        await program.methods.openHourlyMetrics(hourBucket).accounts({ payer: wallet.publicKey }).rpc();
+      }
+      const before = await program.methods.getHourlyMetrics(hourBucket).accounts({ hourlyMetrics }).view();
+
+      const tokenId = Date.now() + 251;
+      const uri = `https://example.com/metrics/${tokenId}.json`;
+      const mint = bridgedMintPda(tokenId);
+      await program.methods
+        .receiveCrossChainMessage(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender), null)
+        .accounts({
+          programState: programStatePda,
+          nftOrigin: PublicKey.findProgramAddressSync(
+            [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+            program.programId
+          )[0],
+          mint,
+          dedupWindow: dedupWindowPda(uri),
+          mintAuthority: mintAuthorityPda,
+          recipient: admin.publicKey,
+          sourceChainConfig: chainConfigPda(zetaChainId),
+          provisionalEscrow: null,
+          custodialTokenAccount: null,
+          inboundOffer: null,
+          pendingInbound: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          recipientTokenAccount: getAssociatedTokenAddressSync(mint, admin.publicKey, false),
+          payer: admin.publicKey,
+          gateway: gatewaySigner.publicKey,
+          hourlyMetrics,
+          inboundValueClaim: null,
+          inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender)),
+        })
+        .remainingAccounts(originPolicyAccounts)
+        .signers([admin, gatewaySigner])
+        .rpc();
+
+      const after = await program.methods.getHourlyMetrics(hourBucket).accounts({ hourlyMetrics }).view();
+      assert.equal(after.hourBucket.toString(), hourBucket.toString());
+      assert.equal(after.receives, before.receives + 1);
+      assert.equal(after.mints, before.mints);
+
+      try {
+        await program.methods
+          .closeStaleMetrics(hourBucket)
+          .accounts({ hourlyMetrics, payer: after.payer, closer: wallet.publicKey })
+          .rpc();
+        assert.fail("The current hour's bucket should not be closable");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "MetricsNotStale");
+      }
+
+      try {
+        await program.methods.openHourlyMetrics(hourBucket.addn(24)).accounts({ payer: wallet.publicKey }).rpc();
+        assert.fail("Buckets more than an hour ahead should be rejected");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "InvalidHourBucket");
+      }
+    });
+
+    it("Should carry a sender note in the message without touching notes-free payloads", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping transfer note test");
+        return;
+      }
+      // Setting the message limit is owner-only
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping transfer note test");
+        return;
+      }
+
+      const nftOriginPda = (tokenId: number) => PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const eventsOf = async (signature: string) => {
+        const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+        return Array.from(parser.parseLogs(tx.meta.logMessages));
+      };
+      const receive = async (tokenId: number, uri: string, note?: Buffer) => {
+        const mint = bridgedMintPda(tokenId);
+        let message = createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender);
+        if (note) {
+          // Note trailer: tag 5, one length byte, the note
+          message = Buffer.concat([message, Buffer.from([5, note.length]), note]);
+        }
+        const signature = await program.methods
+          .receiveCrossChainMessage(message, null)
+          .accounts({
+            programState: programStatePda,
+            nftOrigin: nftOriginPda(tokenId),
+            mint,
+            dedupWindow: dedupWindowPda(uri),
+            mintAuthority: mintAuthorityPda,
+            recipient: admin.publicKey,
+            sourceChainConfig: chainConfigPda(zetaChainId),
+            provisionalEscrow: null,
+            custodialTokenAccount: null,
+            inboundOffer: null,
+            pendingInbound: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            recipientTokenAccount: getAssociatedTokenAddressSync(mint, admin.publicKey, false),
+            payer: admin.publicKey,
+            gateway: gatewaySigner.publicKey,
+            hourlyMetrics: null,
+            inboundValueClaim: null,
+            inboundReceipt: inboundReceiptPda(message),
+          })
+          .remainingAccounts(originPolicyAccounts)
+          .signers([admin, gatewaySigner])
+          .rpc({ commitment: "confirmed" });
+        return (await eventsOf(signature)).find((event) => event.name === "crossChainMessageReceived");
+      };
+      const transfer = async (tokenId: number, note: Buffer | null) => {
+        const mint = bridgedMintPda(tokenId);
+        const { transferNonce } = await program.account.programState.fetch(programStatePda, "confirmed");
+        const signature = await program.methods
+          .transferCrossChain(new BN(tokenId), zetaChainId, Buffer.from(zetaChainRecipient), null, note, null)
+          .accountsPartial({
+            programState: programStatePda,
+            nftOrigin: nftOriginPda(tokenId),
+            mint,
+            userTokenAccount: getAssociatedTokenAddressSync(mint, admin.publicKey, false),
+            user: admin.publicKey,
+            gatewayProgram: new PublicKey(zetaChainTestnetGateway),
+            feeConfig: feeConfigPda,
+            feeTreasury,
+            chainGasConfig: chainConfigPda(zetaChainId),
+            registry: null,
+            receiptConfig: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            zrc20GasVault: null,
+            zrc20VaultTokenAccount: null,
+            gasFeeDestination: null,
+            pendingTransfer: PublicKey.findProgramAddressSync(
+              [Buffer.from("pending_xfer"), transferNonce.toArrayLike(Buffer, 'le', 8)],
+              program.programId
+            )[0],
+            hourlyMetrics: null,
+            insuranceOptIn: null,
+            coOwnership: null,
+            coOwnerConsent: null,
+          })
+          .signers([admin])
+          .rpc({ commitment: "confirmed" });
+        return (await eventsOf(signature)).find((event) => event.name === "outboundUriHashed");
+      };
+
+      // Inbound: the note is re-emitted when present and null when absent
+      const firstTokenId = Date.now() + 261;
+      const shortUri = `https://example.com/note/${firstTokenId}.json`;
+      const received = await receive(firstTokenId, shortUri, Buffer.from("Happy birthday!"));
+      assert.equal(received.data.note, "Happy birthday!");
+      const plain = await receive(firstTokenId + 1, `${shortUri}?plain`);
+      assert.isNull(plain.data.note);
+
+      // Over 128 bytes, or not UTF-8, the note is rejected before anything is burned
+      for (const [note, error] of [
+        [Buffer.alloc(129, 0x61), "NoteTooLong"],
+        [Buffer.from([0xff, 0xfe]), "InvalidNoteEncoding"],
+      ] as [Buffer, string][]) {
+        try {
+          await transfer(firstTokenId, note);
+          assert.fail(`Expected ${error}`);
+        } catch (e) {
+          // @ts-ignore
+          assert.include(e.message, error);
+        }
+      }
+
+      // The note counts toward max_message_bytes: with it, the full URI needs 2 + 40 more
+      // bytes, and the hash-only form is that much larger too. Both carry the 38-byte
+      // provenance of an NFT that arrived without one
+      const note = Buffer.alloc(40, 0x6e);
+      const longUri = `https://example.com/${"n".repeat(280)}.json`;
+      const fullBytes = 5 * 32 + 32 + Math.ceil(longUri.length / 32) * 32 + 38 + 33 + 2 + note.length + 38;
+      const hashOnlyBytes = 5 * 32 + 32 + 96 + 38 + 33 + 2 + note.length + 38;
+      const budgetTokenId = firstTokenId + 2;
+      await receive(budgetTokenId, longUri);
+      await receive(budgetTokenId + 1, `${longUri}?hashed`);
+      const setLimit = (bytes: number) =>
+        program.methods
+          .setMaxMessageBytes(bytes)
+          .accounts({ programState: programStatePda, admin: wallet.publicKey })
+          .rpc({ commitment: "confirmed" });
+      try {
+        await setLimit(fullBytes);
+        assert.isUndefined(await transfer(budgetTokenId, note));
+
+        await setLimit(fullBytes - 1);
+        const hashed = await transfer(budgetTokenId + 1, note);
+        assert.ok(hashed, "Expected an OutboundUriHashed event");
+        assert.equal(hashed.data.messageBytes, hashOnlyBytes);
+      } finally {
+        await program.methods
+          .setMaxMessageBytes(0)
+          .accounts({ programState: programStatePda, admin: wallet.publicKey })
+          .rpc();
+      }
+    });
+
+    it("Should stamp new NFTOrigins with the current schema version", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping NFTOrigin schema version test");
+        return;
+      }
+
+      const tokenId = Date.now() + 271;
+      const uri = `https://example.com/schema/${tokenId}.json`;
+      const mint = bridgedMintPda(tokenId);
+      const nftOrigin = PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      await program.methods
+        .receiveCrossChainMessage(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender), null)
+        .accounts({
+          programState: programStatePda,
+          nftOrigin,
+          mint,
+          dedupWindow: dedupWindowPda(uri),
+          mintAuthority: mintAuthorityPda,
+          recipient: admin.publicKey,
+          sourceChainConfig: chainConfigPda(zetaChainId),
+          provisionalEscrow: null,
+          custodialTokenAccount: null,
+          inboundOffer: null,
+          pendingInbound: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          recipientTokenAccount: getAssociatedTokenAddressSync(mint, admin.publicKey, false),
+          payer: admin.publicKey,
+          gateway: gatewaySigner.publicKey,
+          hourlyMetrics: null,
+          inboundValueClaim: null,
+          inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender)),
+        })
+        .remainingAccounts(originPolicyAccounts)
+        .signers([admin, gatewaySigner])
+        .rpc({ commitment: "confirmed" });
+
+      const version = Number(
+        program.idl.constants.find((c) => c.name.replace(/_/g, "").toLowerCase() === "nftoriginversion").value
+      );
+      const origin = await program.account.nftOrigin.fetch(nftOrigin, "confirmed");
+      assert.equal(origin.schemaVersion, version);
+      // The version byte sits right after the discriminator
+      const info = await connection.getAccountInfo(nftOrigin, "confirmed");
+      assert.equal(info.data[8], version);
+
+      // A current account has nothing to migrate
+      try {
+        await program.methods
+          .migrateNftOrigin(new BN(tokenId))
+          .accounts({ nftOrigin, mint, payer: wallet.publicKey })
+          .rpc();
+        assert.fail("Migrating a current NFTOrigin should be rejected");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "NoChange");
+      }
+    });
+
+    it("Should export the registry and import it back without clobbering entries", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping config export test");
+        return;
+      }
+      // Importing is owner-only
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping config export test");
+        return;
+      }
+
+      const registryPda = PublicKey.findProgramAddressSync([Buffer.from("registry")], program.programId)[0];
+      if (!(await connection.getAccountInfo(registryPda))) {
+        await program.methods.initRegistry().accounts({ admin: wallet.publicKey }).rpc();
+      }
+      const zrc20 = Array.from(new Uint8Array(20).fill(0x41));
+      const exportedContract = Buffer.from(new Uint8Array(20).fill(0x51));
+      await program.methods
+        .setRegistryEntry(zrc20, exportedContract, new BN(97), 1)
+        .accounts({ admin: wallet.publicKey })
+        .rpc();
+
+      // Every registry page, each `version || kind || page || page_count || count || entries`
+      const firstPage: Buffer = await program.methods.exportConfig(0, 0).view();
+      assert.equal(firstPage[0], 2);
+      assert.equal(firstPage[1], 0);
+      const pageCount = firstPage[3];
+      const pages = [firstPage];
+      for (let page = 1; page < pageCount; page++) {
+        pages.push(await program.methods.exportConfig(0, page).view());
+      }
+      try {
+        await program.methods.exportConfig(0, pageCount).view();
+        assert.fail("Exporting past the last page should fail");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "InvalidConfigPage");
+      }
+      const registry = await program.account.registry.fetch(registryPda);
+      assert.equal(pages.reduce((total, page) => total + page[4], 0), registry.count);
+      const pageWithEntry = pages.find((page) => page.includes(Buffer.from(zrc20)));
+      assert.isDefined(pageWithEntry);
+
+      const importPage = async (blob: Buffer, force: boolean) => {
+        const signature = await program.methods
+          .importConfig(blob, force)
+          .accounts({ admin: wallet.publicKey })
+          .rpc({ commitment: "confirmed" });
+        const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+        const parser = new anchor.EventParser(program.programId, program.coder);
+        return Array.from(parser.parseLogs(tx.meta.logMessages)).find((event) => event.name === "configImported");
+      };
+
+      // Importing what the registry already holds changes nothing
+      for (const page of pages) {
+        const imported = await importPage(page, false);
+        assert.equal(imported.data.imported, 0);
+      }
+
+      // A diverged entry is only replaced with force
+      await program.methods
+        .setRegistryEntry(zrc20, Buffer.from(new Uint8Array(20).fill(0x61)), new BN(97), 1)
+        .accounts({ admin: wallet.publicKey })
+        .rpc();
+      try {
+        await importPage(pageWithEntry, false);
+        assert.fail("Import should not overwrite a different entry without force");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "ConfigEntryExists");
+      }
+      const forced = await importPage(pageWithEntry, true);
+      assert.equal(forced.data.imported, 1);
+      assert.equal(forced.data.replaced, 1);
+      const restored: Buffer = await program.methods.lookupConnectedContract(zrc20).view();
+      assert.isTrue(restored.equals(exportedContract));
+
+      // Pages from another format version are refused outright
+      const futureVersion = Buffer.from(pageWithEntry);
+      futureVersion[0] = 3;
+      try {
+        await importPage(futureVersion, true);
+        assert.fail("Import should refuse an unknown format version");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "UnsupportedConfigVersion");
+      }
+    });
+
+    it("Should export chain configs and origin policies and import them back into their PDAs", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping config export test");
+        return;
+      }
+      // Importing is owner-only
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping config export test");
+        return;
+      }
+
+      const importPage = async (blob: Buffer, force: boolean, accounts: PublicKey[]) => {
+        const signature = await program.methods
+          .importConfig(blob, force)
+          .accounts({ admin: wallet.publicKey })
+          .remainingAccounts(accounts.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })))
+          .rpc({ commitment: "confirmed" });
+        const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+        const parser = new anchor.EventParser(program.programId, program.coder);
+        return Array.from(parser.parseLogs(tx.meta.logMessages)).find((event) => event.name === "configImported");
+      };
+
+      // A chain config comes out of the account passed and goes back into the same PDA
+      const chainId = new BN(9_184_002);
+      await ensureChainConfig(chainId);
+      await program.methods
+        .setChainTrust(chainId, 1, 600)
+        .accountsPartial({ admin: wallet.publicKey, chainGasConfig: chainConfigPda(chainId) })
+        .rpc();
+      const configPage: Buffer = await program.methods
+        .exportConfig(1, 0)
+        .remainingAccounts([{ pubkey: chainConfigPda(chainId), isSigner: false, isWritable: false }])
+        .view();
+      assert.deepEqual(Array.from(configPage.subarray(0, 5)), [2, 1, 0, 1, 1]);
+      const unchanged = await importPage(configPage, false, [chainConfigPda(chainId)]);
+      assert.equal(unchanged.data.kind, 1);
+      assert.equal(unchanged.data.imported, 0);
+
+      // provisional_hold_seconds sits after chain_id, both gas limits and trust_level
+      const longerHold = Buffer.from(configPage);
+      longerHold.writeUInt32LE(3600, 5 + 8 + 8 + 8 + 1);
+      try {
+        await importPage(longerHold, false, [chainConfigPda(chainId)]);
+        assert.fail("Import should not overwrite a different chain config without force");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "ConfigEntryExists");
+      }
+      try {
+        await importPage(longerHold, true, [chainConfigPda(new BN(9_184_003))]);
+        assert.fail("Import should refuse an account other than the entry's PDA");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "ConfigAccountMismatch");
+      }
+      const replaced = await importPage(longerHold, true, [chainConfigPda(chainId)]);
+      assert.equal(replaced.data.replaced, 1);
+      const config = await program.account.chainGasConfig.fetch(chainConfigPda(chainId));
+      assert.equal(config.provisionalHoldSeconds, 3600);
+      assert.equal(config.trustLevel, 1);
+
+      // A removed origin policy is recreated from its export
+      const originChain = new BN(CHAIN_ID_ZETACHAIN_TESTNET);
+      const contract = Buffer.from(new Uint8Array(20).fill(0xe4));
+      const policyPda = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("origin_policy"),
+          originChain.toArrayLike(Buffer, 'le', 8),
+          createHash("sha256").update(contract).digest(),
+        ],
+        program.programId
+      )[0];
+      const royaltyRecipient = Keypair.generate().publicKey;
+      await program.methods
+        .setOriginPolicy(originChain, contract, 250, royaltyRecipient, { program: {} }, false)
+        .accounts({ admin: wallet.publicKey, originPolicy: policyPda })
+        .rpc();
+      const policyPage: Buffer = await program.methods
+        .exportConfig(2, 0)
+        .remainingAccounts([{ pubkey: policyPda, isSigner: false, isWritable: false }])
+        .view();
+      await program.methods
+        .removeOriginPolicy(originChain, contract)
+        .accounts({ admin: wallet.publicKey, originPolicy: policyPda })
+        .rpc();
+      try {
+        const recreated = await importPage(policyPage, false, [policyPda]);
+        assert.equal(recreated.data.kind, 2);
+        assert.equal(recreated.data.imported, 1);
+        assert.equal(recreated.data.replaced, 0);
+        const policy = await program.account.originPolicy.fetch(policyPda);
+        assert.equal(policy.royaltyBps, 250);
+        assert.ok(policy.royaltyRecipient.equals(royaltyRecipient));
+        assert.isFalse(policy.isMutable);
+      } finally {
+        await program.methods
+          .removeOriginPolicy(originChain, contract)
+          .accounts({ admin: wallet.publicKey, originPolicy: policyPda })
+          .rpc();
+      }
+    });
+
+    it("Should pay an insured sender back when the gateway reports their transfer reverted", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping transfer insurance test");
+        return;
+      }
+      // Pricing insurance and standing in for the gateway are owner-only
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping transfer insurance test");
+        return;
+      }
+
+      const feeConfig = PublicKey.findProgramAddressSync([Buffer.from("fee_config")], program.programId)[0];
+      const insuranceFund = PublicKey.findProgramAddressSync([Buffer.from("insurance_fund")], program.programId)[0];
+      const insuranceOptIn = PublicKey.findProgramAddressSync(
+        [Buffer.from("insurance_opt_in"), admin.publicKey.toBuffer()],
+        program.programId
+      )[0];
+      const feeAccounts = { programState: programStatePda, feeConfig, admin: wallet.publicKey };
+      if (!(await program.account.feeConfig.fetchNullable(feeConfig))) {
+        await program.methods.setFeeConfig(new BN(0), Keypair.generate().publicKey).accounts(feeAccounts).rpc();
+      }
+      const premium = 1_000_000 + (Date.now() % 1000);
+      const payout = 50 * anchor.web3.LAMPORTS_PER_SOL;
+      await program.methods.setInsuranceConfig(new BN(premium), new BN(payout)).accounts(feeAccounts).rpc();
+
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const eventsOf = async (signature: string) => {
+        const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+        return Array.from(parser.parseLogs(tx.meta.logMessages));
+      };
+      const previousGateway = state.gateway;
+      const gateway = Keypair.generate();
+      try {
+        // Opting in pays one premium into the fund and covers one transfer
+        const fundBefore = await connection.getBalance(insuranceFund, "confirmed");
+        const optIn = () => program.methods
+          .optInTransferInsurance()
+          .accounts({ user: admin.publicKey })
+          .signers([admin])
+          .rpc({ commitment: "confirmed" });
+        await optIn();
+        // The first opt-in ever also funds the fund account's rent
+        const fundRent = fundBefore === 0 ? await connection.getMinimumBalanceForRentExemption(8 + 8 + 8 + 1) : 0;
+        assert.equal(await connection.getBalance(insuranceFund, "confirmed") - fundBefore, premium + fundRent);
+        const cover = await program.account.insuranceOptIn.fetch(insuranceOptIn, "confirmed");
+        assert.isTrue(cover.optedIn);
+        assert.equal(cover.payoutLamports.toNumber(), payout);
+        try {
+          await optIn();
+          assert.fail("A second opt-in should wait for the first cover to be used");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "InsuranceAlreadyActive");
+        }
+
+        // Bring an NFT in, then send it out under the cover
+        const tokenId = Date.now() + 281;
+        const uri = `https://example.com/insured/${tokenId}.json`;
+        const mint = bridgedMintPda(tokenId);
+        const nftOrigin = PublicKey.findProgramAddressSync(
+          [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+          program.programId
+        )[0];
+        const userTokenAccount = getAssociatedTokenAddressSync(mint, admin.publicKey, false);
+        await program.methods
+          .receiveCrossChainMessage(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender), null)
+          .accounts({
+            programState: programStatePda,
+            nftOrigin,
+            mint,
+            dedupWindow: dedupWindowPda(uri),
+            mintAuthority: mintAuthorityPda,
+            recipient: admin.publicKey,
+            sourceChainConfig: chainConfigPda(zetaChainId),
+            provisionalEscrow: null,
+            custodialTokenAccount: null,
+            inboundOffer: null,
+            pendingInbound: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            recipientTokenAccount: userTokenAccount,
+            payer: admin.publicKey,
+            gateway: gatewaySigner.publicKey,
+            hourlyMetrics: null,
+            inboundValueClaim: null,
+            inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender)),
+          })
+          .remainingAccounts(originPolicyAccounts)
+          .signers([admin, gatewaySigner])
+          .rpc({ commitment: "confirmed" });
+        const { transferNonce } = await program.account.programState.fetch(programStatePda, "confirmed");
+        const pendingTransfer = PublicKey.findProgramAddressSync(
+          [Buffer.from("pending_xfer"), transferNonce.toArrayLike(Buffer, 'le', 8)],
+          program.programId
+        )[0];
+        await program.methods
+          .transferCrossChain(new BN(tokenId), zetaChainId, Buffer.from(zetaChainRecipient), null, null, null)
+          .accountsPartial({
+            programState: programStatePda,
+            nftOrigin,
+            mint,
+            userTokenAccount,
+            user: admin.publicKey,
+            gatewayProgram: new PublicKey(zetaChainTestnetGateway),
+            feeConfig: feeConfigPda,
+            feeTreasury,
+            chainGasConfig: chainConfigPda(zetaChainId),
+            registry: null,
+            receiptConfig: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            zrc20GasVault: null,
+            zrc20VaultTokenAccount: null,
+            gasFeeDestination: null,
+            pendingTransfer,
+            hourlyMetrics: null,
+            insuranceOptIn,
+            coOwnership: null,
+            coOwnerConsent: null,
+          })
+          .signers([admin])
+          .rpc({ commitment: "confirmed" });
+        const insured = await program.account.pendingTransfer.fetch(pendingTransfer, "confirmed");
+        assert.isTrue(insured.insured);
+        assert.equal(insured.insuredPayoutLamports.toNumber(), payout);
+        assert.isFalse((await program.account.insuranceOptIn.fetch(insuranceOptIn, "confirmed")).optedIn);
+
+        // Only the gateway reports reverts
+        const report = (signer: Keypair) => program.methods
+          .onTransferReverted(transferNonce)
+          .accountsPartial({ programState: programStatePda, pendingTransfer, insuranceFund, sender: admin.publicKey, gateway: signer.publicKey })
+          .signers([signer])
+          .rpc({ commitment: "confirmed" });
+        try {
+          await report(admin);
+          assert.fail("A revert reported outside the gateway should be rejected");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "Unauthorized");
+        }
+
+        // The payout is far more than the fund holds, so it pays what it can and reports the rest
+        await program.methods.setGateway(gateway.publicKey).accounts({ programState: programStatePda, admin: wallet.publicKey }).rpc();
+        const fundInfo = await connection.getAccountInfo(insuranceFund, "confirmed");
+        const available = fundInfo.lamports - await connection.getMinimumBalanceForRentExemption(fundInfo.data.length);
+        const senderBefore = await connection.getBalance(admin.publicKey, "confirmed");
+        const reverted = (await eventsOf(await report(gateway))).find((event) => event.name === "transferReverted");
+        assert.isTrue(reverted.data.insured);
+        assert.equal(reverted.data.payoutLamports.toNumber(), available);
+        assert.equal(reverted.data.shortfallLamports.toNumber(), payout - available);
+        assert.equal(await connection.getBalance(admin.publicKey, "confirmed") - senderBefore, available);
+        try {
+          await report(gateway);
+          assert.fail("A transfer can only be reported reverted once");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "TransferNotPending");
+        }
+
+        // The payout covers the failed transfer, not the NFT, which the sender still takes back
+        await program.methods
+          .cancelPendingTransfer(transferNonce)
+          .accountsPartial({
+            pendingTransfer,
+            nftOrigin,
+            mint,
+            mintAuthority: mintAuthorityPda,
+            senderTokenAccount: userTokenAccount,
+            sender: admin.publicKey,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            chainStats: PublicKey.findProgramAddressSync(
+              [Buffer.from("chain_stats"), zetaChainId.toArrayLike(Buffer, 'le', 8)],
+              program.programId
+            )[0],
+            nativeMintAuthority: null,
+          })
+          .signers([admin])
+          .rpc({ commitment: "confirmed" });
+        assert.equal((await getAccount(connection, userTokenAccount, "confirmed")).amount, BigInt(1));
+        assert.isNull(await connection.getAccountInfo(pendingTransfer, "confirmed"));
+      } finally {
+        const current = await program.account.programState.fetch(programStatePda, "confirmed");
+        if (!current.gateway.equals(previousGateway)) {
+          await program.methods.setGateway(previousGateway).accounts({ programState: programStatePda, admin: wallet.publicKey }).rpc();
+        }
+        await program.methods.setInsuranceConfig(new BN(0), new BN(0)).accounts(feeAccounts).rpc();
+      }
+    });
+
+    it("Should report a burn the token program would refuse as BurnFailed", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping CPI error mapping test");
+        return;
+      }
+
+      // A native NFT keeps its creator as freeze authority, so the test can freeze it
+      const tokenId = NATIVE_ID_BASE.addn(Date.now() % 1000000 + 3000);
+      const nftOrigin = PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), tokenId.toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      const newMint = Keypair.generate();
+      await program.methods
+        .createMintAndNft("https://example.com/frozen.json", 0, tokenId, null, null, null)
+        .accounts({
+          nftOrigin,
+          mint: newMint.publicKey,
+          mintAuthority: mintAuthority.publicKey,
+          payer: admin.publicKey,
+          feeConfig: feeConfigPda,
+          feeTreasury: null,
+          sellerFeeConfig: null,
+          feeCreator: null,
+          mintOnly: null,
+          lazyRevealConfig: null,
+          hourlyMetrics: null,
+        })
+        .signers([admin, mintAuthority, newMint])
+        .rpc({ commitment: "confirmed" });
+      const userTokenAccount = getAssociatedTokenAddressSync(newMint.publicKey, mintAuthority.publicKey, false);
+      await freezeAccount(connection, admin, userTokenAccount, newMint.publicKey, mintAuthority);
+      if ((await connection.getBalance(mintAuthority.publicKey)) < LAMPORTS_PER_SOL / 10) {
+        await connection.confirmTransaction(await connection.requestAirdrop(mintAuthority.publicKey, LAMPORTS_PER_SOL));
+      }
+
+      const { transferNonce } = await program.account.programState.fetch(programStatePda);
+      try {
+        await program.methods
+          .transferCrossChain(tokenId, zetaChainId, Buffer.from(zetaChainRecipient), null, null, null)
+          .accountsPartial({
+            programState: programStatePda,
+            nftOrigin,
+            mint: newMint.publicKey,
+            userTokenAccount,
+            user: mintAuthority.publicKey,
+            gatewayProgram: new PublicKey(zetaChainTestnetGateway),
+            feeConfig: feeConfigPda,
+            feeTreasury,
+            chainGasConfig: chainConfigPda(zetaChainId),
+            registry: null,
+            receiptConfig: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            zrc20GasVault: null,
+            zrc20VaultTokenAccount: null,
+            gasFeeDestination: null,
+            pendingTransfer: PublicKey.findProgramAddressSync(
+              [Buffer.from("pending_xfer"), transferNonce.toArrayLike(Buffer, 'le', 8)],
+              program.programId
+            )[0],
+            hourlyMetrics: null,
+            insuranceOptIn: null,
+            coOwnership: null,
+            coOwnerConsent: null,
+          })
+          .signers([mintAuthority])
+          .rpc();
+        assert.fail("Burning from a frozen account should fail");
+      } catch (error) {
+        // Our error, not the token program's "custom program error: 0x11"
+        // @ts-ignore
+        assert.include(error.message, "BurnFailed");
+        // @ts-ignore
+        assert.notInclude(error.message, "0x11");
+      }
+    });
+
+    it("Should refuse to batch close metrics buckets that are still retained", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping batch close test");
+        return;
+      }
+      // Batch closing is owner-only
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping batch close test");
+        return;
+      }
+
+      const now = await connection.getBlockTime(await connection.getSlot());
+      const hourBucket = new BN(Math.floor(now / 3600));
+      const hourlyMetrics = PublicKey.findProgramAddressSync(
+        [Buffer.from("hourly_metrics"), hourBucket.toArrayLike(Buffer, 'le', 8)],
+        program.programId
+      )[0];
+      if (!(await connection.getAccountInfo(hourlyMetrics))) {
+        await program.methods.openHourlyMetrics(hourBucket).accounts({ payer: wallet.publicKey }).rpc();
+      }
+      // Remaining accounts pair each bucket with whoever opened it, who gets its rent back
+      const { payer } = await program.account.hourlyMetrics.fetch(hourlyMetrics);
+      const stranger = Keypair.generate().publicKey;
+      const batchClose = (addresses: PublicKey[], remaining: PublicKey[], signer?: Keypair) => program.methods
+        .batchCloseStaleAccounts({ hourlyMetrics: {} }, addresses)
+        .accounts({ programState: programStatePda, admin: (signer ?? wallet.payer).publicKey })
+        .remainingAccounts(remaining.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })))
+        .signers(signer ? [signer] : [])
+        .rpc();
+
+      // The current hour is years from stale; nothing is closed and no rent moves
+      for (const [addresses, remaining, signer, error] of [
+        [[hourlyMetrics], [hourlyMetrics, payer], admin, "Unauthorized"],
+        [[hourlyMetrics], [hourlyMetrics, payer], undefined, "MetricsNotStale"],
+        [[hourlyMetrics], [hourlyMetrics, stranger], undefined, "RentPayerMismatch"],
+        [[], [], undefined, "InvalidBatchClose"],
+        [[hourlyMetrics], [hourlyMetrics], undefined, "InvalidBatchClose"],
+        [[hourlyMetrics], [programStatePda, payer], undefined, "InvalidBatchClose"],
+        [[programStatePda], [programStatePda, payer], undefined, "AccountDiscriminatorMismatch"],
+      ] as [PublicKey[], PublicKey[], Keypair | undefined, string][]) {
+        try {
+          await batchClose(addresses, remaining, signer);
+          assert.fail(`Expected ${error}`);
+        } catch (e) {
+          // @ts-ignore
+          assert.include(e.message, error);
+        }
+      }
+      assert.isNotNull(await connection.getAccountInfo(hourlyMetrics));
+      assert.equal(await connection.getBalance(stranger), 0);
+    });
+
+    it("Should keep an NFT's origin-chain token id and contract apart from its universal id", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping provenance test");
+        return;
+      }
+      const state = await program.account.programState.fetch(programStatePda);
+      const nftOriginPda = (tokenId: number) => PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const transactionOf = (signature: string) =>
+        connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+      // Provenance trailer: tag 6, origin chain and origin token id (8 bytes each, big-endian),
+      // one length byte, the contract
+      const provenanceTrailer = (originChain: number, originTokenId: number, contract: Buffer) => Buffer.concat([
+        Buffer.from([6]),
+        new BN(originChain).toArrayLike(Buffer, 'be', 8),
+        new BN(originTokenId).toArrayLike(Buffer, 'be', 8),
+        Buffer.from([contract.length]),
+        contract,
+      ]);
+      const receive = async (tokenId: number, uri: string, trailer: Buffer | null) => {
+        const mint = bridgedMintPda(tokenId);
+        const message = createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender);
+        const signature = await program.methods
+          .receiveCrossChainMessage(trailer ? Buffer.concat([message, trailer]) : message, null)
+          .accounts({
+            programState: programStatePda,
+            nftOrigin: nftOriginPda(tokenId),
+            mint,
+            dedupWindow: dedupWindowPda(uri),
+            mintAuthority: mintAuthorityPda,
+            recipient: admin.publicKey,
+            sourceChainConfig: chainConfigPda(zetaChainId),
+            provisionalEscrow: null,
+            custodialTokenAccount: null,
+            inboundOffer: null,
+            pendingInbound: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            recipientTokenAccount: getAssociatedTokenAddressSync(mint, admin.publicKey, false),
+            payer: admin.publicKey,
+            gateway: gatewaySigner.publicKey,
+            hourlyMetrics: null,
+            inboundValueClaim: null,
+            inboundReceipt: inboundReceiptPda(trailer ? Buffer.concat([message, trailer]) : message),
+          })
+          .remainingAccounts(originPolicyAccounts)
+          .signers([admin, gatewaySigner])
+          .rpc({ commitment: "confirmed" });
+        const tx = await transactionOf(signature);
+        return Array.from(parser.parseLogs(tx.meta.logMessages)).find((event) => event.name === "crossChainMessageReceived");
+      };
+      const transfer = async (tokenId: number) => {
+        const mint = bridgedMintPda(tokenId);
+        const { transferNonce } = await program.account.programState.fetch(programStatePda, "confirmed");
+        const signature = await program.methods
+          .transferCrossChain(new BN(tokenId), zetaChainId, Buffer.from(zetaChainRecipient), null, null, null)
+          .accountsPartial({
+            programState: programStatePda,
+            nftOrigin: nftOriginPda(tokenId),
+            mint,
+            userTokenAccount: getAssociatedTokenAddressSync(mint, admin.publicKey, false),
+            user: admin.publicKey,
+            gatewayProgram: new PublicKey(zetaChainTestnetGateway),
+            feeConfig: feeConfigPda,
+            feeTreasury,
+            chainGasConfig: chainConfigPda(zetaChainId),
+            registry: null,
+            receiptConfig: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            zrc20GasVault: null,
+            zrc20VaultTokenAccount: null,
+            gasFeeDestination: null,
+            pendingTransfer: PublicKey.findProgramAddressSync(
+              [Buffer.from("pending_xfer"), transferNonce.toArrayLike(Buffer, 'le', 8)],
+              program.programId
+            )[0],
+            hourlyMetrics: null,
+            insuranceOptIn: null,
+            coOwnership: null,
+            coOwnerConsent: null,
+          })
+          .signers([admin])
+          .rpc({ commitment: "confirmed" });
+        // The outbound message is the tail of the gateway call's instruction data
+        const tx = await transactionOf(signature);
+        return tx.meta.innerInstructions
+          .flatMap((inner) => inner.instructions)
+          .map((instruction) => Buffer.from(anchor.utils.bytes.bs58.decode(instruction.data)));
+      };
+
+      // Minted on Sepolia as token 42 and given another id by ZetaChain in transit
+      const tokenId = Date.now() + 287;
+      const originTokenId = 42;
+      const sepolia = 11155111;
+      const contract = Buffer.alloc(20, 0xc7);
+      const trailer = provenanceTrailer(sepolia, originTokenId, contract);
+      const uri = `https://example.com/provenance/${tokenId}.json`;
+
+      const arrived = await receive(tokenId, uri, trailer);
+      assert.equal(arrived.data.tokenId.toNumber(), tokenId);
+      assert.equal(arrived.data.originTokenId.toNumber(), originTokenId);
+      assert.equal(arrived.data.provenanceChain.toNumber(), sepolia);
+      assert.equal(arrived.data.originContractLen, 20);
+      assert.deepEqual(Buffer.from(arrived.data.originContract).subarray(0, 20), contract);
+      let origin = await program.account.nftOrigin.fetch(nftOriginPda(tokenId), "confirmed");
+      assert.equal(origin.tokenId.toNumber(), tokenId);
+      assert.equal(origin.originTokenId.toNumber(), originTokenId);
+
+      // Outbound, the same provenance travels with the NFT
+      const instructions = await transfer(tokenId);
+      assert.isTrue(instructions.some((data) => data.subarray(data.length - trailer.length).equals(trailer)));
+
+      // Back again with the provenance ZetaChain returned, both ids are still apart
+      const returned = await receive(tokenId, uri, trailer);
+      assert.equal(returned.data.originTokenId.toNumber(), originTokenId);
+      origin = await program.account.nftOrigin.fetch(nftOriginPda(tokenId), "confirmed");
+      assert.equal(origin.tokenId.toNumber(), tokenId);
+      assert.equal(origin.originTokenId.toNumber(), originTokenId);
+
+      // A contract over 32 bytes is kept as its sha256, with its full length
+      const longContract = Buffer.alloc(40, 0x5a);
+      const hashedTokenId = tokenId + 1;
+      const hashed = await receive(
+        hashedTokenId,
+        `https://example.com/provenance/${hashedTokenId}.json`,
+        provenanceTrailer(sepolia, 7, longContract),
+      );
+      assert.equal(hashed.data.originContractLen, 40);
+      assert.deepEqual(Buffer.from(hashed.data.originContract), createHash("sha256").update(longContract).digest());
+
+      // Without a provenance trailer the universal contract and the arrival id stand in
+      const plainTokenId = tokenId + 2;
+      const plain = await receive(plainTokenId, `https://example.com/provenance/${plainTokenId}.json`, null);
+      assert.equal(plain.data.originTokenId.toNumber(), plainTokenId);
+      assert.equal(plain.data.originContractLen, 20);
+      assert.deepEqual(Buffer.from(plain.data.originContract).subarray(0, 20), Buffer.from(state.universalNftContract));
+    });
+
+    it("Should record appraisals from approved appraisers and read back the freshest", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping appraisal test");
+        return;
+      }
+      // Approving appraisers is owner-only
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping appraisal test");
+        return;
+      }
+
+      // Bring an NFT in to appraise
+      const tokenId = Date.now() + 293;
+      const uri = `https://example.com/appraised/${tokenId}.json`;
+      const mint = bridgedMintPda(tokenId);
+      const nftOrigin = PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      await program.methods
+        .receiveCrossChainMessage(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender), null)
+        .accounts({
+          programState: programStatePda,
+          nftOrigin,
+          mint,
+          dedupWindow: dedupWindowPda(uri),
+          mintAuthority: mintAuthorityPda,
+          recipient: admin.publicKey,
+          sourceChainConfig: chainConfigPda(zetaChainId),
+          provisionalEscrow: null,
+          custodialTokenAccount: null,
+          inboundOffer: null,
+          pendingInbound: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          recipientTokenAccount: getAssociatedTokenAddressSync(mint, admin.publicKey, false),
+          payer: admin.publicKey,
+          gateway: gatewaySigner.publicKey,
+          hourlyMetrics: null,
+          inboundValueClaim: null,
+          inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender)),
+        })
+        .remainingAccounts(originPolicyAccounts)
+        .signers([admin, gatewaySigner])
+        .rpc({ commitment: "confirmed" });
+
+      const appraisers = [Keypair.generate(), Keypair.generate()];
+      for (const appraiser of appraisers) {
+        await connection.confirmTransaction(await connection.requestAirdrop(appraiser.publicKey, LAMPORTS_PER_SOL));
+      }
+      const appraisalPda = (appraiser: Keypair) => PublicKey.findProgramAddressSync(
+        [Buffer.from("appraisal"), mint.toBuffer(), appraiser.publicKey.toBuffer()],
+        program.programId
+      )[0];
+      const setAppraiser = (appraiser: Keypair, active: boolean) => program.methods
+        .setAppraisalAuthority(appraiser.publicKey, active)
+        .accounts({ programState: programStatePda, admin: wallet.publicKey })
+        .rpc({ commitment: "confirmed" });
+      const submit = (appraiser: Keypair, lamports: number, usdCents: number, methodology: string) => program.methods
+        .submitAppraisal(new BN(tokenId), new BN(lamports), new BN(usdCents), methodology)
+        .accountsPartial({
+          nftOrigin,
+          appraisalAuthority: authorityPda(appraiser),
+          appraisal: appraisalPda(appraiser),
+          appraiser: appraiser.publicKey,
+        })
+        .signers([appraiser])
+        .rpc({ commitment: "confirmed" });
+      const authorityPda = (appraiser: Keypair) => PublicKey.findProgramAddressSync(
+        [Buffer.from("appraisal_authority"), appraiser.publicKey.toBuffer()],
+        program.programId
+      )[0];
+      // Each appraisal is read with its appraiser's authority, so revoked appraisers don't count
+      const freshest = (authorities = appraisers.map(authorityPda)) => program.methods
+        .getAppraisal(new BN(tokenId))
+        .accounts({ nftOrigin })
+        .remainingAccounts(appraisers.flatMap((appraiser, i) => [
+          { pubkey: appraisalPda(appraiser), isSigner: false, isWritable: false },
+          { pubkey: authorities[i], isSigner: false, isWritable: false },
+        ]))
+        .view();
+
+      // Only approved appraisers can submit
+      try {
+        await submit(appraisers[0], LAMPORTS_PER_SOL, 15_000, "floor");
+        assert.fail("An unapproved appraiser should be refused");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "AccountNotInitialized");
+      }
+      for (const appraiser of appraisers) {
+        await setAppraiser(appraiser, true);
+      }
+
+      for (const [lamports, usdCents, methodology] of [
+        [0, 0, "floor"],
+        [LAMPORTS_PER_SOL, 0, "m".repeat(65)],
+      ] as [number, number, string][]) {
+        try {
+          await submit(appraisers[0], lamports, usdCents, methodology);
+          assert.fail("The appraisal should be rejected");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "InvalidAppraisal");
+        }
+      }
+
+      await submit(appraisers[0], 2 * LAMPORTS_PER_SOL, 30_000, "collection floor");
+      const first = await program.account.nftAppraisal.fetch(appraisalPda(appraisers[0]), "confirmed");
+      assert.equal(first.tokenId.toNumber(), tokenId);
+      assert.isTrue(first.mint.equals(mint));
+      assert.equal(first.valueLamports.toNumber(), 2 * LAMPORTS_PER_SOL);
+      assert.equal(first.valueUsdCents.toNumber(), 30_000);
+      assert.equal(first.expiresAt.toNumber() - first.appraisedAt.toNumber(), 30 * 24 * 60 * 60);
+
+      // A later appraisal from another appraiser is the one read back
+      await new Promise((resolve) => setTimeout(resolve, 1500));
+      await submit(appraisers[1], 3 * LAMPORTS_PER_SOL, 45_000, "comparable sales");
+      assert.equal((await freshest()).valueLamports.toNumber(), 3 * LAMPORTS_PER_SOL);
+
+      // A revoked appraiser can't submit again
+      await setAppraiser(appraisers[1], false);
+      try {
+        await submit(appraisers[1], LAMPORTS_PER_SOL, 15_000, "floor");
+        assert.fail("A revoked appraiser should be refused");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "AppraiserNotAuthorized");
+      }
+
+      // Their appraisal stops counting, leaving the earlier one from the active appraiser
+      assert.equal((await freshest()).valueLamports.toNumber(), 2 * LAMPORTS_PER_SOL);
+      await setAppraiser(appraisers[0], false);
+      assert.isNull(await freshest());
+      await setAppraiser(appraisers[0], true);
+
+      // An appraisal can't be vouched for by another appraiser's authority
+      try {
+        await freshest([authorityPda(appraisers[0]), authorityPda(appraisers[0])]);
+        assert.fail("A mismatched appraisal authority should be rejected");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "InvalidAppraisal");
+      }
+    });
+
+    it("Should pass value sent with an inbound NFT to its recipient, or park it when they can't hold it", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping inbound value test");
+        return;
+      }
+
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const valueClaimPda = (recipient: PublicKey) => PublicKey.findProgramAddressSync(
+        [Buffer.from("inbound_value_claim"), recipient.toBuffer()],
+        program.programId
+      )[0];
+      // The message's value trailer names the amount; a zero amount leaves the trailer out
+      const receive = async (tokenId: number, recipient: PublicKey, amount: number, inboundValueClaim: PublicKey | null) => {
+        const uri = `https://example.com/valued/${tokenId}.json`;
+        const mint = bridgedMintPda(tokenId);
+        const message = createZetaChainSuccessMessage(tokenId, uri, recipient.toBytes(), solanaSender);
+        const signature = await program.methods
+          .receiveCrossChainMessage(amount > 0 ? withValue(message, amount) : message, null)
+          .accounts({
+            programState: programStatePda,
+            nftOrigin: PublicKey.findProgramAddressSync(
+              [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+              program.programId
+            )[0],
+            mint,
+            dedupWindow: dedupWindowPda(uri),
+            mintAuthority: mintAuthorityPda,
+            recipient,
+            sourceChainConfig: chainConfigPda(zetaChainId),
+            provisionalEscrow: null,
+            custodialTokenAccount: null,
+            inboundOffer: null,
+            pendingInbound: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            recipientTokenAccount: getAssociatedTokenAddressSync(mint, recipient, true),
+            payer: wallet.publicKey,
+            gateway: gatewaySigner.publicKey,
+            hourlyMetrics: null,
+            inboundValueClaim,
+            inboundReceipt: inboundReceiptPda(amount > 0 ? withValue(message, amount) : message),
+          })
+          .remainingAccounts(originPolicyAccounts)
+          .signers([gatewaySigner])
+          .rpc({ commitment: "confirmed" });
+        const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+        return Array.from(parser.parseLogs(tx.meta.logMessages)).find((event) => event.name === "crossChainMessageReceived");
+      };
+
+      // A funded recipient gets zero, small and large amounts as they are
+      const recipient = Keypair.generate();
+      await connection.confirmTransaction(await connection.requestAirdrop(recipient.publicKey, LAMPORTS_PER_SOL));
+      const tokenId = Date.now() + 307;
+      for (const [offset, amount] of [[0, 0], [1, 1_000], [2, 10 * LAMPORTS_PER_SOL]]) {
+        const before = await connection.getBalance(recipient.publicKey, "confirmed");
+        const received = await receive(tokenId + offset, recipient.publicKey, amount, null);
+        assert.equal(await connection.getBalance(recipient.publicKey, "confirmed") - before, amount);
+        assert.equal(received.data.valueLamports.toNumber(), amount);
+        assert.isFalse(received.data.valueParked);
+      }
+
+      // A zero value trailer isn't a value; messages leave it out instead
+      try {
+        const zeroValueId = tokenId + 5;
+        const uri = `https://example.com/valued/${zeroValueId}.json`;
+        const mint = bridgedMintPda(zeroValueId);
+        await program.methods
+          .receiveCrossChainMessage(withValue(createZetaChainSuccessMessage(zeroValueId, uri, recipient.publicKey.toBytes(), solanaSender), 0), null)
+          .accounts({
+            programState: programStatePda,
+            nftOrigin: PublicKey.findProgramAddressSync(
+              [Buffer.from("nft_origin"), new BN(zeroValueId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+              program.programId
+            )[0],
+            mint,
+            dedupWindow: dedupWindowPda(uri),
+            mintAuthority: mintAuthorityPda,
+            recipient: recipient.publicKey,
+            sourceChainConfig: chainConfigPda(zetaChainId),
+            provisionalEscrow: null,
+            custodialTokenAccount: null,
+            inboundOffer: null,
+            pendingInbound: null,
+            receiptMint: null,
+            receiptTokenAccount: null,
+            recipientTokenAccount: getAssociatedTokenAddressSync(mint, recipient.publicKey, true),
+            payer: wallet.publicKey,
+            gateway: gatewaySigner.publicKey,
+            hourlyMetrics: null,
+            inboundValueClaim: null,
+            inboundReceipt: inboundReceiptPda(withValue(createZetaChainSuccessMessage(zeroValueId, uri, recipient.publicKey.toBytes(), solanaSender), 0)),
+          })
+          .remainingAccounts(originPolicyAccounts)
+          .signers([gatewaySigner])
+          .rpc();
+        assert.fail("A zero value trailer should be refused");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "InvalidMessageFormat");
+      }
+
+      // An empty account can't take less than rent exemption, so that is parked instead
+      const unfunded = Keypair.generate();
+      try {
+        await receive(tokenId + 3, unfunded.publicKey, 1_000, null);
+        assert.fail("Value the recipient can't hold needs the claim account");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "InboundValueClaimMismatch");
+      }
+      const claim = valueClaimPda(unfunded.publicKey);
+      const parked = await receive(tokenId + 3, unfunded.publicKey, 1_000, claim);
+      assert.isTrue(parked.data.valueParked);
+      assert.equal(await connection.getBalance(unfunded.publicKey, "confirmed"), 0);
+      assert.equal((await program.account.inboundValueClaim.fetch(claim, "confirmed")).lamports.toNumber(), 1_000);
+
+      // Enough to make it rent-exempt goes straight to the same recipient
+      const direct = await receive(tokenId + 4, unfunded.publicKey, LAMPORTS_PER_SOL, claim);
+      assert.isFalse(direct.data.valueParked);
+      assert.equal(await connection.getBalance(unfunded.publicKey, "confirmed"), LAMPORTS_PER_SOL);
+
+      // The recipient claims the parked value, and the claim's rent, to any account
+      const destination = Keypair.generate().publicKey;
+      const claimBalance = await connection.getBalance(claim, "confirmed");
+      await program.methods
+        .claimInboundValue()
+        .accounts({ recipient: unfunded.publicKey, destination })
+        .signers([unfunded])
+        .rpc({ commitment: "confirmed" });
+      assert.equal(await connection.getBalance(destination, "confirmed"), claimBalance);
+      assert.isNull(await connection.getAccountInfo(claim, "confirmed"));
+    });
+
+    it("Should store the metadata and edition bumps at mint and check retry_metadata against them", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping metadata bump test");
+        return;
+      }
+      const state = await program.account.programState.fetch(programStatePda);
+      if (state.lazyOriginsEnabled) {
+        console.log("Lazy origins are enabled, skipping metadata bump test");
+        return;
+      }
+
+      const tokenMetadataProgram = new PublicKey("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+      const tokenId = NATIVE_ID_BASE.addn(Date.now() % 1000000 + 1189);
+      const nftOrigin = PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), tokenId.toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      const newMint = Keypair.generate();
+      const signature = await program.methods
+        .createMintAndNft("https://example.com/bumps.json", 0, tokenId, null, null, null)
+        .accounts({
+          nftOrigin,
+          mint: newMint.publicKey,
+          mintAuthority: mintAuthority.publicKey,
+          payer: admin.publicKey,
+          feeConfig: feeConfigPda,
+          feeTreasury: null,
+          sellerFeeConfig: null,
+          feeCreator: null,
+          mintOnly: null,
+          lazyRevealConfig: null,
+          hourlyMetrics: null,
+        })
+        .signers([admin, mintAuthority, newMint])
+        .rpc({ commitment: "confirmed" });
+
+      const [metadata, metadataBump] = PublicKey.findProgramAddressSync(
+        [Buffer.from("metadata"), tokenMetadataProgram.toBuffer(), newMint.publicKey.toBuffer()],
+        tokenMetadataProgram
+      );
+      const [masterEdition, editionBump] = PublicKey.findProgramAddressSync(
+        [Buffer.from("metadata"), tokenMetadataProgram.toBuffer(), newMint.publicKey.toBuffer(), Buffer.from("edition")],
+        tokenMetadataProgram
+      );
+      const origin = await program.account.nftOrigin.fetch(nftOrigin);
+      assert.equal(origin.metadataBump, metadataBump);
+      assert.equal(origin.editionBump, editionBump);
+
+      const retry = (metadataAccount: PublicKey) =>
+        program.methods
+          .retryMetadata(tokenId)
+          .accountsPartial({
+            nftOrigin,
+            mint: newMint.publicKey,
+            tokenMetadataProgram,
+            metadata: metadataAccount,
+            payer: user.publicKey,
+          })
+          .remainingAccounts(originPolicyAccounts)
+          .signers([user])
+          .rpc({ commitment: "confirmed" });
+
+      // The stored bump passes the seeds check, so the handler runs and finds nothing pending
+      try {
+        await retry(metadata);
+        assert.fail("retry_metadata should fail when no metadata is pending");
+      } catch (error) {
+        assert.include(error.message, "MetadataNotPending");
+      }
+      // Any other account fails the seeds check before the handler runs
+      try {
+        await retry(masterEdition);
+        assert.fail("retry_metadata should reject an account other than the metadata PDA");
+      } catch (error) {
+        assert.include(error.message, "ConstraintSeeds");
+      }
+
+      const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+      console.log(`createMintAndNft: ${tx.meta.computeUnitsConsumed} CU; later metadata checks use the stored bumps`);
+    });
+
+    it("Should report NFTOrigins that fail the post-migration integrity check", async () => {
+      if (!isProgramInitialized || testTokenId === undefined) {
+        console.log("Program not initialized or no NFT minted, skipping integrity check test");
+        return;
+      }
+      // The integrity check is owner-only
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping integrity check test");
+        return;
+      }
+
+      const nftOriginFor = (tokenId: BN) =>
+        PublicKey.findProgramAddressSync(
+          [Buffer.from("nft_origin"), tokenId.toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+          program.programId
+        )[0];
+      const reportPda = PublicKey.findProgramAddressSync([Buffer.from("migration_integrity")], program.programId)[0];
+      const verify = (tokenIds: BN[], remaining: PublicKey[], signer?: Keypair) => program.methods
+        .verifyMigrationIntegrity(tokenIds)
+        .accounts({ programState: programStatePda, admin: (signer ?? wallet.payer).publicKey })
+        .remainingAccounts(remaining.map((pubkey) => ({ pubkey, isSigner: false, isWritable: false })))
+        .signers(signer ? [signer] : [])
+        .rpc({ commitment: "confirmed" });
+
+      const minted = new BN(testTokenId);
+      const neverMinted = NATIVE_ID_BASE.addn(999_999_000 + (Date.now() % 1000));
+      for (const [tokenIds, remaining, signer, error] of [
+        [[minted], [nftOriginFor(minted)], admin, "Unauthorized"],
+        [[], [], undefined, "InvalidIntegrityBatch"],
+        [[minted], [nftOriginFor(neverMinted)], undefined, "InvalidIntegrityBatch"],
+        [[minted, neverMinted], [nftOriginFor(minted)], undefined, "InvalidIntegrityBatch"],
+      ] as [BN[], PublicKey[], Keypair | undefined, string][]) {
+        try {
+          await verify(tokenIds, remaining, signer);
+          assert.fail(`Expected ${error}`);
+        } catch (e) {
+          // @ts-ignore
+          assert.include(e.message, error);
+        }
+      }
+
+      // The minted token's origin is current; the other token has none
+      const signature = await verify([minted, neverMinted], [nftOriginFor(minted), nftOriginFor(neverMinted)]);
+      const report = await program.account.migrationIntegrityReport.fetch(reportPda);
+      assert.equal(report.totalChecked, 2);
+      const nftOriginVersion = program.idl.constants.find((c) => c.name.replace(/_/g, "").toLowerCase() === "nftoriginversion");
+      assert.equal(report.nftOriginVersion, Number(nftOriginVersion.value));
+      assert.equal(report.failures.length, 1);
+      assert.equal(report.failures[0].tokenId.toString(), neverMinted.toString());
+      assert.deepEqual(report.failures[0].reason, { missing: {} });
+
+      const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const checked = Array.from(parser.parseLogs(tx.meta.logMessages)).find((event) => event.name === "migrationIntegrityChecked");
+      assert.ok(checked);
+      assert.equal(checked.data.totalChecked, 2);
+      assert.equal(checked.data.failures, 1);
+    });
+
+    it("Should attest a holder's ownership, expire the attestation and relay it to the origin chain", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping ownership attestation test");
+        return;
+      }
+
+      // A bridged token, delivered to admin, whose provenance names ZetaChain
+      const tokenId = Date.now() + 1190;
+      const uri = `https://example.com/attested/${tokenId}.json`;
+      const nftOrigin = PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      const holderTokenAccount = getAssociatedTokenAddressSync(bridgedMintPda(tokenId), admin.publicKey, false);
+      await program.methods
+        .receiveCrossChainMessage(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender), null)
+        .accounts({
+          programState: programStatePda,
+          nftOrigin,
+          mint: bridgedMintPda(tokenId),
+          dedupWindow: dedupWindowPda(uri),
+          mintAuthority: mintAuthorityPda,
+          recipient: admin.publicKey,
+          sourceChainConfig: chainConfigPda(zetaChainId),
+          provisionalEscrow: null,
+          custodialTokenAccount: null,
+          inboundOffer: null,
+          pendingInbound: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          recipientTokenAccount: holderTokenAccount,
+          payer: admin.publicKey,
+          gateway: gatewaySigner.publicKey,
+          hourlyMetrics: null,
+          inboundValueClaim: null,
+          inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender)),
+        })
+        .remainingAccounts(originPolicyAccounts)
+        .signers([admin, gatewaySigner])
+        .rpc({ commitment: "confirmed" });
+
+      const attestationPda = PublicKey.findProgramAddressSync(
+        [Buffer.from("ownership_attestation"), new BN(tokenId).toArrayLike(Buffer, 'le', 8)],
+        program.programId
+      )[0];
+      const attest = (validSeconds: number, relay: boolean, gatewayProgram: PublicKey | null) => program.methods
+        .attestOwnership(new BN(tokenId), validSeconds, relay)
+        .accounts({
+          programState: programStatePda,
+          nftOrigin,
+          holderTokenAccount,
+          holder: admin.publicKey,
+          registry: null,
+          gatewayProgram,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+      const view = () => program.methods
+        .getAttestation(new BN(tokenId))
+        .accounts({ ownershipAttestation: attestationPda })
+        .view();
+      const eventsOf = async (signature: string) => {
+        const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+        const parser = new anchor.EventParser(program.programId, program.coder);
+        return Array.from(parser.parseLogs(tx.meta.logMessages));
+      };
+
+      for (const [validSeconds, relay, gatewayProgram, error] of [
+        [0, false, null, "InvalidAttestationValidity"],
+        [7 * 24 * 60 * 60 + 1, false, null, "InvalidAttestationValidity"],
+        [60, true, null, "AttestationNotRelayable"],
+      ] as [number, boolean, PublicKey | null, string][]) {
+        try {
+          await attest(validSeconds, relay, gatewayProgram);
+          assert.fail(`Expected ${error}`);
+        } catch (e) {
+          // @ts-ignore
+          assert.include(e.message, error);
+        }
+      }
+
+      // Local attestation: stored, returned by the view, nothing relayed
+      const local = await attest(60, false, null);
+      const stored = await program.account.ownershipAttestation.fetch(attestationPda);
+      assert.equal(stored.holder.toString(), admin.publicKey.toString());
+      assert.equal(stored.mint.toString(), bridgedMintPda(tokenId).toString());
+      assert.equal(stored.expiresAt.sub(stored.attestedAt).toNumber(), 60);
+      const viewed = await view();
+      assert.ok(viewed);
+      assert.equal(viewed.slot.toString(), stored.slot.toString());
+      const localEvents = await eventsOf(local);
+      const attested = localEvents.find((event) => event.name === "ownershipAttested");
+      assert.ok(attested);
+      assert.isNull(attested.data.relayedToChain);
+      assert.isUndefined(localEvents.find((event) => event.name === "ownershipAttestationRelayed"));
+
+      // A short attestation replaces it and stops counting once it expires
+      await attest(1, false, null);
+      await new Promise((resolve) => setTimeout(resolve, 3_000));
+      assert.isNull(await view());
+
+      // Relayed: ZetaChain is addressed with a zero destination word, then the version byte
+      // and six ABI words
+      const relayed = await attest(60, true, new PublicKey(zetaChainTestnetGateway));
+      const current = await program.account.ownershipAttestation.fetch(attestationPda);
+      const relayedEvent = (await eventsOf(relayed)).find((event) => event.name === "ownershipAttestationRelayed");
+      assert.ok(relayedEvent);
+      assert.equal(relayedEvent.data.originChain.toNumber(), CHAIN_ID_ZETACHAIN_TESTNET);
+      const message = Buffer.from(relayedEvent.data.message);
+      const word = (index: number) => message.subarray(33 + index * 32, 33 + (index + 1) * 32);
+      const uint = (index: number) => new BN(word(index), "be");
+      assert.equal(message.length, 32 + 1 + 6 * 32);
+      assert.isTrue(message.subarray(0, 32).equals(Buffer.alloc(32)));
+      assert.equal(message[32], 0x03);
+      assert.equal(uint(0).toNumber(), tokenId);
+      assert.equal(uint(1).toNumber(), CHAIN_ID_ZETACHAIN_TESTNET);
+      assert.equal(uint(2).toNumber(), tokenId);
+      assert.isTrue(word(3).equals(admin.publicKey.toBuffer()));
+      assert.equal(uint(4).toString(), current.slot.toString());
+      assert.equal(uint(5).toString(), current.expiresAt.toString());
+    });
+
+    it("Should let a co-owned NFT leave only with the other owner's consent", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping co-ownership test");
+        return;
+      }
+
+      // A bridged token delivered to admin, who co-owns it with user
+      const tokenId = Date.now() + 1191;
+      const uri = `https://example.com/co-owned/${tokenId}.json`;
+      const mint = bridgedMintPda(tokenId);
+      const nftOrigin = PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      const holderTokenAccount = getAssociatedTokenAddressSync(mint, admin.publicKey, false);
+      await program.methods
+        .receiveCrossChainMessage(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender), null)
+        .accounts({
+          programState: programStatePda,
+          nftOrigin,
+          mint,
+          dedupWindow: dedupWindowPda(uri),
+          mintAuthority: mintAuthorityPda,
+          recipient: admin.publicKey,
+          sourceChainConfig: chainConfigPda(zetaChainId),
+          provisionalEscrow: null,
+          custodialTokenAccount: null,
+          inboundOffer: null,
+          pendingInbound: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          recipientTokenAccount: holderTokenAccount,
+          payer: admin.publicKey,
+          gateway: gatewaySigner.publicKey,
+          hourlyMetrics: null,
+          inboundValueClaim: null,
+          inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender)),
+        })
+        .remainingAccounts(originPolicyAccounts)
+        .signers([admin, gatewaySigner])
+        .rpc({ commitment: "confirmed" });
+
+      const coOwnership = PublicKey.findProgramAddressSync([Buffer.from("co_own"), mint.toBuffer()], program.programId)[0];
+      // Consents are keyed by the record's creation time as well as the nonce
+      let recordCreatedAt = new BN(0);
+      const consentPda = (nonce: number, createdAt: BN = recordCreatedAt) => PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("co_consent"),
+          mint.toBuffer(),
+          createdAt.toArrayLike(Buffer, 'le', 8),
+          new BN(nonce).toArrayLike(Buffer, 'le', 8),
+        ],
+        program.programId
+      )[0];
+      const expectError = async (action: Promise<unknown>, error: string) => {
+        try {
+          await action;
+          assert.fail(`Expected ${error}`);
+        } catch (e) {
+          // @ts-ignore
+          assert.include(e.message, error);
+        }
+      };
+      const create = (coOwner: PublicKey) => program.methods
+        .createCoOwnership(new BN(tokenId), coOwner, true)
+        .accounts({ nftOrigin, holderTokenAccount, holder: admin.publicKey })
+        .signers([admin])
+        .rpc();
+      const consent = (approver: Keypair, nonce: number) => program.methods
+        .consentCoOwnedTransfer(new BN(tokenId))
+        .accountsPartial({ nftOrigin, coOwnership, coOwnerConsent: consentPda(nonce), approver: approver.publicKey })
+        .signers([approver])
+        .rpc();
+      const transfer = (record: PublicKey | null, coOwnerConsent: PublicKey | null) => program.methods
+        .transferCrossChain(new BN(tokenId), zetaChainId, Buffer.from(zetaChainRecipient), null, null, null)
+        .accounts({
+          nftOrigin,
+          mint,
+          userTokenAccount: holderTokenAccount,
+          user: admin.publicKey,
+          gatewayProgram: new PublicKey(zetaChainTestnetGateway),
+          feeConfig: feeConfigPda,
+          feeTreasury,
+          chainGasConfig: chainConfigPda(zetaChainId),
+          registry: null,
+          receiptConfig: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          zrc20GasVault: null,
+          zrc20VaultTokenAccount: null,
+          gasFeeDestination: null,
+          hourlyMetrics: null,
+          insuranceOptIn: null,
+          coOwnership: record,
+          coOwnerConsent,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+      const dissolve = (coSigner: Keypair | null) => program.methods
+        .dissolveCoOwnership(new BN(tokenId))
+        .accounts({ nftOrigin, ownerA: admin.publicKey, authority: user.publicKey, coSigner: coSigner?.publicKey ?? null })
+        .signers(coSigner ? [user, coSigner] : [user])
+        .rpc();
+
+      await expectError(create(admin.publicKey), "InvalidCoOwner");
+      await create(user.publicKey);
+      assert.isTrue((await program.account.nftOrigin.fetch(nftOrigin)).coOwned);
+      let record = await program.account.coOwnershipRecord.fetch(coOwnership);
+      recordCreatedAt = record.createdAt;
+      assert.equal(record.ownerA.toString(), admin.publicKey.toString());
+      assert.equal(record.ownerB.toString(), user.publicKey.toString());
+
+      // The record can't be left out, and one owner's signature is not enough
+      await expectError(transfer(null, null), "CoOwnerConsentRequired");
+      await expectError(transfer(coOwnership, null), "CoOwnerConsentRequired");
+      // A consent from the sender themselves doesn't count; its approver can withdraw it
+      await consent(admin, 0);
+      await expectError(transfer(coOwnership, consentPda(0)), "CoOwnerConsentRequired");
+      await program.methods
+        .closeCoOwnerConsent(new BN(tokenId), new BN(0))
+        .accountsPartial({ nftOrigin, coOwnerConsent: consentPda(0), approver: admin.publicKey })
+        .signers([admin])
+        .rpc();
+      assert.isNull(await connection.getAccountInfo(consentPda(0)));
+
+      // A consent left over from a dissolved record doesn't carry over to a recreated one,
+      // which starts again at nonce 0 without colliding with it
+      await consent(user, 0);
+      const staleConsent = consentPda(0);
+      await dissolve(admin);
+      await new Promise((resolve) => setTimeout(resolve, 1500));
+      await create(user.publicKey);
+      record = await program.account.coOwnershipRecord.fetch(coOwnership);
+      assert.equal(record.consentNonce.toNumber(), 0);
+      assert.isTrue(record.createdAt.gt(recordCreatedAt));
+      recordCreatedAt = record.createdAt;
+      assert.notEqual(consentPda(0).toString(), staleConsent.toString());
+      await expectError(transfer(coOwnership, staleConsent), "CoOwnerConsentRequired");
+      // Its approver can still close it
+      await program.methods
+        .closeCoOwnerConsent(new BN(tokenId), new BN(0))
+        .accountsPartial({ nftOrigin, coOwnerConsent: staleConsent, approver: user.publicKey })
+        .signers([user])
+        .rpc();
+      assert.isNull(await connection.getAccountInfo(staleConsent));
+
+      // With the other owner's consent the NFT leaves, and the consent is used up
+      await consent(user, 0);
+      const signature = await transfer(coOwnership, consentPda(0));
+      assert.equal((await program.account.coOwnershipRecord.fetch(coOwnership)).consentNonce.toNumber(), 1);
+      assert.equal(Number((await getAccount(connection, holderTokenAccount)).amount), 0);
+      const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const authorized = Array.from(parser.parseLogs(tx.meta.logMessages)).find((event) => event.name === "coOwnedTransferAuthorized");
+      assert.ok(authorized);
+      assert.equal(authorized.data.initiator.toString(), admin.publicKey.toString());
+      assert.equal(authorized.data.consentNonce.toNumber(), 0);
+
+      // Dissolving needs both owners too
+      await expectError(dissolve(null), "CoOwnerConsentRequired");
+      await dissolve(admin);
+      assert.isNull(await connection.getAccountInfo(coOwnership));
+      assert.isFalse((await program.account.nftOrigin.fetch(nftOrigin)).coOwned);
+    });
+
+    it("Should deliver semi-fungible units and send part of them back out", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping semi-fungible test");
+        return;
+      }
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping semi-fungible test");
+        return;
+      }
+
+      const adminAccounts = { programState: programStatePda, admin: wallet.publicKey };
+      const tokenId = Date.now() + 11902;
+      const uri = `https://example.com/editions/${tokenId}.json`;
+      const mint = bridgedMintPda(tokenId);
+      const nftOrigin = PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      const holderTokenAccount = getAssociatedTokenAddressSync(mint, admin.publicKey, false);
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const eventOf = async (signature: string, name: string) => {
+        const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+        return Array.from(parser.parseLogs(tx.meta.logMessages)).find((event) => event.name === name);
+      };
+      // Amount trailer: tag 7, then the amount (8 bytes, big-endian)
+      const message = Buffer.concat([
+        createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender),
+        Buffer.from([7]),
+        new BN(5).toArrayLike(Buffer, 'be', 8),
+      ]);
+      const receive = () => program.methods
+        .receiveCrossChainMessage(message, null)
+        .accounts({
+          programState: programStatePda,
+          nftOrigin,
+          mint,
+          dedupWindow: dedupWindowPda(uri),
+          mintAuthority: mintAuthorityPda,
+          recipient: admin.publicKey,
+          sourceChainConfig: chainConfigPda(zetaChainId),
+          provisionalEscrow: null,
+          custodialTokenAccount: null,
+          inboundOffer: null,
+          pendingInbound: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          recipientTokenAccount: holderTokenAccount,
+          payer: admin.publicKey,
+          gateway: gatewaySigner.publicKey,
+          hourlyMetrics: null,
+          inboundValueClaim: null,
+          inboundReceipt: inboundReceiptPda(message),
+        })
+        .remainingAccounts(originPolicyAccounts)
+        .signers([admin, gatewaySigner])
+        .rpc({ commitment: "confirmed" });
+      const transfer = (amount: BN) => program.methods
+        .transferCrossChain(new BN(tokenId), zetaChainId, Buffer.from(zetaChainRecipient), null, null, amount)
+        .accounts({
+          nftOrigin,
+          mint,
+          userTokenAccount: holderTokenAccount,
+          user: admin.publicKey,
+          gatewayProgram: new PublicKey(zetaChainTestnetGateway),
+          feeConfig: feeConfigPda,
+          feeTreasury,
+          chainGasConfig: chainConfigPda(zetaChainId),
+          registry: null,
+          receiptConfig: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          zrc20GasVault: null,
+          zrc20VaultTokenAccount: null,
+          gasFeeDestination: null,
+          hourlyMetrics: null,
+          insuranceOptIn: null,
+          coOwnership: null,
+          coOwnerConsent: null,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+      // More than one unit is refused until the admin allows it
+      if (state.allowSemiFungible) {
+        await program.methods.setAllowSemiFungible(false).accounts(adminAccounts).rpc();
+      }
+      try {
+        await receive();
+        assert.fail("Five units should not arrive while semi-fungible tokens are disabled");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "SemiFungibleDisabled");
+      }
+
+      await program.methods.setAllowSemiFungible(true).accounts(adminAccounts).rpc();
+      try {
+        // Inbound of five units: one mint, all five in the recipient's account
+        const received = await eventOf(await receive(), "crossChainMessageReceived");
+        assert.equal(received.data.amount.toNumber(), 5);
+        assert.equal(Number((await getAccount(connection, holderTokenAccount)).amount), 5);
+        assert.equal((await program.account.nftOrigin.fetch(nftOrigin)).amount.toNumber(), 5);
+
+        // No more than the holder has can leave
+        try {
+          await transfer(new BN(6));
+          assert.fail("Six units should not leave an account holding five");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "InsufficientTokens");
+        }
+
+        // Partial outbound: two units burn and leave, three stay
+        const { transferNonce } = await program.account.programState.fetch(programStatePda);
+        const initiated = await eventOf(await transfer(new BN(2)), "crossChainTransferInitiated");
+        assert.equal(initiated.data.amount.toNumber(), 2);
+        assert.equal(Number((await getAccount(connection, holderTokenAccount)).amount), 3);
+        const pendingTransfer = PublicKey.findProgramAddressSync(
+          [Buffer.from("pending_xfer"), transferNonce.toArrayLike(Buffer, 'le', 8)],
+          program.programId
+        )[0];
+        assert.equal((await program.account.pendingTransfer.fetch(pendingTransfer)).amount.toNumber(), 2);
+      } finally {
+        await program.methods.setAllowSemiFungible(false).accounts(adminAccounts).rpc();
+      }
+    });
+
+    it("Should hold a rented NFT on Solana until its lock expires or is cleared", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping rental lock test");
+        return;
+      }
+
+      // A bridged token delivered to admin, rented out through user as its delegate
+      const tokenId = Date.now() + 1192;
+      const uri = `https://example.com/rented/${tokenId}.json`;
+      const mint = bridgedMintPda(tokenId);
+      const nftOrigin = PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      const holderTokenAccount = getAssociatedTokenAddressSync(mint, admin.publicKey, false);
+      await program.methods
+        .receiveCrossChainMessage(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender), null)
+        .accounts({
+          programState: programStatePda,
+          nftOrigin,
+          mint,
+          dedupWindow: dedupWindowPda(uri),
+          mintAuthority: mintAuthorityPda,
+          recipient: admin.publicKey,
+          sourceChainConfig: chainConfigPda(zetaChainId),
+          provisionalEscrow: null,
+          custodialTokenAccount: null,
+          inboundOffer: null,
+          pendingInbound: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          recipientTokenAccount: holderTokenAccount,
+          payer: admin.publicKey,
+          gateway: gatewaySigner.publicKey,
+          hourlyMetrics: null,
+          inboundValueClaim: null,
+          inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender)),
+        })
+        .remainingAccounts(originPolicyAccounts)
+        .signers([admin, gatewaySigner])
+        .rpc({ commitment: "confirmed" });
+
+      const expectError = async (action: Promise<unknown>, error: string) => {
+        try {
+          await action;
+          assert.fail(`Expected ${error}`);
+        } catch (e) {
+          // @ts-ignore
+          assert.include(e.message, error);
+        }
+      };
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const eventOf = async (signature: string, name: string) => {
+        const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+        return Array.from(parser.parseLogs(tx.meta.logMessages)).find((event) => event.name === name);
+      };
+      const now = () => Math.floor(Date.now() / 1000);
+      const lock = (locker: Keypair, until: number) => program.methods
+        .setRentalLock(new BN(tokenId), new BN(until))
+        .accounts({ nftOrigin, holderTokenAccount, locker: locker.publicKey })
+        .signers([locker])
+        .rpc({ commitment: "confirmed" });
+      const clear = (locker: Keypair) => program.methods
+        .clearRentalLock(new BN(tokenId))
+        .accounts({ nftOrigin, locker: locker.publicKey })
+        .signers([locker])
+        .rpc({ commitment: "confirmed" });
+      const transfer = () => program.methods
+        .transferCrossChain(new BN(tokenId), zetaChainId, Buffer.from(zetaChainRecipient), null, null, null)
+        .accounts({
+          nftOrigin,
+          mint,
+          userTokenAccount: holderTokenAccount,
+          user: admin.publicKey,
+          gatewayProgram: new PublicKey(zetaChainTestnetGateway),
+          feeConfig: feeConfigPda,
+          feeTreasury,
+          chainGasConfig: chainConfigPda(zetaChainId),
+          registry: null,
+          receiptConfig: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          zrc20GasVault: null,
+          zrc20VaultTokenAccount: null,
+          gasFeeDestination: null,
+          hourlyMetrics: null,
+          insuranceOptIn: null,
+          coOwnership: null,
+          coOwnerConsent: null,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+      // Only the holder or their delegate can lock, and not for the past or over a year
+      await expectError(lock(user, now() + 3600), "Unauthorized");
+      await expectError(lock(admin, now() - 10), "InvalidRentalLock");
+      await expectError(lock(admin, now() + 366 * 86400), "InvalidRentalLock");
+
+      // The holder's own lock keeps the NFT here until the holder clears it
+      const set = await eventOf(await lock(admin, now() + 3600), "rentalLockSet");
+      assert.equal(set.data.locker.toString(), admin.publicKey.toString());
+      assert.equal(set.data.holder.toString(), admin.publicKey.toString());
+      await expectError(transfer(), "RentalLocked");
+      await expectError(clear(user), "NotRentalLocker");
+      const cleared = await eventOf(await clear(admin), "rentalLockCleared");
+      assert.isFalse(cleared.data.expired);
+      assert.equal((await program.account.nftOrigin.fetch(nftOrigin)).rentalLockedUntil.toNumber(), 0);
+
+      // A rental program locks through the delegate it was approved as; the holder can
+      // neither lift nor replace its lock
+      await approve(connection, admin, holderTokenAccount, user.publicKey, admin, 1);
+      await lock(user, now() + 3);
+      await expectError(clear(admin), "NotRentalLocker");
+      await expectError(lock(admin, now() + 3600), "RentalLocked");
+      await expectError(transfer(), "RentalLocked");
+
+      // Once the lock runs out the transfer goes through and clears it
+      await new Promise((resolve) => setTimeout(resolve, 5000));
+      const expired = await eventOf(await transfer(), "rentalLockCleared");
+      assert.isTrue(expired.data.expired);
+      assert.equal(expired.data.locker.toString(), user.publicKey.toString());
+      const origin = await program.account.nftOrigin.fetch(nftOrigin);
+      assert.equal(origin.rentalLockedUntil.toNumber(), 0);
+      assert.equal(origin.rentalLocker.toString(), PublicKey.default.toString());
+    });
+
+    it("Should replay a delivered message to rebuild its origin without minting", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping replay test");
+        return;
+      }
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping replay test");
+        return;
+      }
+
+      // A delivery that leaves a receipt
+      const tokenId = Date.now() + 1193;
+      const uri = `https://example.com/replayed/${tokenId}.json`;
+      const message = createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender);
+      const mint = bridgedMintPda(tokenId);
+      const nftOrigin = PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      const receiptPda = (bytes: Buffer) => PublicKey.findProgramAddressSync(
+        [Buffer.from("inbound_rcpt"), createHash("sha256").update(bytes).digest()],
+        program.programId
+      )[0];
+      const inboundReceipt = receiptPda(message);
+      await program.methods
+        .receiveCrossChainMessage(message, null)
+        .accounts({
+          programState: programStatePda,
+          nftOrigin,
+          mint,
+          dedupWindow: dedupWindowPda(uri),
+          mintAuthority: mintAuthorityPda,
+          recipient: admin.publicKey,
+          sourceChainConfig: chainConfigPda(zetaChainId),
+          provisionalEscrow: null,
+          custodialTokenAccount: null,
+          inboundOffer: null,
+          pendingInbound: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          recipientTokenAccount: getAssociatedTokenAddressSync(mint, admin.publicKey, false),
+          payer: admin.publicKey,
+          gateway: gatewaySigner.publicKey,
+          hourlyMetrics: null,
+          inboundValueClaim: null,
+          inboundReceipt,
+        })
+        .remainingAccounts(originPolicyAccounts)
+        .signers([admin, gatewaySigner])
+        .rpc({ commitment: "confirmed" });
+      const receipt = await program.account.inboundReceipt.fetch(inboundReceipt);
+      assert.equal(receipt.tokenId.toNumber(), tokenId);
+      assert.equal(receipt.mint.toString(), mint.toString());
+
+      const replay = (bytes: Buffer, slot: BN, signer: Keypair | null) => program.methods
+        .replayInbound(bytes, slot)
+        .accountsPartial({
+          programState: programStatePda,
+          inboundReceipt: receiptPda(bytes),
+          nftOrigin,
+          mint,
+          admin: signer ? signer.publicKey : wallet.publicKey,
+        })
+        .signers(signer ? [signer] : [])
+        .rpc({ commitment: "confirmed" });
+      const expectError = async (action: Promise<unknown>, error: string) => {
+        try {
+          await action;
+          assert.fail(`Expected ${error}`);
+        } catch (e) {
+          // @ts-ignore
+          assert.include(e.message, error);
+        }
+      };
+
+      // Only the owner replays, only at the receipt's slot, and only messages with a receipt
+      await expectError(replay(message, receipt.slot, user), "Unauthorized");
+      await expectError(replay(message, receipt.slot.addn(1), null), "InboundReceiptMismatch");
+      const unreceipted = createZetaChainSuccessMessage(tokenId, `${uri}?v=2`, admin.publicKey.toBytes(), solanaSender);
+      await expectError(replay(unreceipted, receipt.slot, null), "AccountNotInitialized");
+
+      // The replay rewrites the origin and marks it, but the supply stays where it was
+      const supplyBefore = (await getMint(connection, mint)).supply;
+      const signature = await replay(message, receipt.slot, null);
+      assert.equal((await getMint(connection, mint)).supply, supplyBefore);
+      const origin = await program.account.nftOrigin.fetch(nftOrigin);
+      assert.isTrue(origin.reconstructed);
+      assert.equal(origin.metadataUri, uri);
+      assert.equal(origin.mint.toString(), mint.toString());
+      const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const replayed = Array.from(parser.parseLogs(tx.meta.logMessages)).find((event) => event.name === "inboundReplayed");
+      assert.ok(replayed);
+      assert.isFalse(replayed.data.recreated);
+      assert.equal(replayed.data.originalSlot.toString(), receipt.slot.toString());
+    });
+
+    it("Should list parked inbound messages highest priority first", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping inbound priority test");
+        return;
+      }
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping inbound priority test");
+        return;
+      }
+
+      // Priority trailer: tag 8, then the priority byte; the first message has none
+      const baseId = Date.now() + 1194;
+      const priorities = [null, 5, 9, 5];
+      const messages = priorities.map((priority, i) => {
+        const message = createZetaChainSuccessMessage(baseId + i, `https://example.com/priority/${baseId + i}.json`, admin.publicKey.toBytes(), solanaSender);
+        return priority === null ? message : Buffer.concat([message, Buffer.from([8, priority])]);
+      });
+      const hashOf = (message: Buffer) => createHash("sha256").update(message).digest();
+      const pendingPda = (message: Buffer) => PublicKey.findProgramAddressSync(
+        [Buffer.from("pending_inbound"), hashOf(message)],
+        program.programId
+      )[0];
+      const parser = new anchor.EventParser(program.programId, program.coder);
+
+      const adminAccounts = { programState: programStatePda, admin: wallet.publicKey };
+      await program.methods.setInboundMode({ twoPhase: {} }).accounts(adminAccounts).rpc();
+      try {
+        for (const [i, message] of messages.entries()) {
+          const signature = await program.methods
+            .queueInbound(message, null)
+            .accounts({ programState: programStatePda, pendingInbound: pendingPda(message), gateway: gatewaySigner.publicKey, payer: admin.publicKey })
+            .signers([admin, gatewaySigner])
+            .rpc({ commitment: "confirmed" });
+          const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+          const queued = Array.from(parser.parseLogs(tx.meta.logMessages)).find((event) => event.name === "inboundQueued");
+          assert.equal(queued.data.priority, priorities[i] ?? 0);
+          assert.equal((await program.account.pendingInbound.fetch(pendingPda(message))).priority, priorities[i] ?? 0);
+        }
+        // Records queued with the priority byte have nothing to migrate
+        try {
+          await program.methods
+            .migratePendingInbound(Array.from(hashOf(messages[0])))
+            .accounts({ pendingInbound: pendingPda(messages[0]), payer: admin.publicKey })
+            .signers([admin])
+            .rpc();
+          assert.fail("A current PendingInbound should not migrate");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "NoChange");
+        }
+      } finally {
+        await program.methods.setInboundMode({ onePhase: {} }).accounts(adminAccounts).rpc();
+      }
+
+      const list = (minPriority: number, limit: number) => program.methods
+        .listPendingInbound(minPriority, limit)
+        .remainingAccounts(messages.map((message) => ({ pubkey: pendingPda(message), isSigner: false, isWritable: false })))
+        .view();
+      const hex = (hashes: number[][]) => hashes.map((hash) => Buffer.from(hash).toString("hex"));
+
+      // Highest first, the two fives in the order they were queued, the unprioritized last
+      assert.deepEqual(hex(await list(0, 10)), [2, 1, 3, 0].map((i) => hashOf(messages[i]).toString("hex")));
+      assert.deepEqual(hex(await list(5, 10)), [2, 1, 3].map((i) => hashOf(messages[i]).toString("hex")));
+      assert.deepEqual(hex(await list(0, 2)), [2, 1].map((i) => hashOf(messages[i]).toString("hex")));
+      assert.deepEqual(await list(10, 10), []);
+      for (const limit of [0, 31]) {
+        try {
+          await list(0, limit);
+          assert.fail(`A limit of ${limit} should be refused`);
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "InvalidPendingInboundList");
+        }
+      }
+    });
+
+    it("Should refuse transfers reached through another program's CPI", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping reentrancy test");
+        return;
+      }
+      // Loaded through [[test.genesis]] rather than built with the workspace, so its
+      // instruction is put together by hand: Anchor's discriminator, then the Vec<u8>
+      const attackerProgramId = new PublicKey("FojnH4xK6fpdtHbeE7NT7oGuc5Qbk3DUKHBtk6fEm9vz");
+      const forward = (instruction: anchor.web3.TransactionInstruction) => {
+        const length = Buffer.alloc(4);
+        length.writeUInt32LE(instruction.data.length);
+        return new anchor.web3.TransactionInstruction({
+          programId: attackerProgramId,
+          keys: [{ pubkey: program.programId, isSigner: false, isWritable: false }, ...instruction.keys],
+          data: Buffer.concat([
+            createHash("sha256").update("global:forward").digest().subarray(0, 8),
+            length,
+            instruction.data,
+          ]),
+        });
+      };
+
+      // A bridged token delivered to admin
+      const tokenId = Date.now() + 1195;
+      const uri = `https://example.com/reentrancy/${tokenId}.json`;
+      const mint = bridgedMintPda(tokenId);
+      const nftOrigin = PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      const holderTokenAccount = getAssociatedTokenAddressSync(mint, admin.publicKey, false);
+      await program.methods
+        .receiveCrossChainMessage(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender), null)
+        .accounts({
+          programState: programStatePda,
+          nftOrigin,
+          mint,
+          dedupWindow: dedupWindowPda(uri),
+          mintAuthority: mintAuthorityPda,
+          recipient: admin.publicKey,
+          sourceChainConfig: chainConfigPda(zetaChainId),
+          provisionalEscrow: null,
+          custodialTokenAccount: null,
+          inboundOffer: null,
+          pendingInbound: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          recipientTokenAccount: holderTokenAccount,
+          payer: admin.publicKey,
+          gateway: gatewaySigner.publicKey,
+          hourlyMetrics: null,
+          inboundValueClaim: null,
+          inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender)),
+        })
+        .remainingAccounts(originPolicyAccounts)
+        .signers([admin, gatewaySigner])
+        .rpc();
+
+      const transfer = program.methods
+        .transferCrossChain(new BN(tokenId), zetaChainId, Buffer.from(zetaChainRecipient), null, null, null)
+        .accounts({
+          nftOrigin,
+          mint,
+          userTokenAccount: holderTokenAccount,
+          user: admin.publicKey,
+          gatewayProgram: new PublicKey(zetaChainTestnetGateway),
+          feeConfig: feeConfigPda,
+          feeTreasury,
+          chainGasConfig: chainConfigPda(zetaChainId),
+          registry: null,
+          receiptConfig: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          zrc20GasVault: null,
+          zrc20VaultTokenAccount: null,
+          gasFeeDestination: null,
+          hourlyMetrics: null,
+          insuranceOptIn: null,
+          coOwnership: null,
+          coOwnerConsent: null,
+        })
+        .signers([admin]);
+
+      // The same instruction, replayed through the attacker, is stopped before it burns anything
+      const instruction = await transfer.instruction();
+      try {
+        await provider.sendAndConfirm(new anchor.web3.Transaction().add(forward(instruction)), [admin]);
+        assert.fail("Expected ReentrancyDetected");
+      } catch (e) {
+        // @ts-ignore
+        assert.include(e.message + (e.logs ?? []).join("\n"), "ReentrancyDetected");
+      }
+      assert.equal((await getAccount(connection, holderTokenAccount)).amount.toString(), "1");
+
+      // Called directly it goes through, and leaves the lock cleared
+      await transfer.rpc();
+      assert.equal((await getAccount(connection, holderTokenAccount)).amount.toString(), "0");
+      assert.isFalse((await program.account.programState.fetch(programStatePda)).locked);
+    });
+
+    it("Should check the upgrade authority against the announced one", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping integrity test");
+        return;
+      }
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping integrity test");
+        return;
+      }
+
+      // The test validator deploys the program upgradeable, with the provider wallet as authority
+      const adminAccounts = { programState: programStatePda, admin: wallet.publicKey };
+      const bpfLoaderUpgradeable = new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111");
+      const [programData] = PublicKey.findProgramAddressSync([program.programId.toBuffer()], bpfLoaderUpgradeable);
+      const deploySlot = (await connection.getAccountInfo(programData)).data.readBigUInt64LE(4);
+      const verify = () => program.methods
+        .verifyIntegrity()
+        .accounts({ programState: programStatePda, programData, bpfLoaderUpgradeable });
+      const integrityAccounts = { ...adminAccounts, programData, bpfLoaderUpgradeable };
+      const expect = (authority: PublicKey) => program.methods
+        .setExpectedUpgradeAuthority(authority)
+        .accounts({ ...integrityAccounts, upgradeAuthority: wallet.publicKey })
+        .rpc({ commitment: "confirmed" });
+      const parser = new anchor.EventParser(program.programId, program.coder);
+
+      try {
+        // Any other announced authority fails the check, and a transaction logs the mismatch
+        const announced = Keypair.generate().publicKey;
+        await expect(announced);
+        const mismatch = await verify().view();
+        assert.isFalse(mismatch.passed);
+        assert.isFalse(mismatch.immutable);
+        assert.equal(mismatch.upgradeAuthority.toString(), wallet.publicKey.toString());
+        assert.equal(mismatch.expectedUpgradeAuthority.toString(), announced.toString());
+        assert.equal(mismatch.lastDeploySlot.toString(), deploySlot.toString());
+        const signature = await verify().rpc({ commitment: "confirmed" });
+        const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+        const event = Array.from(parser.parseLogs(tx.meta.logMessages)).find((e) => e.name === "integrityMismatch");
+        assert.equal(event.data.expectedUpgradeAuthority.toString(), announced.toString());
+        assert.equal(event.data.upgradeAuthority.toString(), wallet.publicKey.toString());
+
+        // The actual authority passes
+        await expect(wallet.publicKey);
+        const matching = await verify().view();
+        assert.isTrue(matching.passed);
+        assert.equal(matching.upgradeAuthority.toString(), wallet.publicKey.toString());
+
+        // Only the owner announces it, and only with the current upgrade authority co-signing
+        try {
+          await program.methods
+            .setExpectedUpgradeAuthority(admin.publicKey)
+            .accounts({ programState: programStatePda, admin: admin.publicKey, programData, upgradeAuthority: wallet.publicKey, bpfLoaderUpgradeable })
+            .signers([admin])
+            .rpc();
+          assert.fail("Expected Unauthorized");
+        } catch (e) {
+          // @ts-ignore
+          assert.include(e.message, "Unauthorized");
+        }
+        try {
+          await program.methods
+            .setExpectedUpgradeAuthority(admin.publicKey)
+            .accounts({ ...integrityAccounts, upgradeAuthority: admin.publicKey })
+            .signers([admin])
+            .rpc();
+          assert.fail("Expected Unauthorized");
+        } catch (e) {
+          // @ts-ignore
+          assert.include(e.message, "Unauthorized");
+        }
+        assert.equal((await program.account.programState.fetch(programStatePda)).expectedUpgradeAuthority.toString(), wallet.publicKey.toString());
+      } finally {
+        const { expectedUpgradeAuthority } = await program.account.programState.fetch(programStatePda);
+        if (!expectedUpgradeAuthority.equals(state.expectedUpgradeAuthority)) {
+          await expect(state.expectedUpgradeAuthority);
+        }
+      }
+    });
+
+    it("Should return an NFT its origin-chain contract recalls unless the holder contests", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping forced recall test");
+        return;
+      }
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping forced recall test");
+        return;
+      }
+
+      // Arbitrum Sepolia's connected contract is the only sender a recall is taken from
+      const arbitrumSepolia = 421614;
+      const connectedContract = Buffer.alloc(20, 0xa5);
+      const registryPda = PublicKey.findProgramAddressSync([Buffer.from("registry")], program.programId)[0];
+      if (!(await connection.getAccountInfo(registryPda))) {
+        await program.methods.initRegistry().accounts({ admin: wallet.publicKey }).rpc();
+      }
+      await program.methods
+        .setRegistryEntry(Array.from(new Uint8Array(20).fill(0x84)), connectedContract, new BN(arbitrumSepolia), 0)
+        .accounts({ admin: wallet.publicKey })
+        .rpc();
+
+      // An NFT minted on Arbitrum Sepolia, delivered to admin with its provenance
+      const tokenId = Date.now() + 293;
+      const uri = `https://example.com/recalled/${tokenId}.json`;
+      const mint = bridgedMintPda(tokenId);
+      const nftOrigin = PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      const holderTokenAccount = getAssociatedTokenAddressSync(mint, admin.publicKey, false);
+      const provenance = Buffer.concat([
+        Buffer.from([6]),
+        new BN(arbitrumSepolia).toArrayLike(Buffer, 'be', 8),
+        new BN(7).toArrayLike(Buffer, 'be', 8),
+        Buffer.from([connectedContract.length]),
+        connectedContract,
+      ]);
+      await program.methods
+        .receiveCrossChainMessage(
+          Buffer.concat([createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender), provenance]),
+          null
+        )
+        .accounts({
+          programState: programStatePda,
+          nftOrigin,
+          mint,
+          dedupWindow: dedupWindowPda(uri),
+          mintAuthority: mintAuthorityPda,
+          recipient: admin.publicKey,
+          sourceChainConfig: chainConfigPda(zetaChainId),
+          provisionalEscrow: null,
+          custodialTokenAccount: null,
+          inboundOffer: null,
+          pendingInbound: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          recipientTokenAccount: holderTokenAccount,
+          payer: admin.publicKey,
+          gateway: gatewaySigner.publicKey,
+          hourlyMetrics: null,
+          inboundValueClaim: null,
+          inboundReceipt: inboundReceiptPda(Buffer.concat([createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender), provenance])),
+        })
+        .remainingAccounts(originPolicyAccounts)
+        .signers([admin, gatewaySigner])
+        .rpc({ commitment: "confirmed" });
+
+      // Version 0x04, then the token id and the return receiver as ABI words
+      const returnReceiver = Buffer.alloc(20, 0x3e);
+      const recallMessage = Buffer.concat([
+        Buffer.from([0x04]),
+        new BN(tokenId).toArrayLike(Buffer, 'be', 32),
+        Buffer.alloc(12),
+        returnReceiver,
+      ]);
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const eventOf = async (signature: string, name: string) => {
+        const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+        return Array.from(parser.parseLogs(tx.meta.logMessages)).find((event) => event.name === name);
+      };
+      const expectError = async (promise: Promise<unknown>, code: string) => {
+        try {
+          await promise;
+          assert.fail(`Expected ${code}`);
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, code);
+        }
+      };
+      const forcedRecall = PublicKey.findProgramAddressSync(
+        [Buffer.from("forced_recall"), new BN(tokenId).toArrayLike(Buffer, 'le', 8)],
+        program.programId
+      )[0];
+      const gateway = Keypair.generate();
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(gateway.publicKey, LAMPORTS_PER_SOL),
+        'confirmed'
+      );
+      const recall = (sender: Buffer) => program.methods
+        .receiveForcedRecall(new BN(tokenId), Array.from(sender), recallMessage)
+        .accounts({ programState: programStatePda, nftOrigin, gateway: gateway.publicKey })
+        .signers([gateway])
+        .rpc({ commitment: "confirmed" });
+      const execute = () => program.methods
+        .executeForcedRecall(new BN(tokenId))
+        .accounts({
+          programState: programStatePda,
+          nftOrigin,
+          mint,
+          holderTokenAccount,
+          gatewayProgram: new PublicKey(zetaChainTestnetGateway),
+          payer: wallet.publicKey,
+        })
+        .rpc({ commitment: "confirmed" });
+      const adminAccounts = { programState: programStatePda, admin: wallet.publicKey };
+
+      try {
+        await program.methods.setGateway(gateway.publicKey).accounts(adminAccounts).rpc();
+        await expectError(recall(connectedContract), "ForcedRecallDisabled");
+        await program.methods.setForcedRecallPolicy(true, new BN(5)).accounts(adminAccounts).rpc();
+
+        // Only the registry's contract for the origin chain can order a recall
+        await expectError(recall(Buffer.alloc(20, 0xa6)), "RecallSenderNotConnected");
+
+        // The holder contests within the window and the recall is off
+        const requested = await eventOf(await recall(connectedContract), "forcedRecallRequested");
+        assert.equal(requested.data.originChain.toNumber(), arbitrumSepolia);
+        assert.deepEqual(Buffer.from(requested.data.returnReceiver), returnReceiver);
+        await expectError(recall(connectedContract), "ForcedRecallPending");
+        const contested = await eventOf(
+          await program.methods
+            .contestRecall(new BN(tokenId))
+            .accounts({ programState: programStatePda, forcedRecall, holderTokenAccount, holder: admin.publicKey })
+            .signers([admin])
+            .rpc({ commitment: "confirmed" }),
+          "forcedRecallContested"
+        );
+        assert.equal(contested.data.holder.toString(), admin.publicKey.toString());
+        await expectError(execute(), "ForcedRecallNotPending");
+
+        // Uncontested, it runs once the window has closed
+        await recall(connectedContract);
+        await expectError(execute(), "RecallDisputeWindowOpen");
+        await new Promise((resolve) => setTimeout(resolve, 6000));
+        const executed = await eventOf(await execute(), "forcedRecallExecuted");
+        assert.equal(executed.data.holder.toString(), admin.publicKey.toString());
+        assert.equal(executed.data.originChain.toNumber(), arbitrumSepolia);
+        const frozen = await getAccount(connection, holderTokenAccount, "confirmed");
+        assert.isTrue(frozen.isFrozen);
+        const origin = await program.account.nftOrigin.fetch(nftOrigin, "confirmed");
+        assert.equal(origin.strandedSupply.toNumber(), 1);
+        const record = await program.account.forcedRecall.fetch(forcedRecall, "confirmed");
+        assert.equal(record.status, 3);
+      } finally {
+        const current = await program.account.programState.fetch(programStatePda, "confirmed");
+        if (!current.gateway.equals(state.gateway)) {
+          await program.methods.setGateway(state.gateway).accounts(adminAccounts).rpc();
+        }
+        if (current.forcedRecallEnabled) {
+          await program.methods.setForcedRecallPolicy(false, new BN(0)).accounts(adminAccounts).rpc();
+        }
+      }
+    });
+
+    it("Should charge the transfer fee and refuse transfers that leave out its accounts", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping transfer fee test");
+        return;
+      }
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping transfer fee test");
+        return;
+      }
+
+      const tokenId = Date.now() + 14;
+      const uri = `https://example.com/fee/${tokenId}.json`;
+      const nftOriginPda = PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      const mint = bridgedMintPda(tokenId);
+      const userTokenAccount = getAssociatedTokenAddressSync(mint, admin.publicKey, false);
+      await program.methods
+        .receiveCrossChainMessage(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender), null)
+        .accounts({
+          programState: programStatePda,
+          nftOrigin: nftOriginPda,
+          mint,
+          dedupWindow: dedupWindowPda(uri),
+          mintAuthority: mintAuthorityPda,
+          recipient: admin.publicKey,
+          sourceChainConfig: chainConfigPda(zetaChainId),
+          provisionalEscrow: null,
+          custodialTokenAccount: null,
+          inboundOffer: null,
+          pendingInbound: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          recipientTokenAccount: userTokenAccount,
+          payer: admin.publicKey,
+          gateway: gatewaySigner.publicKey,
+          hourlyMetrics: null,
+          inboundValueClaim: null,
+          inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender)),
+        })
+        .remainingAccounts(originPolicyAccounts)
+        .signers([admin, gatewaySigner])
+        .rpc();
+
+      const transfer = (fees: { feeConfig: PublicKey; feeTreasury: PublicKey } | {}) => program.methods
+        .transferCrossChain(new BN(tokenId), zetaChainId, Buffer.from(zetaChainRecipient), null, null, null)
+        .accountsPartial({
+          programState: programStatePda,
+          nftOrigin: nftOriginPda,
+          mint,
+          userTokenAccount,
+          user: admin.publicKey,
+          gatewayProgram: new PublicKey(zetaChainTestnetGateway),
+          ...fees,
+          chainGasConfig: chainConfigPda(zetaChainId),
+          registry: null,
+          receiptConfig: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          zrc20GasVault: null,
+          zrc20VaultTokenAccount: null,
+          gasFeeDestination: null,
+          hourlyMetrics: null,
+          insuranceOptIn: null,
+          coOwnership: null,
+          coOwnerConsent: null,
+        })
+        .signers([admin])
+        .rpc();
+
+      const { transferFeeLamports } = await program.account.feeConfig.fetch(feeConfigPda);
+      const fee = 2_000_000;
+      const feeAccounts = { programState: programStatePda, feeConfig: feeConfigPda, admin: wallet.publicKey };
+      await program.methods.setFeeConfig(new BN(fee), feeTreasury).accounts(feeAccounts).rpc();
+      try {
+        // Leaving the treasury out, or passing another one, doesn't skip the fee
+        for (const fees of [{ feeConfig: feeConfigPda }, { feeConfig: feeConfigPda, feeTreasury: admin.publicKey }]) {
+          try {
+            await transfer(fees);
+            assert.fail("A transfer without the fee treasury should be rejected");
+          } catch (error) {
+            // @ts-ignore
+            assert.notInclude(error.message, "should be rejected");
+          }
+        }
+
+        const treasuryBefore = await connection.getBalance(feeTreasury);
+        await transfer({ feeConfig: feeConfigPda, feeTreasury });
+        assert.equal(await connection.getBalance(feeTreasury) - treasuryBefore, fee);
+      } finally {
+        await program.methods.setFeeConfig(transferFeeLamports, feeTreasury).accounts(feeAccounts).rpc();
+      }
+    });
+
+    it("Should only ping the configured gateway", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping gateway ping test");
+        return;
+      }
+
+      try {
+        await program.methods
+          .pingGateway(Array.from(zetaChainRecipient))
+          .accounts({ programState: programStatePda, sender: admin.publicKey, gatewayProgram: Keypair.generate().publicKey })
+          .signers([admin])
+          .rpc();
+        assert.fail("A ping to another program should be rejected");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "Unauthorized");
+      }
+    });
+
+    it("Should trip the circuit breaker only on reports from a relayer", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping circuit breaker test");
+        return;
+      }
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping circuit breaker test");
+        return;
+      }
+
+      const securityStatePda = PublicKey.findProgramAddressSync([Buffer.from("security_state")], program.programId)[0];
+      const relayerRole = (relayer: PublicKey) => PublicKey.findProgramAddressSync(
+        [Buffer.from("relayer_role"), relayer.toBuffer()],
+        program.programId
+      )[0];
+      const breakerAccounts = { programState: programStatePda, securityState: securityStatePda, admin: wallet.publicKey };
+      const previous = await program.account.securityState.fetchNullable(securityStatePda);
+      // A message that can't decode reproduces a decode failure
+      const undecodable = Buffer.from([0xff, 0x00]);
+      const report = (reporter: Keypair) => program.methods
+        .reportAnomaly({ decodeFailure: {} }, undecodable)
+        .accountsPartial({
+          programState: programStatePda,
+          securityState: securityStatePda,
+          bridgedMint: null,
+          nftOrigin: null,
+          reporter: reporter.publicKey,
+          relayerRole: relayerRole(reporter.publicKey),
+          hourlyMetrics: null,
+        })
+        .signers([reporter])
+        .rpc();
+
+      await program.methods.setCircuitBreakerConfig(new BN(3600), 1, 0).accounts(breakerAccounts).rpc();
+      await program.methods.grantRelayerRole(admin.publicKey).accounts({ admin: wallet.publicKey }).rpc();
+      try {
+        try {
+          await report(user);
+          assert.fail("A report from a wallet without a relayer role should be rejected");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "AccountNotInitialized");
+        }
+        assert.isFalse((await program.account.programState.fetch(programStatePda)).autoPausedInbound);
+
+        // One decode failure meets the threshold of one and pauses inbound deliveries
+        await report(admin);
+        assert.isTrue((await program.account.programState.fetch(programStatePda)).autoPausedInbound);
+      } finally {
+        await program.methods.revokeRelayerRole(admin.publicKey).accounts({ admin: wallet.publicKey }).rpc();
+        await program.methods.clearCircuitBreaker().accounts(breakerAccounts).rpc();
+        await program.methods
+          .setCircuitBreakerConfig(
+            previous?.windowSeconds ?? new BN(3600),
+            previous?.failedDecodeThreshold ?? 0,
+            previous?.replayRejectionThreshold ?? 0
+          )
+          .accounts(breakerAccounts)
+          .rpc();
+      }
+    });
+
+    it("Should cancel a transfer only after the gateway reports it reverted", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping transfer cancel test");
+        return;
+      }
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.gateway.equals(gatewaySigner.publicKey)) {
+        console.log("Gateway is not the test signer, skipping transfer cancel test");
+        return;
+      }
+
+      const tokenId = Date.now() + 1154;
+      const uri = `https://example.com/cancel/${tokenId}.json`;
+      const nftOrigin = PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+        program.programId
+      )[0];
+      const mint = bridgedMintPda(tokenId);
+      const userTokenAccount = getAssociatedTokenAddressSync(mint, admin.publicKey, false);
+      await program.methods
+        .receiveCrossChainMessage(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender), null)
+        .accounts({
+          programState: programStatePda,
+          nftOrigin,
+          mint,
+          dedupWindow: dedupWindowPda(uri),
+          mintAuthority: mintAuthorityPda,
+          recipient: admin.publicKey,
+          sourceChainConfig: chainConfigPda(zetaChainId),
+          provisionalEscrow: null,
+          custodialTokenAccount: null,
+          inboundOffer: null,
+          pendingInbound: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          recipientTokenAccount: userTokenAccount,
+          payer: admin.publicKey,
+          gateway: gatewaySigner.publicKey,
+          hourlyMetrics: null,
+          inboundValueClaim: null,
+          inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender)),
+        })
+        .remainingAccounts(originPolicyAccounts)
+        .signers([admin, gatewaySigner])
+        .rpc();
+
+      const { transferNonce } = await program.account.programState.fetch(programStatePda);
+      const pendingTransfer = PublicKey.findProgramAddressSync(
+        [Buffer.from("pending_xfer"), transferNonce.toArrayLike(Buffer, 'le', 8)],
+        program.programId
+      )[0];
+      await program.methods
+        .transferCrossChain(new BN(tokenId), zetaChainId, Buffer.from(zetaChainRecipient), null, null, null)
+        .accountsPartial({
+          programState: programStatePda,
+          nftOrigin,
+          mint,
+          userTokenAccount,
+          user: admin.publicKey,
+          gatewayProgram: new PublicKey(zetaChainTestnetGateway),
+          feeConfig: feeConfigPda,
+          feeTreasury,
+          chainGasConfig: chainConfigPda(zetaChainId),
+          registry: null,
+          receiptConfig: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          zrc20GasVault: null,
+          zrc20VaultTokenAccount: null,
+          gasFeeDestination: null,
+          pendingTransfer,
+          hourlyMetrics: null,
+          insuranceOptIn: null,
+          coOwnership: null,
+          coOwnerConsent: null,
+        })
+        .signers([admin])
+        .rpc();
+      assert.equal((await getAccount(connection, userTokenAccount)).amount, BigInt(0));
+
+      const cancel = (sender: Keypair = admin) => program.methods
+        .cancelPendingTransfer(transferNonce)
+        .accountsPartial({
+          pendingTransfer,
+          nftOrigin,
+          mint,
+          mintAuthority: mintAuthorityPda,
+          senderTokenAccount: userTokenAccount,
+          sender: sender.publicKey,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          chainStats: PublicKey.findProgramAddressSync(
+            [Buffer.from("chain_stats"), zetaChainId.toArrayLike(Buffer, 'le', 8)],
+            program.programId
+          )[0],
+          nativeMintAuthority: null,
+        })
+        .signers([sender])
+        .rpc();
+      // The message already left with the burn; until it reverts it may still land
+      try {
+        await cancel();
+        assert.fail("A transfer still in flight should not be cancellable");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "TransferNotReverted");
+      }
+
+      await program.methods
+        .onTransferReverted(transferNonce)
+        .accountsPartial({ programState: programStatePda, pendingTransfer, insuranceFund: null, sender: admin.publicKey, gateway: gatewaySigner.publicKey })
+        .signers([gatewaySigner])
+        .rpc();
+      // Only the sender can take the token back
+      try {
+        await cancel(user);
+        assert.fail("Someone other than the sender should not be able to cancel");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "ConstraintHasOne");
+      }
+      await cancel();
+      assert.equal((await getAccount(connection, userTokenAccount)).amount, BigInt(1));
+      assert.isNull(await connection.getAccountInfo(pendingTransfer));
+    });
+
+    it("Should restore a reverted native mint only with its mint authority's signature", async () => {
+      assert.isTrue(isProgramInitialized, "The setup tests should have initialized the program");
+      const state = await program.account.programState.fetch(programStatePda);
+      assert.isTrue(state.gateway.equals(gatewaySigner.publicKey), "The gateway should be the test signer");
+
+      // The admin creates the mint, so it stays the mint authority and holds the token
+      const tokenId = NATIVE_ID_BASE.addn(Date.now() % 1000000 + 11850);
+      const mint = Keypair.generate();
+      await mintNative(tokenId, admin, mint).rpc();
+      const nftOrigin = nftOriginPda(tokenId);
+      const userTokenAccount = getAssociatedTokenAddressSync(mint.publicKey, admin.publicKey, false);
+      const { originChain } = await program.account.nftOrigin.fetch(nftOrigin);
+
+      const { transferNonce } = await program.account.programState.fetch(programStatePda);
+      const pendingTransfer = PublicKey.findProgramAddressSync(
+        [Buffer.from("pending_xfer"), transferNonce.toArrayLike(Buffer, 'le', 8)],
+        program.programId
+      )[0];
+      await program.methods
+        .transferCrossChain(tokenId, zetaChainId, Buffer.from(zetaChainRecipient), null, null, null)
+        .accountsPartial({
+          programState: programStatePda,
+          nftOrigin,
+          mint: mint.publicKey,
+          userTokenAccount,
+          user: admin.publicKey,
+          gatewayProgram: new PublicKey(zetaChainTestnetGateway),
+          feeConfig: feeConfigPda,
+          feeTreasury,
+          chainGasConfig: chainConfigPda(zetaChainId),
+          registry: null,
+          receiptConfig: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          zrc20GasVault: null,
+          zrc20VaultTokenAccount: null,
+          gasFeeDestination: null,
+          pendingTransfer,
+          hourlyMetrics: null,
+          insuranceOptIn: null,
+          coOwnership: null,
+          coOwnerConsent: null,
+        })
+        .signers([admin])
+        .rpc();
+      await program.methods
+        .onTransferReverted(transferNonce)
+        .accountsPartial({ programState: programStatePda, pendingTransfer, insuranceFund: null, sender: admin.publicKey, gateway: gatewaySigner.publicKey })
+        .signers([gatewaySigner])
+        .rpc();
+
+      const cancel = (nativeMintAuthority: Keypair | null) => program.methods
+        .cancelPendingTransfer(transferNonce)
+        .accountsPartial({
+          pendingTransfer,
+          nftOrigin,
+          mint: mint.publicKey,
+          mintAuthority: mintAuthorityPda,
+          senderTokenAccount: userTokenAccount,
+          sender: admin.publicKey,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          chainStats: PublicKey.findProgramAddressSync(
+            [Buffer.from("chain_stats"), originChain.toArrayLike(Buffer, 'le', 8)],
+            program.programId
+          )[0],
+          nativeMintAuthority: nativeMintAuthority?.publicKey ?? null,
+        })
+        .signers(nativeMintAuthority ? [admin, nativeMintAuthority] : [admin])
+        .rpc();
+      // The program PDA can't mint it, and neither can anyone but its mint authority
+      for (const [label, signer] of [["No mint authority", null], ["Another signer", user]] as const) {
+        try {
+          await cancel(signer);
+          assert.fail(`${label} should not be able to restore a native mint`);
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "NativeMintAuthorityRequired");
+        }
+      }
+      await cancel(admin);
+      assert.equal((await getAccount(connection, userTokenAccount)).amount, BigInt(1));
+      assert.isNull(await connection.getAccountInfo(pendingTransfer));
+    });
+
+    it("Should split the mint fee between the collection's creator and the treasury", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping mint fee split test");
+        return;
+      }
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping mint fee split test");
+        return;
+      }
+
+      const feeConfig = PublicKey.findProgramAddressSync([Buffer.from("fee_config")], program.programId)[0];
+      const collectionId = new BN(Date.now() + 171);
+      const sellerFeeConfig = PublicKey.findProgramAddressSync(
+        [Buffer.from("seller_fee_config"), collectionId.toArrayLike(Buffer, 'le', 8)],
+        program.programId
+      )[0];
+      const creator = Keypair.generate().publicKey;
+      const previousFeeConfig = await program.account.feeConfig.fetchNullable(feeConfig);
+      const treasury = previousFeeConfig?.treasury ?? Keypair.generate().publicKey;
+      const feeAccounts = { programState: programStatePda, feeConfig, admin: wallet.publicKey };
+      if (!previousFeeConfig) {
+        await program.methods.setFeeConfig(new BN(0), treasury).accounts(feeAccounts).rpc();
+      }
+      await program.methods
+        .setSellerFeeConfig(collectionId, [{ address: creator, share: 100 }])
+        .accounts({ programState: programStatePda, sellerFeeConfig, collectionAuthority: null, admin: wallet.publicKey })
+        .rpc();
+      // The creator share goes to the collection the mint names, whose reveal config must come along
+      const lazyRevealConfig = PublicKey.findProgramAddressSync(
+        [Buffer.from("lazy_reveal"), collectionId.toArrayLike(Buffer, 'le', 8)],
+        program.programId
+      )[0];
+      await program.methods
+        .configureLazyReveal(collectionId, new BN(Math.floor(Date.now() / 1000) + 3600), 3, "https://example.com/fee-split", Keypair.generate().publicKey)
+        .accountsPartial({ admin: wallet.publicKey, lazyRevealConfig, collectionAuthority: null })
+        .rpc();
+
+      try {
+        await program.methods.setMintFee(new BN(10_000_000), 10_001).accounts(feeAccounts).rpc();
+        assert.fail("A creator share over 10000 bps should be rejected");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "InvalidFeeSplit");
+      }
+
+      // 25% of the fee goes to the collection's first creator, the rest to the treasury
+      await program.methods.setMintFee(new BN(10_000_000), 2_500).accounts(feeAccounts).rpc();
+      try {
+        const mintWithFee = (tokenId: BN, newMint: Keypair, collection: BN | null, config: PublicKey | null = sellerFeeConfig) =>
+          program.methods
+            .createMintAndNft("https://example.com/fee-split.json", 0, tokenId, null, null, collection)
+            .accounts({
+              nftOrigin: PublicKey.findProgramAddressSync(
+                [Buffer.from("nft_origin"), tokenId.toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+                program.programId
+              )[0],
+              mint: newMint.publicKey,
+              mintAuthority: mintAuthority.publicKey,
+              payer: admin.publicKey,
+              feeConfig,
+              feeTreasury: treasury,
+              sellerFeeConfig: config,
+              feeCreator: config ? creator : null,
+              mintOnly: null,
+              lazyRevealConfig: collection ? lazyRevealConfig : null,
+              hourlyMetrics: null,
+            })
+            .signers([admin, mintAuthority, newMint]);
+
+        // A collection's creators can't be credited for a mint outside it
+        try {
+          await mintWithFee(NATIVE_ID_BASE.addn(Date.now() % 1000000 + 3100), Keypair.generate(), null).rpc();
+          assert.fail("A seller fee config for another collection should be rejected");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "SellerFeeConfigMismatch");
+        }
+
+        const tokenId = NATIVE_ID_BASE.addn(Date.now() % 1000000 + 3000);
+        const treasuryBefore = await connection.getBalance(treasury);
+        const signature = await mintWithFee(tokenId, Keypair.generate(), collectionId)
+          .rpc({ commitment: "confirmed" });
+
+        assert.equal(await connection.getBalance(creator), 2_500_000);
+        assert.equal((await connection.getBalance(treasury)) - treasuryBefore, 7_500_000);
+        const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+        const parser = new anchor.EventParser(program.programId, program.coder);
+        const split = Array.from(parser.parseLogs(tx.meta.logMessages)).find((event) => event.name === "feeSplit");
+        assert.ok(split);
+        assert.equal(split.data.totalFee.toNumber(), 10_000_000);
+        assert.equal(split.data.creatorFee.toNumber(), 2_500_000);
+        assert.equal(split.data.protocolFee.toNumber(), 7_500_000);
+        assert.isTrue(split.data.creator.equals(creator));
+        assert.isTrue(split.data.protocolTreasury.equals(treasury));
+        // Leaving the collection's config out doesn't skip the creator share
+        try {
+          await mintWithFee(NATIVE_ID_BASE.addn(Date.now() % 1000000 + 3200), Keypair.generate(), collectionId, null).rpc();
+          assert.fail("A mint naming a collection should need its seller fee config");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "SellerFeeConfigMismatch");
+        }
+      } finally {
+        await program.methods.setMintFee(new BN(0), 0).accounts(feeAccounts).rpc();
+      }
+    });
+
+    it("Should queue inbound messages cheaply and deliver them with execute_inbound", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping two-phase inbound test");
+        return;
+      }
+      const state = await program.account.programState.fetch(programStatePda);
+      if (!state.owner.equals(wallet.publicKey)) {
+        console.log("Provider wallet is not the program owner, skipping two-phase inbound test");
+        return;
+      }
+
+      const tokenId = Date.now() + 191;
+      const uri = "https://example.com/two-phase.json";
+      // Sale proceeds ride along; the gateway deposits them when it queues the message
+      const value = 5_000;
+      const message = withValue(createZetaChainSuccessMessage(tokenId, uri, admin.publicKey.toBytes(), solanaSender), value);
+      const mint = bridgedMintPda(tokenId);
+      const pendingInbound = PublicKey.findProgramAddressSync(
+        [Buffer.from("pending_inbound"), createHash("sha256").update(message).digest()],
+        program.programId
+      )[0];
+      const receiveAccounts = {
+        programState: programStatePda,
+        nftOrigin: PublicKey.findProgramAddressSync(
+          [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+          program.programId
+        )[0],
+        mint,
+        dedupWindow: dedupWindowPda(uri),
+        mintAuthority: mintAuthorityPda,
+        recipient: admin.publicKey,
+        sourceChainConfig: chainConfigPda(zetaChainId),
+        provisionalEscrow: null,
+        custodialTokenAccount: null,
+        inboundOffer: null,
+        receiptMint: null,
+        receiptTokenAccount: null,
+        recipientTokenAccount: getAssociatedTokenAddressSync(mint, admin.publicKey, false),
+        hourlyMetrics: null,
+        inboundValueClaim: null,
+        inboundReceipt: inboundReceiptPda(message),
+      };
+      const computeUnits = async (signature: string) =>
+        (await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 }))
+          .meta.computeUnitsConsumed;
+
+      const adminAccounts = { programState: programStatePda, admin: wallet.publicKey };
+      await program.methods.setInboundMode({ twoPhase: {} }).accounts(adminAccounts).rpc();
+      try {
+        try {
+          await program.methods
+            .receiveCrossChainMessage(message, null)
+            .accounts({ ...receiveAccounts, pendingInbound: null, payer: admin.publicKey, gateway: gatewaySigner.publicKey })
+            .remainingAccounts(originPolicyAccounts)
+            .signers([admin, gatewaySigner])
+            .rpc();
+          assert.fail("One-phase delivery should be refused on a two-phase deployment");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "InboundModeMismatch");
+        }
+
+        // Phase one only validates and records the message
+        try {
+          await program.methods
+            .queueInbound(message, "two-phase")
+            .accounts({ programState: programStatePda, pendingInbound, gateway: admin.publicKey, payer: admin.publicKey })
+            .signers([admin])
+            .rpc();
+          assert.fail("Only the gateway may queue an inbound message");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "Unauthorized");
+        }
+        const queued = await program.methods
+          .queueInbound(message, "two-phase")
+          .accounts({ programState: programStatePda, pendingInbound, gateway: gatewaySigner.publicKey, payer: admin.publicKey })
+          .signers([admin, gatewaySigner])
+          .rpc({ commitment: "confirmed" });
+        const pending = await program.account.pendingInbound.fetch(pendingInbound);
+        assert.equal(pending.tokenId.toNumber(), tokenId);
+        assert.isTrue(pending.recipient.equals(admin.publicKey));
+        assert.deepEqual(Buffer.from(pending.message), message);
+        assert.isNull(await connection.getAccountInfo(mint));
+        const record = await connection.getAccountInfo(pendingInbound);
+        assert.equal(record.lamports - await connection.getMinimumBalanceForRentExemption(record.data.length), value);
+
+        // Phase two can be run by anyone, but only to the recorded recipient, who gets the
+        // deposited value rather than the executor
+        const recipientBefore = await connection.getBalance(admin.publicKey, "confirmed");
+        const executed = await program.methods
+          .executeInbound(message)
+          .accounts({ ...receiveAccounts, pendingInbound, payer: user.publicKey, gateway: null })
+          .remainingAccounts(originPolicyAccounts)
+          .signers([user])
+          .rpc({ commitment: "confirmed" });
+        assert.equal(Number((await getAccount(connection, receiveAccounts.recipientTokenAccount)).amount), 1);
+        assert.equal(await connection.getBalance(admin.publicKey, "confirmed") - recipientBefore, value);
+        assert.isNull(await connection.getAccountInfo(pendingInbound));
+        assert.equal((await program.account.nftOrigin.fetch(receiveAccounts.nftOrigin)).lastMemo, "two-phase");
+
+        const queueUnits = await computeUnits(queued);
+        const executeUnits = await computeUnits(executed);
+        console.log(`queue_inbound: ${queueUnits} CUs, execute_inbound: ${executeUnits} CUs`);
+        assert.isBelow(queueUnits, executeUnits / 2, "queueing should cost a fraction of the delivery");
+      } finally {
+        await program.methods.setInboundMode({ onePhase: {} }).accounts(adminAccounts).rpc();
+      }
+    });
+
+    it("Should look up a token id by its mint", async () => {
+      assert.isTrue(isProgramInitialized, "The setup tests should have initialized the program");
+      const state = await program.account.programState.fetch(programStatePda);
+      assert.isTrue(state.gateway.equals(gatewaySigner.publicKey), "The gateway should be the test signer");
+
+      // Bridged mints are indexed when they arrive, native ones when they are minted
+      const tokenId = Date.now() + 1146;
+      await deliverToAdmin(tokenId, `https://example.com/index/${tokenId}.json`);
+      const bridged = await program.methods.lookupTokenIdByMint(bridgedMintPda(tokenId)).view();
+      assert.equal(bridged.toNumber(), tokenId);
+
+      const nativeId = NATIVE_ID_BASE.addn(Date.now() % 1000000 + 11460);
+      const nativeMint = Keypair.generate();
+      await mintNative(nativeId, mintAuthority, nativeMint).rpc();
+      const native = await program.methods.lookupTokenIdByMint(nativeMint.publicKey).view();
+      assert.equal(native.toString(), nativeId.toString());
+
+      // A mint this program never issued has no index
+      try {
+        await program.methods.lookupTokenIdByMint(Keypair.generate().publicKey).view();
+        assert.fail("An unknown mint should have no token id");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "AccountNotInitialized");
+      }
+    });
+
+    it("Should pay collection royalties out to creators by share", async () => {
+      assert.isTrue(isProgramInitialized, "The setup tests should have initialized the program");
+      const state = await program.account.programState.fetch(programStatePda);
+      assert.isTrue(state.owner.equals(wallet.publicKey), "The provider wallet should own the program");
+
+      const collectionId = new BN(Date.now() + 1148);
+      const sellerFeeConfig = PublicKey.findProgramAddressSync(
+        [Buffer.from("seller_fee_config"), collectionId.toArrayLike(Buffer, 'le', 8)],
+        program.programId
+      )[0];
+      const royaltyBalance = PublicKey.findProgramAddressSync(
+        [Buffer.from("collection_royalty"), collectionId.toArrayLike(Buffer, 'le', 8)],
+        program.programId
+      )[0];
+      const first = Keypair.generate();
+      const second = Keypair.generate();
+      const setCreators = (shares: number[]) => program.methods
+        .setSellerFeeConfig(collectionId, [
+          { address: first.publicKey, share: shares[0] },
+          { address: second.publicKey, share: shares[1] },
+        ])
+        .accounts({ programState: programStatePda, sellerFeeConfig, collectionAuthority: null, admin: wallet.publicKey })
+        .rpc();
+      const deposit = (amount: number) => program.methods
+        .depositRoyalties(collectionId, new BN(amount))
+        .accountsPartial({ sellerFeeConfig, royaltyBalance, depositor: wallet.publicKey })
+        .rpc();
+      const claim = (creator: Keypair) => program.methods
+        .claimCreatorRoyalties(collectionId)
+        .accountsPartial({ royaltyBalance, creator: creator.publicKey })
+        .signers([creator])
+        .rpc();
+
+      try {
+        await setCreators([60, 30]);
+        assert.fail("Creator shares that don't sum to 100 should be rejected");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "InvalidRoyaltyShares");
+      }
+      await setCreators([70, 30]);
+      try {
+        await deposit(0);
+        assert.fail("An empty deposit should be rejected");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "InvalidAmount");
+      }
+
+      // The rounding remainder is credited to the first creator
+      await deposit(100_000_001);
+      const balance = await program.account.collectionRoyaltyBalance.fetch(royaltyBalance);
+      assert.equal(balance.totalDeposited.toNumber(), 100_000_001);
+      assert.deepEqual(balance.owed.map((owed) => owed.amount.toNumber()), [70_000_001, 30_000_000]);
+
+      // A crank pushes the second creator's share; the first pulls theirs
+      const distributed = await program.methods
+        .distributeRoyalties(collectionId)
+        .accountsPartial({ royaltyBalance, relayerRole: null, cranker: wallet.publicKey })
+        .remainingAccounts([{ pubkey: second.publicKey, isSigner: false, isWritable: true }])
+        .rpc({ commitment: "confirmed" });
+      await claim(first);
+      assert.equal(await connection.getBalance(first.publicKey), 70_000_001);
+      assert.equal(await connection.getBalance(second.publicKey), 30_000_000);
+      assert.equal((await program.account.collectionRoyaltyBalance.fetch(royaltyBalance)).totalPaid.toNumber(), 100_000_001);
+
+      const tx = await connection.getTransaction(distributed, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const paid = Array.from(parser.parseLogs(tx.meta.logMessages)).find((event) => event.name === "royaltyDistributed");
+      assert.ok(paid);
+      assert.isTrue(paid.data.creator.equals(second.publicKey));
+      assert.equal(paid.data.amount.toNumber(), 30_000_000);
+
+      for (const creator of [first, second]) {
+        try {
+          await claim(creator);
+          assert.fail("A creator who was paid should have nothing left to claim");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "NoRoyaltiesOwed");
+        }
+      }
+    });
+
+    it("Should send the destination chain's gas limit in the call options", async () => {
+      assert.isTrue(isProgramInitialized, "The setup tests should have initialized the program");
+      const state = await program.account.programState.fetch(programStatePda);
+      assert.isTrue(state.owner.equals(wallet.publicKey), "The provider wallet should own the program");
+      assert.isTrue(state.gateway.equals(gatewaySigner.publicKey), "The gateway should be the test signer");
+
+      const amoy = new BN(80002);
+      const registryPda = PublicKey.findProgramAddressSync([Buffer.from("registry")], program.programId)[0];
+      if (!(await connection.getAccountInfo(registryPda))) {
+        await program.methods.initRegistry().accounts({ admin: wallet.publicKey }).rpc();
+      }
+      await program.methods
+        .setRegistryEntry(Array.from(new Uint8Array(20).fill(0x49)), Buffer.from(new Uint8Array(20).fill(0x4a)), amoy, 0)
+        .accounts({ admin: wallet.publicKey })
+        .rpc();
+      const setGas = (gasLimit: number, blockGasLimit: number) => program.methods
+        .setChainGasConfig(amoy, new BN(gasLimit), new BN(blockGasLimit))
+        .accountsPartial({ programState: programStatePda, chainGasConfig: chainConfigPda(amoy), admin: wallet.publicKey })
+        .rpc();
+
+      // The chain's block gas limit caps what a single call may ask for
+      try {
+        await setGas(600_000, 500_000);
+        assert.fail("A gas limit above the block gas limit should be rejected");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "GasLimitOutOfRange");
+      }
+      await setGas(250_000, 500_000);
+
+      const tokenId = Date.now() + 1149;
+      await deliverToAdmin(tokenId, `https://example.com/call-options/${tokenId}.json`);
+      const mint = bridgedMintPda(tokenId);
+      const signature = await program.methods
+        .transferCrossChain(new BN(tokenId), amoy, Buffer.from(zetaChainRecipient), null, null, null)
+        .accounts({
+          nftOrigin: nftOriginPda(tokenId),
+          mint,
+          userTokenAccount: getAssociatedTokenAddressSync(mint, admin.publicKey, false),
+          user: admin.publicKey,
+          gatewayProgram: new PublicKey(zetaChainTestnetGateway),
+          feeConfig: feeConfigPda,
+          feeTreasury,
+          chainGasConfig: chainConfigPda(amoy),
+          registry: registryPda,
+          receiptConfig: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          zrc20GasVault: null,
+          zrc20VaultTokenAccount: null,
+          gasFeeDestination: null,
+          hourlyMetrics: null,
+          insuranceOptIn: null,
+          coOwnership: null,
+          coOwnerConsent: null,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+      const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+      const callOptions = tx.meta.logMessages.find((log) => log.includes("Call options:"));
+      assert.ok(callOptions);
+      assert.include(callOptions, "gas_limit: 250000");
+    });
+
+    it("Should read a collection's floor price from its Pyth feed", async () => {
+      assert.isTrue(isProgramInitialized, "The setup tests should have initialized the program");
+      const state = await program.account.programState.fetch(programStatePda);
+      assert.isTrue(state.owner.equals(wallet.publicKey), "The provider wallet should own the program");
+      // Loaded from tests/fixtures through Anchor.toml: a verified SOL price of 2.5 for this feed id
+      const priceFeed = new PublicKey("EDysHJG4qkmgdTU392LbfxXPEYK9RUh6NgC2iFKJkkhd");
+      const priceFeedId = Array.from(Buffer.from("17c9205bc2a9ea588f455b847fc38a5616769257d6979ab111dd6aef5ed5755c", "hex"));
+      assert.isNotNull(await connection.getAccountInfo(priceFeed), "Anchor.toml should load the Pyth price fixture");
+
+      const statsFor = (collectionId: BN) => PublicKey.findProgramAddressSync(
+        [Buffer.from("collection_stats"), collectionId.toArrayLike(Buffer, 'le', 8)],
+        program.programId
+      )[0];
+      const register = (collectionId: BN, feed: PublicKey, feedId = priceFeedId) => program.methods
+        .setFloorPriceFeed(collectionId, feed, feedId)
+        .accountsPartial({ programState: programStatePda, collectionStats: statsFor(collectionId), collectionAuthority: null, admin: wallet.publicKey })
+        .rpc();
+      const update = (collectionId: BN, feed: PublicKey) => program.methods
+        .updateFloorPrice(collectionId)
+        .accountsPartial({ collectionStats: statsFor(collectionId), priceFeed: feed, relayerRole: null, cranker: wallet.publicKey })
+        .rpc();
+
+      const collectionId = new BN(Date.now() + 1150);
+      await register(collectionId, priceFeed);
+      // Only the registered feed is read...
+      try {
+        await update(collectionId, programStatePda);
+        assert.fail("A feed other than the registered one should be rejected");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "InvalidPriceFeed");
+      }
+      // ...and only if the Pyth receiver wrote it
+      const spoofed = new BN(Date.now() + 11500);
+      await register(spoofed, programStatePda);
+      try {
+        await update(spoofed, programStatePda);
+        assert.fail("An account the Pyth receiver doesn't own should be rejected");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "InvalidPriceFeed");
+      }
+      // ...and only if it holds the registered feed id
+      const otherFeed = new BN(Date.now() + 11501);
+      await register(otherFeed, priceFeed, new Array(32).fill(7));
+      try {
+        await update(otherFeed, priceFeed);
+        assert.fail("A price for another feed id should be rejected");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "InvalidPriceFeed");
+      }
+      // A zero feed id is what a migrated account holds, so it can't be registered
+      try {
+        await register(otherFeed, priceFeed, new Array(32).fill(0));
+        assert.fail("A zero feed id should be rejected");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "InvalidPriceFeed");
+      }
+
+      await update(collectionId, priceFeed);
+      const stats = await program.methods.getCollectionStats(collectionId).view();
+      assert.equal(stats.collectionId.toString(), collectionId.toString());
+      assert.equal(stats.floorPriceLamports.toNumber(), 2_500_000_000);
+      assert.isAbove(stats.lastUpdated.toNumber(), 0);
+    });
+
+    it("Should refuse chain id zero and stamp native mints with the declared chain", async () => {
+      assert.isTrue(isProgramInitialized, "The setup tests should have initialized the program");
+      const state = await program.account.programState.fetch(programStatePda);
+      assert.isTrue(state.owner.equals(wallet.publicKey), "The provider wallet should own the program");
+
+      // Zero means unset, so it can't name a real chain
+      try {
+        await program.methods
+          .setChainGasConfig(new BN(0), new BN(250_000), new BN(500_000))
+          .accountsPartial({ programState: programStatePda, chainGasConfig: chainConfigPda(new BN(0)), admin: wallet.publicKey })
+          .rpc();
+        assert.fail("Chain id zero should be rejected");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "InvalidChainId");
+      }
+
+      assert.isFalse(state.declaredChainId.isZero());
+      const tokenId = NATIVE_ID_BASE.addn(Date.now() % 1000000 + 11502);
+      await mintNative(tokenId, mintAuthority, Keypair.generate()).rpc();
+      const origin = await program.account.nftOrigin.fetch(nftOriginPda(tokenId));
+      assert.equal(origin.originChain.toString(), state.declaredChainId.toString());
+    });
+
+    it("Should hold a reserved token id for the user who reserved it", async () => {
+      assert.isTrue(isProgramInitialized, "The setup tests should have initialized the program");
+      const state = await program.account.programState.fetch(programStatePda);
+      assert.isTrue(state.owner.equals(wallet.publicKey), "The provider wallet should own the program");
+
+      const adminAccounts = { programState: programStatePda, admin: wallet.publicKey };
+      const fee = state.reservationFee.addn(1_000_000);
+      const reservationPda = (tokenId: BN) => PublicKey.findProgramAddressSync(
+        [Buffer.from("reservation"), tokenId.toArrayLike(Buffer, 'le', 8)],
+        program.programId
+      )[0];
+      const reserve = (tokenId: BN, payment: BN) => program.methods
+        .reserveTokenId(tokenId, payment)
+        .accountsPartial({ programState: programStatePda, reservation: reservationPda(tokenId), user: mintAuthority.publicKey })
+        .signers([mintAuthority])
+        .rpc();
+
+      await program.methods.setReservationFee(fee).accounts(adminAccounts).rpc();
+      try {
+        const tokenId = NATIVE_ID_BASE.addn(Date.now() % 1000000 + 11510);
+        try {
+          await reserve(tokenId, fee.subn(1));
+          assert.fail("A payment below the reservation fee should be rejected");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "InsufficientReservationPayment");
+        }
+        // Ids below the native base belong to bridged NFTs
+        try {
+          await reserve(new BN(Date.now()), fee);
+          assert.fail("An inbound token id should not be reservable");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "TokenIdRangeConflict");
+        }
+
+        await reserve(tokenId, fee);
+        try {
+          await mintNative(tokenId, admin, Keypair.generate()).rpc();
+          assert.fail("Only the user who reserved the id should be able to mint it");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "TokenIdReserved");
+        }
+
+        // Minting closes the reservation and refunds it, rent included, to the minter
+        const reservation = await connection.getAccountInfo(reservationPda(tokenId));
+        const before = await connection.getBalance(mintAuthority.publicKey);
+        await mintNative(tokenId, mintAuthority, Keypair.generate()).rpc();
+        assert.isNull(await connection.getAccountInfo(reservationPda(tokenId)));
+        assert.equal(await connection.getBalance(mintAuthority.publicKey) - before, reservation.lamports);
+      } finally {
+        await program.methods.setReservationFee(state.reservationFee).accounts(adminAccounts).rpc();
+      }
+    });
 
-      const returnTokenAccount = await getAssociatedTokenAddress(
-        returnMint,
-        admin.publicKey,
-        false
-      );
+    it("Should only let minters with a KYC record at the required level mint", async () => {
+      assert.isTrue(isProgramInitialized, "The setup tests should have initialized the program");
+      const state = await program.account.programState.fetch(programStatePda);
+      assert.isTrue(state.owner.equals(wallet.publicKey), "The provider wallet should own the program");
+      // Loaded from tests/fixtures through Anchor.toml: a level 3 record for the fixture minter
+      // under a stand-in identity program, at its ["identity", user] PDA
+      const identityProgram = new PublicKey("8cH9FEURoEmuv4MB77Jm1AfBppbpopE2iA71WiHAmAoV");
+      const kycMinter = Keypair.fromSecretKey(Uint8Array.from(
+        JSON.parse(fs.readFileSync(path.join(__dirname, "fixtures", "kyc-minter.json"), "utf8"))
+      ));
+      const kycRecord = PublicKey.findProgramAddressSync(
+        [Buffer.from("identity"), kycMinter.publicKey.toBuffer()],
+        identityProgram
+      )[0];
+      assert.isNotNull(await connection.getAccountInfo(kycRecord), "Anchor.toml should load the KYC record fixture");
 
-      // Create return message for the round-trip simulation
-      const returnMessage = createZetaChainSuccessMessage(
-        roundTripTokenId,
-        "https://arweave.net/returned-metadata.json",
-        solanaSender, // Return to original Solana address
-        zetaChainRecipient
-      );
-      
-      await program.methods
-        .receiveCrossChainMessage(new BN(roundTripTokenId), returnMessage)
-        .accounts({
-          nftOrigin: returnNftOriginPda,
-          mint: returnMint,
-          mintAuthority: admin.publicKey,
-          recipientTokenAccount: returnTokenAccount,
-          payer: admin.publicKey,
-          systemProgram: SystemProgram.programId,
-          tokenProgram: TOKEN_PROGRAM_ID,
-          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
-          rent: SYSVAR_RENT_PUBKEY,
+      const setLevel = (level: number) => program.methods
+        .setKycConfig(identityProgram, level)
+        .accountsPartial({
+          programState: programStatePda,
+          kycConfig: PublicKey.findProgramAddressSync([Buffer.from("kyc_config")], program.programId)[0],
+          admin: wallet.publicKey,
         })
-        .signers([admin])
         .rpc();
+      const record = [{ pubkey: kycRecord, isSigner: false, isWritable: false }];
+      const nextId = (offset: number) => NATIVE_ID_BASE.addn(Date.now() % 1000000 + offset);
 
-      console.log("✅ NFT returned from ZetaChain to Solana");
-      
-      // Verify NFT exists on Solana again
-      const finalReturnBalance = await getAccount(connection, returnTokenAccount);
-      assert.equal(Number(finalReturnBalance.amount), 1, "Returned NFT should exist on Solana");
-      
-      console.log("=== ROUND-TRIP TRANSFER COMPLETE ===");
-      console.log("✅ Solana → ZetaChain → Solana transfer cycle completed");
-      console.log("✅ NFT successfully moved across chains and back");
-      console.log("✅ Cross-chain functionality fully verified");
+      await setLevel(3);
+      try {
+        const failures: [string, () => Promise<string>][] = [
+          ["A minter without a record", () => mintNative(nextId(11530), mintAuthority, Keypair.generate()).remainingAccounts(record).rpc()],
+          ["A record left out of the remaining accounts", () => mintNative(nextId(11531), kycMinter, Keypair.generate()).rpc()],
+        ];
+        for (const [what, mint] of failures) {
+          try {
+            await mint();
+            assert.fail(`${what} should not be able to mint`);
+          } catch (error) {
+            // @ts-ignore
+            assert.include(error.message, "KYCRequired");
+          }
+        }
+
+        await setLevel(4);
+        try {
+          await mintNative(nextId(11532), kycMinter, Keypair.generate()).remainingAccounts(record).rpc();
+          assert.fail("A record below the required level should not be able to mint");
+        } catch (error) {
+          // @ts-ignore
+          assert.include(error.message, "KYCRequired");
+        }
+
+        await setLevel(3);
+        const tokenId = nextId(11533);
+        const mint = Keypair.generate();
+        await mintNative(tokenId, kycMinter, mint).remainingAccounts(record).rpc();
+        assert.equal(Number((await getMint(connection, mint.publicKey)).supply), 1);
+      } finally {
+        await setLevel(0);
+      }
     });
-  });
 
-  // Helper function to create ZetaChain success messages
-  function createZetaChainSuccessMessage(tokenId: number, uri: string, receiver: Uint8Array, sender: Uint8Array): Buffer {
-    const uriBytes = Buffer.from(uri, 'utf8');
-    const padding = (32 - (uriBytes.length % 32)) % 32;
-    const totalSize = 100 + 8 + uriBytes.length + padding;
-    
-    const message = Buffer.alloc(totalSize);
-    
-    // Receiver (address) - 12 bytes padding + 20 bytes address
-    message.fill(0, 0, 12);
-    Buffer.from(receiver).copy(message, 12);
-    
-    // Token ID (u64) - 8 bytes at bytes 32-39
-    const tokenIdBytes = Buffer.alloc(8);
-    tokenIdBytes.writeBigUInt64BE(BigInt(tokenId), 0);
-    tokenIdBytes.copy(message, 32);
-    
-    // URI offset (u64) - 8 bytes at bytes 64-71, should be 100
-    const offsetBytes = Buffer.alloc(8);
-    offsetBytes.writeBigUInt64BE(BigInt(100), 0);
-    offsetBytes.copy(message, 64);
-    
-    // Sender (address) - 12 bytes padding + 20 bytes address at bytes 80-99
-    message.fill(0, 72, 80);
-    Buffer.from(sender).copy(message, 80);
-    
-    // URI length and data starting at byte 100
-    const uriLengthBytes = Buffer.alloc(8);
-    uriLengthBytes.writeBigUInt64BE(BigInt(uriBytes.length), 0);
-    uriLengthBytes.copy(message, 100);
-    uriBytes.copy(message, 108);
-    
-    // Padding to make total length multiple of 32
-    if (padding > 0) {
-      message.fill(0, 108 + uriBytes.length, 108 + uriBytes.length + padding);
-    }
-    
-    return message;
-  }
+    it("Should move a creator's royalty share only with their consent", async () => {
+      assert.isTrue(isProgramInitialized, "The setup tests should have initialized the program");
+      const state = await program.account.programState.fetch(programStatePda);
+      assert.isTrue(state.owner.equals(wallet.publicKey), "The provider wallet should own the program");
 
-  // Helper function to create ZetaChain failure messages
-  function createZetaChainFailureMessage(tokenId: number, errorMessage: string, receiver: Uint8Array, sender: Uint8Array): Buffer {
-    // For failure messages, we'll use the error message as the URI
-    return createZetaChainSuccessMessage(tokenId, errorMessage, receiver, sender);
-  }
+      const collectionId = new BN(Date.now() + 1156);
+      const sellerFeeConfig = PublicKey.findProgramAddressSync(
+        [Buffer.from("seller_fee_config"), collectionId.toArrayLike(Buffer, 'le', 8)],
+        program.programId
+      )[0];
+      const first = Keypair.generate();
+      const second = Keypair.generate();
+      const dao = Keypair.generate().publicKey;
+      await program.methods
+        .setSellerFeeConfig(collectionId, [{ address: first.publicKey, share: 60 }, { address: second.publicKey, share: 40 }])
+        .accounts({ programState: programStatePda, sellerFeeConfig, collectionAuthority: null, admin: wallet.publicKey })
+        .rpc();
+      const update = (newShare: number, currentRecipient: Keypair) => program.methods
+        .updateRoyaltyRecipient(collectionId, 0, dao, newShare)
+        .accountsPartial({
+          programState: programStatePda,
+          sellerFeeConfig,
+          collectionAuthority: null,
+          admin: wallet.publicKey,
+          currentRecipient: currentRecipient.publicKey,
+        })
+        .signers([currentRecipient])
+        .rpc({ commitment: "confirmed" });
 
-  describe("Phase 5: Gateway Integration Testing", () => {
-    it("Should properly encode cross-chain messages", async () => {
-      // Test message encoding functionality
-      const receiver = Array.from(zetaChainRecipient);
-      const tokenId = 123;
-      const uri = "https://example.com/test-nft.json";
-      const sender = Array.from(solanaSender);
+      // The recipient being replaced has to sign
+      try {
+        await update(60, second);
+        assert.fail("Another creator should not be able to hand over the first creator's share");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "RoyaltyRecipientMismatch");
+      }
+      try {
+        await update(50, first);
+        assert.fail("Shares that no longer sum to 100 should be rejected");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "InvalidRoyaltyShares");
+      }
 
-      // This would test the UniversalNFTCoreImpl.encode_cross_chain_message function
-      console.log("Testing cross-chain message encoding:");
-      console.log("- Receiver:", receiver);
-      console.log("- Token ID:", tokenId);
-      console.log("- URI:", uri);
-      console.log("- Sender:", sender);
+      const signature = await update(60, first);
+      const config = await program.account.sellerFeeConfig.fetch(sellerFeeConfig);
+      assert.isTrue(config.creators[0].address.equals(dao));
+      assert.equal(config.creators[0].share, 60);
+      assert.isTrue(config.creators[1].address.equals(second.publicKey));
 
-      // Verify encoding produces valid message format
-      assert.ok(receiver.length === 20, "Receiver should be 20 bytes");
-      assert.ok(sender.length === 20, "Sender should be 20 bytes");
-      assert.ok(uri.length > 0, "URI should not be empty");
-      assert.ok(tokenId > 0, "Token ID should be positive");
+      const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const updated = Array.from(parser.parseLogs(tx.meta.logMessages)).find((event) => event.name === "royaltyRecipientUpdated");
+      assert.ok(updated);
+      assert.equal(updated.data.collectionId.toString(), collectionId.toString());
+      assert.isTrue(updated.data.oldRecipient.equals(first.publicKey));
+      assert.isTrue(updated.data.newRecipient.equals(dao));
+      assert.equal(updated.data.newShare, 60);
     });
 
-    it("Should properly decode cross-chain messages", async () => {
-      // Test message decoding functionality
-      const testMessage = Buffer.alloc(100).fill(2); // Simulated encoded message
+    it("Should check a token's mint authority before high-value operations", async () => {
+      assert.isTrue(isProgramInitialized, "The setup tests should have initialized the program");
+      const state = await program.account.programState.fetch(programStatePda);
+      assert.isTrue(state.gateway.equals(gatewaySigner.publicKey), "The gateway should be the test signer");
 
-      console.log("Testing cross-chain message decoding:");
-      console.log("- Message length:", testMessage.length);
-      console.log("- Message format: [destination, receiver, token_id, uri, sender]");
+      const tokenId = Date.now() + 1157;
+      await deliverToAdmin(tokenId, `https://example.com/mint-authority/${tokenId}.json`);
+      const check = (expectedAuthority: PublicKey) => program.methods
+        .checkMintAuthorityIx(new BN(tokenId), expectedAuthority)
+        .accounts({ nftOrigin: nftOriginPda(tokenId), mint: bridgedMintPda(tokenId) })
+        .rpc();
 
-      // Verify decoding can handle various message formats
-      assert.ok(testMessage.length >= 84, "Message should be at least 84 bytes for valid format");
+      // Bridged mints are minted by the program's mint authority PDA
+      await check(mintAuthorityPda);
+      try {
+        await check(Keypair.generate().publicKey);
+        assert.fail("Any other expected authority should be rejected");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "MintAuthorityMismatch");
+      }
     });
-  });
 
-  describe("Phase 6: Error Handling & Edge Cases", () => {
-    it("Should reject unauthorized cross-chain transfers", async () => {
-      if (!isProgramInitialized) {
-        console.log("Program not initialized, skipping authorization test");
-        return;
-      }
+    it("Should match the JSON off-chain Rust clients get from the serde feature", async () => {
+      // The same fixture is read by the serde tests in programs/universal-nft/src/serde_helpers.rs
+      const fixture = JSON.parse(fs.readFileSync(path.join(__dirname, "fixtures", "inbound-nft-offer.json"), "utf8"));
+      const offer = {
+        tokenId: new BN(fixture.token_id),
+        recipient: new PublicKey(fixture.recipient),
+        mint: new PublicKey(fixture.mint),
+        sender: Array.from(Buffer.from(fixture.sender.slice(2), "hex")),
+        payer: new PublicKey(fixture.payer),
+        offeredAt: new BN(fixture.offered_at),
+        offerExpiresAt: new BN(fixture.offer_expires_at),
+        bump: fixture.bump,
+      };
+      const accountName = (pattern: RegExp) => program.idl.accounts.find((account) => pattern.test(account.name)).name;
+      const offerAccount = accountName(/inbound_?nft_?offer$/i);
+      const encoded = await program.coder.accounts.encode(offerAccount, offer);
 
-      const unauthorizedUser = Keypair.generate();
-      
-      // Fund unauthorized user
-      const transferAmount = 0.1 * LAMPORTS_PER_SOL;
-      const transfer = await connection.sendTransaction(
-        new Transaction().add(
-          SystemProgram.transfer({
-            fromPubkey: wallet.publicKey,
-            toPubkey: unauthorizedUser.publicKey,
-            lamports: transferAmount,
-          })
-        ),
-        [wallet.payer]
-      );
-      await connection.confirmTransaction(transfer, 'confirmed');
+      // Pubkeys as the SDK's base58 and EVM addresses as 0x-prefixed hex, like serde writes them
+      const decoded = program.coder.accounts.decode(offerAccount, encoded);
+      assert.deepEqual({
+        token_id: decoded.tokenId.toNumber(),
+        recipient: decoded.recipient.toBase58(),
+        mint: decoded.mint.toBase58(),
+        sender: "0x" + Buffer.from(decoded.sender).toString("hex"),
+        payer: decoded.payer.toBase58(),
+        offered_at: decoded.offeredAt.toNumber(),
+        offer_expires_at: decoded.offerExpiresAt.toNumber(),
+        bump: decoded.bump,
+      }, fixture);
 
+      // The bytes only decode as the account they were written for
       try {
-        await program.methods
-          .transferCrossChain(
-            new BN(testTokenId || 1),
-            Array.from(zetaChainRecipient),
-            Array.from(zetaChainZRC20)
-          )
-          .accounts({
-            nftOrigin: PublicKey.findProgramAddressSync(
-              [Buffer.from("nft_origin"), new BN(testTokenId || 1).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
-              program.programId
-            )[0],
-            mint: testMint,
-            userTokenAccount: testTokenAccount,
-            user: unauthorizedUser.publicKey,
-            gatewayProgram: Keypair.generate().publicKey,
-          })
-          .signers([unauthorizedUser])
-          .rpc();
-
-        assert.fail("Should not allow unauthorized user to initiate transfer");
+        program.coder.accounts.decode(accountName(/pending_?transfer$/i), encoded);
+        assert.fail("An offer should not decode as another account");
       } catch (error) {
-        console.log("Successfully rejected unauthorized cross-chain transfer");
         // @ts-ignore
-        assert.include(error.message, "Error", "Should throw authorization error");
+        assert.include(error.message, "Invalid account discriminator");
       }
     });
 
-    it("Should handle invalid destination addresses", async () => {
-      if (!isProgramInitialized) {
-        console.log("Program not initialized, skipping invalid address test");
-        return;
+    it("Should derive every PDA the way the program's constraints do", async () => {
+      assert.isTrue(isProgramInitialized, "The setup tests should have initialized the program");
+      const state = await program.account.programState.fetch(programStatePda);
+      assert.isTrue(state.gateway.equals(gatewaySigner.publicKey), "The gateway should be the test signer");
+
+      // The client's helpers agree with the derivations the tests use...
+      const tokenId = Date.now() + 1169;
+      const uri = `https://example.com/pda/${tokenId}.json`;
+      const client = UniversalNFTClient;
+      const pairs: [string, PublicKey, PublicKey][] = [
+        ["program state", client.getProgramStatePDA()[0], programStatePda],
+        ["nft origin", client.getNFTOriginPDA(tokenId)[0], nftOriginPda(tokenId)],
+        ["bridged mint", client.getBridgedMintPDA(CHAIN_ID_ZETACHAIN_TESTNET, tokenId)[0], bridgedMintPda(tokenId)],
+        ["dedup window", client.getDedupWindowPDA(uri)[0], dedupWindowPda(uri)],
+        ["mint authority", client.getMintAuthorityPDA()[0], mintAuthorityPda],
+        ["chain gas config", client.getChainGasConfigPDA(CHAIN_ID_ZETACHAIN_TESTNET)[0], chainConfigPda(zetaChainId)],
+        ["fee config", client.getFeeConfigPDA()[0], feeConfigPda],
+      ];
+      for (const [name, derived, expected] of pairs) {
+        assert.isTrue(derived.equals(expected), `${name} PDA differs from the client's`);
       }
 
-      const invalidDestination = new Uint8Array(20).fill(0); // Zero address
+      // ...and with the program's seed constraints, which accept them on delivery...
+      await deliverToAdmin(tokenId, uri);
+      assert.equal((await program.account.nftOrigin.fetch(client.getNFTOriginPDA(tokenId)[0])).tokenId.toNumber(), tokenId);
+
+      // ...and with the seeds the IDL publishes, which Anchor resolves on its own
+      const index = await program.methods.lookupTokenIdByMint(bridgedMintPda(tokenId)).pubkeys();
+      assert.isTrue(index.nftIndex.equals(PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_index"), bridgedMintPda(tokenId).toBuffer()],
+        program.programId
+      )[0]));
+      const registry = await program.methods.initRegistry().accounts({ admin: wallet.publicKey }).pubkeys();
+      assert.isTrue(registry.registry.equals(client.getRegistryPDA()[0]));
+      assert.isTrue(registry.programState.equals(programStatePda));
 
+      // An origin derived for another token id fails the seed constraint
       try {
         await program.methods
-          .transferCrossChain(
-            new BN(testTokenId || 1),
-            Array.from(zetaChainRecipient),
-            Array.from(invalidDestination)
-          )
-          .accounts({
-            nftOrigin: PublicKey.findProgramAddressSync(
-              [Buffer.from("nft_origin"), new BN(testTokenId || 1).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
-              program.programId
-            )[0],
-            mint: testMint,
-            userTokenAccount: testTokenAccount,
-            user: user.publicKey,
-            gatewayProgram: Keypair.generate().publicKey,
-          })
-          .signers([user])
+          .checkMintAuthorityIx(new BN(tokenId + 1), mintAuthorityPda)
+          .accounts({ nftOrigin: nftOriginPda(tokenId), mint: bridgedMintPda(tokenId) })
           .rpc();
-
-        console.log("Program accepted invalid destination address");
+        assert.fail("An origin under another token id's seeds should be rejected");
       } catch (error) {
-        console.log("Program correctly rejected invalid destination address");
         // @ts-ignore
-        assert.include(error.message, "Error", "Should throw validation error");
+        assert.include(error.message, "ConstraintSeeds");
       }
     });
-  });
 
-  describe("Phase 7: Integration Testing", () => {
     it("Should complete full Solana to ZetaChain transfer cycle", async () => {
       console.log("=== COMPLETE SOLANA TO ZETACHAIN TRANSFER CYCLE ===");
       console.log("1. ✅ Program initialized with ZetaChain gateway");
@@ -1216,8 +9660,11 @@ describe("Universal NFT Program - Solana to ZetaChain Transfer", () => {
           await program.methods
             .transferCrossChain(
               new BN(i + 1000), // Unique token ID
-              Array.from(zetaChainRecipient),
-              Array.from(zetaChainZRC20)
+              zetaChainId,
+              Buffer.from(zetaChainRecipient),
+              null,
+              null,
+              null
             )
             .accounts({
               nftOrigin: PublicKey.findProgramAddressSync(
@@ -1228,6 +9675,20 @@ describe("Universal NFT Program - Solana to ZetaChain Transfer", () => {
               userTokenAccount: testTokenAccounts[i],
               user: admin.publicKey,
               gatewayProgram: Keypair.generate().publicKey,
+              feeConfig: feeConfigPda,
+              feeTreasury,
+              chainGasConfig: chainConfigPda(zetaChainId),
+              registry: null,
+              receiptConfig: null,
+              receiptMint: null,
+              receiptTokenAccount: null,
+              zrc20GasVault: null,
+              zrc20VaultTokenAccount: null,
+              gasFeeDestination: null,
+              hourlyMetrics: null,
+              insuranceOptIn: null,
+              coOwnership: null,
+              coOwnerConsent: null,
             })
             .signers([admin])
             .rpc();
@@ -1255,6 +9716,159 @@ describe("Universal NFT Program - Solana to ZetaChain Transfer", () => {
       assert.ok(largeUri.length > 100, "Should test with large URI");
       console.log("Large message payload handling test completed");
     });
+
+    it("Should fit a mint in a v0 transaction with room to spare", async () => {
+      const tokenId = NATIVE_ID_BASE.addn(1_000_000);
+      const newMint = Keypair.generate();
+      const buildMintIx = (uri: string) =>
+        program.methods
+          .createMintAndNft(uri, 0, tokenId, null, null, null)
+          .accounts({
+            nftOrigin: PublicKey.findProgramAddressSync(
+              [Buffer.from("nft_origin"), tokenId.toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+              program.programId
+            )[0],
+            mint: newMint.publicKey,
+            mintAuthority: mintAuthority.publicKey,
+            payer: admin.publicKey,
+            feeConfig: feeConfigPda,
+            feeTreasury: null,
+            sellerFeeConfig: null,
+            feeCreator: null,
+            mintOnly: null,
+            lazyRevealConfig: null,
+            hourlyMetrics: null,
+          })
+          .instruction();
+
+      // Sizing only needs the table contents, so it is never created on-chain
+      const lookupTable = new AddressLookupTableAccount({
+        key: Keypair.generate().publicKey,
+        state: {
+          deactivationSlot: BigInt("18446744073709551615"),
+          lastExtendedSlot: 0,
+          lastExtendedSlotStartIndex: 0,
+          authority: admin.publicKey,
+          addresses: getStaticLookupAddresses(program.programId),
+        },
+      });
+      const options = {
+        payer: admin.publicKey,
+        recentBlockhash: Keypair.generate().publicKey.toBase58(),
+        computeUnitLimit: 400_000,
+        priorityFeeMicroLamports: 10_000,
+      };
+      const sizes = async (uri: string) => {
+        const instruction = await buildMintIx(uri);
+        return {
+          legacy: transactionSize(buildTransaction([instruction], options)),
+          v0: transactionSize(buildTransaction([instruction], { ...options, lookupTable })),
+        };
+      };
+
+      // Grow the URI until the legacy transaction sits just under the packet limit
+      const base = await sizes(testMetadataUri);
+      const uri = testMetadataUri + "a".repeat(Math.max(0, PACKET_DATA_SIZE - 40 - base.legacy));
+      const { legacy, v0 } = await sizes(uri);
+      console.log(`Mint transaction size: legacy ${legacy} bytes, v0 ${v0} bytes`);
+
+      assert.isAtMost(legacy, PACKET_DATA_SIZE);
+      assert.isAtLeast(legacy, PACKET_DATA_SIZE - 64, "legacy transaction should be near the limit");
+      assert.isAtLeast(legacy - v0, 150, "lookup table should save at least 150 bytes");
+      assert.isAtMost(v0, PACKET_DATA_SIZE - 150, "v0 transaction should leave room for more instructions");
+    });
+
+    it("Should record codec compute units per URI length", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping encoding benchmark test");
+        return;
+      }
+
+      const benchmark = async (uriLen: number) => {
+        const encodingBenchmark = PublicKey.findProgramAddressSync(
+          [Buffer.from("encoding_benchmark"), new BN(uriLen).toArrayLike(Buffer, 'le', 2)],
+          program.programId
+        )[0];
+        await program.methods
+          .benchmarkEncoding(uriLen)
+          .accounts({ payer: admin.publicKey })
+          .signers([admin])
+          .rpc({ commitment: "confirmed" });
+        return program.account.encodingBenchmark.fetch(encodingBenchmark);
+      };
+
+      const short = await benchmark(32);
+      const long = await benchmark(1000);
+      assert.equal(long.uriLen, 1000);
+      assert.isAbove(short.encodeCus.toNumber(), 0);
+      assert.isAbove(short.decodeCus.toNumber(), 0);
+      assert.equal(long.totalCus.toNumber(), long.encodeCus.toNumber() + long.decodeCus.toNumber());
+      assert.isAbove(long.totalCus.toNumber(), short.totalCus.toNumber(), "longer URIs should cost more");
+
+      try {
+        await benchmark(1001);
+        assert.fail("URIs over MAX_METADATA_URI_BYTES should be rejected");
+      } catch (error) {
+        // @ts-ignore
+        assert.include(error.message, "InvalidUriEncoding");
+      }
+    });
+
+    it("Should stay close to the compute unit estimates used by the client", async () => {
+      if (!isProgramInitialized) {
+        console.log("Program not initialized, skipping compute unit test");
+        return;
+      }
+
+      const tokenId = Date.now() + 17;
+      const mint = bridgedMintPda(tokenId);
+      const instruction = await program.methods
+        .receiveCrossChainMessage(
+          createZetaChainSuccessMessage(tokenId, "https://example.com/cu.json", admin.publicKey.toBytes(), solanaSender),
+          null
+        )
+        .accounts({
+          programState: programStatePda,
+          nftOrigin: PublicKey.findProgramAddressSync(
+            [Buffer.from("nft_origin"), new BN(tokenId).toArrayLike(Buffer, 'le', 8), Buffer.from("unique")],
+            program.programId
+          )[0],
+          mint,
+          dedupWindow: dedupWindowPda("https://example.com/cu.json"),
+          mintAuthority: mintAuthorityPda,
+          recipient: admin.publicKey,
+          sourceChainConfig: chainConfigPda(zetaChainId),
+          provisionalEscrow: null,
+          custodialTokenAccount: null,
+          inboundOffer: null,
+          pendingInbound: null,
+          receiptMint: null,
+          receiptTokenAccount: null,
+          recipientTokenAccount: getAssociatedTokenAddressSync(mint, admin.publicKey, false),
+          payer: admin.publicKey,
+          gateway: gatewaySigner.publicKey,
+          hourlyMetrics: null,
+          inboundValueClaim: null,
+          inboundReceipt: inboundReceiptPda(createZetaChainSuccessMessage(tokenId, "https://example.com/cu.json", admin.publicKey.toBytes(), solanaSender)),
+        })
+        .remainingAccounts(originPolicyAccounts)
+        .instruction();
+
+      const transaction = new VersionedTransaction(
+        new TransactionMessage({
+          payerKey: admin.publicKey,
+          recentBlockhash: (await connection.getLatestBlockhash()).blockhash,
+          instructions: [instruction],
+        }).compileToV0Message()
+      );
+      const { value } = await connection.simulateTransaction(transaction, { sigVerify: false });
+      assert.isNull(value.err, "simulated receive should succeed");
+
+      const expected = COMPUTE_UNIT_ESTIMATES.receiveCrossChainMessage;
+      const drift = Math.abs(value.unitsConsumed! - expected) / expected;
+      console.log(`receiveCrossChainMessage: ${value.unitsConsumed} CU (estimate ${expected})`);
+      assert.isAtMost(drift, COMPUTE_UNIT_DRIFT, "update COMPUTE_UNIT_ESTIMATES.receiveCrossChainMessage");
+    });
   });
 
   after(async () => {